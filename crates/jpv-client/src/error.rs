@@ -0,0 +1,73 @@
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct Error {
+    kind: Box<ErrorKind>,
+}
+
+impl Error {
+    pub(crate) fn new<K>(kind: K) -> Self
+    where
+        ErrorKind: From<K>,
+    {
+        Self {
+            kind: Box::new(kind.into()),
+        }
+    }
+}
+
+impl<K> From<K> for Error
+where
+    ErrorKind: From<K>,
+{
+    #[inline]
+    fn from(value: K) -> Self {
+        Self::new(value)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum ErrorKind {
+    #[error("Invalid URL `{0}`")]
+    InvalidUrl(Box<str>),
+    #[error("HTTP request failed")]
+    Http(
+        #[from]
+        #[source]
+        reqwest::Error,
+    ),
+    #[error("Request failed with status {status}: {body}")]
+    Status {
+        status: reqwest::StatusCode,
+        body: Box<str>,
+    },
+    #[error("WebSocket connection failed")]
+    WebSocket(
+        #[from]
+        #[source]
+        tokio_tungstenite::tungstenite::Error,
+    ),
+    #[error("Failed to encode or decode JSON")]
+    Json(
+        #[from]
+        #[source]
+        musli_json::Error,
+    ),
+    #[error("Failed to encode or decode message")]
+    Storage(
+        #[from]
+        #[source]
+        musli_storage::Error,
+    ),
+    #[error("Request failed: {0}")]
+    Request(Box<str>),
+    #[error("Request not supported by the service")]
+    Protocol(
+        #[from]
+        #[source]
+        lib::api::ProtocolError,
+    ),
+    #[error("Connection closed")]
+    Closed,
+    #[error("Unexpected message from service")]
+    UnexpectedMessage,
+}
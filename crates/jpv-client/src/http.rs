@@ -0,0 +1,132 @@
+use lib::api;
+use lib::config::Config;
+use musli::de::DecodeOwned;
+use musli::mode::Text;
+use musli_json::Encoding;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Url;
+
+use crate::error::{Error, ErrorKind};
+use crate::{Result, WsClient};
+
+const ENCODING: Encoding = Encoding::new();
+
+/// Client for the HTTP API of a service.
+#[derive(Debug, Clone)]
+pub struct Client {
+    client: reqwest::Client,
+    base: Url,
+}
+
+impl Client {
+    /// Construct a client for the service at the given base URL, like
+    /// `http://localhost:44714`.
+    pub fn new(base: &str) -> Result<Self> {
+        let Ok(base) = Url::parse(base) else {
+            return Err(Error::new(ErrorKind::InvalidUrl(base.into())));
+        };
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base,
+        })
+    }
+
+    /// Get the version of the service.
+    pub async fn version(&self) -> Result<String> {
+        Ok(self.build_info().await?.version)
+    }
+
+    /// Get the version of the service and how it was built.
+    pub async fn build_info(&self) -> Result<api::VersionResponse> {
+        self.get("api/version", &[]).await
+    }
+
+    /// Search for phrases, names and kanji.
+    pub async fn search(&self, q: &str) -> Result<api::OwnedSearchResponse> {
+        self.get("api/search", &[("q", q)]).await
+    }
+
+    /// Analyze the text starting at the byte offset `start`.
+    pub async fn analyze(&self, q: &str, start: usize) -> Result<api::OwnedAnalyzeResponse> {
+        let start = start.to_string();
+        let query = [("q", q), ("start", start.as_str())];
+        self.get("api/analyze", &query).await
+    }
+
+    /// Get the entry with the given sequence number.
+    pub async fn entry(&self, sequence: u32) -> Result<api::OwnedEntryResponse> {
+        self.get(&format!("api/entry/{sequence}"), &[]).await
+    }
+
+    /// Get the configuration of the service.
+    pub async fn config(&self) -> Result<Config> {
+        self.get("api/config", &[]).await
+    }
+
+    /// Replace the configuration of the service.
+    pub async fn update_config(&self, config: &Config) -> Result<()> {
+        let body = ENCODING.to_vec(config)?;
+
+        let response = self
+            .client
+            .post(self.url("api/config")?)
+            .header(api::API_VERSION_HEADER, api::API_VERSION)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        let api::Empty = decode(response).await?;
+        Ok(())
+    }
+
+    /// Connect to the WebSocket API of the service, which among other things
+    /// receives events like the progress of tasks.
+    pub async fn connect(&self) -> Result<WsClient> {
+        let mut url = self.url("ws")?;
+
+        let scheme = match url.scheme() {
+            "https" => "wss",
+            _ => "ws",
+        };
+
+        if url.set_scheme(scheme).is_err() {
+            return Err(Error::new(ErrorKind::InvalidUrl(url.as_str().into())));
+        }
+
+        WsClient::connect(url.as_str()).await
+    }
+
+    async fn get<T>(&self, path: &str, query: &[(&str, &str)]) -> Result<T>
+    where
+        T: DecodeOwned<Text>,
+    {
+        let response = self.client.get(self.url(path)?).query(query).send().await?;
+
+        decode(response).await
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        let Ok(url) = self.base.join(path) else {
+            return Err(Error::new(ErrorKind::InvalidUrl(path.into())));
+        };
+
+        Ok(url)
+    }
+}
+
+async fn decode<T>(response: reqwest::Response) -> Result<T>
+where
+    T: DecodeOwned<Text>,
+{
+    let status = response.status();
+    let bytes = response.bytes().await?;
+
+    if !status.is_success() {
+        let body = String::from_utf8_lossy(&bytes).into();
+        return Err(Error::new(ErrorKind::Status { status, body }));
+    }
+
+    Ok(ENCODING.from_slice(&bytes)?)
+}
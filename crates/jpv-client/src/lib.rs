@@ -0,0 +1,54 @@
+//! [<img alt="github" src="https://img.shields.io/badge/github-udoprog/jpv-8da0cb?style=for-the-badge&logo=github" height="20">](https://github.com/udoprog/jpv)
+//! [<img alt="docs.rs" src="https://img.shields.io/badge/docs.rs-jpv--client-66c2a5?style=for-the-badge&logoColor=white&logo=data:image/svg+xml;base64,PHN2ZyByb2xlPSJpbWciIHhtbG5zPSJodHRwOi8vd3d3LnczLm9yZy8yMDAwL3N2ZyIgdmlld0JveD0iMCAwIDUxMiA1MTIiPjxwYXRoIGZpbGw9IiNmNWY1ZjUiIGQ9Ik00ODguNiAyNTAuMkwzOTIgMjE0VjEwNS41YzAtMTUtOS4zLTI4LjQtMjMuNC0zMy43bC0xMDAtMzcuNWMtOC4xLTMuMS0xNy4xLTMuMS0yNS4zIDBsLTEwMCAzNy41Yy0xNC4xIDUuMy0yMy40IDE4LjctMjMuNCAzMy43VjIxNGwtOTYuNiAzNi4yQzkuMyAyNTUuNSAwIDI2OC45IDAgMjgzLjlWMzk0YzAgMTMuNiA3LjcgMjYuMSAxOS45IDMyLjJsMTAwIDUwYzEwLjEgNS4xIDIyLjEgNS4xIDMyLjIgMGwxMDMuOS01MiAxMDMuOSA1MmMxMC4xIDUuMSAyMi4xIDUuMSAzMi4yIDBsMTAwLTUwYzEyLjItNi4xIDE5LjktMTguNiAxOS45LTMyLjJWMjgzLjljMC0xNS05LjMtMjguNC0yMy40LTMzLjd6TTM1OCAyMTQuOGwtODUgMzEuOXYtNjguMmw4NS0zN3Y3My4zek0xNTQgMTA0LjFsMTAyLTM4LjIgMTAyIDM4LjJ2LjZsLTEwMiA0MS40LTEwMi00MS40di0uNnptODQgMjkxLjFsLTg1IDQyLjV2LTc5LjFsODUtMzguOHY3NS40em0wLTExMmwtMTAyIDQxLjQtMTAyLTQxLjR2LS42bDEwMi0zOC4yIDEwMiAzOC4ydi42em0yNDAgMTEybC04NSA0Mi41di03OS4xbDg1LTM4Ljh2NzUuNHptMC0xMTJsLTEwMiA0MS40LTEwMi00MS40di0uNmwxMDItMzguMiAxMDIgMzguMnYuNnoiPjwvcGF0aD48L3N2Zz4K" height="20">](https://docs.rs/jpv-client)
+//! [<img alt="build status" src="https://img.shields.io/github/actions/workflow/status/udoprog/jpv/ci.yml?branch=main&style=for-the-badge" height="20">](https://github.com/udoprog/jpv/actions?query=branch%3Amain)
+//! [<img alt="chat on discord" src="https://img.shields.io/discord/558644981137670144.svg?logo=discord&style=flat-square" height="20">](https://discord.gg/v5AeNkT)
+//!
+//! Client for the HTTP and WebSocket APIs of jpv.
+//!
+//! The client uses the same API types as the service, so it is always in sync
+//! with what the service expects.
+//!
+//! <br>
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use jpv_client::{Client, Event};
+//!
+//! # #[tokio::main] async fn main() -> jpv_client::Result<()> {
+//! let client = Client::new("http://localhost:44714")?;
+//!
+//! let response = client.search("食べる").await?;
+//!
+//! for phrase in &response.phrases {
+//!     println!("{}", phrase.phrase.sequence);
+//! }
+//!
+//! let mut ws = client.connect().await?;
+//! let analysis = ws.analyze("今日は", 0).await?;
+//!
+//! for data in &analysis.data {
+//!     println!("{}", data.string);
+//! }
+//!
+//! while let Some(event) = ws.next_event().await? {
+//!     if let Event::TaskProgress(progress) = event {
+//!         println!("{}: {}/{:?}", progress.name, progress.value, progress.total);
+//!     }
+//! }
+//! # Ok(()) }
+//! ```
+/// Result alias for this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+pub use self::error::Error;
+mod error;
+
+pub use self::http::Client;
+mod http;
+
+pub use self::ws::{Event, WsClient};
+mod ws;
+
+#[cfg(test)]
+mod tests;
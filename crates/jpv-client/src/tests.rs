@@ -0,0 +1,91 @@
+//! Tests which talk to a stub service serving canned responses.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use crate::{Client, Result};
+
+/// Serve a single request with the given status and body, returning the base
+/// URL of the stub and a handle to the request line it received.
+fn serve(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("address"));
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream);
+
+        let mut request = String::new();
+        reader.read_line(&mut request).expect("request line");
+
+        let mut line = String::new();
+
+        while reader.read_line(&mut line).expect("header") > 2 {
+            line.clear();
+        }
+
+        let mut stream = reader.into_inner();
+
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .expect("response");
+
+        request.trim_end().to_owned()
+    });
+
+    (base, handle)
+}
+
+#[tokio::test]
+async fn test_version() -> Result<()> {
+    let (base, handle) = serve("200 OK", r#"{"version":"1.2.3","database-version":32}"#);
+
+    let client = Client::new(&base)?;
+    let info = client.build_info().await?;
+    assert_eq!(info.version, "1.2.3");
+    assert_eq!(info.database_version, 32);
+    assert!(info.features.is_empty());
+
+    assert_eq!(handle.join().unwrap(), "GET /api/version HTTP/1.1");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_query() -> Result<()> {
+    let (base, handle) = serve("200 OK", r#"{"data":[]}"#);
+
+    let client = Client::new(&base)?;
+    let response = client.analyze("今日", 3).await?;
+    assert!(response.data.is_empty());
+
+    assert_eq!(
+        handle.join().unwrap(),
+        "GET /api/analyze?q=%E4%BB%8A%E6%97%A5&start=3 HTTP/1.1"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_errors() -> Result<()> {
+    let Err(error) = Client::new("not a url") else {
+        panic!("expected an invalid URL");
+    };
+
+    assert_eq!(error.to_string(), "Invalid URL `not a url`");
+
+    let (base, handle) = serve("404 Not Found", r#"{"error":"Missing entry by id `7`"}"#);
+
+    let client = Client::new(&base)?;
+    let error = client.entry(7).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        r#"Request failed with status 404 Not Found: {"error":"Missing entry by id `7`"}"#
+    );
+
+    assert_eq!(handle.join().unwrap(), "GET /api/entry/7 HTTP/1.1");
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+
+use futures_util::{SinkExt, StreamExt};
+use lib::api::{self, Request};
+use musli_utils::reader::SliceReader;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::error::{Error, ErrorKind};
+use crate::Result;
+
+/// An event broadcast by the service to every connected client, like the
+/// progress of a task.
+pub type Event = api::OwnedBroadcastKind;
+
+/// Client for the WebSocket API of a service.
+///
+/// Requests are answered in the order they are sent, and events which arrive
+/// while waiting for a response are buffered until they are read with
+/// [`WsClient::next_event`].
+pub struct WsClient {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    serial: u32,
+    events: VecDeque<Event>,
+}
+
+impl WsClient {
+    /// Connect to the WebSocket API at the given URL, like
+    /// `ws://localhost:44714/ws`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (stream, _) = tokio_tungstenite::connect_async(url).await?;
+
+        Ok(Self {
+            stream,
+            serial: 0,
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Search for phrases, names and kanji.
+    pub async fn search(&mut self, q: &str) -> Result<api::OwnedSearchResponse> {
+        self.request(&api::SearchRequest {
+            q: q.to_owned(),
+            refine_of: None,
+            debug: None,
+            continuation: None,
+            readings: false,
+            compact: false,
+            notation: None,
+            client: None,
+        })
+        .await
+    }
+
+    /// Analyze the text starting at the byte offset `start`.
+    pub async fn analyze(&mut self, q: &str, start: usize) -> Result<api::OwnedAnalyzeResponse> {
+        self.request(&api::AnalyzeRequest {
+            q: q.to_owned(),
+            start,
+            offset_encoding: api::OffsetEncoding::default(),
+        })
+        .await
+    }
+
+    /// Get the configuration of the service along with which dictionaries
+    /// are installed.
+    pub async fn config(&mut self) -> Result<api::GetConfigResult> {
+        self.request(&api::GetConfig).await
+    }
+
+    /// Send a request and wait for its response.
+    pub async fn request<T>(&mut self, request: &T) -> Result<T::Response>
+    where
+        T: Request,
+    {
+        let serial = self.serial;
+        self.serial = serial.wrapping_add(1);
+
+        let mut message = musli_storage::to_vec(&api::ClientRequestEnvelope {
+            index: 0,
+            serial,
+            kind: T::KIND,
+            version: api::PROTOCOL_VERSION,
+        })?;
+
+        musli_storage::to_writer(&mut message, request)?;
+        self.stream.send(Message::Binary(message)).await?;
+
+        loop {
+            let Some(bytes) = self.receive().await? else {
+                return Err(Error::new(ErrorKind::Closed));
+            };
+
+            let mut reader = SliceReader::new(&bytes);
+
+            match musli_storage::decode(&mut reader)? {
+                api::ClientEvent::Broadcast(broadcast) => {
+                    self.events.push_back(borrowme::to_owned(broadcast.kind));
+                }
+                api::ClientEvent::ClientResponse(response) => {
+                    if response.serial != serial {
+                        continue;
+                    }
+
+                    if let Some(error) = response.protocol_error {
+                        return Err(Error::new(error));
+                    }
+
+                    if let Some(error) = response.error {
+                        return Err(Error::new(ErrorKind::Request(error.into())));
+                    }
+
+                    let at = bytes.len() - reader.remaining();
+                    return Ok(musli_storage::from_slice(&bytes[at..])?);
+                }
+            }
+        }
+    }
+
+    /// Wait for the next event broadcast by the service, returning `None` once
+    /// the connection is closed.
+    pub async fn next_event(&mut self) -> Result<Option<Event>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let Some(bytes) = self.receive().await? else {
+                return Ok(None);
+            };
+
+            let mut reader = SliceReader::new(&bytes);
+
+            if let api::ClientEvent::Broadcast(broadcast) = musli_storage::decode(&mut reader)? {
+                self.events.push_back(borrowme::to_owned(broadcast.kind));
+            }
+        }
+    }
+
+    /// Receive the next binary message. Pings are answered by the underlying
+    /// connection as it is being read.
+    async fn receive(&mut self) -> Result<Option<Vec<u8>>> {
+        while let Some(message) = self.stream.next().await {
+            match message? {
+                Message::Binary(bytes) => return Ok(Some(bytes)),
+                Message::Close(..) => return Ok(None),
+                Message::Text(..) => return Err(Error::new(ErrorKind::UnexpectedMessage)),
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
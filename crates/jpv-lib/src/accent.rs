@@ -0,0 +1,107 @@
+//! Notation of pitch accent in readings.
+//!
+//! An accent is numbered the way accent dictionaries do it, as the mora after
+//! which pitch drops. `0` means that it doesn't drop within the word (平板),
+//! so a particle following it is high as well.
+//!
+//! Readings with accent are rendered here so that the command line, exports
+//! and the web interface all use the same notation.
+
+use std::fmt::Write;
+
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::morae;
+
+/// The mark inserted after the mora where pitch drops.
+const DOWNSTEP: char = 'ꜜ';
+
+/// How an accent is written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Notation {
+    /// A mark after the mora where pitch drops, like `はꜜし`.
+    #[default]
+    Downstep,
+    /// Whether each mora is low or high, followed by a particle in
+    /// parenthesis, like `HL(L)`.
+    Pattern,
+    /// The number of the accent after the reading, like `はし [1]`.
+    Numeric,
+}
+
+/// Render a reading with the given accent.
+///
+/// Returns `None` if the accent is past the end of the reading.
+pub fn render(reading: &str, accent: usize, notation: Notation) -> Option<String> {
+    let morae = morae::iter(reading).collect::<Vec<_>>();
+
+    if accent > morae.len() {
+        return None;
+    }
+
+    let mut output = String::new();
+
+    match notation {
+        Notation::Downstep => {
+            for (n, mora) in morae.iter().enumerate() {
+                output.push_str(mora);
+
+                if n + 1 == accent {
+                    output.push(DOWNSTEP);
+                }
+            }
+        }
+        Notation::Pattern => {
+            for position in 1..=morae.len() + 1 {
+                if position > morae.len() {
+                    output.push('(');
+                }
+
+                output.push(if is_high(position, accent) { 'H' } else { 'L' });
+            }
+
+            output.push(')');
+        }
+        Notation::Numeric => {
+            // Writing to a string can't fail.
+            _ = write!(output, "{reading} [{accent}]");
+        }
+    }
+
+    Some(output)
+}
+
+/// Test if the mora at the given position, counting from one, is high.
+fn is_high(position: usize, accent: usize) -> bool {
+    match accent {
+        // Rises after the first mora and never drops.
+        0 => position > 1,
+        // Only the first mora is high.
+        1 => position == 1,
+        // Rises after the first mora and drops after the accent.
+        _ => position > 1 && position <= accent,
+    }
+}
+
+#[test]
+fn test_render() {
+    let all = |reading: &str, accent: usize| {
+        [Notation::Downstep, Notation::Pattern, Notation::Numeric]
+            .map(|notation| render(reading, accent, notation))
+    };
+
+    let some = |a: &str, b: &str, c: &str| [a, b, c].map(|s| Some(s.to_owned()));
+
+    assert_eq!(all("はし", 0), some("はし", "LH(H)", "はし [0]"));
+    assert_eq!(all("はし", 1), some("はꜜし", "HL(L)", "はし [1]"));
+    assert_eq!(all("はし", 2), some("はしꜜ", "LH(L)", "はし [2]"));
+    assert_eq!(
+        all("がっこう", 0),
+        some("がっこう", "LHHH(H)", "がっこう [0]")
+    );
+    assert_eq!(all("きょう", 1), some("きょꜜう", "HL(L)", "きょう [1]"));
+    assert_eq!(all("はし", 3), [None, None, None]);
+}
@@ -1,16 +1,57 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 use musli::de::DecodeOwned;
 use musli::mode::Binary;
 use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::config::Config;
-use crate::database::EntryResultKey;
+use crate::config::{Config, EmbedLanding};
+use crate::database::{EntryResultKey, Fallback, InflectionData, LinkKind, Section, WordSort};
+use crate::history::Period;
 use crate::jmdict;
 use crate::jmnedict;
+use crate::kana;
 use crate::kanjidic2;
-use crate::Weight;
+use crate::phrasebook::{self, Phrase};
+use crate::tatoeba;
+use crate::typing::Difficulty;
+use crate::{Priority, Weight};
+
+/// The version of the naming used by the JSON of the HTTP API.
+///
+/// Changelog:
+/// * `1` - Query parameters and request bodies use `snake_case`, while
+///   responses use `kebab-case`.
+/// * `2` - Everything uses `kebab-case`. Query parameters are still accepted
+///   with their names from version 1, and request bodies use the naming of
+///   version 1 unless [`API_VERSION_HEADER`] says otherwise.
+pub const API_VERSION: u32 = 2;
+
+/// The header a client sets to the [`API_VERSION`] it was written for.
+pub const API_VERSION_HEADER: &str = "jpv-api-version";
+
+/// The version of the websocket protocol.
+///
+/// Changelog:
+/// * `1` - Requests carry the version the client speaks and [`Hello`] carries
+///   the version the service speaks. Requests which the service doesn't
+///   support are answered with a [`ProtocolError`] instead of closing the
+///   connection.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A request over the websocket which the service doesn't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum ProtocolError {
+    /// The service doesn't know about the kind of request, which happens if
+    /// the client is newer than the service.
+    #[error("Unsupported request kind")]
+    UnsupportedKind,
+    /// The client speaks a newer version of the protocol than the service.
+    #[error("Unsupported protocol version")]
+    UnsupportedVersion,
+}
 
 pub trait Request: Encode<Binary> {
     /// The kind of the request.
@@ -20,9 +61,70 @@ pub trait Request: Encode<Binary> {
 }
 
 #[derive(Debug, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct AnalyzeRequest {
     pub q: String,
     pub start: usize,
+    /// How `start` and the spans in the response are counted.
+    #[serde(default, alias = "offset_encoding")]
+    #[musli(default)]
+    pub offset_encoding: OffsetEncoding,
+}
+
+/// How offsets into a string are counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum OffsetEncoding {
+    /// Offsets in UTF-8 bytes.
+    #[default]
+    #[serde(rename = "utf-8")]
+    #[musli(mode = Text, name = "utf-8")]
+    Utf8,
+    /// Offsets in UTF-16 code units, as used by JavaScript strings.
+    #[serde(rename = "utf-16")]
+    #[musli(mode = Text, name = "utf-16")]
+    Utf16,
+    /// Offsets in unicode characters.
+    #[serde(rename = "chars")]
+    #[musli(mode = Text, name = "chars")]
+    Chars,
+}
+
+impl OffsetEncoding {
+    /// Convert an offset in this encoding into a byte offset into `string`.
+    ///
+    /// Returns `None` if the offset is out of bounds or doesn't fall on a
+    /// character boundary.
+    pub fn to_byte_offset(self, string: &str, offset: usize) -> Option<usize> {
+        let mut current = 0;
+
+        for (byte, c) in string.char_indices() {
+            if current == offset {
+                return Some(byte);
+            }
+
+            if current > offset {
+                return None;
+            }
+
+            current += self.len(c);
+        }
+
+        (current == offset).then_some(string.len())
+    }
+
+    /// Convert a byte offset into `string` into an offset in this encoding.
+    pub fn to_offset(self, string: &str, byte: usize) -> usize {
+        let prefix = string.get(..byte).unwrap_or(string);
+        prefix.chars().map(|c| self.len(c)).sum()
+    }
+
+    fn len(self, c: char) -> usize {
+        match self {
+            OffsetEncoding::Utf8 => c.len_utf8(),
+            OffsetEncoding::Utf16 => c.len_utf16(),
+            OffsetEncoding::Chars => 1,
+        }
+    }
 }
 
 impl Request for AnalyzeRequest {
@@ -31,8 +133,66 @@ impl Request for AnalyzeRequest {
 }
 
 #[derive(Debug, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SegmentRequest {
+    pub q: String,
+    /// How the spans in the response are counted.
+    #[serde(default, alias = "offset_encoding")]
+    #[musli(default)]
+    pub offset_encoding: OffsetEncoding,
+}
+
+impl Request for SegmentRequest {
+    const KIND: &'static str = "segment";
+    type Response = OwnedSegmentResponse;
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct SearchRequest {
     pub q: String,
+    /// Refine the results of a previous query, only including results which
+    /// were also matched by it.
+    #[serde(default, alias = "refine_of")]
+    #[musli(default)]
+    pub refine_of: Option<String>,
+    /// Include debugging information in the response.
+    #[serde(default)]
+    #[musli(default)]
+    pub debug: Option<SearchDebug>,
+    /// Continue a search for a broad wildcard pattern, as indicated by the
+    /// `continuation` of a previous response.
+    #[serde(default)]
+    #[musli(default)]
+    pub continuation: Option<usize>,
+    /// Include the reading in kana of every form written with kanji, for
+    /// assistive technology like screen readers.
+    #[serde(default)]
+    #[musli(default)]
+    pub readings: bool,
+    /// Leave the example sentences out of phrases, only reporting how many
+    /// each sense has in [`SearchPhrase::examples`].
+    #[serde(default)]
+    #[musli(default)]
+    pub compact: bool,
+    /// Render the pitch accents of readings with the given notation, see
+    /// [`ReadingAccent::rendered`].
+    #[serde(default)]
+    #[musli(default)]
+    pub notation: Option<crate::accent::Notation>,
+    /// An identifier picked by the client, under which the query is
+    /// remembered as the last one it searched for.
+    #[serde(default)]
+    #[musli(default)]
+    pub client: Option<String>,
+}
+
+/// Debugging information which can be requested with a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchDebug {
+    /// Include the time spent in each stage of the search.
+    Timings,
 }
 
 impl Request for SearchRequest {
@@ -43,45 +203,976 @@ impl Request for SearchRequest {
 #[derive(Debug, Encode, Decode)]
 pub struct InstallAllRequest;
 
-impl Request for InstallAllRequest {
-    const KIND: &'static str = "install-all";
-    type Response = Empty;
+impl Request for InstallAllRequest {
+    const KIND: &'static str = "install-all";
+    type Response = Empty;
+}
+
+/// Request to rebuild indexes.
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RebuildRequest {
+    /// Only rebuild the index with the given name, instead of building every
+    /// enabled index which is missing.
+    #[serde(default)]
+    #[musli(default)]
+    pub index: Option<String>,
+}
+
+impl Request for RebuildRequest {
+    const KIND: &'static str = "rebuild";
+    type Response = Empty;
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetState;
+
+impl Request for GetState {
+    const KIND: &'static str = "get-state";
+    type Response = GetStateResult;
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetStateResult {
+    /// Installed dictionaries.
+    pub installed: HashSet<String>,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetConfig;
+
+impl Request for GetConfig {
+    const KIND: &'static str = "get-config";
+    type Response = GetConfigResult;
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct SetIndexEnabledRequest {
+    /// The name of the index, like `jmdict`.
+    pub name: String,
+    pub enabled: bool,
+}
+
+impl Request for SetIndexEnabledRequest {
+    const KIND: &'static str = "set-index-enabled";
+    type Response = OwnedIndexesChanged;
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct EntitiesRequest;
+
+impl Request for EntitiesRequest {
+    const KIND: &'static str = "entities";
+    type Response = EntitiesResponse;
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetKanji {
+    pub kanji: String,
+}
+
+impl Request for GetKanji {
+    const KIND: &'static str = "get-kanji";
+    type Response = OwnedKanjiResponse;
+}
+
+/// Look up the first phrases and names stored under exactly the given key,
+/// without ranking them.
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstantRequest {
+    #[serde(default)]
+    #[musli(default)]
+    pub q: String,
+    /// The maximum number of entries to include.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for InstantRequest {
+    const KIND: &'static str = "instant";
+    type Response = OwnedInstantResponse;
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct InstantResponse<'a> {
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub phrases: Vec<jmdict::Entry<'a>>,
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub names: Vec<jmnedict::Entry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PopupRequest {
+    #[serde(default)]
+    #[musli(default)]
+    pub q: String,
+    /// The language glosses are picked in, like `eng`, falling back to the
+    /// first gloss if a phrase has none in it. Defaults to English.
+    #[serde(default)]
+    #[musli(default)]
+    pub lang: Option<String>,
+}
+
+impl Request for PopupRequest {
+    const KIND: &'static str = "popup";
+    type Response = OwnedPopupResponse;
+}
+
+/// The best candidates for a query, with just enough to show them in a small
+/// popup like the candidate window of an IME.
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PopupResponse<'a> {
+    pub candidates: Vec<PopupCandidate<'a>>,
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PopupCandidate<'a> {
+    /// The sequence number of the phrase, which its details can be fetched
+    /// by.
+    pub sequence: u64,
+    /// How the phrase is written.
+    pub text: &'a str,
+    /// How the phrase is read.
+    pub reading: &'a str,
+    /// The first gloss of the phrase in the requested language.
+    pub gloss: &'a str,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct KanjiCardRequest {
+    /// The kanji to get a card for.
+    #[serde(default)]
+    #[musli(default)]
+    pub literal: String,
+    /// The maximum number of common words to include.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for KanjiCardRequest {
+    const KIND: &'static str = "kanji-card";
+    type Response = OwnedKanjiCardResponse;
+}
+
+/// Everything known about a kanji, collected in one response.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct KanjiCardResponse<'a> {
+    pub literal: &'a str,
+    pub onyomi: Vec<&'a str>,
+    pub kunyomi: Vec<&'a str>,
+    /// English meanings.
+    pub meanings: Vec<&'a str>,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub stroke_count: Option<u8>,
+    /// The radicals which make up the kanji according to KRADFILE.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub decomposition: Vec<&'a str>,
+    /// Details of the radicals in the decomposition from RADKFILE.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub radicals: Vec<Radical<'a>>,
+    /// The most common words written with the kanji.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub words: Vec<jmdict::Entry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct KanjiWordsRequest {
+    /// The kanji the words are written with.
+    #[serde(default)]
+    #[musli(default)]
+    pub literal: String,
+    /// The maximum number of words to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+    /// How words are ordered.
+    #[serde(default)]
+    #[musli(default)]
+    pub sort: WordSort,
+}
+
+impl Request for KanjiWordsRequest {
+    const KIND: &'static str = "kanji-words";
+    type Response = OwnedKanjiWordsResponse;
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct KanjiWordsResponse<'a> {
+    pub words: Vec<jmdict::Entry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShiritoriRequest {
+    /// The kana the candidates should start with.
+    #[serde(default)]
+    #[musli(default)]
+    pub kana: String,
+    /// Include words whose reading ends in ん.
+    #[serde(default, alias = "allow_n")]
+    #[musli(default)]
+    pub allow_n: bool,
+    /// Only include common words.
+    #[serde(default)]
+    #[musli(default)]
+    pub common: bool,
+    /// Comma-separated sequence numbers of words which have already been
+    /// played.
+    #[serde(default)]
+    #[musli(default)]
+    pub played: String,
+    /// The maximum number of candidates to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for ShiritoriRequest {
+    const KIND: &'static str = "shiritori";
+    type Response = OwnedShiritoriResponse;
+}
+
+/// Candidates for the next word in a game of shiritori.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ShiritoriResponse<'a> {
+    pub words: Vec<jmdict::Entry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExampleSentencesRequest {
+    /// The word to find example sentences for.
+    #[serde(default)]
+    #[musli(default)]
+    pub q: String,
+    /// The maximum number of sentences to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for ExampleSentencesRequest {
+    const KIND: &'static str = "example-sentences";
+    type Response = OwnedExampleSentencesResponse;
+}
+
+/// Example sentences containing a word.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ExampleSentencesResponse<'a> {
+    pub sentences: Vec<tatoeba::Sentence<'a>>,
+    /// Set if the word is so common that not every sentence containing it
+    /// was looked through, so some might be missing.
+    #[musli(default, skip_encoding_if = is_false)]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MinimalPairsRequest {
+    /// The reading to find confusable words for, like `おばさん`.
+    #[serde(default)]
+    #[musli(default)]
+    pub reading: String,
+    /// The maximum number of words to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for MinimalPairsRequest {
+    const KIND: &'static str = "minimal-pairs";
+    type Response = OwnedMinimalPairsResponse;
+}
+
+/// A word whose reading is easily confused with the requested reading.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct MinimalPair<'a> {
+    /// How the reading differs from the requested reading.
+    #[copy]
+    pub confusion: kana::Confusion,
+    /// The confusable reading.
+    pub reading: &'a str,
+    pub entry: jmdict::Entry<'a>,
+}
+
+/// Words whose reading is easily confused with the requested reading.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct MinimalPairsResponse<'a> {
+    pub pairs: Vec<MinimalPair<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DrillRequest {
+    /// Comma-separated parts of speech to drill, like `v5k,v1`. Defaults to
+    /// every kind of verb.
+    #[serde(default)]
+    #[musli(default)]
+    pub pos: String,
+    /// Comma-separated forms which drills may combine, like `past,negative`.
+    /// Defaults to every form.
+    #[serde(default)]
+    #[musli(default)]
+    pub forms: String,
+    /// Only drill polite forms if `true`, or plain forms if `false`.
+    #[serde(default)]
+    #[musli(default)]
+    pub polite: Option<bool>,
+    /// Seed deciding which words and forms are drilled.
+    #[serde(default)]
+    #[musli(default)]
+    pub seed: u64,
+    /// The maximum number of drills to generate.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for DrillRequest {
+    const KIND: &'static str = "drill";
+    type Response = DrillResponse;
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct DrillResponse {
+    pub drills: Vec<Drill>,
+}
+
+/// A conjugation drill.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Drill {
+    /// The sequence number of the entry being conjugated.
+    pub sequence: u64,
+    /// The dictionary form being conjugated.
+    pub dictionary: String,
+    /// The reading of the dictionary form.
+    pub reading: String,
+    /// The prompt, like `negative past of 行く`.
+    pub prompt: String,
+    /// Forms of the drilled inflection, like `negative` and `past`.
+    pub forms: Vec<String>,
+    /// Accepted answers, both written with kanji and in kana.
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TypingRequest {
+    /// Comma-separated parts of speech of the words, like `n,adj-i`. Defaults
+    /// to every part of speech.
+    #[serde(default)]
+    #[musli(default)]
+    pub pos: String,
+    /// The minimum number of kana in the reading of a word.
+    #[serde(default, alias = "min_length")]
+    #[musli(default)]
+    pub min_length: Option<usize>,
+    /// The maximum number of kana in the reading of a word.
+    #[serde(default, alias = "max_length")]
+    #[musli(default)]
+    pub max_length: Option<usize>,
+    /// Only include words which are usually written in kana alone.
+    #[serde(default, alias = "kana_only")]
+    #[musli(default)]
+    pub kana_only: bool,
+    /// Only include words of the given difficulty.
+    #[serde(default)]
+    #[musli(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Seed deciding which words are picked. A random seed is used if not
+    /// set, which is included in the response.
+    #[serde(default)]
+    #[musli(default)]
+    pub seed: Option<u64>,
+    /// Continue picking words, as indicated by the `continuation` of a
+    /// previous response with the same seed.
+    #[serde(default)]
+    #[musli(default)]
+    pub continuation: Option<usize>,
+    /// The maximum number of words to include.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for TypingRequest {
+    const KIND: &'static str = "typing";
+    type Response = TypingResponse;
+}
+
+/// Words for typing practice.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct TypingResponse {
+    pub words: Vec<TypingWord>,
+    /// The seed the words were picked with.
+    pub seed: u64,
+    /// Pass this with the same seed to get more words, if there are any left.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub continuation: Option<usize>,
+}
+
+/// A word to type.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct TypingWord {
+    pub sequence: u64,
+    /// The word as it's usually written.
+    pub text: String,
+    /// The kana to type.
+    pub reading: String,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RadicalsRequest {
+    /// Radicals to filter kanji by. Every character is treated as a separate
+    /// radical.
+    #[serde(default)]
+    #[musli(default)]
+    pub radicals: String,
+    /// Only include kanji with at least this many strokes.
+    #[serde(default, alias = "min_strokes")]
+    #[musli(default)]
+    pub min_strokes: Option<u8>,
+    /// Only include kanji with at most this many strokes.
+    #[serde(default, alias = "max_strokes")]
+    #[musli(default)]
+    pub max_strokes: Option<u8>,
+    /// Only include kanji taught in this grade or earlier.
+    #[serde(default, alias = "max_grade")]
+    #[musli(default)]
+    pub max_grade: Option<u8>,
+}
+
+impl Request for RadicalsRequest {
+    const KIND: &'static str = "radicals";
+    type Response = OwnedRadicalsResponse;
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExamplesRequest {
+    /// The sequence number of the entry to get examples for.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequence: u32,
+    /// Only include examples which contain the given keyword, either in the
+    /// example sentence or in one of its translations.
+    #[serde(default)]
+    #[musli(default)]
+    pub q: Option<String>,
+    /// Only include examples from the source with the given id, such as the
+    /// sentence id in the Tanaka corpus.
+    #[serde(default)]
+    #[musli(default)]
+    pub source: Option<String>,
+    /// The page of examples to get, starting at zero.
+    #[serde(default)]
+    #[musli(default)]
+    pub page: usize,
+    /// The number of examples per page.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+    /// Include the reading in kana of every example sentence, for assistive
+    /// technology like screen readers.
+    #[serde(default)]
+    #[musli(default)]
+    pub readings: bool,
+}
+
+impl Request for ExamplesRequest {
+    const KIND: &'static str = "examples";
+    type Response = ExamplesResponse;
+}
+
+/// A segment of an example sentence, annotated with its reading if it contains
+/// kanji.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ExampleSegment {
+    pub text: String,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub reading: Option<String>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ExampleTranslation {
+    pub text: String,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Example {
+    /// The index of the sense the example belongs to.
+    pub sense: usize,
+    /// The id of the example in its source.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub source: Option<String>,
+    /// The type of the source, such as `tat` for the Tanaka corpus.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub source_type: Option<String>,
+    /// URL to audio for the example sentence, if an audio template is
+    /// configured and the sentence comes from Tatoeba.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub audio: Option<String>,
+    /// The example sentence annotated with furigana.
+    pub furigana: Vec<ExampleSegment>,
+    /// The example sentence written entirely in kana, if readings were
+    /// requested and every word in it could be read.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub reading: Option<String>,
+    pub translations: Vec<ExampleTranslation>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ExamplesResponse {
+    pub sequence: u32,
+    pub page: usize,
+    pub limit: usize,
+    /// The total number of examples matching the request.
+    pub total: usize,
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NormalizeRequest {
+    /// The query to normalize.
+    #[serde(default)]
+    #[musli(default)]
+    pub q: String,
+}
+
+impl Request for NormalizeRequest {
+    const KIND: &'static str = "normalize";
+    type Response = NormalizeResponse;
+}
+
+/// Long-form descriptions of every entity which might be attached to an
+/// entry, such as `uk` or `vs`.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct EntitiesResponse {
+    pub entities: Vec<EntityDescription>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct EntityDescription {
+    /// The kind of entity, like `misc` or `pos`.
+    pub kind: String,
+    /// The identifier of the entity as it's used in tags, like `uk`.
+    pub ident: String,
+    /// The long-form description of the entity.
+    pub help: String,
+}
+
+/// How a search query is interpreted by the server.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct NormalizeResponse {
+    /// The query after the width of its characters was folded, like
+    /// `ｔａｂｅｒｕ` to `taberu`, which is what the rest is parsed from.
+    pub q: String,
+    /// Phrases which are looked up, in order, by the keys they are looked up
+    /// with.
+    pub phrases: Vec<String>,
+    /// Entities which matching entries must be tagged with, like `v5s`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub entities: Vec<String>,
+    /// Fields whose senses are boosted, like `comp`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub fields: Vec<String>,
+    /// Priority filters, like `nf<=10`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub priorities: Vec<String>,
+    /// Inflection forms to produce, like `past`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub inflection: Vec<String>,
+    /// Latin words used to filter phrases by glossary, like `eat` in `食べる
+    /// eat`. Words which only ask what a phrase means, like `meaning` in `食べる
+    /// meaning`, are left out.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub glosses: Vec<String>,
+    /// Whether the user's own data is searched with `#mine`.
+    #[musli(default, skip_encoding_if = is_false)]
+    pub mine: bool,
+    /// The category of the phrasebook which is searched with
+    /// `#phrasebook:<category>`, which is empty if every category is.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub phrasebook: Option<String>,
+    /// Whether only phrases with example sentences are included with
+    /// `#has-example`.
+    #[musli(default, skip_encoding_if = is_false)]
+    pub has_example: bool,
+    /// Whether only common words are included with `#common`, or every word
+    /// with `#all`. If neither is used, this is up to the configuration.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub common: Option<bool>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RelatedRequest {
+    /// The sequence number of the entry to get related entries for.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequence: u32,
+    /// The maximum number of related entries to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for RelatedRequest {
+    const KIND: &'static str = "related";
+    type Response = OwnedRelatedResponse;
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RelatedEntry<'a> {
+    /// How strongly the entry is related, higher is more related.
+    pub score: f32,
+    pub entry: jmdict::Entry<'a>,
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RelatedResponse<'a> {
+    pub sequence: u32,
+    /// Related entries, ordered by how strongly they are related.
+    pub entries: Vec<RelatedEntry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RubyRequest {
+    /// Render every reading of the entry with the given sequence number.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequence: Option<u32>,
+    /// Render the given text. Without a `reading`, the text is split into
+    /// words and each word is rendered with its reading.
+    #[serde(default)]
+    #[musli(default)]
+    pub text: Option<String>,
+    /// The reading of `text`.
+    #[serde(default)]
+    #[musli(default)]
+    pub reading: Option<String>,
+}
+
+impl Request for RubyRequest {
+    const KIND: &'static str = "ruby";
+    type Response = RubyResponse;
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RubyForm {
+    pub text: String,
+    pub reading: String,
+    /// The text rendered as `<ruby>` HTML.
+    pub html: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RubyResponse {
+    pub forms: Vec<RubyForm>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GlossRequest {
+    /// The text to split into words.
+    #[serde(default)]
+    #[musli(default)]
+    pub text: String,
+    /// Detect grammar patterns in the text.
+    #[serde(default)]
+    #[musli(default)]
+    pub grammar: bool,
+}
+
+impl Request for GlossRequest {
+    const KIND: &'static str = "gloss";
+    type Response = GlossResponse;
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct GlossSegment {
+    pub text: String,
+    /// The reading of the text if it's a word written with kanji.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub reading: Option<String>,
+    /// The sequence number of the entry the text is a form of, if it's a word.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub sequence: Option<u64>,
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub glosses: Vec<String>,
+}
+
+/// A grammar pattern detected in glossed text.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct GrammarHint {
+    /// Identifier of the pattern, like `te-iru`.
+    pub pattern: String,
+    /// The keyword of the inflection the pattern corresponds to, which is the
+    /// same as used by `Form::parse_keyword`.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub form: Option<String>,
+    pub title: String,
+    /// Tutorial URL for the pattern.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub url: Option<String>,
+    /// Index of the first segment which is part of the pattern.
+    pub start: usize,
+    /// Index one past the last segment which is part of the pattern.
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct GlossResponse {
+    pub segments: Vec<GlossSegment>,
+    /// Grammar patterns in the text, if they were requested.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub grammar: Vec<GrammarHint>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DigestRequest {
+    /// The period to summarize.
+    #[serde(default)]
+    #[musli(default)]
+    pub period: Period,
+    /// The offset of local time from UTC in minutes, deciding when days start.
+    #[serde(default, alias = "utc_offset")]
+    #[musli(default)]
+    pub utc_offset: i64,
+    /// The maximum number of words to return.
+    #[serde(default)]
+    #[musli(default)]
+    pub limit: Option<usize>,
+}
+
+impl Request for DigestRequest {
+    const KIND: &'static str = "digest";
+    type Response = OwnedDigestResponse;
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct DigestEntry<'a> {
+    /// How many times the word was looked up during the period.
+    pub count: usize,
+    /// When the word was last looked up, in seconds since the Unix epoch.
+    pub last: u64,
+    /// The date the word was last looked up, formatted for the locale of the
+    /// request.
+    #[musli(default)]
+    pub last_text: String,
+    /// A `jpv://` link searching for the word.
+    pub link: String,
+    pub entry: jmdict::Entry<'a>,
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct DigestResponse<'a> {
+    pub period: Period,
+    /// The total number of distinct words looked up during the period, which
+    /// might be more than the number of entries returned.
+    pub total: usize,
+    /// Looked up words, with the most looked up first.
+    pub entries: Vec<DigestEntry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MaintenanceRequest {
+    /// Only report what would be removed, without removing anything.
+    #[serde(default, alias = "dry_run")]
+    #[musli(default)]
+    pub dry_run: bool,
+}
+
+impl Request for MaintenanceRequest {
+    const KIND: &'static str = "maintenance";
+    type Response = MaintenanceResponse;
+}
+
+/// The kind of an artifact found during maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// A built index in the data directory.
+    Index,
+    /// A downloaded dictionary in the cache directory.
+    Download,
+    /// An unrecognized file.
+    Other,
+}
+
+/// The status of an artifact found during maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum ArtifactStatus {
+    /// The artifact is valid and in use.
+    Valid,
+    /// The artifact is being used by an ongoing installation.
+    Installing,
+    /// The index was built for an older database version.
+    Outdated,
+    /// The index has been replaced by a newer generation.
+    Stale,
+    /// The index could not be opened.
+    Invalid,
+    /// The artifact does not belong to any configured index.
+    Orphaned,
+    /// The file is not recognized, and is never removed since it might hold
+    /// user data.
+    Unknown,
+}
+
+impl ArtifactStatus {
+    /// Test if an artifact with this status should be removed.
+    pub fn is_removable(&self) -> bool {
+        matches!(
+            self,
+            Self::Outdated | Self::Stale | Self::Invalid | Self::Orphaned
+        )
+    }
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    pub path: String,
+    /// Size of the artifact in bytes.
+    pub size: u64,
+    /// Size of the artifact formatted for the locale of the request.
+    #[musli(default)]
+    pub size_text: String,
+    pub status: ArtifactStatus,
+    /// Indicates that the artifact was removed.
+    #[musli(default, skip_encoding_if = is_false)]
+    pub removed: bool,
 }
 
-#[derive(Debug, Encode, Decode)]
-pub struct GetState;
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct MaintenanceResponse {
+    pub dry_run: bool,
+    pub artifacts: Vec<Artifact>,
+}
 
-impl Request for GetState {
-    const KIND: &'static str = "get-state";
-    type Response = GetStateResult;
+/// Request to dump how an entry is stored, for debugging how entries are
+/// encoded.
+///
+/// Either `sequence` or both `index` and `offset` have to be set.
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RawEntryRequest {
+    /// The sequence number of the phrase to dump.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequence: Option<u32>,
+    /// The name of the index to dump the entry at `offset` from.
+    #[serde(default)]
+    #[musli(default)]
+    pub index: Option<String>,
+    /// The byte offset of the entry to dump.
+    #[serde(default)]
+    #[musli(default)]
+    pub offset: Option<u32>,
 }
 
-#[derive(Debug, Encode, Decode)]
-pub struct GetStateResult {
-    /// Installed dictionaries.
-    pub installed: HashSet<String>,
+impl Request for RawEntryRequest {
+    const KIND: &'static str = "raw-entry";
+    type Response = OwnedRawEntryResponse;
 }
 
+/// An entry together with the bytes it's stored as.
+#[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
-pub struct GetConfig;
-
-impl Request for GetConfig {
-    const KIND: &'static str = "get-config";
-    type Response = GetConfigResult;
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RawEntry<'a> {
+    /// The name of the index the entry is stored in.
+    pub index: &'a str,
+    #[copy]
+    pub section: Section,
+    /// The byte offset of the entry in the index.
+    pub offset: u32,
+    /// The stored bytes in hex.
+    pub bytes: String,
+    /// Whether encoding the decoded entry again produces the same bytes.
+    pub round_trips: bool,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub phrase: Option<jmdict::Entry<'a>>,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub kanji: Option<kanjidic2::Character<'a>>,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub name: Option<jmnedict::Entry<'a>>,
 }
 
+#[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
-pub struct GetKanji {
-    pub kanji: String,
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RawEntryResponse<'a> {
+    pub entries: Vec<RawEntry<'a>>,
 }
 
-impl Request for GetKanji {
-    const KIND: &'static str = "get-kanji";
-    type Response = OwnedKanjiResponse;
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 /// Missing OCR support.
 #[derive(Debug, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct InstallUrl {
     /// Title of the URL.
     pub text: String,
@@ -93,6 +1184,7 @@ pub struct InstallUrl {
 
 /// Missing OCR support.
 #[derive(Debug, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct MissingOcr {
     /// The URL where to install it from.
     #[musli(default, skip_encoding_if = Option::is_none)]
@@ -125,6 +1217,9 @@ pub struct GetConfigResult {
     /// Installed dictionaries.
     #[musli(default, skip_encoding_if = HashSet::is_empty)]
     pub installed: HashSet<String>,
+    /// Languages of the glosses in installed dictionaries, by dictionary.
+    #[musli(default, skip_encoding_if = BTreeMap::is_empty)]
+    pub languages: BTreeMap<String, Vec<String>>,
     /// Indicates that OCR support is missing, and some indications of how to install it.
     #[musli(default, skip_encoding_if = Option::is_none)]
     pub missing_ocr: Option<MissingOcr>,
@@ -151,6 +1246,249 @@ pub struct UpdateConfigResponse {
     pub config: Option<Config>,
 }
 
+#[derive(Debug, Serialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PinsResponse {
+    /// Pinned entries by the query pattern they apply to.
+    pub pins: BTreeMap<String, Vec<u32>>,
+}
+
+#[derive(Debug, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct UpdatePinsRequest {
+    /// The query pattern to pin entries for, like `ある` or `ある*`.
+    pub pattern: String,
+    /// Sequence numbers of the entries to pin, in order. If empty the pins for
+    /// the pattern are removed.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequences: Vec<u32>,
+}
+
+impl Request for UpdatePinsRequest {
+    const KIND: &'static str = "update-pins";
+    type Response = PinsResponse;
+}
+
+#[derive(Debug, Serialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct LogFilterResponse {
+    /// Directives of the filter of what the service logs, like `jpv=info`.
+    pub filter: String,
+}
+
+#[derive(Debug, Default, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct UpdateLogFilterRequest {
+    /// Directives to filter what the service logs with, which use the same
+    /// syntax as `RUST_LOG`, like `jpv=info,jpv::ocr=debug`. If not set the
+    /// filter the service was started with is used.
+    #[serde(default)]
+    #[musli(default)]
+    pub filter: Option<String>,
+    /// Save the filter in the configuration, so that it's used after the
+    /// service restarts.
+    #[serde(default)]
+    #[musli(default)]
+    pub persist: bool,
+}
+
+impl Request for UpdateLogFilterRequest {
+    const KIND: &'static str = "update-log-filter";
+    type Response = LogFilterResponse;
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PhrasebookRequest {
+    /// Only include phrases in the given category.
+    #[serde(default)]
+    #[musli(default)]
+    pub category: Option<String>,
+    /// Only include phrases containing the given text in the phrase, its
+    /// reading, translation or notes.
+    #[serde(default)]
+    #[musli(default)]
+    pub q: Option<String>,
+}
+
+impl Request for PhrasebookRequest {
+    const KIND: &'static str = "phrasebook";
+    type Response = PhrasebookResponse;
+}
+
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PhrasebookResponse {
+    /// Every category in the phrasebook.
+    pub categories: Vec<String>,
+    /// Matching phrases, in the order they were added.
+    pub phrases: Vec<Phrase>,
+}
+
+#[derive(Debug, Default, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SavePhraseRequest {
+    /// The identifier of the phrase to update, or `None` to add a new phrase.
+    #[serde(default)]
+    #[musli(default)]
+    pub id: Option<u64>,
+    pub text: String,
+    #[serde(default)]
+    #[musli(default)]
+    pub reading: String,
+    #[serde(default)]
+    #[musli(default)]
+    pub translation: String,
+    #[serde(default)]
+    #[musli(default)]
+    pub notes: String,
+    /// The category to sort the phrase into, which is added if it doesn't
+    /// exist.
+    #[serde(default)]
+    #[musli(default)]
+    pub category: Option<String>,
+}
+
+impl Request for SavePhraseRequest {
+    const KIND: &'static str = "save-phrase";
+    type Response = SavePhraseResponse;
+}
+
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SavePhraseResponse {
+    pub phrase: Phrase,
+}
+
+#[derive(Debug, Default, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RemovePhraseRequest {
+    pub id: u64,
+}
+
+impl Request for RemovePhraseRequest {
+    const KIND: &'static str = "remove-phrase";
+    type Response = Empty;
+}
+
+/// Add, rename or remove a category in the phrasebook.
+#[derive(Debug, Default, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct UpdateCategoryRequest {
+    /// The category to update, which is added unless it's renamed or removed.
+    pub name: String,
+    /// Rename the category, moving its phrases along with it.
+    #[serde(default)]
+    #[musli(default)]
+    pub rename: Option<String>,
+    /// Remove the category, keeping its phrases without a category.
+    #[serde(default)]
+    #[musli(default)]
+    pub remove: bool,
+}
+
+impl Request for UpdateCategoryRequest {
+    const KIND: &'static str = "update-category";
+    type Response = CategoriesResponse;
+}
+
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct CategoriesResponse {
+    /// Every category in the phrasebook.
+    pub categories: Vec<String>,
+}
+
+/// Export the phrasebook as a file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportPhrasebookRequest {
+    #[serde(default)]
+    pub format: phrasebook::Format,
+    /// Only export phrases in the given category.
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// Render the results of a search as a printable vocabulary sheet.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SearchHtmlRequest {
+    pub q: String,
+    /// The maximum number of phrases on the sheet.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Get what the embedded view shows when it's opened without a query.
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EmbedLandingRequest {
+    /// The offset of local time from UTC in minutes, deciding when the word
+    /// of the day changes.
+    #[serde(default, alias = "utc_offset")]
+    #[musli(default)]
+    pub utc_offset: i64,
+    /// The identifier of the client, whose last query is restored.
+    #[serde(default)]
+    #[musli(default)]
+    pub client: Option<String>,
+}
+
+impl Request for EmbedLandingRequest {
+    const KIND: &'static str = "embed-landing";
+    type Response = EmbedLandingResponse;
+}
+
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct EmbedLandingResponse {
+    /// The configured landing.
+    pub landing: EmbedLanding,
+    /// The query the embedded view should search for, like the last query or
+    /// the word of the day.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub query: Option<String>,
+    /// Phrases from the phrasebook to show, most recently added first.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub phrasebook: Vec<Phrase>,
+}
+
+/// The version of a service and how it was built, to include in bug reports.
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct VersionResponse {
+    pub version: String,
+    /// The git revision the service was built from.
+    #[musli(default)]
+    pub rev: String,
+    /// When the service was built, in seconds since the Unix epoch.
+    #[musli(default)]
+    pub built: u64,
+    /// Cargo features the service was built with, like `bundle`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub features: Vec<String>,
+    /// The version of the database format the service reads, see
+    /// [`DATABASE_VERSION`].
+    ///
+    /// [`DATABASE_VERSION`]: crate::DATABASE_VERSION
+    #[musli(default)]
+    pub database_version: u32,
+    /// The latest version of the naming used by the JSON API which the
+    /// service understands, see [`API_VERSION`].
+    #[musli(default)]
+    pub api_version: u32,
+    /// The version of the tesseract library, if it's loaded.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub tesseract: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Encode, Decode)]
 #[musli(mode = Text, name_all = "kebab-case")]
 pub struct Empty;
@@ -162,15 +1500,31 @@ pub struct SendClipboard<'a> {
     pub ty: Option<&'a str>,
     #[borrowme(owned = Box<[u8]>, to_owned_with = Box::from)]
     pub data: &'a [u8],
+    /// Text which was left out because it was longer than the clipboard
+    /// limit, so that it can be analyzed a chunk at a time.
+    #[musli(mode = Text, default, skip_encoding_if = Option::is_none)]
+    pub rest: Option<&'a str>,
+    /// The number of characters before the text which were left out, since
+    /// it was captured to be analyzed from further in.
+    #[copy]
+    #[musli(mode = Text, default, skip_encoding_if = Option::is_none)]
+    pub skipped: Option<usize>,
 }
 
 /// Json payload when sending the clipboard.
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct SendClipboardJson {
     pub primary: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[musli(default, skip_encoding_if = Option::is_none)]
     pub secondary: Option<String>,
+    /// Byte offset into the primary text to analyze from, instead of
+    /// searching for the whole text.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "analyze_at")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub analyze_at: Option<usize>,
 }
 
 #[borrowme::borrowme]
@@ -179,6 +1533,43 @@ pub struct LogBackFill<'a> {
     pub log: Vec<LogEntry<'a>>,
 }
 
+/// The indexes which are installed, and which of them are enabled.
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct IndexesChanged<'a> {
+    pub installed: Vec<&'a str>,
+    pub enabled: Vec<&'a str>,
+}
+
+/// Indicates that the service has moved to a new address.
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Rebind<'a> {
+    /// The URL the service is now reachable at.
+    pub url: &'a str,
+}
+
+/// Sent first to every client which connects to the websocket.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Hello {
+    /// The session sequence numbers belong to.
+    pub session: u64,
+    /// The sequence number of the last event sent by the service.
+    pub sequence: u64,
+    /// If the connection resumed an earlier one, in which case the events
+    /// the client missed are sent right after this one. Otherwise the client
+    /// should discard any state it has built up from earlier events, since a
+    /// backfill follows.
+    pub resumed: bool,
+    /// The version of the protocol the service speaks, see
+    /// [`PROTOCOL_VERSION`]. A client which speaks another version should be
+    /// reloaded.
+    #[musli(default)]
+    pub version: u32,
+}
+
 #[borrowme::borrowme]
 #[derive(Debug, Clone, Encode, Decode)]
 pub enum BroadcastKind<'a> {
@@ -188,12 +1579,21 @@ pub enum BroadcastKind<'a> {
     TaskProgress(TaskProgress<'a>),
     TaskCompleted(TaskCompleted<'a>),
     Refresh,
+    Rebind(Rebind<'a>),
+    IndexesChanged(IndexesChanged<'a>),
+    OcrJob(OcrJob),
+    // New variants go last, since variants are encoded by their position.
+    Hello(Hello),
 }
 
 #[borrowme::borrowme]
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct Broadcast<'a> {
     pub kind: BroadcastKind<'a>,
+    /// The sequence number of the event being broadcast, which the client
+    /// passes back when reconnecting to resume from it.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub sequence: Option<u64>,
 }
 
 #[borrowme::borrowme]
@@ -213,7 +1613,13 @@ pub enum ClientResponse<'a> {
 pub struct ClientRequestEnvelope<'de> {
     pub index: usize,
     pub serial: u32,
+    /// The [`Request::KIND`] of the request which follows the envelope.
     pub kind: &'de str,
+    /// The version of the protocol the client speaks, see
+    /// [`PROTOCOL_VERSION`]. Clients from before the protocol was versioned
+    /// send `0`.
+    #[musli(default)]
+    pub version: u32,
 }
 
 #[borrowme::borrowme]
@@ -223,6 +1629,17 @@ pub struct ClientResponseEnvelope<'de> {
     pub serial: u32,
     #[musli(default, skip_encoding_if = Option::is_none)]
     pub error: Option<&'de str>,
+    /// Set if the request failed because the service doesn't support it.
+    #[copy]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub protocol_error: Option<ProtocolError>,
+    /// The number of requests from the same client which were queued up
+    /// behind this one.
+    #[musli(default)]
+    pub queue: u32,
+    /// How long it took to handle the request in milliseconds.
+    #[musli(default)]
+    pub latency: u32,
 }
 
 #[borrowme::borrowme]
@@ -232,12 +1649,145 @@ pub enum ClientEvent<'a> {
     ClientResponse(ClientResponseEnvelope<'a>),
 }
 
+/// Parameters used when connecting to the websocket, like
+/// `/ws?compression=deflate`.
+#[derive(Default, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WebSocketParams {
+    /// Compression of messages sent to the client.
+    #[serde(default)]
+    pub compression: Compression,
+    /// The session to resume, as reported in [`Hello`].
+    #[serde(default)]
+    pub session: Option<u64>,
+    /// The sequence number of the last event seen in the resumed session.
+    #[serde(default)]
+    pub sequence: Option<u64>,
+}
+
+/// Compression of messages sent over the websocket.
+///
+/// If a client asks for compression, every message sent to it starts with a
+/// byte which says how the rest of the message is encoded, since small
+/// messages are not worth compressing.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+    /// Messages are not compressed and have no leading byte.
+    #[default]
+    None,
+    /// Messages are compressed with deflate.
+    Deflate,
+}
+
+impl Compression {
+    /// Leading byte of a message which is not compressed.
+    pub const PLAIN: u8 = 0;
+    /// Leading byte of a message which is compressed with deflate.
+    pub const DEFLATE: u8 = 1;
+
+    /// The query parameter value of the compression.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Deflate => "deflate",
+        }
+    }
+}
+
 #[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
 #[musli(mode = Text, name_all = "kebab-case")]
 pub struct SearchPhrase<'a> {
     pub key: EntryResultKey,
     pub phrase: jmdict::Entry<'a>,
+    /// The form the phrase is preferably displayed as.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub preferred: Option<PreferredForm<'a>>,
+    /// The inflection sources of the phrase resolved to the forms they match.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub inflections: Vec<InflectionMatch<'a>>,
+    /// Every form of the phrase written with kanji together with its reading,
+    /// if readings were requested. Readings which contain kanji are left out.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub readings: Vec<KanaForm<'a>>,
+    /// Pitch accents of the forms of the phrase, if an index of pitch accents
+    /// has them.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub accents: Vec<ReadingAccent<'a>>,
+    /// Set if a name with the same form and reading was collapsed into the
+    /// phrase.
+    #[musli(default, skip_encoding_if = is_false)]
+    pub also_name: bool,
+    /// Sequence numbers of the names which are written and read the same as
+    /// the phrase.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub names: Vec<u64>,
+    /// The number of example sentences in each sense of the phrase in a
+    /// compact response, where the examples themselves are left out. It's
+    /// left empty if none of them have any, or if the examples are included.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub examples: Vec<usize>,
+    /// The name of the index the phrase comes from.
+    pub index: &'a str,
+}
+
+/// A form written with kanji, together with its reading in kana.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct KanaForm<'a> {
+    pub text: &'a str,
+    pub reading: &'a str,
+}
+
+/// The pitch accents of a form of a phrase.
+#[borrowme::borrowme]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ReadingAccent<'a> {
+    /// The form, which is the same as the reading for phrases written in kana.
+    pub text: &'a str,
+    pub reading: &'a str,
+    /// Accents numbered as described in [`crate::accent`], with the most
+    /// common first.
+    pub accents: Vec<u8>,
+    /// The reading rendered with each accent in the notation requested, if
+    /// one was.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub rendered: Vec<String>,
+}
+
+/// The kanji and reading with the highest priority in a phrase.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PreferredForm<'a> {
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub kanji: Option<&'a str>,
+    pub reading: &'a str,
+}
+
+/// An inflection source resolved against the phrase it belongs to, such as
+/// `帰った` being matched through `帰る` read as `かえる`.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct InflectionMatch<'a> {
+    /// The inflection data of the source.
+    #[copy]
+    pub data: InflectionData,
+    /// The kanji of the dictionary form, if it has any.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub kanji: Option<&'a str>,
+    /// The reading of the dictionary form.
+    pub reading: &'a str,
+    /// The inflected form.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub inflected: Option<String>,
+    /// The reading of the inflected form.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub inflected_reading: Option<String>,
 }
 
 #[borrowme::borrowme]
@@ -246,6 +1796,12 @@ pub struct SearchPhrase<'a> {
 pub struct SearchName<'a> {
     pub key: EntryResultKey,
     pub name: jmnedict::Entry<'a>,
+    /// Sequence numbers of the phrases which are written and read the same as
+    /// the name.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub phrases: Vec<u64>,
+    /// The name of the index the name comes from.
+    pub index: &'a str,
 }
 
 #[borrowme::borrowme]
@@ -255,38 +1811,220 @@ pub struct SearchResponse<'a> {
     pub phrases: Vec<SearchPhrase<'a>>,
     pub names: Vec<SearchName<'a>>,
     pub characters: Vec<kanjidic2::Character<'a>>,
+    /// Inflected forms requested with selectors like `+past`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub inflections: Vec<SearchInflection>,
+    /// Entries from the user's own data when searching with `#mine`, which
+    /// are pinned entries followed by words which were looked up before,
+    /// most recent first.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub mine: Vec<jmdict::Entry<'a>>,
+    /// Phrases from the phrasebook, when searching with `#phrasebook` or
+    /// `#mine`.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub phrasebook: Vec<Phrase>,
+    /// Paths of shards from the manifest which weren't loaded and could hold
+    /// more results.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub missing_shards: Vec<String>,
+    /// Set if the search matched too many entries to return at once, and
+    /// can be continued by passing this in the `continuation` of the next
+    /// request.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub continuation: Option<usize>,
+    /// Set if the phrase searched for found nothing as written, and was
+    /// modified to find something, like by stripping a trailing particle.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub fallback: Option<Fallback>,
+    /// Time spent in each stage of the search, if requested.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub timings: Option<SearchTimings>,
+}
+
+/// An inflected form of a phrase.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SearchInflection {
+    /// The sequence number of the phrase which was inflected.
+    pub sequence: u64,
+    pub text: String,
+    pub reading: String,
+    /// The inflected form with furigana, such as `食[た]べなかった`.
+    pub furigana: String,
+}
+
+/// Time spent in each stage of a search in microseconds.
+#[derive(Debug, Clone, Copy, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SearchTimings {
+    pub parse: u64,
+    pub lookup: u64,
+    pub decode: u64,
+    pub rank: u64,
+    /// Converting results into a response.
+    pub serialize: u64,
 }
 
 #[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct AnalyzeEntry<'a> {
     pub key: Weight,
     pub string: &'a str,
+    /// Start of the matched string in the query, in the requested offset
+    /// encoding.
+    #[musli(default)]
+    pub start: usize,
+    /// End of the matched string in the query, in the requested offset
+    /// encoding.
+    #[musli(default)]
+    pub end: usize,
+    /// How confident the analysis is that this is the right segmentation,
+    /// between zero and one. The confidences of every entry in a response
+    /// sum up to one, so a client can pick an entry by itself only if it's
+    /// confident enough.
+    #[musli(default)]
+    pub confidence: f32,
 }
 
 #[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct AnalyzeResponse<'a> {
     pub data: Vec<AnalyzeEntry<'a>>,
 }
 
+/// A word in segmented text.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SegmentEntry<'a> {
+    pub string: &'a str,
+    /// Start of the word in the query, in the requested offset encoding.
+    pub start: usize,
+    /// End of the word in the query, in the requested offset encoding.
+    pub end: usize,
+    /// The sequence number of the phrase the word is, if it's known.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub sequence: Option<u64>,
+    /// Whether the word is an inflected form of the phrase.
+    #[musli(default)]
+    pub inflection: bool,
+}
+
+/// Text split into words.
+#[borrowme::borrowme]
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct SegmentResponse<'a> {
+    pub segments: Vec<SegmentEntry<'a>>,
+}
+
+#[derive(Debug, Default, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EntryRequest {
+    /// The sequence number of the entry to get.
+    #[serde(default)]
+    #[musli(default)]
+    pub sequence: u32,
+}
+
+impl Request for EntryRequest {
+    const KIND: &'static str = "entry";
+    type Response = OwnedEntryResponse;
+}
+
 #[borrowme::borrowme]
 #[derive(Debug, Encode, Decode)]
 #[musli(mode = Text, name_all = "kebab-case")]
 pub struct EntryResponse<'a> {
     pub entry: jmdict::Entry<'a>,
+    /// Explanations of the priority codes used in the entry.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub priorities: Vec<PriorityInfo>,
+    /// Links from the senses of the entry to other entries.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub links: Vec<Link>,
+}
+
+/// A link from a sense of an entry to another entry.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Link {
+    /// The index of the sense the link is made from.
+    pub sense: usize,
+    pub kind: LinkKind,
+    /// The sequence of the linked entry.
+    pub sequence: u32,
+    /// The sense of the linked entry being referred to, counting from 1.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub target_sense: Option<usize>,
+    /// The reference as written in the dictionary, like `日本・にほん`.
+    pub text: String,
+}
+
+/// A decoded priority code.
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct PriorityInfo {
+    /// The priority code, such as `news1`.
+    pub code: String,
+    /// The category of the priority, such as `news`.
+    pub category: String,
+    pub level: usize,
+    /// Human readable explanation of the category.
+    pub title: String,
+}
+
+impl PriorityInfo {
+    /// Decode the given priority.
+    pub fn new(priority: &Priority) -> Self {
+        Self {
+            code: priority.to_string(),
+            category: priority.category().to_owned(),
+            level: priority.level(),
+            title: priority.title().to_owned(),
+        }
+    }
 }
 
 #[borrowme::borrowme]
 #[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct KanjiResponse<'a> {
     pub kanji: kanjidic2::Character<'a>,
     #[musli(default, skip_encoding_if = Vec::is_empty)]
     pub radicals: Vec<&'a str>,
+    /// Old and variant forms of the kanji, or its standard form if it's a
+    /// variant.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub variants: Vec<String>,
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Radical<'a> {
+    pub radical: &'a str,
+    pub strokes: u8,
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub image: Option<&'a str>,
+}
+
+#[borrowme::borrowme]
+#[derive(Debug, Clone, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct RadicalsResponse<'a> {
+    /// All available radicals.
+    pub radicals: Vec<Radical<'a>>,
+    /// Kanji which satisfy all the requested constraints.
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub kanji: Vec<&'a str>,
 }
 
 #[borrowme::borrowme]
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct LogEntry<'a> {
     /// Timestamp of the log entry in milliseconds since the unix epoch.
     pub timestamp: u64,
@@ -301,6 +2039,7 @@ pub struct LogEntry<'a> {
 /// A message indicating task progress.
 #[borrowme::borrowme]
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct TaskProgress<'a> {
     pub name: &'a str,
     pub value: usize,
@@ -308,11 +2047,86 @@ pub struct TaskProgress<'a> {
     pub step: usize,
     pub steps: usize,
     pub text: &'a str,
+    /// How much of the whole task is done in per mille, weighted by how long
+    /// each step is expected to take.
+    #[musli(default)]
+    pub done: u32,
+    /// How many items per second the current step processes.
+    #[musli(default)]
+    pub rate: Option<f32>,
+    /// Estimated number of seconds left of the whole task.
+    #[musli(default)]
+    pub remaining: Option<u64>,
+    /// The progress of the current step formatted for the locale of the
+    /// client, like `12,345 / 200,000 (1,234/s)`.
+    #[musli(default)]
+    pub summary: &'a str,
+}
+
+/// The state of an image recognition job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum OcrState {
+    /// The job is waiting for an earlier job to finish.
+    Queued,
+    /// Text is being recognized in the image.
+    Recognizing,
+    /// Recognition has finished.
+    Done,
+    /// Recognition failed.
+    Failed,
+    /// The job was superseded by a newer image.
+    Cancelled,
+}
+
+/// Progress of an image recognition job.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct OcrJob {
+    pub id: u64,
+    pub state: OcrState,
+}
+
+/// The response to recognizing text in an uploaded image.
+#[derive(Debug, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct OcrResponse {
+    /// The recognized text after it has been cleaned up, which is empty if
+    /// no text was recognized.
+    pub text: String,
+    /// Byte offset of the word in the text which is most likely to be the
+    /// one being looked up.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub target: Option<usize>,
+    /// The word at `target`.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub word: Option<String>,
+    /// The results of searching for `word`, if it was requested.
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub search: Option<OwnedSearchResponse>,
 }
 
 /// Indicates that a task has been completed.
 #[borrowme::borrowme]
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
 pub struct TaskCompleted<'a> {
     pub name: &'a str,
 }
+
+#[test]
+fn test_offset_encoding() {
+    // The second character is outside of the basic multilingual plane.
+    let string = "食𠮷べる";
+
+    assert_eq!(OffsetEncoding::Utf8.to_byte_offset(string, 3), Some(3));
+    assert_eq!(OffsetEncoding::Utf8.to_byte_offset(string, 4), None);
+    assert_eq!(OffsetEncoding::Utf16.to_byte_offset(string, 3), Some(7));
+    assert_eq!(OffsetEncoding::Utf16.to_byte_offset(string, 2), None);
+    assert_eq!(OffsetEncoding::Chars.to_byte_offset(string, 2), Some(7));
+    assert_eq!(OffsetEncoding::Chars.to_byte_offset(string, 4), Some(13));
+    assert_eq!(OffsetEncoding::Chars.to_byte_offset(string, 5), None);
+
+    assert_eq!(OffsetEncoding::Utf16.to_offset(string, 7), 3);
+    assert_eq!(OffsetEncoding::Chars.to_offset(string, 13), 4);
+}
@@ -0,0 +1,101 @@
+//! Limits on text captured from the clipboard.
+//!
+//! Analyzing a whole article which was copied by accident stalls both the
+//! service and the interface, so long text is cut into chunks which end at
+//! sentence boundaries and only one chunk is analyzed at a time.
+
+/// The default number of characters of captured text which are analyzed at
+/// once.
+pub const DEFAULT_LIMIT: u32 = 2000;
+
+/// Split off the first chunk of at most `limit` characters from the text.
+///
+/// The chunk ends after the last sentence in it, unless it doesn't contain
+/// the end of a sentence in which case it's cut at the limit. Returns the
+/// chunk and the rest of the text, which is empty if the whole text fits. A
+/// limit of `0` means that text isn't limited.
+pub fn split(text: &str, limit: usize) -> (&str, &str) {
+    if limit == 0 {
+        return (text, "");
+    }
+
+    let Some((end, _)) = text.char_indices().nth(limit) else {
+        return (text, "");
+    };
+
+    let mut cut = end;
+
+    if let Some((n, c)) = text[..end]
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| is_sentence_end(c))
+    {
+        cut = n + c.len_utf8();
+
+        // Closing quotes belong to the sentence they end.
+        for c in text[cut..end].chars() {
+            if !matches!(c, '」' | '』' | '）' | ')' | '"') {
+                break;
+            }
+
+            cut += c.len_utf8();
+        }
+    }
+
+    let (chunk, rest) = text.split_at(cut);
+    (chunk, rest.trim_start())
+}
+
+/// Find the chunk which contains the given byte offset, so that analysis can
+/// start from it.
+///
+/// Returns the byte offset the chunk starts at, the chunk and the rest of the
+/// text after it. If the offset is past the end of the text, the first chunk
+/// is returned.
+pub fn chunk_at(text: &str, limit: usize, offset: usize) -> (usize, &str, &str) {
+    let mut start = 0;
+    let mut remaining = text;
+
+    while !remaining.is_empty() {
+        let (chunk, rest) = split(remaining, limit);
+
+        if offset < start + chunk.len() {
+            return (start, chunk, rest);
+        }
+
+        start = text.len() - rest.len();
+        remaining = rest;
+    }
+
+    let (chunk, rest) = split(text, limit);
+    (0, chunk, rest)
+}
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '!' | '?' | '．' | '\n')
+}
+
+#[test]
+fn test_split() {
+    assert_eq!(split("今日は。明日は。", 0), ("今日は。明日は。", ""));
+    assert_eq!(split("今日は。明日は。", 10), ("今日は。明日は。", ""));
+    assert_eq!(split("今日は。明日は。", 6), ("今日は。", "明日は。"));
+    assert_eq!(
+        split("「今日は。」明日は。", 8),
+        ("「今日は。」", "明日は。")
+    );
+    assert_eq!(split("今日は\n 明日は", 6), ("今日は\n", "明日は"));
+    // Without a sentence boundary the text is cut at the limit.
+    assert_eq!(split("今日明日明後日", 3), ("今日明", "日明後日"));
+}
+
+#[test]
+fn test_chunk_at() {
+    let text = "今日は。明日は。明後日は。";
+    assert_eq!(chunk_at(text, 5, 0), (0, "今日は。", "明日は。明後日は。"));
+    assert_eq!(chunk_at(text, 5, 12), (12, "明日は。", "明後日は。"));
+    assert_eq!(
+        chunk_at(text, 5, 100),
+        (0, "今日は。", "明日は。明後日は。")
+    );
+}
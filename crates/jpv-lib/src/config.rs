@@ -1,11 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::storage;
 use crate::Dirs;
 
 const JMDICT_URL: &str = "http://ftp.edrdg.org/pub/Nihongo/JMdict_e_examp.gz";
@@ -25,6 +27,16 @@ const KRADFILE_URL: &str = "http://ftp.edrdg.org/pub/Nihongo/kradfile.gz";
 const KRADFILE_HELP: &str = "https://www.edrdg.org/krad/kradinf.html";
 const KRADFILE_DESCRIPTION: &str = "Radicals from KRADFILE";
 
+const RADKFILE_URL: &str = "http://ftp.edrdg.org/pub/Nihongo/radkfile.gz";
+const RADKFILE_HELP: &str = "https://www.edrdg.org/krad/kradinf.html";
+const RADKFILE_DESCRIPTION: &str = "Radical lookup from RADKFILE";
+
+const PITCH_ACCENT_HELP: &str = "https://github.com/mifunetoshiro/kanjium";
+const PITCH_ACCENT_DESCRIPTION: &str = "Pitch accents from Kanjium";
+
+const TATOEBA_HELP: &str = "https://tatoeba.org/en/downloads";
+const TATOEBA_DESCRIPTION: &str = "Example sentences from Tatoeba";
+
 #[derive(Debug, Error)]
 #[error("Invalid index format")]
 #[non_exhaustive]
@@ -52,6 +64,10 @@ pub enum IndexFormat {
     Jmnedict,
     Kanjidic2,
     Kradfile,
+    Radkfile,
+    PitchAccent,
+    Yomichan,
+    Tatoeba,
 }
 
 impl IndexFormat {
@@ -62,6 +78,22 @@ impl IndexFormat {
             Self::Jmnedict,
             Self::Kanjidic2,
             Self::Kradfile,
+            Self::Radkfile,
+            Self::PitchAccent,
+            Self::Yomichan,
+            Self::Tatoeba,
+        ]
+    }
+
+    /// Get an interator over the index formats which have a default source
+    /// and are configured out of the box.
+    pub fn builtin() -> impl IntoIterator<Item = Self> {
+        [
+            Self::Jmdict,
+            Self::Jmnedict,
+            Self::Kanjidic2,
+            Self::Kradfile,
+            Self::Radkfile,
         ]
     }
 
@@ -72,6 +104,10 @@ impl IndexFormat {
             Self::Jmnedict => "jmnedict",
             Self::Kanjidic2 => "kanjidic2",
             Self::Kradfile => "kradfile",
+            Self::Radkfile => "radkfile",
+            Self::PitchAccent => "pitch-accent",
+            Self::Yomichan => "yomichan",
+            Self::Tatoeba => "tatoeba",
         }
     }
 
@@ -82,6 +118,10 @@ impl IndexFormat {
             Self::Jmnedict => "Names from JMnedict",
             Self::Kanjidic2 => "Kanji from Kanjidic2",
             Self::Kradfile => "Radicals from KRADFILE",
+            Self::Radkfile => "Radical lookup from RADKFILE",
+            Self::PitchAccent => "Pitch accents",
+            Self::Yomichan => "Yomichan or Yomitan dictionary",
+            Self::Tatoeba => "Example sentences",
         }
     }
 
@@ -93,32 +133,80 @@ impl IndexFormat {
                 url: JMDICT_URL.to_owned(),
                 enabled,
                 installing: false,
+                name: None,
                 description: Some(JMDICT_DESCRIPTION.to_owned()),
                 help: Some(JMDICT_HELP.to_owned()),
+                frequencies: None,
             },
             IndexFormat::Jmnedict => ConfigIndex {
                 format: self,
                 url: JMNEDICT_URL.to_owned(),
                 enabled,
                 installing: false,
+                name: None,
                 description: Some(JMNEDICT_DESCRIPTION.to_owned()),
                 help: Some(JMNEDICT_HELP.to_owned()),
+                frequencies: None,
             },
             IndexFormat::Kanjidic2 => ConfigIndex {
                 format: self,
                 url: KANJIDIC2_URL.to_owned(),
                 enabled,
                 installing: false,
+                name: None,
                 description: Some(KANJIDIC2_DESCRIPTION.to_owned()),
                 help: Some(KANJIDIC2_HELP.to_owned()),
+                frequencies: None,
             },
             IndexFormat::Kradfile => ConfigIndex {
                 format: self,
                 url: KRADFILE_URL.to_owned(),
                 enabled,
                 installing: false,
+                name: None,
                 description: Some(KRADFILE_DESCRIPTION.to_owned()),
                 help: Some(KRADFILE_HELP.to_owned()),
+                frequencies: None,
+            },
+            IndexFormat::Radkfile => ConfigIndex {
+                format: self,
+                url: RADKFILE_URL.to_owned(),
+                enabled,
+                installing: false,
+                name: None,
+                description: Some(RADKFILE_DESCRIPTION.to_owned()),
+                help: Some(RADKFILE_HELP.to_owned()),
+                frequencies: None,
+            },
+            IndexFormat::PitchAccent => ConfigIndex {
+                format: self,
+                url: String::new(),
+                enabled,
+                installing: false,
+                name: None,
+                description: Some(PITCH_ACCENT_DESCRIPTION.to_owned()),
+                help: Some(PITCH_ACCENT_HELP.to_owned()),
+                frequencies: None,
+            },
+            IndexFormat::Yomichan => ConfigIndex {
+                format: self,
+                url: String::new(),
+                enabled,
+                installing: false,
+                name: None,
+                description: None,
+                help: None,
+                frequencies: None,
+            },
+            IndexFormat::Tatoeba => ConfigIndex {
+                format: self,
+                url: String::new(),
+                enabled,
+                installing: false,
+                name: None,
+                description: Some(TATOEBA_DESCRIPTION.to_owned()),
+                help: Some(TATOEBA_HELP.to_owned()),
+                frequencies: None,
             },
         }
     }
@@ -133,6 +221,10 @@ impl FromStr for IndexFormat {
             "jmnedict" => Ok(Self::Jmnedict),
             "kanjidic2" => Ok(Self::Kanjidic2),
             "kradfile" => Ok(Self::Kradfile),
+            "radkfile" => Ok(Self::Radkfile),
+            "pitch-accent" => Ok(Self::PitchAccent),
+            "yomichan" => Ok(Self::Yomichan),
+            "tatoeba" => Ok(Self::Tatoeba),
             _ => Err(IndexFormatError),
         }
     }
@@ -148,22 +240,58 @@ pub struct ConfigIndex {
     #[serde(default, skip_serializing_if = "is_false")]
     #[musli(default, skip_encoding_if = is_false)]
     pub installing: bool,
+    /// The name the index is shown as instead of its identifier, in the
+    /// settings and next to the search results which come from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub name: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[musli(default, skip_encoding_if = Option::is_none)]
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[musli(default, skip_encoding_if = Option::is_none)]
     pub help: Option<String>,
+    /// Path to a word frequency list used to rank phrases from an
+    /// [`IndexFormat::Jmdict`] index, or a list of how common names are used
+    /// to rank names from an [`IndexFormat::Jmnedict`] index. See
+    /// [`frequency`] and [`jmnedict::Frequencies`] for their formats.
+    ///
+    /// [`frequency`]: crate::frequency
+    /// [`jmnedict::Frequencies`]: crate::jmnedict::Frequencies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub frequencies: Option<String>,
 }
 
 fn is_false(value: &bool) -> bool {
     !*value
 }
 
+/// The version of the user data stored in the configuration.
+///
+/// User data only refers to entries by their sequence ids, which are stable
+/// across rebuilds of the indexes. Changes to how it's stored are migrated by
+/// [`Config::migrate`].
+///
+/// Changelog:
+/// * `1` - Introduced versioning. Duplicate and empty pins are removed.
+pub const CONFIG_VERSION: u32 = 1;
+
 /// A configuration used for the application.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 #[musli(mode = Text, name_all = "kebab-case")]
 pub struct Config {
+    /// The version of the user data, see [`CONFIG_VERSION`].
+    #[serde(default)]
+    #[musli(default)]
+    pub version: u32,
+    /// The [`DATABASE_VERSION`] which references in the user data were last
+    /// resolved against.
+    ///
+    /// [`DATABASE_VERSION`]: crate::DATABASE_VERSION
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub database_version: Option<u32>,
     /// Enabled indexes.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     #[musli(default, skip_encoding_if = BTreeMap::is_empty)]
@@ -171,24 +299,295 @@ pub struct Config {
     /// Whether OCR support is enabled or not.
     #[serde(default = "default_ocr")]
     pub ocr: bool,
+    /// Whether desktop notifications are emitted when long-running tasks like
+    /// rebuilds finish or fail.
+    #[serde(default)]
+    #[musli(default)]
+    pub notifications: bool,
+    /// Whether a digest of the words looked up the day before is shown as a
+    /// desktop notification once a day, to encourage reviewing them.
+    #[serde(default)]
+    #[musli(default)]
+    pub digest: bool,
+    /// The address the web server binds to. Changing this while the service
+    /// is running causes it to rebind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub bind: Option<String>,
+    /// Whether searches match old and variant forms of kanji (旧字体 and 異体字)
+    /// with their standard forms, so that searching for 國 finds 国.
+    #[serde(default)]
+    #[musli(default)]
+    pub kanji_variants: bool,
+    /// Whether names which are written and read the same as a phrase in the
+    /// search results are collapsed into it.
+    #[serde(default)]
+    #[musli(default)]
+    pub collapse_names: bool,
+    /// Whether searches only include common words by default, as if they
+    /// were tagged with `#common`. Searching with `#all` includes every word.
+    /// Names and kanji are always included.
+    #[serde(default)]
+    #[musli(default)]
+    pub common_only: bool,
+    /// The highest word frequency category (`nf`) a word can have and still
+    /// be considered common. Words marked by the ichi, news, spec or gai
+    /// lists are always common.
+    #[serde(default = "default_common_frequency")]
+    pub common_frequency: u32,
+    /// How the words which text could start with are ranked when it's
+    /// analyzed.
+    #[serde(default)]
+    #[musli(default)]
+    pub analyze_weights: AnalyzeWeights,
+    /// Whether the lookup tries of indexes are read into memory in the
+    /// background after startup, so that the first search doesn't stall while
+    /// they are paged in.
+    #[serde(default)]
+    #[musli(default)]
+    pub warmup: bool,
+    /// Path to the JSON IPC socket of an mpv instance to follow subtitles from,
+    /// as configured with `--input-ipc-server`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub mpv: Option<String>,
+    /// Entries pinned to the top of search results, by the query pattern they
+    /// apply to. Patterns may use `*` to match any sequence of characters.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[musli(default, skip_encoding_if = BTreeMap::is_empty)]
+    pub pins: BTreeMap<String, Vec<u32>>,
+    /// URL template used to link example sentences from the Tanaka corpus to
+    /// their audio, where `{id}` is replaced with the Tatoeba sentence id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub example_audio: Option<String>,
+    /// The percentage of letters in captured clipboard text which have to be
+    /// kana or kanji for it to trigger a search. Set to `0` to capture text in
+    /// any language.
+    #[serde(default = "default_clipboard_threshold")]
+    pub clipboard_threshold: u32,
+    /// The number of characters of captured clipboard text which are
+    /// analyzed at once. Longer text is cut at a sentence boundary, and the
+    /// rest can be analyzed a chunk at a time. Set to `0` to analyze text of
+    /// any length.
+    #[serde(default = "default_clipboard_limit")]
+    pub clipboard_limit: u32,
+    /// URLs which a JSON summary of searches and clipboard captures is posted
+    /// to, to integrate with other tools.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub webhooks: Vec<Webhook>,
+    /// What the embedded view shows when it's opened without a query.
+    #[serde(default)]
+    #[musli(default)]
+    pub embed_landing: EmbedLanding,
+    /// Whether the service is a public instance which refuses requests that
+    /// modify it, and which doesn't show local paths, the history of looked
+    /// up words or the phrasebook in its responses.
+    #[serde(default)]
+    #[musli(default)]
+    pub read_only: bool,
+    /// Directives which filter what the service logs, like
+    /// `jpv=info,jpv::ocr=debug`. These are used instead of the defaults
+    /// unless `RUST_LOG` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub log_filter: Option<String>,
+}
+
+/// How the words which text could start with are ranked when it's analyzed.
+///
+/// Both weights are percentages. With a `frequency` of `0` and a `length` of
+/// `100`, words are ranked the same way as they are in search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct AnalyzeWeights {
+    /// How much more strongly common words are preferred than in search
+    /// results.
+    #[serde(default = "default_analyze_frequency")]
+    pub frequency: u32,
+    /// How strongly longer words are preferred, where `0` doesn't prefer them
+    /// at all. Lowering this keeps a long and rare compound from winning over
+    /// a common word it starts with.
+    #[serde(default = "default_analyze_length")]
+    pub length: u32,
+}
+
+impl Default for AnalyzeWeights {
+    fn default() -> Self {
+        Self {
+            frequency: default_analyze_frequency(),
+            length: default_analyze_length(),
+        }
+    }
+}
+
+fn default_analyze_frequency() -> u32 {
+    50
+}
+
+fn default_analyze_length() -> u32 {
+    75
+}
+
+/// What the embedded view shows when it's opened without a query.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum EmbedLanding {
+    /// Nothing, until something is looked up.
+    #[default]
+    Empty,
+    /// The last query searched for by the same client since the service
+    /// started.
+    LastQuery,
+    /// The most recently added phrases in the phrasebook.
+    Phrasebook,
+    /// A common word picked for the day.
+    WordOfDay,
+}
+
+impl EmbedLanding {
+    /// Get an iterator over every landing.
+    pub fn all() -> impl IntoIterator<Item = Self> {
+        [
+            Self::Empty,
+            Self::LastQuery,
+            Self::Phrasebook,
+            Self::WordOfDay,
+        ]
+    }
+
+    /// Get the identifier of a landing.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::LastQuery => "last-query",
+            Self::Phrasebook => "phrasebook",
+            Self::WordOfDay => "word-of-day",
+        }
+    }
+
+    /// Get a description of a landing.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Empty => "Nothing",
+            Self::LastQuery => "The last query",
+            Self::Phrasebook => "Recently added phrases in the phrasebook",
+            Self::WordOfDay => "A word of the day",
+        }
+    }
+}
+
+/// A URL which events are posted to as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Webhook {
+    pub url: String,
+    /// The events which are posted, or every event if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[musli(default, skip_encoding_if = Vec::is_empty)]
+    pub events: Vec<WebhookEvent>,
+}
+
+impl Webhook {
+    /// Test if the given event is posted to this webhook.
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// An event which can be posted to a [`Webhook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum WebhookEvent {
+    /// A search was performed.
+    Search,
+    /// Text was captured from the clipboard, or recognized in a captured
+    /// image.
+    Clipboard,
+}
+
+/// User interface settings of a single client, like a browser on a particular
+/// device.
+///
+/// These are stored separately from [`Config`], keyed by an identifier the
+/// client is assigned on its first visit.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct ClientSettings {
+    /// Whether the interface is embedded, hiding navigation.
+    #[serde(default)]
+    #[musli(default)]
+    pub embed: bool,
+    /// The name of the theme in use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub theme: Option<String>,
+    /// The font size in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub font_size: Option<u32>,
+    /// The number of results shown before more have to be requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub result_limit: Option<usize>,
+    /// When the settings were last updated in seconds since the Unix epoch,
+    /// which is set by the service and used to prune old clients.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    #[musli(default, skip_encoding_if = is_zero)]
+    pub updated: u64,
+}
+
+impl ClientSettings {
+    /// Test if these settings are the same as the defaults, in which case
+    /// they don't have to be stored.
+    pub fn is_default(&self) -> bool {
+        *self
+            == Self {
+                updated: self.updated,
+                ..Self::default()
+            }
+    }
+
+    /// Load the settings of every client by their identifier.
+    pub fn load_all(dirs: &Dirs) -> Result<BTreeMap<String, Self>> {
+        let path = dirs.client_settings_path();
+        let clients = storage::load(&path, |data| Ok(toml::from_str(data)?))?;
+        Ok(clients.unwrap_or_default())
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
 }
 
 fn default_ocr() -> bool {
     true
 }
 
+fn default_common_frequency() -> u32 {
+    24
+}
+
+fn default_clipboard_threshold() -> u32 {
+    50
+}
+
+fn default_clipboard_limit() -> u32 {
+    crate::clipboard::DEFAULT_LIMIT
+}
+
 impl Config {
     pub fn load(dirs: &Dirs) -> Result<Self> {
         let config_path = dirs.config_path();
 
-        let mut config = if config_path.exists() {
-            let data = std::fs::read_to_string(&config_path)?;
-            toml::from_str(&data)?
-        } else {
-            Self::default()
-        };
+        let mut config = storage::load(&config_path, |data| Ok(toml::from_str::<Self>(data)?))?
+            .unwrap_or_default();
 
-        for format in IndexFormat::all() {
+        for format in IndexFormat::builtin() {
             if !config.indexes.contains_key(format.id()) {
                 config
                     .indexes
@@ -199,6 +598,61 @@ impl Config {
         Ok(config)
     }
 
+    /// Migrate user data written by an older version of the configuration,
+    /// returning `true` if anything was migrated.
+    pub fn migrate(&mut self) -> bool {
+        if self.version >= CONFIG_VERSION {
+            return false;
+        }
+
+        if self.version < 1 {
+            for sequences in self.pins.values_mut() {
+                let mut seen = HashSet::new();
+                sequences.retain(|sequence| seen.insert(*sequence));
+            }
+
+            self.pins.retain(|_, sequences| !sequences.is_empty());
+        }
+
+        self.version = CONFIG_VERSION;
+        true
+    }
+
+    /// Validate options which would otherwise only fail once they're used,
+    /// like the address to bind to when the service starts.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(bind) = &self.bind {
+            bind.parse::<SocketAddr>()
+                .with_context(|| format!("Invalid bind address `{bind}`"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove local paths and addresses which a public instance shouldn't
+    /// show.
+    pub fn redact(&mut self) {
+        self.bind = None;
+        self.mpv = None;
+        self.webhooks.clear();
+
+        for index in self.indexes.values_mut() {
+            index.frequencies = None;
+        }
+    }
+
+    /// Construct the audio URL for the example sentence with the given source
+    /// id and type, if a template is configured and the source is Tatoeba.
+    pub fn example_audio_url(&self, id: &str, ty: Option<&str>) -> Option<String> {
+        let template = self.example_audio.as_deref()?;
+
+        if ty != Some("tat") {
+            return None;
+        }
+
+        Some(template.replace("{id}", id))
+    }
+
     /// Toggle the specified index kind.
     pub fn toggle(&mut self, id: &str) {
         if let Some(index) = self.indexes.get_mut(id) {
@@ -206,6 +660,16 @@ impl Config {
         }
     }
 
+    /// Get the name the given index is shown as, which is its identifier
+    /// unless it has been given a name.
+    pub fn index_name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.indexes
+            .get(id)
+            .and_then(|index| index.name.as_deref())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(id)
+    }
+
     /// Test if the given index is enabled.
     pub fn is_enabled(&self, id: &str) -> bool {
         let Some(index) = self.indexes.get(id) else {
@@ -238,10 +702,49 @@ impl Default for Config {
     fn default() -> Self {
         let mut indexes = BTreeMap::new();
 
-        for format in IndexFormat::all() {
+        for format in IndexFormat::builtin() {
             indexes.insert(format.id().to_owned(), format.default_config(true));
         }
 
-        Self { indexes, ocr: true }
+        Self {
+            version: CONFIG_VERSION,
+            database_version: None,
+            indexes,
+            ocr: true,
+            notifications: false,
+            digest: false,
+            bind: None,
+            kanji_variants: false,
+            collapse_names: false,
+            common_only: false,
+            common_frequency: default_common_frequency(),
+            analyze_weights: AnalyzeWeights::default(),
+            warmup: false,
+            mpv: None,
+            pins: BTreeMap::new(),
+            example_audio: None,
+            clipboard_threshold: default_clipboard_threshold(),
+            clipboard_limit: default_clipboard_limit(),
+            webhooks: Vec::new(),
+            embed_landing: EmbedLanding::default(),
+            read_only: false,
+            log_filter: None,
+        }
+    }
+}
+
+#[test]
+fn test_validate() {
+    let mut config = Config::default();
+    assert!(config.validate().is_ok());
+
+    for bind in ["0.0.0.0:44714", "127.0.0.1:8080", "[::1]:44714"] {
+        config.bind = Some(bind.to_owned());
+        assert!(config.validate().is_ok(), "{bind}");
+    }
+
+    for bind in ["", "localhost", "127.0.0.1", "127.0.0.1:port"] {
+        config.bind = Some(bind.to_owned());
+        assert!(config.validate().is_err(), "{bind}");
     }
 }
@@ -12,10 +12,11 @@ mod r#impl;
 
 pub use self::r#impl::open;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
+use crate::database::shard::Manifest;
 use crate::database::Location;
 use crate::dirs::Dirs;
 
@@ -40,3 +41,29 @@ pub fn open_from_args(indexes: &[PathBuf], dirs: &Dirs) -> Result<Vec<(Data, Loc
 
     Ok(output)
 }
+
+/// Open the selected shards listed in the manifest at the given path, see
+/// [`ManifestShard::is_selected`].
+///
+/// [`ManifestShard::is_selected`]: crate::database::shard::ManifestShard::is_selected
+pub fn open_shards(
+    manifest: &Path,
+    selected: &[String],
+) -> Result<(Manifest, Vec<(Data, Location)>)> {
+    let loaded = Manifest::load(manifest)?;
+    let root = manifest.parent().unwrap_or(Path::new(""));
+
+    let mut output = Vec::new();
+
+    for shard in &loaded.shards {
+        if !shard.is_selected(selected) {
+            continue;
+        }
+
+        let path = root.join(&shard.path);
+        let data = r#impl::open(&path).with_context(|| path.display().to_string())?;
+        output.push((data, Location::Path(path.into())));
+    }
+
+    Ok((loaded, output))
+}
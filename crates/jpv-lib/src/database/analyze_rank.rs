@@ -0,0 +1,125 @@
+//! Ranking of the words which analyzed text could start with.
+//!
+//! The weight of an entry is made for ordering search results, where a longer
+//! match is almost always what's being looked for. When text is analyzed the
+//! candidates are prefixes of each other, so that preference lets a long and
+//! rare compound win over the common word it starts with. Here the length
+//! preference of the weight is replaced with a configurable one, and how
+//! frequent a word is can be given extra weight.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::AnalyzeWeights;
+use crate::priority::Priority;
+use crate::sort_key::length_boost;
+use crate::Weight;
+
+/// Rank a word which analyzed text could start with.
+///
+/// The `weight` is the weight of the entry matching the word, and
+/// `frequency` is the weight of its priorities as returned by [`frequency`].
+pub(super) fn rank(word: &str, weight: Weight, frequency: f32, weights: &AnalyzeWeights) -> Weight {
+    let length = length_boost(word);
+
+    if length <= 0.0 {
+        return weight;
+    }
+
+    let length_weight = weights.length as f32 / 100.0;
+    let frequency_weight = weights.frequency as f32 / 100.0;
+
+    let value = weight.value() / length
+        * length.powf(length_weight)
+        * frequency.max(1.0).powf(frequency_weight);
+
+    Weight::new(value)
+}
+
+/// How frequent an entry with the given priorities is, where `1.0` is an
+/// entry without any.
+pub(super) fn frequency<'a, I>(priorities: I) -> f32
+where
+    I: IntoIterator<Item = &'a Priority>,
+{
+    priorities
+        .into_iter()
+        .map(Priority::weight)
+        .fold(1.0, f32::max)
+}
+
+/// Order ranked words by their rank, with the best ranked word first.
+///
+/// Words which rank the same are resolved in favor of the shortest one, since
+/// it's the one which is least likely to swallow the start of the next word.
+pub(super) fn order(ranked: HashMap<&str, Weight>) -> BTreeMap<Weight, &str> {
+    let mut ranked = ranked.into_iter().collect::<Vec<_>>();
+    // All words are prefixes of the same text, so their lengths in bytes are
+    // ordered the same as their lengths in characters.
+    ranked.sort_by_key(|&(word, _)| word.len());
+
+    let mut output = BTreeMap::new();
+
+    for (word, weight) in ranked {
+        output.entry(weight).or_insert(word);
+    }
+
+    output
+}
+
+/// Cases of analyzed text, with the features of the entries which each
+/// candidate matches and the word which should be picked.
+#[cfg(test)]
+const CASES: &str = include_str!("analyze_cases.txt");
+
+#[test]
+fn test_cases() {
+    let weights = AnalyzeWeights::default();
+
+    let mut lines = CASES
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let mut count = 0;
+
+    while let Some(line) = lines.next() {
+        let (text, expected) = line.split_once(" => ").expect("missing expected word");
+        let mut ranked = HashMap::new();
+
+        while let Some(line) = lines.next_if(|line| line.starts_with(' ')) {
+            let mut columns = line.split_whitespace();
+            let word = columns.next().expect("missing word");
+            let weight = columns.next().expect("missing weight");
+            let weight = Weight::new(weight.parse().expect("bad weight"));
+
+            let priorities = columns
+                .next()
+                .unwrap_or("-")
+                .split(',')
+                .filter(|p| *p != "-")
+                .map(|p| Priority::parse(p).expect("bad priority"))
+                .collect::<Vec<_>>();
+
+            assert!(text.starts_with(word), "{word} is not a prefix of {text}");
+            ranked.insert(word, rank(word, weight, frequency(&priorities), &weights));
+        }
+
+        let best = order(ranked).into_values().next();
+        assert_eq!(best, Some(expected), "analyzing {text}");
+        count += 1;
+    }
+
+    assert!(count > 0);
+}
+
+#[test]
+fn test_order_ties() {
+    let ranked = HashMap::from([
+        ("入学試験", Weight::new(2.0)),
+        ("入学", Weight::new(2.0)),
+        ("入", Weight::new(1.0)),
+    ]);
+
+    let order = order(ranked);
+    assert_eq!(order.into_values().collect::<Vec<_>>(), ["入学", "入"]);
+}
@@ -1,14 +1,25 @@
 //! Database that can be used as a dictionary.
 
 mod analyze_glossary;
+mod analyze_rank;
+mod segment;
+pub mod shard;
 mod stored;
 mod string_indexer;
+#[cfg(test)]
+mod tests;
+mod wildcard;
 
 use std::borrow::Cow;
-use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
-use std::fmt;
+use std::collections::hash_map::{self, DefaultHasher};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write as _};
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::slice;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, ensure, Context, Result};
 use fixed_map::Set;
@@ -18,17 +29,26 @@ use musli_zerocopy::{swiss, trie, OwnedBuf, Ref, ZeroCopy};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::config::Config;
+use crate::config::{AnalyzeWeights, Config};
 use crate::data::Data;
+use crate::drill;
+use crate::frequency;
 use crate::inflection::{self, Inflection};
 use crate::jmdict;
 use crate::jmnedict;
-use crate::kana;
+use crate::kana::{self, Fragments};
 use crate::kanjidic2;
 use crate::kradfile;
+use crate::pitch;
+use crate::priority;
+use crate::radkfile;
 use crate::reporter::Reporter;
-use crate::romaji::{self, Segment};
+use crate::romaji;
+use crate::tatoeba;
 use crate::token::Token;
+use crate::typing;
+use crate::variants;
+use crate::yomichan;
 use crate::{PartOfSpeech, Weight};
 use crate::{DATABASE_MAGIC, DATABASE_VERSION};
 
@@ -37,6 +57,12 @@ use self::string_indexer::StringIndexer;
 /// Encoding used for storing database.
 const ENCODING: Encoding = Encoding::new();
 
+/// The percentage of building an index which is spent in [`build`], where
+/// the rest is spent reading the input and writing the index.
+///
+/// The steps reported by [`build`] are weighted to add up to this.
+pub const BUILD_WEIGHT: u32 = 85;
+
 /// An error raised while interacting with the database.
 #[derive(Debug, Error)]
 pub enum IndexOpenError {
@@ -160,6 +186,11 @@ pub enum NameIndex {
     Katakana,
     /// Romanized.
     Romanized,
+    /// Translation of the name, like `Tanaka` for 田中.
+    Translation,
+    /// The literal reading with variant kanji replaced by their standard
+    /// forms.
+    Variant,
 }
 
 #[derive(
@@ -195,6 +226,14 @@ pub enum PhraseIndex {
     Romanized,
     /// Indexed by meaning.
     Meaning,
+    /// Indexed by kanji reading with its okurigana removed.
+    Okurigana,
+    /// Indexed by kanji reading with variant kanji replaced by their standard
+    /// forms.
+    Variant,
+    /// Indexed by a whole meaning, as opposed to [`PhraseIndex::Meaning`]
+    /// which also indexes the phrases a meaning starts with.
+    MeaningExact,
 }
 
 /// Data stored for a given inflection.
@@ -219,6 +258,83 @@ pub struct InflectionData {
     pub inflection: Inflection,
 }
 
+impl InflectionData {
+    /// Resolve the kanji and reading of the given entry which the inflection
+    /// is based on.
+    pub fn resolve<'a>(&self, entry: &jmdict::Entry<'a>) -> Option<(Option<&'a str>, &'a str)> {
+        let reading = entry
+            .reading_elements
+            .get(usize::from(self.reading.reading))?;
+
+        let kanji = match self.reading.kanji {
+            u8::MAX => None,
+            kanji => Some(entry.kanji_elements.get(usize::from(kanji))?.text),
+        };
+
+        Some((kanji, reading.text))
+    }
+}
+
+/// How words found by [`Database::words_with_kanji`] are ordered.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum WordSort {
+    /// Most common words first, preferring shorter words among words which
+    /// are equally common.
+    #[default]
+    Common,
+    /// Shortest words first, preferring more common words among words of the
+    /// same length.
+    Short,
+}
+
+/// The kind of a link from a sense to another phrase.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    ZeroCopy,
+)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+#[repr(u8)]
+pub enum LinkKind {
+    /// A cross-reference to a phrase sharing a meaning with the sense.
+    Synonym,
+    /// An antonym of the sense.
+    Antonym,
+    /// Any other cross-reference.
+    SeeAlso,
+}
+
+/// A link from a sense to another phrase.
+#[derive(Debug, Clone, Copy)]
+pub struct Link {
+    /// The index of the sense the link is made from.
+    pub sense: usize,
+    /// The index of the link among the cross-references of the sense, or among
+    /// its antonyms if the link is an antonym.
+    pub index: usize,
+    /// The kind of the link.
+    pub kind: LinkKind,
+    /// The sequence of the linked phrase.
+    pub sequence: u32,
+    /// The sense of the linked phrase being referred to, counting from 1.
+    pub target_sense: Option<usize>,
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, Encode, Decode,
 )]
@@ -248,6 +364,66 @@ impl Source {
             _ => false,
         }
     }
+
+    /// Test if the source is a whole meaning of a phrase.
+    pub fn is_exact_meaning(&self) -> bool {
+        match self {
+            Source::Phrase {
+                index: PhraseIndex::MeaningExact,
+            } => true,
+            _ => false,
+        }
+    }
+}
+
+/// The part of an index an entry is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Section {
+    Phrases,
+    Kanji,
+    Names,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Phrases => write!(f, "phrases"),
+            Section::Kanji => write!(f, "kanji"),
+            Section::Names => write!(f, "names"),
+        }
+    }
+}
+
+/// An entry together with the bytes it's stored as, for debugging how
+/// entries are encoded.
+pub struct RawEntry<'a> {
+    /// The name of the index the entry is stored in.
+    pub index: &'a str,
+    /// The part of the index the entry is stored in.
+    pub section: Section,
+    /// The byte offset of the entry in the index.
+    pub offset: u32,
+    /// The bytes the entry is stored as.
+    pub bytes: &'a [u8],
+    /// Whether encoding the decoded entry again produces the same bytes.
+    pub round_trips: bool,
+    /// The decoded entry.
+    pub entry: Entry<'a>,
+}
+
+impl RawEntry<'_> {
+    /// The stored bytes formatted as lowercase hex.
+    pub fn hex(&self) -> String {
+        let mut output = String::with_capacity(self.bytes.len() * 2);
+
+        for b in self.bytes {
+            _ = write!(output, "{b:02x}");
+        }
+
+        output
+    }
 }
 
 #[derive(
@@ -284,11 +460,23 @@ impl Id {
 }
 
 /// Input to build the database.
+#[derive(Clone, Copy)]
 pub enum Input<'a> {
-    Jmdict(&'a str),
+    /// JMdict together with an optional word frequency list, see
+    /// [`frequency`].
+    Jmdict(&'a str, Option<&'a str>),
     Kanjidic2(&'a str),
-    Jmnedict(&'a str),
+    /// JMnedict together with optional name frequencies, see
+    /// [`jmnedict::Frequencies`].
+    Jmnedict(&'a str, Option<&'a str>),
     Kradfile(&'a [u8]),
+    Radkfile(&'a [u8]),
+    /// A list of pitch accents, see [`pitch`].
+    PitchAccent(&'a str),
+    /// A corpus of example sentences, see [`tatoeba`].
+    Tatoeba(&'a str),
+    /// The banks of a Yomichan or Yomitan dictionary, see [`yomichan`].
+    Yomichan(&'a [yomichan::Bank<'a>]),
 }
 
 impl Input<'_> {
@@ -298,15 +486,265 @@ impl Input<'_> {
             Input::Kanjidic2(..) => "Kanjidic2",
             Input::Jmnedict(..) => "JMnedict",
             Input::Kradfile(..) => "Kradfile",
+            Input::Radkfile(..) => "Radkfile",
+            Input::PitchAccent(..) => "Pitch accents",
+            Input::Tatoeba(..) => "Tatoeba",
+            Input::Yomichan(..) => "Yomichan",
+        }
+    }
+}
+
+/// Source of phrases being built, which are either parsed or converted from
+/// another dictionary format.
+enum Phrases<'a> {
+    Jmdict(jmdict::Parser<'a>),
+    Converted(slice::Iter<'a, jmdict::OwnedEntry>),
+}
+
+impl<'a> Phrases<'a> {
+    fn new(input: Input<'a>, yomichan: Option<&'a yomichan::Dictionary>) -> Option<Self> {
+        match (input, yomichan) {
+            (Input::Jmdict(input, _), _) => Some(Phrases::Jmdict(jmdict::Parser::new(input))),
+            (_, Some(dictionary)) => Some(Phrases::Converted(dictionary.phrases.iter())),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<jmdict::Entry<'a>>> {
+        match self {
+            Phrases::Jmdict(parser) => parser.parse(),
+            Phrases::Converted(iter) => Ok(iter.next().map(crate::borrow)),
+        }
+    }
+}
+
+/// Source of kanji being built, which are either parsed or converted from
+/// another dictionary format.
+enum Characters<'a> {
+    Kanjidic2(kanjidic2::Parser<'a>),
+    Converted(slice::Iter<'a, kanjidic2::OwnedCharacter>),
+}
+
+impl<'a> Characters<'a> {
+    fn new(input: Input<'a>, yomichan: Option<&'a yomichan::Dictionary>) -> Option<Self> {
+        match (input, yomichan) {
+            (Input::Kanjidic2(input), _) => {
+                Some(Characters::Kanjidic2(kanjidic2::Parser::new(input)))
+            }
+            (_, Some(dictionary)) => Some(Characters::Converted(dictionary.kanji.iter())),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<kanjidic2::Character<'a>>> {
+        match self {
+            Characters::Kanjidic2(parser) => parser.parse(),
+            Characters::Converted(iter) => Ok(iter.next().map(crate::borrow)),
+        }
+    }
+}
+
+/// The maximum number of ids produced by a search for a wildcard pattern like
+/// `*` or `食*`, which could otherwise match a large part of the database.
+pub const WILDCARD_LIMIT: usize = 10_000;
+
+/// The maximum number of sentences looked through by
+/// [`Database::example_sentences`] when the word isn't listed among their
+/// words, since common kana are part of most sentences.
+const EXAMPLE_SENTENCE_SCAN_LIMIT: usize = 10_000;
+
+/// The longest word in characters which text is split into by
+/// [`Database::segment`].
+const SEGMENT_MAX_WORD: usize = 16;
+
+/// The longest text in characters which is split into words by
+/// [`Database::segment`], the rest is left as a single unknown segment.
+pub const SEGMENT_MAX_TEXT: usize = 4096;
+
+/// The longest phrase of a meaning in characters which is indexed, longer
+/// phrases are unlikely to be searched for.
+const MAX_MEANING_PHRASE: usize = 24;
+
+/// How much phrases with a meaning which is exactly what's being searched for
+/// are boosted, over those which have a meaning starting with it.
+const EXACT_MEANING_BOOST: f32 = 3.0;
+
+/// The number of phrases which are looked at when picking the word of the day
+/// before giving up.
+const WORD_OF_DAY_ATTEMPTS: usize = 1000;
+
+/// The number of characters a phrase needs to have to fall back to finding
+/// entries starting with it.
+const MIN_PREFIX: usize = 2;
+
+/// Particles which are stripped from the end of a phrase which found nothing,
+/// longest first so that compound particles like `には` are stripped whole.
+const TRAILING_PARTICLES: &[&str] = &[
+    "からは",
+    "までは",
+    "には",
+    "では",
+    "とは",
+    "へは",
+    "にも",
+    "でも",
+    "から",
+    "まで",
+    "より",
+    "を",
+    "は",
+    "が",
+    "へ",
+    "に",
+    "で",
+    "と",
+    "も",
+    "の",
+    "や",
+    "か",
+    "ね",
+    "よ",
+];
+
+/// Sentences found by [`Database::example_sentences`].
+pub struct ExampleSentences<'a> {
+    pub sentences: Vec<tatoeba::Sentence<'a>>,
+    /// Set if too many sentences contain the word for all of them to be looked
+    /// through, so some of them might be missing.
+    pub truncated: bool,
+}
+
+/// Ids found by a lookup.
+pub struct Lookup {
+    pub ids: Vec<Id>,
+    /// Set if a lookup of a wildcard pattern matched more than
+    /// [`WILDCARD_LIMIT`] ids, and can be continued from here.
+    pub continuation: Option<usize>,
+}
+
+impl Lookup {
+    /// Collect at most `limit` ids, skipping the ones before the
+    /// continuation.
+    fn collect<I>(ids: I, continuation: usize, limit: usize) -> Result<Self>
+    where
+        I: IntoIterator<Item = Result<Id>>,
+    {
+        let mut it = ids.into_iter().skip(continuation);
+        let mut output = Vec::new();
+
+        for id in it.by_ref().take(limit) {
+            output.push(id?);
         }
+
+        let continuation = it.next().is_some().then_some(continuation + output.len());
+
+        Ok(Self {
+            ids: output,
+            continuation,
+        })
     }
 }
 
+/// A word in text split by [`Database::segment`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<'q> {
+    /// The text of the word.
+    pub text: &'q str,
+    /// The byte offset where the word starts in the segmented text.
+    pub start: usize,
+    /// The sequence number of the phrase the word is, or `None` if it's not
+    /// a known word.
+    pub sequence: Option<u64>,
+    /// Whether the word is an inflected form of the phrase.
+    pub inflection: bool,
+}
+
 /// A search result.
 pub struct Search<'a> {
     pub phrases: Vec<(EntryResultKey, jmdict::Entry<'a>)>,
     pub names: Vec<(EntryResultKey, jmnedict::Entry<'a>)>,
     pub characters: Vec<kanjidic2::Character<'a>>,
+    /// The requested inflection of matching phrases, by their sequence number.
+    pub inflections: Vec<(u64, Fragments<'a>)>,
+    /// Pinned entries when searching with `#mine`.
+    pub mine: Vec<jmdict::Entry<'a>>,
+    /// Paths from the manifest of shards which weren't loaded and could hold
+    /// more results, see [`Database::open_partial`].
+    pub missing_shards: Vec<String>,
+    /// Set if the first phrase searched for is a wildcard pattern which
+    /// matched too many entries, and the search can be continued from here.
+    pub continuation: Option<usize>,
+    /// Set if the phrase searched for found nothing as written, and was
+    /// modified to find something.
+    pub fallback: Option<Fallback>,
+    /// Time spent in each stage of the search.
+    pub timings: Timings,
+}
+
+/// How a phrase which found nothing as written was modified to find
+/// something.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Fallback {
+    /// The phrase as it was written, like `本を。`.
+    pub original: String,
+    /// The phrase which was searched for instead, like `本`.
+    pub matched: String,
+    /// Trailing particles and punctuation which were stripped, like `を。`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[musli(default, skip_encoding_if = String::is_empty)]
+    pub stripped: String,
+    /// Whether entries starting with `matched` were found, rather than
+    /// entries matching it exactly.
+    #[serde(default)]
+    #[musli(default)]
+    pub prefix: bool,
+}
+
+/// Constraints which kanji found by [`Database::search_kanji`] have to
+/// satisfy all of.
+#[derive(Debug, Default, Clone)]
+pub struct KanjiConstraints<'a> {
+    /// Radicals which the kanji have to contain.
+    pub radicals: Vec<&'a str>,
+    /// The range of stroke counts the kanji have to be in.
+    pub strokes: Option<RangeInclusive<u8>>,
+    /// The highest grade the kanji can be taught in.
+    pub max_grade: Option<u8>,
+}
+
+impl KanjiConstraints<'_> {
+    /// Test if there are no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.radicals.is_empty() && self.strokes.is_none() && self.max_grade.is_none()
+    }
+}
+
+/// Constraints on candidates for a game of shiritori.
+#[derive(Debug, Default, Clone)]
+pub struct ShiritoriConstraints {
+    /// Exclude words whose reading ends in ん, since playing them loses the
+    /// game.
+    pub exclude_n: bool,
+    /// Only include words which have a priority, making them common.
+    pub common: bool,
+    /// Sequence numbers of words which have already been played.
+    pub played: HashSet<u32>,
+    /// The maximum number of candidates to return.
+    pub limit: usize,
+}
+
+/// Time spent in each stage of a search.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    /// Parsing the query.
+    pub parse: Duration,
+    /// Looking up matching entries in each index.
+    pub lookup: Duration,
+    /// Decoding and filtering matching entries.
+    pub decode: Duration,
+    /// Weighing and sorting results.
+    pub rank: Duration,
 }
 
 /// Build a dictionary from the given jmdict and kanjidic sources.
@@ -315,6 +753,35 @@ pub fn build(
     shutdown: &Token,
     name: &str,
     input: Input<'_>,
+) -> Result<OwnedBuf> {
+    build_inner(reporter, shutdown, name, input, None)
+}
+
+/// Build a single shard of a dictionary, which only holds the phrases and
+/// names whose first reading is in the given shard, see [`shard::of`].
+pub fn build_shard(
+    reporter: &dyn Reporter,
+    shutdown: &Token,
+    name: &str,
+    input: Input<'_>,
+    shard: &str,
+) -> Result<OwnedBuf> {
+    ensure!(
+        matches!(input, Input::Jmdict(..) | Input::Jmnedict(..)),
+        "{} can't be split into shards",
+        input.name()
+    );
+
+    ensure!(shard::SHARDS.contains(&shard), "No shard named `{shard}`");
+    build_inner(reporter, shutdown, name, input, Some(shard))
+}
+
+fn build_inner(
+    reporter: &dyn Reporter,
+    shutdown: &Token,
+    name: &str,
+    input: Input<'_>,
+    shard: Option<&str>,
 ) -> Result<OwnedBuf> {
     let mut buf = OwnedBuf::new();
 
@@ -322,197 +789,362 @@ pub fn build(
     let index = buf.store_uninit::<stored::IndexHeader>();
 
     let name = buf.store_unsized(name);
+    let stored_shard = buf.store_unsized(shard.unwrap_or_default());
+
+    // Test if an entry with the given first reading belongs in the shard
+    // being built.
+    let in_shard = |reading: Option<&str>| {
+        let Some(shard) = shard else {
+            return true;
+        };
+
+        reading.and_then(shard::of).unwrap_or(shard::OTHER) == shard
+    };
+
+    let yomichan = match input {
+        Input::Yomichan(banks) => {
+            let dictionary = yomichan::Dictionary::parse(banks)?;
+
+            if let Some(title) = &dictionary.title {
+                report_info!(reporter, "Importing `{title}`");
+            }
+
+            for bank in &dictionary.skipped {
+                report_warn!(reporter, "Skipped unsupported bank `{bank}`");
+            }
+
+            Some(dictionary)
+        }
+        _ => None,
+    };
 
     let mut output = Vec::new();
     let mut lookup = Vec::new();
 
-    let mut by_sequence = HashMap::new();
-    let mut by_pos = HashMap::<_, HashSet<_>>::new();
-    let mut kanji_literals = HashMap::new();
-    let mut input_radicals = HashMap::new();
-    let mut input_radicals_to_kanji = HashMap::<_, Vec<_>>::new();
+    // Everything which is iterated over while building uses ordered
+    // collections, so that identical inputs produce identical indexes.
+    let mut by_sequence = BTreeMap::new();
+    let mut links = BTreeMap::new();
+    let mut words_by_kanji = BTreeMap::<_, Vec<_>>::new();
+    let mut name_frequencies = Vec::new();
+    let mut phrase_frequencies = Vec::new();
+    let mut commonness = Vec::new();
+    let mut languages = BTreeSet::new();
+    let mut by_pos = BTreeMap::<_, BTreeSet<_>>::new();
+    let mut kanji_literals = BTreeMap::new();
+    let mut input_kanji_by_strokes = BTreeMap::<_, Vec<_>>::new();
+    let mut input_kanji_by_grade = BTreeMap::<_, Vec<_>>::new();
+    let mut input_radicals = BTreeMap::new();
+    let mut input_radicals_to_kanji = BTreeMap::<_, Vec<_>>::new();
+    let mut input_by_radical = BTreeMap::new();
+    let mut input_accents = BTreeMap::<_, Vec<u8>>::new();
+    let mut input_phrases_by_form = BTreeMap::<_, Vec<_>>::new();
+    let mut input_names_by_form = BTreeMap::<_, Vec<_>>::new();
+    let mut input_sentences_by_word = BTreeMap::<_, Vec<_>>::new();
+    let mut sentences_by_char = BTreeMap::<_, Vec<_>>::new();
     let mut inflections = Vec::new();
     let mut inflections_index = HashMap::new();
     let mut phrases = Vec::new();
     let mut kanji = Vec::new();
+    let mut names = Vec::new();
+    let mut radkfile = Vec::new();
+    let mut sentences = Vec::new();
 
     reporter.instrument_start(
         module_path!(),
         &format_args!("Processing dictionary `{}`", input.name()),
         None,
+        45,
     );
 
     let mut count = 0;
 
-    match input {
-        Input::Jmdict(input) => {
-            let mut jmdict = jmdict::Parser::new(input);
+    if let Some(mut source) = Phrases::new(input, yomichan.as_ref()) {
+        let mut references = References::default();
 
-            while let Some(entry) = jmdict.parse()? {
-                ensure!(!shutdown.is_set(), "Task shut down");
+        let ranks = match input {
+            Input::Jmdict(_, Some(frequencies)) => frequency::Ranks::parse(frequencies),
+            _ => frequency::Ranks::default(),
+        };
 
-                if count % 1000 == 0 {
-                    reporter.instrument_progress(1000);
+        if !ranks.is_empty() {
+            report_info!(reporter, "Ranking phrases by frequency");
+        }
+
+        if ranks.skipped() > 0 {
+            report_warn!(
+                reporter,
+                "Skipped {} line(s) of word frequencies which could not be parsed",
+                ranks.skipped()
+            );
+        }
+
+        while let Some(entry) = source.next()? {
+            ensure!(!shutdown.is_set(), "Task shut down");
+
+            if count % 1000 == 0 {
+                reporter.instrument_progress(1000);
+            }
+
+            count += 1;
+
+            // References are collected from every phrase so that links to
+            // phrases in other shards are resolved, but only the links from
+            // phrases in the shard are kept.
+            references.insert(&entry);
+
+            if !in_shard(entry.reading_elements.first().map(|el| el.text)) {
+                continue;
+            }
+
+            output.clear();
+            ENCODING.to_writer(&mut output, &entry)?;
+
+            let entry_ref = buf.store_slice(&output).offset() as u32;
+            phrases.push(entry_ref);
+
+            if let Some(rank) = ranks.rank(&entry) {
+                phrase_frequencies.push((entry.sequence as u32, rank));
+            }
+
+            if let Some(c) = priority::commonness(entry.priorities()).level() {
+                commonness.push((entry_ref, c));
+            }
+
+            for sense in &entry.senses {
+                for gloss in &sense.gloss {
+                    languages.insert(gloss.lang.unwrap_or(jmdict::elements::DEFAULT_LANGUAGE));
                 }
+            }
 
-                count += 1;
+            by_sequence.insert(
+                entry.sequence as u32,
+                stored::PhrasePos {
+                    offset: entry_ref,
+                    reading: PhraseIndex::Entry,
+                },
+            );
 
-                output.clear();
-                ENCODING.to_writer(&mut output, &entry)?;
+            for key in phrase_form_keys(&entry) {
+                let sequences = input_phrases_by_form.entry(key).or_default();
 
-                let entry_ref = buf.store_slice(&output).offset() as u32;
-                phrases.push(entry_ref);
+                if !sequences.contains(&(entry.sequence as u32)) {
+                    sequences.push(entry.sequence as u32);
+                }
+            }
 
-                by_sequence.insert(
-                    entry.sequence as u32,
-                    stored::PhrasePos {
+            for sense in &entry.senses {
+                for pos in &sense.pos {
+                    by_pos.entry(pos).or_default().insert(stored::PhrasePos {
                         offset: entry_ref,
-                        reading: PhraseIndex::Entry,
-                    },
-                );
+                        reading: PhraseIndex::Meaning,
+                    });
+                }
 
-                for sense in &entry.senses {
-                    for pos in &sense.pos {
-                        by_pos.entry(pos).or_default().insert(stored::PhrasePos {
-                            offset: entry_ref,
-                            reading: PhraseIndex::Meaning,
-                        });
+                let id = stored::Id::phrase(entry_ref, PhraseIndex::Meaning);
+                let exact = stored::Id::phrase(entry_ref, PhraseIndex::MeaningExact);
+
+                for g in &sense.gloss {
+                    if g.ty == Some("expl") {
+                        continue;
                     }
 
-                    let id = stored::Id::phrase(entry_ref, PhraseIndex::Meaning);
+                    populate_analyzed(g.text, &mut lookup, id);
 
-                    for g in &sense.gloss {
-                        if g.ty == Some("expl") {
-                            continue;
-                        }
+                    // Whole meanings are indexed on their own so that
+                    // they can be ranked above meanings which only start
+                    // with what's being searched for.
+                    let text = g.text.trim();
 
-                        populate_analyzed(g.text, &mut lookup, id);
+                    if !text.is_empty() && text.chars().count() <= MAX_MEANING_PHRASE {
+                        lookup.push((Cow::Owned(text.to_lowercase()), exact));
                     }
                 }
+            }
+
+            for el in &entry.reading_elements {
+                lookup.push((
+                    Cow::Borrowed(el.text),
+                    stored::Id::phrase(entry_ref, PhraseIndex::Hiragana),
+                ));
 
-                for el in &entry.reading_elements {
+                let a = stored::Id::phrase(entry_ref, PhraseIndex::Romanized);
+                let b = stored::Id::phrase(entry_ref, PhraseIndex::Katakana);
+                other_readings(&mut lookup, el.text, a, b, |s| s.katakana());
+            }
+
+            for el in &entry.kanji_elements {
+                if let Some(s) = full_to_half_string(el.text) {
                     lookup.push((
-                        Cow::Borrowed(el.text),
-                        stored::Id::phrase(entry_ref, PhraseIndex::Hiragana),
+                        Cow::Owned(s),
+                        stored::Id::phrase(entry_ref, PhraseIndex::KanjiHalf),
                     ));
-
-                    let a = stored::Id::phrase(entry_ref, PhraseIndex::Romanized);
-                    let b = stored::Id::phrase(entry_ref, PhraseIndex::Katakana);
-                    other_readings(&mut lookup, el.text, a, b, |s| s.katakana());
                 }
 
-                for el in &entry.kanji_elements {
-                    if let Some(s) = full_to_half_string(el.text) {
-                        lookup.push((
-                            Cow::Owned(s),
-                            stored::Id::phrase(entry_ref, PhraseIndex::KanjiHalf),
-                        ));
-                    }
+                lookup.push((
+                    Cow::Borrowed(el.text),
+                    stored::Id::phrase(entry_ref, PhraseIndex::Kanji),
+                ));
 
+                if let Cow::Owned(key) = variants::normalize(el.text) {
                     lookup.push((
-                        Cow::Borrowed(el.text),
-                        stored::Id::phrase(entry_ref, PhraseIndex::Kanji),
+                        Cow::Owned(key),
+                        stored::Id::phrase(entry_ref, PhraseIndex::Variant),
                     ));
                 }
 
-                for (reading, c, _) in inflection::conjugate(&entry) {
-                    for (inflection, pair) in c.iter() {
-                        let data = InflectionData {
-                            reading,
-                            inflection: *inflection,
-                        };
+                // Spellings which only differ in okurigana, like 引っ越す
+                // and 引越す, converge on the same key.
+                if entry.kanji_elements.len() > 1 {
+                    if let Some(key) = okurigana_key(el.text) {
+                        lookup.push((
+                            Cow::Owned(key),
+                            stored::Id::phrase(entry_ref, PhraseIndex::Okurigana),
+                        ));
+                    }
+                }
+            }
 
-                        let index = match inflections_index.entry(data) {
-                            hash_map::Entry::Vacant(e) => {
-                                let index = *e.insert(inflections.len() as u32);
-                                inflections.push(data);
-                                index
-                            }
-                            hash_map::Entry::Occupied(e) => *e.get(),
-                        };
+            let literals = entry
+                .kanji_elements
+                .iter()
+                .flat_map(|el| el.text.chars())
+                .filter(|&c| is_kanji_literal(c))
+                .collect::<BTreeSet<_>>();
+
+            for c in literals {
+                words_by_kanji
+                    .entry(c as u32)
+                    .or_default()
+                    .push(entry.sequence as u32);
+            }
 
-                        assert!(index < u16::MAX as u32);
-                        let id = stored::Id::inflection(entry_ref, index as u16);
+            for (reading, c, _) in inflection::conjugate(&entry) {
+                for (inflection, pair) in c.iter() {
+                    let data = InflectionData {
+                        reading,
+                        inflection: *inflection,
+                    };
 
-                        if pair.text() != pair.reading() {
-                            let key = Cow::Owned(format!("{}{}", pair.text(), pair.suffix()));
-                            lookup.push((key, id));
+                    let index = match inflections_index.entry(data) {
+                        hash_map::Entry::Vacant(e) => {
+                            let index = *e.insert(inflections.len() as u32);
+                            inflections.push(data);
+                            index
                         }
+                        hash_map::Entry::Occupied(e) => *e.get(),
+                    };
+
+                    assert!(index < u16::MAX as u32);
+                    let id = stored::Id::inflection(entry_ref, index as u16);
 
-                        let key: Cow<'_, str> =
-                            Cow::Owned(format!("{}{}", pair.reading(), pair.suffix()));
-                        other_readings(&mut lookup, key.as_ref(), id, id, |text| text.katakana());
+                    if pair.text() != pair.reading() {
+                        let key = Cow::Owned(format!("{}{}", pair.text(), pair.suffix()));
                         lookup.push((key, id));
                     }
+
+                    let key: Cow<'_, str> =
+                        Cow::Owned(format!("{}{}", pair.reading(), pair.suffix()));
+                    other_readings(&mut lookup, key.as_ref(), id, id, |text| text.katakana());
+                    lookup.push((key, id));
                 }
             }
         }
-        Input::Kanjidic2(input) => {
-            let mut kanjidic2 = kanjidic2::Parser::new(input);
 
-            while let Some(c) = kanjidic2.parse()? {
-                ensure!(!shutdown.is_set(), "Task shut down");
+        links = references.resolve();
+        links.retain(|sequence, _| by_sequence.contains_key(sequence));
+    }
 
-                if count % 1000 == 0 {
-                    reporter.instrument_progress(1000);
-                }
+    if let Some(mut source) = Characters::new(input, yomichan.as_ref()) {
+        while let Some(c) = source.next()? {
+            ensure!(!shutdown.is_set(), "Task shut down");
 
-                count += 1;
+            if count % 1000 == 0 {
+                reporter.instrument_progress(1000);
+            }
 
-                output.clear();
-                ENCODING.to_writer(&mut output, &c)?;
+            count += 1;
 
-                let kanji_ref = buf.store_slice(&output).offset() as u32;
-                kanji.push(kanji_ref);
+            output.clear();
+            ENCODING.to_writer(&mut output, &c)?;
 
-                kanji_literals.insert(c.literal, kanji_ref);
+            let kanji_ref = buf.store_slice(&output).offset() as u32;
+            kanji.push(kanji_ref);
 
-                lookup.push((
-                    Cow::Borrowed(c.literal),
-                    stored::Id::kanji(kanji_ref, KanjiIndex::Literal),
-                ));
+            kanji_literals.insert(c.literal, kanji_ref);
 
-                for reading in &c.readings {
-                    match reading.ty {
-                        "ja_kun" => {
-                            if let Some((prefix, _)) = reading.text.split_once('.') {
-                                let a = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiRomanize);
-                                let b = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiKatakana);
-                                other_readings(&mut lookup, prefix, a, b, |s| s.katakana());
-                                let id = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiHiragana);
-                                lookup.push((Cow::Borrowed(prefix), id));
-                            }
+            // Only the first stroke count is correct, the rest are common
+            // miscounts.
+            if let Some(&strokes) = c.misc.stroke_counts.first() {
+                input_kanji_by_strokes
+                    .entry(strokes)
+                    .or_default()
+                    .push(c.literal);
+            }
 
-                            let a = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullRomanized);
-                            let b = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullKatakana);
-                            other_readings(&mut lookup, reading.text, a, b, |s| s.katakana());
+            if let Some(grade) = c.misc.grade {
+                input_kanji_by_grade
+                    .entry(grade)
+                    .or_default()
+                    .push(c.literal);
+            }
 
-                            let id = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullHiragana);
-                            lookup.push((Cow::Borrowed(reading.text), id));
-                        }
-                        "ja_on" => {
-                            let a = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiRomanized);
-                            let b = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiHiragana);
-                            other_readings(&mut lookup, reading.text, a, b, |s| s.hiragana());
-                            let id = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiKatakana);
-                            lookup.push((Cow::Borrowed(reading.text), id));
+            lookup.push((
+                Cow::Borrowed(c.literal),
+                stored::Id::kanji(kanji_ref, KanjiIndex::Literal),
+            ));
+
+            for reading in &c.readings {
+                match reading.ty {
+                    "ja_kun" => {
+                        if let Some((prefix, _)) = reading.text.split_once('.') {
+                            let a = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiRomanize);
+                            let b = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiKatakana);
+                            other_readings(&mut lookup, prefix, a, b, |s| s.katakana());
+                            let id = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiHiragana);
+                            lookup.push((Cow::Borrowed(prefix), id));
                         }
-                        _ => {
-                            let id = stored::Id::kanji(kanji_ref, KanjiIndex::Other);
-                            lookup.push((Cow::Borrowed(reading.text), id));
-                        }
-                    };
-                }
 
-                for meaning in &c.meanings {
-                    let id = stored::Id::kanji(kanji_ref, KanjiIndex::Meaning);
-                    populate_analyzed(meaning.text, &mut lookup, id);
-                }
+                        let a = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullRomanized);
+                        let b = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullKatakana);
+                        other_readings(&mut lookup, reading.text, a, b, |s| s.katakana());
+
+                        let id = stored::Id::kanji(kanji_ref, KanjiIndex::KunyomiFullHiragana);
+                        lookup.push((Cow::Borrowed(reading.text), id));
+                    }
+                    "ja_on" => {
+                        let a = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiRomanized);
+                        let b = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiHiragana);
+                        other_readings(&mut lookup, reading.text, a, b, |s| s.hiragana());
+                        let id = stored::Id::kanji(kanji_ref, KanjiIndex::OnyomiKatakana);
+                        lookup.push((Cow::Borrowed(reading.text), id));
+                    }
+                    _ => {
+                        let id = stored::Id::kanji(kanji_ref, KanjiIndex::Other);
+                        lookup.push((Cow::Borrowed(reading.text), id));
+                    }
+                };
+            }
+
+            for meaning in &c.meanings {
+                let id = stored::Id::kanji(kanji_ref, KanjiIndex::Meaning);
+                populate_analyzed(meaning.text, &mut lookup, id);
             }
         }
-        Input::Jmnedict(input) => {
+    }
+
+    match input {
+        Input::Jmnedict(input, frequencies) => {
             let mut jmnedict = jmnedict::Parser::new(input);
 
+            let frequencies = frequencies
+                .map(jmnedict::Frequencies::parse)
+                .unwrap_or_default();
+
+            if !frequencies.is_empty() {
+                report_info!(reporter, "Ranking names by frequency");
+            }
+
             while let Some(entry) = jmnedict.next()? {
                 ensure!(!shutdown.is_set(), "Task shut down");
 
@@ -522,16 +1154,56 @@ pub fn build(
 
                 count += 1;
 
+                if !in_shard(entry.reading.first().map(|reading| reading.text)) {
+                    continue;
+                }
+
                 output.clear();
                 ENCODING.to_writer(&mut output, &entry)?;
 
                 let name_ref = buf.store_slice(&output).offset() as u32;
+                names.push(name_ref);
+
+                let frequency = entry
+                    .kanji
+                    .iter()
+                    .copied()
+                    .chain(entry.reading.iter().map(|reading| reading.text))
+                    .filter_map(|name| frequencies.get(name))
+                    .max();
+
+                if let Some(frequency) = frequency {
+                    name_frequencies.push((name_ref, frequency));
+                }
+
+                for key in name_form_keys(&entry) {
+                    let sequences = input_names_by_form.entry(key).or_default();
+
+                    if !sequences.contains(&(entry.sequence as u32)) {
+                        sequences.push(entry.sequence as u32);
+                    }
+                }
+
+                for translation in &entry.translations {
+                    languages.insert(
+                        translation
+                            .lang
+                            .unwrap_or(jmdict::elements::DEFAULT_LANGUAGE),
+                    );
+                }
 
                 for kanji in entry.kanji.iter().copied() {
                     lookup.push((
                         Cow::Borrowed(kanji),
                         stored::Id::name(name_ref, NameIndex::Literal),
                     ));
+
+                    if let Cow::Owned(key) = variants::normalize(kanji) {
+                        lookup.push((
+                            Cow::Owned(key),
+                            stored::Id::name(name_ref, NameIndex::Variant),
+                        ));
+                    }
                 }
 
                 for reading in entry.reading {
@@ -543,10 +1215,16 @@ pub fn build(
                     let b = stored::Id::name(name_ref, NameIndex::Katakana);
                     other_readings(&mut lookup, reading.text, a, b, |s| s.katakana());
                 }
+
+                for translation in entry.translations {
+                    let id = stored::Id::name(name_ref, NameIndex::Translation);
+                    populate_analyzed(translation.text, &mut lookup, id);
+                }
             }
         }
         Input::Kradfile(data) => {
             let mut parser = kradfile::Parser::new(data);
+            report_info!(reporter, "Detected encoding {}", parser.encoding().name());
 
             while let Some(entry) = parser.parse() {
                 ensure!(!shutdown.is_set(), "Task shut down");
@@ -570,26 +1248,165 @@ pub fn build(
                         .push(radicals_ref);
                 }
             }
+
+            if parser.skipped() > 0 {
+                report_warn!(
+                    reporter,
+                    "Skipped {} line(s) which could not be decoded as {}",
+                    parser.skipped(),
+                    parser.encoding().name()
+                );
+            }
         }
-    }
+        Input::Radkfile(data) => {
+            let mut parser = radkfile::Parser::new(data);
+            report_info!(reporter, "Detected encoding {}", parser.encoding().name());
 
-    let phrases = buf.store_slice(&phrases);
-    let kanji = buf.store_slice(&kanji);
+            while let Some(entry) = parser.parse() {
+                ensure!(!shutdown.is_set(), "Task shut down");
 
-    reporter.instrument_end(count);
+                if count % 100 == 0 {
+                    reporter.instrument_progress(100);
+                }
+
+                count += 1;
+
+                output.clear();
+                ENCODING.to_writer(&mut output, &entry)?;
+
+                let radical_ref = buf.store_slice(&output).offset() as u32;
+                radkfile.push(radical_ref);
+                input_by_radical.insert(entry.radical, radical_ref);
+            }
+
+            if parser.skipped() > 0 {
+                report_warn!(
+                    reporter,
+                    "Skipped {} line(s) which could not be decoded as {}",
+                    parser.skipped(),
+                    parser.encoding().name()
+                );
+            }
+        }
+        Input::PitchAccent(input) => {
+            let mut parser = pitch::Parser::new(input);
+
+            while let Some(entry) = parser.parse() {
+                ensure!(!shutdown.is_set(), "Task shut down");
+
+                if count % 1000 == 0 {
+                    reporter.instrument_progress(1000);
+                }
+
+                count += 1;
+
+                let accents = input_accents
+                    .entry(form_key(entry.text, entry.reading))
+                    .or_default();
+
+                for accent in entry.accents {
+                    if !accents.contains(&accent) {
+                        accents.push(accent);
+                    }
+                }
+            }
+
+            if parser.skipped() > 0 {
+                report_warn!(
+                    reporter,
+                    "Skipped {} line(s) which could not be parsed",
+                    parser.skipped()
+                );
+            }
+        }
+        Input::Tatoeba(input) => {
+            let mut parser = tatoeba::Parser::new(input);
+
+            while let Some(sentence) = parser.parse() {
+                ensure!(!shutdown.is_set(), "Task shut down");
 
+                if count % 1000 == 0 {
+                    reporter.instrument_progress(1000);
+                }
+
+                count += 1;
+
+                output.clear();
+                ENCODING.to_writer(&mut output, &sentence)?;
+
+                let sentence_ref = buf.store_slice(&output).offset() as u32;
+                sentences.push(sentence_ref);
+
+                for &word in &sentence.words {
+                    input_sentences_by_word
+                        .entry(word)
+                        .or_default()
+                        .push(sentence_ref);
+                }
+
+                let chars = sentence
+                    .japanese
+                    .chars()
+                    .filter(|&c| kana::is_japanese(c))
+                    .collect::<BTreeSet<_>>();
+
+                for c in chars {
+                    sentences_by_char
+                        .entry(c as u32)
+                        .or_default()
+                        .push(sentence_ref);
+                }
+            }
+
+            if parser.skipped() > 0 {
+                report_warn!(
+                    reporter,
+                    "Skipped {} line(s) which could not be parsed",
+                    parser.skipped()
+                );
+            }
+        }
+        Input::Jmdict(..) | Input::Kanjidic2(..) | Input::Yomichan(..) => {}
+    }
+
+    let phrases = buf.store_slice(&phrases);
+    let kanji = buf.store_slice(&kanji);
+    let names = buf.store_slice(&names);
+    let radkfile = buf.store_slice(&radkfile);
+    let sentences = buf.store_slice(&sentences);
+
+    reporter.instrument_end(count);
+
+    // Queries have their long vowel marks expanded, so keys are indexed with
+    // them expanded as well.
+    let expanded = lookup
+        .iter()
+        .filter_map(|(key, id)| match romaji::expand_long_vowels(key) {
+            Cow::Owned(key) => Some((Cow::Owned(key), *id)),
+            Cow::Borrowed(..) => None,
+        })
+        .collect::<Vec<_>>();
+
+    lookup.extend(expanded);
     lookup.sort_by(|(a, _), (b, _)| b.as_ref().cmp(a.as_ref()));
     tracing::info!("Inserting {} readings", lookup.len());
 
     let mut readings2 = Vec::with_capacity(lookup.len());
     let by_kanji_literal;
+    let kanji_by_strokes;
+    let kanji_by_grade;
     let radicals;
     let radicals_to_kanji;
+    let by_radical;
+    let accents;
+    let phrases_by_form;
+    let names_by_form;
+    let sentences_by_word;
 
     {
         let mut indexer = StringIndexer::new();
 
-        reporter.instrument_start(module_path!(), &"Inserting strings", Some(lookup.len()));
+        reporter.instrument_start(module_path!(), &"Inserting strings", Some(lookup.len()), 10);
 
         for (index, (key, id)) in lookup.iter().enumerate() {
             ensure!(!shutdown.is_set(), "Task shut down");
@@ -605,33 +1422,120 @@ pub fn build(
         reporter.instrument_end(lookup.len());
 
         by_kanji_literal = {
-            let mut output = HashMap::new();
+            let mut output = Vec::new();
 
             for (key, value) in kanji_literals {
                 let s = indexer.store(&mut buf, key.as_ref())?;
-                output.insert(s, value);
+                output.push((s, value));
+            }
+
+            output
+        };
+
+        kanji_by_strokes = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_kanji_by_strokes {
+                let mut literals = Vec::with_capacity(values.len());
+
+                for value in values {
+                    literals.push(indexer.store(&mut buf, value)?);
+                }
+
+                output.push((*key, literals));
+            }
+
+            output
+        };
+
+        kanji_by_grade = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_kanji_by_grade {
+                let mut literals = Vec::with_capacity(values.len());
+
+                for value in values {
+                    literals.push(indexer.store(&mut buf, value)?);
+                }
+
+                output.push((*key, literals));
             }
 
             output
         };
 
         radicals = {
-            let mut output = HashMap::new();
+            let mut output = Vec::new();
 
             for (key, value) in &input_radicals {
                 let s = indexer.store(&mut buf, key)?;
-                output.insert(s, *value);
+                output.push((s, *value));
             }
 
             output
         };
 
         radicals_to_kanji = {
-            let mut output = HashMap::new();
+            let mut output = Vec::new();
 
             for (key, values) in &input_radicals_to_kanji {
                 let s = indexer.store(&mut buf, key)?;
-                output.insert(s, values);
+                output.push((s, values));
+            }
+
+            output
+        };
+
+        by_radical = {
+            let mut output = Vec::new();
+
+            for (key, value) in &input_by_radical {
+                let s = indexer.store(&mut buf, key)?;
+                output.push((s, *value));
+            }
+
+            output
+        };
+
+        accents = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_accents {
+                let s = indexer.store(&mut buf, key)?;
+                output.push((s, values));
+            }
+
+            output
+        };
+
+        phrases_by_form = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_phrases_by_form {
+                let s = indexer.store(&mut buf, key)?;
+                output.push((s, values));
+            }
+
+            output
+        };
+
+        names_by_form = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_names_by_form {
+                let s = indexer.store(&mut buf, key)?;
+                output.push((s, values));
+            }
+
+            output
+        };
+
+        sentences_by_word = {
+            let mut output = Vec::new();
+
+            for (key, values) in &input_sentences_by_word {
+                let s = indexer.store(&mut buf, key)?;
+                output.push((s, values));
             }
 
             output
@@ -648,13 +1552,13 @@ pub fn build(
 
     let step_len = readings2.len();
 
-    reporter.instrument_start(module_path!(), &"Building lookup table", Some(step_len));
+    reporter.instrument_start(module_path!(), &"Building lookup table", Some(step_len), 25);
 
     let mut lookup = trie::Builder::with_flavor();
 
     for (index, (key, id)) in readings2.into_iter().rev().enumerate() {
-        if index % 100000 == 0 {
-            reporter.instrument_progress(100000);
+        if index % 10_000 == 0 {
+            reporter.instrument_progress(10_000);
         }
 
         ensure!(!shutdown.is_set(), "Task shut down");
@@ -663,7 +1567,7 @@ pub fn build(
 
     reporter.instrument_end(step_len);
 
-    reporter.instrument_start(module_path!(), &"Saving index", None);
+    reporter.instrument_start(module_path!(), &"Saving index", None, 5);
 
     let lookup = lookup.build(&mut buf)?;
 
@@ -673,13 +1577,7 @@ pub fn build(
         for (key, set) in by_pos.into_iter() {
             ensure!(!shutdown.is_set(), "Task shut down");
 
-            let mut values = Vec::new();
-
-            for v in set {
-                values.push(v);
-            }
-
-            values.sort();
+            let values = set.into_iter().collect::<Vec<_>>();
             let set = buf.store_slice(&values);
             entries.push((key, set));
         }
@@ -693,6 +1591,32 @@ pub fn build(
         swiss::store_map(&mut buf, by_kanji_literal)?
     };
 
+    let kanji_by_strokes = {
+        tracing::info!("Storing kanji_by_strokes: {}...", kanji_by_strokes.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in kanji_by_strokes {
+            let values = buf.store_slice(&values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
+    let kanji_by_grade = {
+        tracing::info!("Storing kanji_by_grade: {}...", kanji_by_grade.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in kanji_by_grade {
+            let values = buf.store_slice(&values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
     let radicals = {
         tracing::info!("Storing radicals: {}...", radicals.len());
         swiss::store_map(&mut buf, radicals)?
@@ -711,24 +1635,165 @@ pub fn build(
         swiss::store_map(&mut buf, intermediate)?
     };
 
+    let by_radical = {
+        tracing::info!("Storing by_radical: {}...", by_radical.len());
+        swiss::store_map(&mut buf, by_radical)?
+    };
+
     let by_sequence = {
         tracing::info!("Storing by_sequence: {}...", by_sequence.len());
         swiss::store_map(&mut buf, by_sequence)?
     };
 
+    let links = {
+        let mut entries = Vec::new();
+
+        for (sequence, links) in links {
+            entries.push((sequence, buf.store_slice(&links)));
+        }
+
+        tracing::info!("Storing links: {}...", entries.len());
+        swiss::store_map(&mut buf, entries)?
+    };
+
+    let words_by_kanji = {
+        let mut entries = Vec::new();
+
+        for (literal, sequences) in words_by_kanji {
+            entries.push((literal, buf.store_slice(&sequences)));
+        }
+
+        tracing::info!("Storing words_by_kanji: {}...", entries.len());
+        swiss::store_map(&mut buf, entries)?
+    };
+
+    let languages = {
+        if !languages.is_empty() {
+            let languages = languages.iter().copied().collect::<Vec<_>>();
+            report_info!(reporter, "Gloss languages: {}", languages.join(", "));
+        }
+
+        let mut refs = Vec::new();
+
+        for language in languages {
+            refs.push(buf.store_unsized(language));
+        }
+
+        buf.store_slice(&refs)
+    };
+
+    let name_frequencies = {
+        tracing::info!("Storing name_frequencies: {}...", name_frequencies.len());
+        swiss::store_map(&mut buf, name_frequencies)?
+    };
+
+    let commonness = {
+        tracing::info!("Storing commonness: {}...", commonness.len());
+        swiss::store_map(&mut buf, commonness)?
+    };
+
+    let accents = {
+        tracing::info!("Storing accents: {}...", accents.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in accents {
+            let values = buf.store_slice(values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
+    let phrases_by_form = {
+        tracing::info!("Storing phrases_by_form: {}...", phrases_by_form.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in phrases_by_form {
+            let values = buf.store_slice(values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
+    let names_by_form = {
+        tracing::info!("Storing names_by_form: {}...", names_by_form.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in names_by_form {
+            let values = buf.store_slice(values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
+    let phrase_frequencies = {
+        tracing::info!(
+            "Storing phrase_frequencies: {}...",
+            phrase_frequencies.len()
+        );
+        swiss::store_map(&mut buf, phrase_frequencies)?
+    };
+
+    let sentences_by_word = {
+        tracing::info!("Storing sentences_by_word: {}...", sentences_by_word.len());
+
+        let mut intermediate = Vec::new();
+
+        for (key, values) in sentences_by_word {
+            let values = buf.store_slice(values);
+            intermediate.push((key, values));
+        }
+
+        swiss::store_map(&mut buf, intermediate)?
+    };
+
+    let sentences_by_char = {
+        let mut entries = Vec::new();
+
+        for (c, values) in sentences_by_char {
+            entries.push((c, buf.store_slice(&values)));
+        }
+
+        tracing::info!("Storing sentences_by_char: {}...", entries.len());
+        swiss::store_map(&mut buf, entries)?
+    };
+
     let inflections = buf.store_slice(&inflections);
 
     buf.load_uninit_mut(index).write(&stored::IndexHeader {
         name,
+        shard: stored_shard,
+        languages,
         lookup,
         by_pos,
         by_kanji_literal,
+        kanji_by_strokes,
+        kanji_by_grade,
         radicals,
         radicals_to_kanji,
+        by_radical,
         by_sequence,
+        links,
+        words_by_kanji,
+        name_frequencies,
+        commonness,
+        accents,
+        phrases_by_form,
+        names_by_form,
+        phrase_frequencies,
+        sentences_by_word,
+        sentences_by_char,
         inflections,
         phrases,
         kanji,
+        names,
+        radkfile,
+        sentences,
     });
 
     buf.load_uninit_mut(header).write(&stored::GlobalHeader {
@@ -741,51 +1806,252 @@ pub fn build(
     Ok(buf)
 }
 
-fn populate_analyzed<'a>(
+/// Test if a character is a kanji which can be looked up in the
+/// kanji-to-words index.
+fn is_kanji_literal(c: char) -> bool {
+    kana::is_japanese(c) && kana::is_kanji(c) && !matches!(c, 'ー' | '々')
+}
+
+/// A reference from a sense to another phrase.
+struct Reference<'a> {
+    sequence: u32,
+    sense: u16,
+    index: u16,
+    kind: LinkKind,
     text: &'a str,
-    lookup: &mut Vec<(Cow<'a, str>, stored::Id)>,
-    id: stored::Id,
-) {
-    fn is_common(phrase: &str) -> bool {
-        match phrase {
-            "to" | "a" | "in" | "of" | "for" | "so" | "if" | "by" | "but" | "not" | "any"
-            | "way" | "into" => true,
-            string if string.starts_with("e.g.") => {
-                if let Some(rest) = string.strip_prefix("e.g. ") {
-                    is_common(rest)
-                } else {
-                    string == "e.g."
+}
+
+/// Cross-references and antonyms collected while building, which are resolved
+/// to sequences once every phrase has been seen.
+#[derive(Default)]
+struct References<'a> {
+    references: Vec<Reference<'a>>,
+    /// Sequences of phrases by their kanji and readings.
+    elements: HashMap<&'a str, Vec<u32>>,
+    /// Readings of every phrase.
+    readings: HashSet<(u32, &'a str)>,
+    /// Glosses of every sense of every phrase.
+    glosses: HashMap<u32, Vec<Vec<&'a str>>>,
+}
+
+impl<'a> References<'a> {
+    fn insert(&mut self, entry: &jmdict::Entry<'a>) {
+        let sequence = entry.sequence as u32;
+
+        for el in &entry.kanji_elements {
+            self.elements.entry(el.text).or_default().push(sequence);
+        }
+
+        for el in &entry.reading_elements {
+            self.elements.entry(el.text).or_default().push(sequence);
+            self.readings.insert((sequence, el.text));
+        }
+
+        let mut glosses = Vec::with_capacity(entry.senses.len());
+
+        for (sense, s) in entry.senses.iter().enumerate() {
+            let texts = [
+                (LinkKind::SeeAlso, &s.xref),
+                (LinkKind::Antonym, &s.antonym),
+            ];
+
+            for (kind, texts) in texts {
+                for (index, &text) in texts.iter().enumerate() {
+                    self.references.push(Reference {
+                        sequence,
+                        sense: sense as u16,
+                        index: index as u16,
+                        kind,
+                        text,
+                    });
                 }
             }
-            _ => false,
+
+            glosses.push(s.gloss.iter().map(|g| g.text).collect());
         }
+
+        self.glosses.insert(sequence, glosses);
     }
 
-    for phrase in analyze_glossary::analyze(text) {
-        if phrase.chars().count() > 24 {
-            continue;
-        }
+    /// Resolve references into links by the sequence they are made from.
+    ///
+    /// Cross-references to a phrase which shares a gloss with the referring
+    /// sense are considered synonyms.
+    fn resolve(self) -> BTreeMap<u32, Vec<stored::Link>> {
+        let mut output = BTreeMap::<_, Vec<_>>::new();
 
-        // Skip overly common prefixes which would mostly just be
-        // unhelpful to index.
-        if is_common(phrase) {
-            continue;
-        }
+        for r in &self.references {
+            let (text, reading, target_sense) = parse_xref(r.text);
 
-        let lowercase = phrase.to_lowercase();
+            let Some(candidates) = self.elements.get(text) else {
+                continue;
+            };
 
-        let key = if phrase == lowercase {
-            Cow::Borrowed(phrase)
-        } else {
-            Cow::Owned(lowercase)
-        };
+            let found = candidates.iter().copied().find(|&sequence| {
+                if sequence == r.sequence {
+                    return false;
+                }
 
-        lookup.push((key, id));
-    }
-}
+                match reading {
+                    Some(reading) => self.readings.contains(&(sequence, reading)),
+                    None => true,
+                }
+            });
 
-fn full_to_half_char(c: char) -> Option<char> {
-    let c = match c {
+            let Some(sequence) = found else {
+                continue;
+            };
+
+            let kind = match r.kind {
+                LinkKind::SeeAlso if self.shares_gloss(r, sequence, target_sense) => {
+                    LinkKind::Synonym
+                }
+                kind => kind,
+            };
+
+            output.entry(r.sequence).or_default().push(stored::Link {
+                sequence,
+                sense: r.sense,
+                index: r.index,
+                target_sense: target_sense.unwrap_or_default(),
+                kind,
+            });
+        }
+
+        output
+    }
+
+    fn shares_gloss(&self, r: &Reference<'_>, sequence: u32, target_sense: Option<u16>) -> bool {
+        let Some(source) = self
+            .glosses
+            .get(&r.sequence)
+            .and_then(|senses| senses.get(usize::from(r.sense)))
+        else {
+            return false;
+        };
+
+        let Some(senses) = self.glosses.get(&sequence) else {
+            return false;
+        };
+
+        let senses = match target_sense {
+            Some(n) => senses.get(usize::from(n).wrapping_sub(1)..usize::from(n)),
+            None => Some(&senses[..]),
+        };
+
+        senses
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|gloss| source.contains(gloss))
+    }
+}
+
+/// Parse a cross-reference like `日本・にほん・1` into the kanji or reading
+/// being referenced, an optional reading, and an optional sense counting from
+/// 1.
+fn parse_xref(text: &str) -> (&str, Option<&str>, Option<u16>) {
+    let mut parts = text.split('・');
+    let first = parts.next().unwrap_or(text);
+
+    let mut reading = None;
+    let mut sense = None;
+
+    for part in parts {
+        match part.parse() {
+            Ok(n) => sense = Some(n),
+            Err(..) => reading = Some(part),
+        }
+    }
+
+    (first, reading, sense)
+}
+
+/// Hash a value with a seed, used to shuffle entries in an order which
+/// depends on the seed.
+fn seeded_hash(seed: u64, value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Test if the input matches the given pattern, where `*` matches any sequence
+/// of characters.
+fn glob(pattern: &str, input: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    let Some(first) = parts.next() else {
+        return input.is_empty();
+    };
+
+    let Some(mut rest) = input.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts = parts.peekable();
+
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+
+        let Some(n) = rest.find(part) else {
+            return false;
+        };
+
+        rest = &rest[n + part.len()..];
+    }
+
+    rest.is_empty()
+}
+
+/// Test if the phrase is overly common.
+fn is_common(phrase: &str) -> bool {
+    match phrase {
+        "to" | "a" | "in" | "of" | "for" | "so" | "if" | "by" | "but" | "not" | "any" | "way"
+        | "into" => true,
+        string if string.starts_with("e.g.") => {
+            if let Some(rest) = string.strip_prefix("e.g. ") {
+                is_common(rest)
+            } else {
+                string == "e.g."
+            }
+        }
+        _ => false,
+    }
+}
+
+fn populate_analyzed<'a>(
+    text: &'a str,
+    lookup: &mut Vec<(Cow<'a, str>, stored::Id)>,
+    id: stored::Id,
+) {
+    for phrase in analyze_glossary::analyze(text) {
+        if phrase.chars().count() > MAX_MEANING_PHRASE {
+            continue;
+        }
+
+        // Skip overly common prefixes which would mostly just be
+        // unhelpful to index.
+        if is_common(phrase) {
+            continue;
+        }
+
+        let lowercase = phrase.to_lowercase();
+
+        let key = if phrase == lowercase {
+            Cow::Borrowed(phrase)
+        } else {
+            Cow::Owned(lowercase)
+        };
+
+        lookup.push((key, id));
+    }
+}
+
+fn full_to_half_char(c: char) -> Option<char> {
+    let c = match c {
         '\u{FF01}' => '!',
         '\u{FF02}' => '"',
         '\u{FF03}' => '#',
@@ -864,22 +2130,96 @@ fn full_to_half_string(input: &str) -> Option<String> {
     Some(output)
 }
 
+/// Construct a key for the given kanji spelling with its okurigana removed,
+/// so that 引っ越す, 引越す and 引越 all produce 引越.
+///
+/// Kana leading up to the first kanji is kept, and the key has to contain at
+/// least two kanji since it would otherwise match too many unrelated words.
+fn okurigana_key(text: &str) -> Option<String> {
+    let mut output = String::new();
+    let mut kanji = 0;
+
+    for c in text.chars() {
+        if kana::is_hiragana(c) {
+            if kanji > 0 {
+                continue;
+            }
+        } else if kana::is_kanji(c) {
+            kanji += 1;
+        }
+
+        output.push(c);
+    }
+
+    if kanji < 2 || output.len() == text.len() {
+        return None;
+    }
+
+    Some(output)
+}
+
+/// The key pitch accents and links between phrases and names are stored
+/// under, which is the form of a word and its reading separated by a tab.
+fn form_key(text: &str, reading: &str) -> String {
+    format!("{text}\t{reading}")
+}
+
+/// The keys a phrase is linked to names by, which are every form of it
+/// together with the readings which apply to it. Readings are only included
+/// on their own if the phrase is usually written in kana, since otherwise a
+/// name written in kana would be linked to every phrase it sounds like.
+fn phrase_form_keys(entry: &jmdict::Entry<'_>) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    if entry.is_usually_kana() {
+        for r in &entry.reading_elements {
+            keys.push(form_key(r.text, r.text));
+        }
+    }
+
+    for k in &entry.kanji_elements {
+        for r in &entry.reading_elements {
+            if r.applies_to(k.text) {
+                keys.push(form_key(k.text, r.text));
+            }
+        }
+    }
+
+    keys
+}
+
+/// The keys a name is linked to phrases by, which are every form of it
+/// together with every reading, or only its readings if it has no kanji.
+fn name_form_keys(entry: &jmnedict::Entry<'_>) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    for r in &entry.reading {
+        if entry.kanji.is_empty() {
+            keys.push(form_key(r.text, r.text));
+        }
+
+        for &k in &entry.kanji {
+            keys.push(form_key(k, r.text));
+        }
+    }
+
+    keys
+}
+
 fn other_readings(
     output: &mut Vec<(Cow<'_, str>, stored::Id)>,
     text: &str,
     a: stored::Id,
     b: stored::Id,
-    f: for<'a> fn(&'a Segment<'_>) -> &'a str,
+    f: for<'a> fn(&'a romaji::Segment<'_>) -> &'a str,
 ) {
-    let mut romanized = String::new();
     let mut other = String::new();
 
     for part in romaji::analyze(text) {
-        romanized += part.romanize();
         other += f(&part);
     }
 
-    output.push((Cow::Owned(romanized), a));
+    output.push((Cow::Owned(romaji::romanize(text)), a));
     output.push((Cow::Owned(other), b));
 }
 
@@ -915,6 +2255,126 @@ impl Index {
         Ok(self.data.as_buf().load(self.header.name)?)
     }
 
+    /// Load the shard the index holds, or `None` if it holds every entry.
+    pub fn shard(&self) -> Result<Option<&str>> {
+        let shard = self.data.as_buf().load(self.header.shard)?;
+        Ok((!shard.is_empty()).then_some(shard))
+    }
+
+    /// Walk the lookup trie of the index, which every search starts with, so
+    /// that it's paged in. Returns the number of keys walked.
+    ///
+    /// The rest of the index is left to be paged in as it's used.
+    fn warmup(&self, shutdown: &Token) -> usize {
+        let mut sum = 0u8;
+        let mut count = 0;
+
+        for entry in self.header.lookup.iter(self.data.as_buf()) {
+            if count % 4096 == 0 && shutdown.is_set() {
+                break;
+            }
+
+            let Ok((key, _)) = entry else {
+                break;
+            };
+
+            sum = sum.wrapping_add(key.first().copied().unwrap_or_default());
+            count += 1;
+        }
+
+        std::hint::black_box(sum);
+        count
+    }
+
+    /// Load the languages of the glosses in the index.
+    pub fn languages(&self) -> Result<Vec<&str>> {
+        let buf = self.data.as_buf();
+        let mut output = Vec::new();
+
+        for language in buf.load(self.header.languages)? {
+            output.push(buf.load(*language)?);
+        }
+
+        Ok(output)
+    }
+
+    /// Get how common the name at the given offset is, if it's known.
+    fn name_frequency(&self, offset: u32) -> Result<Option<u32>> {
+        let buf = self.data.as_buf();
+        Ok(self.header.name_frequencies.get(buf, &offset)?.copied())
+    }
+
+    /// Get how common the phrase at the given offset is, see
+    /// [`priority::Commonness::level`].
+    fn commonness(&self, offset: u32) -> Result<Option<u8>> {
+        let buf = self.data.as_buf();
+        Ok(self.header.commonness.get(buf, &offset)?.copied())
+    }
+
+    /// Find the section which has an entry starting at the given offset.
+    fn section_at(&self, offset: u32) -> Result<Option<Section>> {
+        let buf = self.data.as_buf();
+
+        let sections = [
+            (Section::Phrases, self.header.phrases),
+            (Section::Kanji, self.header.kanji),
+            (Section::Names, self.header.names),
+        ];
+
+        for (section, offsets) in sections {
+            if buf.load(offsets)?.contains(&offset) {
+                return Ok(Some(section));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the entry starting at the given offset together with the bytes it's
+    /// stored as.
+    fn raw_entry_at(&self, offset: u32) -> Result<Option<RawEntry<'_>>> {
+        let Some(section) = self.section_at(offset)? else {
+            return Ok(None);
+        };
+
+        let Some(bytes) = self.data.as_buf().get(offset as usize..) else {
+            return Err(anyhow!("Missing entry at {offset}"));
+        };
+
+        let mut encoded = Vec::new();
+
+        let entry = match section {
+            Section::Phrases => {
+                let entry: jmdict::Entry<'_> = ENCODING.from_slice(bytes)?;
+                ENCODING.to_writer(&mut encoded, &entry)?;
+                Entry::Phrase(entry)
+            }
+            Section::Kanji => {
+                let entry: kanjidic2::Character<'_> = ENCODING.from_slice(bytes)?;
+                ENCODING.to_writer(&mut encoded, &entry)?;
+                Entry::Kanji(entry)
+            }
+            Section::Names => {
+                let entry: jmnedict::Entry<'_> = ENCODING.from_slice(bytes)?;
+                ENCODING.to_writer(&mut encoded, &entry)?;
+                Entry::Name(entry)
+            }
+        };
+
+        // The length of an entry isn't stored, so it's taken to be the length
+        // of the entry when it's encoded again.
+        let bytes = &bytes[..encoded.len().min(bytes.len())];
+
+        Ok(Some(RawEntry {
+            index: self.name()?,
+            section,
+            offset,
+            bytes,
+            round_trips: bytes == encoded,
+            entry,
+        }))
+    }
+
     /// Get an entry from the database.
     fn entry_at(&self, id: Id) -> Result<Entry<'_>> {
         let Some(bytes) = self.data.as_buf().get(id.offset as usize..) else {
@@ -935,6 +2395,18 @@ impl Index {
 pub struct Database {
     indexes: Arc<[Index]>,
     disabled: Arc<[String]>,
+    /// Shards from the manifest which weren't loaded.
+    missing: Arc<[shard::ManifestShard]>,
+    pins: Arc<BTreeMap<String, Vec<u32>>>,
+    /// Whether variant kanji in queries are replaced by their standard forms.
+    kanji_variants: bool,
+    /// How words are ranked when text is analyzed.
+    analyze_weights: AnalyzeWeights,
+    /// Whether searches only include common words unless `#all` is used.
+    common_only: bool,
+    /// The highest word frequency category a word can have and still be
+    /// considered common.
+    common_frequency: u32,
 }
 
 impl Database {
@@ -943,6 +2415,24 @@ impl Database {
     where
         I: IntoIterator<Item = (Data, Location)>,
     {
+        Self::open_partial(iter, config, &shard::Manifest::default())
+    }
+
+    /// Open a subset of the shards listed in a manifest.
+    ///
+    /// Searches report the shards which weren't opened and could hold more
+    /// results in [`Search::missing_shards`], so that they can be loaded on
+    /// demand.
+    pub fn open_partial<I>(iter: I, config: &Config, manifest: &shard::Manifest) -> Result<Self>
+    where
+        I: IntoIterator<Item = (Data, Location)>,
+    {
+        ensure!(
+            manifest.shards.is_empty() || manifest.version == DATABASE_VERSION,
+            "Manifest is for database version {}, but {DATABASE_VERSION} is in use",
+            manifest.version
+        );
+
         let mut indexes = Vec::new();
         let mut disabled = Vec::new();
 
@@ -964,12 +2454,61 @@ impl Database {
             indexes.push(index);
         }
 
+        let mut missing = Vec::new();
+
+        for s in &manifest.shards {
+            if !config.is_enabled(&s.index) {
+                continue;
+            }
+
+            let mut loaded = false;
+
+            for index in &indexes {
+                if index.name()? == s.index && index.shard()? == s.shard.as_deref() {
+                    loaded = true;
+                    break;
+                }
+            }
+
+            if !loaded {
+                missing.push(s.clone());
+            }
+        }
+
         Ok(Self {
             indexes: indexes.into(),
             disabled: disabled.into(),
+            missing: missing.into(),
+            pins: Arc::new(config.pins.clone()),
+            kanji_variants: config.kanji_variants,
+            analyze_weights: config.analyze_weights,
+            common_only: config.common_only,
+            common_frequency: config.common_frequency,
         })
     }
 
+    /// Test if no indexes are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    /// Get the paths of the shards which weren't loaded and could hold
+    /// entries matching any of the given phrases.
+    fn missing_shards(&self, phrases: &[&str]) -> Vec<String> {
+        let mut output = Vec::new();
+
+        for s in self.missing.iter() {
+            if phrases
+                .iter()
+                .any(|phrase| shard::could_match(s.shard.as_deref(), phrase))
+            {
+                output.push(s.path.clone());
+            }
+        }
+
+        output
+    }
+
     /// Get the identifiers of all installed indexes.
     pub fn installed(&self) -> Result<HashSet<String>> {
         let mut output = HashSet::with_capacity(self.indexes.len());
@@ -982,6 +2521,26 @@ impl Database {
         Ok(output)
     }
 
+    /// Get the gloss languages of each index in use, by the name of the index.
+    pub fn languages(&self) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut output = BTreeMap::new();
+
+        for index in self.indexes.iter() {
+            let languages = index.languages()?;
+
+            if languages.is_empty() {
+                continue;
+            }
+
+            output.insert(
+                index.name()?.to_owned(),
+                languages.into_iter().map(str::to_owned).collect(),
+            );
+        }
+
+        Ok(output)
+    }
+
     /// Convert a sequence to Id.
     pub fn sequence_to_id(&self, sequence: u32) -> Result<Vec<Id>> {
         let mut output = Vec::new();
@@ -997,77 +2556,986 @@ impl Database {
         Ok(output)
     }
 
-    /// Get all entries matching the given id.
-    pub fn entry_at(&self, id: Id) -> Result<Entry<'_>> {
-        let i = self
-            .indexes
-            .get(id.index as usize)
-            .context("missing index")?;
-        i.entry_at(id)
-    }
+    /// Get the phrase with the given sequence from every index it's stored in,
+    /// together with the bytes it's stored as.
+    pub fn raw_entries_by_sequence(&self, sequence: u32) -> Result<Vec<RawEntry<'_>>> {
+        let mut output = Vec::new();
 
-    /// Get kanji by character.
-    pub fn literal_to_kanji(&self, literal: &str) -> Result<Option<kanjidic2::Character<'_>>> {
         for d in self.indexes.iter() {
-            let Some(index) = d.header.by_kanji_literal.get(d.data.as_buf(), literal)? else {
+            let Some(pos) = d.header.by_sequence.get(d.data.as_buf(), &sequence)? else {
                 continue;
             };
 
-            let Some(bytes) = d.data.as_buf().get(*index as usize..) else {
-                return Err(anyhow!("Missing entry at {}", *index));
-            };
-
-            return Ok(Some(ENCODING.from_slice(bytes)?));
+            output.extend(d.raw_entry_at(pos.offset)?);
         }
 
-        Ok(None)
+        Ok(output)
     }
 
-    /// Get radicals by character.
-    pub fn literal_to_radicals(&self, literal: &str) -> Result<Option<kradfile::Entry<'_>>> {
+    /// Get the entry starting at the given byte offset in the named index,
+    /// together with the bytes it's stored as.
+    ///
+    /// Returns `None` if no entry starts at the offset.
+    pub fn raw_entry_at(&self, index: &str, offset: u32) -> Result<Option<RawEntry<'_>>> {
         for d in self.indexes.iter() {
-            let Some(index) = d.header.radicals.get(d.data.as_buf(), literal)? else {
-                continue;
-            };
+            if d.name()? == index {
+                return d.raw_entry_at(offset);
+            }
+        }
 
-            let Some(bytes) = d.data.as_buf().get(*index as usize..) else {
-                return Err(anyhow!("Missing entry at {}", *index));
-            };
+        Err(anyhow!("No index named `{index}` is loaded"))
+    }
 
-            return Ok(Some(ENCODING.from_slice(bytes)?));
+    /// Find pinned entries which no longer resolve to an entry in any index,
+    /// as pairs of the pattern they're pinned for and their sequence id.
+    pub fn unresolved_pins(&self) -> Result<Vec<(String, u32)>> {
+        let mut output = Vec::new();
+
+        for (pattern, sequences) in self.pins.iter() {
+            for &sequence in sequences {
+                if self.sequence_to_entry(sequence)?.is_none() {
+                    output.push((pattern.clone(), sequence));
+                }
+            }
         }
 
-        Ok(None)
+        Ok(output)
     }
 
-    /// Get identifier by sequence.
-    pub fn sequence_to_entry(&self, sequence: u32) -> Result<Option<jmdict::Entry<'_>>> {
-        for d in self.indexes.iter() {
-            let Some(pos) = d.header.by_sequence.get(d.data.as_buf(), &sequence)? else {
-                continue;
-            };
+    /// Page in the lookup tries of all indexes ahead of time, so that the
+    /// first searches don't stall while they are read. Returns the number of
+    /// keys walked.
+    ///
+    /// This blocks until done or until the shutdown token is set.
+    pub fn warmup(&self, shutdown: &Token) -> usize {
+        let mut total = 0;
 
-            let Some(bytes) = d.data.as_buf().get(pos.offset as usize..) else {
-                return Err(anyhow!("Missing entry at {}", pos.offset));
-            };
+        for index in self.indexes.iter() {
+            if shutdown.is_set() {
+                break;
+            }
 
-            return Ok(Some(ENCODING.from_slice(bytes)?));
+            total += index.warmup(shutdown);
         }
 
-        Ok(None)
+        total
     }
 
-    /// Get indexes by part of speech.
-    #[tracing::instrument(skip_all)]
-    pub fn by_pos(&self, pos: Set<PartOfSpeech>) -> Result<Vec<Id>> {
-        let mut unique = BTreeSet::new();
-        let mut output = Vec::new();
-
-        for (index, d) in self.indexes.iter().enumerate() {
-            let mut first = true;
-            unique.clear();
-
-            for pos in pos.iter() {
+    /// Get all entries matching the given id.
+    pub fn entry_at(&self, id: Id) -> Result<Entry<'_>> {
+        let i = self
+            .indexes
+            .get(id.index as usize)
+            .context("missing index")?;
+        i.entry_at(id)
+    }
+
+    /// Get the name of the index the entry with the given key is stored in.
+    pub fn index_name(&self, key: Key) -> Result<&str> {
+        let i = self
+            .indexes
+            .get(key.index as usize)
+            .context("missing index")?;
+        i.name()
+    }
+
+    /// Get how common the name with the given identifier is, if it's known.
+    fn name_frequency(&self, id: Id) -> Result<Option<u32>> {
+        let i = self
+            .indexes
+            .get(id.index as usize)
+            .context("missing index")?;
+        i.name_frequency(id.offset)
+    }
+
+    /// Test if the phrase with the given identifier is common, which is if
+    /// it's marked as common by the ichi, news, spec or gai lists or if its
+    /// word frequency category is at most the configured threshold.
+    fn is_common(&self, id: Id) -> Result<bool> {
+        let i = self
+            .indexes
+            .get(id.index as usize)
+            .context("missing index")?;
+
+        Ok(i.commonness(id.offset)?
+            .is_some_and(|c| u32::from(c) <= self.common_frequency))
+    }
+
+    /// Test if the phrase with the given identifier is marked as common by
+    /// its priorities, regardless of its word frequency category.
+    fn is_marked_common(&self, id: Id) -> Result<bool> {
+        let i = self
+            .indexes
+            .get(id.index as usize)
+            .context("missing index")?;
+
+        Ok(i.commonness(id.offset)? == Some(0))
+    }
+
+    /// Get the rank of the phrase with the given sequence number in the word
+    /// frequency list it was built with, where `1` is the most frequent word.
+    pub(crate) fn frequency_rank(&self, sequence: u64) -> Result<Option<u32>> {
+        let Ok(sequence) = u32::try_from(sequence) else {
+            return Ok(None);
+        };
+
+        for d in self.indexes.iter() {
+            if let Some(&rank) = d
+                .header
+                .phrase_frequencies
+                .get(d.data.as_buf(), &sequence)?
+            {
+                return Ok(Some(rank));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the sequence numbers of the names which are written and read the
+    /// same as the given phrase.
+    pub fn linked_names(&self, phrase: &jmdict::Entry<'_>) -> Result<Vec<u64>> {
+        self.linked(&phrase_form_keys(phrase), |header| &header.names_by_form)
+    }
+
+    /// Get the sequence numbers of the phrases which are written and read the
+    /// same as the given name.
+    pub fn linked_phrases(&self, name: &jmnedict::Entry<'_>) -> Result<Vec<u64>> {
+        self.linked(&name_form_keys(name), |header| &header.phrases_by_form)
+    }
+
+    /// Collect the sequence numbers stored under the given keys in a map of
+    /// every index.
+    fn linked(
+        &self,
+        keys: &[String],
+        map: fn(&stored::IndexHeader) -> &swiss::MapRef<Ref<str>, Ref<[u32]>>,
+    ) -> Result<Vec<u64>> {
+        let mut output = Vec::new();
+
+        for d in self.indexes.iter() {
+            let buf = d.data.as_buf();
+
+            for key in keys {
+                let Some(sequences) = map(&d.header).get(buf, key.as_str())? else {
+                    continue;
+                };
+
+                for &sequence in buf.load(*sequences)? {
+                    if !output.contains(&u64::from(sequence)) {
+                        output.push(u64::from(sequence));
+                    }
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Get the pitch accents of a word written as `text` and read as
+    /// `reading`, from the first index which has them. Words written in kana
+    /// are looked up with the reading as their text.
+    ///
+    /// Accents are numbered as described in [`crate::accent`].
+    pub fn accents(&self, text: &str, reading: &str) -> Result<Option<&[u8]>> {
+        let key = form_key(text, reading);
+
+        for d in self.indexes.iter() {
+            let buf = d.data.as_buf();
+
+            let Some(accents) = d.header.accents.get(buf, key.as_str())? else {
+                continue;
+            };
+
+            return Ok(Some(buf.load(*accents)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Get kanji by character.
+    pub fn literal_to_kanji(&self, literal: &str) -> Result<Option<kanjidic2::Character<'_>>> {
+        for d in self.indexes.iter() {
+            let Some(index) = d.header.by_kanji_literal.get(d.data.as_buf(), literal)? else {
+                continue;
+            };
+
+            let Some(bytes) = d.data.as_buf().get(*index as usize..) else {
+                return Err(anyhow!("Missing entry at {}", *index));
+            };
+
+            return Ok(Some(ENCODING.from_slice(bytes)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Get radicals by character.
+    pub fn literal_to_radicals(&self, literal: &str) -> Result<Option<kradfile::Entry<'_>>> {
+        for d in self.indexes.iter() {
+            let Some(index) = d.header.radicals.get(d.data.as_buf(), literal)? else {
+                continue;
+            };
+
+            let Some(bytes) = d.data.as_buf().get(*index as usize..) else {
+                return Err(anyhow!("Missing entry at {}", *index));
+            };
+
+            return Ok(Some(ENCODING.from_slice(bytes)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Get all radicals from RADKFILE in the order they were defined, which is
+    /// by stroke count.
+    pub fn radicals(&self) -> Result<Vec<radkfile::Entry<'_>>> {
+        let mut output = Vec::new();
+
+        for d in self.indexes.iter() {
+            for offset in d.header.radkfile.iter() {
+                let offset = *d.data.as_buf().load(offset)?;
+
+                let Some(bytes) = d.data.as_buf().get(offset as usize..) else {
+                    return Err(anyhow!("Missing entry at {}", offset));
+                };
+
+                output.push(ENCODING.from_slice(bytes)?);
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Search for kanji which satisfy every one of the given constraints.
+    ///
+    /// Each constraint is looked up in an index of its own, and the kanji are
+    /// the intersection of them. No kanji are returned without constraints.
+    pub fn search_kanji(&self, constraints: &KanjiConstraints<'_>) -> Result<Vec<&str>> {
+        let mut sets = Vec::new();
+
+        for radical in &constraints.radicals {
+            let mut set = HashSet::new();
+            self.radical_to_kanji(radical, &mut set)?;
+            sets.push(set);
+        }
+
+        if let Some(strokes) = &constraints.strokes {
+            let mut set = HashSet::new();
+            self.kanji_by_attribute(|h| &h.kanji_by_strokes, strokes.clone(), &mut set)?;
+            sets.push(set);
+        }
+
+        if let Some(max_grade) = constraints.max_grade {
+            let mut set = HashSet::new();
+            self.kanji_by_attribute(|h| &h.kanji_by_grade, 1..=max_grade, &mut set)?;
+            sets.push(set);
+        }
+
+        // Intersecting from the smallest set keeps the work proportional to
+        // the most restrictive constraint.
+        sets.sort_by_key(|set| set.len());
+
+        let mut sets = sets.into_iter();
+
+        let Some(first) = sets.next() else {
+            return Ok(Vec::new());
+        };
+
+        let rest = sets.collect::<Vec<_>>();
+
+        let mut output = first
+            .into_iter()
+            .filter(|kanji| rest.iter().all(|set| set.contains(kanji)))
+            .collect::<Vec<_>>();
+
+        output.sort();
+        Ok(output)
+    }
+
+    fn kanji_by_attribute<'this, F>(
+        &'this self,
+        map: F,
+        values: RangeInclusive<u8>,
+        output: &mut HashSet<&'this str>,
+    ) -> Result<()>
+    where
+        F: Fn(&stored::IndexHeader) -> &swiss::MapRef<u8, Ref<[Ref<str>]>>,
+    {
+        for d in self.indexes.iter() {
+            let buf = d.data.as_buf();
+            let map = map(&d.header);
+
+            for value in values.clone() {
+                let Some(literals) = map.get(buf, &value)? else {
+                    continue;
+                };
+
+                for literal in buf.load(*literals)? {
+                    output.insert(buf.load(*literal)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collect the kanji which contain the given radical.
+    ///
+    /// This prefers the RADKFILE mapping, but falls back to the inverse of
+    /// KRADFILE in case it isn't available.
+    fn radical_to_kanji<'this>(
+        &'this self,
+        radical: &str,
+        output: &mut HashSet<&'this str>,
+    ) -> Result<()> {
+        for d in self.indexes.iter() {
+            let Some(offset) = d.header.by_radical.get(d.data.as_buf(), radical)? else {
+                continue;
+            };
+
+            let Some(bytes) = d.data.as_buf().get(*offset as usize..) else {
+                return Err(anyhow!("Missing entry at {}", *offset));
+            };
+
+            let entry: radkfile::Entry<'_> = ENCODING.from_slice(bytes)?;
+            output.extend(entry.kanji);
+            return Ok(());
+        }
+
+        for d in self.indexes.iter() {
+            let Some(offsets) = d.header.radicals_to_kanji.get(d.data.as_buf(), radical)? else {
+                continue;
+            };
+
+            for offset in offsets.iter() {
+                let offset = *d.data.as_buf().load(offset)?;
+
+                let Some(bytes) = d.data.as_buf().get(offset as usize..) else {
+                    return Err(anyhow!("Missing entry at {}", offset));
+                };
+
+                let entry: kradfile::Entry<'_> = ENCODING.from_slice(bytes)?;
+                output.insert(entry.kanji);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get identifier by sequence.
+    pub fn sequence_to_entry(&self, sequence: u32) -> Result<Option<jmdict::Entry<'_>>> {
+        for d in self.indexes.iter() {
+            let Some(pos) = d.header.by_sequence.get(d.data.as_buf(), &sequence)? else {
+                continue;
+            };
+
+            let Some(bytes) = d.data.as_buf().get(pos.offset as usize..) else {
+                return Err(anyhow!("Missing entry at {}", pos.offset));
+            };
+
+            return Ok(Some(ENCODING.from_slice(bytes)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Get links from the senses of the phrase with the given sequence to
+    /// other phrases, like its antonyms.
+    pub fn links(&self, sequence: u32) -> Result<Vec<Link>> {
+        for d in self.indexes.iter() {
+            let buf = d.data.as_buf();
+
+            let Some(links) = d.header.links.get(buf, &sequence)? else {
+                continue;
+            };
+
+            let links = buf.load(*links)?;
+
+            return Ok(links
+                .iter()
+                .map(|link| Link {
+                    sense: usize::from(link.sense),
+                    index: usize::from(link.index),
+                    kind: link.kind,
+                    sequence: link.sequence,
+                    target_sense: match link.target_sense {
+                        0 => None,
+                        n => Some(usize::from(n)),
+                    },
+                })
+                .collect());
+        }
+
+        Ok(Vec::new())
+    }
+
+    /// Find words written with the given kanji, using an index built from
+    /// every kanji used in the spellings of phrases.
+    ///
+    /// See [`WordSort`] for how words are ordered.
+    #[tracing::instrument(skip_all)]
+    pub fn words_with_kanji(
+        &self,
+        literal: &str,
+        limit: usize,
+        sort: WordSort,
+    ) -> Result<Vec<jmdict::Entry<'_>>> {
+        let mut chars = literal.chars();
+
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut seen = HashSet::new();
+        let mut words = Vec::new();
+
+        for d in self.indexes.iter() {
+            let buf = d.data.as_buf();
+
+            let Some(sequences) = d.header.words_by_kanji.get(buf, &(c as u32))? else {
+                continue;
+            };
+
+            for &sequence in buf.load(*sequences)? {
+                if !seen.insert(sequence) {
+                    continue;
+                }
+
+                let Some(entry) = self.sequence_to_entry(sequence)? else {
+                    continue;
+                };
+
+                let priority = entry
+                    .priorities()
+                    .map(|p| p.weight())
+                    .fold(0.0f32, f32::max);
+
+                let length = entry
+                    .kanji_elements
+                    .iter()
+                    .filter(|k| k.text.contains(c))
+                    .map(|k| k.text.chars().count())
+                    .min()
+                    .unwrap_or(usize::MAX);
+
+                words.push((priority, length, entry));
+            }
+        }
+
+        match sort {
+            WordSort::Common => {
+                words.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+            }
+            WordSort::Short => {
+                words.sort_by(|a, b| a.1.cmp(&b.1).then(b.0.total_cmp(&a.0)));
+            }
+        }
+
+        words.truncate(limit);
+        Ok(words.into_iter().map(|(_, _, entry)| entry).collect())
+    }
+
+    /// Find example sentences containing the given word.
+    ///
+    /// Sentences which list the word among their words are returned first,
+    /// followed by sentences which contain it anywhere in their text. Shorter
+    /// sentences are preferred within each group.
+    #[tracing::instrument(skip_all)]
+    pub fn example_sentences(&self, query: &str, limit: usize) -> Result<ExampleSentences<'_>> {
+        let query = query.trim();
+
+        if query.is_empty() {
+            return Ok(ExampleSentences {
+                sentences: Vec::new(),
+                truncated: false,
+            });
+        }
+
+        let mut seen = HashSet::new();
+        let mut sentences = Vec::new();
+        let mut truncated = false;
+
+        for (index, d) in self.indexes.iter().enumerate() {
+            let buf = d.data.as_buf();
+
+            let mut candidates = Vec::new();
+
+            if let Some(offsets) = d.header.sentences_by_word.get(buf, query)? {
+                candidates.extend(buf.load(*offsets)?.iter().map(|&offset| (0u8, offset)));
+            }
+
+            // Look through the sentences containing the least common
+            // character of the query, since all matches must contain it.
+            let mut rarest: Option<&[u32]> = None;
+
+            for c in query.chars().filter(|&c| kana::is_japanese(c)) {
+                let offsets = match d.header.sentences_by_char.get(buf, &(c as u32))? {
+                    Some(offsets) => buf.load(*offsets)?,
+                    None => &[],
+                };
+
+                if rarest.is_none_or(|rarest| rarest.len() > offsets.len()) {
+                    rarest = Some(offsets);
+                }
+            }
+
+            if let Some(offsets) = rarest {
+                truncated |= offsets.len() > EXAMPLE_SENTENCE_SCAN_LIMIT;
+                let offsets = &offsets[..offsets.len().min(EXAMPLE_SENTENCE_SCAN_LIMIT)];
+                candidates.extend(offsets.iter().map(|&offset| (1u8, offset)));
+            }
+
+            for (tier, offset) in candidates {
+                if !seen.insert((index, offset)) {
+                    continue;
+                }
+
+                let Some(bytes) = buf.get(offset as usize..) else {
+                    return Err(anyhow!("Missing sentence at {}", offset));
+                };
+
+                let sentence: tatoeba::Sentence<'_> = ENCODING.from_slice(bytes)?;
+
+                if tier > 0 && !sentence.japanese.contains(query) {
+                    continue;
+                }
+
+                sentences.push((tier, sentence.japanese.chars().count(), sentence));
+            }
+        }
+
+        sentences.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+        sentences.truncate(limit);
+
+        Ok(ExampleSentences {
+            sentences: sentences
+                .into_iter()
+                .map(|(_, _, sentence)| sentence)
+                .collect(),
+            truncated,
+        })
+    }
+
+    /// Find nouns whose reading starts with the given kana, for use in a game
+    /// of shiritori.
+    ///
+    /// Candidates are ordered by how common they are, and the kana can be
+    /// given either as hiragana or katakana.
+    pub fn shiritori_candidates(
+        &self,
+        last_kana: &str,
+        constraints: &ShiritoriConstraints,
+    ) -> Result<Vec<jmdict::Entry<'_>>> {
+        fn hiragana(text: &str) -> String {
+            romaji::analyze(text).map(|s| s.hiragana()).collect()
+        }
+
+        let prefix = hiragana(last_kana);
+
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let katakana = romaji::analyze(last_kana)
+            .map(|s| s.katakana())
+            .collect::<String>();
+
+        let mut seen = HashSet::new();
+        let mut words = Vec::new();
+
+        for query in [&prefix, &katakana] {
+            for id in self.lookup(&format!("{query}*"))? {
+                if !matches!(
+                    id.source,
+                    Source::Phrase {
+                        index: PhraseIndex::Hiragana
+                    }
+                ) {
+                    continue;
+                }
+
+                if !seen.insert(id.key()) {
+                    continue;
+                }
+
+                let Entry::Phrase(entry) = self.entry_at(id)? else {
+                    continue;
+                };
+
+                if u32::try_from(entry.sequence)
+                    .is_ok_and(|sequence| constraints.played.contains(&sequence))
+                {
+                    continue;
+                }
+
+                let is_noun = entry
+                    .senses
+                    .iter()
+                    .any(|s| s.pos.iter().any(|pos| pos.generic() == Some("noun")));
+
+                if !is_noun {
+                    continue;
+                }
+
+                let priority = entry
+                    .priorities()
+                    .map(|p| p.weight())
+                    .fold(0.0f32, f32::max);
+
+                if constraints.common && priority == 0.0 {
+                    continue;
+                }
+
+                let playable = entry.reading_elements.iter().any(|reading| {
+                    let reading = hiragana(reading.text);
+                    reading.starts_with(&prefix)
+                        && !(constraints.exclude_n && reading.ends_with('ん'))
+                });
+
+                if !playable {
+                    continue;
+                }
+
+                words.push((priority, entry));
+            }
+        }
+
+        words.sort_by(|a, b| b.0.total_cmp(&a.0));
+        words.truncate(constraints.limit);
+        Ok(words.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Find words whose reading is easily confused with the given reading,
+    /// like おばさん and おばあさん, for listening practice.
+    ///
+    /// Each word is returned together with its confusable reading and how it
+    /// differs from the given reading. Words are grouped by their reading in
+    /// the order the variants are generated, and ordered by how common they
+    /// are within each group.
+    pub fn minimal_pairs(
+        &self,
+        reading: &str,
+        limit: usize,
+    ) -> Result<Vec<(kana::Confusion, &str, jmdict::Entry<'_>)>> {
+        // Variants are in hiragana, but readings of words like パス are in
+        // katakana and might prolong vowels with `ー`.
+        fn fold(text: &str) -> String {
+            let text = text.chars().map(kana::to_hiragana).collect::<String>();
+            romaji::expand_long_vowels(&text).into_owned()
+        }
+
+        let mut seen = HashSet::new();
+        let mut output = Vec::new();
+
+        for (confusion, variant) in kana::confusions(reading) {
+            // Words written in katakana are only indexed by their katakana
+            // reading.
+            let katakana = variant.chars().map(kana::to_katakana).collect::<String>();
+
+            let mut ids = Vec::new();
+            self.lookup_exact(&self.lookup_key(&variant), &mut ids)?;
+            self.lookup_exact(&self.lookup_key(&katakana), &mut ids)?;
+
+            let variant = fold(&variant);
+            let mut words = Vec::new();
+
+            for id in ids {
+                let Entry::Phrase(entry) = self.entry_at(id)? else {
+                    continue;
+                };
+
+                let Some(text) = entry
+                    .reading_elements
+                    .iter()
+                    .find(|r| fold(r.text) == variant)
+                    .map(|r| r.text)
+                else {
+                    continue;
+                };
+
+                if !seen.insert(entry.sequence) {
+                    continue;
+                }
+
+                let priority = entry
+                    .priorities()
+                    .map(|p| p.weight())
+                    .fold(0.0f32, f32::max);
+
+                words.push((priority, text, entry));
+            }
+
+            words.sort_by(|a, b| b.0.total_cmp(&a.0));
+            output.extend(
+                words
+                    .into_iter()
+                    .map(|(_, text, entry)| (confusion, text, entry)),
+            );
+
+            if output.len() >= limit {
+                break;
+            }
+        }
+
+        output.truncate(limit);
+        Ok(output)
+    }
+
+    /// Generate conjugation drills for common words with any of the given parts
+    /// of speech, or any verb if none are given.
+    ///
+    /// One drill is picked for each word, and which words and drills are
+    /// picked depends on the seed so that different seeds produce different
+    /// worksheets.
+    pub fn drills(
+        &self,
+        pos: &[PartOfSpeech],
+        filter: &drill::Filter,
+        seed: u64,
+        limit: usize,
+    ) -> Result<Vec<drill::Drill>> {
+        let verbs;
+
+        let pos = if pos.is_empty() {
+            verbs = PartOfSpeech::VALUES
+                .iter()
+                .copied()
+                .filter(|pos| pos.generic() == Some("verb"))
+                .collect::<Vec<_>>();
+
+            &verbs[..]
+        } else {
+            pos
+        };
+
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+
+        for &pos in pos {
+            let mut set = Set::new();
+            set.insert(pos);
+
+            for id in self.by_pos(set)? {
+                if seen.insert(id.key()) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        ids.sort_by_key(|id| seeded_hash(seed, id.key()));
+
+        let mut output = Vec::new();
+
+        for id in ids {
+            if output.len() >= limit {
+                break;
+            }
+
+            let Entry::Phrase(entry) = self.entry_at(id)? else {
+                continue;
+            };
+
+            if entry.priorities().next().is_none() {
+                continue;
+            }
+
+            let mut drills = drill::drills(&entry, filter);
+
+            if drills.is_empty() {
+                continue;
+            }
+
+            let n = seeded_hash(seed, entry.sequence) as usize % drills.len();
+            output.push(drills.swap_remove(n));
+        }
+
+        Ok(output)
+    }
+
+    /// Collect the phrases marked as common with any of the given parts of
+    /// speech, or with any part of speech if none are given, which words for
+    /// typing practice are picked from by [`Database::typing_words`].
+    ///
+    /// Collecting them means going through every phrase with the given parts
+    /// of speech, so they're meant to be reused for as long as the database
+    /// is.
+    pub fn typing_candidates(&self, pos: &[PartOfSpeech]) -> Result<Vec<Id>> {
+        let pos = if pos.is_empty() {
+            PartOfSpeech::VALUES
+        } else {
+            pos
+        };
+
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+
+        for &pos in pos {
+            let mut set = Set::new();
+            set.insert(pos);
+
+            for id in self.by_pos(set)? {
+                if seen.insert(id.key()) && self.is_marked_common(id)? {
+                    ids.push(id);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Pick words for typing practice out of the given candidates.
+    ///
+    /// Which words are picked and in which order depends on the seed. Words
+    /// are picked starting at the given continuation, and the continuation
+    /// to pick more words with is returned if there are any left.
+    pub fn typing_words(
+        &self,
+        candidates: &[Id],
+        filter: &typing::Filter,
+        seed: u64,
+        continuation: usize,
+        limit: usize,
+    ) -> Result<(Vec<typing::Word>, Option<usize>)> {
+        let mut ids = candidates.to_vec();
+        ids.sort_by_key(|id| seeded_hash(seed, id.key()));
+
+        let mut output = Vec::new();
+
+        for (n, id) in ids.iter().enumerate().skip(continuation) {
+            if output.len() >= limit {
+                return Ok((output, Some(n)));
+            }
+
+            let Entry::Phrase(entry) = self.entry_at(*id)? else {
+                continue;
+            };
+
+            if let Some(word) = typing::word(&entry, filter) {
+                output.push(word);
+            }
+        }
+
+        Ok((output, None))
+    }
+
+    /// Move entries pinned for the given input to the front, in the order they
+    /// were pinned. Pinned entries which are not part of the results are
+    /// ignored.
+    fn apply_pins(&self, input: &str, phrases: &mut [(EntryResultKey, jmdict::Entry<'_>)]) {
+        let mut front = 0;
+
+        for (pattern, sequences) in self.pins.iter() {
+            if !glob(pattern, input) {
+                continue;
+            }
+
+            for &sequence in sequences {
+                let Some(n) = phrases[front..]
+                    .iter()
+                    .position(|(_, e)| e.sequence == u64::from(sequence))
+                else {
+                    continue;
+                };
+
+                phrases[front..=front + n].rotate_right(1);
+                front += 1;
+            }
+        }
+    }
+
+    /// Find entries related to the one with the given sequence number.
+    ///
+    /// Entries are scored by the glossary tokens and kanji they share with the
+    /// entry, where rare tokens count for more than common ones. Tokens which
+    /// are shared by too many entries aren't considered at all.
+    #[tracing::instrument(skip_all)]
+    pub fn related(&self, sequence: u32, limit: usize) -> Result<Vec<(f32, jmdict::Entry<'_>)>> {
+        /// Tokens shared by more entries than this are not significant.
+        const MAX_SHARED: usize = 200;
+
+        let Some(entry) = self.sequence_to_entry(sequence)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut queries = BTreeSet::new();
+
+        for sense in &entry.senses {
+            for gloss in &sense.gloss {
+                if gloss.ty == Some("expl") {
+                    continue;
+                }
+
+                for phrase in analyze_glossary::analyze(gloss.text) {
+                    if phrase.chars().count() <= MAX_MEANING_PHRASE && !is_common(phrase) {
+                        queries.insert(phrase.to_lowercase());
+                    }
+                }
+            }
+        }
+
+        for kanji in &entry.kanji_elements {
+            for c in kanji.text.chars().filter(|&c| kana::is_kanji(c)) {
+                queries.insert(format!("{c}*"));
+            }
+        }
+
+        let mut scores = HashMap::<Key, (Id, f32)>::new();
+        let mut keys = HashMap::new();
+
+        for query in &queries {
+            keys.clear();
+
+            for id in self.lookup(query)? {
+                if let Source::Phrase { .. } | Source::Inflection { .. } = id.source {
+                    keys.insert(id.key(), id);
+                }
+            }
+
+            if keys.is_empty() || keys.len() > MAX_SHARED {
+                continue;
+            }
+
+            let score = 1.0 / (1.0 + keys.len() as f32).ln();
+
+            for (&key, &id) in &keys {
+                scores.entry(key).or_insert((id, 0.0)).1 += score;
+            }
+        }
+
+        let mut scores = scores.into_values().collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut output = Vec::new();
+
+        for (id, score) in scores {
+            if output.len() >= limit {
+                break;
+            }
+
+            let Entry::Phrase(related) = self.entry_at(id)? else {
+                continue;
+            };
+
+            if related.sequence != entry.sequence {
+                output.push((score, related));
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Get indexes by part of speech.
+    #[tracing::instrument(skip_all)]
+    pub fn by_pos(&self, pos: Set<PartOfSpeech>) -> Result<Vec<Id>> {
+        let mut unique = BTreeSet::new();
+        let mut output = Vec::new();
+
+        for (index, d) in self.indexes.iter().enumerate() {
+            let mut first = true;
+            unique.clear();
+
+            for pos in pos.iter() {
                 if let Some(by_pos) = d.header.by_pos.get(d.data.as_buf(), &pos)? {
                     if first {
                         first = false;
@@ -1101,94 +3569,233 @@ impl Database {
             }
         }
 
-        Ok(output)
+        Ok(output)
+    }
+
+    /// Iterate over every phrase, kanji and name in the database.
+    ///
+    /// Ids are produced lazily, so this can be used to walk the whole
+    /// database without holding all of it in memory.
+    pub fn all(&self) -> impl Iterator<Item = Result<Id>> + '_ {
+        self.indexes.iter().enumerate().flat_map(move |(index, d)| {
+            let buf = d.data.as_buf();
+
+            let phrases = d.header.phrases.iter().map(move |result| {
+                let id = *buf.load(result)?;
+                self.convert_id(index, stored::Id::phrase(id, PhraseIndex::Entry))
+            });
+
+            let kanji = d.header.kanji.iter().map(move |result| {
+                let id = *buf.load(result)?;
+                self.convert_id(index, stored::Id::kanji(id, KanjiIndex::Entry))
+            });
+
+            let names = d.header.names.iter().map(move |result| {
+                let id = *buf.load(result)?;
+                self.convert_id(index, stored::Id::name(id, NameIndex::Literal))
+            });
+
+            phrases.chain(kanji).chain(names)
+        })
+    }
+
+    /// Pick a common word for the given day, counted in days since the Unix
+    /// epoch.
+    ///
+    /// The same day picks the same word for as long as the indexes aren't
+    /// rebuilt.
+    pub fn word_of_day(&self, day: u64) -> Result<Option<jmdict::Entry<'_>>> {
+        for (index, d) in self.indexes.iter().enumerate() {
+            let len = d.header.phrases.len();
+
+            if len == 0 {
+                continue;
+            }
+
+            let buf = d.data.as_buf();
+
+            // Spread consecutive days out over the index, and walk it from
+            // there until a common word is found.
+            let start = (day.wrapping_mul(0x9e37_79b9_7f4a_7c15) % len as u64) as usize;
+
+            let ids = d
+                .header
+                .phrases
+                .iter()
+                .skip(start)
+                .chain(d.header.phrases.iter().take(start));
+
+            for result in ids.take(WORD_OF_DAY_ATTEMPTS) {
+                let id = *buf.load(result)?;
+                let id = self.convert_id(index, stored::Id::phrase(id, PhraseIndex::Entry))?;
+
+                let Entry::Phrase(entry) = self.entry_at(id)? else {
+                    continue;
+                };
+
+                if entry.priorities().any(|p| p.is_common()) {
+                    return Ok(Some(entry));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Perform a free text lookup, producing every matching id.
+    #[tracing::instrument(skip_all)]
+    pub fn lookup(&self, query: &str) -> Result<Vec<Id>> {
+        Ok(self.lookup_limited(query, 0, usize::MAX)?.ids)
     }
 
-    /// Lookup any entries matching a custom filter.
+    /// Perform a free text lookup of a query from a user, where lookups of
+    /// wildcard patterns are limited to [`WILDCARD_LIMIT`] ids at a time,
+    /// starting at the given continuation.
     #[tracing::instrument(skip_all)]
-    pub fn all(&self) -> Result<Vec<Id>> {
-        let mut output = Vec::new();
+    pub fn lookup_from(&self, query: &str, continuation: usize) -> Result<Lookup> {
+        self.lookup_limited(query, continuation, WILDCARD_LIMIT)
+    }
 
-        for (index, d) in self.indexes.iter().enumerate() {
-            for result in d.header.phrases.iter() {
-                let id = *d.data.as_buf().load(result)?;
-                let id = stored::Id::phrase(id, PhraseIndex::Entry);
-                output.push(self.convert_id(index, id)?);
-            }
+    /// Get the key a phrase is looked up by.
+    ///
+    /// Entries written with variant kanji are also indexed by their standard
+    /// forms, so the standard form finds both. Long vowel marks are matched
+    /// against the vowel they prolong, so that `らーめん` finds `らあめん` and
+    /// the other way around.
+    pub fn lookup_key<'q>(&self, phrase: &'q str) -> Cow<'q, str> {
+        let normalized = if self.kanji_variants {
+            variants::normalize(phrase)
+        } else {
+            Cow::Borrowed(phrase)
+        };
 
-            for result in d.header.kanji.iter() {
-                let id = *d.data.as_buf().load(result)?;
-                let id = stored::Id::kanji(id, KanjiIndex::Entry);
-                output.push(self.convert_id(index, id)?);
-            }
+        match normalized {
+            Cow::Borrowed(phrase) => romaji::expand_long_vowels(phrase),
+            Cow::Owned(phrase) => match romaji::expand_long_vowels(&phrase) {
+                Cow::Borrowed(..) => Cow::Owned(phrase),
+                Cow::Owned(expanded) => Cow::Owned(expanded),
+            },
         }
-
-        Ok(output)
     }
 
-    /// Perform a free text lookup.
-    #[tracing::instrument(skip_all)]
-    pub fn lookup(&self, query: &str) -> Result<Vec<Id>> {
-        let mut output = Vec::new();
+    fn lookup_limited(&self, query: &str, continuation: usize, limit: usize) -> Result<Lookup> {
+        let key = self.lookup_key(query);
+        let query = key.as_ref();
 
         if query.chars().all(|c| matches!(c, '*' | '＊')) {
-            for (index, d) in self.indexes.iter().enumerate() {
-                for result in d.header.phrases.iter() {
-                    let id = *d.data.as_buf().load(result)?;
-                    let id = stored::Id::phrase(id, PhraseIndex::Entry);
-                    output.push(self.convert_id(index, id)?);
-                }
-            }
+            let ids = self.indexes.iter().enumerate().flat_map(|(index, d)| {
+                let buf = d.data.as_buf();
+
+                d.header.phrases.iter().map(move |result| {
+                    let id = *buf.load(result)?;
+                    self.convert_id(index, stored::Id::phrase(id, PhraseIndex::Entry))
+                })
+            });
 
-            return Ok(output);
+            return Lookup::collect(ids, continuation, limit);
         }
 
-        let Some((prefix, suffix)) = query.split_once(['*', '＊']) else {
-            for (n, d) in self.indexes.iter().enumerate() {
-                if let Some(lookup) = d.header.lookup.get(d.data.as_buf(), query)? {
-                    for id in lookup {
-                        output.push(self.convert_id(n, *id)?);
-                    }
+        let Some(pattern) = wildcard::Pattern::parse(query) else {
+            let mut output = Vec::new();
+            self.lookup_exact(query, &mut output)?;
+
+            // Fall back to a spelling with different okurigana.
+            if output.is_empty() {
+                if let Some(key) = okurigana_key(query) {
+                    self.lookup_exact(&key, &mut output)?;
                 }
             }
 
-            return Ok(output);
+            return Ok(Lookup {
+                ids: output,
+                continuation: None,
+            });
         };
 
-        let parts = suffix
-            .split(['*', '＊'])
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>();
+        let pattern = &pattern;
+        let prefix = pattern.prefix();
 
-        for (n, d) in self.indexes.iter().enumerate() {
-            'outer: for id in d.header.lookup.iter_in(d.data.as_buf(), prefix) {
-                let (string, id) = id?;
+        let ids = self.indexes.iter().enumerate().flat_map(|(n, d)| {
+            d.header
+                .lookup
+                .iter_in(d.data.as_buf(), prefix)
+                .map(move |id| -> Result<Option<Id>> {
+                    let (string, id) = id?;
 
-                let Some(mut rest) = string.strip_prefix(prefix.as_bytes()) else {
-                    continue;
-                };
+                    let Some(rest) = string.strip_prefix(prefix.as_bytes()) else {
+                        return Ok(None);
+                    };
 
-                if let [head @ .., tail] = &parts[..] {
-                    for &part in head {
-                        let Some(next) = memchr::memmem::find(rest, part.as_bytes()) else {
-                            continue 'outer;
-                        };
+                    let Ok(rest) = std::str::from_utf8(rest) else {
+                        return Ok(None);
+                    };
 
-                        rest = &rest[next + part.len()..];
+                    if !pattern.matches_rest(rest) {
+                        return Ok(None);
                     }
 
-                    if !rest.ends_with(tail.as_bytes()) {
-                        continue;
-                    }
+                    Ok(Some(self.convert_id(n, *id)?))
+                })
+                .filter_map(Result::transpose)
+        });
+
+        Lookup::collect(ids, continuation, limit)
+    }
+
+    /// Look up the first phrases and names stored under exactly the given
+    /// key, for uses like hovering over words which need to be fast.
+    ///
+    /// Unlike [`Database::search`] this doesn't rank anything, and entries
+    /// are only decoded until `limit` of them have been found.
+    #[tracing::instrument(skip_all)]
+    pub fn lookup_exact_limited(&self, key: &str, limit: usize) -> Result<Vec<Entry<'_>>> {
+        let key = self.lookup_key(key);
+        let key = key.as_ref();
+
+        let mut output = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (n, d) in self.indexes.iter().enumerate() {
+            let Some(lookup) = d.header.lookup.get(d.data.as_buf(), key)? else {
+                continue;
+            };
+
+            for id in lookup {
+                if output.len() >= limit {
+                    return Ok(output);
+                }
+
+                if matches!(id.source, stored::Source::Kanji { .. }) {
+                    continue;
+                }
+
+                let id = self.convert_id(n, *id)?;
+
+                if !seen.insert(id.key()) {
+                    continue;
                 }
 
-                output.push(self.convert_id(n, *id)?);
+                output.push(self.entry_at(id)?);
             }
         }
 
         Ok(output)
     }
 
+    fn lookup_exact(&self, query: &str, output: &mut Vec<Id>) -> Result<()> {
+        for (n, d) in self.indexes.iter().enumerate() {
+            let _span = tracing::debug_span!("index", index = n).entered();
+
+            if let Some(lookup) = d.header.lookup.get(d.data.as_buf(), query)? {
+                for id in lookup {
+                    output.push(self.convert_id(n, *id)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all)]
     fn convert_id(&self, index: usize, id: stored::Id) -> Result<Id> {
         Ok(Id {
@@ -1222,17 +3829,55 @@ impl Database {
     }
 
     /// Perform the given search.
-    #[tracing::instrument(skip_all)]
     pub fn search(&self, input: &str) -> Result<Search<'_>> {
+        self.search_from(input, 0)
+    }
+
+    /// Perform the given search, continuing the lookup of the first phrase
+    /// from the given continuation if it's a wildcard pattern.
+    ///
+    /// Other phrases only narrow down the results, so they are looked up in
+    /// full.
+    #[tracing::instrument(skip_all)]
+    pub fn search_from(&self, input: &str, continuation: usize) -> Result<Search<'_>> {
+        self.search_with(input, continuation, WILDCARD_LIMIT)
+    }
+
+    /// Perform the given search where only the first `limit` ids matching a
+    /// wildcard pattern are looked up, for uses like popups which only show
+    /// the best few results.
+    #[tracing::instrument(skip_all)]
+    pub fn search_limited(&self, input: &str, limit: usize) -> Result<Search<'_>> {
+        self.search_with(input, 0, limit)
+    }
+
+    fn search_with(&self, input: &str, continuation: usize, limit: usize) -> Result<Search<'_>> {
         let mut phrases = Vec::new();
         let mut names = Vec::new();
         let mut characters = Vec::new();
         let mut dedup_phrases = HashMap::new();
         let mut dedup_names = HashMap::new();
         let mut seen = HashSet::new();
+        let mut timings = Timings::default();
+
+        let start = Instant::now();
+
+        let input = crate::search::normalize(input);
+        let input = input.as_ref();
+        let query = tracing::debug_span!("parse").in_scope(|| crate::search::parse(input));
+
+        timings.parse = start.elapsed();
+
+        let missing_shards = self.missing_shards(&query.phrases);
+        let common = query.common.unwrap_or(self.common_only);
 
-        let query = crate::search::parse(input);
+        let mine = if query.mine {
+            self.search_mine(&query.phrases)?
+        } else {
+            Vec::new()
+        };
 
+        let single = query.phrases.len() == 1;
         let mut inputs = query.phrases.into_iter();
 
         let Some(first) = inputs.next() else {
@@ -1240,11 +3885,23 @@ impl Database {
                 phrases,
                 names,
                 characters,
+                inflections: Vec::new(),
+                mine,
+                missing_shards,
+                continuation: None,
+                fallback: None,
+                timings,
             });
         };
 
+        let start = Instant::now();
+        let span = tracing::debug_span!("lookup").entered();
+
         self.populate_kanji(first, &mut seen, &mut characters)?;
-        let mut ids = self.lookup(first)?;
+        let Lookup {
+            mut ids,
+            continuation,
+        } = self.lookup_limited(first, continuation, limit)?;
 
         for remainder in inputs {
             self.populate_kanji(remainder, &mut seen, &mut characters)?;
@@ -1258,10 +3915,35 @@ impl Database {
             ids.retain(|id| current.contains(&(id.index, id.offset)));
         }
 
+        let mut fallback = None;
+
+        if ids.is_empty() && single && continuation.is_none() {
+            if let Some((fallback_ids, found)) = self.lookup_fallback(first)? {
+                ids = fallback_ids;
+                fallback = Some(found);
+            }
+        }
+
+        span.exit();
+        timings.lookup = start.elapsed();
+
+        let start = Instant::now();
+        let span = tracing::debug_span!("decode", ids = ids.len()).entered();
+
         let mut current = HashSet::new();
         let mut buf = String::new();
 
         for id in ids {
+            // Check how common phrases are before decoding them, since most
+            // phrases are filtered out when only common words are included.
+            // Names and kanji don't have priorities, so they are always
+            // included.
+            let phrase = matches!(id.source, Source::Phrase { .. } | Source::Inflection { .. });
+
+            if common && phrase && !self.is_common(id)? {
+                continue;
+            }
+
             match self.entry_at(id)? {
                 Entry::Kanji(kanji) => {
                     if seen.insert(kanji.literal) {
@@ -1284,6 +3966,14 @@ impl Database {
                         }
                     }
 
+                    if !query
+                        .priorities
+                        .iter()
+                        .all(|filter| entry.priorities().any(|p| filter.matches(p)))
+                    {
+                        continue;
+                    }
+
                     let Some(&i) = dedup_phrases.get(&id.key()) else {
                         dedup_phrases.insert(id.key(), phrases.len());
 
@@ -1304,6 +3994,11 @@ impl Database {
                     data.sources.insert(id.source);
                 }
                 Entry::Name(entry) => {
+                    // Names don't have priorities.
+                    if !query.priorities.is_empty() {
+                        continue;
+                    }
+
                     if !query.entities.is_empty() {
                         current.clear();
                         current.extend(query.entities.iter().copied());
@@ -1320,10 +4015,18 @@ impl Database {
                     let Some(&i) = dedup_names.get(&id.key()) else {
                         dedup_names.insert(id.key(), names.len());
 
+                        // Names are ranked by how common they are if their
+                        // frequencies are known, and otherwise keep the order
+                        // in which they were found.
+                        let weight = match self.name_frequency(id)? {
+                            Some(frequency) => Weight::new(frequency as f32),
+                            None => Weight::default(),
+                        };
+
                         let data = EntryResultKey {
                             key: id.key(),
                             sources: [id.source].into_iter().collect(),
-                            weight: Weight::default(),
+                            weight,
                         };
 
                         names.push((data, entry));
@@ -1339,13 +4042,61 @@ impl Database {
             }
         }
 
+        if query.has_example {
+            phrases.retain(|(_, entry)| entry.senses.iter().any(|s| !s.examples.is_empty()));
+        }
+
+        if !query.glosses.is_empty() {
+            let glosses = query
+                .glosses
+                .iter()
+                .map(|gloss| gloss.to_lowercase())
+                .collect::<Vec<_>>();
+
+            let matches = |texts: &mut dyn Iterator<Item = &str>| {
+                let texts = texts.map(str::to_lowercase).collect::<Vec<_>>();
+
+                glosses
+                    .iter()
+                    .all(|gloss| texts.iter().any(|text| text.contains(gloss.as_str())))
+            };
+
+            // Results have to match the glosses as well as the Japanese part
+            // of the query, so nothing is left if no glossary matches.
+            phrases.retain(|(_, entry)| {
+                matches(&mut entry.senses.iter().flat_map(|s| &s.gloss).map(|g| g.text))
+            });
+
+            names.retain(|(_, entry)| matches(&mut entry.translations.iter().map(|t| t.text)));
+        }
+
+        span.exit();
+        timings.decode = start.elapsed();
+
+        let start = Instant::now();
+        let span = tracing::debug_span!("rank").entered();
+
+        let ranked_by = fallback.as_ref().map_or(input, |f| f.matched.as_str());
+
         for (data, e) in &mut phrases {
             let inflection = data.sources.iter().any(|source| source.is_inflection());
-            data.weight = e.weight(input, inflection);
+            let mut boost = e.sense_boost(&query.entities, &query.fields);
+
+            if data.sources.iter().any(|source| source.is_exact_meaning()) {
+                boost *= EXACT_MEANING_BOOST;
+            }
+
+            for filter in &query.priorities {
+                boost *= filter.boost(e.priorities());
+            }
+
+            let frequency = self.frequency_rank(e.sequence)?;
+            data.weight = e.weight(ranked_by, inflection, frequency).boost(boost);
         }
 
         names.sort_by(|a, b| a.0.weight.cmp(&b.0.weight));
         phrases.sort_by(|a, b| a.0.weight.cmp(&b.0.weight));
+        self.apply_pins(input.trim(), &mut phrases);
 
         for (_, entry) in &phrases {
             for kanji in &entry.kanji_elements {
@@ -1359,13 +4110,166 @@ impl Database {
             }
         }
 
+        let mut inflections = Vec::new();
+
+        if !query.inflection.is_empty() {
+            let mut unique = HashSet::new();
+
+            for (_, entry) in &phrases {
+                for (_, c, _) in inflection::conjugate(entry) {
+                    let Some(form) = c.get(query.inflection) else {
+                        continue;
+                    };
+
+                    if unique.insert(form.to_string()) {
+                        inflections.push((entry.sequence, form.clone()));
+                    }
+                }
+            }
+        }
+
+        span.exit();
+        timings.rank = start.elapsed();
+
         Ok(Search {
             phrases,
             names,
             characters,
+            inflections,
+            mine,
+            missing_shards,
+            continuation,
+            fallback,
+            timings,
         })
     }
 
+    /// Look up a phrase which found nothing as written, first with trailing
+    /// particles and punctuation stripped, like `本を` as `本`, and then by
+    /// entries starting with it.
+    fn lookup_fallback(&self, phrase: &str) -> Result<Option<(Vec<Id>, Fallback)>> {
+        if wildcard::Pattern::parse(phrase).is_some() {
+            return Ok(None);
+        }
+
+        let (matched, stripped) = strip_trailing(phrase).unwrap_or((phrase, ""));
+
+        if !stripped.is_empty() {
+            let ids = self.lookup(matched)?;
+
+            if !ids.is_empty() {
+                return Ok(Some((
+                    ids,
+                    Fallback {
+                        original: phrase.to_owned(),
+                        matched: matched.to_owned(),
+                        stripped: stripped.to_owned(),
+                        prefix: false,
+                    },
+                )));
+            }
+        }
+
+        // Too short prefixes match large parts of the database.
+        if matched.chars().count() < MIN_PREFIX {
+            return Ok(None);
+        }
+
+        let ids = self.lookup(&format!("{matched}*"))?;
+
+        if ids.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            ids,
+            Fallback {
+                original: phrase.to_owned(),
+                matched: matched.to_owned(),
+                stripped: stripped.to_owned(),
+                prefix: true,
+            },
+        )))
+    }
+
+    /// Search the user's own data for the given phrases, returning every
+    /// pinned entry if there are no phrases.
+    ///
+    /// An entry matches if it's pinned for a pattern which either matches or
+    /// contains one of the phrases.
+    fn search_mine(&self, phrases: &[&str]) -> Result<Vec<jmdict::Entry<'_>>> {
+        let mut output = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (pattern, sequences) in self.pins.iter() {
+            let matches = phrases.is_empty()
+                || phrases
+                    .iter()
+                    .any(|phrase| glob(pattern, phrase) || pattern.contains(phrase));
+
+            if !matches {
+                continue;
+            }
+
+            for &sequence in sequences {
+                if !seen.insert(sequence) {
+                    continue;
+                }
+
+                if let Some(entry) = self.sequence_to_entry(sequence)? {
+                    output.push(entry);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Find up to `limit` phrases among the ones with the given sequence
+    /// numbers, like the ones in the history of looked up words, which are
+    /// written or read in a way which matches or contains one of the given
+    /// phrases. Every phrase matches if there are no phrases.
+    pub fn search_sequences<I>(
+        &self,
+        sequences: I,
+        phrases: &[&str],
+        limit: usize,
+    ) -> Result<Vec<jmdict::Entry<'_>>>
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let mut output = Vec::new();
+
+        for sequence in sequences {
+            if output.len() >= limit {
+                break;
+            }
+
+            let Some(entry) = self.sequence_to_entry(sequence)? else {
+                continue;
+            };
+
+            let mut forms = entry
+                .kanji_elements
+                .iter()
+                .map(|k| k.text)
+                .chain(entry.reading_elements.iter().map(|r| r.text));
+
+            let matches = phrases.is_empty()
+                || forms.any(|form| {
+                    phrases
+                        .iter()
+                        .any(|phrase| glob(phrase, form) || form.contains(phrase))
+                });
+
+            if matches {
+                output.push(entry);
+            }
+        }
+
+        Ok(output)
+    }
+
     fn populate_kanji<'this>(
         &'this self,
         input: &str,
@@ -1403,6 +4307,8 @@ impl Database {
 
     /// Analyze the given string, looking it up in the database and returning
     /// all prefix matching entries and their texts.
+    ///
+    /// Entries are ranked according to the configured [`AnalyzeWeights`].
     pub fn analyze<'q>(&self, q: &'q str, start: usize) -> Result<BTreeMap<Weight, &'q str>> {
         let Some(suffix) = q.get(start..) else {
             return Ok(BTreeMap::new());
@@ -1421,13 +4327,24 @@ impl Database {
                 for stored_id in values {
                     let id = self.convert_id(index, *stored_id)?;
 
-                    let key = match d.entry_at(id)? {
-                        Entry::Phrase(e) => e.weight(it.as_str(), id.source.is_inflection()),
-                        Entry::Name(e) => e.weight(it.as_str()).boost(0.5),
-                        Entry::Kanji(e) => e.weight(it.as_str()).boost(0.5),
+                    let word = it.as_str();
+
+                    let (weight, frequency) = match d.entry_at(id)? {
+                        Entry::Phrase(e) => (
+                            e.weight(
+                                word,
+                                id.source.is_inflection(),
+                                self.frequency_rank(e.sequence)?,
+                            ),
+                            analyze_rank::frequency(e.priorities()),
+                        ),
+                        Entry::Name(e) => (e.weight(word).boost(0.5), 1.0),
+                        Entry::Kanji(e) => (e.weight(word).boost(0.5), 1.0),
                     };
 
-                    match results.entry(it.as_str()) {
+                    let key = analyze_rank::rank(word, weight, frequency, &self.analyze_weights);
+
+                    match results.entry(word) {
                         hash_map::Entry::Occupied(mut e) => {
                             e.insert((*e.get()).max(key));
                         }
@@ -1441,12 +4358,307 @@ impl Database {
             it.next_back();
         }
 
-        let mut inputs = BTreeMap::new();
+        Ok(analyze_rank::order(results))
+    }
+
+    /// Split text into words.
+    ///
+    /// Unlike [`Database::analyze`], which ranks the words the text at a
+    /// single position could start with, this finds the most plausible way to
+    /// split all of it. Inflected words and how frequent words are both count,
+    /// so that pasted sentences are split into natural word boundaries.
+    ///
+    /// Only the first [`SEGMENT_MAX_TEXT`] characters are split, anything
+    /// after them is returned as a single segment which isn't a known word.
+    #[tracing::instrument(skip_all)]
+    pub fn segment<'q>(&self, q: &'q str) -> Result<Vec<Segment<'q>>> {
+        let (q, rest) = match q.char_indices().nth(SEGMENT_MAX_TEXT) {
+            Some((n, _)) => q.split_at(n),
+            None => (q, ""),
+        };
+
+        let mut edges = HashMap::<_, Vec<_>>::new();
+        // Phrases together with how frequent they are, by where they're
+        // stored, since the same phrase is matched by many of its forms.
+        let mut phrases = HashMap::new();
+
+        for (start, _) in q.char_indices() {
+            // The cheapest entry each word starting here matches.
+            let mut words = HashMap::<usize, segment::Edge<(u64, bool)>>::new();
+
+            let ends = q[start..]
+                .char_indices()
+                .map(|(n, c)| start + n + c.len_utf8())
+                .take(SEGMENT_MAX_WORD);
+
+            for end in ends {
+                let word = &q[start..end];
+
+                for (index, d) in self.indexes.iter().enumerate() {
+                    let Some(values) = d.header.lookup.get(d.data.as_buf(), word)? else {
+                        continue;
+                    };
+
+                    for stored_id in values {
+                        let id = self.convert_id(index, *stored_id)?;
+
+                        // Words only match how phrases are written and read,
+                        // not their meanings.
+                        if let Source::Phrase {
+                            index: PhraseIndex::Meaning | PhraseIndex::MeaningExact,
+                        } = id.source
+                        {
+                            continue;
+                        }
+
+                        let phrase = match phrases.entry(id.key()) {
+                            hash_map::Entry::Occupied(e) => e.into_mut(),
+                            hash_map::Entry::Vacant(e) => {
+                                // Names and kanji are left out, since they
+                                // would otherwise split up words.
+                                let phrase = match d.entry_at(id)? {
+                                    Entry::Phrase(p) => {
+                                        let frequency = analyze_rank::frequency(p.priorities());
+                                        let frequency_rank = self.frequency_rank(p.sequence)?;
+                                        Some((p, frequency, frequency_rank))
+                                    }
+                                    _ => None,
+                                };
+
+                                e.insert(phrase)
+                            }
+                        };
+
+                        let Some((e, frequency, frequency_rank)) = phrase else {
+                            continue;
+                        };
+
+                        let inflection = id.source.is_inflection();
+                        let weight = e.weight(word, inflection, *frequency_rank);
+
+                        let rank =
+                            analyze_rank::rank(word, weight, *frequency, &self.analyze_weights);
+
+                        let cost = segment::cost(rank);
+
+                        if words.get(&end).is_some_and(|edge| edge.cost <= cost) {
+                            continue;
+                        }
+
+                        words.insert(
+                            end,
+                            segment::Edge {
+                                end,
+                                cost,
+                                value: (e.sequence, inflection),
+                            },
+                        );
+                    }
+                }
+            }
+
+            if !words.is_empty() {
+                edges.insert(start, words.into_values().collect());
+            }
+        }
+
+        let mut output = Vec::new();
+
+        for (range, value) in segment::best_path(q, &edges) {
+            output.push(Segment {
+                text: &q[range.clone()],
+                start: range.start,
+                sequence: value.map(|(sequence, _)| sequence),
+                inflection: value.is_some_and(|(_, inflection)| inflection),
+            });
+        }
 
-        for (string, key) in results {
-            inputs.insert(key, string);
+        if !rest.is_empty() {
+            output.push(Segment {
+                text: rest,
+                start: q.len(),
+                sequence: None,
+                inflection: false,
+            });
         }
 
-        Ok(inputs)
+        Ok(output)
+    }
+}
+
+/// Strip trailing punctuation and a particle from a phrase, returning the
+/// remainder and what was stripped.
+///
+/// Returns `None` if there's nothing to strip or nothing would remain.
+fn strip_trailing(phrase: &str) -> Option<(&str, &str)> {
+    fn is_punctuation(c: char) -> bool {
+        c.is_whitespace()
+            || c.is_ascii_punctuation()
+            || matches!(
+                c,
+                '。' | '、' | '！' | '？' | '「' | '」' | '『' | '』' | '（' | '）' | '…' | '・'
+            )
+    }
+
+    let text = phrase.trim_end_matches(is_punctuation);
+
+    let text = TRAILING_PARTICLES
+        .iter()
+        .find_map(|particle| text.strip_suffix(particle).filter(|rest| !rest.is_empty()))
+        .unwrap_or(text);
+
+    let text = text.trim_end_matches(is_punctuation);
+
+    if text.is_empty() || text.len() == phrase.len() {
+        return None;
+    }
+
+    Some((text, &phrase[text.len()..]))
+}
+
+/// Assign a confidence between zero and one to each candidate of an analysis
+/// in the order they are iterated over, which sum up to one.
+///
+/// Candidates are scored by their weight and by how many characters they
+/// cover, since a segmentation with fewer and longer segments is more likely
+/// to be right.
+pub fn analysis_confidence(analysis: &BTreeMap<Weight, &str>) -> Vec<f32> {
+    let scores = analysis
+        .iter()
+        .map(|(weight, string)| weight.value().max(0.0) * string.chars().count() as f32)
+        .collect::<Vec<_>>();
+
+    let total = scores.iter().sum::<f32>();
+
+    if total <= 0.0 {
+        let uniform = 1.0 / scores.len().max(1) as f32;
+        return vec![uniform; scores.len()];
+    }
+
+    scores.into_iter().map(|score| score / total).collect()
+}
+
+#[test]
+fn test_glob() {
+    assert!(glob("ある", "ある"));
+    assert!(!glob("ある", "あるく"));
+    assert!(glob("ある*", "あるく"));
+    assert!(glob("*る", "ある"));
+    assert!(glob("た*る", "たべる"));
+    assert!(!glob("る*る", "る"));
+    assert!(glob("*", ""));
+}
+
+#[test]
+fn test_strip_trailing() {
+    assert_eq!(strip_trailing("本を"), Some(("本", "を")));
+    assert_eq!(strip_trailing("学校へ。"), Some(("学校", "へ。")));
+    assert_eq!(strip_trailing("日本には"), Some(("日本", "には")));
+    assert_eq!(strip_trailing("猫が！"), Some(("猫", "が！")));
+    assert_eq!(strip_trailing("本"), None);
+    assert_eq!(strip_trailing("を"), None);
+}
+
+#[test]
+fn test_analysis_confidence() {
+    let analysis = BTreeMap::from([(Weight::new(3.0), "にわ"), (Weight::new(1.0), "に")]);
+    assert_eq!(analysis_confidence(&analysis), [6.0 / 7.0, 1.0 / 7.0]);
+
+    let analysis = BTreeMap::from([(Weight::new(0.0), "に")]);
+    assert_eq!(analysis_confidence(&analysis), [1.0]);
+    assert!(analysis_confidence(&BTreeMap::new()).is_empty());
+}
+
+#[test]
+fn test_okurigana_key() {
+    assert_eq!(okurigana_key("引っ越す").as_deref(), Some("引越"));
+    assert_eq!(okurigana_key("引越す").as_deref(), Some("引越"));
+    assert_eq!(okurigana_key("お取り寄せ").as_deref(), Some("お取寄"));
+    assert_eq!(okurigana_key("引越"), None);
+    assert_eq!(okurigana_key("食べる"), None);
+    assert_eq!(okurigana_key("たべる"), None);
+}
+
+#[test]
+fn test_parse_xref() {
+    assert_eq!(
+        parse_xref("日本・にほん・1"),
+        ("日本", Some("にほん"), Some(1))
+    );
+    assert_eq!(parse_xref("日本・2"), ("日本", None, Some(2)));
+    assert_eq!(parse_xref("にほん"), ("にほん", None, None));
+}
+
+#[test]
+fn test_build_deterministic() {
+    use crate::reporter::EmptyReporter;
+
+    const JMDICT: &str = r#"<JMdict>
+<entry><ent_seq>1</ent_seq>
+<k_ele><keb>食べる</keb></k_ele>
+<r_ele><reb>たべる</reb></r_ele>
+<sense><pos>&v1;</pos><pos>&vt;</pos><xref>飲む</xref><gloss>to eat</gloss></sense>
+</entry>
+<entry><ent_seq>2</ent_seq>
+<k_ele><keb>飲む</keb></k_ele>
+<r_ele><reb>のむ</reb></r_ele>
+<sense><pos>&v5m;</pos><xref>食べる・たべる</xref><gloss>to drink</gloss></sense>
+</entry>
+<entry><ent_seq>3</ent_seq>
+<k_ele><keb>食事</keb></k_ele>
+<r_ele><reb>しょくじ</reb></r_ele>
+<sense><pos>&n;</pos><pos>&vs;</pos><ant>飲む</ant><gloss>meal</gloss><gloss xml:lang="fre">repas</gloss></sense>
+</entry>
+</JMdict>"#;
+
+    const KRADFILE: &str = "亜 : ｜ 一 口\n唖 : ｜ 一 口 亜\n食 : 人 良\n飲 : 人 食 欠\n";
+
+    const KANJIDIC2: &str = r#"<kanjidic2>
+<character><literal>食</literal>
+<misc><grade>2</grade><stroke_count>9</stroke_count><freq>328</freq></misc>
+<reading_meaning><rmgroup>
+<reading r_type="ja_on">ショク</reading><reading r_type="ja_kun">た.べる</reading><reading r_type="ja_kun">く.う</reading>
+<meaning>eat</meaning><meaning>food</meaning><meaning m_lang="fr">manger</meaning>
+</rmgroup><nanori>け</nanori></reading_meaning>
+</character>
+<character><literal>飲</literal>
+<misc><grade>3</grade><stroke_count>12</stroke_count><freq>969</freq></misc>
+<reading_meaning><rmgroup>
+<reading r_type="ja_on">イン</reading><reading r_type="ja_kun">の.む</reading>
+<meaning>drink</meaning><meaning>swallow</meaning>
+</rmgroup></reading_meaning>
+</character>
+</kanjidic2>"#;
+
+    const JMNEDICT: &str = r#"<JMnedict>
+<entry><ent_seq>10</ent_seq>
+<k_ele><keb>食野</keb></k_ele>
+<r_ele><reb>じきの</reb></r_ele>
+<trans><name_type>&surname;</name_type><trans_det>Jikino</trans_det></trans>
+</entry>
+<entry><ent_seq>11</ent_seq>
+<k_ele><keb>飲田</keb></k_ele><k_ele><keb>飯田</keb></k_ele>
+<r_ele><reb>いいだ</reb></r_ele><r_ele><reb>いんだ</reb></r_ele>
+<trans><name_type>&surname;</name_type><name_type>&place;</name_type><trans_det>Iida</trans_det></trans>
+</entry>
+</JMnedict>"#;
+
+    let inputs = [
+        Input::Jmdict(JMDICT, None),
+        Input::Kradfile(KRADFILE.as_bytes()),
+        Input::Kanjidic2(KANJIDIC2),
+        Input::Jmnedict(JMNEDICT, None),
+    ];
+
+    for input in inputs {
+        let run = || build(&EmptyReporter, &Token::default(), "test", input).unwrap();
+        let expected = run();
+
+        // Hash maps are seeded differently every time they're constructed, so
+        // building a couple of times is enough to catch them leaking into the
+        // index.
+        for _ in 0..4 {
+            assert_eq!(run().as_slice(), expected.as_slice());
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! Segmentation of text into words.
+//!
+//! Every word which starts at every position of the text forms a lattice, and
+//! the text is split along the cheapest path through it. Words are cheaper
+//! the higher they rank, and every word costs a little extra so that fewer
+//! and longer words are preferred when they rank about the same. Characters
+//! which aren't part of any known word can always be passed over one at a
+//! time, but at a cost which is higher than that of any known word.
+
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
+
+use crate::kana;
+use crate::Weight;
+
+/// The cost of every word, which favors fewer and longer words.
+const WORD_COST: f32 = 3.0;
+/// The cost of every character which isn't part of a known word.
+const UNKNOWN_COST: f32 = 10.0;
+/// The lowest rank a word is considered to have, which keeps the cost of a
+/// known word below [`UNKNOWN_COST`].
+const MIN_RANK: f32 = 0.01;
+
+/// A known word in the lattice.
+pub(super) struct Edge<T> {
+    /// The byte offset where the word ends.
+    pub(super) end: usize,
+    /// The cost of the word, see [`cost`].
+    pub(super) cost: f32,
+    pub(super) value: T,
+}
+
+/// The cost of a word with the given rank, as returned by
+/// [`super::analyze_rank::rank`].
+pub(super) fn cost(rank: Weight) -> f32 {
+    WORD_COST - rank.value().max(MIN_RANK).ln()
+}
+
+/// Find the cheapest way to split `text` into words, where `edges` holds the
+/// known words starting at each byte offset.
+///
+/// Runs of characters which aren't part of any known word are returned
+/// without a value. Unknown katakana and alphanumeric characters are kept
+/// together, since they are most likely loan words, names or numbers.
+pub(super) fn best_path<T>(
+    text: &str,
+    edges: &HashMap<usize, Vec<Edge<T>>>,
+) -> Vec<(Range<usize>, Option<T>)>
+where
+    T: Copy,
+{
+    // The cost of the cheapest path to each byte offset, together with where
+    // the last word on it starts and its value.
+    let mut best = vec![None::<(f32, usize, Option<T>)>; text.len() + 1];
+    best[0] = Some((0.0, 0, None));
+
+    for (start, c) in text.char_indices() {
+        let Some((cost, _, _)) = best[start] else {
+            continue;
+        };
+
+        let known = edges
+            .get(&start)
+            .into_iter()
+            .flatten()
+            .map(|edge| (edge.end, edge.cost, Some(edge.value)));
+
+        let unknown = iter::once((start + c.len_utf8(), UNKNOWN_COST, None));
+
+        for (end, edge_cost, value) in known.chain(unknown) {
+            let Some(current) = best.get_mut(end) else {
+                continue;
+            };
+
+            let total = cost + edge_cost;
+
+            if !current.is_some_and(|(cost, _, _)| cost <= total) {
+                *current = Some((total, start, value));
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut end = text.len();
+
+    while end > 0 {
+        let Some((_, start, value)) = best[end] else {
+            break;
+        };
+
+        path.push((start..end, value));
+        end = start;
+    }
+
+    path.reverse();
+
+    let mut output = Vec::<(Range<usize>, Option<T>)>::with_capacity(path.len());
+
+    for (range, value) in path {
+        if let Some((last, None)) = output.last_mut() {
+            if value.is_none() && keep_together(&text[last.clone()], &text[range.clone()]) {
+                last.end = range.end;
+                continue;
+            }
+        }
+
+        output.push((range, value));
+    }
+
+    output
+}
+
+/// Test if two adjacent runs of unknown characters belong together.
+fn keep_together(a: &str, b: &str) -> bool {
+    let (Some(a), Some(b)) = (a.chars().next_back(), b.chars().next()) else {
+        return false;
+    };
+
+    let katakana = |c| kana::is_katakana(c) || c == 'ー';
+    let alphanumeric = |c: char| c.is_alphanumeric() && !kana::is_japanese(c);
+    katakana(a) && katakana(b) || alphanumeric(a) && alphanumeric(b)
+}
+
+#[cfg(test)]
+fn segment(text: &str, words: &[(&'static str, f32)]) -> Vec<(String, Option<&'static str>)> {
+    let mut edges = HashMap::<_, Vec<_>>::new();
+
+    for (start, _) in text.char_indices() {
+        for &(word, cost) in words {
+            if text[start..].starts_with(word) {
+                edges.entry(start).or_default().push(Edge {
+                    end: start + word.len(),
+                    cost,
+                    value: word,
+                });
+            }
+        }
+    }
+
+    best_path(text, &edges)
+        .into_iter()
+        .map(|(range, value)| (text[range].to_owned(), value))
+        .collect()
+}
+
+#[test]
+fn test_best_path() {
+    let words = [
+        ("今", 2.0),
+        ("今日", 1.0),
+        ("今日は", 4.0),
+        ("日", 2.0),
+        ("は", 1.5),
+        ("晴れ", 1.0),
+    ];
+
+    let path = segment("今日は晴れ", &words);
+
+    assert_eq!(
+        path,
+        [
+            ("今日".to_owned(), Some("今日")),
+            ("は".to_owned(), Some("は")),
+            ("晴れ".to_owned(), Some("晴れ")),
+        ]
+    );
+}
+
+#[test]
+fn test_best_path_unknown() {
+    let words = [("の", 1.0), ("本", 1.0), ("は", 1.0)];
+
+    let path = segment("JPVの本はテレビ", &words);
+
+    assert_eq!(
+        path,
+        [
+            ("JPV".to_owned(), None),
+            ("の".to_owned(), Some("の")),
+            ("本".to_owned(), Some("本")),
+            ("は".to_owned(), Some("は")),
+            ("テレビ".to_owned(), None),
+        ]
+    );
+}
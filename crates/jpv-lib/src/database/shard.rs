@@ -0,0 +1,206 @@
+//! Indexes split into shards which can be loaded independently.
+//!
+//! Phrases and names are split by the row of the kana their first reading
+//! starts with, like `ka` for `かわ` and `ガス`. Every other kind of index is
+//! only split by its type, since each type is already built into an index of
+//! its own.
+//!
+//! The shards which were built are listed in a [`Manifest`], which is used
+//! when opening a subset of them to tell which could hold more results.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::IndexFormat;
+
+/// The name of the file the manifest is stored in, next to the shards.
+pub const MANIFEST: &str = "manifest.json";
+
+/// The shard of entries whose reading doesn't start with kana.
+pub const OTHER: &str = "other";
+
+/// The shards phrases and names are split into.
+pub const SHARDS: [&str; 11] = [
+    "a", "ka", "sa", "ta", "na", "ha", "ma", "ya", "ra", "wa", OTHER,
+];
+
+/// Test if indexes of the given format are split by kana.
+pub fn is_sharded(format: IndexFormat) -> bool {
+    matches!(format, IndexFormat::Jmdict | IndexFormat::Jmnedict)
+}
+
+/// Get the shard of a reading by the row of the kana it starts with, or
+/// `None` if it doesn't start with kana.
+///
+/// Katakana belong to the same row as hiragana, and voiced kana to the row of
+/// their unvoiced form, so both `が` and `ガ` are in `ka`.
+pub fn of(reading: &str) -> Option<&'static str> {
+    let c = match reading.chars().next()? {
+        c @ 'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60)?,
+        c => c,
+    };
+
+    let shard = match c {
+        'ぁ'..='お' | 'ゔ' => "a",
+        'か'..='ご' | 'ゕ' | 'ゖ' => "ka",
+        'さ'..='ぞ' => "sa",
+        'た'..='ど' => "ta",
+        'な'..='の' => "na",
+        'は'..='ぽ' => "ha",
+        'ま'..='も' => "ma",
+        'ゃ'..='よ' => "ya",
+        'ら'..='ろ' => "ra",
+        'ゎ'..='ん' => "wa",
+        _ => return None,
+    };
+
+    Some(shard)
+}
+
+/// Test if a shard could hold entries matching the given phrase.
+///
+/// Phrases which don't start with kana, like kanji, romaji or English, could
+/// match entries in any shard.
+pub fn could_match(shard: Option<&str>, phrase: &str) -> bool {
+    let Some(shard) = shard else {
+        return true;
+    };
+
+    match of(phrase) {
+        Some(expected) => expected == shard,
+        None => true,
+    }
+}
+
+/// A listing of every shard built from a set of indexes.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    /// The database version the shards were built with.
+    pub version: u32,
+    pub shards: Vec<ManifestShard>,
+}
+
+impl Manifest {
+    /// Load the manifest from the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| path.display().to_string())?;
+        serde_json::from_slice(&data).with_context(|| path.display().to_string())
+    }
+}
+
+/// A single shard in a [`Manifest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ManifestShard {
+    /// The name of the index the shard belongs to.
+    pub index: String,
+    /// The shard, or `None` if the index isn't split by kana.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<String>,
+    /// The path of the shard, relative to the manifest.
+    pub path: String,
+    /// The size of the shard in bytes.
+    pub size: u64,
+}
+
+impl ManifestShard {
+    /// Test if the shard is selected by the given names of shards, which
+    /// always selects indexes which aren't split by kana.
+    pub fn is_selected(&self, selected: &[String]) -> bool {
+        match &self.shard {
+            Some(shard) => selected.is_empty() || selected.contains(shard),
+            None => true,
+        }
+    }
+}
+
+#[test]
+fn test_of() {
+    assert_eq!(of("かわ"), Some("ka"));
+    assert_eq!(of("ガス"), Some("ka"));
+    assert_eq!(of("っと"), Some("ta"));
+    assert_eq!(of("ぱん"), Some("ha"));
+    assert_eq!(of("ヴァイオリン"), Some("a"));
+    assert_eq!(of("ん"), Some("wa"));
+    assert_eq!(of("ーる"), None);
+    assert_eq!(of("食べる"), None);
+    assert_eq!(of("taberu"), None);
+    assert_eq!(of(""), None);
+}
+
+#[test]
+fn test_could_match() {
+    assert!(could_match(None, "かわ"));
+    assert!(could_match(Some("ka"), "かわ"));
+    assert!(!could_match(Some("sa"), "かわ"));
+    assert!(could_match(Some("sa"), "川"));
+    assert!(could_match(Some("sa"), "river"));
+}
+
+#[test]
+fn test_load_shards() -> Result<()> {
+    use crate::config::Config;
+    use crate::data;
+    use crate::database::{build_shard, Database, Input};
+    use crate::reporter::EmptyReporter;
+    use crate::token::Token;
+
+    const JMDICT: &str = r#"<JMdict>
+<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>猿</keb></k_ele><r_ele><reb>さる</reb></r_ele><sense><gloss>monkey</gloss></sense></entry>
+</JMdict>"#;
+
+    let root = std::env::temp_dir().join(format!("jpv-test-shards-{}", std::process::id()));
+    fs::create_dir_all(&root)?;
+
+    let mut manifest = Manifest {
+        version: crate::DATABASE_VERSION,
+        shards: Vec::new(),
+    };
+
+    for shard in SHARDS {
+        let buf = build_shard(
+            &EmptyReporter,
+            &Token::default(),
+            "jmdict",
+            Input::Jmdict(JMDICT, None),
+            shard,
+        )?;
+
+        let path = format!("jmdict.{shard}.index");
+        fs::write(root.join(&path), buf.as_slice())?;
+
+        manifest.shards.push(ManifestShard {
+            index: String::from("jmdict"),
+            shard: Some(String::from(shard)),
+            path,
+            size: buf.len() as u64,
+        });
+    }
+
+    fs::write(root.join(MANIFEST), serde_json::to_vec(&manifest)?)?;
+
+    let (manifest, indexes) = data::open_shards(&root.join(MANIFEST), &[String::from("ka")])?;
+    assert_eq!(indexes.len(), 1);
+
+    let db = Database::open_partial(indexes, &Config::default(), &manifest)?;
+
+    let search = db.search("かわ")?;
+    assert_eq!(search.phrases.len(), 1);
+    assert!(search.missing_shards.is_empty());
+
+    let search = db.search("さる")?;
+    assert!(search.phrases.is_empty());
+    assert_eq!(search.missing_shards, ["jmdict.sa.index"]);
+
+    let search = db.search("猿")?;
+    assert!(search.phrases.is_empty());
+    assert_eq!(search.missing_shards.len(), SHARDS.len() - 1);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -3,7 +3,7 @@ use musli_zerocopy::{slice, swiss, trie, Ref, ZeroCopy};
 
 use crate::PartOfSpeech;
 
-use super::{InflectionData, KanjiIndex, NameIndex, PhraseIndex};
+use super::{InflectionData, KanjiIndex, LinkKind, NameIndex, PhraseIndex};
 
 pub struct CompactTrie;
 
@@ -31,19 +31,64 @@ pub(super) struct GlobalHeader {
 #[repr(C)]
 pub(super) struct IndexHeader {
     pub(super) name: Ref<str>,
+    /// The shard the index holds, which is empty if it holds everything, see
+    /// [`super::shard`].
+    pub(super) shard: Ref<str>,
+    /// Languages of the glosses in the index, like `eng`.
+    pub(super) languages: Ref<[Ref<str>]>,
     pub(super) lookup: trie::TrieRef<Id, CompactTrie>,
     /// Phrases by position.
     pub(super) by_pos: swiss::MapRef<PartOfSpeech, Ref<[PhrasePos]>>,
     /// Kanjis by literal.
     pub(super) by_kanji_literal: swiss::MapRef<Ref<str>, u32>,
+    /// Kanji literals by their stroke count.
+    pub(super) kanji_by_strokes: swiss::MapRef<u8, Ref<[Ref<str>]>>,
+    /// Kanji literals by the grade they are taught in.
+    pub(super) kanji_by_grade: swiss::MapRef<u8, Ref<[Ref<str>]>>,
     pub(super) radicals: swiss::MapRef<Ref<str>, u32>,
     pub(super) radicals_to_kanji: swiss::MapRef<Ref<str>, Ref<[u32]>>,
+    /// RADKFILE entries by radical.
+    pub(super) by_radical: swiss::MapRef<Ref<str>, u32>,
     pub(super) by_sequence: swiss::MapRef<u32, PhrasePos>,
+    /// Links from the senses of a phrase to other phrases, by sequence.
+    pub(super) links: swiss::MapRef<u32, Ref<[Link]>>,
+    /// Sequences of phrases written with a kanji, by the kanji as a `char`.
+    pub(super) words_by_kanji: swiss::MapRef<u32, Ref<[u32]>>,
+    /// How common names are by their offset, from an auxiliary dataset of
+    /// name frequencies.
+    pub(super) name_frequencies: swiss::MapRef<u32, u32>,
+    /// How common phrases are by their offset, see
+    /// [`crate::priority::commonness`]. Phrases which aren't marked as common
+    /// are missing.
+    pub(super) commonness: swiss::MapRef<u32, u8>,
+    /// Pitch accents of words by their form and reading, see
+    /// [`super::form_key`].
+    pub(super) accents: swiss::MapRef<Ref<str>, Ref<[u8]>>,
+    /// Sequences of phrases by each form and reading they have, see
+    /// [`super::form_key`].
+    pub(super) phrases_by_form: swiss::MapRef<Ref<str>, Ref<[u32]>>,
+    /// Sequences of names by their form and reading, see
+    /// [`super::form_key`].
+    pub(super) names_by_form: swiss::MapRef<Ref<str>, Ref<[u32]>>,
+    /// Ranks of phrases in a word frequency list by their sequence number.
+    pub(super) phrase_frequencies: swiss::MapRef<u32, u32>,
+    /// Example sentences by the words they list, see
+    /// [`crate::tatoeba::Sentence::words`].
+    pub(super) sentences_by_word: swiss::MapRef<Ref<str>, Ref<[u32]>>,
+    /// Example sentences by the Japanese characters they contain, as `char`.
+    pub(super) sentences_by_char: swiss::MapRef<u32, Ref<[u32]>>,
     pub(super) inflections: Ref<[InflectionData]>,
     /// The offset of all phrases stored in the index.
     pub(super) phrases: Ref<[u32]>,
     /// The offset of all kanji stored in the index.
     pub(super) kanji: Ref<[u32]>,
+    /// The offset of all names stored in the index.
+    pub(super) names: Ref<[u32]>,
+    /// The offset of all RADKFILE entries stored in the index, in the order
+    /// they were defined.
+    pub(super) radkfile: Ref<[u32]>,
+    /// The offset of all example sentences stored in the index.
+    pub(super) sentences: Ref<[u32]>,
 }
 
 /// Extra information about an index.
@@ -68,6 +113,23 @@ pub(super) struct PhrasePos {
     pub(super) reading: PhraseIndex,
 }
 
+/// A resolved reference from a sense to another phrase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, ZeroCopy)]
+#[repr(C)]
+pub(super) struct Link {
+    /// The sequence of the referenced phrase.
+    pub(super) sequence: u32,
+    /// The index of the sense the reference is made from.
+    pub(super) sense: u16,
+    /// The index of the reference among the cross-references or antonyms of
+    /// the sense.
+    pub(super) index: u16,
+    /// The sense of the referenced phrase counting from 1, or 0 if it refers
+    /// to the whole phrase.
+    pub(super) target_sense: u16,
+    pub(super) kind: LinkKind,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, ZeroCopy)]
 #[repr(C)]
 pub(super) struct Id {
@@ -0,0 +1,708 @@
+//! Tests which build and search whole databases.
+
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+
+use crate::config::{Config, IndexFormat};
+use crate::data;
+use crate::reporter::EmptyReporter;
+use crate::token::Token;
+
+use super::{build, Database, Input, Location, WordSort};
+
+/// Build a database with an enabled index for each input, named after the
+/// index it is built as.
+pub(super) fn open(inputs: &[(&str, Input<'_>)]) -> Result<Database> {
+    open_with(inputs, Config::default())
+}
+
+/// Build a database like [`open`] with the given configuration.
+pub(super) fn open_with(inputs: &[(&str, Input<'_>)], mut config: Config) -> Result<Database> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let id = NEXT.fetch_add(1, Ordering::SeqCst);
+    let root = std::env::temp_dir().join(format!("jpv-test-database-{}-{id}", process::id()));
+    fs::create_dir_all(&root)?;
+
+    let mut indexes = Vec::new();
+
+    for &(name, input) in inputs {
+        if let Ok(format) = name.parse::<IndexFormat>() {
+            config
+                .indexes
+                .entry(name.to_owned())
+                .or_insert_with(|| format.default_config(true));
+        }
+
+        let buf = build(&EmptyReporter, &Token::default(), name, input)?;
+        let path = root.join(format!("{name}.index"));
+        fs::write(&path, buf.as_slice())?;
+        let data = data::open(&path)?;
+        indexes.push((data, Location::Path(path.into())));
+    }
+
+    let db = Database::open(indexes, &config)?;
+    // Indexes might still be mapped, which on some platforms prevents them
+    // from being removed.
+    let _ = fs::remove_dir_all(&root);
+    Ok(db)
+}
+
+/// Wrap entries in a JMdict document.
+pub(super) fn jmdict(entries: &str) -> String {
+    format!("<JMdict>{entries}</JMdict>")
+}
+
+#[test]
+fn test_word_of_day() -> Result<()> {
+    let input = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>鶫</keb></k_ele><r_ele><reb>つぐみ</reb></r_ele><sense><gloss>thrush</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>川</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>かわ</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><k_ele><keb>鵯</keb></k_ele><r_ele><reb>ひよどり</reb></r_ele><sense><gloss>bulbul</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&input, None))])?;
+
+    // Every day picks the only common word, and picking it again gives the
+    // same one.
+    for day in [0, 1, 2, 19_000, u64::MAX] {
+        let first = db.word_of_day(day)?.map(|entry| entry.sequence);
+        let second = db.word_of_day(day)?.map(|entry| entry.sequence);
+        assert_eq!(first, Some(2), "day {day}");
+        assert_eq!(first, second, "day {day}");
+    }
+
+    let input = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>鶫</keb></k_ele><r_ele><reb>つぐみ</reb></r_ele><sense><gloss>thrush</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&input, None))])?;
+    assert!(db.word_of_day(0)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_wildcard_limit() -> Result<()> {
+    use super::WILDCARD_LIMIT;
+
+    const DIGITS: [char; 10] = ['あ', 'い', 'う', 'え', 'お', 'か', 'き', 'く', 'け', 'こ'];
+
+    let mut entries = String::new();
+
+    for n in 0..=WILDCARD_LIMIT {
+        let reading = n
+            .to_string()
+            .bytes()
+            .map(|b| DIGITS[usize::from(b - b'0')])
+            .collect::<String>();
+
+        entries.push_str(&format!(
+            "<entry><ent_seq>{n}</ent_seq><r_ele><reb>さ{reading}</reb></r_ele><sense><gloss>test</gloss></sense></entry>"
+        ));
+    }
+
+    let input = jmdict(&entries);
+    let db = open(&[("jmdict", Input::Jmdict(&input, None))])?;
+
+    // Lookups which aren't made by users aren't limited.
+    assert_eq!(db.lookup("さ*")?.len(), WILDCARD_LIMIT + 1);
+
+    let lookup = db.lookup_from("さ*", 0)?;
+    assert_eq!(lookup.ids.len(), WILDCARD_LIMIT);
+    assert_eq!(lookup.continuation, Some(WILDCARD_LIMIT));
+
+    let lookup = db.lookup_from("さ*", WILDCARD_LIMIT)?;
+    assert_eq!(lookup.ids.len(), 1);
+    assert_eq!(lookup.continuation, None);
+
+    let search = db.search("さ*")?;
+    assert_eq!(search.continuation, Some(WILDCARD_LIMIT));
+
+    let search = db.search_from("さ*", WILDCARD_LIMIT)?;
+    assert_eq!(search.phrases.len(), 1);
+    assert_eq!(search.continuation, None);
+    Ok(())
+}
+
+#[test]
+fn test_linked_names() -> Result<()> {
+    let phrases = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>桜</keb></k_ele><r_ele><reb>さくら</reb></r_ele><sense><misc>&uk;</misc><gloss>cherry blossom</gloss></sense></entry>"#,
+    );
+
+    let names = r#"<JMnedict>
+<entry><ent_seq>10</ent_seq><r_ele><reb>かわ</reb></r_ele><trans><trans_det>Kawa</trans_det></trans></entry>
+<entry><ent_seq>11</ent_seq><k_ele><keb>河</keb></k_ele><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><trans><trans_det>Kawa</trans_det></trans></entry>
+<entry><ent_seq>12</ent_seq><r_ele><reb>さくら</reb></r_ele><trans><trans_det>Sakura</trans_det></trans></entry>
+</JMnedict>"#;
+
+    let db = open(&[
+        ("jmdict", Input::Jmdict(&phrases, None)),
+        ("jmnedict", Input::Jmnedict(names, None)),
+    ])?;
+
+    let search = db.search("川")?;
+    let (_, river) = search
+        .phrases
+        .iter()
+        .find(|(_, p)| p.sequence == 1)
+        .unwrap();
+
+    // A name written in kana isn't linked to a phrase written in kanji only
+    // because they sound the same, but names are linked by any of their forms.
+    assert_eq!(db.linked_names(river)?, [11]);
+
+    let search = db.search("さくら")?;
+    let (_, cherry) = search
+        .phrases
+        .iter()
+        .find(|(_, p)| p.sequence == 2)
+        .unwrap();
+    assert_eq!(db.linked_names(cherry)?, [12]);
+
+    for (_, name) in &search.names {
+        assert_eq!(db.linked_phrases(name)?, [2], "{}", name.sequence);
+    }
+
+    let search = db.search("河")?;
+    let (_, name) = search.names.iter().find(|(_, n)| n.sequence == 11).unwrap();
+    assert_eq!(db.linked_phrases(name)?, [1]);
+    Ok(())
+}
+
+#[test]
+fn test_segment() -> Result<()> {
+    use super::SEGMENT_MAX_TEXT;
+
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>食べる</keb></k_ele><r_ele><reb>たべる</reb></r_ele><sense><pos>&v1;</pos><gloss>to eat</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><r_ele><reb>わに</reb></r_ele><sense><gloss>crocodile</gloss></sense></entry>
+<entry><ent_seq>4</ent_seq><r_ele><reb>か</reb></r_ele><sense><gloss>mosquito</gloss></sense></entry>
+<entry><ent_seq>5</ent_seq><r_ele><reb>に</reb></r_ele><sense><gloss>two</gloss></sense></entry>"#,
+    );
+
+    let segments = |db: &super::Database, text: &str| -> Result<Vec<(String, Option<u64>, bool)>> {
+        Ok(db
+            .segment(text)?
+            .into_iter()
+            .map(|s| (s.text.to_owned(), s.sequence, s.inflection))
+            .collect())
+    };
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    // Inflected words are kept whole.
+    assert_eq!(
+        segments(&db, "食べた")?,
+        [("食べた".to_owned(), Some(1), true)]
+    );
+
+    // Where the text is split depends on which of the words is more
+    // frequent.
+    for (frequencies, expected) in [
+        ("かわ\n", [("かわ", 2), ("に", 5)]),
+        ("わに\n", [("か", 4), ("わに", 3)]),
+    ] {
+        let db = open(&[("jmdict", Input::Jmdict(&entries, Some(frequencies)))])?;
+
+        let expected = expected.map(|(text, sequence)| (text.to_owned(), Some(sequence), false));
+        assert_eq!(segments(&db, "かわに")?, expected, "{frequencies:?}");
+    }
+
+    // Text which is too long is only split up to a point.
+    let text = "か".repeat(SEGMENT_MAX_TEXT + 2);
+    let output = db.segment(&text)?;
+    let last = output.last().unwrap();
+    assert_eq!(last.text, "かか");
+    assert_eq!(last.start, SEGMENT_MAX_TEXT * 'か'.len_utf8());
+    assert_eq!(last.sequence, None);
+    Ok(())
+}
+
+#[test]
+fn test_frequency_rank() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>端</keb></k_ele><r_ele><reb>はし</reb></r_ele><sense><gloss>edge</gloss></sense><sense><gloss>end</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>箸</keb></k_ele><r_ele><reb>はし</reb></r_ele><sense><gloss>chopsticks</gloss></sense></entry>"#,
+    );
+
+    let order = |db: &super::Database| -> Result<Vec<u64>> {
+        let search = db.search("はし")?;
+        Ok(search.phrases.iter().map(|(_, p)| p.sequence).collect())
+    };
+
+    // Without a frequency list the homograph with more senses comes first.
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+    assert_eq!(order(&db)?, [1, 2]);
+    assert_eq!(db.frequency_rank(2)?, None);
+
+    // Ranks are resolved by how phrases are written, and not by readings
+    // which are shared by both.
+    let frequencies = "はし\n箸\n";
+    let db = open(&[("jmdict", Input::Jmdict(&entries, Some(frequencies)))])?;
+    assert_eq!(db.frequency_rank(1)?, None);
+    assert_eq!(db.frequency_rank(2)?, Some(2));
+    assert_eq!(order(&db)?, [2, 1]);
+    Ok(())
+}
+
+#[test]
+fn test_minimal_pairs() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><r_ele><reb>バス</reb></r_ele><sense><gloss>bus</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><r_ele><reb>パス</reb></r_ele><sense><gloss>pass</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><r_ele><reb>ボール</reb></r_ele><sense><gloss>ball</gloss></sense></entry>
+<entry><ent_seq>4</ent_seq><k_ele><keb>蓮</keb></k_ele><r_ele><reb>はす</reb></r_ele><sense><gloss>lotus</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    let pairs = |reading: &str| -> Result<Vec<(String, u64)>> {
+        let mut pairs = db
+            .minimal_pairs(reading, 10)?
+            .into_iter()
+            .map(|(_, text, entry)| (text.to_owned(), entry.sequence))
+            .collect::<Vec<_>>();
+        pairs.sort();
+        Ok(pairs)
+    };
+
+    // Readings in katakana are compared to variants in hiragana.
+    assert_eq!(
+        pairs("ばす")?,
+        [(String::from("はす"), 4), (String::from("パス"), 2)]
+    );
+    assert_eq!(
+        pairs("パス")?,
+        [(String::from("はす"), 4), (String::from("バス"), 1)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_full_width_wildcard() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>桜</keb></k_ele><r_ele><reb>さくら</reb></r_ele><sense><gloss>cherry blossom</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>作文</keb></k_ele><r_ele><reb>さくぶん</reb></r_ele><sense><gloss>composition</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    let sequences = |q: &str| -> Result<Vec<u64>> {
+        let search = db.search(q)?;
+        assert_eq!(search.fallback, None, "{q}");
+        let mut sequences = search
+            .phrases
+            .iter()
+            .map(|(_, p)| p.sequence)
+            .collect::<Vec<_>>();
+        sequences.sort();
+        Ok(sequences)
+    };
+
+    // A full-width `＊` is a wildcard just like `*`.
+    assert_eq!(sequences("さく＊")?, [1, 2]);
+    assert_eq!(sequences("さく*")?, [1, 2]);
+    assert_eq!(sequences("＊ら")?, [1]);
+    assert_eq!(db.lookup_from("さく＊", 0)?.ids.len(), 2);
+
+    // Patterns which found nothing aren't modified to find something.
+    assert!(sequences("ないよ＊")?.is_empty());
+    assert!(db.lookup_fallback("ないよ＊")?.is_none());
+    assert!(db.lookup_fallback("さくら＊")?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_priority_boost() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>端</keb><ke_pri>ichi1</ke_pri><ke_pri>nf40</ke_pri></k_ele><r_ele><reb>はし</reb></r_ele><sense><gloss>edge</gloss></sense><sense><gloss>end</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>箸</keb><ke_pri>nf01</ke_pri></k_ele><r_ele><reb>はし</reb></r_ele><sense><gloss>chopsticks</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    let order = |q: &str| -> Result<Vec<u64>> {
+        let search = db.search(q)?;
+        Ok(search.phrases.iter().map(|(_, p)| p.sequence).collect())
+    };
+
+    assert_eq!(order("はし")?, [1, 2]);
+
+    // Words rank by the priorities which are filtered by.
+    assert_eq!(order("はし #nf")?, [2, 1]);
+    assert_eq!(order("はし #ichi")?, [1]);
+    Ok(())
+}
+
+#[test]
+fn test_exact_meaning() -> Result<()> {
+    use super::MAX_MEANING_PHRASE;
+
+    let long = "a".repeat(MAX_MEANING_PHRASE + 1);
+
+    let entries = jmdict(&format!(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>猫舌</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>ねこじた</reb></r_ele><sense><gloss>cat tongue</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><sense><gloss>cat</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><r_ele><reb>ながい</reb></r_ele><sense><gloss>{long}</gloss></sense></entry>"#
+    ));
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    // Whole meanings rank above meanings starting with the same word, even
+    // if those are more common.
+    let search = db.search("cat")?;
+    let order = search
+        .phrases
+        .iter()
+        .map(|(_, p)| p.sequence)
+        .collect::<Vec<_>>();
+    assert_eq!(order, [2, 1]);
+
+    // Meanings which are too long aren't indexed.
+    assert!(db.search(&long)?.phrases.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_grammar_hints() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>例えば</keb></k_ele><r_ele><reb>たとえば</reb></r_ele><sense><pos>&adv;</pos><gloss>for example</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>書く</keb></k_ele><r_ele><reb>かく</reb></r_ele><sense><pos>&v5k;</pos><gloss>to write</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    let hints = |text: &str| -> Result<Vec<&'static str>> {
+        let segments = crate::gloss::segments(&db, text)?;
+        Ok(crate::grammar::detect(&segments)
+            .into_iter()
+            .map(|hint| hint.pattern.id)
+            .collect())
+    };
+
+    assert_eq!(hints("書けば")?, ["ba"]);
+    assert!(hints("例えば")?.is_empty());
+    assert!(hints("たとえば")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_warmup() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><sense><gloss>cat</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&entries, None))])?;
+
+    // Every key of the lookup trie is walked.
+    let keys = db.warmup(&Token::default());
+    assert!(keys >= 3, "{keys}");
+
+    // Nothing is walked once shutting down.
+    let shutdown = Token::default();
+    shutdown.set();
+    assert_eq!(db.warmup(&shutdown), 0);
+    Ok(())
+}
+
+#[test]
+fn test_search_kanji() -> Result<()> {
+    let kanjidic2 = r#"<kanjidic2>
+<character><literal>亜</literal><misc><stroke_count>7</stroke_count></misc></character>
+<character><literal>唖</literal><misc><stroke_count>10</stroke_count></misc></character>
+<character><literal>花</literal><misc><grade>1</grade><stroke_count>7</stroke_count></misc></character>
+</kanjidic2>"#;
+
+    let radkfile = "$ 一 1\n亜唖\n$ 口 3\n唖\n$ 化 4\n花\n";
+
+    let db = open(&[
+        ("kanjidic2", Input::Kanjidic2(kanjidic2)),
+        ("radkfile", Input::Radkfile(radkfile.as_bytes())),
+    ])?;
+
+    let search = |radicals: &[&'static str], strokes, max_grade| {
+        let constraints = super::KanjiConstraints {
+            radicals: radicals.to_vec(),
+            strokes,
+            max_grade,
+        };
+
+        db.search_kanji(&constraints)
+    };
+
+    assert!(search(&[], None, None)?.is_empty());
+    assert_eq!(search(&["一"], None, None)?, ["亜", "唖"]);
+    assert_eq!(search(&["一", "口"], None, None)?, ["唖"]);
+    assert_eq!(search(&["一"], Some(7..=7), None)?, ["亜"]);
+    assert_eq!(search(&[], Some(7..=7), Some(1))?, ["花"]);
+    assert!(search(&["口"], Some(7..=7), None)?.is_empty());
+
+    // Without RADKFILE, radicals are looked up through KRADFILE.
+    let kradfile = "亜 : 一\n唖 : 一 口\n";
+    let db = open(&[("kradfile", Input::Kradfile(kradfile.as_bytes()))])?;
+
+    let constraints = super::KanjiConstraints {
+        radicals: vec!["口"],
+        ..Default::default()
+    };
+
+    assert_eq!(db.search_kanji(&constraints)?, ["唖"]);
+    Ok(())
+}
+
+#[test]
+fn test_sheet() -> Result<()> {
+    let entries = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>花火</keb></k_ele><r_ele><reb>はなび</reb></r_ele><sense><gloss>fireworks</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>花</keb></k_ele><r_ele><reb>はな</reb></r_ele><sense><gloss>flower</gloss><gloss>blossom</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><r_ele><reb>カメラ</reb></r_ele><sense><gloss>camera</gloss></sense></entry>"#,
+    );
+
+    let kanjidic2 = r#"<kanjidic2>
+<character><literal>花</literal><reading_meaning><rmgroup><reading r_type="ja_on">カ</reading><reading r_type="ja_kun">はな</reading><meaning>flower</meaning></rmgroup></reading_meaning></character>
+<character><literal>火</literal><reading_meaning><rmgroup><reading r_type="ja_on">カ</reading><reading r_type="ja_kun">ひ</reading><meaning>fire</meaning><meaning m_lang="fr">feu</meaning></rmgroup></reading_meaning></character>
+</kanjidic2>"#;
+
+    let db = open(&[
+        ("jmdict", Input::Jmdict(&entries, None)),
+        ("kanjidic2", Input::Kanjidic2(kanjidic2)),
+    ])?;
+
+    let mut phrases = Vec::new();
+
+    for sequence in [1, 2, 3] {
+        phrases.extend(db.sequence_to_entry(sequence)?);
+    }
+
+    let sheet = crate::sheet::Sheet::new(&db, "Words", &phrases)?;
+
+    let words = sheet
+        .words
+        .iter()
+        .map(|w| (w.text.as_str(), w.reading.as_str(), w.glosses.join("; ")))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        words,
+        [
+            ("花火", "はなび", String::from("fireworks")),
+            ("花", "はな", String::from("flower; blossom")),
+            ("カメラ", "カメラ", String::from("camera")),
+        ]
+    );
+
+    // Every kanji is listed once, in the order it first appears.
+    let kanji = sheet
+        .kanji
+        .iter()
+        .map(|k| {
+            (
+                k.literal.as_str(),
+                k.kunyomi.join("、"),
+                k.meanings.join("; "),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        kanji,
+        [
+            ("花", String::from("はな"), String::from("flower")),
+            ("火", String::from("ひ"), String::from("fire")),
+        ]
+    );
+
+    let html = crate::sheet::html(&sheet).to_string();
+    assert!(html.contains("<h1>Words</h1>"));
+    assert!(html.contains("<ruby>花火<rt>はなび</rt></ruby>"));
+    assert!(html.contains("<td class=\"literal\">火</td>"));
+    Ok(())
+}
+
+#[test]
+fn test_example_sentences() -> Result<()> {
+    let corpus = "A: 寿司を食べるのが好きだ。\tI like eating sushi.#ID=1_1
+B: 寿司 を 食べる の が 好き だ
+A: 魚を食べる。\tI eat fish.#ID=2_2
+B: 魚 を 食べる
+A: 明日は雨だ。\tIt will rain tomorrow.#ID=3_3
+B: 明日 は 雨 だ
+A: 食べる?\tWill you eat?#ID=4_4
+B: 食う
+";
+
+    let db = open(&[("tatoeba", Input::Tatoeba(corpus))])?;
+
+    let found = db.example_sentences("食べる", 10)?;
+    let sentences = found
+        .sentences
+        .iter()
+        .map(|s| s.japanese)
+        .collect::<Vec<_>>();
+
+    // Sentences listing the word come first, shortest first within each group.
+    assert_eq!(
+        sentences,
+        ["魚を食べる。", "寿司を食べるのが好きだ。", "食べる?"]
+    );
+    assert!(!found.truncated);
+
+    let found = db.example_sentences("食べる", 1)?;
+    assert_eq!(found.sentences.len(), 1);
+    assert!(db.example_sentences("  ", 10)?.sentences.is_empty());
+
+    // A character which is too common to look through every sentence of.
+    let mut corpus = String::new();
+
+    for n in 0..=super::EXAMPLE_SENTENCE_SCAN_LIMIT {
+        corpus.push_str(&format!("{n}\t猫です。\t{n}\tIt's a cat.\n"));
+    }
+
+    let db = open(&[("tatoeba", Input::Tatoeba(&corpus))])?;
+    let found = db.example_sentences("猫", 10)?;
+    assert_eq!(found.sentences.len(), 10);
+    assert!(found.truncated);
+    Ok(())
+}
+
+#[test]
+fn test_common() -> Result<()> {
+    let phrases = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>かわ</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>皮</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>skin</gloss></sense></entry>"#,
+    );
+
+    let names = r#"<JMnedict>
+<entry><ent_seq>10</ent_seq><k_ele><keb>河</keb></k_ele><r_ele><reb>かわ</reb></r_ele><trans><trans_det>Kawa</trans_det></trans></entry>
+</JMnedict>"#;
+
+    let kanjidic2 = r#"<kanjidic2><character><literal>皮</literal></character></kanjidic2>"#;
+
+    let inputs = [
+        ("jmdict", Input::Jmdict(&phrases, None)),
+        ("jmnedict", Input::Jmnedict(names, None)),
+        ("kanjidic2", Input::Kanjidic2(kanjidic2)),
+    ];
+
+    /// Search for the sequences of phrases and names and the kanji found.
+    fn search<'a>(db: &'a Database, q: &str) -> Result<(Vec<u64>, Vec<u64>, Vec<&'a str>)> {
+        let search = db.search(q)?;
+
+        let mut phrases = search
+            .phrases
+            .iter()
+            .map(|(_, p)| p.sequence)
+            .collect::<Vec<_>>();
+
+        phrases.sort();
+
+        let names = search
+            .names
+            .iter()
+            .map(|(_, n)| n.sequence)
+            .collect::<Vec<_>>();
+        let kanji = search
+            .characters
+            .iter()
+            .map(|c| c.literal)
+            .collect::<Vec<_>>();
+        Ok((phrases, names, kanji))
+    }
+
+    // Only phrases are filtered, names and kanji are always included.
+    let db = open(&inputs)?;
+    assert_eq!(search(&db, "かわ")?.0, [1, 2]);
+    assert_eq!(search(&db, "かわ #common")?, (vec![1], vec![10], vec![]));
+    assert_eq!(search(&db, "皮 #common")?, (vec![], vec![], vec!["皮"]));
+
+    let config = Config {
+        common_only: true,
+        ..Config::default()
+    };
+
+    let db = open_with(&inputs, config)?;
+    assert_eq!(search(&db, "かわ")?, (vec![1], vec![10], vec![]));
+    assert_eq!(search(&db, "かわ #all")?.0, [1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_words_with_kanji() -> Result<()> {
+    let input = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>小川</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>おがわ</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>brook</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><k_ele><keb>天の川</keb></k_ele><r_ele><reb>あまのがわ</reb></r_ele><sense><gloss>Milky Way</gloss></sense></entry>
+<entry><ent_seq>4</ent_seq><k_ele><keb>山</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>やま</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>mountain</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&input, None))])?;
+
+    let words = |literal: &str, limit: usize, sort: WordSort| -> Result<Vec<u64>> {
+        Ok(db
+            .words_with_kanji(literal, limit, sort)?
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect())
+    };
+
+    assert_eq!(words("川", 10, WordSort::Common)?, [2, 1, 3]);
+    assert_eq!(words("川", 10, WordSort::Short)?, [1, 2, 3]);
+    assert_eq!(words("川", 2, WordSort::Short)?, [1, 2]);
+    assert_eq!(words("山", 10, WordSort::Common)?, [4]);
+
+    // Only a single kanji can be looked up.
+    assert!(words("小川", 10, WordSort::Common)?.is_empty());
+    assert!(words("", 10, WordSort::Common)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_preferred_form() -> Result<()> {
+    let input = jmdict(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>一寸</keb></k_ele><r_ele><reb>ちょっと</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>a little</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>一寸</keb><ke_pri>news1</ke_pri></k_ele><r_ele><reb>いっすん</reb><re_pri>news1</re_pri></r_ele><sense><gloss>one sun</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><k_ele><keb>一寸</keb><ke_inf>&sK;</ke_inf></k_ele><k_ele><keb>鶏冠</keb></k_ele><k_ele><keb>鶏頭</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>けいかん</reb><re_restr>鶏冠</re_restr></r_ele><r_ele><reb>とさか</reb></r_ele><r_ele><reb>けいとう</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>crest</gloss></sense></entry>
+<entry><ent_seq>4</ent_seq><r_ele><reb>ねこ</reb></r_ele><r_ele><reb>ネコ</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>cat</gloss></sense></entry>"#,
+    );
+
+    let db = open(&[("jmdict", Input::Jmdict(&input, None))])?;
+
+    let weight = |query: &str, sequence: u64| -> Result<f32> {
+        let search = db.search(query)?;
+
+        let (key, _) = search
+            .phrases
+            .iter()
+            .find(|(_, p)| p.sequence == sequence)
+            .expect("phrase");
+
+        Ok(key.weight.value())
+    };
+
+    // Phrases are ranked by the priority of the form which was searched
+    // for, rather than by the most common form of the phrase.
+    assert!(weight("一寸", 2)? > weight("一寸", 1)?);
+    assert!(weight("ちょっと", 1)? > weight("いっすん", 2)?);
+
+    let preferred = |sequence: u32| -> Result<(Option<String>, String)> {
+        let entry = db.sequence_to_entry(sequence)?.expect("entry");
+        let (kanji, reading) = entry.preferred_form().expect("preferred form");
+        Ok((kanji.map(|k| k.text.to_owned()), reading.text.to_owned()))
+    };
+
+    let form = |kanji: Option<&str>, reading: &str| (kanji.map(str::to_owned), reading.to_owned());
+
+    // The first form is preferred if none is more common.
+    assert_eq!(preferred(1)?, form(Some("一寸"), "ちょっと"));
+    // Search-only forms are never preferred, and readings have to apply to
+    // the preferred kanji.
+    assert_eq!(preferred(3)?, form(Some("鶏頭"), "けいとう"));
+    assert_eq!(preferred(4)?, form(None, "ネコ"));
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Wildcard patterns used when looking up phrases.
+//!
+//! The following is supported:
+//! * `*` or `＊` matches any sequence of characters.
+//! * `?` or `？` matches any single character.
+//! * `[かが]` or `［かが］` matches any one of the enclosed characters.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A literal character.
+    Char(char),
+    /// Any single character.
+    Any,
+    /// Any sequence of characters.
+    Many,
+    /// Any one of the given characters.
+    Class(Vec<char>),
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::Any => true,
+            Token::Many => false,
+            Token::Class(class) => class.contains(&c),
+        }
+    }
+}
+
+/// A parsed wildcard pattern.
+#[derive(Debug)]
+pub(super) struct Pattern {
+    prefix: String,
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    /// Parse a wildcard pattern, returns `None` if the query doesn't contain
+    /// any wildcards.
+    pub(super) fn parse(query: &str) -> Option<Self> {
+        let mut tokens = Vec::new();
+        let mut it = query.chars();
+
+        while let Some(c) = it.next() {
+            let token = match c {
+                '*' | '＊' => {
+                    if tokens.last() == Some(&Token::Many) {
+                        continue;
+                    }
+
+                    Token::Many
+                }
+                '?' | '？' => Token::Any,
+                '[' | '［' => match class(it.as_str()) {
+                    Some((class, rest)) => {
+                        it = rest.chars();
+                        Token::Class(class)
+                    }
+                    None => Token::Char(c),
+                },
+                c => Token::Char(c),
+            };
+
+            tokens.push(token);
+        }
+
+        let literal = tokens
+            .iter()
+            .take_while(|t| matches!(t, Token::Char(..)))
+            .count();
+
+        if literal == tokens.len() {
+            return None;
+        }
+
+        let prefix = tokens
+            .drain(..literal)
+            .map(|t| match t {
+                Token::Char(c) => c,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        Some(Self { prefix, tokens })
+    }
+
+    /// The literal prefix which every match starts with.
+    pub(super) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Test if the given input matches the pattern, with the prefix already
+    /// stripped.
+    pub(super) fn matches_rest(&self, input: &str) -> bool {
+        let mut t = 0;
+        let mut s = 0;
+        // The last `*` seen and the input position it currently resumes from.
+        let mut star = None::<(usize, usize)>;
+
+        loop {
+            if let Some(token) = self.tokens.get(t) {
+                if let Token::Many = token {
+                    star = Some((t, s));
+                    t += 1;
+                    continue;
+                }
+
+                if let Some(c) = input[s..].chars().next() {
+                    if token.matches(c) {
+                        t += 1;
+                        s += c.len_utf8();
+                        continue;
+                    }
+                }
+            } else if s == input.len() {
+                return true;
+            }
+
+            let Some((star_t, star_s)) = star else {
+                return false;
+            };
+
+            let Some(c) = input[star_s..].chars().next() else {
+                return false;
+            };
+
+            star = Some((star_t, star_s + c.len_utf8()));
+            t = star_t + 1;
+            s = star_s + c.len_utf8();
+        }
+    }
+}
+
+/// Parse the remainder of a character class, returning the characters in it
+/// and the input following the closing bracket.
+fn class(input: &str) -> Option<(Vec<char>, &str)> {
+    let end = input.find([']', '］'])?;
+    let class = input[..end].chars().collect::<Vec<_>>();
+
+    if class.is_empty() {
+        return None;
+    }
+
+    let close = input[end..].chars().next()?;
+    Some((class, &input[end + close.len_utf8()..]))
+}
+
+#[test]
+fn test_wildcard() {
+    fn matches(pattern: &str, input: &str) -> bool {
+        let pattern = Pattern::parse(pattern).expect("pattern with wildcards");
+
+        let Some(rest) = input.strip_prefix(pattern.prefix()) else {
+            return false;
+        };
+
+        pattern.matches_rest(rest)
+    }
+
+    assert!(Pattern::parse("ある").is_none());
+    assert!(Pattern::parse("[ある").is_none());
+    assert_eq!(Pattern::parse("気*ち").unwrap().prefix(), "気");
+
+    assert!(matches("気*ち", "気持ち"));
+    assert!(matches("気*ち", "気ち"));
+    assert!(!matches("気*ち", "気持ちい"));
+    assert!(matches("?かる", "わかる"));
+    assert!(matches("？かる", "わかる"));
+    assert!(!matches("?かる", "かる"));
+    assert!(matches("*[かが]る", "さがる"));
+    assert!(matches("*［かが］る", "わかる"));
+    assert!(!matches("*[かが]る", "ある"));
+    assert!(matches("*る*る", "するする"));
+    assert!(!matches("る*る", "る"));
+}
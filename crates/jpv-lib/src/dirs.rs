@@ -1,3 +1,5 @@
+use std::collections::{btree_map, BTreeMap};
+use std::env;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -5,40 +7,194 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 
+/// Environment variable used to override the configuration directory.
+const CONFIG_DIR_VAR: &str = "JPV_CONFIG_DIR";
+/// Environment variable used to override the data directory.
+const DATA_DIR_VAR: &str = "JPV_DATA_DIR";
+/// Environment variable used to override the cache directory.
+const CACHE_DIR_VAR: &str = "JPV_CACHE_DIR";
+
 /// Directories helper.
 pub struct Dirs {
-    project_dirs: ProjectDirs,
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
 }
 
 impl Dirs {
     /// Open directories for this project.
+    ///
+    /// Each directory can be overridden through the `JPV_CONFIG_DIR`,
+    /// `JPV_DATA_DIR` and `JPV_CACHE_DIR` environment variables.
     pub fn open() -> Result<Dirs> {
+        let project_dirs = ProjectDirs::from("se", "tedro", "jpv");
+
+        let dir = |var: &str, default: fn(&ProjectDirs) -> &Path| {
+            if let Some(path) = env::var_os(var) {
+                return Ok(PathBuf::from(path));
+            }
+
+            let project_dirs = project_dirs
+                .as_ref()
+                .context("Could not figure out base directories")?;
+
+            Ok::<_, anyhow::Error>(default(project_dirs).to_owned())
+        };
+
         Ok(Dirs {
-            project_dirs: directories::ProjectDirs::from("se", "tedro", "jpv")
-                .context("Could not figure out base directories")?,
+            config_dir: dir(CONFIG_DIR_VAR, ProjectDirs::config_dir)?,
+            data_dir: dir(DATA_DIR_VAR, ProjectDirs::data_dir)?,
+            cache_dir: dir(CACHE_DIR_VAR, ProjectDirs::cache_dir)?,
         })
     }
 
+    /// Use directories under the given root instead of the ones of the
+    /// current user, like in tests.
+    pub fn in_dir(root: &Path) -> Dirs {
+        Dirs {
+            config_dir: root.join("config"),
+            data_dir: root.join("data"),
+            cache_dir: root.join("cache"),
+        }
+    }
+
     /// Get the configuration directory.
     pub fn config_dir(&self) -> &Path {
-        self.project_dirs.config_dir()
+        &self.config_dir
     }
 
     /// Get the path of the configuration file.
     pub fn config_path(&self) -> PathBuf {
-        self.project_dirs.config_dir().join("config.toml")
+        self.config_dir.join("config.toml")
+    }
+
+    /// Get the path of the file storing user interface settings of clients.
+    pub fn client_settings_path(&self) -> PathBuf {
+        self.config_dir.join("clients.toml")
+    }
+
+    /// Get the data directory, where indexes are stored.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Get the path of the history of looked up words.
+    pub fn history_path(&self) -> PathBuf {
+        self.data_dir.join("history.json")
+    }
+
+    /// Get the path of the phrasebook.
+    pub fn phrasebook_path(&self) -> PathBuf {
+        self.data_dir.join("phrasebook.json")
+    }
+
+    /// Get the path of the checkpoint of a build which is in progress.
+    pub fn checkpoint_path(&self) -> PathBuf {
+        self.data_dir.join("build-checkpoint.json")
+    }
+
+    /// Get the path of the file where the running service records the address
+    /// it listens on.
+    pub fn service_address_path(&self) -> PathBuf {
+        self.cache_dir.join("service-address")
+    }
+
+    /// Get the cache directory, where downloads are stored.
+    pub fn cache_root(&self) -> &Path {
+        &self.cache_dir
     }
 
-    /// The path to an individual index.
+    /// The path to the current generation of an individual index.
+    ///
+    /// Rebuilt indexes are written as a new generation next to the old one, so
+    /// that processes which have the old generation open are unaffected.
     pub fn index_path(&self, name: &str) -> PathBuf {
-        self.project_dirs.data_dir().join(format!("{name}.index"))
+        let current = self
+            .index_files()
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter(|(n, ..)| n == name)
+            .max_by_key(|&(_, generation, _)| generation);
+
+        match current {
+            Some((_, _, path)) => path,
+            None => self.data_dir.join(format!("{name}.index")),
+        }
+    }
+
+    /// The path to write the next generation of an individual index to.
+    pub fn next_index_path(&self, name: &str) -> Result<PathBuf> {
+        let generation = self
+            .index_files()?
+            .into_iter()
+            .filter(|(n, ..)| n == name)
+            .map(|(_, generation, _)| generation + 1)
+            .max()
+            .unwrap_or(0);
+
+        Ok(self.data_dir.join(format!("{name}.{generation}.index")))
     }
 
-    /// Get dictionary path.
+    /// Get the paths to the current generation of every index.
     pub fn indexes(&self) -> Result<Vec<PathBuf>> {
+        let mut current = BTreeMap::new();
+
+        for (name, generation, path) in self.index_files()? {
+            match current.entry(name) {
+                btree_map::Entry::Vacant(e) => {
+                    e.insert((generation, path));
+                }
+                btree_map::Entry::Occupied(mut e) => {
+                    if e.get().0 < generation {
+                        e.insert((generation, path));
+                    }
+                }
+            }
+        }
+
+        Ok(current.into_values().map(|(_, path)| path).collect())
+    }
+
+    /// Get the paths to index generations which have been replaced by a newer
+    /// generation.
+    pub fn stale_indexes(&self) -> Result<Vec<PathBuf>> {
+        let current = self.indexes()?;
+
+        Ok(self
+            .index_files()?
+            .into_iter()
+            .map(|(_, _, path)| path)
+            .filter(|path| !current.contains(path))
+            .collect())
+    }
+
+    /// Get the name and generation of the index at the given path.
+    ///
+    /// Indexes are named `<name>.index`, which is the first generation, or
+    /// `<name>.<generation>.index`.
+    pub fn index_name(path: &Path) -> Option<(&str, u64)> {
+        if path.extension() != Some("index".as_ref()) {
+            return None;
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+
+        if let Some((name, generation)) = stem.rsplit_once('.') {
+            if let Ok(generation) = generation.parse() {
+                return Some((name, generation));
+            }
+        }
+
+        Some((stem, 0))
+    }
+
+    /// List all index files in the data directory, together with the name and
+    /// generation of the index.
+    fn index_files(&self) -> Result<Vec<(String, u64, PathBuf)>> {
         let mut indexes = Vec::new();
 
-        let d = match fs::read_dir(self.project_dirs.data_dir()) {
+        let d = match fs::read_dir(&self.data_dir) {
             Ok(d) => d,
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 return Ok(indexes);
@@ -52,12 +208,14 @@ impl Dirs {
             let e = e?;
             let path = e.path();
 
-            if path.extension() != Some("index".as_ref()) {
+            let Some((name, generation)) = Self::index_name(&path) else {
                 continue;
-            }
+            };
+
+            let name = name.to_owned();
 
             if path.is_file() {
-                indexes.push(path);
+                indexes.push((name, generation, path));
             }
         }
 
@@ -69,6 +227,6 @@ impl Dirs {
     where
         P: AsRef<Path>,
     {
-        self.project_dirs.cache_dir().join(path)
+        self.cache_dir.join(path)
     }
 }
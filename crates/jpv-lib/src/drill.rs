@@ -0,0 +1,139 @@
+//! Conjugation drills generated with the inflection engine.
+
+use std::collections::HashMap;
+
+use crate::inflection::{self, Form, Inflection};
+use crate::jmdict;
+
+/// Which drills to generate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Filter {
+    /// Forms which drills may combine. Setting past and negative drills the
+    /// past, negative and negative past forms. If empty every form is drilled.
+    pub forms: Inflection,
+    /// Only drill polite forms if `true`, or plain forms if `false`.
+    pub polite: Option<bool>,
+}
+
+impl Filter {
+    fn matches(&self, inflection: Inflection) -> bool {
+        if let Some(polite) = self.polite {
+            if inflection.contains(Form::Honorific) != polite {
+                return false;
+            }
+        }
+
+        self.forms.is_empty()
+            || inflection
+                .iter()
+                .all(|form| form == Form::Honorific || self.forms.contains(form))
+    }
+}
+
+/// A single conjugation drill.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drill {
+    /// The sequence number of the entry being conjugated.
+    pub sequence: u64,
+    /// The dictionary form being conjugated.
+    pub dictionary: String,
+    /// The reading of the dictionary form.
+    pub reading: String,
+    /// The inflection being drilled.
+    pub inflection: Inflection,
+    /// The prompt, like `negative past of 行く`.
+    pub prompt: String,
+    /// Accepted answers, both written with kanji and in kana.
+    pub answers: Vec<String>,
+}
+
+/// Generate every drill for the given entry which matches the filter.
+///
+/// Readings which conjugate the same dictionary form are merged into one drill
+/// accepting all of them.
+pub fn drills(entry: &jmdict::Entry<'_>, filter: &Filter) -> Vec<Drill> {
+    let mut output = Vec::<Drill>::new();
+    let mut existing = HashMap::new();
+
+    for (_, c, _) in inflection::conjugate(entry) {
+        let dictionary = c.dictionary.furigana();
+        let kanji = dictionary.kanji().to_string();
+        let reading = dictionary.reading().to_string();
+
+        for (&inflection, fragments) in c.iter() {
+            if !filter.matches(inflection) {
+                continue;
+            }
+
+            let prompt = format!("{} of {kanji}", describe(inflection));
+            let furigana = fragments.furigana();
+
+            let answers = [furigana.kanji().to_string(), furigana.reading().to_string()];
+
+            let drill = match existing.get(&prompt) {
+                Some(&index) => &mut output[index],
+                None => {
+                    existing.insert(prompt.clone(), output.len());
+
+                    output.push(Drill {
+                        sequence: entry.sequence,
+                        dictionary: kanji.clone(),
+                        reading: reading.clone(),
+                        inflection,
+                        prompt,
+                        answers: Vec::new(),
+                    });
+
+                    output.last_mut().expect("just pushed")
+                }
+            };
+
+            for answer in answers {
+                if !drill.answers.contains(&answer) {
+                    drill.answers.push(answer);
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Describe an inflection for use in a prompt, like `polite negative past`.
+pub fn describe(inflection: Inflection) -> String {
+    let forms = inflection
+        .iter()
+        .map(|form| match form {
+            Form::Honorific => String::from("polite"),
+            form => form.keyword(),
+        })
+        .collect::<Vec<_>>();
+
+    forms.join(" ")
+}
+
+#[test]
+fn test_filter() {
+    let mut forms = Inflection::default();
+    forms.insert(Form::Past);
+    forms.insert(Form::Negative);
+
+    let filter = Filter {
+        forms,
+        polite: Some(false),
+    };
+
+    let mut past = Inflection::default();
+    past.insert(Form::Past);
+    assert!(filter.matches(past));
+
+    let mut polite_past = past;
+    polite_past.insert(Form::Honorific);
+    assert!(!filter.matches(polite_past));
+
+    let mut te = Inflection::default();
+    te.insert(Form::Te);
+    assert!(!filter.matches(te));
+
+    assert_eq!(describe(polite_past), "polite past");
+}
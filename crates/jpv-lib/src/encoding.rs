@@ -0,0 +1,78 @@
+//! Detection of the text encoding used by legacy dictionary sources.
+//!
+//! Files like KRADFILE and RADKFILE are officially distributed as EUC-JP, but
+//! converted variants in UTF-8 and Shift_JIS circulate online.
+
+use encoding_rs::{DecoderResult, Encoding, EUC_JP, SHIFT_JIS, UTF_8};
+
+/// The number of bytes sampled when detecting an encoding.
+const SAMPLE: usize = 1 << 16;
+
+/// Detect the encoding of the given input.
+///
+/// Inputs with a byte order mark or which are valid UTF-8 are treated as
+/// UTF-8. Otherwise the input is decoded as both EUC-JP and Shift_JIS, and the
+/// encoding which produces the fewest malformed sequences is picked. EUC-JP is
+/// preferred if they are equally good.
+pub fn detect(input: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(input) {
+        return encoding;
+    }
+
+    let sample = &input[..input.len().min(SAMPLE)];
+
+    if is_utf8_prefix(sample, sample.len() < input.len()) {
+        return UTF_8;
+    }
+
+    let euc_jp = malformed(EUC_JP, sample);
+    let shift_jis = malformed(SHIFT_JIS, sample);
+
+    if shift_jis < euc_jp {
+        SHIFT_JIS
+    } else {
+        EUC_JP
+    }
+}
+
+/// Test if the sample is valid UTF-8, allowing for it to be cut off in the
+/// middle of a character if it is truncated.
+fn is_utf8_prefix(sample: &[u8], truncated: bool) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(..) => true,
+        Err(e) => truncated && e.error_len().is_none(),
+    }
+}
+
+/// Count the number of malformed sequences when decoding the sample with the
+/// given encoding.
+fn malformed(encoding: &'static Encoding, mut sample: &[u8]) -> usize {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut buf = [0; 4096];
+    let mut count = 0;
+
+    loop {
+        let (result, read, _) = decoder.decode_to_utf8_without_replacement(sample, &mut buf, true);
+        sample = &sample[read..];
+
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => {}
+            DecoderResult::Malformed(..) => {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+#[test]
+fn test_detect() {
+    let (euc_jp, _, _) = EUC_JP.encode("亜 : ｜ 一 口\n");
+    let (shift_jis, _, _) = SHIFT_JIS.encode("亜 : ｜ 一 口\n");
+
+    assert_eq!(detect("亜 : ｜ 一 口\n".as_bytes()), UTF_8);
+    assert_eq!(detect(&euc_jp), EUC_JP);
+    assert_eq!(detect(&shift_jis), SHIFT_JIS);
+}
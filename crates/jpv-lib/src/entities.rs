@@ -14,7 +14,7 @@ macro_rules! entity {
     ) => {
         $(
             $(#[$($meta)*])*
-            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Key, ZeroCopy, Visit)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Key, ZeroCopy, Visit)]
             #[key(bitset)]
             #[repr(u8)]
             #[musli(mode = Text, name_all = "kebab-case")]
@@ -388,6 +388,27 @@ entity! {
     }
 }
 
+/// Describe every known entity as a tuple of the kind of entity, its
+/// identifier and its long-form description.
+pub fn descriptions() -> impl Iterator<Item = (&'static str, &'static str, &'static str)> {
+    macro_rules! describe {
+        ($($kind:literal => $ty:ident),* $(,)?) => {
+            std::iter::empty()
+                $(.chain($ty::VALUES.iter().map(|e| ($kind, e.ident(), e.help()))))*
+        };
+    }
+
+    describe! {
+        "misc" => Miscellaneous,
+        "pos" => PartOfSpeech,
+        "kanji-info" => KanjiInfo,
+        "reading-info" => ReadingInfo,
+        "dialect" => Dialect,
+        "field" => Field,
+        "name-type" => NameType,
+    }
+}
+
 impl PartOfSpeech {
     /// Get a generic category for this part of speech.
     pub(crate) fn generic(&self) -> Option<&'static str> {
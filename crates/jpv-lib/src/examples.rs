@@ -0,0 +1,116 @@
+//! Furigana annotation of example sentences.
+
+use crate::inflection;
+use crate::jmdict;
+use crate::{FuriganaGroup, OwnedFurigana};
+
+/// A segment of an annotated example sentence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// The text of the segment.
+    pub text: String,
+    /// The reading of the segment, if it contains kanji.
+    pub reading: Option<String>,
+}
+
+impl Segment {
+    fn text(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            reading: None,
+        }
+    }
+}
+
+/// Annotate the given sentence with furigana for the given entry.
+///
+/// Both the dictionary forms and the inflections of the entry are aligned
+/// against the sentence, and the longest one which occurs in it is annotated.
+/// If nothing matches the whole sentence is returned as a single segment.
+pub fn annotate(entry: &jmdict::Entry<'_>, sentence: &str) -> Vec<Segment> {
+    let mut candidates = Vec::new();
+
+    for (kanji, (_, reading), _) in inflection::reading_permutations(entry) {
+        let kanji = kanji.map(|(_, kanji)| kanji).unwrap_or(reading);
+        candidates.push(OwnedFurigana::new([kanji], [reading], [""]));
+    }
+
+    for (_, c, _) in inflection::conjugate(entry) {
+        for form in c.inflections.values() {
+            candidates.push(form.furigana());
+        }
+    }
+
+    let mut best = None::<(usize, usize, &OwnedFurigana)>;
+
+    for furigana in &candidates {
+        let kanji = furigana.kanji().to_string();
+
+        let Some(start) = sentence.find(kanji.as_str()) else {
+            continue;
+        };
+
+        if best.is_none_or(|(_, len, _)| kanji.len() > len) {
+            best = Some((start, kanji.len(), furigana));
+        }
+    }
+
+    let Some((start, len, furigana)) = best else {
+        return vec![Segment::text(sentence)];
+    };
+
+    let mut segments = Vec::new();
+
+    if start > 0 {
+        segments.push(Segment::text(&sentence[..start]));
+    }
+
+    for group in furigana.iter() {
+        segments.push(match group {
+            FuriganaGroup::Kanji(kanji, reading) => Segment {
+                text: kanji.to_owned(),
+                reading: Some(reading.to_owned()),
+            },
+            FuriganaGroup::Kana(kana) => Segment::text(kana),
+        });
+    }
+
+    if start + len < sentence.len() {
+        segments.push(Segment::text(&sentence[start + len..]));
+    }
+
+    segments
+}
+
+#[test]
+fn test_annotate() {
+    let input = r#"<JMdict><entry><ent_seq>1</ent_seq><k_ele><keb>食べる</keb></k_ele><r_ele><reb>たべる</reb></r_ele><sense><pos>&v1;</pos><gloss>to eat</gloss></sense></entry></JMdict>"#;
+    let entry = jmdict::Parser::new(input).parse().unwrap().unwrap();
+
+    let segment = |text: &str, reading: Option<&str>| Segment {
+        text: text.to_owned(),
+        reading: reading.map(str::to_owned),
+    };
+
+    assert_eq!(
+        annotate(&entry, "食べる"),
+        [segment("食", Some("た")), segment("べる", None)]
+    );
+
+    // Inflected forms are annotated with the text around them.
+    assert_eq!(
+        annotate(&entry, "パンを食べた。"),
+        [
+            segment("パンを", None),
+            segment("食", Some("た")),
+            segment("べ", None),
+            segment("た", None),
+            segment("。", None),
+        ]
+    );
+
+    assert_eq!(
+        annotate(&entry, "何もない。"),
+        [segment("何もない。", None)]
+    );
+}
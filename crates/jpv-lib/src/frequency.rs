@@ -0,0 +1,197 @@
+//! Parser for word frequency lists.
+//!
+//! Three kinds of lists are supported, which are all expected to be ordered
+//! from the most to the least frequent word:
+//!
+//! * The BCCWJ frequency lists, which are tab-separated with a header naming
+//!   their columns. The word is taken from the `lemma` column, and its rank
+//!   from the `rank` column if there is one.
+//! * Lists with one word per line, like the Netflix frequency list.
+//! * Lists with a word and how often it occurs on each line in either order,
+//!   like the Innocent Corpus `count\tword` report.
+//!
+//! Words which don't come with a rank are ranked by the order they appear in.
+
+use std::collections::HashMap;
+
+use crate::jmdict;
+
+/// The frequency rank of a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub text: &'a str,
+    /// The rank of the word, where `1` is the most frequent.
+    pub rank: u32,
+}
+
+/// Columns of a list with a header, like the BCCWJ.
+struct Header {
+    lemma: usize,
+    rank: Option<usize>,
+}
+
+/// A parser for word frequency lists.
+pub struct Parser<'a> {
+    lines: std::str::Lines<'a>,
+    header: Option<Header>,
+    first: bool,
+    rank: u32,
+    skipped: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Construct a new word frequency parser.
+    pub fn new(input: &'a str) -> Self {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+        Self {
+            lines: input.lines(),
+            header: None,
+            first: true,
+            rank: 0,
+            skipped: 0,
+        }
+    }
+
+    /// The number of lines which were skipped since they could not be parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Parse an entry.
+    pub fn parse(&mut self) -> Option<Entry<'a>> {
+        for line in self.lines.by_ref() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns = if line.contains('\t') {
+                line.split('\t').map(str::trim).collect::<Vec<_>>()
+            } else {
+                line.split_whitespace().collect::<Vec<_>>()
+            };
+
+            if std::mem::take(&mut self.first) {
+                if let Some(lemma) = columns.iter().position(|c| c.eq_ignore_ascii_case("lemma")) {
+                    let rank = columns.iter().position(|c| c.eq_ignore_ascii_case("rank"));
+                    self.header = Some(Header { lemma, rank });
+                    continue;
+                }
+            }
+
+            let (text, rank) = match &self.header {
+                Some(header) => {
+                    let rank = header
+                        .rank
+                        .and_then(|n| columns.get(n)?.parse::<u32>().ok());
+                    (columns.get(header.lemma).copied(), rank)
+                }
+                None => (columns.iter().copied().find(|c| !is_number(c)), None),
+            };
+
+            let Some(text) = text.filter(|text| !text.is_empty()) else {
+                self.skipped += 1;
+                continue;
+            };
+
+            self.rank += 1;
+
+            return Some(Entry {
+                text,
+                rank: rank.unwrap_or(self.rank),
+            });
+        }
+
+        None
+    }
+}
+
+/// The ranks of every word in a word frequency list.
+#[derive(Debug, Default)]
+pub struct Ranks<'a> {
+    words: HashMap<&'a str, u32>,
+    skipped: usize,
+}
+
+impl<'a> Ranks<'a> {
+    /// Parse every word in a word frequency list, keeping the best rank of
+    /// words which are listed more than once.
+    pub fn parse(input: &'a str) -> Self {
+        let mut parser = Parser::new(input);
+        let mut words = HashMap::new();
+
+        while let Some(entry) = parser.parse() {
+            words
+                .entry(entry.text)
+                .and_modify(|rank: &mut u32| *rank = (*rank).min(entry.rank))
+                .or_insert(entry.rank);
+        }
+
+        Self {
+            words,
+            skipped: parser.skipped(),
+        }
+    }
+
+    /// Test if there are no ranks.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The number of lines which were skipped since they could not be parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Get the best rank of a phrase.
+    ///
+    /// Phrases are looked up by how they are written, and by their readings if
+    /// they are usually written in kana since readings are shared by many
+    /// phrases otherwise.
+    pub fn rank(&self, entry: &jmdict::Entry<'_>) -> Option<u32> {
+        let kanji = entry.kanji_elements.iter().map(|e| e.text);
+
+        let readings = entry
+            .reading_elements
+            .iter()
+            .filter(|_| entry.is_usually_kana())
+            .map(|e| e.text);
+
+        kanji
+            .chain(readings)
+            .filter_map(|text| self.words.get(text).copied())
+            .min()
+    }
+}
+
+/// Test if a column holds a count or a rank rather than a word.
+fn is_number(column: &str) -> bool {
+    !column.is_empty() && column.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+#[test]
+fn test_parse() {
+    let entry = |text, rank| Entry { text, rank };
+
+    let mut parser = Parser::new(
+        "rank\tlForm\tlemma\tpos\tfrequency\n1\tノ\tの\t助詞-格助詞\t5061558\n3\tテ\tて\t助詞-接続助詞\t3650659\n",
+    );
+
+    assert_eq!(parser.parse(), Some(entry("の", 1)));
+    assert_eq!(parser.parse(), Some(entry("て", 3)));
+    assert_eq!(parser.parse(), None);
+
+    let mut parser = Parser::new("する\nある\n\n# comment\nいる\n");
+
+    assert_eq!(parser.parse(), Some(entry("する", 1)));
+    assert_eq!(parser.parse(), Some(entry("ある", 2)));
+    assert_eq!(parser.parse(), Some(entry("いる", 3)));
+    assert_eq!(parser.parse(), None);
+
+    let mut parser = Parser::new("20298322\tの\n9000\t12\nする 120\n");
+
+    assert_eq!(parser.parse(), Some(entry("の", 1)));
+    assert_eq!(parser.parse(), Some(entry("する", 2)));
+    assert_eq!(parser.parse(), None);
+    assert_eq!(parser.skipped(), 1);
+}
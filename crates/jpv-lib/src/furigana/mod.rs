@@ -4,7 +4,9 @@ mod tests;
 use core::fmt;
 use core::ops::Range;
 
-use crate::kana::{is_hiragana, is_katakana};
+use std::borrow::Cow;
+
+use crate::kana::{is_hiragana, is_katakana, to_hiragana};
 use crate::morae;
 
 /// A string pair.
@@ -88,6 +90,58 @@ impl<'a> Furigana<'a> {
     pub fn reading(&self) -> Pair<'a> {
         Pair::new(self.reading, self.suffix)
     }
+
+    /// Render furigana as `<ruby>` HTML, such as
+    /// `<ruby>食<rt>た</rt></ruby>べる`.
+    ///
+    /// Both kanji and readings are HTML escaped.
+    pub fn ruby(&self) -> impl fmt::Display + 'a {
+        struct Ruby<'a>(Furigana<'a>);
+
+        impl fmt::Display for Ruby<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for group in self.0.iter() {
+                    match group {
+                        FuriganaGroup::Kanji(kanji, kana) => {
+                            write!(f, "<ruby>{}<rt>{}</rt></ruby>", Escape(kanji), Escape(kana))?;
+                        }
+                        FuriganaGroup::Kana(kana) => {
+                            write!(f, "{}", Escape(kana))?;
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+
+        Ruby(*self)
+    }
+}
+
+/// Escape a string for inclusion in HTML.
+pub(crate) struct Escape<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+
+        while let Some(n) = rest.find(['&', '<', '>', '"', '\'']) {
+            f.write_str(&rest[..n])?;
+
+            f.write_str(match rest.as_bytes()[n] {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                _ => "&#39;",
+            })?;
+
+            rest = &rest[n + 1..];
+        }
+
+        f.write_str(rest)
+    }
 }
 
 pub struct OwnedFurigana {
@@ -205,10 +259,20 @@ fn groups(kanji: &str) -> impl Iterator<Item = Range<usize>> + '_ {
     })
 }
 
+/// Fold katakana into hiragana, so that the two can be matched against each
+/// other.
+fn fold(text: &str) -> Cow<'_, str> {
+    if text.chars().all(|c| to_hiragana(c) == c) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(text.chars().map(to_hiragana).collect())
+}
+
 fn reverse_find<'a>(
     kanji: &'a str,
     reading: &'a str,
-) -> impl Iterator<Item = (&'a str, usize, Range<usize>)> {
+) -> impl Iterator<Item = (usize, Range<usize>)> + 'a {
     use memchr::memmem::rfind_iter;
 
     let mut reading_len = reading.len();
@@ -233,7 +297,11 @@ fn reverse_find<'a>(
                 let c = reading[next..].chars().next()?;
                 let reading_kana = &reading[next + c.len_utf8()..reading_len];
 
-                morae_count += morae::iter(reading_kana).count();
+                // A small tsu doesn't count towards the morae of a kanji, so
+                // that readings like がっ for 学 are treated as short.
+                morae_count += morae::iter(reading_kana)
+                    .filter(|mora| !matches!(*mora, "っ" | "ッ"))
+                    .count();
 
                 // Find the largest group that doesn't violate the heuristic that
                 // each kanji has two morae.
@@ -248,7 +316,7 @@ fn reverse_find<'a>(
         };
 
         kanji_len = g.start;
-        Some((kana, reading_len, g))
+        Some((reading_len, g))
     })
 }
 
@@ -264,7 +332,11 @@ fn furigana<'a>(
     use core::mem;
     use FuriganaGroup::*;
 
-    let positions = reverse_find(kanji, reading).collect::<Vec<_>>();
+    // Kana are matched regardless of whether they're written in hiragana or
+    // katakana, since names in particular mix the two between how they're
+    // written and their reading. Folding preserves byte offsets, so positions
+    // found in the folded text apply to the original text.
+    let positions = reverse_find(&fold(kanji), &fold(reading)).collect::<Vec<_>>();
 
     let mut last = (0, 0);
     let mut it = positions.into_iter().rev();
@@ -277,7 +349,7 @@ fn furigana<'a>(
 
         let (k, r) = last;
 
-        let Some((kana, at, g)) = it.next() else {
+        let Some((at, g)) = it.next() else {
             if !kanji[k..].is_empty() {
                 last = (kanji.len(), reading.len());
                 return Some(Kanji(&kanji[k..], &reading[r..]));
@@ -290,6 +362,8 @@ fn furigana<'a>(
             return None;
         };
 
+        let kana = &kanji[g.start..g.end];
+
         let ret = if kanji[k..].starts_with(kana) {
             Some(Kana(kana))
         } else {
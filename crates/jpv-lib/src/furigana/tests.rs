@@ -96,6 +96,31 @@ fn furigana_unmatched() {
     );
 }
 
+#[test]
+fn furigana_mixed_kana() {
+    // Katakana in the kanji with a hiragana reading.
+    test_case!("ゴミ箱", "ごみばこ", [Kn("ゴミ"), K("箱", "ばこ")]);
+
+    test_case!(
+        "江戸川コナン",
+        "えどがわこなん",
+        [K("江戸川", "えどがわ"), Kn("コナン")]
+    );
+
+    // Katakana reading over kanji with hiragana in it.
+    test_case!(
+        "お茶の水",
+        "オチャノミズ",
+        [Kn("お"), K("茶", "チャ"), Kn("の"), K("水", "ミズ")]
+    );
+
+    test_case!(
+        "ジョン万次郎",
+        "ジョンまんじろう",
+        [Kn("ジョン"), K("万次郎", "まんじろう")]
+    );
+}
+
 #[test]
 fn furigana_isolated() {
     test_case!(
@@ -130,3 +155,51 @@ fn pair() {
     test_case!(("a", "bc"), ("ab", "c"));
     test_case!(("ab", "cd"), ("ab", "cd"));
 }
+
+#[test]
+fn furigana_ruby() {
+    let furigana = Furigana::new("食べる", "たべる", "");
+    assert_eq!(
+        furigana.ruby().to_string(),
+        "<ruby>食<rt>た</rt></ruby>べる"
+    );
+
+    let furigana = Furigana::new("R&B", "あーるあんどびー", "");
+    assert_eq!(
+        furigana.ruby().to_string(),
+        "<ruby>R&amp;B<rt>あーるあんどびー</rt></ruby>"
+    );
+}
+
+#[test]
+fn furigana_small_tsu() {
+    // The small tsu in the reading of a kanji doesn't make it look too long
+    // to match.
+    test_case!("三つ", "みっつ", [K("三", "みっ"), Kn("つ")]);
+    test_case!("八つ", "やっつ", [K("八", "やっ"), Kn("つ")]);
+    test_case!("学校", "がっこう", [K("学校", "がっこう")]);
+
+    test_case!(
+        "ぶっ飛ぶ",
+        "ぶっとぶ",
+        [Kn("ぶっ"), K("飛", "と"), Kn("ぶ")]
+    );
+
+    test_case!(
+        "引っ掛かる",
+        "ひっかかる",
+        [K("引", "ひ"), Kn("っ"), K("掛", "か"), Kn("かる")]
+    );
+
+    test_case!(
+        "真っ只中",
+        "まっただなか",
+        [K("真", "ま"), Kn("っ"), K("只中", "ただなか")]
+    );
+
+    test_case!(
+        "掛かりっ放し",
+        "かかりっぱなし",
+        [K("掛", "か"), Kn("かりっ"), K("放", "ぱな"), Kn("し")]
+    );
+}
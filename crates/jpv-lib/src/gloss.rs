@@ -0,0 +1,270 @@
+//! Glossing of free text.
+//!
+//! Text is split into words using the same analysis as when clicking on text,
+//! and each word is annotated with its reading and meaning. The result can be
+//! rendered as HTML with ruby furigana, either as a fragment or as a
+//! standalone document where meanings are shown when hovering over words.
+
+use std::fmt;
+
+use anyhow::Result;
+
+use crate::database::{Database, Entry, Source};
+use crate::furigana::Escape;
+use crate::html;
+use crate::inflection::{self, Inflection};
+use crate::kana;
+use crate::{Furigana, Weight};
+
+/// The number of meanings included for each word.
+const MAX_GLOSSES: usize = 3;
+
+/// A segment of glossed text.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// The text of the segment.
+    pub text: String,
+    /// The reading of the text if it's a word written with kanji.
+    pub reading: Option<String>,
+    /// The sequence number of the entry the text is a form of, if it's a word.
+    pub sequence: Option<u64>,
+    /// Meanings of the word.
+    pub glosses: Vec<String>,
+    /// The inflection the text is of the word, if it's an inflected form.
+    pub inflection: Option<Inflection>,
+}
+
+impl Segment {
+    fn plain(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            reading: None,
+            sequence: None,
+            glosses: Vec::new(),
+            inflection: None,
+        }
+    }
+
+    /// Get the text of the segment as kana.
+    pub fn kana(&self) -> &str {
+        self.reading.as_deref().unwrap_or(&self.text)
+    }
+
+    /// Get the text of the segment with furigana.
+    pub fn furigana(&self) -> Furigana<'_> {
+        Furigana::new(&self.text, self.kana(), "")
+    }
+
+    /// Render the segment as HTML, using `<ruby>` if it has a reading.
+    fn ruby(&self) -> impl fmt::Display + '_ {
+        struct Ruby<'a>(&'a Segment);
+
+        impl fmt::Display for Ruby<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0.reading {
+                    Some(..) => write!(f, "{}", self.0.furigana().ruby()),
+                    None => write!(f, "{}", Escape(&self.0.text)),
+                }
+            }
+        }
+
+        Ruby(self)
+    }
+}
+
+/// Split text into glossed segments.
+///
+/// Text which isn't Japanese or which can't be found in the dictionary is kept
+/// as plain segments, so concatenating the text of every segment reproduces
+/// the input.
+pub fn segments(db: &Database, text: &str) -> Result<Vec<Segment>> {
+    let mut output = Vec::new();
+    let mut plain = 0;
+    let mut pos = 0;
+
+    while let Some(c) = text[pos..].chars().next() {
+        let word = if kana::is_japanese(c) {
+            db.analyze(text, pos)?.into_values().next()
+        } else {
+            None
+        };
+
+        let Some(word) = word else {
+            pos += c.len_utf8();
+            continue;
+        };
+
+        if plain < pos {
+            output.push(Segment::plain(&text[plain..pos]));
+        }
+
+        output.push(describe(db, word)?);
+        pos += word.len();
+        plain = pos;
+    }
+
+    if plain < text.len() {
+        output.push(Segment::plain(&text[plain..]));
+    }
+
+    Ok(output)
+}
+
+/// Render segments as `<ruby>` HTML, such as `<ruby>食<rt>た</rt></ruby>べる`.
+pub fn ruby(segments: &[Segment]) -> impl fmt::Display + '_ {
+    struct Ruby<'a>(&'a [Segment]);
+
+    impl fmt::Display for Ruby<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for segment in self.0 {
+                write!(f, "{}", segment.ruby())?;
+            }
+
+            Ok(())
+        }
+    }
+
+    Ruby(segments)
+}
+
+/// Render segments as a standalone HTML document with the given title.
+///
+/// Words are rendered with ruby furigana, and their meanings are shown when
+/// hovering over them. The document doesn't refer to anything outside of
+/// itself, so it can be opened without the service running.
+pub fn html<'a>(title: &'a str, segments: &'a [Segment]) -> impl fmt::Display + 'a {
+    html::document(title, STYLE, move |f| {
+        write!(f, "<p>")?;
+
+        for segment in segments {
+            if !segment.glosses.is_empty() {
+                write!(
+                    f,
+                    "<span class=\"word\" data-gloss=\"{}\">{}</span>",
+                    Escape(&segment.glosses.join("; ")),
+                    segment.ruby()
+                )?;
+            } else if segment.reading.is_some() {
+                write!(f, "{}", segment.ruby())?;
+            } else {
+                let mut lines = segment.text.split('\n');
+
+                if let Some(line) = lines.next() {
+                    write!(f, "{}", Escape(line))?;
+                }
+
+                for line in lines {
+                    write!(f, "<br>\n{}", Escape(line))?;
+                }
+            }
+        }
+
+        writeln!(f, "</p>")
+    })
+}
+
+const STYLE: &str =
+    "body{font-size:1.5em;line-height:2.2;max-width:40em;margin:2em auto;padding:0 1em}\
+rt{font-size:0.5em}\
+.word{position:relative;border-bottom:1px dotted #999}\
+.word:hover{background:#eef}\
+.word:hover::after{content:attr(data-gloss);position:absolute;left:0;top:100%;z-index:1;\
+width:max-content;max-width:20em;padding:0.2em 0.5em;font-size:0.6em;line-height:1.4;\
+background:#333;color:#fff;border-radius:0.3em}";
+
+/// Describe a word, using the most relevant phrase it's a form of.
+fn describe(db: &Database, word: &str) -> Result<Segment> {
+    let mut best = None::<(Weight, _)>;
+
+    for id in db.lookup(word)? {
+        let Entry::Phrase(entry) = db.entry_at(id)? else {
+            continue;
+        };
+
+        let frequency = db.frequency_rank(entry.sequence)?;
+        let weight = entry.weight(word, id.source().is_inflection(), frequency);
+
+        // Weights are ordered with the heaviest first.
+        let better = match &best {
+            Some((best, _)) => weight < *best,
+            None => true,
+        };
+
+        if better {
+            best = Some((weight, (entry, *id.source())));
+        }
+    }
+
+    let Some((_, (entry, source))) = best else {
+        return Ok(Segment::plain(word));
+    };
+
+    let has_kanji = word
+        .chars()
+        .any(|c| kana::is_japanese(c) && !kana::is_hiragana(c) && !kana::is_katakana(c));
+
+    let reading = if has_kanji {
+        inflection::reading_permutations(&entry)
+            .into_iter()
+            .find_map(|(kanji, (_, reading), _)| word_reading(word, kanji?.1, reading))
+    } else {
+        None
+    };
+
+    let glosses = entry
+        .senses
+        .iter()
+        .filter(|sense| sense.is_lang("eng"))
+        .flat_map(|sense| sense.gloss.iter())
+        .filter(|gloss| gloss.lang.unwrap_or("eng") == "eng")
+        .map(|gloss| gloss.text.to_owned())
+        .take(MAX_GLOSSES)
+        .collect();
+
+    Ok(Segment {
+        text: word.to_owned(),
+        reading,
+        sequence: Some(entry.sequence),
+        glosses,
+        inflection: match source {
+            Source::Inflection { data } => Some(data.inflection),
+            _ => None,
+        },
+    })
+}
+
+/// Get the reading of a word which is a form of the given kanji and reading,
+/// like `たべた` for `食べた` as a form of `食べる` read as `たべる`.
+///
+/// The kana the kanji and the reading end with are stripped to get their
+/// stems, and the word has to start with the stem of the kanji.
+fn word_reading(word: &str, mut kanji: &str, mut reading: &str) -> Option<String> {
+    while let (Some(a), Some(b)) = (kanji.chars().next_back(), reading.chars().next_back()) {
+        if a != b {
+            break;
+        }
+
+        kanji = &kanji[..kanji.len() - a.len_utf8()];
+        reading = &reading[..reading.len() - b.len_utf8()];
+    }
+
+    if kanji.is_empty() {
+        return None;
+    }
+
+    let rest = word.strip_prefix(kanji)?;
+    Some(format!("{reading}{rest}"))
+}
+
+#[test]
+fn test_word_reading() {
+    assert_eq!(
+        word_reading("食べた", "食べる", "たべる").as_deref(),
+        Some("たべた")
+    );
+    assert_eq!(
+        word_reading("日本語", "日本語", "にほんご").as_deref(),
+        Some("にほんご")
+    );
+    assert_eq!(word_reading("飲んだ", "食べる", "たべる"), None);
+}
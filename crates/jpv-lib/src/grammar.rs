@@ -0,0 +1,245 @@
+//! Detection of grammar patterns in glossed text.
+//!
+//! Patterns are matched over the segments produced by [`gloss::segments`],
+//! so that a user interface can show a note next to the words which make up
+//! a pattern like `～ている` or conditional `～ば`. Patterns which correspond
+//! to an inflection refer to its [`Form`], which has a title and a link to a
+//! tutorial.
+//!
+//! [`gloss::segments`]: crate::gloss::segments
+
+use crate::gloss::Segment;
+use crate::inflection::Form;
+
+/// A grammar pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pattern {
+    /// Identifier of the pattern, like `te-iru`.
+    pub id: &'static str,
+    /// The inflection the pattern corresponds to, if any.
+    pub form: Option<Form>,
+    /// Title of the pattern, which is the title of its form if it has one.
+    title: &'static str,
+    /// Ways the pattern is written.
+    suffixes: &'static [&'static str],
+    /// Whether the pattern has to end where a word ends, which avoids
+    /// matching short patterns in the middle of unrelated words. If the
+    /// pattern has a form, the word also has to be inflected into it, so that
+    /// words like `例えば` aren't mistaken for conditionals.
+    at_end: bool,
+}
+
+impl Pattern {
+    /// The title of the pattern.
+    pub fn title(&self) -> &'static str {
+        match self.form {
+            Some(form) => form.title(),
+            None => self.title,
+        }
+    }
+
+    /// Tutorial URL for the pattern.
+    pub fn url(&self) -> Option<&'static str> {
+        self.form?.url()
+    }
+}
+
+/// Patterns which are detected, in the order they are tried.
+pub const PATTERNS: &[Pattern] = &[
+    Pattern {
+        id: "nakereba-naranai",
+        form: None,
+        title: "～なければならない, must do ~, have to do ~",
+        suffixes: &[
+            "なければならない",
+            "なければなりません",
+            "なければいけない",
+            "なければいけません",
+            "なくてはならない",
+            "なくてはいけない",
+        ],
+        at_end: false,
+    },
+    Pattern {
+        id: "te-iru",
+        form: Some(Form::TeIru),
+        title: "",
+        suffixes: &[
+            "ている",
+            "ていた",
+            "ていない",
+            "ています",
+            "ていました",
+            "でいる",
+            "でいた",
+            "でいない",
+            "でいます",
+            "でいました",
+        ],
+        at_end: false,
+    },
+    Pattern {
+        id: "ba",
+        form: Some(Form::Hypothetical),
+        title: "",
+        suffixes: &[
+            "えば", "けば", "げば", "せば", "てば", "ねば", "べば", "めば", "れば",
+        ],
+        at_end: true,
+    },
+];
+
+/// A grammar pattern detected in a sequence of segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    /// The pattern which was detected.
+    pub pattern: &'static Pattern,
+    /// Index of the first segment which is part of the pattern.
+    pub start: usize,
+    /// Index one past the last segment which is part of the pattern.
+    pub end: usize,
+}
+
+/// Detect grammar patterns in the given segments.
+///
+/// A pattern may span several segments, like `食べて` followed by `いる`.
+/// Patterns don't overlap, and the one which is found first in [`PATTERNS`]
+/// wins.
+pub fn detect(segments: &[Segment]) -> Vec<Hint> {
+    let mut text = String::new();
+    // The byte offset at which each segment starts in the text.
+    let mut starts = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        starts.push(text.len());
+        text.push_str(&segment.text);
+    }
+
+    let segment_at = |offset: usize| starts.partition_point(|&start| start <= offset) - 1;
+
+    let mut hints = Vec::new();
+    let mut taken = vec![false; segments.len()];
+
+    for pattern in PATTERNS {
+        for suffix in pattern.suffixes {
+            for (offset, _) in text.match_indices(suffix) {
+                let end_offset = offset + suffix.len();
+                let mut start = segment_at(offset);
+                let last = segment_at(end_offset - 1);
+
+                // The pattern has to follow a word in the same segment or in
+                // the one right before it, in which case the hint covers that
+                // word too.
+                if offset == starts[start] {
+                    if start == 0 {
+                        continue;
+                    }
+
+                    start -= 1;
+                }
+
+                if pattern.at_end {
+                    let segment_end = starts[last] + segments[last].text.len();
+
+                    if end_offset != segment_end || segments[last].sequence.is_none() {
+                        continue;
+                    }
+
+                    if let Some(form) = pattern.form {
+                        let inflection = segments[last].inflection;
+
+                        if !inflection.is_some_and(|inflection| inflection.contains(form)) {
+                            continue;
+                        }
+                    }
+                }
+
+                if taken[start..=last].iter().any(|&taken| taken) {
+                    continue;
+                }
+
+                taken[start..=last].fill(true);
+
+                hints.push(Hint {
+                    pattern,
+                    start,
+                    end: last + 1,
+                });
+            }
+        }
+    }
+
+    hints.sort_by_key(|hint| hint.start);
+    hints
+}
+
+#[cfg(test)]
+fn segment(text: &str, word: bool) -> Segment {
+    Segment {
+        text: text.to_owned(),
+        reading: None,
+        sequence: word.then_some(1),
+        glosses: Vec::new(),
+        inflection: None,
+    }
+}
+
+#[cfg(test)]
+fn inflected(text: &str, forms: &[Form]) -> Segment {
+    let mut inflection = crate::inflection::Inflection::default();
+
+    for &form in forms {
+        inflection.insert(form);
+    }
+
+    Segment {
+        inflection: Some(inflection),
+        ..segment(text, true)
+    }
+}
+
+#[test]
+fn test_detect() {
+    let ids = |segments: &[Segment]| {
+        detect(segments)
+            .into_iter()
+            .map(|hint| (hint.pattern.id, hint.start, hint.end))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        ids(&[segment("本を", false), segment("読んでいる", true)]),
+        [("te-iru", 1, 2)]
+    );
+
+    assert_eq!(
+        ids(&[segment("食べて", true), segment("いました", true)]),
+        [("te-iru", 0, 2)]
+    );
+
+    assert_eq!(
+        ids(&[segment("行か", true), segment("なければならない", true)]),
+        [("nakereba-naranai", 0, 2)]
+    );
+
+    assert_eq!(
+        ids(&[
+            inflected("行けば", &[Form::Hypothetical]),
+            segment("わかる", true)
+        ]),
+        [("ba", 0, 1)]
+    );
+
+    // Words which end like a conditional but aren't inflected into one.
+    assert_eq!(ids(&[segment("例えば", true), segment("りんご", true)]), []);
+    assert_eq!(
+        ids(&[
+            inflected("行けば", &[Form::Potential]),
+            segment("わかる", true)
+        ]),
+        []
+    );
+
+    // Not at the end of a word.
+    assert_eq!(ids(&[segment("これば", false), segment("かり", false)]), []);
+}
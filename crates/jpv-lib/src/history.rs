@@ -0,0 +1,278 @@
+//! History of looked up words, and digests of it which encourage reviewing
+//! them.
+//!
+//! Lookups only refer to entries by sequence number, so the digest is
+//! generated here without access to the database and entries are resolved by
+//! whoever presents it.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use anyhow::Result;
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::jmdict;
+use crate::{storage, Dirs};
+
+/// The number of seconds in a day.
+const DAY: u64 = 24 * 60 * 60;
+
+/// The maximum number of lookups kept, older lookups are dropped.
+const MAX_LOOKUPS: usize = 10_000;
+
+/// Repeated lookups of the same entry within this many seconds are counted
+/// once, like when a search is refined.
+const REPEAT_WINDOW: u64 = 60;
+
+/// A single lookup of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup {
+    /// The sequence number of the entry looked up.
+    pub sequence: u32,
+    /// When the entry was looked up, in seconds since the Unix epoch.
+    pub at: u64,
+}
+
+/// The history of looked up entries, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default)]
+    lookups: Vec<Lookup>,
+    /// The day, counted from the Unix epoch, whose digest was last sent as a
+    /// notification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notified: Option<u64>,
+}
+
+impl History {
+    /// Load the history, which is empty if none has been recorded.
+    pub fn load(dirs: &Dirs) -> Result<Self> {
+        let path = dirs.history_path();
+        let history = storage::load(&path, |data| Ok(serde_json::from_str(data)?))?;
+        Ok(history.unwrap_or_default())
+    }
+
+    /// Lookups in the history, oldest first.
+    pub fn lookups(&self) -> &[Lookup] {
+        &self.lookups
+    }
+
+    /// Record a lookup, returning `false` if the same entry was looked up
+    /// within the last minute and the lookup wasn't recorded.
+    pub fn record(&mut self, sequence: u32, at: u64) -> bool {
+        let mut recent = self
+            .lookups
+            .iter()
+            .rev()
+            .take_while(|l| l.at + REPEAT_WINDOW > at);
+
+        if recent.any(|l| l.sequence == sequence) {
+            return false;
+        }
+
+        if self.lookups.len() >= MAX_LOOKUPS {
+            let excess = self.lookups.len() + 1 - MAX_LOOKUPS;
+            self.lookups.drain(..excess);
+        }
+
+        self.lookups.push(Lookup { sequence, at });
+        true
+    }
+
+    /// Mark the digest of the given day as sent, returning `false` if it
+    /// already was.
+    pub fn mark_notified(&mut self, day: u64) -> bool {
+        if self.notified >= Some(day) {
+            return false;
+        }
+
+        self.notified = Some(day);
+        true
+    }
+}
+
+/// The period a digest covers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Period {
+    /// The previous day.
+    #[default]
+    Yesterday,
+    /// The last seven days, including today.
+    Week,
+}
+
+impl Period {
+    /// Get the range of times covered by the period, in seconds since the Unix
+    /// epoch.
+    ///
+    /// Days start at local midnight, where `utc_offset` is the offset of local
+    /// time from UTC in seconds.
+    pub fn range(self, now: u64, utc_offset: i64) -> Range<u64> {
+        let today = day(now, utc_offset);
+        let start = |day: u64| (day * DAY).saturating_add_signed(-utc_offset);
+
+        match self {
+            Period::Yesterday => start(today.saturating_sub(1))..start(today),
+            Period::Week => start(today.saturating_sub(6))..now.saturating_add(1),
+        }
+    }
+
+    /// Describe the period, like `yesterday`.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Period::Yesterday => "yesterday",
+            Period::Week => "this week",
+        }
+    }
+}
+
+/// Get the local day of the given time, counted from the Unix epoch.
+pub fn day(at: u64, utc_offset: i64) -> u64 {
+    at.saturating_add_signed(utc_offset) / DAY
+}
+
+/// An entry in a digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Digested {
+    /// The sequence number of the entry.
+    pub sequence: u32,
+    /// How many times the entry was looked up.
+    pub count: usize,
+    /// When the entry was last looked up, in seconds since the Unix epoch.
+    pub last: u64,
+}
+
+/// Summarize lookups in the given range of times.
+///
+/// Lookups are grouped by entry, and entries are ranked by how many times they
+/// were looked up, with the most recently looked up entry first among those
+/// looked up equally often.
+pub fn digest(lookups: &[Lookup], range: Range<u64>) -> Vec<Digested> {
+    let mut grouped = BTreeMap::<u32, Digested>::new();
+
+    for lookup in lookups.iter().filter(|l| range.contains(&l.at)) {
+        let digested = grouped.entry(lookup.sequence).or_insert(Digested {
+            sequence: lookup.sequence,
+            count: 0,
+            last: lookup.at,
+        });
+
+        digested.count += 1;
+        digested.last = digested.last.max(lookup.at);
+    }
+
+    let mut output = grouped.into_values().collect::<Vec<_>>();
+    output.sort_by_key(|word| Reverse((word.count, word.last)));
+    output
+}
+
+/// A word in a digest, resolved against the database.
+pub struct Word<'a> {
+    /// How the word is written, preferring kanji.
+    pub text: &'a str,
+    pub digested: Digested,
+    pub entry: jmdict::Entry<'a>,
+}
+
+/// Resolve up to `limit` entries of a digest against the database.
+///
+/// Entries which can't be found, like ones provided by an index which has been
+/// disabled, are skipped.
+pub fn resolve<'a>(db: &'a Database, digest: &[Digested], limit: usize) -> Result<Vec<Word<'a>>> {
+    let mut output = Vec::new();
+
+    for &digested in digest {
+        if output.len() >= limit {
+            break;
+        }
+
+        let Some(entry) = db.sequence_to_entry(digested.sequence)? else {
+            continue;
+        };
+
+        let Some((kanji, reading)) = entry.preferred_form() else {
+            continue;
+        };
+
+        let text = kanji.map_or(reading.text, |k| k.text);
+
+        output.push(Word {
+            text,
+            digested,
+            entry,
+        });
+    }
+
+    Ok(output)
+}
+
+/// Get a `jpv://` link which searches for the given text.
+pub fn search_link(text: &str) -> impl fmt::Display + '_ {
+    struct Link<'a>(&'a str);
+
+    impl fmt::Display for Link<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("jpv://search?q=")?;
+
+            for c in self.0.chars() {
+                match c {
+                    '%' | '&' | '#' | '+' | '=' | '?' => write!(f, "%{:02X}", c as u32)?,
+                    ' ' => f.write_char('+')?,
+                    c => f.write_char(c)?,
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    Link(text)
+}
+
+#[test]
+fn test_digest() {
+    let lookup = |sequence, at| Lookup { sequence, at };
+
+    let mut history = History::default();
+    assert!(history.record(1, DAY + 10));
+    assert!(!history.record(1, DAY + 20));
+    assert!(history.record(2, DAY + 30));
+    assert!(history.record(1, DAY + 100));
+    assert_eq!(history.lookups().len(), 3);
+
+    let lookups = [
+        lookup(1, DAY - 1),
+        lookup(2, DAY + 10),
+        lookup(3, DAY + 20),
+        lookup(3, DAY + 30),
+        lookup(4, DAY + 40),
+        lookup(2, 2 * DAY),
+    ];
+
+    let range = Period::Yesterday.range(2 * DAY + 5, 0);
+    assert_eq!(range, DAY..2 * DAY);
+
+    let sequences = digest(&lookups, range)
+        .into_iter()
+        .map(|d| (d.sequence, d.count))
+        .collect::<Vec<_>>();
+
+    assert_eq!(sequences, [(3, 2), (4, 1), (2, 1)]);
+
+    // An hour ahead of UTC, the lookup just before midnight UTC was made
+    // yesterday as well.
+    let range = Period::Yesterday.range(2 * DAY + 5, 3600);
+    assert_eq!(range, DAY - 3600..2 * DAY - 3600);
+    assert_eq!(digest(&lookups, range).len(), 4);
+
+    assert_eq!(
+        search_link("食べる & 飲む").to_string(),
+        "jpv://search?q=食べる+%26+飲む"
+    );
+}
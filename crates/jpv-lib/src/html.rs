@@ -0,0 +1,38 @@
+//! Helpers shared by exporters which render standalone HTML documents.
+
+use std::fmt;
+
+use crate::furigana::Escape;
+
+/// Render a standalone HTML document with the given title and inline style,
+/// where the body is written by the given closure.
+pub(crate) fn document<'a, B>(title: &'a str, style: &'a str, body: B) -> impl fmt::Display + 'a
+where
+    B: 'a + Fn(&mut fmt::Formatter<'_>) -> fmt::Result,
+{
+    struct Document<'a, B>(&'a str, &'a str, B);
+
+    impl<B> fmt::Display for Document<'_, B>
+    where
+        B: Fn(&mut fmt::Formatter<'_>) -> fmt::Result,
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let Document(title, style, body) = self;
+
+            writeln!(f, "<!DOCTYPE html>")?;
+            writeln!(f, "<html lang=\"ja\">")?;
+            writeln!(f, "<head>")?;
+            writeln!(f, "<meta charset=\"utf-8\">")?;
+            writeln!(f, "<title>{}</title>", Escape(title))?;
+            writeln!(f, "<style>{style}</style>")?;
+            writeln!(f, "</head>")?;
+            writeln!(f, "<body>")?;
+            body(f)?;
+            writeln!(f, "</body>")?;
+            writeln!(f, "</html>")?;
+            Ok(())
+        }
+    }
+
+    Document(title, style, body)
+}
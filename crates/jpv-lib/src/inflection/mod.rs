@@ -93,6 +93,45 @@ form! {
     }
 }
 
+impl Form {
+    /// Parse a form from a keyword.
+    ///
+    /// This is either the kebab-case name of the form like `past` or `te-iru`,
+    /// its short description like `pot`, or `polite` for honorific forms.
+    pub fn parse_keyword(keyword: &str) -> Option<Form> {
+        if keyword == "polite" {
+            return Some(Form::Honorific);
+        }
+
+        Form::ALL
+            .into_iter()
+            .find(|form| form.describe() == keyword || form.keyword() == keyword)
+    }
+
+    /// The kebab-case keyword of the form, like `te-iru`.
+    pub fn keyword(&self) -> String {
+        kebab_case(&format!("{self:?}"))
+    }
+}
+
+fn kebab_case(name: &str) -> String {
+    let mut output = String::with_capacity(name.len());
+
+    for (n, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if n > 0 {
+                output.push('-');
+            }
+
+            output.push(c.to_ascii_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+
+    output
+}
+
 #[derive(
     Default,
     Clone,
@@ -161,6 +200,11 @@ impl Inflection {
         Self { form }
     }
 
+    /// Insert the given form.
+    pub fn insert(&mut self, form: Form) {
+        self.form.insert(form);
+    }
+
     /// Toggle the given form.
     pub fn toggle(&mut self, form: Form) {
         if self.form.contains(form) {
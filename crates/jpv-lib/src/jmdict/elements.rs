@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::entities::{Dialect, Field, KanjiInfo, Miscellaneous, PartOfSpeech, ReadingInfo};
 use crate::priority::Priority;
+use crate::sort_key::{frequency_boost, length_boost};
 use crate::Weight;
 
 #[borrowme::borrowme]
@@ -26,7 +27,7 @@ pub struct Entry<'a> {
     pub senses: Vec<Sense<'a>>,
 }
 
-impl Entry<'_> {
+impl<'a> Entry<'a> {
     /// Return all unique entities associated with an entry.
     pub fn visit_entities(&self, buf: &mut String, mut f: impl FnMut(&str)) {
         for sense in &self.senses {
@@ -80,8 +81,26 @@ impl Entry<'_> {
         buf.clear();
     }
 
-    /// Entry weight.
-    pub fn weight(&self, input: &str, conjugation: bool) -> Weight {
+    /// Iterate over the priorities of all reading and kanji elements.
+    pub fn priorities(&self) -> impl Iterator<Item = &Priority> + '_ {
+        let readings = self.reading_elements.iter().flat_map(|e| &e.priority);
+        let kanji = self.kanji_elements.iter().flat_map(|e| &e.priority);
+        readings.chain(kanji)
+    }
+
+    /// Test if the entry is usually written in kana, which is when it has no
+    /// kanji or every sense says so.
+    pub fn is_usually_kana(&self) -> bool {
+        self.kanji_elements.is_empty()
+            || self
+                .senses
+                .iter()
+                .all(|sense| sense.misc.contains(Miscellaneous::UsuallyKana))
+    }
+
+    /// Entry weight, where `frequency` is the rank of the entry in a word
+    /// frequency list if it's listed in one.
+    pub fn weight(&self, input: &str, conjugation: bool, frequency: Option<u32>) -> Weight {
         // Boost based on exact query.
         let mut query = 1.0f32;
         // Store the priority which performs the maximum boost.
@@ -91,32 +110,40 @@ impl Entry<'_> {
         // Conjugation boost.
         let conjugation = if conjugation { 1.2 } else { 1.0 };
         // Calculate length boost.
-        let length = (input.chars().count().min(10) as f32 / 10.0) * 1.2;
+        let length = length_boost(input);
+        // The priority of the elements matching the query, which takes
+        // precedence over the priority of the entry as a whole.
+        let mut matched = None::<f32>;
 
         for element in &self.reading_elements {
+            let p = priority_weight(&element.priority);
+
             if element.text == input {
                 if element.no_kanji || self.kanji_elements.iter().all(|k| k.is_rare()) {
                     query = query.max(3.0);
                 } else {
                     query = query.max(2.0);
                 }
-            }
 
-            for p in &element.priority {
-                priority = priority.max(p.weight());
+                matched = Some(matched.map_or(p, |m| m.max(p)));
             }
+
+            priority = priority.max(p);
         }
 
         for element in &self.kanji_elements {
+            let p = priority_weight(&element.priority);
+
             if element.text == input {
                 query = query.max(3.0);
+                matched = Some(matched.map_or(p, |m| m.max(p)));
             }
 
-            for p in &element.priority {
-                priority = priority.max(p.weight());
-            }
+            priority = priority.max(p);
         }
 
+        let priority = matched.unwrap_or(priority);
+
         for sense in &self.senses {
             for gloss in &sense.gloss {
                 if gloss.text == input {
@@ -125,10 +152,87 @@ impl Entry<'_> {
             }
         }
 
-        Weight::new(query * priority * sense_count * conjugation * length)
+        // Boost common words.
+        let frequency = frequency_boost(frequency);
+
+        Weight::new(query * priority * sense_count * conjugation * length * frequency)
+    }
+
+    /// The kanji and reading the entry is preferably displayed as, which are
+    /// the elements with the highest priority. Ties are broken by the order of
+    /// the elements in the entry.
+    pub fn preferred_form(&self) -> Option<(Option<&KanjiElement<'a>>, &ReadingElement<'a>)> {
+        let mut kanji = None::<(f32, &KanjiElement<'a>)>;
+
+        for element in &self.kanji_elements {
+            if element.is_search_only() {
+                continue;
+            }
+
+            let p = priority_weight(&element.priority);
+
+            if kanji.is_none_or(|(best, _)| p > best) {
+                kanji = Some((p, element));
+            }
+        }
+
+        let kanji = kanji.map(|(_, element)| element);
+        let mut reading = None::<(f32, &ReadingElement<'a>)>;
+
+        for element in &self.reading_elements {
+            let applies = match kanji {
+                Some(kanji) => element.applies_to(kanji.text),
+                None => !element.is_search_only(),
+            };
+
+            if !applies {
+                continue;
+            }
+
+            let p = priority_weight(&element.priority);
+
+            if reading.is_none_or(|(best, _)| p > best) {
+                reading = Some((p, element));
+            }
+        }
+
+        let reading = match reading {
+            Some((_, element)) => element,
+            None => self.reading_elements.first()?,
+        };
+
+        Some((kanji, reading))
+    }
+
+    /// Boost for entries with senses tagged with any of the given entities or
+    /// fields, so that specialized senses outrank general ones.
+    pub fn sense_boost(&self, entities: &[&str], fields: &[&str]) -> f32 {
+        if self.senses.is_empty() || entities.is_empty() && fields.is_empty() {
+            return 1.0;
+        }
+
+        let matching = self
+            .senses
+            .iter()
+            .filter(|sense| {
+                entities.iter().any(|e| sense.has_entity(e))
+                    || fields.iter().any(|f| sense.has_field(f))
+            })
+            .count();
+
+        if matching == 0 {
+            return 1.0;
+        }
+
+        2.0 + matching as f32 / self.senses.len() as f32
     }
 }
 
+/// The weight of the highest priority in the given collection.
+fn priority_weight(priority: &[Priority]) -> f32 {
+    priority.iter().map(Priority::weight).fold(1.0, f32::max)
+}
+
 #[borrowme::borrowme]
 #[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode)]
 #[musli(mode = Binary, packed)]
@@ -324,7 +428,15 @@ pub struct Glossary<'a> {
     pub lang: Option<&'a str>,
 }
 
-const DEFAULT_LANGUAGE: &str = "eng";
+impl Glossary<'_> {
+    /// Test if the gloss is in the given language.
+    pub fn is_lang(&self, arg: &str) -> bool {
+        self.lang.unwrap_or(DEFAULT_LANGUAGE) == arg
+    }
+}
+
+/// The language of glosses which don't specify one.
+pub(crate) const DEFAULT_LANGUAGE: &str = "eng";
 
 #[borrowme::borrowme]
 #[derive(Default, Clone, Debug, Serialize, Deserialize, Encode, Decode)]
@@ -365,6 +477,21 @@ pub struct Sense<'a> {
 }
 
 impl Sense<'_> {
+    /// Test if the sense is tagged with the given entity.
+    pub fn has_entity(&self, entity: &str) -> bool {
+        self.pos
+            .iter()
+            .any(|pos| pos.ident() == entity || pos.generic() == Some(entity))
+            || self.misc.iter().any(|misc| misc.ident() == entity)
+            || self.dialect.iter().any(|dialect| dialect.ident() == entity)
+            || self.has_field(entity)
+    }
+
+    /// Test if the sense belongs to the given field, like `comp`.
+    pub fn has_field(&self, field: &str) -> bool {
+        self.field.iter().any(|f| f.ident() == field)
+    }
+
     /// Test if sense applies to the current kanji.
     pub fn applies_to(&self, kanji: Option<&str>, reading: &str) -> bool {
         if let Some(kanji) = kanji {
@@ -451,13 +578,7 @@ impl Sense<'_> {
     }
 
     pub fn is_lang(&self, arg: &str) -> bool {
-        for g in &self.gloss {
-            if g.lang.unwrap_or(DEFAULT_LANGUAGE) == arg {
-                return true;
-            }
-        }
-
-        false
+        self.gloss.iter().any(|g| g.is_lang(arg))
     }
 }
 
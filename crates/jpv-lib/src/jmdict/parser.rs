@@ -11,6 +11,7 @@ use crate::jmdict::Entry;
 use crate::PartOfSpeech;
 use crate::Priority;
 
+use super::elements::DEFAULT_LANGUAGE;
 use super::{
     Example, ExampleSentence, ExampleSource, Glossary, KanjiElement, ReadingElement, Sense,
     SourceLanguage,
@@ -171,6 +172,9 @@ impl<'a> Parser<'a> {
                     ([.., State::Gloss(builder)], "g_type") => {
                         set_option!(builder.ty, value);
                     }
+                    ([.., State::Gloss(builder)], "lang") => {
+                        set_option!(builder.lang, value);
+                    }
                     ([.., State::ExampleSource(builder)], "exsrc_type") => {
                         set_option!(builder.ty, value);
                     }
@@ -299,7 +303,8 @@ impl<'a> Parser<'a> {
                             sense.gloss.push(Glossary {
                                 text: builder.text.context("Missing glossary text")?,
                                 ty: builder.ty,
-                                lang: builder.lang,
+                                // English glosses don't specify a language.
+                                lang: Some(builder.lang.unwrap_or(DEFAULT_LANGUAGE)),
                             });
                         }
                         ([.., State::Sense(sense)], State::Example(example)) => {
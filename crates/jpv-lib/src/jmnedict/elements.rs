@@ -2,6 +2,7 @@ use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
 use crate::entities::NameType;
+use crate::sort_key::length_boost;
 use crate::Weight;
 
 #[borrowme::borrowme]
@@ -32,7 +33,7 @@ impl Entry<'_> {
         // Boost based on exact query.
         let mut query = 1.0f32;
         // Calculate length boost.
-        let length = (input.chars().count().min(10) as f32 / 10.0) * 1.2;
+        let length = length_boost(input);
 
         for element in self.kanji.iter().copied() {
             if element == input {
@@ -46,6 +47,12 @@ impl Entry<'_> {
             }
         }
 
+        for translation in self.translations.iter() {
+            if translation.text.eq_ignore_ascii_case(input) {
+                query = query.max(2.0);
+            }
+        }
+
         Weight::new(query * length)
     }
 }
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+/// Frequencies of names from an auxiliary dataset, like lists derived from a
+/// census, used to rank names by how common they are.
+///
+/// The input has one name per line followed by how common it is, separated by
+/// a tab, a comma or spaces. Names can be written in kanji or kana. Empty
+/// lines, lines starting with `#` and lines without a valid count are ignored.
+#[derive(Debug, Default)]
+pub struct Frequencies<'a> {
+    names: HashMap<&'a str, u32>,
+}
+
+impl<'a> Frequencies<'a> {
+    /// Parse name frequencies.
+    pub fn parse(input: &'a str) -> Self {
+        let mut names = HashMap::new();
+
+        for line in input.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((name, count)) = line.rsplit_once(['\t', ',', ' ']) else {
+                continue;
+            };
+
+            let Ok(count) = count.trim().parse::<u32>() else {
+                continue;
+            };
+
+            let name = name.trim_end_matches(['\t', ',', ' ']);
+
+            if name.is_empty() {
+                continue;
+            }
+
+            let current = names.entry(name).or_default();
+            *current = count.max(*current);
+        }
+
+        Self { names }
+    }
+
+    /// Test if there are no frequencies.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Get the frequency of a name, if it's known.
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.names.get(name).copied()
+    }
+}
+
+#[test]
+fn test_parse() {
+    let frequencies =
+        Frequencies::parse("# surnames\n佐藤\t1928000\nすずき,1707000\n高橋 1416000\n\nbad\n");
+    assert_eq!(frequencies.get("佐藤"), Some(1928000));
+    assert_eq!(frequencies.get("すずき"), Some(1707000));
+    assert_eq!(frequencies.get("高橋"), Some(1416000));
+    assert_eq!(frequencies.get("bad"), None);
+}
@@ -1,5 +1,8 @@
 pub use self::parser::Parser;
 pub mod parser;
 
+pub use self::frequencies::Frequencies;
+mod frequencies;
+
 pub use self::elements::{Entry, OwnedEntry, OwnedReading, OwnedTranslation, Reading, Translation};
 mod elements;
@@ -38,6 +38,37 @@ pub fn is_hiragana_lower(c: char) -> bool {
     matches!(get_hiragana(c), Some(c) if matches!(c, Class::L))
 }
 
+/// Test if a character is Japanese script, which is either kana, kanji or
+/// one of the marks which accompany them. Unlike [`is_kanji`] this makes no
+/// assumptions about the character.
+pub fn is_japanese(c: char) -> bool {
+    is_hiragana(c)
+        || is_katakana(c)
+        || matches!(
+            c,
+            'ー' | '々' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}'
+        )
+}
+
+/// Convert a katakana character to hiragana, leaving everything else as-is.
+///
+/// The hiragana is encoded with the same number of bytes as the katakana, so
+/// byte offsets into converted text apply to the original text.
+pub fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        c => c,
+    }
+}
+
+/// Convert a hiragana character to katakana, leaving everything else as-is.
+pub fn to_katakana(c: char) -> char {
+    match c {
+        'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+        c => c,
+    }
+}
+
 fn get_katakana(c: char) -> Option<Class> {
     let c = usize::try_from(c as u32).ok()?;
     let c = c.checked_sub(tables::KATA_B)?;
@@ -0,0 +1,177 @@
+use musli::{Decode, Encode};
+
+use super::{is_hiragana_lower, to_hiragana};
+
+/// How a reading was changed to produce a confusable variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Confusion {
+    /// A vowel was lengthened or shortened, like おばさん and おばあさん.
+    VowelLength,
+    /// A consonant was voiced or unvoiced, like はし and ばし.
+    Voicing,
+    /// A consonant was doubled or undoubled, like きて and きって.
+    Gemination,
+}
+
+/// Voicing groups, where every kana in a group can be confused with the
+/// others.
+const VOICING: &[&[char]] = &[
+    &['か', 'が'],
+    &['き', 'ぎ'],
+    &['く', 'ぐ'],
+    &['け', 'げ'],
+    &['こ', 'ご'],
+    &['さ', 'ざ'],
+    &['し', 'じ'],
+    &['す', 'ず'],
+    &['せ', 'ぜ'],
+    &['そ', 'ぞ'],
+    &['た', 'だ'],
+    &['ち', 'ぢ'],
+    &['つ', 'づ'],
+    &['て', 'で'],
+    &['と', 'ど'],
+    &['は', 'ば', 'ぱ'],
+    &['ひ', 'び', 'ぴ'],
+    &['ふ', 'ぶ', 'ぷ'],
+    &['へ', 'べ', 'ぺ'],
+    &['ほ', 'ぼ', 'ぽ'],
+];
+
+/// Generate readings which are easily confused with the given reading when
+/// listening, because they only differ in vowel length, voicing or a doubled
+/// consonant.
+///
+/// The reading is expected to be hiragana, and katakana is converted to
+/// hiragana. The reading itself is not included among the variants.
+pub fn confusions(reading: &str) -> Vec<(Confusion, String)> {
+    let chars = reading.chars().map(to_hiragana).collect::<Vec<_>>();
+    let mut output = Vec::new();
+
+    let mut push = |confusion: Confusion, chars: Vec<char>| {
+        let variant = chars.into_iter().collect::<String>();
+
+        if !output.iter().any(|(_, v)| *v == variant) {
+            output.push((confusion, variant));
+        }
+    };
+
+    for (n, &c) in chars.iter().enumerate() {
+        let next = chars.get(n + 1).copied();
+
+        if let Some(vowel) = vowel(c) {
+            if next.is_some_and(|next| lengthens(vowel, next)) {
+                let mut variant = chars.clone();
+                variant.remove(n + 1);
+                push(Confusion::VowelLength, variant);
+            } else if !next.is_some_and(is_hiragana_lower) {
+                let mut variant = chars.clone();
+                variant.insert(n + 1, long_vowel(vowel));
+                push(Confusion::VowelLength, variant);
+            }
+        }
+
+        if let Some(group) = VOICING.iter().find(|group| group.contains(&c)) {
+            for &other in group.iter().filter(|&&other| other != c) {
+                let mut variant = chars.clone();
+                variant[n] = other;
+                push(Confusion::Voicing, variant);
+            }
+        }
+
+        if c == 'っ' {
+            let mut variant = chars.clone();
+            variant.remove(n);
+            push(Confusion::Gemination, variant);
+        } else if n > 0 && chars[n - 1] != 'っ' && can_geminate(c) {
+            let mut variant = chars.clone();
+            variant.insert(n, 'っ');
+            push(Confusion::Gemination, variant);
+        }
+    }
+
+    output
+}
+
+/// Get the vowel a kana ends in.
+fn vowel(c: char) -> Option<char> {
+    Some(match c {
+        'あ' | 'か' | 'が' | 'さ' | 'ざ' | 'た' | 'だ' | 'な' | 'は' | 'ば' | 'ぱ' | 'ま'
+        | 'や' | 'ら' | 'わ' | 'ゃ' | 'ぁ' => 'あ',
+        'い' | 'き' | 'ぎ' | 'し' | 'じ' | 'ち' | 'ぢ' | 'に' | 'ひ' | 'び' | 'ぴ' | 'み'
+        | 'り' | 'ぃ' => 'い',
+        'う' | 'く' | 'ぐ' | 'す' | 'ず' | 'つ' | 'づ' | 'ぬ' | 'ふ' | 'ぶ' | 'ぷ' | 'む'
+        | 'ゆ' | 'る' | 'ゅ' | 'ぅ' => 'う',
+        'え' | 'け' | 'げ' | 'せ' | 'ぜ' | 'て' | 'で' | 'ね' | 'へ' | 'べ' | 'ぺ' | 'め'
+        | 'れ' | 'ぇ' => 'え',
+        'お' | 'こ' | 'ご' | 'そ' | 'ぞ' | 'と' | 'ど' | 'の' | 'ほ' | 'ぼ' | 'ぽ' | 'も'
+        | 'よ' | 'ろ' | 'を' | 'ょ' | 'ぉ' => 'お',
+        _ => return None,
+    })
+}
+
+/// Test if the kana following a vowel lengthens it.
+fn lengthens(vowel: char, next: char) -> bool {
+    next == 'ー'
+        || next == vowel
+        || matches!((vowel, next), ('お', 'う') | ('え', 'い') | ('う', 'ぅ'))
+}
+
+/// The kana which is most commonly used to lengthen a vowel.
+fn long_vowel(vowel: char) -> char {
+    match vowel {
+        'お' => 'う',
+        'え' => 'い',
+        vowel => vowel,
+    }
+}
+
+/// Test if a kana starts with a consonant which can be doubled with っ.
+fn can_geminate(c: char) -> bool {
+    matches!(
+        c,
+        'か' | 'き'
+            | 'く'
+            | 'け'
+            | 'こ'
+            | 'さ'
+            | 'し'
+            | 'す'
+            | 'せ'
+            | 'そ'
+            | 'た'
+            | 'ち'
+            | 'つ'
+            | 'て'
+            | 'と'
+            | 'ぱ'
+            | 'ぴ'
+            | 'ぷ'
+            | 'ぺ'
+            | 'ぽ'
+    )
+}
+
+#[test]
+fn test_confusions() {
+    fn variants(reading: &str, confusion: Confusion) -> Vec<String> {
+        confusions(reading)
+            .into_iter()
+            .filter(|(c, _)| *c == confusion)
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    assert!(variants("おばさん", Confusion::VowelLength).contains(&String::from("おばあさん")));
+    assert!(variants("おばあさん", Confusion::VowelLength).contains(&String::from("おばさん")));
+    assert!(variants("びょういん", Confusion::VowelLength).contains(&String::from("びょいん")));
+    assert_eq!(
+        variants("はし", Confusion::Voicing),
+        ["ばし", "ぱし", "はじ"]
+    );
+    assert!(variants("きて", Confusion::Gemination).contains(&String::from("きって")));
+    assert!(variants("きって", Confusion::Gemination).contains(&String::from("きて")));
+    assert!(!confusions("はし").iter().any(|(_, v)| v == "はし"));
+    assert!(variants("ハシ", Confusion::Voicing).contains(&String::from("ばし")));
+}
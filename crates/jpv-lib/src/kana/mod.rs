@@ -1,10 +1,14 @@
 mod classify;
 #[doc(inline)]
 pub use self::classify::{
-    is_hiragana, is_hiragana_lower, is_hiragana_upper, is_kanji, is_katakana, is_katakana_lower,
-    is_katakana_upper,
+    is_hiragana, is_hiragana_lower, is_hiragana_upper, is_japanese, is_kanji, is_katakana,
+    is_katakana_lower, is_katakana_upper, to_hiragana, to_katakana,
 };
 
+mod confusion;
+#[doc(inline)]
+pub use self::confusion::{confusions, Confusion};
+
 use core::fmt;
 
 use crate::concat::Concat;
@@ -147,3 +151,35 @@ impl fmt::Display for Fragments<'_> {
         Ok(())
     }
 }
+
+/// Calculate the percentage of letters in the text which are Japanese script,
+/// as determined by [`is_japanese`]. Whitespace, punctuation and symbols are
+/// ignored, and text without any letters is `0` percent Japanese.
+pub fn japanese_percentage(text: &str) -> u32 {
+    let mut total = 0u64;
+    let mut japanese = 0u64;
+
+    for c in text.chars() {
+        if is_japanese(c) {
+            japanese += 1;
+            total += 1;
+        } else if c.is_alphanumeric() {
+            total += 1;
+        }
+    }
+
+    if total == 0 {
+        return 0;
+    }
+
+    (japanese * 100 / total) as u32
+}
+
+#[test]
+fn test_japanese_percentage() {
+    assert_eq!(japanese_percentage(""), 0);
+    assert_eq!(japanese_percentage("hello world"), 0);
+    assert_eq!(japanese_percentage("日本語を勉強しています。"), 100);
+    assert_eq!(japanese_percentage("ラーメン、食べたい！"), 100);
+    assert_eq!(japanese_percentage("ab 日本"), 50);
+}
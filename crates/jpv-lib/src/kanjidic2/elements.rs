@@ -1,6 +1,7 @@
 use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
+use crate::sort_key::length_boost;
 use crate::Weight;
 
 #[borrowme::borrowme]
@@ -48,7 +49,7 @@ impl Character<'_> {
         // Boost based on exact query.
         let mut query = 1.0f32;
         // Calculate length boost.
-        let length = (input.chars().count().min(10) as f32 / 10.0) * 1.2;
+        let length = length_boost(input);
 
         if self.literal == input {
             query = query.max(3.0);
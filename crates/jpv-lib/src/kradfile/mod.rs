@@ -1,6 +1,6 @@
 use std::str;
 
-use encoding_rs::{DecoderResult, EUC_JP};
+use encoding_rs::{DecoderResult, Encoding};
 use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
@@ -20,12 +20,40 @@ pub struct Entry<'a> {
 pub struct Parser<'a> {
     input: &'a [u8],
     pos: usize,
+    encoding: &'static Encoding,
+    skipped: usize,
 }
 
 impl<'a> Parser<'a> {
     /// Construct a new KRADFILE parser.
+    ///
+    /// The encoding of the input is detected automatically, see
+    /// [`crate::encoding::detect`].
     pub fn new(input: &'a [u8]) -> Self {
-        Self { input, pos: 0 }
+        let encoding = crate::encoding::detect(input);
+
+        let pos = match Encoding::for_bom(input) {
+            Some((_, len)) => len,
+            None => 0,
+        };
+
+        Self {
+            input,
+            pos,
+            encoding,
+            skipped: 0,
+        }
+    }
+
+    /// The encoding which was detected for the input.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The number of lines which were skipped since they could not be decoded
+    /// or parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
     }
 
     /// Step to the next byte.
@@ -55,6 +83,10 @@ impl<'a> Parser<'a> {
                 self.advance();
             }
 
+            if self.is_eof() {
+                break;
+            }
+
             if self.peek() == b'#' {
                 while !matches!(self.peek(), b'\n' | NUL) {
                     self.advance();
@@ -72,25 +104,25 @@ impl<'a> Parser<'a> {
             let end = self.pos;
             self.advance();
 
-            let mut decoder = EUC_JP.new_decoder();
+            let mut decoder = self.encoding.new_decoder_without_bom_handling();
             let (result, _, written) =
                 decoder.decode_to_utf8_without_replacement(&self.input[start..end], &mut buf, true);
 
             match result {
                 DecoderResult::InputEmpty => {}
-                DecoderResult::OutputFull => {
-                    continue;
-                }
-                DecoderResult::Malformed(..) => {
+                DecoderResult::OutputFull | DecoderResult::Malformed(..) => {
+                    self.skipped += 1;
                     continue;
                 }
             }
 
             let Ok(line) = str::from_utf8(&buf[..written]) else {
+                self.skipped += 1;
                 continue;
             };
 
             let Some((kanji, remainder)) = line.split_once(" : ") else {
+                self.skipped += 1;
                 continue;
             };
 
@@ -8,7 +8,11 @@
 /// Dictionary magic `JPVD`.
 pub const DATABASE_MAGIC: u32 = 0x4a_50_56_44;
 /// Current database version in use.
-pub const DATABASE_VERSION: u32 = 11;
+///
+/// Indexes built with a different version have to be rebuilt. User data only
+/// refers to entries by sequence ids and is re-resolved against the new
+/// indexes, see [`config::CONFIG_VERSION`].
+pub const DATABASE_VERSION: u32 = 32;
 
 /// Helper to convert a type to its owned variant.
 pub use ::borrowme::to_owned;
@@ -30,6 +34,8 @@ pub use self::inflection::{Form, Inflection, Inflections, OwnedInflections};
 
 pub mod config;
 
+pub mod storage;
+
 pub mod data;
 
 pub mod api;
@@ -43,10 +49,29 @@ pub use self::concat::Concat;
 pub use self::sort_key::{Key, Weight};
 mod sort_key;
 
+pub mod clipboard;
+pub mod drill;
+pub mod encoding;
+pub mod examples;
+pub mod gloss;
+pub mod grammar;
+pub mod history;
+pub mod locale;
+pub mod phrasebook;
+pub mod sheet;
+pub mod typing;
+
+mod html;
+
+pub mod frequency;
 pub mod jmdict;
 pub mod jmnedict;
 pub mod kanjidic2;
 pub mod kradfile;
+pub mod pitch;
+pub mod radkfile;
+pub mod tatoeba;
+pub mod yomichan;
 
 pub mod entities;
 pub use self::entities::PartOfSpeech;
@@ -54,12 +79,14 @@ pub use self::entities::PartOfSpeech;
 mod furigana;
 pub use self::furigana::{Furigana, FuriganaGroup, OwnedFurigana};
 
+pub mod accent;
 pub mod kana;
 pub mod morae;
 pub mod romaji;
+pub mod variants;
 
 mod priority;
-pub use self::priority::Priority;
+pub use self::priority::{Priority, PriorityFilter};
 
 pub mod database;
 
@@ -0,0 +1,217 @@
+//! Locale-aware formatting of numbers, sizes and dates in human-facing
+//! strings.
+//!
+//! Only a handful of locales are told apart, by how they group digits,
+//! separate decimals and write dates. Formatted strings are always sent
+//! alongside the raw values they're formatted from, so clients are free to
+//! format them differently.
+
+use std::env;
+
+/// The number of seconds in a day.
+const DAY: u64 = 24 * 60 * 60;
+
+/// Abbreviated English month names.
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A locale used to format human-facing strings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Japanese,
+    German,
+    French,
+    Swedish,
+}
+
+impl Locale {
+    /// Pick the preferred supported locale from an `Accept-Language` header,
+    /// like `sv-SE,sv;q=0.9,en;q=0.8`, falling back to English.
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut best = None::<(f32, Self)>;
+
+        for range in header.split(',') {
+            let mut params = range.split(';');
+            let tag = params.next().unwrap_or_default().trim();
+
+            let q = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            let Some(locale) = Self::from_tag(tag) else {
+                continue;
+            };
+
+            if best.is_none_or(|(best, _)| q > best) {
+                best = Some((q, locale));
+            }
+        }
+
+        best.map(|(_, locale)| locale).unwrap_or_default()
+    }
+
+    /// Get the locale of the current process from the environment, like
+    /// `LANG=sv_SE.UTF-8`, falling back to English.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_tag(&value))
+            .unwrap_or_default()
+    }
+
+    /// Get the locale of a language tag, like `en-US` or `sv_SE.UTF-8`.
+    fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_', '.']).next()?;
+
+        Some(match language.to_ascii_lowercase().as_str() {
+            "en" => Self::English,
+            "ja" => Self::Japanese,
+            "de" => Self::German,
+            "fr" => Self::French,
+            "sv" => Self::Swedish,
+            _ => return None,
+        })
+    }
+
+    /// The separator between groups of digits, and the decimal separator.
+    fn separators(self) -> (&'static str, char) {
+        match self {
+            Self::English | Self::Japanese => (",", '.'),
+            Self::German => (".", ','),
+            Self::French => ("\u{202f}", ','),
+            Self::Swedish => ("\u{a0}", ','),
+        }
+    }
+
+    /// Format a whole number, like `12,345`.
+    pub fn number(self, n: u64) -> String {
+        let (group, _) = self.separators();
+        let digits = n.to_string();
+        let mut output = String::new();
+
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                output.push_str(group);
+            }
+
+            output.push(c);
+        }
+
+        output
+    }
+
+    /// Format a number with the given number of decimals, like `1,234.5`.
+    pub fn decimal(self, value: f64, decimals: usize) -> String {
+        let (_, separator) = self.separators();
+        let formatted = format!("{:.*}", decimals, value.abs());
+
+        let (whole, fraction) = match formatted.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+
+        let mut output = String::new();
+
+        if value < 0.0 && formatted.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            output.push('-');
+        }
+
+        output.push_str(&self.number(whole.parse().unwrap_or_default()));
+
+        if let Some(fraction) = fraction {
+            output.push(separator);
+            output.push_str(fraction);
+        }
+
+        output
+    }
+
+    /// Format a size in bytes, like `1.5 MiB`.
+    pub fn size(self, size: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+        let mut value = size as f64;
+        let mut unit = 0;
+
+        while value >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            format!("{} {}", self.number(size), UNITS[unit])
+        } else {
+            format!("{} {}", self.decimal(value, 1), UNITS[unit])
+        }
+    }
+
+    /// Format the date of a time in seconds since the Unix epoch, like
+    /// `Jan 5, 2024`, where `utc_offset` is the offset of local time from UTC
+    /// in seconds.
+    pub fn date(self, at: u64, utc_offset: i64) -> String {
+        let (year, month, day) = civil_from_days(at.saturating_add_signed(utc_offset) / DAY);
+
+        match self {
+            Self::English => format!("{} {day}, {year}", MONTHS[(month - 1) as usize]),
+            Self::Japanese => format!("{year}年{month}月{day}日"),
+            Self::German => format!("{day}.{month}.{year}"),
+            Self::French => format!("{day:02}/{month:02}/{year}"),
+            Self::Swedish => format!("{year}-{month:02}-{day:02}"),
+        }
+    }
+}
+
+/// Convert a number of days since the Unix epoch into a year, month and day.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn test_locale() {
+    assert_eq!(
+        Locale::from_accept_language("sv-SE,sv;q=0.9,en;q=0.8"),
+        Locale::Swedish
+    );
+    assert_eq!(
+        Locale::from_accept_language("nl;q=1.0, en;q=0.5, ja;q=0.7"),
+        Locale::Japanese
+    );
+    assert_eq!(Locale::from_accept_language("nl"), Locale::English);
+    assert_eq!(Locale::from_tag("de_DE.UTF-8"), Some(Locale::German));
+
+    assert_eq!(Locale::English.number(0), "0");
+    assert_eq!(Locale::English.number(1234567), "1,234,567");
+    assert_eq!(Locale::German.number(123456), "123.456");
+    assert_eq!(Locale::English.decimal(1234.56, 1), "1,234.6");
+    assert_eq!(Locale::Swedish.decimal(-0.5, 1), "-0,5");
+    assert_eq!(Locale::English.decimal(-0.01, 1), "0.0");
+    assert_eq!(Locale::English.size(512), "512 B");
+    assert_eq!(Locale::German.size(1536), "1,5 KiB");
+
+    // 2024-01-05 12:00 UTC.
+    let at = 1_704_456_000;
+    assert_eq!(Locale::English.date(at, 0), "Jan 5, 2024");
+    assert_eq!(Locale::Japanese.date(at, 0), "2024年1月5日");
+    assert_eq!(Locale::German.date(at, 0), "5.1.2024");
+    assert_eq!(Locale::French.date(at, 0), "05/01/2024");
+    assert_eq!(Locale::Swedish.date(at, 13 * 3600), "2024-01-06");
+    assert_eq!(Locale::Swedish.date(0, 0), "1970-01-01");
+    assert_eq!(Locale::Swedish.date(951_782_400, 0), "2000-02-29");
+}
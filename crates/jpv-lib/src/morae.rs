@@ -17,9 +17,12 @@ impl<'a> Iterator for Morae<'a> {
         let mut it = self.input.chars();
 
         let a = it.next()?;
-        let b = it
-            .next()
-            .map(|c| (c, kana::is_hiragana_lower(c) || kana::is_katakana_lower(c)));
+        // The small tsu is a mora of its own rather than part of the one
+        // before it.
+        let b = it.next().map(|c| {
+            let small = kana::is_hiragana_lower(c) || kana::is_katakana_lower(c);
+            (c, small && !matches!(c, 'っ' | 'ッ'))
+        });
 
         let head = match (kana::is_hiragana_upper(a) || kana::is_katakana_upper(a), b) {
             (true, Some((b, true))) => {
@@ -53,4 +56,8 @@ fn count_morae() {
     let input = "モーラ";
     let morae: Vec<_> = iter(input).collect();
     assert_eq!(morae, vec!["モ", "ー", "ラ"]);
+
+    let input = "がっこう";
+    let morae: Vec<_> = iter(input).collect();
+    assert_eq!(morae, vec!["が", "っ", "こ", "う"]);
 }
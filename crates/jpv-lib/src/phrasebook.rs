@@ -0,0 +1,416 @@
+//! A phrasebook of saved phrases and sentences with notes, sorted into
+//! categories like `travel` or `work`.
+//!
+//! Unlike pins, which refer to dictionary entries, phrases are free text so
+//! they're kept in a store of their own next to the history of looked up
+//! words.
+
+use std::fmt::{self, Write};
+
+use anyhow::Result;
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{storage, Dirs};
+
+/// A phrase or category which can't be saved to the phrasebook.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("Phrase is empty")]
+    EmptyPhrase,
+    #[error("Category `{0}` must not be empty or surrounded by whitespace")]
+    Category(String),
+}
+
+/// A phrase in the phrasebook.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Phrase {
+    /// The identifier of the phrase, assigned when it's added.
+    pub id: u64,
+    /// The phrase itself, like `駅はどこですか`.
+    pub text: String,
+    /// The reading of the phrase in kana.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[musli(default, skip_encoding_if = String::is_empty)]
+    pub reading: String,
+    /// What the phrase means.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[musli(default, skip_encoding_if = String::is_empty)]
+    pub translation: String,
+    /// Free form notes, like when the phrase is used.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[musli(default, skip_encoding_if = String::is_empty)]
+    pub notes: String,
+    /// The category the phrase is sorted into.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[musli(default, skip_encoding_if = Option::is_none)]
+    pub category: Option<String>,
+    /// When the phrase was added, in seconds since the Unix epoch.
+    #[serde(default)]
+    #[musli(default)]
+    pub added: u64,
+}
+
+impl Phrase {
+    /// Test if the phrase contains the given text, ignoring case.
+    fn contains(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+
+        [&self.text, &self.reading, &self.translation, &self.notes]
+            .into_iter()
+            .any(|field| field.to_lowercase().contains(&text))
+    }
+}
+
+/// The phrasebook, with phrases in the order they were added.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Phrasebook {
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    phrases: Vec<Phrase>,
+    /// The identifier assigned to the next phrase which is added.
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl Phrasebook {
+    /// Load the phrasebook, which is empty if nothing has been saved to it.
+    pub fn load(dirs: &Dirs) -> Result<Self> {
+        let path = dirs.phrasebook_path();
+        let phrasebook = storage::load(&path, |data| Ok(serde_json::from_str(data)?))?;
+        Ok(phrasebook.unwrap_or_default())
+    }
+
+    /// Categories in the phrasebook, in the order they were added.
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// Phrases in the phrasebook, in the order they were added.
+    pub fn phrases(&self) -> &[Phrase] {
+        &self.phrases
+    }
+
+    /// Get the phrase with the given identifier.
+    pub fn get(&self, id: u64) -> Option<&Phrase> {
+        self.phrases.iter().find(|p| p.id == id)
+    }
+
+    /// Add a phrase, assigning it a new identifier.
+    ///
+    /// The category of the phrase is added if it doesn't exist.
+    pub fn add(&mut self, mut phrase: Phrase, at: u64) -> Result<&Phrase> {
+        validate(&phrase)?;
+        self.ensure_category(phrase.category.as_deref());
+
+        self.next_id = self.next_id.max(1);
+        phrase.id = self.next_id;
+        phrase.added = at;
+        self.next_id += 1;
+
+        self.phrases.push(phrase);
+        Ok(&self.phrases[self.phrases.len() - 1])
+    }
+
+    /// Replace the phrase with the same identifier, keeping when it was added.
+    ///
+    /// Returns `None` if there is no such phrase.
+    pub fn update(&mut self, mut phrase: Phrase) -> Result<Option<&Phrase>> {
+        validate(&phrase)?;
+
+        let Some(index) = self.phrases.iter().position(|p| p.id == phrase.id) else {
+            return Ok(None);
+        };
+
+        self.ensure_category(phrase.category.as_deref());
+
+        phrase.added = self.phrases[index].added;
+        self.phrases[index] = phrase;
+        Ok(Some(&self.phrases[index]))
+    }
+
+    /// Remove the phrase with the given identifier.
+    pub fn remove(&mut self, id: u64) -> Option<Phrase> {
+        let index = self.phrases.iter().position(|p| p.id == id)?;
+        Some(self.phrases.remove(index))
+    }
+
+    /// Add a category, returning `false` if it already exists.
+    pub fn add_category(&mut self, name: &str) -> Result<bool> {
+        let name = category_name(name)?;
+
+        if self.categories.iter().any(|c| c == name) {
+            return Ok(false);
+        }
+
+        self.categories.push(name.to_owned());
+        Ok(true)
+    }
+
+    /// Rename a category and move its phrases along with it, merging it into
+    /// the category it's renamed to if that already exists.
+    ///
+    /// Returns `false` if there is no such category.
+    pub fn rename_category(&mut self, from: &str, to: &str) -> Result<bool> {
+        let to = category_name(to)?;
+
+        let Some(index) = self.categories.iter().position(|c| c == from) else {
+            return Ok(false);
+        };
+
+        if from == to {
+            return Ok(true);
+        }
+
+        if self.categories.iter().any(|c| c == to) {
+            self.categories.remove(index);
+        } else {
+            self.categories[index] = to.to_owned();
+        }
+
+        for phrase in &mut self.phrases {
+            if phrase.category.as_deref() == Some(from) {
+                phrase.category = Some(to.to_owned());
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Remove a category, keeping its phrases without a category.
+    ///
+    /// Returns `false` if there is no such category.
+    pub fn remove_category(&mut self, name: &str) -> bool {
+        let Some(index) = self.categories.iter().position(|c| c == name) else {
+            return false;
+        };
+
+        self.categories.remove(index);
+
+        for phrase in &mut self.phrases {
+            if phrase.category.as_deref() == Some(name) {
+                phrase.category = None;
+            }
+        }
+
+        true
+    }
+
+    /// Find phrases which contain every one of the given terms, optionally
+    /// only in the given category.
+    ///
+    /// If no terms are given every phrase in the category matches.
+    pub fn search<'a>(
+        &'a self,
+        category: Option<&'a str>,
+        terms: &'a [&str],
+    ) -> impl Iterator<Item = &'a Phrase> + 'a {
+        self.phrases.iter().filter(move |phrase| {
+            if category.is_some() && phrase.category.as_deref() != category {
+                return false;
+            }
+
+            terms.iter().all(|term| phrase.contains(term))
+        })
+    }
+
+    fn ensure_category(&mut self, name: Option<&str>) {
+        if let Some(name) = name {
+            if !self.categories.iter().any(|c| c == name) {
+                self.categories.push(name.to_owned());
+            }
+        }
+    }
+}
+
+fn validate(phrase: &Phrase) -> Result<(), Error> {
+    if phrase.text.trim().is_empty() {
+        return Err(Error::EmptyPhrase);
+    }
+
+    if let Some(category) = &phrase.category {
+        category_name(category)?;
+    }
+
+    Ok(())
+}
+
+fn category_name(name: &str) -> Result<&str, Error> {
+    if name.trim().is_empty() || name.trim() != name {
+        return Err(Error::Category(name.to_owned()));
+    }
+
+    Ok(name)
+}
+
+/// The format the phrasebook is exported in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Format {
+    /// Comma-separated values with a header, for spreadsheets.
+    #[default]
+    Csv,
+    /// Tab-separated notes which Anki can import, with the phrase on the front,
+    /// its reading, translation and notes on the back, and the category as a
+    /// tag.
+    Anki,
+}
+
+impl Format {
+    /// The content type of an export in this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Anki => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// The file extension of an export in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Anki => "txt",
+        }
+    }
+}
+
+/// Export phrases in the given format.
+pub fn export<'a, I>(format: Format, phrases: I) -> String
+where
+    I: IntoIterator<Item = &'a Phrase>,
+{
+    let mut output = String::new();
+
+    match format {
+        Format::Csv => {
+            output.push_str("text,reading,translation,notes,category\n");
+
+            for p in phrases {
+                let category = p.category.as_deref().unwrap_or_default();
+                let fields = [&p.text[..], &p.reading, &p.translation, &p.notes, category];
+                write_row(&mut output, ',', fields);
+            }
+        }
+        Format::Anki => {
+            output.push_str("#separator:tab\n#html:false\n#tags column:3\n");
+
+            for p in phrases {
+                let back = [&p.reading[..], &p.translation, &p.notes]
+                    .into_iter()
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                // Tags can't contain spaces.
+                let tag = p
+                    .category
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("_");
+
+                write_row(&mut output, '\t', [&p.text[..], &back, &tag]);
+            }
+        }
+    }
+
+    output
+}
+
+fn write_row<'a>(output: &mut String, separator: char, fields: impl IntoIterator<Item = &'a str>) {
+    for (n, field) in fields.into_iter().enumerate() {
+        if n > 0 {
+            output.push(separator);
+        }
+
+        // Writing to a string can't fail.
+        _ = write!(output, "{}", Field(field, separator));
+    }
+
+    output.push('\n');
+}
+
+/// A field which is quoted if it contains the separator, quotes or newlines.
+struct Field<'a>(&'a str, char);
+
+impl fmt::Display for Field<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Field(text, separator) = *self;
+
+        if !text.contains([separator, '"', '\n', '\r']) {
+            return f.write_str(text);
+        }
+
+        f.write_char('"')?;
+
+        for c in text.chars() {
+            if c == '"' {
+                f.write_char('"')?;
+            }
+
+            f.write_char(c)?;
+        }
+
+        f.write_char('"')
+    }
+}
+
+#[test]
+fn test_phrasebook() {
+    let phrase = |text: &str, category: Option<&str>| Phrase {
+        text: text.to_owned(),
+        category: category.map(str::to_owned),
+        ..Phrase::default()
+    };
+
+    let mut book = Phrasebook::default();
+    let id = book
+        .add(phrase("駅はどこですか", Some("travel")), 10)
+        .unwrap()
+        .id;
+    book.add(phrase("お疲れ様です", Some("work")), 20).unwrap();
+    assert!(book.add(phrase(" ", None), 30).is_err());
+    assert_eq!(book.categories(), ["travel", "work"]);
+
+    let mut updated = phrase("駅はどこですか", Some("travel"));
+    updated.id = id;
+    updated.translation = String::from("Where is the station?");
+    assert_eq!(book.update(updated).unwrap().unwrap().added, 10);
+
+    assert_eq!(book.search(None, &["station"]).count(), 1);
+    assert_eq!(book.search(Some("work"), &["station"]).count(), 0);
+    assert_eq!(book.search(Some("work"), &[]).count(), 1);
+
+    // Renaming a category to itself keeps it.
+    assert!(book.rename_category("travel", "travel").unwrap());
+    assert_eq!(book.categories(), ["travel", "work"]);
+    assert_eq!(book.search(Some("travel"), &[]).count(), 1);
+
+    assert!(book.rename_category("travel", "work").unwrap());
+    assert_eq!(book.categories(), ["work"]);
+    assert_eq!(book.search(Some("work"), &[]).count(), 2);
+
+    assert!(book.remove_category("work"));
+    assert!(book.phrases().iter().all(|p| p.category.is_none()));
+    assert!(book.remove(id).is_some());
+    assert!(book.get(id).is_none());
+
+    let mut p = phrase("はい, \"そう\"", Some("daily life"));
+    p.translation = String::from("Yes,\tthat's right");
+
+    assert_eq!(
+        export(Format::Csv, [&p]),
+        "text,reading,translation,notes,category\n\"はい, \"\"そう\"\"\",,\"Yes,\tthat's right\",,daily life\n"
+    );
+
+    assert_eq!(
+        export(Format::Anki, [&p]),
+        "#separator:tab\n#html:false\n#tags column:3\n\"はい, \"\"そう\"\"\"\t\"Yes,\tthat's right\"\tdaily_life\n"
+    );
+}
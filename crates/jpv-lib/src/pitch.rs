@@ -0,0 +1,121 @@
+//! Parser for lists of pitch accents, like the `accents.txt` of the [Kanjium]
+//! accent database.
+//!
+//! Each line holds a word, its reading and its accents separated by tabs, like
+//! `橋\tはし\t2`. The reading is left empty for words written in kana. Accents
+//! are numbered as described in [`crate::accent`], and words with more than
+//! one list them separated by commas. Each accent can be prefixed with the
+//! part of speech it applies to in parenthesis, like `(名)0,(副)1`.
+//!
+//! [Kanjium]: https://github.com/mifunetoshiro/kanjium
+
+/// The pitch accents of a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    /// The word, which is the same as the reading for words written in kana.
+    pub text: &'a str,
+    pub reading: &'a str,
+    /// The accents of the word, with the most common first.
+    pub accents: Vec<u8>,
+}
+
+/// A parser for pitch accent lists.
+pub struct Parser<'a> {
+    lines: std::str::Lines<'a>,
+    skipped: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Construct a new pitch accent parser.
+    pub fn new(input: &'a str) -> Self {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+        Self {
+            lines: input.lines(),
+            skipped: 0,
+        }
+    }
+
+    /// The number of lines which were skipped since they could not be parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Parse an entry.
+    pub fn parse(&mut self) -> Option<Entry<'a>> {
+        for line in self.lines.by_ref() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.split('\t').map(str::trim);
+
+            let (Some(text), Some(reading), Some(accents)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                self.skipped += 1;
+                continue;
+            };
+
+            let Some(accents) = parse_accents(accents) else {
+                self.skipped += 1;
+                continue;
+            };
+
+            if text.is_empty() {
+                self.skipped += 1;
+                continue;
+            }
+
+            let reading = if reading.is_empty() { text } else { reading };
+
+            return Some(Entry {
+                text,
+                reading,
+                accents,
+            });
+        }
+
+        None
+    }
+}
+
+/// Parse a comma-separated list of accents, ignoring the parts of speech they
+/// might be annotated with.
+fn parse_accents(input: &str) -> Option<Vec<u8>> {
+    let mut accents = Vec::new();
+
+    for accent in input.split(',') {
+        let accent = match accent.trim().rsplit_once(')') {
+            Some((_, accent)) => accent,
+            None => accent.trim(),
+        };
+
+        let accent = accent.parse::<u8>().ok()?;
+
+        if !accents.contains(&accent) {
+            accents.push(accent);
+        }
+    }
+
+    Some(accents)
+}
+
+#[test]
+fn test_parse() {
+    let mut parser = Parser::new(
+        "橋\tはし\t2\nあいさつ\t\t1\n# comment\n\n相\tあい\t(名)0,(副)1,0\nbad\n箸\tはし\tx\n",
+    );
+
+    let entry = |text, reading, accents: &[u8]| Entry {
+        text,
+        reading,
+        accents: accents.to_vec(),
+    };
+
+    assert_eq!(parser.parse(), Some(entry("橋", "はし", &[2])));
+    assert_eq!(parser.parse(), Some(entry("あいさつ", "あいさつ", &[1])));
+    assert_eq!(parser.parse(), Some(entry("相", "あい", &[0, 1])));
+    assert_eq!(parser.parse(), None);
+    assert_eq!(parser.skipped(), 2);
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use musli::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 #[serde(rename_all = "kebab-case")]
 #[musli(mode = Text, name_all = "kebab-case")]
 pub enum PriorityKind {
@@ -21,6 +21,31 @@ pub enum PriorityKind {
     WordFrequency,
 }
 
+impl PriorityKind {
+    /// Parse a priority category, such as `news`.
+    fn parse(string: &str) -> Option<Self> {
+        Some(match string {
+            "ichi" => PriorityKind::Ichi,
+            "news" => PriorityKind::News,
+            "gai" => PriorityKind::Gai,
+            "spec" => PriorityKind::Spec,
+            "nf" => PriorityKind::WordFrequency,
+            _ => return None,
+        })
+    }
+
+    /// The identifier of the category, such as `news`.
+    fn ident(&self) -> &'static str {
+        match self {
+            PriorityKind::Ichi => "ichi",
+            PriorityKind::News => "news",
+            PriorityKind::Gai => "gai",
+            PriorityKind::Spec => "spec",
+            PriorityKind::WordFrequency => "nf",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode)]
 #[musli(mode = Binary, packed)]
 #[musli(mode = Text, name_all = "kebab-case")]
@@ -34,16 +59,7 @@ impl Priority {
     pub fn parse(string: &str) -> Option<Priority> {
         let n = string.find(char::is_numeric)?;
         let level = string[n..].parse().ok()?;
-
-        let kind = match &string[..n] {
-            "ichi" => PriorityKind::Ichi,
-            "news" => PriorityKind::News,
-            "gai" => PriorityKind::Gai,
-            "spec" => PriorityKind::Spec,
-            "nf" => PriorityKind::WordFrequency,
-            _ => return None,
-        };
-
+        let kind = PriorityKind::parse(&string[..n])?;
         Some(Priority { level, kind })
     }
 
@@ -54,13 +70,7 @@ impl Priority {
 
     /// Get priority category.
     pub fn category(&self) -> &'static str {
-        match self.kind {
-            PriorityKind::Ichi => "ichi",
-            PriorityKind::News => "news",
-            PriorityKind::Gai => "gai",
-            PriorityKind::Spec => "spec",
-            PriorityKind::WordFrequency => "nf",
-        }
+        self.kind.ident()
     }
 
     pub fn title(&self) -> &'static str {
@@ -75,6 +85,17 @@ impl Priority {
         }
     }
 
+    /// Test if the priority marks a word as common the way JMdict does, which
+    /// is `ichi1`, `news1`, `gai1`, `spec1` or `spec2`. Word frequency
+    /// categories don't on their own.
+    pub fn is_common(&self) -> bool {
+        match self.kind {
+            PriorityKind::WordFrequency => false,
+            PriorityKind::Spec => self.level <= 2,
+            _ => self.level == 1,
+        }
+    }
+
     /// Weight for these priorities.
     pub(crate) fn weight(&self) -> f32 {
         let level = self.level.saturating_sub(1) as f32;
@@ -96,8 +117,203 @@ impl Priority {
     }
 }
 
+/// How common a word is going by its priorities.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Commonness {
+    /// Whether the word is marked as common, see [`Priority::is_common`].
+    pub(crate) common: bool,
+    /// The best word frequency category of the word.
+    pub(crate) frequency: Option<u8>,
+}
+
+impl Commonness {
+    /// The level of commonness, where `0` means that the word is marked as
+    /// common and anything else is its best word frequency category. `None`
+    /// means that it's neither.
+    pub(crate) fn level(&self) -> Option<u8> {
+        if self.common {
+            Some(0)
+        } else {
+            self.frequency
+        }
+    }
+}
+
+/// Get how common a word with the given priorities is.
+pub(crate) fn commonness<'a, I>(priorities: I) -> Commonness
+where
+    I: IntoIterator<Item = &'a Priority>,
+{
+    let mut commonness = Commonness::default();
+
+    for p in priorities {
+        commonness.common |= p.is_common();
+
+        if let PriorityKind::WordFrequency = p.kind {
+            let frequency = commonness.frequency.map_or(p.level, |f| f.min(p.level));
+            commonness.frequency = Some(frequency);
+        }
+    }
+
+    commonness
+}
+
 impl fmt::Display for Priority {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}{}", self.category(), self.level)
     }
 }
+
+/// How the level of a priority is compared in a [`PriorityFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Any,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A filter over priorities, such as `news1` or `nf<=10`.
+///
+/// A filter without a level, like `news`, matches any level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFilter {
+    kind: PriorityKind,
+    comparison: Comparison,
+    level: u8,
+}
+
+impl PriorityFilter {
+    /// Parse a priority filter.
+    pub fn parse(string: &str) -> Option<PriorityFilter> {
+        let n = string
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(string.len());
+
+        let kind = PriorityKind::parse(&string[..n])?;
+        let rest = &string[n..];
+
+        if rest.is_empty() {
+            return Some(PriorityFilter {
+                kind,
+                comparison: Comparison::Any,
+                level: 0,
+            });
+        }
+
+        let (comparison, level) = if let Some(level) = rest.strip_prefix("<=") {
+            (Comparison::Le, level)
+        } else if let Some(level) = rest.strip_prefix(">=") {
+            (Comparison::Ge, level)
+        } else if let Some(level) = rest.strip_prefix('<') {
+            (Comparison::Lt, level)
+        } else if let Some(level) = rest.strip_prefix('>') {
+            (Comparison::Gt, level)
+        } else if let Some(level) = rest.strip_prefix('=') {
+            (Comparison::Eq, level)
+        } else {
+            (Comparison::Eq, rest)
+        };
+
+        Some(PriorityFilter {
+            kind,
+            comparison,
+            level: level.parse().ok()?,
+        })
+    }
+
+    /// Test if the given priority matches the filter.
+    pub fn matches(&self, priority: &Priority) -> bool {
+        if self.kind != priority.kind {
+            return false;
+        }
+
+        match self.comparison {
+            Comparison::Any => true,
+            Comparison::Eq => priority.level == self.level,
+            Comparison::Lt => priority.level < self.level,
+            Comparison::Le => priority.level <= self.level,
+            Comparison::Gt => priority.level > self.level,
+            Comparison::Ge => priority.level >= self.level,
+        }
+    }
+
+    /// Boost for a word by the best of its priorities which match the
+    /// filter, so that `nf01` ranks before `nf40` when filtering by `nf`.
+    pub(crate) fn boost<'a, I>(&self, priorities: I) -> f32
+    where
+        I: IntoIterator<Item = &'a Priority>,
+    {
+        priorities
+            .into_iter()
+            .filter(|p| self.matches(p))
+            .map(Priority::weight)
+            .fold(1.0, f32::max)
+    }
+}
+
+impl fmt::Display for PriorityFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Any => return write!(f, "{}", self.kind.ident()),
+            Comparison::Eq => "",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        };
+
+        write!(f, "{}{comparison}{}", self.kind.ident(), self.level)
+    }
+}
+
+#[test]
+fn test_priority_filter() {
+    let news1 = Priority::parse("news1").unwrap();
+    let nf05 = Priority::parse("nf05").unwrap();
+    let nf20 = Priority::parse("nf20").unwrap();
+
+    let filter = PriorityFilter::parse("news1").unwrap();
+    assert!(filter.matches(&news1));
+    assert!(!filter.matches(&nf05));
+
+    let filter = PriorityFilter::parse("nf<=10").unwrap();
+    assert!(filter.matches(&nf05));
+    assert!(!filter.matches(&nf20));
+    assert!(!filter.matches(&news1));
+
+    let filter = PriorityFilter::parse("nf").unwrap();
+    assert!(filter.matches(&nf05));
+    assert!(filter.matches(&nf20));
+
+    assert!(PriorityFilter::parse("v5s").is_none());
+    assert!(PriorityFilter::parse("nf<=").is_none());
+}
+
+#[test]
+fn test_priority_filter_display() {
+    for string in ["news", "news1", "nf<=10", "nf>5", "ichi<2", "spec>=1"] {
+        assert_eq!(PriorityFilter::parse(string).unwrap().to_string(), string);
+    }
+}
+
+#[test]
+fn test_commonness() {
+    let of = |priorities: &[&str]| {
+        let priorities = priorities
+            .iter()
+            .map(|p| Priority::parse(p).unwrap())
+            .collect::<Vec<_>>();
+
+        commonness(&priorities).level()
+    };
+
+    assert_eq!(of(&["news1", "nf05"]), Some(0));
+    assert_eq!(of(&["spec2"]), Some(0));
+    assert_eq!(of(&["news2", "nf30"]), Some(30));
+    assert_eq!(of(&["nf30", "nf12"]), Some(12));
+    assert_eq!(of(&["ichi2"]), None);
+    assert_eq!(of(&[]), None);
+}
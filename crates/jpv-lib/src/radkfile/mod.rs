@@ -0,0 +1,201 @@
+use encoding_rs::{DecoderResult, Encoding};
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+const NUL: u8 = 0;
+
+/// A radical and the kanji it is part of.
+#[borrowme::borrowme]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Binary, packed)]
+pub struct Entry<'a> {
+    pub radical: &'a str,
+    /// The number of strokes in the radical.
+    pub strokes: u8,
+    /// Name of an image to use in case the radical can't be displayed, or the
+    /// JIS code of the radical as it is specified in RADKFILE.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<&'a str>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kanji: Vec<&'a str>,
+}
+
+/// A RADKFILE parser.
+pub struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    encoding: &'static Encoding,
+    skipped: usize,
+    line: String,
+    current: Option<OwnedEntry>,
+}
+
+impl<'a> Parser<'a> {
+    /// Construct a new RADKFILE parser.
+    ///
+    /// The encoding of the input is detected automatically, see
+    /// [`crate::encoding::detect`].
+    pub fn new(input: &'a [u8]) -> Self {
+        let encoding = crate::encoding::detect(input);
+
+        let pos = match Encoding::for_bom(input) {
+            Some((_, len)) => len,
+            None => 0,
+        };
+
+        Self {
+            input,
+            pos,
+            encoding,
+            skipped: 0,
+            line: String::new(),
+            current: None,
+        }
+    }
+
+    /// The encoding which was detected for the input.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The number of lines which were skipped since they could not be decoded
+    /// or parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn peek(&self) -> u8 {
+        let Some(byte) = self.input.get(self.pos) else {
+            return NUL;
+        };
+
+        *byte
+    }
+
+    fn advance(&mut self) {
+        self.pos = self.pos.saturating_add(1).min(self.input.len());
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    /// Decode the next line into the line buffer, skipping over comments and
+    /// empty lines.
+    fn next_line(&mut self) -> bool {
+        while !self.is_eof() {
+            while self.peek().is_ascii_whitespace() {
+                self.advance();
+            }
+
+            if self.is_eof() {
+                break;
+            }
+
+            let comment = self.peek() == b'#';
+            let start = self.pos;
+
+            while !matches!(self.peek(), b'\n' | NUL) {
+                self.advance();
+            }
+
+            let end = self.pos;
+            self.advance();
+
+            if comment {
+                continue;
+            }
+
+            let input = &self.input[start..end];
+
+            let mut decoder = self.encoding.new_decoder_without_bom_handling();
+
+            let Some(len) = decoder.max_utf8_buffer_length_without_replacement(input.len()) else {
+                self.skipped += 1;
+                continue;
+            };
+
+            self.line.clear();
+            self.line.reserve(len);
+
+            let (result, _) =
+                decoder.decode_to_string_without_replacement(input, &mut self.line, true);
+
+            match result {
+                DecoderResult::InputEmpty => {}
+                DecoderResult::OutputFull | DecoderResult::Malformed(..) => {
+                    self.skipped += 1;
+                    continue;
+                }
+            }
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Parse an entry.
+    pub fn parse(&mut self) -> Option<OwnedEntry> {
+        while self.next_line() {
+            let Some(header) = self.line.strip_prefix('$') else {
+                let Some(current) = &mut self.current else {
+                    self.skipped += 1;
+                    continue;
+                };
+
+                for c in self.line.trim().chars() {
+                    current.kanji.push(c.to_string());
+                }
+
+                continue;
+            };
+
+            let mut it = header.split_whitespace();
+
+            let (Some(radical), Some(strokes)) = (it.next(), it.next()) else {
+                self.skipped += 1;
+                continue;
+            };
+
+            let Ok(strokes) = strokes.parse() else {
+                self.skipped += 1;
+                continue;
+            };
+
+            let entry = OwnedEntry {
+                radical: radical.to_owned(),
+                strokes,
+                image: it.next().map(str::to_owned),
+                kanji: Vec::new(),
+            };
+
+            if let Some(entry) = self.current.replace(entry) {
+                return Some(entry);
+            }
+        }
+
+        self.current.take()
+    }
+}
+
+#[test]
+fn test_parse() {
+    let input = "# comment\n$ 一 1\n亜唖\n娃\n$ 化 2 js01\n花\n";
+    let mut parser = Parser::new(input.as_bytes());
+
+    let entry = parser.parse().unwrap();
+    assert_eq!(entry.radical, "一");
+    assert_eq!(entry.strokes, 1);
+    assert_eq!(entry.image, None);
+    assert_eq!(entry.kanji, ["亜", "唖", "娃"]);
+
+    let entry = parser.parse().unwrap();
+    assert_eq!(entry.radical, "化");
+    assert_eq!(entry.strokes, 2);
+    assert_eq!(entry.image.as_deref(), Some("js01"));
+    assert_eq!(entry.kanji, ["花"]);
+
+    assert!(parser.parse().is_none());
+    assert_eq!(parser.skipped(), 0);
+}
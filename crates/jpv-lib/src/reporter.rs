@@ -2,6 +2,11 @@
 
 use std::fmt;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a step has to have run for before it's used to estimate how much
+/// time is left, since early progress is too noisy to say much.
+const MIN_ESTIMATE: Duration = Duration::from_millis(500);
 
 /// The level being reported.
 pub enum Level {
@@ -9,13 +14,44 @@ pub enum Level {
     Warn,
 }
 
+/// Report an informational message through a [`Reporter`].
+#[macro_export]
+macro_rules! report_info {
+    ($reporter:expr, $($arg:tt)*) => {
+        $crate::reporter::Reporter::report(
+            &$reporter,
+            module_path!(),
+            $crate::reporter::Level::Info,
+            &format_args!($($arg)*),
+        )
+    };
+}
+
+/// Report a warning through a [`Reporter`].
+#[macro_export]
+macro_rules! report_warn {
+    ($reporter:expr, $($arg:tt)*) => {
+        $crate::reporter::Reporter::report(
+            &$reporter,
+            module_path!(),
+            $crate::reporter::Level::Warn,
+            &format_args!($($arg)*),
+        )
+    };
+}
+
 pub trait Reporter: Send + Sync {
-    /// Start instrumenting.
+    /// Start instrumenting a step.
+    ///
+    /// The `weight` is the percentage of the whole task the step is expected
+    /// to take, which is used to estimate how much of the task is done. See
+    /// [`Tracker`].
     fn instrument_start(
         &self,
         module_path: &'static str,
         what: &dyn fmt::Display,
         total: Option<usize>,
+        weight: u32,
     );
 
     /// Report instrumenting progress.
@@ -23,6 +59,9 @@ pub trait Reporter: Send + Sync {
 
     /// Start instrumenting.
     fn instrument_end(&self, total: usize);
+
+    /// Report a diagnostic message.
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display);
 }
 
 impl<T> Reporter for &T
@@ -35,8 +74,9 @@ where
         module_path: &'static str,
         what: &dyn fmt::Display,
         total: Option<usize>,
+        weight: u32,
     ) {
-        (*self).instrument_start(module_path, what, total)
+        (*self).instrument_start(module_path, what, total, weight)
     }
 
     #[inline]
@@ -48,6 +88,11 @@ where
     fn instrument_end(&self, total: usize) {
         (*self).instrument_end(total)
     }
+
+    #[inline]
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display) {
+        (*self).report(module_path, level, what)
+    }
 }
 
 impl<T> Reporter for Arc<T>
@@ -60,8 +105,9 @@ where
         module_path: &'static str,
         what: &dyn fmt::Display,
         total: Option<usize>,
+        weight: u32,
     ) {
-        (**self).instrument_start(module_path, what, total)
+        (**self).instrument_start(module_path, what, total, weight)
     }
 
     #[inline]
@@ -73,6 +119,11 @@ where
     fn instrument_end(&self, total: usize) {
         (**self).instrument_end(total)
     }
+
+    #[inline]
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display) {
+        (**self).report(module_path, level, what)
+    }
 }
 
 pub struct EmptyReporter;
@@ -84,6 +135,7 @@ impl Reporter for EmptyReporter {
         module_path: &'static str,
         value: &dyn fmt::Display,
         _: Option<usize>,
+        _: u32,
     ) {
         tracing::event!(tracing::Level::INFO, "{module_path}: {}", value);
     }
@@ -93,4 +145,165 @@ impl Reporter for EmptyReporter {
 
     #[inline]
     fn instrument_end(&self, _: usize) {}
+
+    #[inline]
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display) {
+        match level {
+            Level::Info => tracing::info!("{module_path}: {what}"),
+            Level::Warn => tracing::warn!("{module_path}: {what}"),
+        }
+    }
+}
+
+/// Tracks the progress of a task made up of weighted steps, as reported
+/// through [`Reporter`].
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    started: Instant,
+    step_started: Instant,
+    /// The combined weight of finished steps.
+    finished: u32,
+    /// The weight of the current step.
+    weight: u32,
+    value: usize,
+    total: Option<usize>,
+}
+
+impl Tracker {
+    /// Construct a new tracker for a task which starts now.
+    pub fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            started: now,
+            step_started: now,
+            finished: 0,
+            weight: 0,
+            value: 0,
+            total: None,
+        }
+    }
+
+    /// Start a new step, see [`Reporter::instrument_start`].
+    pub fn start(&mut self, total: Option<usize>, weight: u32) {
+        self.step_started = Instant::now();
+        self.weight = weight;
+        self.value = 0;
+        self.total = total;
+    }
+
+    /// Report progress in the current step.
+    pub fn progress(&mut self, stride: usize) {
+        self.value = self.value.wrapping_add(stride);
+    }
+
+    /// End the current step.
+    pub fn end(&mut self, total: usize) {
+        self.finished = self.finished.saturating_add(self.weight).min(100);
+        self.weight = 0;
+        self.value = total;
+        self.total = Some(total);
+    }
+
+    /// Progress in the current step.
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// The total of the current step, if known.
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+
+    /// How much of the whole task is done in per mille, weighted by how long
+    /// each step is expected to take.
+    pub fn done(&self) -> u32 {
+        let current = match self.total {
+            Some(total) if total > 0 => {
+                self.weight as f32 * (self.value.min(total) as f32 / total as f32)
+            }
+            _ => 0.0,
+        };
+
+        ((self.finished as f32 + current) * 10.0).min(1000.0) as u32
+    }
+
+    /// The rate at which the current step progresses per second.
+    pub fn rate(&self) -> Option<f32> {
+        let elapsed = self.step_started.elapsed();
+
+        if elapsed < MIN_ESTIMATE || self.value == 0 {
+            return None;
+        }
+
+        Some(self.value as f32 / elapsed.as_secs_f32())
+    }
+
+    /// Estimate how much time is left of the whole task, by assuming that the
+    /// rest of it progresses as fast as it has so far.
+    pub fn remaining(&self) -> Option<Duration> {
+        let elapsed = self.started.elapsed();
+        let done = self.done();
+
+        if elapsed < MIN_ESTIMATE || done == 0 {
+            return None;
+        }
+
+        Some(elapsed.mul_f32((1000 - done) as f32 / done as f32))
+    }
+}
+
+impl Default for Tracker {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Format a number of seconds as a duration, like `1m 05s`.
+pub fn format_seconds(seconds: u64) -> impl fmt::Display {
+    struct Seconds(u64);
+
+    impl fmt::Display for Seconds {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let (h, m, s) = (self.0 / 3600, self.0 / 60 % 60, self.0 % 60);
+
+            if h > 0 {
+                write!(f, "{h}h {m:02}m")
+            } else if m > 0 {
+                write!(f, "{m}m {s:02}s")
+            } else {
+                write!(f, "{s}s")
+            }
+        }
+    }
+
+    Seconds(seconds)
+}
+
+#[test]
+fn test_tracker() {
+    let mut tracker = Tracker::new();
+    assert_eq!(tracker.done(), 0);
+
+    tracker.start(Some(100), 10);
+    tracker.progress(50);
+    assert_eq!(tracker.done(), 50);
+
+    tracker.end(100);
+    tracker.start(None, 40);
+    tracker.progress(1000);
+    assert_eq!(tracker.done(), 100);
+
+    tracker.end(1000);
+    tracker.start(Some(10), 50);
+    tracker.progress(10);
+    assert_eq!(tracker.done(), 1000);
+}
+
+#[test]
+fn test_format_seconds() {
+    assert_eq!(format_seconds(5).to_string(), "5s");
+    assert_eq!(format_seconds(65).to_string(), "1m 05s");
+    assert_eq!(format_seconds(3725).to_string(), "1h 02m");
 }
@@ -10,6 +10,9 @@ mod chars;
 mod tests;
 
 use std::array::from_fn;
+use std::borrow::Cow;
+
+use crate::kana;
 
 #[allow(unused)]
 macro_rules! hira {
@@ -36,6 +39,11 @@ pub enum Transform {
 }
 
 /// Perform an analysis.
+///
+/// Moraic `n` follows the usual Hepburn conventions. It's written as `n'` or
+/// `n-` when followed by a vowel or `y` (`shin'ya`), as `nn` (`konnichiwa`), or
+/// as a plain `n` when followed by any other consonant (`kanji`). A trailing
+/// `n` is kept as-is since it might still be completed into a syllable.
 pub fn analyze(input: &str) -> Analysis<'_> {
     Analysis { input }
 }
@@ -57,6 +65,15 @@ impl<'a> Iterator for Analysis<'a> {
         let mut it = self.input.chars();
         let chars: [char; 4] = from_fn(|_| it.next().unwrap_or('\0'));
 
+        if let Some(n) = moraic_n(chars) {
+            let (string, tail) = self.input.split_at(n);
+            self.input = tail;
+            return Some(Segment {
+                string,
+                moraic_n: true,
+            });
+        }
+
         macro_rules! pattern {
             ([$a:pat, $b:pat, $c:pat, $d:pat]) => {
                 [$a, $b, $c, $d]
@@ -98,19 +115,151 @@ impl<'a> Iterator for Analysis<'a> {
         let n = romaji_table!(implement_match);
         let (string, tail) = self.input.split_at(n);
         self.input = tail;
-        Some(Segment { string })
+
+        Some(Segment {
+            string,
+            moraic_n: false,
+        })
+    }
+}
+
+/// Test if the input starts with a moraic `n` which isn't covered by the
+/// table, returning the length of the segment.
+fn moraic_n(chars: [char; 4]) -> Option<usize> {
+    fn is_vowel(c: char) -> bool {
+        matches!(c, 'a' | 'i' | 'u' | 'e' | 'o' | 'y')
+    }
+
+    match chars {
+        // `nn` followed by a vowel, like `konnichiwa` or `konnyaku`, only the
+        // first `n` is moraic.
+        ['n', 'n', c, _] if is_vowel(c) => Some(1),
+        // `n-` used as a separator, like `shin-osaka`.
+        ['n', '-', c, _] if c.is_ascii_alphabetic() => Some(2),
+        // `n` followed by a consonant, like `kanji`.
+        ['n', c, _, _] if c.is_ascii_alphabetic() && !is_vowel(c) && c != 'n' => Some(1),
+        _ => None,
+    }
+}
+
+/// Romanize the given input.
+///
+/// Unlike [`Segment::romanize`] this only separates a moraic `n` with an
+/// apostrophe when it would otherwise be ambiguous, so `しんや` becomes
+/// `shin'ya` while `しんぶん` becomes `shinbun`. Long vowel marks are written
+/// as the vowel they prolong, so `ラーメン` becomes `raamen` the same as
+/// `らあめん`.
+pub fn romanize(input: &str) -> String {
+    let mut output = String::new();
+    let mut it = analyze(input).peekable();
+
+    while let Some(segment) = it.next() {
+        let romanized = segment.romanize();
+
+        if segment == "ー" {
+            if let Some(vowel) = output.chars().next_back().filter(|&c| is_vowel(c)) {
+                output.push(vowel);
+                continue;
+            }
+        }
+
+        if romanized == "n'" {
+            let separate = match it.peek() {
+                Some(next) => {
+                    let next = next.romanize();
+                    next == "n'" || next.starts_with(['a', 'i', 'u', 'e', 'o', 'y'])
+                }
+                None => false,
+            };
+
+            if !separate {
+                output.push('n');
+                continue;
+            }
+        }
+
+        output.push_str(romanized);
     }
+
+    output
+}
+
+/// Expand long vowel marks (`ー`) into the vowel they prolong, written in the
+/// same script as the kana before them. So `ラーメン` becomes `ラアメン` and
+/// `らーめん` becomes `らあめん`.
+///
+/// Marks which don't follow a kana ending in a vowel, like the one in `ンー`,
+/// are left as-is.
+pub fn expand_long_vowels(input: &str) -> Cow<'_, str> {
+    if !input.contains('ー') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut previous = None;
+
+    for segment in analyze(input) {
+        if segment == "ー" {
+            if let Some(vowel) = previous.and_then(long_vowel) {
+                output.push_str(vowel);
+                continue;
+            }
+        }
+
+        output.push_str(segment.string);
+        previous = Some(segment);
+    }
+
+    Cow::Owned(output)
+}
+
+/// The vowel a long vowel mark following the given segment prolongs.
+fn long_vowel(segment: Segment<'_>) -> Option<&'static str> {
+    let c = segment.string.chars().next_back()?;
+
+    let vowels = if kana::is_hiragana(c) {
+        ["あ", "い", "う", "え", "お"]
+    } else if kana::is_katakana(c) {
+        ["ア", "イ", "ウ", "エ", "オ"]
+    } else {
+        return None;
+    };
+
+    // A small tsu is romanized as `xtu` but has no vowel to prolong.
+    if matches!(c, 'っ' | 'ッ') {
+        return None;
+    }
+
+    let index = match segment.romanize().chars().next_back()? {
+        'a' => 0,
+        'i' => 1,
+        'u' => 2,
+        'e' => 3,
+        'o' => 4,
+        _ => return None,
+    };
+
+    Some(vowels[index])
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
 }
 
 /// A section that can be restructured.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Segment<'a> {
     string: &'a str,
+    moraic_n: bool,
 }
 
 impl<'a> Segment<'a> {
     /// Convert the analyzed segment into hiragana.
     pub fn hiragana(&self) -> &'a str {
+        if self.moraic_n {
+            return "ん";
+        }
+
         macro_rules! implement_match {
             (
                 $((
@@ -135,6 +284,10 @@ impl<'a> Segment<'a> {
 
     /// Convert the analyzed segment into katakana.
     pub fn katakana(&self) -> &'a str {
+        if self.moraic_n {
+            return "ン";
+        }
+
         macro_rules! implement_match {
             (
                 $((
@@ -159,6 +312,10 @@ impl<'a> Segment<'a> {
 
     /// Romanize the analyzed segment.
     pub fn romanize(&self) -> &'a str {
+        if self.moraic_n {
+            return "n";
+        }
+
         macro_rules! implement_match {
             (
                 $((
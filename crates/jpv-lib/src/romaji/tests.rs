@@ -1,4 +1,6 @@
-use super::analyze;
+use std::borrow::Cow;
+
+use super::{analyze, expand_long_vowels, romanize};
 
 #[test]
 fn segmentations() {
@@ -103,3 +105,61 @@ fn katakana() {
 
     romaji_table!(test);
 }
+
+#[test]
+fn hepburn() {
+    fn hiragana(input: &str) -> String {
+        analyze(input).map(|segment| segment.hiragana()).collect()
+    }
+
+    assert_eq!(hiragana("kon'nichiwa"), "こんにちわ");
+    assert_eq!(hiragana("konnichiwa"), "こんにちわ");
+    assert_eq!(hiragana("konnyaku"), "こんにゃく");
+    assert_eq!(hiragana("shin'ya"), "しんや");
+    assert_eq!(hiragana("shinya"), "しにゃ");
+    assert_eq!(hiragana("shin-osaka"), "しんおさか");
+    assert_eq!(hiragana("kanji"), "かんじ");
+    // A trailing `n` might still be completed into a syllable.
+    assert_eq!(hiragana("kan"), "かn");
+    assert_eq!(hiragana("kann"), "かん");
+
+    assert_eq!(romanize("こんにちは"), "konnichiha");
+    assert_eq!(romanize("しんや"), "shin'ya");
+    assert_eq!(romanize("しんぶん"), "shinbun");
+    assert_eq!(romanize("ほんをよむ"), "honwoyomu");
+}
+
+#[test]
+fn long_vowels() {
+    fn hiragana(input: &str) -> String {
+        analyze(input).map(|segment| segment.hiragana()).collect()
+    }
+
+    fn katakana(input: &str) -> String {
+        analyze(input).map(|segment| segment.katakana()).collect()
+    }
+
+    assert_eq!(expand_long_vowels("ラーメン"), "ラアメン");
+    assert_eq!(expand_long_vowels("らーめん"), "らあめん");
+    assert_eq!(expand_long_vowels("コーヒー"), "コオヒイ");
+    assert_eq!(expand_long_vowels("ジュース"), "ジュウス");
+    assert_eq!(expand_long_vowels("すごーーい"), "すごおおい");
+    assert_eq!(expand_long_vowels("ンー"), "ンー");
+    assert_eq!(expand_long_vowels("ーあ"), "ーあ");
+    assert!(matches!(expand_long_vowels("ラアメン"), Cow::Borrowed(..)));
+
+    assert_eq!(romanize("ラーメン"), "raamen");
+    assert_eq!(romanize("らーめん"), "raamen");
+    assert_eq!(romanize("らあめん"), "raamen");
+    assert_eq!(romanize("コーヒー"), "koohii");
+
+    // Romaji, hiragana and katakana all end up in the same place.
+    assert_eq!(hiragana("koohii"), expand_long_vowels("こーひー"));
+    assert_eq!(katakana("koohii"), expand_long_vowels("コーヒー"));
+    assert_eq!(hiragana(&expand_long_vowels("ラーメン")), "らあめん");
+    assert_eq!(katakana(&expand_long_vowels("らーめん")), "ラアメン");
+    assert_eq!(
+        romanize(&expand_long_vowels("ラーメン")),
+        romanize("ラーメン")
+    );
+}
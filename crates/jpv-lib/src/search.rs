@@ -1,13 +1,106 @@
+use std::borrow::Cow;
 use std::ops::Range;
 
+use crate::inflection::{Form, Inflection};
+use crate::kana;
+use crate::priority::PriorityFilter;
+
 const NUL: char = '\0';
 
+/// Words which separate a natural-language filter from its topic, like
+/// `about` in `ichidan verbs about cooking`.
+const CONNECTORS: &[&[&str]] = &[
+    &["about"],
+    &["for"],
+    &["meaning"],
+    &["related", "to"],
+    &["relating", "to"],
+];
+
+/// Words in Latin script which only ask for what a Japanese word means, like
+/// `meaning` in `食べる meaning`, and are therefore not used as gloss filters.
+const FILLERS: &[&str] = &[
+    "definition",
+    "english",
+    "meaning",
+    "meanings",
+    "means",
+    "translation",
+];
+
+/// Descriptions which can be used in natural-language filters and the
+/// entities they correspond to. An empty entity means that the words are
+/// allowed but don't filter anything, like `words` in `slang words about
+/// money`.
+const DESCRIPTIONS: &[(&[&str], &str)] = &[
+    (&["ichidan", "verb"], "v1"),
+    (&["ru", "verb"], "v1"),
+    (&["ru-verb"], "v1"),
+    (&["suru", "verb"], "vs"),
+    (&["i", "adjective"], "adj-i"),
+    (&["i-adjective"], "adj-i"),
+    (&["na", "adjective"], "adj-na"),
+    (&["na-adjective"], "adj-na"),
+    (&["verb"], "verb"),
+    (&["noun"], "noun"),
+    (&["adjective"], "adjective"),
+    (&["adverb"], "adverb"),
+    (&["pronoun"], "pronoun"),
+    (&["particle"], "particle"),
+    (&["counter"], "counter"),
+    (&["expression"], "expression"),
+    (&["interjection"], "interjection"),
+    (&["onomatopoeia"], "on-mim"),
+    (&["onomatopoeic"], "on-mim"),
+    (&["idiom"], "id"),
+    (&["idiomatic"], "id"),
+    (&["proverb"], "proverb"),
+    (&["abbreviation"], "abbr"),
+    (&["slang"], "sl"),
+    (&["archaic"], "arch"),
+    (&["colloquial"], "col"),
+    (&["vulgar"], "vulg"),
+    (&["polite"], "pol"),
+    (&["honorific"], "hon"),
+    (&["humble"], "hum"),
+    (&["kansai"], "ksb"),
+    (&["word"], ""),
+    (&["term"], ""),
+];
+
 /// Helper to analyze a search query.
 #[derive(Default)]
 pub struct SearchQuery<'a> {
     pub phrases: Vec<&'a str>,
     pub phrase_ranges: Vec<Range<usize>>,
     pub entities: Vec<&'a str>,
+    /// Priorities to filter by, such as `#news1` or `#nf<=10`. Words with
+    /// better matching priorities are ranked higher.
+    pub priorities: Vec<PriorityFilter>,
+    /// Fields whose senses are ranked higher without filtering anything out,
+    /// such as `#field:comp`.
+    pub fields: Vec<&'a str>,
+    /// The inflection to produce for matching phrases, such as `+past
+    /// +negative`.
+    pub inflection: Inflection,
+    /// Words in Latin script which were mixed with Japanese in a phrase, such
+    /// as `eat` in `食べる eat`. Only phrases whose glossary contains all of
+    /// them are included.
+    pub glosses: Vec<&'a str>,
+    /// Also search the user's own data with `#mine`, which covers pinned
+    /// entries, words which were looked up before and the phrasebook.
+    pub mine: bool,
+    /// Also search the phrasebook with `#phrasebook`, or only a category of it
+    /// with a tag like `#phrasebook:travel`, which is quoted like
+    /// `#phrasebook:"day trips"` if it contains spaces. Set to the category,
+    /// which is empty if every category is searched.
+    pub phrasebook: Option<&'a str>,
+    /// Only include phrases with at least one example sentence with
+    /// `#has-example`.
+    pub has_example: bool,
+    /// Only include common words with `#common`, or every word with `#all`.
+    /// If neither is used, this is up to the configuration.
+    pub common: Option<bool>,
 }
 
 /// Parse an input.
@@ -15,6 +108,70 @@ pub fn parse(input: &str) -> SearchQuery<'_> {
     SearchParser::new(input).parse()
 }
 
+/// Full-width forms of the half-width katakana and punctuation from `｡` to
+/// `ﾟ`, where the sound marks are combining like they are in NFKC.
+const HALF_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン\u{3099}\u{309a}";
+
+/// Fold the width of characters the way NFKC does, so that queries typed
+/// with full-width latin letters like `ｔａｂｅｒｕ #ｖ５ｓ` or half-width
+/// katakana like `ｶﾞｽ` are interpreted like `taberu #v5s` and `ガス`.
+///
+/// Kana are otherwise matched as they are written, since indexes already
+/// include every reading in both hiragana and katakana.
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    if !input.chars().any(needs_folding) {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        let c = match c {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            '\u{ff61}'..='\u{ff9f}' => HALF_WIDTH.chars().nth(c as usize - 0xff61).unwrap_or(c),
+            c => c,
+        };
+
+        // Sound marks are composed with the kana before them if possible.
+        let composed = match (output.chars().last(), c) {
+            (Some(last), '\u{3099}' | '\u{309a}') => compose(last, c),
+            _ => None,
+        };
+
+        match composed {
+            Some(composed) => {
+                output.pop();
+                output.push(composed);
+            }
+            None => output.push(c),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+fn needs_folding(c: char) -> bool {
+    matches!(c, '\u{3000}' | '\u{ff01}'..='\u{ff5e}' | '\u{ff61}'..='\u{ff9f}')
+}
+
+/// Compose a katakana with a combining voiced (`゙`) or semi-voiced (`゚`)
+/// sound mark.
+fn compose(c: char, mark: char) -> Option<char> {
+    let offset = match (c, mark) {
+        ('カ'..='チ', '\u{3099}') if (c as u32 - 'カ' as u32) % 2 == 0 => 1,
+        ('ツ' | 'テ' | 'ト', '\u{3099}') => 1,
+        ('ハ'..='ホ', '\u{3099}') if (c as u32 - 'ハ' as u32) % 3 == 0 => 1,
+        ('ハ'..='ホ', '\u{309a}') if (c as u32 - 'ハ' as u32) % 3 == 0 => 2,
+        ('ウ', '\u{3099}') => return Some('ヴ'),
+        ('ワ', '\u{3099}') => return Some('ヷ'),
+        ('ヲ', '\u{3099}') => return Some('ヺ'),
+        _ => return None,
+    };
+
+    char::from_u32(c as u32 + offset)
+}
+
 struct SearchParser<'a> {
     input: &'a str,
     pos: usize,
@@ -58,6 +215,50 @@ impl<'a> SearchParser<'a> {
         &self.input[start..self.pos]
     }
 
+    /// Parse a tag, which is an identifier optionally qualified like
+    /// `field:comp` or followed by a comparison like `nf<=10`.
+    fn tag(&mut self) -> &'a str {
+        let start = self.pos;
+
+        self.ident();
+
+        if self.peek() == ':' {
+            self.step();
+            self.qualifier();
+        }
+
+        if matches!(self.peek(), '<' | '>' | '=') {
+            while matches!(self.peek(), '<' | '>' | '=') {
+                self.step();
+            }
+
+            while self.peek().is_ascii_digit() {
+                self.step();
+            }
+        }
+
+        &self.input[start..self.pos]
+    }
+
+    /// Parse the qualifier of a tag, which runs until the next whitespace
+    /// unless it's quoted like `"day trips"`.
+    fn qualifier(&mut self) {
+        if self.peek() == '"' {
+            self.step();
+
+            while !matches!(self.peek(), '"' | NUL) {
+                self.step();
+            }
+
+            self.step();
+            return;
+        }
+
+        while !(self.peek().is_whitespace() || matches!(self.peek(), '#' | NUL)) {
+            self.step();
+        }
+    }
+
     fn parse(&mut self) -> SearchQuery<'a> {
         let mut query = SearchQuery::default();
 
@@ -75,17 +276,63 @@ impl<'a> SearchParser<'a> {
                 }
                 '#' => {
                     if let Some(start) = start.take() {
-                        query.phrase_ranges.push(start..end);
-                        query.phrases.push(&self.input[start..end]);
+                        self.push_phrase(&mut query, start..end);
                     }
 
                     self.step();
-                    query.entities.push(self.ident());
+
+                    let tag = self.tag();
+
+                    if matches!(tag, "mine" | "mine:") {
+                        query.mine = true;
+                    } else if tag == "has-example" {
+                        query.has_example = true;
+                    } else if tag == "common" {
+                        query.common = Some(true);
+                    } else if tag == "all" {
+                        query.common = Some(false);
+                    } else if tag == "phrasebook" {
+                        query.phrasebook = Some("");
+                    } else if let Some(category) = tag.strip_prefix("phrasebook:") {
+                        let category = category
+                            .strip_prefix('"')
+                            .map_or(category, |c| c.strip_suffix('"').unwrap_or(c));
+
+                        query.phrasebook = Some(category);
+                    } else if let Some(field) = tag.strip_prefix("field:") {
+                        query.fields.push(field);
+                    } else if let Some(priority) = PriorityFilter::parse(tag) {
+                        query.priorities.push(priority);
+                    } else {
+                        query.entities.push(tag);
+                    }
+                }
+                '+' => {
+                    let plus = self.pos;
+                    self.step();
+
+                    // Only a known keyword selects an inflection, otherwise
+                    // the `+` is part of the phrase like in `C++`.
+                    if let Some(form) = Form::parse_keyword(self.ident()) {
+                        if let Some(start) = start.take() {
+                            self.push_phrase(&mut query, start..end);
+                        }
+
+                        query.inflection.insert(form);
+                    } else {
+                        self.pos = plus;
+
+                        if start.is_none() {
+                            start = Some(self.pos);
+                        }
+
+                        self.step();
+                        end = self.pos;
+                    }
                 }
                 ',' | '、' | '.' | '。' => {
                     if let Some(start) = start.take() {
-                        query.phrase_ranges.push(start..end);
-                        query.phrases.push(&self.input[start..end]);
+                        self.push_phrase(&mut query, start..end);
                     }
 
                     self.step();
@@ -102,12 +349,123 @@ impl<'a> SearchParser<'a> {
         }
 
         if let Some(start) = start.take() {
-            query.phrase_ranges.push(start..end);
-            query.phrases.push(&self.input[start..end]);
+            self.push_phrase(&mut query, start..end);
         }
 
         query
     }
+
+    /// Push a phrase.
+    ///
+    /// If the phrase mixes words in Japanese with words in Latin script, like
+    /// `食べる meaning`, only the Japanese words are looked up and the Latin
+    /// words are used to filter by glossary instead.
+    ///
+    /// A phrase in Latin script which describes what to look for, like
+    /// `ichidan verbs about cooking`, is turned into entity filters and only
+    /// its topic is looked up. See [`natural_filter`].
+    fn push_phrase(&self, query: &mut SearchQuery<'a>, range: Range<usize>) {
+        let phrase = &self.input[range.clone()];
+
+        if let Some((entities, topic)) = natural_filter(phrase) {
+            let start = range.start + (topic.as_ptr() as usize - phrase.as_ptr() as usize);
+            query.entities.extend(entities);
+            query.phrase_ranges.push(start..start + topic.len());
+            query.phrases.push(topic);
+            return;
+        }
+
+        let mixed = phrase.split_whitespace().any(is_japanese)
+            && phrase.split_whitespace().any(|word| !is_japanese(word));
+
+        if !mixed {
+            query.phrase_ranges.push(range);
+            query.phrases.push(phrase);
+            return;
+        }
+
+        for word in phrase.split_whitespace() {
+            if is_japanese(word) {
+                let start = range.start + (word.as_ptr() as usize - phrase.as_ptr() as usize);
+                query.phrase_ranges.push(start..start + word.len());
+                query.phrases.push(word);
+            } else if !FILLERS.iter().any(|f| f.eq_ignore_ascii_case(word)) {
+                query.glosses.push(word);
+            }
+        }
+    }
+}
+
+/// Parse a natural-language filter like `ichidan verbs about cooking` into
+/// the entities it describes and its topic.
+///
+/// Every word before the connector has to be a known description, otherwise
+/// `None` is returned and the phrase is searched for as-is. This keeps
+/// ordinary English searches like `care for` working.
+fn natural_filter(phrase: &str) -> Option<(Vec<&'static str>, &str)> {
+    let words = phrase.split_whitespace().collect::<Vec<_>>();
+
+    if words.iter().any(|word| is_japanese(word)) {
+        return None;
+    }
+
+    let (at, connector) = (1..words.len()).find_map(|n| {
+        let connector = CONNECTORS
+            .iter()
+            .find(|connector| starts_with(&words[n..], connector, false))?;
+        Some((n, connector.len()))
+    })?;
+
+    let topic = *words.get(at + connector)?;
+    let last = words.last()?;
+    let end = last.as_ptr() as usize + last.len() - phrase.as_ptr() as usize;
+    let topic = &phrase[topic.as_ptr() as usize - phrase.as_ptr() as usize..end];
+
+    let mut entities = Vec::new();
+    let mut rest = &words[..at];
+
+    while !rest.is_empty() {
+        let (description, entity) = DESCRIPTIONS
+            .iter()
+            .find(|(description, _)| starts_with(rest, description, true))?;
+
+        if !entity.is_empty() && !entities.contains(entity) {
+            entities.push(*entity);
+        }
+
+        rest = &rest[description.len()..];
+    }
+
+    if entities.is_empty() {
+        return None;
+    }
+
+    Some((entities, topic))
+}
+
+/// Test if words start with the given words, ignoring case. If `plural` is
+/// set, the words may also be in plural like `verbs`.
+fn starts_with(words: &[&str], expected: &[&str], plural: bool) -> bool {
+    words.len() >= expected.len()
+        && words.iter().zip(expected).all(|(word, expected)| {
+            word.eq_ignore_ascii_case(expected)
+                || plural
+                    && word.len() == expected.len() + 1
+                    && word[expected.len()..].eq_ignore_ascii_case("s")
+                    && word[..expected.len()].eq_ignore_ascii_case(expected)
+        })
+}
+
+/// Test if a word contains any Japanese characters.
+///
+/// [`kana::is_kanji`] assumes that the character is Japanese, so kanji are
+/// recognized by their unicode blocks instead.
+fn is_japanese(word: &str) -> bool {
+    word.chars().any(|c| {
+        kana::is_hiragana(c)
+            || kana::is_katakana(c)
+            || matches!(c, '々' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+    })
 }
 
 #[test]
@@ -123,3 +481,164 @@ fn test_parse() {
     assert_eq!(query.phrases[1], "first tail phrase*");
     assert_eq!(query.phrases[2], "second tail phrase");
 }
+
+#[test]
+fn test_parse_priorities() {
+    let mut parser = SearchParser::new("#news1 taberu #nf<=10 #v5s");
+    let query = parser.parse();
+
+    assert_eq!(query.entities, ["v5s"]);
+    assert_eq!(query.phrases, ["taberu"]);
+    assert_eq!(
+        query.priorities,
+        [
+            PriorityFilter::parse("news1").unwrap(),
+            PriorityFilter::parse("nf<=10").unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_inflection() {
+    let mut parser = SearchParser::new("食べる +past +negative +te-iru");
+    let query = parser.parse();
+
+    assert_eq!(query.phrases, ["食べる"]);
+    assert!(query.inflection.contains(Form::Past));
+    assert!(query.inflection.contains(Form::Negative));
+    assert!(query.inflection.contains(Form::TeIru));
+    assert!(!query.inflection.contains(Form::Honorific));
+}
+
+#[test]
+fn test_parse_plus() {
+    let mut parser = SearchParser::new("C++");
+    let query = parser.parse();
+
+    assert_eq!(query.phrases, ["C++"]);
+    assert!(query.inflection.is_empty());
+
+    let mut parser = SearchParser::new("1+1, 食べる+past");
+    let query = parser.parse();
+
+    assert_eq!(query.phrases, ["1+1", "食べる"]);
+    assert!(query.inflection.contains(Form::Past));
+}
+
+#[test]
+fn test_parse_fields() {
+    let mut parser = SearchParser::new("#field:comp ファイル #n");
+    let query = parser.parse();
+
+    assert_eq!(query.fields, ["comp"]);
+    assert_eq!(query.entities, ["n"]);
+    assert_eq!(query.phrases, ["ファイル"]);
+}
+
+#[test]
+fn test_parse_mixed() {
+    let query = parse("食べる meaning, to eat");
+
+    assert_eq!(query.phrases, ["食べる", "to eat"]);
+    assert_eq!(query.phrase_ranges, [0..9, 19..25]);
+    assert!(query.glosses.is_empty());
+
+    let query = parse("食べる food");
+
+    assert_eq!(query.phrases, ["食べる"]);
+    assert_eq!(query.glosses, ["food"]);
+
+    let query = parse("hello world");
+    assert_eq!(query.phrases, ["hello world"]);
+    assert!(query.glosses.is_empty());
+}
+
+#[test]
+fn test_parse_mine() {
+    let query = parse("#mine: 食べる");
+
+    assert!(query.mine);
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["食べる"]);
+
+    let query = parse("#phrasebook:travel 駅");
+    assert_eq!(query.phrasebook, Some("travel"));
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["駅"]);
+
+    // Categories which aren't identifiers, or which are quoted since they
+    // contain spaces.
+    let query = parse("#phrasebook:旅行 駅");
+    assert_eq!(query.phrasebook, Some("旅行"));
+    assert_eq!(query.phrases, ["駅"]);
+
+    let query = parse("#phrasebook:\"day trips\" 駅");
+    assert_eq!(query.phrasebook, Some("day trips"));
+    assert_eq!(query.phrases, ["駅"]);
+
+    let query = parse("#phrasebook:\"day trips");
+    assert_eq!(query.phrasebook, Some("day trips"));
+
+    let query = parse("#has-example 食べる");
+    assert!(query.has_example);
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["食べる"]);
+}
+
+#[test]
+fn test_parse_common() {
+    let query = parse("#common 食べる");
+    assert_eq!(query.common, Some(true));
+    assert!(query.entities.is_empty());
+    assert!(query.priorities.is_empty());
+    assert_eq!(query.phrases, ["食べる"]);
+
+    let query = parse("食べる #all");
+    assert_eq!(query.common, Some(false));
+    assert_eq!(query.phrases, ["食べる"]);
+
+    assert_eq!(parse("食べる").common, None);
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn test_parse_natural_filter() {
+    let query = parse("ichidan verbs about cooking");
+    assert_eq!(query.entities, ["v1"]);
+    assert_eq!(query.phrases, ["cooking"]);
+    assert_eq!(query.phrase_ranges, [20..27]);
+
+    let query = parse("Slang words related to money #news1");
+    assert_eq!(query.entities, ["sl"]);
+    assert_eq!(query.phrases, ["money"]);
+    assert_eq!(query.priorities.len(), 1);
+
+    let query = parse("honorific verbs for eating food");
+    assert_eq!(query.entities, ["hon", "verb"]);
+    assert_eq!(query.phrases, ["eating food"]);
+
+    // Anything which isn't fully recognized is searched for as-is.
+    let query = parse("care for");
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["care for"]);
+
+    let query = parse("tasty verbs about cooking");
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["tasty verbs about cooking"]);
+
+    let query = parse("words about cooking");
+    assert!(query.entities.is_empty());
+    assert_eq!(query.phrases, ["words about cooking"]);
+}
+
+#[test]
+fn test_normalize() {
+    assert!(matches!(normalize("食べる #v5s"), Cow::Borrowed(..)));
+    assert_eq!(normalize("ｔａｂｅｒｕ　＃ｖ５ｓ"), "taberu #v5s");
+    assert_eq!(normalize("ｶﾞｽ"), "ガス");
+    assert_eq!(normalize("ﾊﾟﾝﾀﾞ"), "パンダ");
+    assert_eq!(normalize("ﾂﾞﾂﾞｸ"), "ヅヅク");
+    assert_eq!(normalize("ｳﾞｧｲｵﾘﾝ"), "ヴァイオリン");
+    assert_eq!(normalize("ｯﾞ"), "ッ\u{3099}");
+    assert_eq!(normalize("ﾗｰﾒﾝ｡"), "ラーメン。");
+}
@@ -0,0 +1,181 @@
+//! Printable vocabulary sheets.
+//!
+//! A sheet lists phrases with furigana and their meanings, followed by a
+//! summary of every kanji used to write them. It's rendered as a standalone
+//! HTML document which is laid out for printing.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use anyhow::Result;
+
+use crate::database::Database;
+use crate::furigana::Escape;
+use crate::html;
+use crate::jmdict;
+use crate::kana;
+use crate::Furigana;
+
+/// The number of meanings included for each phrase.
+const MAX_GLOSSES: usize = 5;
+
+/// The number of meanings included for each kanji.
+const MAX_MEANINGS: usize = 3;
+
+/// A phrase on a sheet.
+#[derive(Debug, Clone)]
+pub struct Word {
+    /// The preferred form of the phrase.
+    pub text: String,
+    /// The reading of the preferred form.
+    pub reading: String,
+    /// Meanings of the phrase.
+    pub glosses: Vec<String>,
+}
+
+/// A kanji on a sheet.
+#[derive(Debug, Clone)]
+pub struct Kanji {
+    pub literal: String,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub meanings: Vec<String>,
+}
+
+/// A vocabulary sheet.
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    pub title: String,
+    pub words: Vec<Word>,
+    pub kanji: Vec<Kanji>,
+}
+
+impl Sheet {
+    /// Build a sheet out of the given phrases, looking up every kanji used in
+    /// them in the order they first appear.
+    pub fn new<'a, I>(db: &Database, title: &str, phrases: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = &'a jmdict::Entry<'a>>,
+    {
+        let mut words = Vec::new();
+        let mut kanji = Vec::new();
+        let mut seen = HashSet::new();
+
+        for entry in phrases {
+            let Some((k, reading)) = entry.preferred_form() else {
+                continue;
+            };
+
+            let text = k.map_or(reading.text, |k| k.text);
+
+            for c in text.chars() {
+                if !kana::is_japanese(c) || !kana::is_kanji(c) || !seen.insert(c) {
+                    continue;
+                }
+
+                let Some(character) = db.literal_to_kanji(c.encode_utf8(&mut [0; 4]))? else {
+                    continue;
+                };
+
+                let mut onyomi = Vec::new();
+                let mut kunyomi = Vec::new();
+
+                for reading in &character.readings {
+                    match reading.ty {
+                        "ja_on" => onyomi.push(reading.text.to_owned()),
+                        "ja_kun" => kunyomi.push(reading.text.to_owned()),
+                        _ => {}
+                    }
+                }
+
+                let meanings = character
+                    .meanings
+                    .iter()
+                    .filter(|m| matches!(m.lang, None | Some("en")))
+                    .map(|m| m.text.to_owned())
+                    .take(MAX_MEANINGS)
+                    .collect();
+
+                kanji.push(Kanji {
+                    literal: character.literal.to_owned(),
+                    onyomi,
+                    kunyomi,
+                    meanings,
+                });
+            }
+
+            let glosses = entry
+                .senses
+                .iter()
+                .filter(|sense| sense.is_lang("eng"))
+                .flat_map(|sense| sense.gloss.iter())
+                .filter(|gloss| gloss.lang.unwrap_or("eng") == "eng")
+                .map(|gloss| gloss.text.to_owned())
+                .take(MAX_GLOSSES)
+                .collect();
+
+            words.push(Word {
+                text: text.to_owned(),
+                reading: reading.text.to_owned(),
+                glosses,
+            });
+        }
+
+        Ok(Self {
+            title: title.to_owned(),
+            words,
+            kanji,
+        })
+    }
+}
+
+/// Render a sheet as a standalone HTML document.
+///
+/// The document doesn't refer to anything outside of itself, so it can be
+/// saved and printed without the service running.
+pub fn html(sheet: &Sheet) -> impl fmt::Display + '_ {
+    html::document(&sheet.title, STYLE, move |f| {
+        writeln!(f, "<h1>{}</h1>", Escape(&sheet.title))?;
+        writeln!(f, "<table class=\"words\">")?;
+
+        for word in &sheet.words {
+            writeln!(
+                f,
+                "<tr><td class=\"word\">{}</td><td>{}</td></tr>",
+                Furigana::new(&word.text, &word.reading, "").ruby(),
+                Escape(&word.glosses.join("; "))
+            )?;
+        }
+
+        writeln!(f, "</table>")?;
+
+        if sheet.kanji.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, "<h2>Kanji</h2>")?;
+        writeln!(f, "<table class=\"kanji\">")?;
+
+        for kanji in &sheet.kanji {
+            writeln!(
+                f,
+                "<tr><td class=\"literal\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                Escape(&kanji.literal),
+                Escape(&kanji.onyomi.join("、")),
+                Escape(&kanji.kunyomi.join("、")),
+                Escape(&kanji.meanings.join("; "))
+            )?;
+        }
+
+        writeln!(f, "</table>")
+    })
+}
+
+const STYLE: &str = "body{max-width:50em;margin:2em auto;padding:0 1em}\
+table{width:100%;border-collapse:collapse;margin-bottom:2em}\
+tr{page-break-inside:avoid;border-bottom:1px solid #ccc}\
+td{padding:0.4em 0.5em;vertical-align:middle}\
+rt{font-size:0.5em}\
+.word{font-size:1.5em;white-space:nowrap}\
+.literal{font-size:2em}\
+@media print{body{margin:0;max-width:none}}";
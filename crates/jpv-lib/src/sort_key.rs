@@ -19,12 +19,39 @@ impl Weight {
         Self(weight)
     }
 
+    /// Get the value of the weight, where heavier weights are better.
+    pub fn value(self) -> f32 {
+        self.0
+    }
+
     /// Boost the weight with the given factor.
     pub fn boost(self, factor: f32) -> Self {
         Self(self.0 * factor)
     }
 }
 
+/// The boost given to matching an input of the given length, which favors
+/// longer matches.
+pub(crate) fn length_boost(input: &str) -> f32 {
+    (input.chars().count().min(10) as f32 / 10.0) * 1.2
+}
+
+/// The rank in a word frequency list below which words are boosted.
+const FREQUENCY_RANKS: f32 = 100_000.0;
+
+/// The boost given to a word with the given rank in a word frequency list,
+/// which favors common words. The boost falls off logarithmically so that
+/// the difference between the 10th and the 100th word matters as much as the
+/// one between the 1000th and the 10000th.
+pub(crate) fn frequency_boost(rank: Option<u32>) -> f32 {
+    let Some(rank) = rank else {
+        return 1.0;
+    };
+
+    let rank = (rank.max(1) as f32).min(FREQUENCY_RANKS);
+    1.0 + (1.0 - rank.log10() / FREQUENCY_RANKS.log10()) * 0.5
+}
+
 impl PartialEq for Weight {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
@@ -50,3 +77,25 @@ impl Ord for Weight {
         }
     }
 }
+
+#[test]
+fn test_frequency_boost() {
+    assert_eq!(frequency_boost(None), 1.0);
+    assert_eq!(frequency_boost(Some(0)), frequency_boost(Some(1)));
+    assert_eq!(frequency_boost(Some(1)), 1.5);
+    assert_eq!(frequency_boost(Some(100_000)), 1.0);
+    assert_eq!(frequency_boost(Some(u32::MAX)), 1.0);
+
+    // More frequent words are boosted more, but never less than words which
+    // aren't listed at all.
+    let ranks = [1, 10, 100, 1_000, 10_000, 100_000];
+
+    for pair in ranks.windows(2) {
+        assert!(frequency_boost(Some(pair[0])) > frequency_boost(Some(pair[1])));
+    }
+
+    // The boost falls off logarithmically.
+    let a = frequency_boost(Some(10)) - frequency_boost(Some(100));
+    let b = frequency_boost(Some(1_000)) - frequency_boost(Some(10_000));
+    assert!((a - b).abs() < 1e-6);
+}
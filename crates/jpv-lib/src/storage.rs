@@ -0,0 +1,189 @@
+//! Crash-safe storage of configuration and user data.
+//!
+//! Files are written by first writing a temporary file next to them which is
+//! synced to disk and then renamed over the original, so a crash leaves
+//! either the old or the new version in place but never a partial one. The
+//! version being replaced is kept as a backup if it can be parsed, which is
+//! loaded instead if the file can't be, like after it has been edited by hand.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::{Context, Result};
+
+/// Counter used to give temporary files written by this process unique names.
+static NEXT: AtomicU32 = AtomicU32::new(0);
+
+/// Get the path of the backup kept for the given path.
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Load and parse the file at the given path, returning `None` if it doesn't
+/// exist.
+///
+/// If the file can't be read or parsed, the backup kept by [`save`] is parsed
+/// instead. The error of the file itself is returned if there is no backup or
+/// the backup can't be parsed either.
+pub fn load<T, F>(path: &Path, mut parse: F) -> Result<Option<T>>
+where
+    F: FnMut(&str) -> Result<T>,
+{
+    let error = match read(path, &mut parse) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+
+    let backup = backup_path(path);
+
+    match read(&backup, &mut parse) {
+        Ok(Some(value)) => {
+            tracing::warn!(
+                "Loaded backup {} since {} is broken: {error:#}",
+                backup.display(),
+                path.display()
+            );
+
+            Ok(Some(value))
+        }
+        Ok(None) => Err(error),
+        Err(backup_error) => {
+            tracing::warn!("Backup {} is broken: {backup_error:#}", backup.display());
+            Err(error)
+        }
+    }
+}
+
+/// Atomically replace the file at the given path with new contents.
+///
+/// The current version of the file is kept as a backup which [`load`] falls
+/// back to, but only if it can be parsed with `parse` so that a broken file
+/// never replaces a good backup. Parent directories are created if they're
+/// missing.
+pub fn save<T, F>(path: &Path, data: &[u8], mut parse: F) -> Result<()>
+where
+    F: FnMut(&str) -> Result<T>,
+{
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    fs::create_dir_all(parent)
+        .with_context(|| format!("Creating directory {}", parent.display()))?;
+
+    let temp = with_suffix(
+        path,
+        &format!(
+            ".{}.{}.tmp",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+
+    let result = write_synced(&temp, data)
+        .with_context(|| format!("Writing {}", temp.display()))
+        .and_then(|()| replace(path, &temp, &mut parse));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    result?;
+    sync_dir(parent);
+    Ok(())
+}
+
+/// Read and parse a file, returning `None` if it doesn't exist.
+fn read<T>(path: &Path, parse: &mut dyn FnMut(&str) -> Result<T>) -> Result<Option<T>> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(error).with_context(|| format!("Reading {}", path.display()));
+        }
+    };
+
+    let value = parse(&data).with_context(|| format!("Parsing {}", path.display()))?;
+    Ok(Some(value))
+}
+
+/// Write data to a new file and make sure it's on disk.
+fn write_synced(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+/// Replace the file at `path` with `temp`, keeping the current version as a
+/// backup if it can be parsed.
+fn replace<T>(path: &Path, temp: &Path, parse: &mut dyn FnMut(&str) -> Result<T>) -> Result<()> {
+    match read(path, parse) {
+        Ok(Some(..)) => {
+            let backup = backup_path(path);
+
+            // The current version is copied rather than moved, so that there's
+            // always a complete file at the path.
+            backup_synced(path, &backup).with_context(|| {
+                format!("Backing up {} to {}", path.display(), backup.display())
+            })?;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            tracing::warn!("Not backing up broken file: {error:#}");
+        }
+    }
+
+    fs::rename(temp, path)
+        .with_context(|| format!("Renaming {} to {}", temp.display(), path.display()))?;
+    Ok(())
+}
+
+/// Copy a file to its backup and make sure the backup is on disk.
+fn backup_synced(path: &Path, backup: &Path) -> io::Result<()> {
+    fs::copy(path, backup)?;
+    File::open(backup)?.sync_all()
+}
+
+/// Sync a directory so that renames in it are on disk. This is only possible
+/// on some platforms, so errors are ignored.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[test]
+fn test_save_and_load() {
+    let dir = std::env::temp_dir().join(format!("jpv-storage-test-{}", process::id()));
+    let path = dir.join("config.toml");
+    let parse = |data: &str| -> Result<u32> { Ok(data.parse()?) };
+
+    assert_eq!(load(&path, parse).unwrap(), None);
+
+    save(&path, b"1", parse).unwrap();
+    save(&path, b"2", parse).unwrap();
+    assert_eq!(load(&path, parse).unwrap(), Some(2));
+    assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "1");
+
+    // A broken file falls back to the backup.
+    fs::write(&path, "broken").unwrap();
+    assert_eq!(load(&path, parse).unwrap(), Some(1));
+
+    // And doesn't replace the backup when it's saved over.
+    save(&path, b"3", parse).unwrap();
+    assert_eq!(load(&path, parse).unwrap(), Some(3));
+    assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "1");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,202 @@
+//! Parser for corpora of example sentences from the [Tatoeba] project.
+//!
+//! Two formats are supported. The first is the Tanaka Corpus as distributed
+//! by the EDRDG, where each sentence is on a line starting with `A:` with its
+//! translation after a tab, and is followed by a line starting with `B:`
+//! listing the words it contains:
+//!
+//! ```text
+//! A: 彼は忙しい生活の中で家族と会うことがない。 He doesn't see his family in his busy life.#ID=303645_100000
+//! B: 彼(かれ)[01] は 忙しい 生活 の 中 で 家族 と 会う 事{こと} が 無い{ない}
+//! ```
+//!
+//! The second is the tab-separated sentence pairs which can be downloaded
+//! from Tatoeba, either as `id`, `japanese`, `id` and `english` columns, or
+//! as just `japanese` and `english`.
+//!
+//! [Tatoeba]: https://tatoeba.org
+
+use std::iter::Peekable;
+use std::str::Lines;
+
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// A Japanese sentence together with its English translation.
+#[borrowme::borrowme]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Encode, Decode)]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub struct Sentence<'a> {
+    /// The identifier of the Japanese sentence on Tatoeba, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub japanese: &'a str,
+    pub english: &'a str,
+    /// The dictionary forms of the words in the sentence, if the corpus lists
+    /// them.
+    #[borrowed_attr(serde(borrow))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<&'a str>,
+}
+
+/// A parser for example sentence corpora.
+pub struct Parser<'a> {
+    lines: Peekable<Lines<'a>>,
+    skipped: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Construct a new example sentence parser.
+    pub fn new(input: &'a str) -> Self {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+        Self {
+            lines: input.lines().peekable(),
+            skipped: 0,
+        }
+    }
+
+    /// The number of lines which were skipped since they could not be parsed.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Parse a sentence.
+    pub fn parse(&mut self) -> Option<Sentence<'a>> {
+        while let Some(line) = self.lines.next() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let sentence = if let Some(rest) = line.strip_prefix("A: ") {
+                let (text, id) = match rest.rsplit_once("#ID=") {
+                    Some((text, id)) => (text, parse_id(id)),
+                    None => (rest, None),
+                };
+
+                let words = match self.lines.next_if(|line| line.starts_with("B: ")) {
+                    Some(line) => words(&line[3..]),
+                    None => Vec::new(),
+                };
+
+                text.split_once('\t')
+                    .map(|(japanese, english)| (id, japanese, english, words))
+            } else {
+                let columns = line.split('\t').collect::<Vec<_>>();
+
+                match columns[..] {
+                    [id, japanese, _, english] => {
+                        Some((parse_id(id), japanese, english, Vec::new()))
+                    }
+                    [japanese, english] => Some((None, japanese, english, Vec::new())),
+                    _ => None,
+                }
+            };
+
+            let Some((id, japanese, english, words)) = sentence else {
+                self.skipped += 1;
+                continue;
+            };
+
+            let japanese = japanese.trim();
+            let english = english.trim();
+
+            if japanese.is_empty() || english.is_empty() {
+                self.skipped += 1;
+                continue;
+            }
+
+            return Some(Sentence {
+                id,
+                japanese,
+                english,
+                words,
+            });
+        }
+
+        None
+    }
+}
+
+/// Parse the identifier of a sentence, which in the Tanaka Corpus is followed
+/// by the identifier of its translation like `303645_100000`.
+fn parse_id(id: &str) -> Option<u64> {
+    let id = id.trim();
+    let id = id.split_once('_').map_or(id, |(id, _)| id);
+    id.parse().ok()
+}
+
+/// Parse the words of a `B:` line, which are annotated with their reading in
+/// parenthesis, the sense they're used in in brackets, the form they appear
+/// in in braces and a trailing `~` if the sentence is a good example of them.
+fn words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+
+    for word in line.split_whitespace() {
+        let word = match word.find(['(', '[', '{', '~', '|']) {
+            Some(n) => &word[..n],
+            None => word,
+        };
+
+        if !word.is_empty() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+
+    words
+}
+
+#[test]
+fn test_parse() {
+    let input = "\
+A: 彼は忙しい生活の中で家族と会うことがない。\tHe doesn't see his family in his busy life.#ID=303645_100000
+B: 彼(かれ)[01] は 忙しい 生活 の 中 で 家族 と 会う 事{こと} が 無い{ない} は~
+4702\t猫が好きです。\t1234\tI like cats.
+犬がいる。\tThere is a dog.
+bad
+";
+
+    let mut parser = Parser::new(input);
+
+    let sentence = parser.parse().expect("sentence");
+    assert_eq!(sentence.id, Some(303645));
+    assert_eq!(
+        sentence.japanese,
+        "彼は忙しい生活の中で家族と会うことがない。"
+    );
+    assert_eq!(
+        sentence.english,
+        "He doesn't see his family in his busy life."
+    );
+    assert_eq!(
+        sentence.words,
+        [
+            "彼",
+            "は",
+            "忙しい",
+            "生活",
+            "の",
+            "中",
+            "で",
+            "家族",
+            "と",
+            "会う",
+            "事",
+            "が",
+            "無い"
+        ]
+    );
+
+    let sentence = parser.parse().expect("sentence");
+    assert_eq!(sentence.id, Some(4702));
+    assert_eq!(sentence.japanese, "猫が好きです。");
+    assert_eq!(sentence.english, "I like cats.");
+    assert!(sentence.words.is_empty());
+
+    let sentence = parser.parse().expect("sentence");
+    assert_eq!(sentence.id, None);
+    assert_eq!(sentence.japanese, "犬がいる。");
+
+    assert_eq!(parser.parse(), None);
+    assert_eq!(parser.skipped(), 1);
+}
@@ -0,0 +1,150 @@
+//! Words for kana typing practice.
+//!
+//! Only common words are picked, since they are the ones worth practicing, and
+//! they are grouped into difficulties by how frequent they are according to
+//! the priorities in the dictionary.
+
+use musli::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::entities::Miscellaneous;
+use crate::jmdict;
+use crate::kana;
+use crate::priority;
+use crate::Priority;
+
+/// How difficult a word is to type, going by how frequent it is.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "kebab-case")]
+#[musli(mode = Text, name_all = "kebab-case")]
+pub enum Difficulty {
+    /// Among the 5000 most frequent words.
+    Easy,
+    /// Among the 12000 most frequent words, or common without a known
+    /// frequency.
+    Medium,
+    /// Any other common word.
+    Hard,
+}
+
+impl Difficulty {
+    /// The difficulty of a word with the given priorities, or `None` if it's
+    /// not a common word.
+    pub fn of<'a, I>(priorities: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a Priority>,
+    {
+        let commonness = priority::commonness(priorities);
+
+        if !commonness.common {
+            return None;
+        }
+
+        // Frequency categories are in bands of 500 words.
+        match commonness.frequency {
+            Some(1..=10) => Some(Difficulty::Easy),
+            Some(11..=24) | None => Some(Difficulty::Medium),
+            Some(..) => Some(Difficulty::Hard),
+        }
+    }
+}
+
+/// Which words to pick.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Filter {
+    /// The minimum number of kana in the reading.
+    pub min_length: Option<usize>,
+    /// The maximum number of kana in the reading.
+    pub max_length: Option<usize>,
+    /// Only pick words which are usually written in kana alone.
+    pub kana_only: bool,
+    /// Only pick words of the given difficulty.
+    pub difficulty: Option<Difficulty>,
+}
+
+/// A word to type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The sequence number of the entry.
+    pub sequence: u64,
+    /// The word as it's usually written.
+    pub text: String,
+    /// The kana to type.
+    pub reading: String,
+    pub difficulty: Difficulty,
+}
+
+/// Convert an entry into a word to type, if it matches the filter.
+pub fn word(entry: &jmdict::Entry<'_>, filter: &Filter) -> Option<Word> {
+    let difficulty = Difficulty::of(entry.priorities())?;
+
+    if filter.difficulty.is_some_and(|d| d != difficulty) {
+        return None;
+    }
+
+    let (kanji, reading) = entry.preferred_form()?;
+
+    // Words have to be typed entirely in kana, so readings with symbols or
+    // latin letters in them are skipped.
+    if !reading.text.chars().all(is_kana) {
+        return None;
+    }
+
+    let length = reading.text.chars().count();
+
+    if filter.min_length.is_some_and(|min| length < min)
+        || filter.max_length.is_some_and(|max| length > max)
+    {
+        return None;
+    }
+
+    let usually_kana = kanji.is_none()
+        || entry
+            .senses
+            .iter()
+            .all(|sense| sense.misc.contains(Miscellaneous::UsuallyKana));
+
+    if filter.kana_only && !usually_kana {
+        return None;
+    }
+
+    let text = match kanji {
+        Some(kanji) if !usually_kana => kanji.text,
+        _ => reading.text,
+    };
+
+    Some(Word {
+        sequence: entry.sequence,
+        text: text.to_owned(),
+        reading: reading.text.to_owned(),
+        difficulty,
+    })
+}
+
+fn is_kana(c: char) -> bool {
+    kana::is_hiragana(c) || kana::is_katakana(c) || c == 'ー'
+}
+
+#[test]
+fn test_difficulty() {
+    let of = |priorities: &[&str]| {
+        let priorities = priorities
+            .iter()
+            .map(|p| Priority::parse(p).unwrap())
+            .collect::<Vec<_>>();
+
+        Difficulty::of(&priorities)
+    };
+
+    assert_eq!(of(&["ichi1", "news1", "nf02"]), Some(Difficulty::Easy));
+    assert_eq!(of(&["news1", "nf15"]), Some(Difficulty::Medium));
+    assert_eq!(of(&["spec1"]), Some(Difficulty::Medium));
+    assert_eq!(of(&["spec2"]), Some(Difficulty::Medium));
+    assert_eq!(of(&["news1", "nf40"]), Some(Difficulty::Hard));
+    assert_eq!(of(&["news2", "nf40"]), None);
+    assert_eq!(of(&["nf02"]), None);
+    assert_eq!(of(&["ichi2"]), None);
+    assert_eq!(of(&[]), None);
+}
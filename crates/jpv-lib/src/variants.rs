@@ -0,0 +1,259 @@
+//! Old and variant forms of kanji, like 國 for 国.
+//!
+//! Old forms (旧字体) and variant forms (異体字) are still found in names and
+//! older texts. Every such kanji is mapped to its standard form, so that text
+//! written with them can be matched against text written with standard forms.
+
+use std::borrow::Cow;
+
+/// Variant kanji and their standard forms, sorted by variant.
+const VARIANTS: &[(char, char)] = &[
+    ('乘', '乗'),
+    ('亂', '乱'),
+    ('亞', '亜'),
+    ('佛', '仏'),
+    ('來', '来'),
+    ('假', '仮'),
+    ('傳', '伝'),
+    ('價', '価'),
+    ('兒', '児'),
+    ('兩', '両'),
+    ('冨', '富'),
+    ('剩', '剰'),
+    ('劍', '剣'),
+    ('勞', '労'),
+    ('勳', '勲'),
+    ('區', '区'),
+    ('卷', '巻'),
+    ('參', '参'),
+    ('單', '単'),
+    ('嚴', '厳'),
+    ('圈', '圏'),
+    ('國', '国'),
+    ('圍', '囲'),
+    ('圓', '円'),
+    ('圖', '図'),
+    ('壓', '圧'),
+    ('壯', '壮'),
+    ('壽', '寿'),
+    ('姬', '姫'),
+    ('學', '学'),
+    ('寢', '寝'),
+    ('實', '実'),
+    ('寫', '写'),
+    ('將', '将'),
+    ('專', '専'),
+    ('對', '対'),
+    ('屆', '届'),
+    ('嶋', '島'),
+    ('帶', '帯'),
+    ('廢', '廃'),
+    ('廣', '広'),
+    ('廳', '庁'),
+    ('彈', '弾'),
+    ('彌', '弥'),
+    ('徑', '径'),
+    ('從', '従'),
+    ('德', '徳'),
+    ('惠', '恵'),
+    ('惡', '悪'),
+    ('應', '応'),
+    ('戀', '恋'),
+    ('戰', '戦'),
+    ('戲', '戯'),
+    ('拂', '払'),
+    ('拜', '拝'),
+    ('擇', '択'),
+    ('擔', '担'),
+    ('據', '拠'),
+    ('擧', '挙'),
+    ('收', '収'),
+    ('效', '効'),
+    ('敎', '教'),
+    ('數', '数'),
+    ('斷', '断'),
+    ('晝', '昼'),
+    ('曉', '暁'),
+    ('會', '会'),
+    ('條', '条'),
+    ('榮', '栄'),
+    ('樂', '楽'),
+    ('樓', '楼'),
+    ('檢', '検'),
+    ('櫻', '桜'),
+    ('權', '権'),
+    ('歐', '欧'),
+    ('歡', '歓'),
+    ('步', '歩'),
+    ('歸', '帰'),
+    ('殘', '残'),
+    ('每', '毎'),
+    ('氣', '気'),
+    ('涉', '渉'),
+    ('淨', '浄'),
+    ('淺', '浅'),
+    ('溫', '温'),
+    ('滿', '満'),
+    ('澁', '渋'),
+    ('澤', '沢'),
+    ('濱', '浜'),
+    ('瀧', '滝'),
+    ('燈', '灯'),
+    ('爐', '炉'),
+    ('爭', '争'),
+    ('狀', '状'),
+    ('狹', '狭'),
+    ('獨', '独'),
+    ('獸', '獣'),
+    ('瓣', '弁'),
+    ('產', '産'),
+    ('畫', '画'),
+    ('當', '当'),
+    ('疊', '畳'),
+    ('發', '発'),
+    ('盜', '盗'),
+    ('盡', '尽'),
+    ('眞', '真'),
+    ('硏', '研'),
+    ('碎', '砕'),
+    ('祕', '秘'),
+    ('禮', '礼'),
+    ('稱', '称'),
+    ('稻', '稲'),
+    ('穗', '穂'),
+    ('竊', '窃'),
+    ('竝', '並'),
+    ('粹', '粋'),
+    ('經', '経'),
+    ('縣', '県'),
+    ('縱', '縦'),
+    ('總', '総'),
+    ('繪', '絵'),
+    ('續', '続'),
+    ('纖', '繊'),
+    ('聲', '声'),
+    ('聽', '聴'),
+    ('肅', '粛'),
+    ('脫', '脱'),
+    ('臟', '臓'),
+    ('與', '与'),
+    ('舊', '旧'),
+    ('舍', '舎'),
+    ('莊', '荘'),
+    ('萠', '萌'),
+    ('萬', '万'),
+    ('藏', '蔵'),
+    ('藝', '芸'),
+    ('藥', '薬'),
+    ('處', '処'),
+    ('號', '号'),
+    ('螢', '蛍'),
+    ('蟲', '虫'),
+    ('蠶', '蚕'),
+    ('衞', '衛'),
+    ('裝', '装'),
+    ('覺', '覚'),
+    ('觀', '観'),
+    ('觸', '触'),
+    ('證', '証'),
+    ('譯', '訳'),
+    ('譽', '誉'),
+    ('讀', '読'),
+    ('變', '変'),
+    ('豐', '豊'),
+    ('貳', '弐'),
+    ('賣', '売'),
+    ('賴', '頼'),
+    ('輕', '軽'),
+    ('轉', '転'),
+    ('辨', '弁'),
+    ('辭', '辞'),
+    ('辯', '弁'),
+    ('遲', '遅'),
+    ('邉', '辺'),
+    ('邊', '辺'),
+    ('鄕', '郷'),
+    ('醉', '酔'),
+    ('醫', '医'),
+    ('釀', '醸'),
+    ('錢', '銭'),
+    ('鐵', '鉄'),
+    ('鑛', '鉱'),
+    ('關', '関'),
+    ('陷', '陥'),
+    ('隨', '随'),
+    ('險', '険'),
+    ('雙', '双'),
+    ('雜', '雑'),
+    ('靈', '霊'),
+    ('靜', '静'),
+    ('顯', '顕'),
+    ('餘', '余'),
+    ('驅', '駆'),
+    ('驗', '験'),
+    ('驛', '駅'),
+    ('體', '体'),
+    ('髙', '高'),
+    ('髮', '髪'),
+    ('鬪', '闘'),
+    ('鷄', '鶏'),
+    ('鹽', '塩'),
+    ('麥', '麦'),
+    ('黑', '黒'),
+    ('默', '黙'),
+    ('黨', '党'),
+    ('齊', '斉'),
+    ('齋', '斎'),
+    ('齒', '歯'),
+    ('齡', '齢'),
+    ('龍', '竜'),
+    ('﨑', '崎'),
+];
+
+/// Get the standard form of a variant kanji, like 国 for 國.
+pub fn standard(c: char) -> Option<char> {
+    let n = VARIANTS
+        .binary_search_by_key(&c, |&(variant, _)| variant)
+        .ok()?;
+    Some(VARIANTS[n].1)
+}
+
+/// Get every other form of a kanji, including its standard form if it's a
+/// variant, like 国 for 國 and 國 for 国.
+pub fn variants(c: char) -> impl Iterator<Item = char> {
+    let standard = standard(c).unwrap_or(c);
+
+    let others = VARIANTS
+        .iter()
+        .filter(move |&&(v, s)| s == standard && v != c)
+        .map(|&(v, _)| v);
+
+    (standard != c)
+        .then_some(standard)
+        .into_iter()
+        .chain(others)
+}
+
+/// Replace variant kanji in the given text with their standard forms.
+pub fn normalize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| standard(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(text.chars().map(|c| standard(c).unwrap_or(c)).collect())
+}
+
+#[test]
+fn test_variants() {
+    assert!(VARIANTS.windows(2).all(|w| w[0].0 < w[1].0));
+
+    assert_eq!(normalize("國語"), "国語");
+    assert!(matches!(normalize("国語"), Cow::Borrowed(..)));
+    assert_eq!(normalize("齋藤さん"), "斎藤さん");
+
+    assert_eq!(variants('國').collect::<Vec<_>>(), ['国']);
+    assert_eq!(variants('国').collect::<Vec<_>>(), ['國']);
+    assert_eq!(variants('辯').collect::<Vec<_>>(), ['弁', '瓣', '辨']);
+    assert_eq!(variants('弁').count(), 3);
+    assert_eq!(variants('語').count(), 0);
+}
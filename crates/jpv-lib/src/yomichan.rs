@@ -0,0 +1,583 @@
+//! Support for dictionaries in the format used by Yomichan and Yomitan.
+//!
+//! Such a dictionary is a zip archive of JSON files called banks. Terms are
+//! converted into phrases and kanji into characters, so that they are indexed
+//! and shown like entries from JMdict and kanjidic2. Tags which are JMdict
+//! entities, like `n` or `v5r`, are kept as parts of speech and the like,
+//! while the notes of other tags are kept as information on the sense.
+//!
+//! Term and kanji meta banks, which hold frequencies and pitch accents, are
+//! not supported and are skipped.
+
+use std::collections::hash_map::{self, HashMap};
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{Map, Value};
+
+use crate::entities::{Dialect, Field, Miscellaneous, PartOfSpeech};
+use crate::jmdict;
+use crate::kana;
+use crate::kanjidic2;
+use crate::Priority;
+
+/// Terms are given sequences in a range of their own starting from here,
+/// which is far above the sequences used by JMdict and JMnedict. Dictionaries
+/// converted from JMdict reuse its sequences, which would otherwise be shown
+/// and linked to as if they were the same entries.
+///
+/// The sequence of a term is put at the same offset into the range, as long
+/// as that is below [`GENERATED_SEQUENCE`].
+const SEQUENCE_BASE: u64 = 0x8000_0000;
+
+/// Sequences are given to terms which don't have one starting from here.
+const GENERATED_SEQUENCE: u64 = 0xc000_0000;
+
+/// A bank in a dictionary archive.
+#[derive(Debug, Clone, Copy)]
+pub struct Bank<'a> {
+    /// The name of the file in the archive.
+    pub name: &'a str,
+    /// The JSON contents of the file.
+    pub contents: &'a str,
+}
+
+/// The kind of a bank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankKind {
+    /// The `index.json` which describes the dictionary.
+    Index,
+    Term,
+    TermMeta,
+    Kanji,
+    KanjiMeta,
+    Tag,
+}
+
+impl BankKind {
+    /// Get the kind of bank from the name of its file, or `None` if it's not
+    /// a bank.
+    pub fn of(name: &str) -> Option<Self> {
+        let name = name.rsplit('/').next().unwrap_or(name);
+
+        if name == "index.json" {
+            return Some(BankKind::Index);
+        }
+
+        let prefix = name
+            .strip_suffix(".json")?
+            .trim_end_matches(|c: char| c.is_ascii_digit());
+
+        Some(match prefix {
+            "term_bank_" => BankKind::Term,
+            "term_meta_bank_" => BankKind::TermMeta,
+            "kanji_bank_" => BankKind::Kanji,
+            "kanji_meta_bank_" => BankKind::KanjiMeta,
+            "tag_bank_" => BankKind::Tag,
+            _ => return None,
+        })
+    }
+}
+
+/// A converted dictionary.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    /// The title of the dictionary.
+    pub title: Option<String>,
+    /// Terms converted into phrases.
+    pub phrases: Vec<jmdict::OwnedEntry>,
+    /// Kanji converted into characters.
+    pub kanji: Vec<kanjidic2::OwnedCharacter>,
+    /// The names of banks which were skipped because what they contain is not
+    /// supported.
+    pub skipped: Vec<String>,
+}
+
+/// A tag defined in a tag bank.
+struct Tag {
+    category: String,
+    notes: String,
+}
+
+impl Dictionary {
+    /// Convert a dictionary from its banks.
+    pub fn parse(banks: &[Bank<'_>]) -> Result<Self> {
+        let mut dictionary = Dictionary::default();
+        let mut version = 3;
+        let mut tags = HashMap::new();
+
+        for bank in banks {
+            match BankKind::of(bank.name) {
+                Some(BankKind::Index) => {
+                    let index = serde_json::from_str::<Map<String, Value>>(bank.contents)
+                        .with_context(|| anyhow!("{}", bank.name))?;
+
+                    dictionary.title = index.get("title").and_then(Value::as_str).map(From::from);
+
+                    if let Some(v) = index.get("format").or_else(|| index.get("version")) {
+                        version = v.as_u64().context("Unsupported dictionary version")?;
+                    }
+                }
+                Some(BankKind::Tag) => {
+                    for row in rows(bank)? {
+                        let (Some(name), Some(category)) = (str_at(&row, 0), str_at(&row, 1))
+                        else {
+                            continue;
+                        };
+
+                        let notes = str_at(&row, 3).unwrap_or_default();
+
+                        tags.insert(
+                            name.to_owned(),
+                            Tag {
+                                category: category.to_owned(),
+                                notes: notes.to_owned(),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !matches!(version, 1 | 3) {
+            bail!("Unsupported dictionary version {version}");
+        }
+
+        let mut terms = Terms::default();
+
+        for bank in banks {
+            match BankKind::of(bank.name) {
+                Some(BankKind::Term) => {
+                    for (n, row) in rows(bank)?.into_iter().enumerate() {
+                        terms
+                            .insert(&row, version, &tags)
+                            .with_context(|| anyhow!("{}: term {}", bank.name, n + 1))?;
+                    }
+                }
+                Some(BankKind::Kanji) => {
+                    for (n, row) in rows(bank)?.into_iter().enumerate() {
+                        let character = character(&row, version)
+                            .with_context(|| anyhow!("{}: kanji {}", bank.name, n + 1))?;
+                        dictionary.kanji.push(character);
+                    }
+                }
+                Some(BankKind::TermMeta | BankKind::KanjiMeta) => {
+                    dictionary.skipped.push(bank.name.to_owned());
+                }
+                _ => {}
+            }
+        }
+
+        dictionary.phrases = terms.finish();
+        Ok(dictionary)
+    }
+}
+
+/// Terms being converted into phrases, where terms with the same sequence are
+/// merged into one phrase like they are in JMdict.
+#[derive(Default)]
+struct Terms {
+    phrases: Vec<jmdict::OwnedEntry>,
+    by_sequence: HashMap<u64, usize>,
+    generated: u64,
+}
+
+impl Terms {
+    fn insert(&mut self, row: &[Value], version: u64, tags: &HashMap<String, Tag>) -> Result<()> {
+        let expression = str_at(row, 0).context("Missing expression")?;
+        let reading = str_at(row, 1)
+            .filter(|r| !r.is_empty())
+            .unwrap_or(expression);
+
+        let mut glosses = Vec::new();
+
+        if version == 1 {
+            for value in row.iter().skip(5) {
+                glossary(value, &mut glosses);
+            }
+        } else if let Some(Value::Array(values)) = row.get(5) {
+            for value in values {
+                glossary(value, &mut glosses);
+            }
+        }
+
+        let sequence = match row.get(6).and_then(Value::as_i64) {
+            Some(sequence)
+                if version == 3
+                    && sequence > 0
+                    && (sequence as u64) < GENERATED_SEQUENCE - SEQUENCE_BASE =>
+            {
+                SEQUENCE_BASE + sequence as u64
+            }
+            _ => {
+                self.generated += 1;
+                GENERATED_SEQUENCE + self.generated
+            }
+        };
+
+        let index = match self.by_sequence.entry(sequence) {
+            hash_map::Entry::Occupied(e) => *e.get(),
+            hash_map::Entry::Vacant(e) => {
+                self.phrases.push(jmdict::OwnedEntry {
+                    sequence,
+                    reading_elements: Vec::new(),
+                    kanji_elements: Vec::new(),
+                    senses: Vec::new(),
+                });
+
+                *e.insert(self.phrases.len() - 1)
+            }
+        };
+
+        let entry = &mut self.phrases[index];
+
+        // Popular terms are marked as common, which is what the `P` tag of
+        // dictionaries converted from JMdict stands for.
+        let popular = words(str_at(row, 7))
+            .any(|tag| tag == "P" || tags.get(tag).is_some_and(|t| t.category == "popular"));
+
+        let priority = popular
+            .then(|| Priority::parse("spec1"))
+            .flatten()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let has_kanji = expression != reading && !expression.chars().all(is_kana);
+
+        if has_kanji && !entry.kanji_elements.iter().any(|k| k.text == expression) {
+            entry.kanji_elements.push(jmdict::OwnedKanjiElement {
+                text: expression.to_owned(),
+                priority: priority.clone(),
+                info: Default::default(),
+            });
+        }
+
+        match entry
+            .reading_elements
+            .iter_mut()
+            .find(|r| r.text == reading)
+        {
+            Some(element) => {
+                if has_kanji && !element.reading_string.is_empty() {
+                    element.reading_string.insert(expression.to_owned());
+                }
+            }
+            None => {
+                let mut reading_string = HashSet::new();
+
+                if has_kanji {
+                    reading_string.insert(expression.to_owned());
+                }
+
+                entry.reading_elements.push(jmdict::OwnedReadingElement {
+                    text: reading.to_owned(),
+                    no_kanji: false,
+                    reading_string,
+                    priority,
+                    info: Default::default(),
+                });
+            }
+        }
+
+        if glosses.is_empty() {
+            return Ok(());
+        }
+
+        // Terms with the same sequence repeat the same glossary for each way
+        // the phrase is written.
+        let duplicate = entry.senses.iter().any(|sense| {
+            sense.gloss.len() == glosses.len()
+                && sense.gloss.iter().zip(&glosses).all(|(a, b)| a.text == *b)
+        });
+
+        if duplicate {
+            return Ok(());
+        }
+
+        let mut sense = jmdict::OwnedSense::default();
+        let mut notes = Vec::new();
+
+        for tag in words(str_at(row, 2)).chain(words(str_at(row, 3))) {
+            if let Some(pos) = PartOfSpeech::parse_keyword(tag) {
+                sense.pos.insert(pos);
+            } else if let Some(misc) = Miscellaneous::parse_keyword(tag) {
+                sense.misc.insert(misc);
+            } else if let Some(dialect) = Dialect::parse_keyword(tag) {
+                sense.dialect.insert(dialect);
+            } else if let Some(field) = Field::parse_keyword(tag) {
+                sense.field.insert(field);
+            } else if let Some(tag) = tags.get(tag).filter(|t| !t.notes.is_empty()) {
+                if !notes.contains(&tag.notes.as_str()) {
+                    notes.push(tag.notes.as_str());
+                }
+            }
+        }
+
+        if !notes.is_empty() {
+            sense.info = Some(notes.join(", "));
+        }
+
+        sense.gloss = glosses
+            .into_iter()
+            .map(|text| jmdict::OwnedGlossary {
+                text,
+                ty: None,
+                lang: None,
+            })
+            .collect();
+
+        entry.senses.push(sense);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Vec<jmdict::OwnedEntry> {
+        // Readings which apply to every way a phrase is written don't need to
+        // say so.
+        for entry in &mut self.phrases {
+            let kanji = entry.kanji_elements.len();
+
+            for element in &mut entry.reading_elements {
+                if element.reading_string.len() == kanji {
+                    element.reading_string.clear();
+                }
+            }
+        }
+
+        self.phrases
+    }
+}
+
+/// Convert a row of a kanji bank into a character.
+fn character(row: &[Value], version: u64) -> Result<kanjidic2::OwnedCharacter> {
+    let literal = str_at(row, 0).context("Missing character")?;
+
+    let mut readings = Vec::new();
+
+    for (ty, index) in [("ja_on", 1), ("ja_kun", 2)] {
+        for text in words(str_at(row, index)) {
+            readings.push(kanjidic2::OwnedReading {
+                text: text.to_owned(),
+                ty: ty.to_owned(),
+            });
+        }
+    }
+
+    let meanings = match version {
+        1 => row.iter().skip(4).collect::<Vec<_>>(),
+        _ => match row.get(4) {
+            Some(Value::Array(values)) => values.iter().collect(),
+            _ => Vec::new(),
+        },
+    };
+
+    let meanings = meanings
+        .into_iter()
+        .filter_map(Value::as_str)
+        .map(|text| kanjidic2::OwnedMeaning {
+            text: text.to_owned(),
+            lang: None,
+        })
+        .collect();
+
+    let mut misc = kanjidic2::OwnedMisc::default();
+
+    if let Some(Value::Object(stats)) = row.get(5) {
+        let stat = |name: &str| {
+            let value = stats.get(name)?;
+            value
+                .as_u64()
+                .or_else(|| value.as_str()?.trim().parse().ok())
+        };
+
+        misc.stroke_counts
+            .extend(stat("strokes").and_then(|n| u8::try_from(n).ok()));
+        misc.grade = stat("grade").and_then(|n| u8::try_from(n).ok());
+        misc.freq = stat("freq").and_then(|n| u32::try_from(n).ok());
+        misc.jlpt = stat("jlpt").and_then(|n| u8::try_from(n).ok());
+    }
+
+    Ok(kanjidic2::OwnedCharacter {
+        literal: literal.to_owned(),
+        code_point: Vec::new(),
+        radical: Vec::new(),
+        misc,
+        dictionary_references: Vec::new(),
+        query_codes: Vec::new(),
+        readings,
+        meanings,
+        nanori: Vec::new(),
+    })
+}
+
+/// Collect the text of a glossary item.
+///
+/// Items are either plain strings or objects, where structured content is
+/// flattened into text. Images and other items without text are skipped.
+fn glossary(value: &Value, output: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            output.push(text.clone());
+        }
+        Value::Object(object) => {
+            let text = match object.get("type").and_then(Value::as_str) {
+                Some("text") => object.get("text").and_then(Value::as_str).map(From::from),
+                Some("structured-content") => {
+                    let mut text = String::new();
+
+                    if let Some(content) = object.get("content") {
+                        structured(content, &mut text);
+                    }
+
+                    Some(text)
+                }
+                _ => None,
+            };
+
+            if let Some(text) = text {
+                let text = text.trim().trim_end_matches(';').trim_end();
+
+                if !text.is_empty() {
+                    output.push(text.to_owned());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Flatten structured content into text.
+fn structured(value: &Value, output: &mut String) {
+    match value {
+        Value::String(text) => {
+            output.push_str(text);
+        }
+        Value::Array(values) => {
+            for value in values {
+                structured(value, output);
+            }
+        }
+        Value::Object(object) => {
+            let tag = object
+                .get("tag")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+
+            match tag {
+                // Readings on top of ruby text would otherwise be run together
+                // with the text they're for.
+                "rt" | "rp" | "img" => return,
+                "br" | "div" | "li" | "p" | "tr" => {
+                    let trimmed = output.trim_end();
+
+                    if !trimmed.is_empty() && !trimmed.ends_with(';') {
+                        output.truncate(trimmed.len());
+                        output.push_str("; ");
+                    }
+                }
+                _ => {}
+            }
+
+            if let Some(content) = object.get("content") {
+                structured(content, output);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parse the rows of a bank, which is a JSON array of arrays.
+fn rows(bank: &Bank<'_>) -> Result<Vec<Vec<Value>>> {
+    serde_json::from_str(bank.contents).with_context(|| anyhow!("{}", bank.name))
+}
+
+fn str_at(row: &[Value], index: usize) -> Option<&str> {
+    row.get(index)?.as_str()
+}
+
+/// Split space-separated words, like tags and readings.
+fn words(string: Option<&str>) -> impl Iterator<Item = &str> {
+    string.unwrap_or_default().split_whitespace()
+}
+
+fn is_kana(c: char) -> bool {
+    kana::is_hiragana(c) || kana::is_katakana(c) || c == 'ー'
+}
+
+#[test]
+fn test_bank_kind() {
+    assert_eq!(BankKind::of("index.json"), Some(BankKind::Index));
+    assert_eq!(BankKind::of("term_bank_12.json"), Some(BankKind::Term));
+    assert_eq!(
+        BankKind::of("dict/term_meta_bank_1.json"),
+        Some(BankKind::TermMeta)
+    );
+    assert_eq!(BankKind::of("kanji_bank_1.json"), Some(BankKind::Kanji));
+    assert_eq!(BankKind::of("tag_bank_1.json"), Some(BankKind::Tag));
+    assert_eq!(BankKind::of("styles.css"), None);
+}
+
+#[test]
+fn test_parse() {
+    let index = r#"{"title": "Test", "format": 3, "revision": "1"}"#;
+    let tags =
+        r#"[["P", "popular", -10, "popular term", 10], ["news", "misc", 0, "used in news", 0]]"#;
+
+    let terms = r#"[
+        ["食べる", "たべる", "v1 vt", "v1", 0, ["to eat"], 1358280, "P"],
+        ["喰べる", "たべる", "v1 vt", "v1", 0, ["to eat"], 1358280, ""],
+        ["ふりがな", "", "n news", "", 0, [{"type": "structured-content", "content": [{"tag": "ruby", "content": ["振", {"tag": "rt", "content": "ふ"}]}, "り仮名", {"tag": "br"}, "reading aid"]}], 0, ""]
+    ]"#;
+
+    let kanji = r#"[["食", "ショク ジキ", "く.う た.べる", "jouyou", ["eat", "food"], {"strokes": "9", "grade": "2"}]]"#;
+
+    let banks = [
+        Bank {
+            name: "index.json",
+            contents: index,
+        },
+        Bank {
+            name: "tag_bank_1.json",
+            contents: tags,
+        },
+        Bank {
+            name: "term_bank_1.json",
+            contents: terms,
+        },
+        Bank {
+            name: "kanji_bank_1.json",
+            contents: kanji,
+        },
+        Bank {
+            name: "term_meta_bank_1.json",
+            contents: "[]",
+        },
+    ];
+
+    let dictionary = Dictionary::parse(&banks).unwrap();
+    assert_eq!(dictionary.title.as_deref(), Some("Test"));
+    assert_eq!(dictionary.skipped, ["term_meta_bank_1.json"]);
+    assert_eq!(dictionary.phrases.len(), 2);
+
+    let taberu = &dictionary.phrases[0];
+    assert_eq!(taberu.sequence, SEQUENCE_BASE + 1358280);
+    assert_eq!(taberu.kanji_elements.len(), 2);
+    assert_eq!(taberu.kanji_elements[0].priority.len(), 1);
+    assert_eq!(taberu.reading_elements.len(), 1);
+    assert!(taberu.reading_elements[0].reading_string.is_empty());
+    assert_eq!(taberu.senses.len(), 1);
+    assert!(taberu.senses[0].pos.contains(PartOfSpeech::VerbIchidan));
+
+    let furigana = &dictionary.phrases[1];
+    assert!(furigana.sequence > GENERATED_SEQUENCE);
+    assert!(furigana.kanji_elements.is_empty());
+    assert_eq!(furigana.senses[0].gloss[0].text, "振り仮名; reading aid");
+    assert_eq!(furigana.senses[0].info.as_deref(), Some("used in news"));
+
+    let c = &dictionary.kanji[0];
+    assert_eq!(c.literal, "食");
+    assert_eq!(c.readings.len(), 4);
+    assert_eq!(c.meanings.len(), 2);
+    assert_eq!(c.misc.stroke_counts, [9]);
+    assert_eq!(c.misc.grade, Some(2));
+}
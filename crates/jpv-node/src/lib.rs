@@ -0,0 +1,123 @@
+//! Node.js bindings for jpv.
+//!
+//! This embeds the dictionary in Node and Electron applications without
+//! running the service. It reads the same indexes and configuration as the
+//! service, so indexes have to be built with `jpv build` first.
+//!
+//! ```js
+//! const jpv = require('jpv');
+//!
+//! const dictionary = await jpv.open();
+//! const search = await dictionary.search('食べる');
+//!
+//! for (const phrase of search.phrases) {
+//!     console.log(phrase.sequence);
+//! }
+//!
+//! console.log(await dictionary.analyze('今日は', 0));
+//! ```
+//!
+//! Every method runs on a blocking thread and returns a promise, so lookups
+//! don't stall the event loop.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use lib::api::OffsetEncoding;
+use lib::config::Config;
+use lib::data;
+use lib::database::Database;
+use lib::{jmdict, jmnedict, kanjidic2, Dirs};
+use napi_derive::napi;
+use serde::Serialize;
+
+/// Open the dictionary with the indexes installed by jpv, or with the given
+/// index files.
+#[napi]
+pub async fn open(indexes: Option<Vec<String>>) -> napi::Result<Dictionary> {
+    let indexes = indexes
+        .unwrap_or_default()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    let db = blocking(move || {
+        let dirs = Dirs::open()?;
+        let config = Config::load(&dirs).context("Loading configuration")?;
+        let indexes = data::open_from_args(&indexes, &dirs)?;
+        Database::open(indexes, &config)
+    })
+    .await?;
+
+    Ok(Dictionary { db })
+}
+
+/// An opened dictionary.
+#[napi]
+pub struct Dictionary {
+    db: Database,
+}
+
+#[napi]
+impl Dictionary {
+    /// Search the dictionary, using the same query syntax as the service.
+    ///
+    /// Resolves to an object with the matching `phrases`, `names` and
+    /// `characters`.
+    #[napi]
+    pub async fn search(&self, q: String) -> napi::Result<serde_json::Value> {
+        let db = self.db.clone();
+        blocking(move || search(&db, &q)).await
+    }
+
+    /// Find the words which the text at the given offset could be the start
+    /// of, with the most likely word first. The offset is in UTF-16 code
+    /// units, like the indexes of JavaScript strings.
+    #[napi]
+    pub async fn analyze(&self, text: String, start: u32) -> napi::Result<Vec<String>> {
+        let db = self.db.clone();
+
+        blocking(move || {
+            let Some(start) = OffsetEncoding::Utf16.to_byte_offset(&text, start as usize) else {
+                bail!("Offset {start} is not a character boundary");
+            };
+
+            let words = db.analyze(&text, start)?;
+            Ok(words.into_values().map(str::to_owned).collect())
+        })
+        .await
+    }
+}
+
+/// The results of a search.
+#[derive(Serialize)]
+struct Search<'a> {
+    phrases: Vec<&'a jmdict::Entry<'a>>,
+    names: Vec<&'a jmnedict::Entry<'a>>,
+    characters: &'a [kanjidic2::Character<'a>],
+}
+
+fn search(db: &Database, q: &str) -> Result<serde_json::Value> {
+    let search = db.search(q)?;
+
+    let search = Search {
+        phrases: search.phrases.iter().map(|(_, entry)| entry).collect(),
+        names: search.names.iter().map(|(_, entry)| entry).collect(),
+        characters: &search.characters,
+    };
+
+    Ok(serde_json::to_value(&search)?)
+}
+
+/// Run the given closure on a blocking thread, converting errors into
+/// JavaScript errors.
+async fn blocking<T, F>(f: F) -> napi::Result<T>
+where
+    T: 'static + Send,
+    F: 'static + Send + FnOnce() -> Result<T>,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result.map_err(|error| napi::Error::from_reason(format!("{error:#}"))),
+        Err(error) => Err(napi::Error::from_reason(error.to_string())),
+    }
+}
@@ -1,6 +1,7 @@
 use std::ffi::c_void;
 use std::ffi::CString;
 use std::ffi::{c_char, c_int};
+use std::fs;
 use std::io;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
@@ -17,6 +18,10 @@ use crate::Result;
 
 /// Open the tesseract library.
 pub fn open(language: &str) -> Result<Tesseract> {
+    if !crate::is_language_name(language) {
+        return Err(Error::new(IllegalLanguage(language.into())));
+    }
+
     let key = match winctx::OpenRegistryKey::local_machine().open("Software\\Tesseract-OCR") {
         Ok(key) => key,
         Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::new(NotInstalled)),
@@ -47,6 +52,7 @@ pub fn open(language: &str) -> Result<Tesseract> {
         return Err(Error::new(MissingLanguage(expected_data.into())));
     }
 
+    let languages = languages(&tessdata);
     let tessdata = tessdata.into_os_string();
     let tessdata = tessdata.to_string_lossy();
 
@@ -95,12 +101,38 @@ pub fn open(language: &str) -> Result<Tesseract> {
 
         Ok(Tesseract {
             path: path.into(),
+            version: version.as_ref().into(),
+            languages,
             inner: inner.clone(),
             base,
         })
     }
 }
 
+/// List the languages which have data installed in the given directory.
+fn languages(tessdata: &Path) -> Vec<String> {
+    let Ok(dir) = fs::read_dir(tessdata) else {
+        return Vec::new();
+    };
+
+    let mut languages = Vec::new();
+
+    for e in dir.flatten() {
+        let path = e.path();
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some("traineddata") {
+            continue;
+        }
+
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            languages.push(stem.to_owned());
+        }
+    }
+
+    languages.sort();
+    languages
+}
+
 struct Inner {
     tess_base_api_create: Symbol<unsafe extern "C" fn() -> *mut BaseApiPtr>,
     tess_base_api_init3:
@@ -150,6 +182,10 @@ struct BaseApiPtr(c_void);
 /// A base API instance, associated with a specific language.
 pub struct Tesseract {
     path: Box<Path>,
+    /// The version of tesseract, as it's recorded in the registry.
+    version: Box<str>,
+    /// Languages which have data installed.
+    languages: Vec<String>,
     inner: Arc<Inner>,
     base: *mut BaseApiPtr,
 }
@@ -160,6 +196,16 @@ impl Tesseract {
         Some(&self.path)
     }
 
+    /// The version of the tesseract library.
+    pub fn version(&self) -> Option<String> {
+        Some(self.version.to_string())
+    }
+
+    /// The languages which have data installed.
+    pub fn languages(&self) -> Vec<String> {
+        self.languages.clone()
+    }
+
     /// Convert image data to text.
     pub fn image_to_text(
         &mut self,
@@ -44,6 +44,9 @@ pub(super) enum ErrorKind {
         #[source]
         TryFromIntError,
     ),
+    #[error("Illegal language name `{0}`")]
+    #[cfg(any(windows, feature = "linked"))]
+    IllegalLanguage(Box<str>),
     #[error("Failed to initialize")]
     #[cfg(any(windows, feature = "linked"))]
     Initialize,
@@ -29,6 +29,16 @@ impl Tesseract {
         None
     }
 
+    /// The version of the tesseract library.
+    pub fn version(&self) -> Option<String> {
+        None
+    }
+
+    /// The languages which have data installed.
+    pub fn languages(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// Perform OCR recognition on a frame of image data.
     pub fn image_to_text(
         &self,
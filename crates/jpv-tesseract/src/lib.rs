@@ -15,3 +15,25 @@ mod error;
 #[cfg_attr(windows, path = "dll.rs")]
 mod r#impl;
 pub use self::r#impl::{open, Tesseract, TesseractString};
+
+/// Test if the given name of a language could name installed language data,
+/// like `jpn` or `jpn_vert`.
+///
+/// Names are used to build the path of the language data, so anything which
+/// could escape the data directory is rejected.
+pub fn is_language_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-'))
+}
+
+#[test]
+fn test_is_language_name() {
+    assert!(is_language_name("jpn"));
+    assert!(is_language_name("jpn_vert"));
+    assert!(!is_language_name(""));
+    assert!(!is_language_name("../jpn"));
+    assert!(!is_language_name("C:\\jpn"));
+    assert!(!is_language_name("jpn/../../eng"));
+}
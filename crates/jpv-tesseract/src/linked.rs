@@ -1,5 +1,5 @@
-use std::ffi::CString;
 use std::ffi::{c_char, c_int};
+use std::ffi::{CStr, CString};
 use std::ops::Deref;
 use std::path::Path;
 use std::ptr;
@@ -7,8 +7,9 @@ use std::slice;
 use std::str;
 
 use tesseract_sys::{
-    TessBaseAPICreate, TessBaseAPIDelete, TessBaseAPIGetUTF8Text, TessBaseAPIInit3,
-    TessBaseAPISetImage, TessDeleteText,
+    TessBaseAPICreate, TessBaseAPIDelete, TessBaseAPIGetAvailableLanguagesAsVector,
+    TessBaseAPIGetUTF8Text, TessBaseAPIInit3, TessBaseAPISetImage, TessDeleteText,
+    TessDeleteTextArray, TessVersion,
 };
 
 use crate::error::{Error, ErrorKind};
@@ -16,6 +17,10 @@ use crate::Result;
 
 /// Try and open the tesseract API.
 pub fn open(language: &str) -> Result<Tesseract> {
+    if !crate::is_language_name(language) {
+        return Err(Error::new(ErrorKind::IllegalLanguage(language.into())));
+    }
+
     let language = CString::new(language)?;
 
     unsafe {
@@ -67,6 +72,43 @@ impl Tesseract {
         None
     }
 
+    /// The version of the tesseract library.
+    pub fn version(&self) -> Option<String> {
+        unsafe {
+            let version = TessVersion();
+
+            if version.is_null() {
+                return None;
+            }
+
+            Some(CStr::from_ptr(version).to_string_lossy().into_owned())
+        }
+    }
+
+    /// The languages which have data installed.
+    pub fn languages(&self) -> Vec<String> {
+        let mut languages = Vec::new();
+
+        unsafe {
+            let array = TessBaseAPIGetAvailableLanguagesAsVector(self.base);
+
+            if array.is_null() {
+                return languages;
+            }
+
+            let mut cur = array;
+
+            while !(*cur).is_null() {
+                languages.push(CStr::from_ptr(*cur).to_string_lossy().into_owned());
+                cur = cur.add(1);
+            }
+
+            TessDeleteTextArray(array);
+        }
+
+        languages
+    }
+
     /// Perform OCR recognition on a frame of image data.
     pub fn image_to_text(
         &mut self,
@@ -3,6 +3,7 @@ use std::env;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const URL: &str = "https://github.com/udoprog/jpv";
 
@@ -65,7 +66,15 @@ fn main() -> Result<()> {
         user_agent = format!("jpv/0 (git {rev}; +{URL})");
     }
 
+    // Honour SOURCE_DATE_EPOCH so that builds can be reproduced.
+    let built = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.parse::<u64>().context("SOURCE_DATE_EPOCH")?,
+        Err(_) => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
     fs::write(out_dir.join("version.txt"), &version).context("writing version.txt")?;
     fs::write(out_dir.join("user_agent.txt"), user_agent).context("writing user_agent.txt")?;
+    fs::write(out_dir.join("rev.txt"), rev).context("writing rev.txt")?;
+    fs::write(out_dir.join("built.txt"), built.to_string()).context("writing built.txt")?;
     Ok(())
 }
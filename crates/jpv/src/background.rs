@@ -1,50 +1,196 @@
-use std::collections::HashMap;
-use std::io::Read;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{anyhow, bail, Context, Result};
 use flate2::read::GzDecoder;
-use lib::config::{Config, IndexFormat};
-use lib::database::{self, Database, Input};
-use lib::reporter::Reporter;
+use lib::config::{ClientSettings, Config, IndexFormat};
+use lib::database::{self, Database, Id, Input, Key};
+use lib::history::{self, Digested, History, Period};
+use lib::phrasebook::Phrasebook;
+use lib::reporter::{Reporter, Tracker};
 use lib::token::Token;
-use lib::{api, data, Dirs};
-use tempfile::NamedTempFile;
+use lib::{api, storage, yomichan, Dirs, DATABASE_VERSION};
 use tokio::fs;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::{oneshot, Mutex, RwLock};
 
-use crate::reporter::EventsReporter;
+use crate::checkpoint::Checkpoint;
+use crate::reporter::{ConsoleReporter, EventsReporter};
 use crate::system::{self, SystemEvents};
 use crate::tasks::{CompletedTask, TaskCompletion, TaskName, Tasks};
-use crate::Args;
+use crate::webhooks;
+use crate::{Args, Indexes};
 
 #[derive(Default)]
 pub(crate) struct BackgroundTasks {
     pub(crate) progress: HashMap<TaskName, system::TaskProgress>,
 }
 
+/// The number of searches whose results are kept for refinement.
+const SEARCH_CACHE_SIZE: usize = 32;
+
+/// The number of popup responses which are kept.
+const POPUP_CACHE_SIZE: usize = 256;
+
+/// The number of lists of typing practice candidates which are kept.
+const TYPING_CACHE_SIZE: usize = 16;
+
+/// The number of clients whose last query is remembered.
+const LAST_QUERY_CLIENTS: usize = 64;
+
+/// The number of clients whose user interface settings are stored.
+pub(crate) const CLIENT_SETTINGS_CLIENTS: usize = 64;
+
+/// How long recorded lookups are held in memory before the history is saved
+/// to disk, so that a burst of lookups results in a single write.
+const HISTORY_SAVE_DELAY: Duration = Duration::from_secs(5);
+
+/// The number of steps reported while building an index.
+pub(crate) const BUILD_STEPS: usize = 6;
+
+/// The percentage of building an index which is spent reading or downloading
+/// its input.
+const READ_WEIGHT: u32 = 10;
+
+/// The percentage of building an index which is spent writing it to disk.
+const WRITE_WEIGHT: u32 = 100 - READ_WEIGHT - database::BUILD_WEIGHT;
+
+/// The bytes a gzip compressed file starts with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Responses to recent queries, like the keys of the results of searches
+/// which are used to refine them.
+///
+/// Responses are only valid for the generation of the database they were
+/// produced by. Responses for any other generation are ignored, since queries
+/// which were running against an older database might finish after it has
+/// been re-opened.
+struct QueryCache<T> {
+    generation: u64,
+    entries: VecDeque<(String, T)>,
+    capacity: usize,
+}
+
+impl<T> QueryCache<T>
+where
+    T: Clone,
+{
+    fn new(generation: u64, capacity: usize) -> Self {
+        Self {
+            generation,
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&self, generation: u64, q: &str) -> Option<T> {
+        if generation != self.generation {
+            return None;
+        }
+
+        let (_, value) = self.entries.iter().find(|(query, _)| query == q)?;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, generation: u64, q: String, value: T) {
+        if generation != self.generation {
+            return;
+        }
+
+        self.entries.retain(|(query, _)| *query != q);
+
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back((q, value));
+    }
+
+    /// Clear the cache and only accept responses for the given generation.
+    fn reset(&mut self, generation: u64) {
+        self.generation = generation;
+        self.entries.clear();
+    }
+}
+
 pub(crate) struct Mutable {
     config: Config,
     database: Database,
+    /// The generation of the database, which changes every time it is
+    /// re-opened. It starts out random, so that responses which clients
+    /// cached before a restart aren't mistaken for current ones.
+    generation: u64,
+    searches: StdMutex<QueryCache<Arc<HashSet<Key>>>>,
+    popups: StdMutex<QueryCache<Arc<api::OwnedPopupResponse>>>,
+    typing: StdMutex<QueryCache<Arc<Vec<Id>>>>,
 }
 
 impl Mutable {
     /// Re-open the underlying database.
-    pub(crate) fn reopen_database(&mut self, indexes: &[PathBuf], dirs: &Dirs) -> Result<()> {
-        let indexes = data::open_from_args(indexes, dirs).context("Opening database files")?;
-        let db =
-            lib::database::Database::open(indexes, &self.config).context("Opening the database")?;
+    pub(crate) fn reopen_database(&mut self, indexes: &Indexes, dirs: &Dirs) -> Result<()> {
+        let db = indexes
+            .open(dirs, &self.config)
+            .context("Opening the database")?;
         self.database = db;
+        self.generation = self.generation.wrapping_add(1);
+        // Keys are only valid for the database they were produced by.
+        self.searches.get_mut().unwrap().reset(self.generation);
+        self.popups.get_mut().unwrap().reset(self.generation);
+        self.typing.get_mut().unwrap().reset(self.generation);
+
+        if let Err(error) = self.resolve_user_data(dirs) {
+            tracing::error!("Failed to resolve user data: {error:#}");
+        }
+
+        Ok(())
+    }
+
+    /// Re-resolve stored references in user data against the current
+    /// database, which is done every time it is opened.
+    ///
+    /// Pinned entries which can no longer be found are reported, but kept in
+    /// case they're provided by an index which hasn't been rebuilt yet. The
+    /// database version user data was last resolved against is saved in the
+    /// configuration, which is only written when it changes.
+    fn resolve_user_data(&mut self, dirs: &Dirs) -> Result<()> {
+        if self.database.is_empty() {
+            return Ok(());
+        }
+
+        for (pattern, sequence) in self.database.unresolved_pins()? {
+            tracing::warn!("Entry {sequence} pinned for `{pattern}` no longer exists");
+        }
+
+        if self.config.database_version != Some(DATABASE_VERSION) {
+            tracing::info!("Resolved user data against database version {DATABASE_VERSION}");
+            self.config.database_version = Some(DATABASE_VERSION);
+
+            if let Err(error) = save_config(dirs, &self.config) {
+                tracing::warn!("Failed to save configuration: {error:#}");
+            }
+        }
+
         Ok(())
     }
 }
 
+/// Write the configuration to disk.
+fn save_config(dirs: &Dirs, config: &Config) -> Result<()> {
+    let path = dirs.config_path();
+    let config = lib::toml::to_string_pretty(config)?;
+    storage::save(&path, config.as_bytes(), |data| {
+        Ok(lib::toml::from_str::<Config>(data)?)
+    })?;
+    tracing::info!("Wrote new configuration to {}", path.display());
+    Ok(())
+}
+
 /// Configuration to install.
 #[derive(Default)]
 pub struct Install {
@@ -65,7 +211,23 @@ pub enum BackgroundEvent {
 struct Shared {
     dirs: Dirs,
     tesseract: Option<Mutex<tesseract::Tesseract>>,
+    /// The version of tesseract, which is read up front so that it can be
+    /// reported without waiting for recognition in progress.
+    tesseract_version: Option<String>,
+    /// Tesseract handles for other languages, which are opened on first use.
+    languages: StdMutex<HashMap<String, Arc<Mutex<tesseract::Tesseract>>>>,
     ocr: AtomicBool,
+    /// Settings of clients, which are loaded on first use.
+    clients: Mutex<Option<BTreeMap<String, ClientSettings>>>,
+    /// History of looked up words, which is loaded on first use.
+    history: Mutex<Option<History>>,
+    /// Whether the history has changes which are waiting to be saved.
+    history_pending: AtomicBool,
+    /// The phrasebook, which is loaded on first use.
+    phrasebook: Mutex<Option<Phrasebook>>,
+    /// The last query searched for by each client, which is forgotten when
+    /// the service restarts.
+    last_queries: StdMutex<VecDeque<(String, String)>>,
 }
 
 #[derive(Clone)]
@@ -79,26 +241,63 @@ pub struct Background {
 }
 
 impl Background {
+    /// Construct the background service.
+    ///
+    /// User data is migrated and resolved against the database here, before
+    /// anything else has access to the configuration.
     pub(crate) fn new(
         dirs: Dirs,
         channel: UnboundedSender<BackgroundEvent>,
-        config: Config,
+        mut config: Config,
         database: Database,
         system_events: SystemEvents,
         tesseract: Option<tesseract::Tesseract>,
         log: crate::log::Capture,
     ) -> Result<Self> {
+        let tesseract_version = tesseract.as_ref().and_then(|t| t.version());
         let tesseract = tesseract.map(Mutex::new);
 
+        // A configuration which can't be saved, like one in a read-only
+        // directory, is still used as migrated and saved on the next change.
+        if config.migrate() {
+            tracing::info!("Migrating user data to version {}", config.version);
+
+            if let Err(error) = save_config(&dirs, &config) {
+                tracing::warn!("Failed to save migrated configuration: {error:#}");
+            }
+        }
+
+        let generation = rand::random();
+
+        let mut mutable = Mutable {
+            config,
+            database,
+            generation,
+            searches: StdMutex::new(QueryCache::new(generation, SEARCH_CACHE_SIZE)),
+            popups: StdMutex::new(QueryCache::new(generation, POPUP_CACHE_SIZE)),
+            typing: StdMutex::new(QueryCache::new(generation, TYPING_CACHE_SIZE)),
+        };
+
+        if let Err(error) = mutable.resolve_user_data(&dirs) {
+            tracing::error!("Failed to resolve user data: {error:#}");
+        }
+
         Ok(Self {
             shared: Arc::new(Shared {
                 dirs,
                 tesseract,
-                ocr: AtomicBool::new(config.ocr),
+                tesseract_version,
+                languages: StdMutex::new(HashMap::new()),
+                ocr: AtomicBool::new(mutable.config.ocr),
+                clients: Mutex::new(None),
+                history: Mutex::new(None),
+                history_pending: AtomicBool::new(false),
+                phrasebook: Mutex::new(None),
+                last_queries: StdMutex::new(VecDeque::new()),
             }),
             channel,
             system_events,
-            mutable: Arc::new(RwLock::new(Mutable { config, database })),
+            mutable: Arc::new(RwLock::new(mutable)),
             tasks: Arc::new(StdMutex::new(BackgroundTasks::default())),
             log,
         })
@@ -113,6 +312,28 @@ impl Background {
         self.shared.tesseract.as_ref()
     }
 
+    /// Get the version of tesseract, if it's in use.
+    pub(crate) fn tesseract_version(&self) -> Option<&str> {
+        self.tesseract()?;
+        self.shared.tesseract_version.as_deref()
+    }
+
+    /// Get a tesseract API handle for another language than the one used for
+    /// captured images, which is kept around once it's been opened.
+    ///
+    /// This blocks while the language is being loaded.
+    pub(crate) fn tesseract_for(&self, language: &str) -> Result<Arc<Mutex<tesseract::Tesseract>>> {
+        let mut languages = self.shared.languages.lock().unwrap();
+
+        if let Some(tesseract) = languages.get(language) {
+            return Ok(tesseract.clone());
+        }
+
+        let tesseract = Arc::new(Mutex::new(tesseract::open(language)?));
+        languages.insert(language.to_owned(), tesseract.clone());
+        Ok(tesseract)
+    }
+
     /// Get the current log backfill.
     pub(crate) fn log(&self) -> Vec<api::OwnedLogEntry> {
         self.log.read()
@@ -123,7 +344,7 @@ impl Background {
         let (sender, receiver) = oneshot::channel();
 
         // Complement missing index formats.
-        for format in IndexFormat::all() {
+        for format in IndexFormat::builtin() {
             if !config.indexes.contains_key(format.id()) {
                 config
                     .indexes
@@ -140,11 +361,130 @@ impl Background {
         }
 
         self.shared.ocr.store(config.ocr, Ordering::SeqCst);
-        self.mutable.write().await.config = config.clone();
+
+        {
+            let mut mutable = self.mutable.write().await;
+
+            if mutable.config.log_filter != config.log_filter {
+                if let Err(error) = self.log.set_config_filter(config.log_filter.as_deref()) {
+                    tracing::warn!(?error, "Failed to change log filter");
+                }
+            }
+
+            mutable.config = config.clone();
+        }
+
         self.system_events.send(system::Event::Refresh);
         Some(config)
     }
 
+    /// Get the directives of the filter of what is logged.
+    pub(crate) fn log_filter(&self) -> Result<String> {
+        self.log.filter()
+    }
+
+    /// Filter what is logged with the given directives, or with the filter the
+    /// service was started with if `None`, optionally saving them in the
+    /// configuration.
+    ///
+    /// Returns the directives of the filter now in use.
+    pub(crate) async fn set_log_filter(
+        &self,
+        directives: Option<String>,
+        persist: bool,
+    ) -> Result<String> {
+        self.log.set_filter(directives.as_deref())?;
+
+        if persist {
+            let mut config = self.config().await;
+            config.log_filter = directives;
+
+            if self.update_config(config).await.is_none() {
+                bail!("Failed to update configuration");
+            }
+        }
+
+        self.log.filter()
+    }
+
+    /// Enable or disable the index with the given name, saving the
+    /// configuration and swapping the database in one operation.
+    ///
+    /// Returns `None` if there is no such index.
+    pub(crate) async fn set_index_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+    ) -> Result<Option<api::OwnedIndexesChanged>> {
+        let mut config = self.config().await;
+
+        let Some(index) = config.indexes.get_mut(name) else {
+            return Ok(None);
+        };
+
+        index.enabled = enabled;
+
+        let Some(config) = self.update_config(config).await else {
+            bail!("Failed to update configuration");
+        };
+
+        let mut installed = self
+            .database()
+            .await
+            .installed()?
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        installed.sort();
+
+        let enabled = installed
+            .iter()
+            .filter(|name| config.is_enabled(name))
+            .cloned()
+            .collect();
+
+        let changed = api::OwnedIndexesChanged { installed, enabled };
+
+        self.system_events
+            .send(system::Event::IndexesChanged(changed.clone()));
+
+        Ok(Some(changed))
+    }
+
+    /// Record that a search was performed, remembering it as the last query
+    /// of the client which performed it and telling webhooks about it. The
+    /// search is only summarized if any webhook is configured.
+    pub(crate) async fn searched<F>(&self, client: Option<&str>, query: &str, summarize: F)
+    where
+        F: FnOnce() -> webhooks::Search,
+    {
+        if let Some(client) = client.filter(|_| !query.trim().is_empty()) {
+            let mut last_queries = self.shared.last_queries.lock().unwrap();
+            last_queries.retain(|(c, _)| c != client);
+
+            if last_queries.len() >= LAST_QUERY_CLIENTS {
+                last_queries.pop_front();
+            }
+
+            last_queries.push_back((client.to_owned(), query.to_owned()));
+        }
+
+        if self.mutable.read().await.config.webhooks.is_empty() {
+            return;
+        }
+
+        self.system_events
+            .send(system::Event::Searched(summarize()));
+    }
+
+    /// The last query searched for by the given client since the service
+    /// started.
+    pub(crate) fn last_query(&self, client: &str) -> Option<String> {
+        let last_queries = self.shared.last_queries.lock().unwrap();
+        let (_, query) = last_queries.iter().find(|(c, _)| c == client)?;
+        Some(query.clone())
+    }
+
     /// Trigger a custom installation.
     pub(crate) fn install(&self, install_all: Install) {
         let _ = self.channel.send(BackgroundEvent::Install(install_all));
@@ -155,11 +495,366 @@ impl Background {
         self.mutable.read().await.config.clone()
     }
 
+    /// Access current configuration as it's shown to clients, which for a
+    /// read-only service doesn't include local paths.
+    pub(crate) async fn public_config(&self) -> Config {
+        let mut config = self.config().await;
+
+        if config.read_only {
+            config.redact();
+        }
+
+        config
+    }
+
+    /// Test if the service refuses requests which modify it.
+    pub(crate) async fn is_read_only(&self) -> bool {
+        self.mutable.read().await.config.read_only
+    }
+
+    /// The number of characters of captured text which are analyzed at once.
+    pub(crate) async fn clipboard_limit(&self) -> usize {
+        self.mutable.read().await.config.clipboard_limit as usize
+    }
+
+    /// Access project directories.
+    pub(crate) fn dirs(&self) -> &Dirs {
+        &self.shared.dirs
+    }
+
+    /// Get the user interface settings of the client with the given
+    /// identifier.
+    pub(crate) async fn client_settings(&self, id: &str) -> Result<ClientSettings> {
+        let mut clients = self.shared.clients.lock().await;
+
+        let clients = match &mut *clients {
+            Some(clients) => clients,
+            clients => clients.insert(ClientSettings::load_all(&self.shared.dirs)?),
+        };
+
+        Ok(clients.get(id).cloned().unwrap_or_default())
+    }
+
+    /// Update the user interface settings of the client with the given
+    /// identifier and save the settings of every client to disk.
+    ///
+    /// Settings which are the same as the defaults aren't stored, and only
+    /// the most recently updated clients are kept.
+    pub(crate) async fn update_client_settings(
+        &self,
+        id: &str,
+        settings: ClientSettings,
+    ) -> Result<()> {
+        let mut clients = self.shared.clients.lock().await;
+
+        let clients = match &mut *clients {
+            Some(clients) => clients,
+            clients => clients.insert(ClientSettings::load_all(&self.shared.dirs)?),
+        };
+
+        if settings.is_default() {
+            clients.remove(id);
+        } else {
+            clients.insert(
+                id.to_owned(),
+                ClientSettings {
+                    updated: now(),
+                    ..settings
+                },
+            );
+        }
+
+        // Forget the clients which were updated the longest time ago.
+        while clients.len() > CLIENT_SETTINGS_CLIENTS {
+            let Some(oldest) = clients
+                .iter()
+                .filter(|(key, _)| key.as_str() != id)
+                .min_by_key(|(_, settings)| settings.updated)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+
+            clients.remove(&oldest);
+        }
+
+        let path = self.shared.dirs.client_settings_path();
+        let data = lib::toml::to_string_pretty(&*clients)?;
+
+        tokio::task::spawn_blocking(move || {
+            storage::save(&path, data.as_bytes(), |data| {
+                Ok(lib::toml::from_str::<BTreeMap<String, ClientSettings>>(
+                    data,
+                )?)
+            })
+        })
+        .await?
+        .context("Saving client settings to disk")
+    }
+
+    /// Record that the entry with the given sequence number was looked up.
+    ///
+    /// The history is saved to disk after [`HISTORY_SAVE_DELAY`], together
+    /// with any other lookups recorded in the meantime. Lookups made through a
+    /// read-only service aren't recorded.
+    pub(crate) async fn record_lookup(&self, sequence: u32) -> Result<()> {
+        if self.is_read_only().await {
+            return Ok(());
+        }
+
+        {
+            let mut history = self.shared.history.lock().await;
+
+            let history = match &mut *history {
+                Some(history) => history,
+                history => history.insert(History::load(&self.shared.dirs)?),
+            };
+
+            if !history.record(sequence, now()) {
+                return Ok(());
+            }
+        }
+
+        if !self.shared.history_pending.swap(true, Ordering::SeqCst) {
+            let bg = self.clone();
+
+            tokio::spawn(async move {
+                tokio::time::sleep(HISTORY_SAVE_DELAY).await;
+
+                if let Err(error) = bg.save_history().await {
+                    tracing::error!("Failed to save history: {error:#}");
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Save changes to the history which are waiting to be saved, like when
+    /// shutting down.
+    pub(crate) async fn save_history(&self) -> Result<()> {
+        let history = self.shared.history.lock().await;
+
+        if !self.shared.history_pending.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let Some(history) = &*history else {
+            return Ok(());
+        };
+
+        self.write_history(history).await
+    }
+
+    /// Summarize the words looked up during the given period, where
+    /// `utc_offset` is the offset of local time from UTC in seconds.
+    pub(crate) async fn digest(&self, period: Period, utc_offset: i64) -> Result<Vec<Digested>> {
+        let mut history = self.shared.history.lock().await;
+
+        let history = match &mut *history {
+            Some(history) => history,
+            history => history.insert(History::load(&self.shared.dirs)?),
+        };
+
+        let range = period.range(now(), utc_offset);
+        Ok(history::digest(history.lookups(), range))
+    }
+
+    /// Mark the digest of the given day as sent as a notification, returning
+    /// `false` if it already was.
+    pub(crate) async fn mark_digest_notified(&self, day: u64) -> Result<bool> {
+        self.with_history(|history| history.mark_notified(day))
+            .await
+    }
+
+    /// Read the phrasebook.
+    pub(crate) async fn phrasebook<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Phrasebook) -> T,
+    {
+        let mut phrasebook = self.shared.phrasebook.lock().await;
+
+        let phrasebook = match &mut *phrasebook {
+            Some(phrasebook) => phrasebook,
+            phrasebook => phrasebook.insert(Phrasebook::load(&self.shared.dirs)?),
+        };
+
+        Ok(f(phrasebook))
+    }
+
+    /// Modify the phrasebook and save it to disk.
+    ///
+    /// Nothing is saved if the modification fails, so it has to fail before
+    /// changing anything.
+    pub(crate) async fn update_phrasebook<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Phrasebook) -> Result<T>,
+    {
+        let mut phrasebook = self.shared.phrasebook.lock().await;
+
+        let phrasebook = match &mut *phrasebook {
+            Some(phrasebook) => phrasebook,
+            phrasebook => phrasebook.insert(Phrasebook::load(&self.shared.dirs)?),
+        };
+
+        let output = f(phrasebook)?;
+
+        let path = self.shared.dirs.phrasebook_path();
+        let data = serde_json::to_vec(&*phrasebook)?;
+
+        tokio::task::spawn_blocking(move || {
+            storage::save(&path, &data, |data| {
+                Ok(serde_json::from_str::<Phrasebook>(data)?)
+            })
+        })
+        .await?
+        .context("Saving phrasebook to disk")?;
+
+        Ok(output)
+    }
+
+    /// Get the sequence numbers of the entries which were looked up, most
+    /// recently looked up first.
+    pub(crate) async fn looked_up(&self) -> Result<Vec<u32>> {
+        let mut history = self.shared.history.lock().await;
+
+        let history = match &mut *history {
+            Some(history) => history,
+            history => history.insert(History::load(&self.shared.dirs)?),
+        };
+
+        let mut seen = HashSet::new();
+
+        Ok(history
+            .lookups()
+            .iter()
+            .rev()
+            .map(|lookup| lookup.sequence)
+            .filter(|&sequence| seen.insert(sequence))
+            .collect())
+    }
+
+    /// Modify the history, saving it to disk if the modification returns
+    /// `true`.
+    async fn with_history<F>(&self, f: F) -> Result<bool>
+    where
+        F: FnOnce(&mut History) -> bool,
+    {
+        let mut history = self.shared.history.lock().await;
+
+        let history = match &mut *history {
+            Some(history) => history,
+            history => history.insert(History::load(&self.shared.dirs)?),
+        };
+
+        if !f(history) {
+            return Ok(false);
+        }
+
+        // Lookups waiting to be saved are saved with this change.
+        self.shared.history_pending.store(false, Ordering::SeqCst);
+        self.write_history(history).await?;
+        Ok(true)
+    }
+
+    async fn write_history(&self, history: &History) -> Result<()> {
+        let path = self.shared.dirs.history_path();
+        let data = serde_json::to_vec(history)?;
+
+        tokio::task::spawn_blocking(move || {
+            storage::save(&path, &data, |data| {
+                Ok(serde_json::from_str::<History>(data)?)
+            })
+        })
+        .await?
+        .context("Saving history to disk")
+    }
+
     /// Access the database currently in use.
     pub(crate) async fn database(&self) -> Database {
         self.mutable.read().await.database.clone()
     }
 
+    /// Get the database together with its generation, which is used to cache
+    /// responses produced by it.
+    pub(crate) async fn database_generation(&self) -> (Database, u64) {
+        let mutable = self.mutable.read().await;
+        (mutable.database.clone(), mutable.generation)
+    }
+
+    /// Get the keys of the results of a recent search against the given
+    /// generation of the database.
+    pub(crate) async fn cached_search(
+        &self,
+        generation: u64,
+        q: &str,
+    ) -> Option<Arc<HashSet<Key>>> {
+        let mutable = self.mutable.read().await;
+        let searches = mutable.searches.lock().unwrap();
+        searches.get(generation, q)
+    }
+
+    /// Store the keys of the results of a search so that it can be refined.
+    ///
+    /// The keys are ignored if the database has been re-opened since the
+    /// given generation.
+    pub(crate) async fn cache_search(&self, generation: u64, q: &str, keys: Arc<HashSet<Key>>) {
+        let mutable = self.mutable.read().await;
+        let mut searches = mutable.searches.lock().unwrap();
+        searches.insert(generation, q.to_owned(), keys);
+    }
+
+    /// Get a recent popup response produced by the given generation of the
+    /// database.
+    pub(crate) async fn cached_popup(
+        &self,
+        generation: u64,
+        q: &str,
+    ) -> Option<Arc<api::OwnedPopupResponse>> {
+        let mutable = self.mutable.read().await;
+        let popups = mutable.popups.lock().unwrap();
+        popups.get(generation, q)
+    }
+
+    /// Store a popup response so that it can be reused.
+    ///
+    /// The response is ignored if the database has been re-opened since the
+    /// given generation.
+    pub(crate) async fn cache_popup(
+        &self,
+        generation: u64,
+        q: &str,
+        response: Arc<api::OwnedPopupResponse>,
+    ) {
+        let mutable = self.mutable.read().await;
+        let mut popups = mutable.popups.lock().unwrap();
+        popups.insert(generation, q.to_owned(), response);
+    }
+
+    /// Get candidates for typing practice collected by the given generation
+    /// of the database, by the parts of speech they were collected for.
+    pub(crate) async fn cached_typing(&self, generation: u64, pos: &str) -> Option<Arc<Vec<Id>>> {
+        let mutable = self.mutable.read().await;
+        let typing = mutable.typing.lock().unwrap();
+        typing.get(generation, pos)
+    }
+
+    /// Store candidates for typing practice so that they can be reused.
+    ///
+    /// The candidates are ignored if the database has been re-opened since
+    /// the given generation.
+    pub(crate) async fn cache_typing(&self, generation: u64, pos: &str, ids: Arc<Vec<Id>>) {
+        let mutable = self.mutable.read().await;
+        let mut typing = mutable.typing.lock().unwrap();
+        typing.insert(generation, pos.to_owned(), ids);
+    }
+
+    /// Test if indexes are being read into memory after startup.
+    pub(crate) fn is_warming_up(&self) -> bool {
+        let inner = self.tasks.lock().unwrap();
+        inner.progress.contains_key(&TaskName::Warmup)
+    }
+
     /// Mark the given task as completed.
     pub(crate) fn start_task(&self, completed: &TaskCompletion, steps: usize) {
         let Some(name) = completed.name() else {
@@ -172,8 +867,7 @@ impl Background {
             name.clone(),
             system::TaskProgress {
                 name: name.to_string().into(),
-                value: 0,
-                total: None,
+                tracker: Tracker::new(),
                 text: String::new(),
                 step: 0,
                 steps,
@@ -222,24 +916,11 @@ impl Background {
 
         match event {
             BackgroundEvent::SaveConfig(config, callback) => {
-                let path = self.shared.dirs.config_path();
-
-                let config_dir = self.shared.dirs.config_dir().to_owned();
+                let shared = self.shared.clone();
                 let new_config = config.clone();
 
                 let task = async {
-                    ensure_parent_dir(&path).await?;
-
-                    tokio::task::spawn_blocking(move || {
-                        let config = lib::toml::to_string_pretty(&config)?;
-
-                        let mut tempfile = NamedTempFile::new_in(config_dir)?;
-                        std::io::copy(&mut config.as_bytes(), &mut tempfile)?;
-                        tempfile.persist(&path)?;
-                        tracing::info!("Wrote new configuration to {}", path.display());
-                        Ok::<_, anyhow::Error>(())
-                    })
-                    .await?
+                    tokio::task::spawn_blocking(move || save_config(&shared.dirs, &config)).await?
                 };
 
                 let task1 = task.await.context("Saving configuration to disk");
@@ -247,7 +928,7 @@ impl Background {
                 let mut mutable = self.mutable.write().await;
                 mutable.config = new_config;
                 let task2 = mutable
-                    .reopen_database(&args.index[..], &self.shared.dirs)
+                    .reopen_database(&args.indexes(), &self.shared.dirs)
                     .context("Re-opening database");
 
                 report!(task1, task2);
@@ -276,10 +957,10 @@ impl Background {
 
                     installing.push(to_download.name.clone());
 
-                    self.start_task(&completion, 6);
+                    self.start_task(&completion, BUILD_STEPS);
 
                     let mutable = self.mutable.clone();
-                    let index = args.index.clone();
+                    let indexes = args.indexes();
 
                     let reporter = Arc::new(EventsReporter {
                         tasks: self.tasks.clone(),
@@ -308,9 +989,29 @@ impl Background {
                             let mut mutable = mutable.write().await;
                             mutable.config.set_installing(&to_download.name, false);
                             let task2 = mutable
-                                .reopen_database(&index[..], &shared.dirs)
+                                .reopen_database(&indexes, &shared.dirs)
                                 .context("Re-opening database");
 
+                            if task2.is_ok() {
+                                remove_stale_indexes(&shared.dirs);
+                            }
+
+                            if mutable.config.notifications {
+                                let body = match (&task1, &task2) {
+                                    (Ok(..), Ok(..)) => {
+                                        format!("Finished building {}", to_download.name)
+                                    }
+                                    (Err(error), _) | (_, Err(error)) => {
+                                        format!("Failed to build {}: {error}", to_download.name)
+                                    }
+                                };
+
+                                system_events.send(system::Event::Notify(system::Notification {
+                                    summary: String::from("Japanese Dictionary"),
+                                    body,
+                                }));
+                            }
+
                             report!(task1, task2);
                             system_events.send(system::Event::Refresh);
                         }
@@ -325,7 +1026,7 @@ impl Background {
                     }
 
                     mutable
-                        .reopen_database(&args.index[..], &self.shared.dirs)
+                        .reopen_database(&args.indexes(), &self.shared.dirs)
                         .context("Re-opening database")?;
 
                     self.system_events.send(system::Event::Refresh);
@@ -337,6 +1038,113 @@ impl Background {
     }
 }
 
+/// Build the given indexes one after another, like when building from the
+/// command line.
+///
+/// Forced builds are checkpointed, so that if the build is interrupted the
+/// indexes which have already been rebuilt are kept when it's run again.
+pub(crate) async fn build_all<F>(dirs: &Dirs, to_download: &[ToDownload], force: F) -> Result<()>
+where
+    F: Fn(&ToDownload) -> bool,
+{
+    let forced = to_download
+        .iter()
+        .filter(|to_download| force(to_download))
+        .map(|to_download| to_download.name.clone())
+        .collect();
+
+    let mut checkpoint = Checkpoint::resume(dirs, forced);
+
+    for to_download in to_download {
+        let mut rebuild = force(to_download);
+
+        if rebuild && checkpoint.is_completed(&to_download.name) {
+            tracing::info!(
+                "Keeping {} which was rebuilt before the build was interrupted",
+                to_download.name
+            );
+
+            rebuild = false;
+        }
+
+        let reporter = Arc::new(ConsoleReporter::new(&to_download.name, BUILD_STEPS));
+        let (_sender, shutdown) = oneshot::channel();
+
+        let built = build(reporter, shutdown, dirs, to_download, rebuild).await?;
+
+        if built && rebuild {
+            let path = dirs.index_path(&to_download.name);
+            checkpoint.complete(dirs, &to_download.name, &path)?;
+        }
+    }
+
+    checkpoint.finish(dirs);
+    Ok(())
+}
+
+/// Remove index generations which have been replaced by newer ones.
+///
+/// Failing to remove an index is not an error, since it might still be in use
+/// by another process. It is removed after a later rebuild instead.
+pub(crate) fn remove_stale_indexes(dirs: &Dirs) {
+    remove_stale_temporary_files(dirs);
+
+    let stale = match dirs.stale_indexes() {
+        Ok(stale) => stale,
+        Err(error) => {
+            tracing::warn!("Failed to list stale indexes: {error}");
+            return;
+        }
+    };
+
+    for path in stale {
+        match std::fs::remove_file(&path) {
+            Ok(()) => tracing::info!("Removed stale index {}", path.display()),
+            Err(error) => tracing::debug!("Could not remove {}: {error}", path.display()),
+        }
+    }
+}
+
+/// Remove temporary index files left behind by builds which were
+/// interrupted. Only files which haven't been touched for a while are
+/// removed, since other builds might be writing to theirs.
+fn remove_stale_temporary_files(dirs: &Dirs) {
+    const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+    let Ok(dir) = std::fs::read_dir(dirs.data_dir()) else {
+        return;
+    };
+
+    for e in dir.flatten() {
+        let path = e.path();
+
+        let is_temporary = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".index.tmp"));
+
+        if !is_temporary {
+            continue;
+        }
+
+        let is_stale = e
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= MAX_AGE);
+
+        if !is_stale {
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => tracing::info!("Removed stale temporary file {}", path.display()),
+            Err(error) => tracing::debug!("Could not remove {}: {error}", path.display()),
+        }
+    }
+}
+
 /// Path and url to download.
 pub struct ToDownload {
     pub name: String,
@@ -344,6 +1152,9 @@ pub struct ToDownload {
     pub index_path: Box<Path>,
     pub path: Option<Box<Path>>,
     pub format: IndexFormat,
+    /// Path to the word or name frequencies used when building phrases or
+    /// names.
+    pub frequencies: Option<Box<Path>>,
 }
 
 /// Download override paths.
@@ -391,6 +1202,7 @@ pub fn config_to_download(
             index_path: dirs.index_path(id).into(),
             path,
             format: index.format,
+            frequencies: index.frequencies.as_deref().map(|p| Path::new(p).into()),
         });
     }
 
@@ -453,6 +1265,7 @@ pub(crate) async fn build(
 
     tracing::info!("Loading `{}` from {}", download.name, path.display());
 
+    let frequencies = read_frequencies(download).await?;
     let start = Instant::now();
     let kind = download.format;
     let name = download.name.clone();
@@ -461,14 +1274,9 @@ pub(crate) async fn build(
         let reporter = reporter.clone();
         let shutdown_token = shutdown_token.clone();
         move || {
-            let input = match kind {
-                IndexFormat::Jmdict => Input::Jmdict(str::from_utf8(&data[..])?),
-                IndexFormat::Kanjidic2 => Input::Kanjidic2(str::from_utf8(&data[..])?),
-                IndexFormat::Jmnedict => Input::Jmnedict(str::from_utf8(&data[..])?),
-                IndexFormat::Kradfile => Input::Kradfile(&data[..]),
-            };
-
-            database::build(&*reporter, &shutdown_token, &name, input)
+            build_input(kind, &data, frequencies.as_deref(), |input| {
+                database::build(&*reporter, &shutdown_token, &name, input)
+            })
         }
     });
 
@@ -484,25 +1292,189 @@ pub(crate) async fn build(
 
     let duration = Instant::now().duration_since(start);
 
+    let index_path = dirs.next_index_path(&download.name)?;
+
     reporter.instrument_start(
         module_path!(),
-        &format_args!("Saving to {}", download.index_path.display()),
+        &format_args!("Saving to {}", index_path.display()),
         None,
+        WRITE_WEIGHT,
     );
 
-    fs::write(&download.index_path, buf.as_slice())
-        .await
-        .with_context(|| anyhow!("{}", download.index_path.display()))?;
+    // The index is written to a temporary file which is then renamed into
+    // place as a new generation, so that the index is never observed partially
+    // written and processes with the old generation open are unaffected.
+    tokio::task::spawn_blocking({
+        let data_dir = dirs.data_dir().to_owned();
+        let prefix = format!("{}.", download.name);
+        let index_path = index_path.clone();
+
+        move || {
+            let mut tempfile = tempfile::Builder::new()
+                .prefix(&prefix)
+                .suffix(".index.tmp")
+                .tempfile_in(data_dir)?;
+
+            tempfile.write_all(buf.as_slice())?;
+            tempfile.as_file().sync_all()?;
+            tempfile.persist_noclobber(&index_path)?;
+            Ok::<_, anyhow::Error>(())
+        }
+    })
+    .await?
+    .with_context(|| anyhow!("{}", index_path.display()))?;
 
     tracing::info!(
         "Took {duration:?} to build index at {}",
-        download.index_path.display()
+        index_path.display()
     );
 
     reporter.instrument_end(0);
     Ok(true)
 }
 
+/// Build the given dictionary into shards in the given directory, returning
+/// the shards which were written, see [`database::shard`].
+pub(crate) async fn build_shards(
+    reporter: Arc<dyn Reporter>,
+    shutdown: oneshot::Receiver<()>,
+    dirs: &Dirs,
+    download: &ToDownload,
+    out: &Path,
+) -> Result<Vec<database::shard::ManifestShard>> {
+    let shutdown_token = Token::default();
+
+    let (path, data) = read_or_download(
+        &*reporter,
+        download.path.as_deref(),
+        dirs,
+        &download.url,
+        false,
+    )
+    .await
+    .context("Reading dictionary")?;
+
+    tracing::info!("Sharding `{}` from {}", download.name, path.display());
+
+    let frequencies = read_frequencies(download).await?;
+    let kind = download.format;
+    let name = download.name.clone();
+    let out = out.to_owned();
+
+    let mut task = tokio::task::spawn_blocking({
+        let shutdown_token = shutdown_token.clone();
+
+        move || {
+            build_input(kind, &data, frequencies.as_deref(), |input| {
+                let shards = if database::shard::is_sharded(kind) {
+                    database::shard::SHARDS.map(Some).to_vec()
+                } else {
+                    vec![None]
+                };
+
+                let mut output = Vec::new();
+
+                for shard in shards {
+                    let (file, buf) = match shard {
+                        Some(shard) => (
+                            format!("{name}.{shard}.index"),
+                            database::build_shard(
+                                &*reporter,
+                                &shutdown_token,
+                                &name,
+                                input,
+                                shard,
+                            )?,
+                        ),
+                        None => (
+                            format!("{name}.index"),
+                            database::build(&*reporter, &shutdown_token, &name, input)?,
+                        ),
+                    };
+
+                    let path = out.join(&file);
+                    std::fs::write(&path, buf.as_slice())
+                        .with_context(|| anyhow!("{}", path.display()))?;
+
+                    tracing::info!("Wrote {}", path.display());
+
+                    output.push(database::shard::ManifestShard {
+                        index: name.clone(),
+                        shard: shard.map(str::to_owned),
+                        path: file,
+                        size: buf.as_slice().len() as u64,
+                    });
+                }
+
+                Ok(output)
+            })
+        }
+    });
+
+    let shards = tokio::select! {
+        result = &mut task => {
+            result??
+        }
+        _ = shutdown => {
+            shutdown_token.set();
+            task.await??
+        }
+    };
+
+    Ok(shards)
+}
+
+/// Read the word or name frequencies to build the given dictionary with, if
+/// any.
+async fn read_frequencies(download: &ToDownload) -> Result<Option<String>> {
+    let (IndexFormat::Jmdict | IndexFormat::Jmnedict, Some(path)) =
+        (download.format, &download.frequencies)
+    else {
+        return Ok(None);
+    };
+
+    tracing::info!("Loading frequencies from {}", path.display());
+
+    let frequencies = fs::read_to_string(path)
+        .await
+        .with_context(|| anyhow!("Reading frequencies from {}", path.display()))?;
+
+    Ok(Some(frequencies))
+}
+
+/// Construct the input to build a dictionary of the given format from.
+fn build_input<T>(
+    kind: IndexFormat,
+    data: &[u8],
+    frequencies: Option<&str>,
+    f: impl FnOnce(Input<'_>) -> Result<T>,
+) -> Result<T> {
+    let files;
+    let banks;
+
+    let input = match kind {
+        IndexFormat::Jmdict => Input::Jmdict(str::from_utf8(data)?, frequencies),
+        IndexFormat::Kanjidic2 => Input::Kanjidic2(str::from_utf8(data)?),
+        IndexFormat::Jmnedict => Input::Jmnedict(str::from_utf8(data)?, frequencies),
+        IndexFormat::Kradfile => Input::Kradfile(data),
+        IndexFormat::Radkfile => Input::Radkfile(data),
+        IndexFormat::PitchAccent => Input::PitchAccent(str::from_utf8(data)?),
+        IndexFormat::Tatoeba => Input::Tatoeba(str::from_utf8(data)?),
+        IndexFormat::Yomichan => {
+            files = read_yomichan(data)?;
+
+            banks = files
+                .iter()
+                .map(|(name, contents)| yomichan::Bank { name, contents })
+                .collect::<Vec<_>>();
+
+            Input::Yomichan(&banks)
+        }
+    };
+
+    f(input)
+}
+
 async fn read_or_download(
     reporter: &dyn Reporter,
     path: Option<&Path>,
@@ -511,21 +1483,18 @@ async fn read_or_download(
     force: bool,
 ) -> Result<(PathBuf, Vec<u8>), anyhow::Error> {
     let (path, bytes) = match path {
-        Some(path) => (path.to_owned(), fs::read(path).await?),
+        Some(path) => (path.to_owned(), read(reporter, path).await?),
         None => {
-            let Some((_, name)) = url.rsplit_once('/') else {
+            let Some(path) = download_path(dirs, url) else {
                 bail!("Url doesn't have a trailing component: {url}")
             };
 
-            let hash = crate::hash::hash(url);
-            let path = dirs.cache_dir(format!("{hash:08x}-{name}"));
-
             let bytes = if !path.is_file() || force {
                 download(reporter, url, &path)
                     .await
                     .with_context(|| anyhow!("Downloading {url} to {}", path.display()))?
             } else {
-                fs::read(&path).await?
+                read(reporter, &path).await?
             };
 
             (path, bytes)
@@ -534,6 +1503,12 @@ async fn read_or_download(
 
     reporter.instrument_end(bytes.len());
 
+    // Inputs which aren't compressed with gzip, like Yomichan archives which
+    // are unpacked when built or plain lists of pitch accents, are used as-is.
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok((path, bytes));
+    }
+
     let mut input = GzDecoder::new(&bytes[..]);
     let mut bytes = Vec::new();
     input
@@ -542,6 +1517,43 @@ async fn read_or_download(
     Ok((path, bytes))
 }
 
+/// Read the banks of a Yomichan dictionary from a zip archive.
+fn read_yomichan(data: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+
+    for entry in crate::zip::entries(data)? {
+        if yomichan::BankKind::of(entry.name()).is_none() {
+            continue;
+        }
+
+        let contents = String::from_utf8(entry.read()?)
+            .with_context(|| anyhow!("{}: Bank is not UTF-8", entry.name()))?;
+
+        files.push((entry.name().to_owned(), contents));
+    }
+
+    Ok(files)
+}
+
+/// Read the input of an index from the given path.
+async fn read(reporter: &dyn Reporter, path: &Path) -> Result<Vec<u8>> {
+    reporter.instrument_start(
+        module_path!(),
+        &format_args!("Reading {}", path.display()),
+        None,
+        READ_WEIGHT,
+    );
+
+    Ok(fs::read(path).await?)
+}
+
+/// The path in the cache directory where the given url is downloaded to.
+pub(crate) fn download_path(dirs: &Dirs, url: &str) -> Option<PathBuf> {
+    let (_, name) = url.rsplit_once('/')?;
+    let hash = crate::hash::hash(url);
+    Some(dirs.cache_dir(format!("{hash:08x}-{name}")))
+}
+
 #[cfg(not(feature = "reqwest"))]
 async fn download(_: &dyn Reporter, _: &str, _: &Path) -> Result<Vec<u8>> {
     bail!("Downloading is not supported")
@@ -549,30 +1561,95 @@ async fn download(_: &dyn Reporter, _: &str, _: &Path) -> Result<Vec<u8>> {
 
 #[cfg(feature = "reqwest")]
 async fn download(reporter: &dyn Reporter, url: &str, path: &Path) -> Result<Vec<u8>> {
-    use reqwest::Method;
+    use reqwest::header::{IF_RANGE, RANGE};
+    use reqwest::{Method, StatusCode};
     use tokio::io::AsyncWriteExt;
 
     tracing::info!("Downloading {url} to {}", path.display());
 
     ensure_parent_dir(path).await?;
 
+    // Downloads are written to a partial file which is renamed into place
+    // once complete, so that an interrupted download is resumed rather than
+    // mistaken for a complete one.
+    let part = sibling_path(path, ".part");
+    let validator_path = sibling_path(path, ".part.validator");
+
+    let mut data = match fs::read(&part).await {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => return Err(error.into()),
+    };
+
+    let validator = match fs::read_to_string(&validator_path).await {
+        Ok(validator) => Some(validator),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error.into()),
+    };
+
     let client = reqwest::ClientBuilder::new().build()?;
 
-    let request = client
-        .request(Method::GET, url)
-        .header("User-Agent", crate::USER_AGENT)
-        .build()?;
+    let request = |resume: Option<(usize, &str)>| {
+        let mut request = client
+            .request(Method::GET, url)
+            .header("User-Agent", crate::USER_AGENT);
+
+        // With `If-Range` the remainder is only sent if the remote file is the
+        // one the partial file was downloaded from, otherwise all of it is.
+        if let Some((offset, validator)) = resume {
+            request = request
+                .header(RANGE, format!("bytes={offset}-"))
+                .header(IF_RANGE, validator);
+        }
+
+        request.build()
+    };
+
+    // A partial file can only be resumed if it's known which version of the
+    // remote file it's from.
+    let mut resume = match &validator {
+        Some(validator) if !data.is_empty() => Some((data.len(), validator.as_str())),
+        _ => None,
+    };
+
+    let mut response = client.execute(request(resume)?).await?;
 
-    let mut response = client.execute(request).await?;
+    // The partial file doesn't match what's being served, so start over.
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        resume = None;
+        response = client.execute(request(resume)?).await?;
+    }
 
-    let total = response
-        .content_length()
-        .map(|n| usize::try_from(n).unwrap_or(usize::MAX));
+    let mut response = response.error_for_status()?;
 
-    let mut f = fs::File::create(path).await?;
-    let mut data = Vec::new();
+    let mut f = if resume.is_some() && response.status() == StatusCode::PARTIAL_CONTENT {
+        tracing::info!("Resuming download of {url} at {} bytes", data.len());
+        fs::OpenOptions::new().append(true).open(&part).await?
+    } else {
+        data.clear();
 
-    reporter.instrument_start(module_path!(), &format!("Downloading {url}"), total);
+        match download_validator(&response) {
+            Some(validator) => fs::write(&validator_path, validator).await?,
+            None => remove_if_exists(&validator_path).await?,
+        }
+
+        fs::File::create(&part).await?
+    };
+
+    let total = response.content_length().map(|n| {
+        usize::try_from(n)
+            .unwrap_or(usize::MAX)
+            .saturating_add(data.len())
+    });
+
+    reporter.instrument_start(
+        module_path!(),
+        &format!("Downloading {url}"),
+        total,
+        READ_WEIGHT,
+    );
+
+    reporter.instrument_progress(data.len());
 
     while let Some(chunk) = response.chunk().await? {
         f.write_all(chunk.as_ref()).await?;
@@ -580,9 +1657,115 @@ async fn download(reporter: &dyn Reporter, url: &str, path: &Path) -> Result<Vec
         reporter.instrument_progress(chunk.as_ref().len());
     }
 
+    f.sync_all().await?;
+    drop(f);
+    fs::rename(&part, path).await?;
+    remove_if_exists(&validator_path).await?;
     Ok(data)
 }
 
+/// The validator which identifies the version of a downloaded file, which is
+/// its entity tag unless it's weak, or else when it was last modified.
+///
+/// Weak entity tags can't be used with `If-Range`.
+#[cfg(feature = "reqwest")]
+fn download_validator(response: &reqwest::Response) -> Option<&str> {
+    use reqwest::header::{ETAG, LAST_MODIFIED};
+
+    let headers = response.headers();
+
+    let etag = headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"));
+
+    etag.or_else(|| headers.get(LAST_MODIFIED)?.to_str().ok())
+}
+
+/// A path next to the given path with the given suffix, like the one an
+/// interrupted download of it is kept at.
+#[cfg(feature = "reqwest")]
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(feature = "reqwest")]
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Information about the latest version of a remote dictionary.
+#[derive(Default)]
+pub(crate) struct RemoteVersion {
+    /// The `Last-Modified` header of the remote file.
+    pub(crate) last_modified: Option<String>,
+    /// The size of the remote file in bytes.
+    pub(crate) size: Option<u64>,
+}
+
+#[cfg(not(feature = "reqwest"))]
+pub(crate) async fn remote_version(_: &str) -> Result<RemoteVersion> {
+    bail!("Downloading is not supported")
+}
+
+/// Query the latest version of the given url with a `HEAD` request.
+#[cfg(feature = "reqwest")]
+pub(crate) async fn remote_version(url: &str) -> Result<RemoteVersion> {
+    use reqwest::header::LAST_MODIFIED;
+    use reqwest::Method;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let request = client
+        .request(Method::HEAD, url)
+        .header("User-Agent", crate::USER_AGENT)
+        .build()?;
+
+    let response = client.execute(request).await?.error_for_status()?;
+
+    let last_modified = response
+        .headers()
+        .get(LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    Ok(RemoteVersion {
+        last_modified,
+        size: response.content_length(),
+    })
+}
+
+/// Read the version or creation date declared in the header of a downloaded
+/// dictionary, like `JMdict created: 2023-11-01`.
+pub(crate) fn declared_version(path: &Path) -> Result<Option<String>> {
+    /// The number of decompressed bytes to look for a declaration in.
+    const HEADER: u64 = 1 << 16;
+
+    let f = std::fs::File::open(path)?;
+    let mut header = Vec::new();
+    GzDecoder::new(f).take(HEADER).read_to_end(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+
+    if let Some((_, rest)) = header.split_once("created: ") {
+        let end = rest.find([' ', '\n']).unwrap_or(rest.len());
+        return Ok(Some(rest[..end].to_owned()));
+    }
+
+    if let Some((_, rest)) = header.split_once("<date_of_creation>") {
+        if let Some((date, _)) = rest.split_once("</date_of_creation>") {
+            return Ok(Some(date.to_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
 async fn ensure_parent_dir(path: &Path) -> Result<&Path> {
     let Some(parent) = path.parent() else {
         bail!("Missing parent directory for {}", path.display());
@@ -600,3 +1783,99 @@ async fn ensure_parent_dir(path: &Path) -> Result<&Path> {
 
     Ok(parent)
 }
+
+/// The current time in seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "reqwest")]
+#[tokio::test]
+async fn test_resume_download() -> Result<()> {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    use lib::reporter::EmptyReporter;
+
+    /// Serve the given number of requests for a file with the entity tag
+    /// `"v1"`, returning the requests as lowercase header lines.
+    fn serve(n: usize) -> (String, std::thread::JoinHandle<Vec<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/file.gz", listener.local_addr().expect("address"));
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for _ in 0..n {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                let mut headers = Vec::new();
+                let mut line = String::new();
+
+                while reader.read_line(&mut line).expect("header") > 2 {
+                    headers.push(line.trim_end().to_lowercase());
+                    line.clear();
+                }
+
+                let resume = headers.iter().any(|h| h == "if-range: \"v1\"")
+                    && headers.iter().any(|h| h == "range: bytes=6-");
+
+                let (status, body) = if resume {
+                    ("206 Partial Content", "world")
+                } else {
+                    ("200 OK", "hello world")
+                };
+
+                write!(
+                    reader.into_inner(),
+                    "HTTP/1.1 {status}\r\netag: \"v1\"\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .expect("response");
+
+                requests.push(headers);
+            }
+
+            requests
+        });
+
+        (url, handle)
+    }
+
+    let root = std::env::temp_dir().join(format!("jpv-test-download-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+
+    let path = root.join("file.gz");
+    let part = sibling_path(&path, ".part");
+    let validator = sibling_path(&path, ".part.validator");
+
+    // The remainder is appended to a partial file of the same version.
+    std::fs::write(&part, "hello ")?;
+    std::fs::write(&validator, "\"v1\"")?;
+    let (url, handle) = serve(1);
+    assert_eq!(download(&EmptyReporter, &url, &path).await?, b"hello world");
+    assert_eq!(std::fs::read(&path)?, b"hello world");
+    assert!(!part.exists() && !validator.exists());
+    handle.join().unwrap();
+
+    // A partial file of another version is replaced.
+    std::fs::write(&part, "stale ")?;
+    std::fs::write(&validator, "\"v0\"")?;
+    let (url, handle) = serve(1);
+    assert_eq!(download(&EmptyReporter, &url, &path).await?, b"hello world");
+    let requests = handle.join().unwrap();
+    assert!(requests[0].contains(&String::from("if-range: \"v0\"")));
+
+    // Without a validator, the partial file isn't resumed.
+    std::fs::write(&part, "hello ")?;
+    let (url, handle) = serve(1);
+    assert_eq!(download(&EmptyReporter, &url, &path).await?, b"hello world");
+    let requests = handle.join().unwrap();
+    assert!(!requests[0].iter().any(|h| h.starts_with("range:")));
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Checkpoints of forced builds, so that a build which is interrupted can be
+//! resumed without rebuilding indexes which were already rebuilt.
+//!
+//! The checkpoint records which indexes a build is rebuilding and, for every
+//! index which has been rebuilt, the path and checksum of the new index. When
+//! the same indexes are rebuilt again, the ones whose new index is still
+//! intact are kept. The checkpoint is removed once the build completes, and
+//! checkpoints which are too old or which are for other indexes are discarded.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use lib::{storage, Dirs};
+use serde::{Deserialize, Serialize};
+
+/// Checkpoints older than this are discarded.
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// An index which has been rebuilt.
+#[derive(Serialize, Deserialize)]
+struct Completed {
+    path: PathBuf,
+    checksum: u64,
+}
+
+/// The checkpoint of a build.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    /// When the build was started, in seconds since the Unix epoch.
+    started: u64,
+    /// The indexes being rebuilt.
+    names: BTreeSet<String>,
+    /// Indexes which have been rebuilt.
+    completed: BTreeMap<String, Completed>,
+}
+
+impl Checkpoint {
+    /// Resume the checkpoint of an interrupted build of the given indexes,
+    /// or start a new one.
+    ///
+    /// Builds which don't rebuild anything don't need a checkpoint, and leave
+    /// any existing checkpoint alone.
+    pub(crate) fn resume(dirs: &Dirs, names: BTreeSet<String>) -> Self {
+        if names.is_empty() {
+            return Self::default();
+        }
+
+        let path = dirs.checkpoint_path();
+
+        let parse = |data: &str| Ok(serde_json::from_str::<Self>(data)?);
+
+        let existing = match storage::load(&path, parse) {
+            Ok(existing) => existing,
+            Err(error) => {
+                tracing::warn!("Discarding broken build checkpoint: {error:#}");
+                None
+            }
+        };
+
+        if let Some(existing) = existing {
+            let age = now().saturating_sub(existing.started);
+
+            if existing.names == names && age < MAX_AGE.as_secs() {
+                tracing::info!(
+                    "Resuming interrupted build, {} of {} index(es) already rebuilt",
+                    existing.completed.len(),
+                    names.len()
+                );
+
+                return existing;
+            }
+
+            tracing::info!("Removing stale build checkpoint {}", path.display());
+            remove(&path);
+        }
+
+        Self {
+            started: now(),
+            names,
+            completed: BTreeMap::new(),
+        }
+    }
+
+    /// Test if the given index has already been rebuilt, and its new index is
+    /// still intact.
+    pub(crate) fn is_completed(&self, name: &str) -> bool {
+        let Some(completed) = self.completed.get(name) else {
+            return false;
+        };
+
+        match checksum(&completed.path) {
+            Ok(checksum) => checksum == completed.checksum,
+            Err(error) => {
+                tracing::warn!("{}: {error:#}", completed.path.display());
+                false
+            }
+        }
+    }
+
+    /// Record that the given index has been rebuilt to the given path.
+    pub(crate) fn complete(&mut self, dirs: &Dirs, name: &str, path: &Path) -> Result<()> {
+        let checksum = checksum(path)?;
+
+        self.completed.insert(
+            name.to_owned(),
+            Completed {
+                path: path.to_owned(),
+                checksum,
+            },
+        );
+
+        let data = serde_json::to_vec(self)?;
+        storage::save(&dirs.checkpoint_path(), &data, |data| {
+            Ok(serde_json::from_str::<Self>(data)?)
+        })?;
+        Ok(())
+    }
+
+    /// Finish the build, removing the checkpoint.
+    pub(crate) fn finish(self, dirs: &Dirs) {
+        if !self.names.is_empty() {
+            remove(&dirs.checkpoint_path());
+        }
+    }
+}
+
+/// Calculate the checksum of the file at the given path.
+fn checksum(path: &Path) -> Result<u64> {
+    let data = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    Ok(crate::hash::hash(&data[..]))
+}
+
+/// Remove a checkpoint together with its backup.
+fn remove(path: &Path) {
+    for path in [path.to_owned(), storage::backup_path(path)] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => tracing::warn!("Failed to remove {}: {error}", path.display()),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,14 +1,17 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 
 use lib::config::Config;
-use lib::reporter::EmptyReporter;
-use lib::Dirs;
+use lib::database::shard::{Manifest, MANIFEST};
+use lib::{Dirs, DATABASE_VERSION};
+use tokio::signal::ctrl_c;
 use tokio::sync::oneshot;
 
-use crate::background::DownloadOverrides;
+use crate::background::{DownloadOverrides, ToDownload};
+use crate::reporter::ConsoleReporter;
 use crate::Args;
 
 #[derive(Parser)]
@@ -20,6 +23,17 @@ pub(crate) struct BuildArgs {
     /// Force a dictionary rebuild.
     #[arg(long, short = 'f', value_name = "name")]
     force: Vec<String>,
+    /// Only rebuild the dictionary with the specified id, leaving the others
+    /// as they are.
+    #[arg(long, value_name = "name")]
+    only: Vec<String>,
+    /// Build the dictionaries split into shards in the given directory
+    /// together with a manifest listing them, instead of installing them.
+    ///
+    /// Phrases and names are split by the kana their reading starts with,
+    /// everything else by the type of dictionary.
+    #[arg(long, value_name = "dir")]
+    shards: Option<PathBuf>,
 }
 
 pub(crate) async fn run(
@@ -38,24 +52,72 @@ pub(crate) async fn run(
         overrides.insert(id, path);
     }
 
-    let to_download = crate::background::config_to_download(&config, dirs, overrides, None);
+    for id in &build_args.only {
+        if !config.indexes.contains_key(id) {
+            bail!("No dictionary with the id `{id}`");
+        }
+    }
+
+    let only = (!build_args.only.is_empty()).then_some(&build_args.only[..]);
+    let to_download = crate::background::config_to_download(&config, dirs, overrides, only);
+
+    if let Some(out) = &build_args.shards {
+        return build_shards(dirs, &to_download, out).await;
+    }
 
     let force_all = build_args.force.first().is_some_and(|v| v == "all");
 
+    crate::background::build_all(dirs, &to_download, |to_download| {
+        force_all || only.is_some() || build_args.force.contains(&to_download.name)
+    })
+    .await?;
+
+    crate::background::remove_stale_indexes(dirs);
+
+    crate::dbus::shutdown().await?;
+    Ok(())
+}
+
+/// Build every dictionary into shards in the given directory and write the
+/// manifest listing them.
+async fn build_shards(dirs: &Dirs, to_download: &[ToDownload], out: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(out)
+        .await
+        .with_context(|| anyhow!("{}", out.display()))?;
+
+    let mut manifest = Manifest {
+        version: DATABASE_VERSION,
+        shards: Vec::new(),
+    };
+
     for to_download in to_download {
-        let tracing_reporter = Arc::new(EmptyReporter);
-        let (_sender, shutdown) = oneshot::channel();
-
-        crate::background::build(
-            tracing_reporter,
-            shutdown,
-            dirs,
-            &to_download,
-            force_all || build_args.force.contains(&to_download.name),
-        )
-        .await?;
+        let (sender, shutdown) = oneshot::channel();
+
+        let interrupt = tokio::spawn(async move {
+            if ctrl_c().await.is_ok() {
+                let _ = sender.send(());
+            }
+        });
+
+        let reporter = Arc::new(ConsoleReporter::new(
+            &to_download.name,
+            crate::background::BUILD_STEPS,
+        ));
+
+        let shards =
+            crate::background::build_shards(reporter, shutdown, dirs, to_download, out).await;
+
+        interrupt.abort();
+        manifest.shards.extend(shards?);
     }
 
-    crate::dbus::shutdown().await?;
+    let path = out.join(MANIFEST);
+    let contents = serde_json::to_vec_pretty(&manifest)?;
+
+    tokio::fs::write(&path, contents)
+        .await
+        .with_context(|| anyhow!("{}", path.display()))?;
+
+    tracing::info!("Wrote manifest to {}", path.display());
     Ok(())
 }
@@ -4,14 +4,15 @@ use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 use std::fs;
 use std::io::Write;
-use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
+use lib::accent::Notation;
+use lib::api;
 use lib::config::Config;
-use lib::data;
-use lib::database::{Database, Entry, Id};
+use lib::database::{Database, Entry};
 use lib::inflection;
 use lib::{Dirs, Form, Furigana, PartOfSpeech};
 
@@ -49,10 +50,23 @@ pub(crate) struct CliArgs {
     /// Only fetch the specified sequence ids.
     #[arg(long = "seq")]
     sequences: Vec<u32>,
+    /// Dump the bytes the entries given with `--seq` are stored as in hex,
+    /// together with where they're stored and the decoded entry. This is for
+    /// debugging how entries are encoded.
+    #[arg(long)]
+    raw: bool,
+    /// Dump the bytes of the entry stored at the given byte offset in an
+    /// index, like `jmdict:1024`, in the same way as `--raw`.
+    #[arg(long = "raw-at", value_name = "index:offset")]
+    raw_at: Vec<String>,
     /// Output format to use, defaults to `json`. Available options are: rich,
     /// json, json-pretty.
     #[arg(long)]
     output_format: Option<String>,
+    /// Always open indexes directly, instead of querying the service if it is
+    /// running.
+    #[arg(long)]
+    direct: bool,
     /// Search arguments to filter by. Must be either kana or kanji, which is
     /// matched against entries searched for.
     #[arg(name = "arguments")]
@@ -89,19 +103,45 @@ pub(crate) async fn run(
         return Ok(());
     }
 
+    let current_lang = cli_args.lang.as_deref().unwrap_or("eng");
+
+    // Prefer querying a running service for plain searches, since it already
+    // has the indexes loaded.
+    if can_query_service(args, cli_args) {
+        let bind = config.bind.as_deref().unwrap_or(crate::web::BIND);
+
+        if let Some(responses) = query_service(&service_address(bind), &cli_args.arguments).await {
+            let mut results = Results::default();
+
+            for response in &responses {
+                results.extend(
+                    response.phrases.iter().map(|p| {
+                        let accents = p.accents.iter().map(lib::borrow).collect();
+                        (lib::borrow(&p.phrase), accents)
+                    }),
+                    response.names.iter().map(|n| lib::borrow(&n.name)),
+                    response.characters.iter().map(lib::borrow),
+                );
+            }
+
+            return results.print(format, cli_args, current_lang);
+        }
+    }
+
     // SAFETY: we know this is only initialized once here exclusively.
-    let indexes = data::open_from_args(&args.index[..], dirs)?;
-    let db = Database::open(indexes, &config)?;
+    let db = args.indexes().open(dirs, &config)?;
 
-    if let Some(path) = &cli_args.long {
-        let ids = db.all()?;
+    if cli_args.raw || !cli_args.raw_at.is_empty() {
+        return dump_raw(&db, cli_args);
+    }
 
+    if let Some(path) = &cli_args.long {
         let mut f = fs::File::create(path)?;
 
         let mut uniq = HashSet::new();
 
-        for id in ids {
-            let Entry::Phrase(entry) = db.entry_at(id)? else {
+        for id in db.all() {
+            let Entry::Phrase(entry) = db.entry_at(id?)? else {
                 continue;
             };
 
@@ -137,6 +177,50 @@ pub(crate) async fn run(
         return Ok(());
     }
 
+    let pos = parse_pos(&cli_args.parts_of_speech)?;
+
+    // Arguments are searched for the same way a running service searches for
+    // them, unless they only narrow down the given sequences.
+    if cli_args.sequences.is_empty() && !cli_args.arguments.is_empty() {
+        let by_pos = match &pos {
+            Some(pos) => Some(
+                db.by_pos(*pos)?
+                    .into_iter()
+                    .map(|id| id.key())
+                    .collect::<HashSet<_>>(),
+            ),
+            None => None,
+        };
+
+        let mut results = Results::default();
+
+        for input in &cli_args.arguments {
+            let mut search = db.search(input)?;
+
+            // Only phrases have parts of speech.
+            if let Some(by_pos) = &by_pos {
+                search.phrases.retain(|(key, _)| by_pos.contains(&key.key));
+                search.names.clear();
+                search.characters.clear();
+            }
+
+            let mut phrases = Vec::with_capacity(search.phrases.len());
+
+            for (_, phrase) in search.phrases {
+                let accents = crate::web::reading_accents(&db, &phrase, None)?;
+                phrases.push((phrase, accents));
+            }
+
+            results.extend(
+                phrases,
+                search.names.into_iter().map(|(_, name)| name),
+                search.characters,
+            );
+        }
+
+        return results.print(format, cli_args, current_lang);
+    }
+
     let mut to_look_up = BTreeSet::new();
 
     for &seq in &cli_args.sequences {
@@ -144,78 +228,326 @@ pub(crate) async fn run(
     }
 
     for input in &cli_args.arguments {
-        let seed = cli_args.sequences.is_empty();
-
-        if seed {
-            to_look_up.extend(db.lookup(input)?);
-        } else {
-            let filter = db
-                .lookup(input)?
-                .into_iter()
-                .map(|id| id.key())
-                .collect::<HashSet<_>>();
-            to_look_up.retain(|id| filter.contains(&id.key()));
-        }
+        let filter = db
+            .lookup(input)?
+            .into_iter()
+            .map(|id| id.key())
+            .collect::<HashSet<_>>();
+        to_look_up.retain(|id| filter.contains(&id.key()));
     }
 
-    if !cli_args.parts_of_speech.is_empty() {
-        let mut seed = cli_args.arguments.is_empty() && cli_args.sequences.is_empty();
-        let mut pos = fixed_map::Set::new();
-
-        for p in cli_args
-            .parts_of_speech
-            .iter()
-            .flat_map(|s| PartOfSpeech::parse_keyword(s))
-        {
-            pos.insert(p);
-        }
-
+    if let Some(pos) = pos {
         let ids = db.by_pos(pos)?;
 
-        if mem::take(&mut seed) {
+        if cli_args.sequences.is_empty() {
             to_look_up.extend(ids);
         } else {
             to_look_up.retain(|id| ids.contains(id));
         }
     }
 
-    let current_lang = cli_args.lang.as_deref().unwrap_or("eng");
-
     let o = std::io::stdout();
     let mut o = o.lock();
 
+    let single = to_look_up.len() == 1 || !cli_args.sequences.is_empty();
+
     for (i, id) in to_look_up.iter().enumerate() {
-        match format {
-            OutputFormat::Rich => {
-                print_rich(&mut o, &db, cli_args, current_lang, &to_look_up, i, *id)?
+        let entry = db.entry_at(*id)?;
+
+        let accents = match &entry {
+            Entry::Phrase(phrase) => crate::web::reading_accents(&db, phrase, None)?,
+            _ => Vec::new(),
+        };
+
+        print(
+            &mut o,
+            format,
+            cli_args,
+            current_lang,
+            single,
+            i,
+            &entry,
+            &accents,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Parse the parts of speech given with `--pos`, or `None` if none were
+/// given.
+fn parse_pos(arguments: &[String]) -> Result<Option<fixed_map::Set<PartOfSpeech>>> {
+    if arguments.is_empty() {
+        return Ok(None);
+    }
+
+    let mut pos = fixed_map::Set::new();
+
+    for argument in arguments {
+        let Some(p) = PartOfSpeech::parse_keyword(argument) else {
+            bail!("Unknown part of speech `{argument}`, see `--list-pos`");
+        };
+
+        pos.insert(p);
+    }
+
+    Ok(Some(pos))
+}
+
+/// The results of searching for each argument, combined the same way whether
+/// they were searched for by a running service or in indexes opened
+/// directly.
+#[derive(Default)]
+struct Results<'a> {
+    phrases: HashSet<u64>,
+    names: HashSet<u64>,
+    characters: HashSet<&'a str>,
+    entries: Vec<(Entry<'a>, Vec<api::ReadingAccent<'a>>)>,
+}
+
+impl<'a> Results<'a> {
+    /// Add the results of a search, skipping entries which were already
+    /// found by an earlier one.
+    fn extend(
+        &mut self,
+        phrases: impl IntoIterator<Item = (lib::jmdict::Entry<'a>, Vec<api::ReadingAccent<'a>>)>,
+        names: impl IntoIterator<Item = lib::jmnedict::Entry<'a>>,
+        characters: impl IntoIterator<Item = lib::kanjidic2::Character<'a>>,
+    ) {
+        for (phrase, accents) in phrases {
+            if self.phrases.insert(phrase.sequence) {
+                self.entries.push((Entry::Phrase(phrase), accents));
+            }
+        }
+
+        for name in names {
+            if self.names.insert(name.sequence) {
+                self.entries.push((Entry::Name(name), Vec::new()));
             }
-            OutputFormat::Json | OutputFormat::JsonPretty => print_json(
+        }
+
+        for c in characters {
+            if self.characters.insert(c.literal) {
+                self.entries.push((Entry::Kanji(c), Vec::new()));
+            }
+        }
+    }
+
+    fn print(&self, format: OutputFormat, cli_args: &CliArgs, current_lang: &str) -> Result<()> {
+        let o = std::io::stdout();
+        let mut o = o.lock();
+
+        let single = self.entries.len() == 1;
+
+        for (i, (entry, accents)) in self.entries.iter().enumerate() {
+            print(
                 &mut o,
-                &db,
+                format,
                 cli_args,
-                matches!(format, OutputFormat::JsonPretty),
+                current_lang,
+                single,
                 i,
-                id,
-            )?,
+                entry,
+                accents,
+            )?;
         }
+
+        Ok(())
+    }
+}
+
+/// Get the address a service bound to the given address is reached on, where
+/// services bound to every interface are reached on the loopback interface.
+pub(crate) fn service_address(bind: &str) -> String {
+    let Ok(mut addr) = bind.parse::<SocketAddr>() else {
+        return bind.to_owned();
+    };
+
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+        IpAddr::V6(ip) if ip.is_unspecified() => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+        _ => {}
+    }
+
+    addr.to_string()
+}
+
+/// Test if the query can be answered by a running service.
+fn can_query_service(args: &Args, cli_args: &CliArgs) -> bool {
+    !cli_args.direct
+        && args.index.is_empty()
+        && args.manifest.is_none()
+        && cli_args.long.is_none()
+        && cli_args.sequences.is_empty()
+        && cli_args.parts_of_speech.is_empty()
+        && !cli_args.raw
+        && cli_args.raw_at.is_empty()
+        && !cli_args.arguments.is_empty()
+}
+
+/// Dump the bytes entries are stored as.
+fn dump_raw(db: &Database, cli_args: &CliArgs) -> Result<()> {
+    if cli_args.raw && cli_args.sequences.is_empty() {
+        bail!("`--raw` needs the entries to dump to be given with `--seq`");
+    }
+
+    let mut entries = Vec::new();
+
+    for &seq in &cli_args.sequences {
+        let found = db.raw_entries_by_sequence(seq)?;
+
+        if found.is_empty() {
+            bail!("No phrase with the sequence {seq}");
+        }
+
+        entries.extend(found);
+    }
+
+    for at in &cli_args.raw_at {
+        let Some((index, offset)) = at.rsplit_once(':') else {
+            bail!("Expected `<index>:<offset>`, but got `{at}`");
+        };
+
+        let offset = offset
+            .parse()
+            .with_context(|| format!("Bad offset in `{at}`"))?;
+
+        let Some(entry) = db.raw_entry_at(index, offset)? else {
+            bail!("No entry starts at offset {offset} in `{index}`");
+        };
+
+        entries.push(entry);
+    }
+
+    let o = std::io::stdout();
+    let mut o = o.lock();
+
+    for raw in &entries {
+        writeln!(
+            o,
+            "{} {} at {} ({} bytes, round trips: {})",
+            raw.index,
+            raw.section,
+            raw.offset,
+            raw.bytes.len(),
+            raw.round_trips
+        )?;
+
+        for (n, chunk) in raw.bytes.chunks(32).enumerate() {
+            write!(o, "  {:08x}:", raw.offset as usize + n * 32)?;
+
+            for b in chunk {
+                write!(o, " {b:02x}")?;
+            }
+
+            writeln!(o)?;
+        }
+
+        writeln!(o, "{}", serde_json::to_string_pretty(&raw.entry)?)?;
     }
 
     Ok(())
 }
 
+/// Search for each argument in a service running on the given address,
+/// returning `None` if it is not running or any query fails.
+#[cfg(feature = "reqwest")]
+async fn query_service(bind: &str, arguments: &[String]) -> Option<Vec<api::OwnedSearchResponse>> {
+    let client = reqwest::ClientBuilder::new()
+        .connect_timeout(std::time::Duration::from_millis(250))
+        .build()
+        .ok()?;
+
+    let url = format!("http://{bind}/api/search");
+
+    let mut responses = Vec::with_capacity(arguments.len());
+
+    for q in arguments {
+        responses.push(search_service(&client, &url, q).await?);
+    }
+
+    Some(responses)
+}
+
+#[cfg(feature = "reqwest")]
+async fn search_service(
+    client: &reqwest::Client,
+    url: &str,
+    q: &str,
+) -> Option<api::OwnedSearchResponse> {
+    let response = match client
+        .get(url)
+        .query(&[("q", q)])
+        .header("User-Agent", crate::USER_AGENT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(error) => {
+            tracing::debug!("Service not available: {error}");
+            return None;
+        }
+    };
+
+    if !response.status().is_success() {
+        tracing::debug!("Service responded with {}", response.status());
+        return None;
+    }
+
+    let bytes = response.bytes().await.ok()?;
+
+    match musli_json::Encoding::new().from_slice(&bytes) {
+        Ok(response) => Some(response),
+        Err(error) => {
+            tracing::debug!("Bad response from service: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "reqwest"))]
+async fn query_service(_: &str, _: &[String]) -> Option<Vec<api::OwnedSearchResponse>> {
+    None
+}
+
+fn print<O>(
+    o: &mut O,
+    format: OutputFormat,
+    cli_args: &CliArgs,
+    current_lang: &str,
+    single: bool,
+    i: usize,
+    entry: &Entry<'_>,
+    accents: &[api::ReadingAccent<'_>],
+) -> Result<()>
+where
+    O: ?Sized + Write,
+{
+    match format {
+        OutputFormat::Rich => print_rich(o, cli_args, current_lang, single, i, entry, accents),
+        OutputFormat::Json | OutputFormat::JsonPretty => print_json(
+            o,
+            cli_args,
+            matches!(format, OutputFormat::JsonPretty),
+            i,
+            entry,
+        ),
+    }
+}
+
 fn print_rich<O>(
     o: &mut O,
-    db: &Database,
     cli_args: &CliArgs,
     current_lang: &str,
-    to_look_up: &BTreeSet<Id>,
+    single: bool,
     i: usize,
-    id: Id,
+    entry: &Entry<'_>,
+    accents: &[api::ReadingAccent<'_>],
 ) -> Result<()>
 where
     O: ?Sized + Write,
 {
-    match db.entry_at(id)? {
+    match entry {
         Entry::Phrase(d) => {
             println!("#{i} Sequence: {}", d.sequence);
 
@@ -227,6 +559,20 @@ where
                 println!("  #{index} {:?}", kanji.debug_sparse());
             }
 
+            for accent in accents {
+                let rendered = accent
+                    .accents
+                    .iter()
+                    .filter_map(|&n| {
+                        lib::accent::render(accent.reading, usize::from(n), Notation::Downstep)
+                    })
+                    .collect::<Vec<_>>();
+
+                if !rendered.is_empty() {
+                    println!("  Accent: {} {}", accent.text, rendered.join(", "));
+                }
+            }
+
             for (index, sense) in d.senses.iter().enumerate() {
                 if !cli_args.any_lang && !sense.is_lang(current_lang) {
                     continue;
@@ -251,13 +597,13 @@ where
                 }
             }
 
-            if !cli_args.inflection || (to_look_up.len() > 1 && cli_args.sequences.is_empty()) {
+            if !cli_args.inflection || !single {
                 return Ok(());
             }
 
             let p = "  ";
 
-            for (_, c, _) in inflection::conjugate(&d) {
+            for (_, c, _) in inflection::conjugate(d) {
                 writeln!(o, "{p}# Inflections:")?;
 
                 writeln!(o, "{p}  Dictionary:")?;
@@ -285,11 +631,11 @@ where
         Entry::Kanji(kanji) => {
             writeln!(o, "Kanji: {}", kanji.literal)?;
 
-            for reading in kanji.readings {
+            for reading in &kanji.readings {
                 writeln!(o, "{}: {}", reading.ty, reading.text)?;
             }
 
-            for meaning in kanji.meanings {
+            for meaning in &kanji.meanings {
                 if let Some(lang) = meaning.lang {
                     writeln!(o, "{lang}: {}", meaning.text)?;
                 } else {
@@ -315,23 +661,14 @@ where
     Ok(())
 }
 
-fn print_json<O>(
-    o: &mut O,
-    db: &Database,
-    _: &CliArgs,
-    pretty: bool,
-    _: usize,
-    id: &Id,
-) -> Result<()>
+fn print_json<O>(o: &mut O, _: &CliArgs, pretty: bool, _: usize, entry: &Entry<'_>) -> Result<()>
 where
     O: ?Sized + Write,
 {
-    let output = db.entry_at(*id)?;
-
     if pretty {
-        serde_json::to_writer_pretty(&mut *o, &output)?;
+        serde_json::to_writer_pretty(&mut *o, entry)?;
     } else {
-        serde_json::to_writer(&mut *o, &output)?;
+        serde_json::to_writer(&mut *o, entry)?;
     }
 
     writeln!(o)?;
@@ -361,3 +698,11 @@ fn maybe_furigana(furigana: Furigana<'_>, do_furigana: bool) -> impl fmt::Displa
         do_furigana,
     }
 }
+
+#[test]
+fn test_service_address() {
+    assert_eq!(service_address("0.0.0.0:44714"), "127.0.0.1:44714");
+    assert_eq!(service_address("[::]:44714"), "[::1]:44714");
+    assert_eq!(service_address("192.168.0.2:44714"), "192.168.0.2:44714");
+    assert_eq!(service_address("localhost:44714"), "localhost:44714");
+}
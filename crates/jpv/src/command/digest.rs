@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::Parser;
+use lib::config::Config;
+use lib::data;
+use lib::database::Database;
+use lib::history::{self, History, Period};
+use lib::Dirs;
+
+use crate::background;
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct DigestArgs {
+    /// Summarize the last seven days instead of yesterday.
+    #[arg(long)]
+    week: bool,
+    /// The offset of local time from UTC in minutes, deciding when days
+    /// start.
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    utc_offset: i64,
+    /// The number of words to print.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    digest_args: &DigestArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    let period = if digest_args.week {
+        Period::Week
+    } else {
+        Period::Yesterday
+    };
+
+    let history = History::load(dirs)?;
+    let range = period.range(background::now(), digest_args.utc_offset.saturating_mul(60));
+    let digest = history::digest(history.lookups(), range);
+
+    if digest.is_empty() {
+        println!("No words looked up {}", period.describe());
+        return Ok(());
+    }
+
+    let indexes = data::open_from_args(&args.index[..], dirs)?;
+    let db = Database::open(indexes, &config)?;
+
+    println!("{} word(s) looked up {}:", digest.len(), period.describe());
+
+    for (n, word) in history::resolve(&db, &digest, digest_args.limit)?
+        .iter()
+        .enumerate()
+    {
+        println!(
+            "{:>3}. {} ({}×) {}",
+            n + 1,
+            word.text,
+            word.digested.count,
+            history::search_link(word.text)
+        );
+    }
+
+    Ok(())
+}
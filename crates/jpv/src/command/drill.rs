@@ -0,0 +1,92 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use lib::config::Config;
+use lib::data;
+use lib::database::Database;
+use lib::drill::Filter;
+use lib::{Dirs, Form, PartOfSpeech};
+
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct DrillArgs {
+    /// Parts of speech to drill, like `v5k` or `v1`. Defaults to every kind of
+    /// verb.
+    #[arg(long = "pos", name = "pos")]
+    parts_of_speech: Vec<String>,
+    /// Forms which drills may combine, like `past` and `negative`. Defaults to
+    /// every form.
+    #[arg(long = "form", name = "form")]
+    forms: Vec<String>,
+    /// Only drill polite forms.
+    #[arg(long, conflicts_with = "plain")]
+    polite: bool,
+    /// Only drill plain forms.
+    #[arg(long)]
+    plain: bool,
+    /// Seed deciding which words and forms are drilled.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// The number of drills to generate.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+    /// Leave out the answer key.
+    #[arg(long)]
+    no_answers: bool,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    drill_args: &DrillArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    let mut pos = Vec::new();
+
+    for argument in &drill_args.parts_of_speech {
+        let Some(p) = PartOfSpeech::parse_keyword(argument) else {
+            bail!("Unknown part of speech `{argument}`");
+        };
+
+        pos.push(p);
+    }
+
+    let mut filter = Filter {
+        polite: match (drill_args.polite, drill_args.plain) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+        ..Filter::default()
+    };
+
+    for argument in &drill_args.forms {
+        let Some(form) = Form::parse_keyword(argument) else {
+            bail!("Unknown form `{argument}`");
+        };
+
+        filter.forms.insert(form);
+    }
+
+    let indexes = data::open_from_args(&args.index[..], dirs)?;
+    let db = Database::open(indexes, &config)?;
+
+    let drills = db.drills(&pos, &filter, drill_args.seed, drill_args.limit)?;
+
+    for (n, drill) in drills.iter().enumerate() {
+        println!("{:>3}. {} ({})", n + 1, drill.prompt, drill.reading);
+    }
+
+    if drill_args.no_answers {
+        return Ok(());
+    }
+
+    println!();
+    println!("Answers:");
+
+    for (n, drill) in drills.iter().enumerate() {
+        println!("{:>3}. {}", n + 1, drill.answers.join(" / "));
+    }
+
+    Ok(())
+}
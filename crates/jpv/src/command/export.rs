@@ -0,0 +1,123 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use lib::config::Config;
+use lib::data;
+use lib::database::{Database, Entry};
+use lib::Dirs;
+use serde::Serialize;
+
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct ExportArgs {
+    #[command(subcommand)]
+    command: ExportCommand,
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Write every phrase, kanji and name as line-delimited JSON to
+    /// `phrases.jsonl`, `kanji.jsonl` and `names.jsonl`, where each line
+    /// records the index the entry comes from.
+    Jsonl(JsonlArgs),
+}
+
+#[derive(Parser)]
+struct JsonlArgs {
+    /// The directory to write to, which is created if it doesn't exist.
+    #[arg(long, value_name = "dir")]
+    out: PathBuf,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    export_args: &ExportArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    match &export_args.command {
+        ExportCommand::Jsonl(jsonl_args) => jsonl(args, jsonl_args, dirs, &config),
+    }
+}
+
+fn jsonl(args: &Args, jsonl_args: &JsonlArgs, dirs: &Dirs, config: &Config) -> Result<()> {
+    let indexes = data::open_from_args(&args.index[..], dirs)?;
+    let db = Database::open(indexes, config)?;
+
+    fs::create_dir_all(&jsonl_args.out)
+        .with_context(|| format!("Creating {}", jsonl_args.out.display()))?;
+
+    let mut phrases = Output::create(&jsonl_args.out.join("phrases.jsonl"))?;
+    let mut kanji = Output::create(&jsonl_args.out.join("kanji.jsonl"))?;
+    let mut names = Output::create(&jsonl_args.out.join("names.jsonl"))?;
+
+    // Entries are written as they're read, so only one is held in memory at
+    // a time.
+    for id in db.all() {
+        let id = id?;
+        let index = db.index_name(id.key())?;
+
+        match db.entry_at(id)? {
+            Entry::Phrase(entry) => phrases.write(index, &entry)?,
+            Entry::Kanji(entry) => kanji.write(index, &entry)?,
+            Entry::Name(entry) => names.write(index, &entry)?,
+            _ => {}
+        }
+    }
+
+    for (what, output) in [("phrase", phrases), ("kanji", kanji), ("name", names)] {
+        let (count, path) = output.finish()?;
+        println!("Wrote {count} {what}(s) to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// A line in an export.
+#[derive(Serialize)]
+struct Line<'a, T> {
+    /// The name of the index the entry comes from, like `jmdict`.
+    index: &'a str,
+    entry: &'a T,
+}
+
+/// A file entries are exported to.
+struct Output {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    count: usize,
+}
+
+impl Output {
+    fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Creating {}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            writer: BufWriter::new(file),
+            count: 0,
+        })
+    }
+
+    fn write<T>(&mut self, index: &str, entry: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        serde_json::to_writer(&mut self.writer, &Line { index, entry })?;
+        self.writer.write_all(b"\n")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(usize, PathBuf)> {
+        self.writer
+            .flush()
+            .with_context(|| format!("Writing {}", self.path.display()))?;
+
+        Ok((self.count, self.path))
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use lib::config::Config;
+use lib::data;
+use lib::database::Database;
+use lib::gloss;
+use lib::Dirs;
+
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct GlossArgs {
+    /// Output format, either `html` for a standalone document with furigana
+    /// and meanings shown when hovering over words, or `text` for furigana in
+    /// brackets like `食[た]べる`.
+    #[arg(long, default_value = "html")]
+    format: String,
+    /// Write the output to the given file instead of stdout.
+    #[arg(long, value_name = "path")]
+    out: Option<PathBuf>,
+    /// The text file to gloss, or `-` to read from stdin.
+    #[arg(value_name = "path")]
+    input: PathBuf,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    gloss_args: &GlossArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    let (title, text) = if gloss_args.input.as_os_str() == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        (String::from("jpv"), text)
+    } else {
+        let text = fs::read_to_string(&gloss_args.input)
+            .with_context(|| format!("Reading {}", gloss_args.input.display()))?;
+
+        let title = gloss_args
+            .input
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        (title, text)
+    };
+
+    let indexes = data::open_from_args(&args.index[..], dirs)?;
+    let db = Database::open(indexes, &config)?;
+
+    let segments = gloss::segments(&db, &text)?;
+
+    let output = match gloss_args.format.as_str() {
+        "html" => gloss::html(&title, &segments).to_string(),
+        "text" => {
+            let mut output = String::new();
+
+            for segment in &segments {
+                write!(output, "{}", segment.furigana())?;
+            }
+
+            output
+        }
+        format => bail!("Unsupported format `{format}`, expected `html` or `text`"),
+    };
+
+    match &gloss_args.out {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("Writing {}", path.display()))?;
+        }
+        None => {
+            io::stdout().write_all(output.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
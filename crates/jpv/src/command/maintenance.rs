@@ -0,0 +1,58 @@
+use anyhow::Result;
+use clap::Parser;
+use lib::config::Config;
+use lib::locale::Locale;
+use lib::Dirs;
+
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct MaintenanceArgs {
+    /// Only report what would be removed, without removing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub(crate) async fn run(
+    _: &Args,
+    maintenance_args: &MaintenanceArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    let locale = Locale::from_env();
+    let report = crate::maintenance::run(dirs, &config, maintenance_args.dry_run, locale)?;
+
+    let mut total = 0;
+    let mut reclaimed = 0;
+
+    for artifact in &report.artifacts {
+        total += artifact.size;
+
+        let action = if artifact.status.is_removable() {
+            reclaimed += artifact.size;
+
+            if artifact.removed {
+                "removed"
+            } else {
+                "would remove"
+            }
+        } else {
+            "kept"
+        };
+
+        println!(
+            "{:>10} {:?}/{:?} ({action}): {}",
+            artifact.size_text, artifact.kind, artifact.status, artifact.path
+        );
+    }
+
+    println!("Total: {}", locale.size(total));
+
+    if report.dry_run {
+        println!("Would reclaim: {}", locale.size(reclaimed));
+    } else {
+        println!("Reclaimed: {}", locale.size(reclaimed));
+    }
+
+    Ok(())
+}
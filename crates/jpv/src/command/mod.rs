@@ -1,4 +1,12 @@
 pub mod build;
 pub mod cli;
+pub mod digest;
+pub mod drill;
+pub mod export;
+pub mod gloss;
+pub mod maintenance;
+pub mod ocr;
+pub mod open;
 pub mod send_clipboard;
 pub mod service;
+pub mod sources;
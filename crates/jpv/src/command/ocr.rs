@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use lib::config::Config;
+use lib::data;
+use lib::database::Database;
+use lib::history;
+use lib::Dirs;
+
+use crate::ocr;
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct OcrArgs {
+    /// The tesseract language to recognize text with, like `jpn_vert` for
+    /// vertical text.
+    #[arg(long, default_value = ocr::LANGUAGE)]
+    lang: String,
+    /// Also search for the word which is most likely being looked up.
+    #[arg(long)]
+    search: bool,
+    /// The number of search results to print.
+    #[arg(long, default_value_t = 5)]
+    limit: usize,
+    /// The image to recognize text in.
+    #[arg(value_name = "path")]
+    input: PathBuf,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    ocr_args: &OcrArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    let image = image::open(&ocr_args.input)
+        .with_context(|| format!("Reading {}", ocr_args.input.display()))?;
+
+    let mut tesseract = tesseract::open(&ocr_args.lang)
+        .with_context(|| format!("Loading tesseract for `{}`", ocr_args.lang))?;
+
+    let indexes = data::open_from_args(&args.index[..], dirs)?;
+    let db = Database::open(indexes, &config)?;
+
+    let Some(recognized) = ocr::recognize_image(&mut tesseract, &db, &image)? else {
+        println!("No text recognized");
+        return Ok(());
+    };
+
+    println!("{}", recognized.text);
+
+    if !ocr_args.search {
+        return Ok(());
+    }
+
+    let Some(word) = recognized.word(&db)? else {
+        println!("No word found to search for");
+        return Ok(());
+    };
+
+    let search = db.search(word)?;
+
+    println!();
+    println!(
+        "{word}: {} result(s) {}",
+        search.phrases.len(),
+        history::search_link(word)
+    );
+
+    for (n, (_, entry)) in search.phrases.iter().take(ocr_args.limit).enumerate() {
+        let text = entry
+            .kanji_elements
+            .first()
+            .map(|k| k.text)
+            .or_else(|| entry.reading_elements.first().map(|r| r.text))
+            .unwrap_or_default();
+
+        let gloss = entry
+            .senses
+            .iter()
+            .flat_map(|sense| sense.gloss.iter())
+            .find(|gloss| gloss.lang.unwrap_or("eng") == "eng")
+            .map(|gloss| gloss.text)
+            .unwrap_or_default();
+
+        println!("{:>3}. {text} - {gloss}", n + 1);
+    }
+
+    Ok(())
+}
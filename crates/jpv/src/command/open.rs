@@ -0,0 +1,46 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use lib::config::Config;
+use lib::Dirs;
+
+use crate::command::service::ServiceArgs;
+use crate::system;
+use crate::Args;
+
+/// The scheme of URLs handled by the dictionary.
+const SCHEME: &str = "jpv://";
+
+#[derive(Parser)]
+pub(crate) struct OpenArgs {
+    /// The URL to open, such as `jpv://search?q=食べる`.
+    url: String,
+}
+
+pub(crate) async fn run(
+    args: &Args,
+    open_args: &OpenArgs,
+    dirs: Dirs,
+    config: Config,
+    system_events: system::SystemEvents,
+    log: crate::log::Capture,
+) -> Result<()> {
+    let mut service_args = ServiceArgs::default();
+    service_args.path = Some(url_to_path(&open_args.url)?);
+
+    super::service::run(args, &service_args, dirs, config, system_events, log).await
+}
+
+/// Convert a `jpv://` URL into the path of the web interface it corresponds
+/// to.
+fn url_to_path(url: &str) -> Result<String> {
+    let Some(rest) = url.strip_prefix(SCHEME) else {
+        bail!("Unsupported URL `{url}`, expected it to start with `{SCHEME}`");
+    };
+
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    match action.trim_end_matches('/') {
+        "search" => Ok(format!("/?{query}")),
+        action => bail!("Unsupported action `{action}` in URL `{url}`"),
+    }
+}
@@ -23,6 +23,7 @@ pub(crate) async fn run(args: &SendClipboardArgs) -> Result<()> {
             let json = lib::api::SendClipboardJson {
                 primary: args.data.to_string_lossy().into_owned(),
                 secondary: args.secondary.clone(),
+                analyze_at: None,
             };
 
             let data = musli_storage::to_vec(&json)?;
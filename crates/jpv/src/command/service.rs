@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::mem;
 use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::pin::pin;
@@ -7,17 +8,22 @@ use anyhow::{Context, Result};
 use async_fuse::Fuse;
 use clap::Parser;
 use lib::config::Config;
-use lib::data;
 use lib::Dirs;
 use tokio::signal::ctrl_c;
-use tokio::sync::Notify;
+use tokio::sync::{oneshot, Notify};
 
-use crate::background::Background;
+use crate::background::{Background, BackgroundEvent};
+use crate::command::cli::service_address;
 use crate::dbus;
+use crate::digest;
+use crate::mpv;
+use crate::ocr;
 use crate::open_uri;
 use crate::system;
 use crate::tasks::Tasks;
+use crate::warmup;
 use crate::web;
+use crate::webhooks;
 use crate::windows;
 use crate::Args;
 
@@ -43,6 +49,10 @@ pub(crate) struct ServiceArgs {
     /// Do not open the URI of the dictionary when started.
     #[arg(long)]
     pub(crate) no_open: bool,
+    /// Never try to launch a browser, for example when running headless or
+    /// inside of a container. This implies `--no-open`.
+    #[arg(long)]
+    pub(crate) no_open_uri: bool,
     /// Disable D-Bus binding.
     #[cfg(all(unix, feature = "dbus"))]
     #[arg(long)]
@@ -51,9 +61,14 @@ pub(crate) struct ServiceArgs {
     #[cfg(all(unix, feature = "dbus"))]
     #[arg(long)]
     pub(crate) dbus_system: bool,
-    /// Bind to the given address. Default is `127.0.0.1:44714`.
+    /// Bind to the given address. Default is `127.0.0.1:44714`. This takes
+    /// precedence over the `bind` configuration option, and prevents the
+    /// service from being rebound when it changes.
     #[arg(long, value_name = "address")]
     bind: Option<String>,
+    /// Path and query to open the dictionary at, as used by `jpv open`.
+    #[arg(skip)]
+    pub(crate) path: Option<String>,
 }
 
 pub(crate) async fn run(
@@ -64,12 +79,36 @@ pub(crate) async fn run(
     system_events: system::SystemEvents,
     log: crate::log::Capture,
 ) -> Result<()> {
-    let addr: SocketAddr = service_args
+    if config.log_filter.is_some() {
+        if let Err(error) = log.set_config_filter(config.log_filter.as_deref()) {
+            tracing::warn!(?error, "Ignoring invalid log filter in configuration");
+        }
+    }
+
+    // Addresses are validated when the configuration is saved, but one which
+    // was saved by an older version shouldn't prevent the service from
+    // starting.
+    let config_bind = config.bind.as_deref().filter(|bind| {
+        let valid = bind.parse::<SocketAddr>().is_ok();
+
+        if !valid {
+            tracing::warn!("Ignoring invalid bind address `{bind}` in configuration");
+        }
+
+        valid
+    });
+
+    let mut addr: SocketAddr = service_args
         .bind
         .as_deref()
+        .or(config_bind)
         .unwrap_or(self::web::BIND)
         .parse()?;
 
+    if service_args.no_open_uri {
+        open_uri::disable();
+    }
+
     let shutdown = Notify::new();
 
     let mut dbus = match dbus::setup(service_args)
@@ -81,13 +120,13 @@ pub(crate) async fn run(
             tracing::info!("Listening on http://localhost:{port}");
 
             if !service_args.no_open {
-                let address = format!("http://localhost:{port}");
-                open_uri::open(&address);
+                open_uri::open(&address(port, service_args));
             }
 
             return Ok(());
         }
         system::Setup::Busy => {
+            open_running(&dirs, addr, service_args);
             return Ok(());
         }
     };
@@ -98,19 +137,19 @@ pub(crate) async fn run(
             tracing::info!("Listening on http://localhost:{port}");
 
             if !service_args.no_open {
-                let address = format!("http://localhost:{port}");
-                open_uri::open(&address);
+                open_uri::open(&address(port, service_args));
             }
 
             return Ok(());
         }
         system::Setup::Busy => {
+            open_running(&dirs, addr, service_args);
             return Ok(());
         }
     };
 
     let listener = TcpListener::bind(addr)?;
-    let local_addr = listener.local_addr()?;
+    let mut local_addr = listener.local_addr()?;
     let local_port = web::PORT.unwrap_or(local_addr.port());
 
     let mut windows = match &mut windows {
@@ -124,12 +163,11 @@ pub(crate) async fn run(
     };
 
     // SAFETY: we know this is only initialized once here exclusively.
-    let indexes = data::open_from_args(&args.index[..], &dirs)?;
-    let db = lib::database::Database::open(indexes, &config)?;
+    let db = args.indexes().open(&dirs, &config)?;
 
     let (channel, mut receiver) = tokio::sync::mpsc::unbounded_channel();
 
-    let tesseract = match tesseract::open("jpn") {
+    let tesseract = match tesseract::open(ocr::LANGUAGE) {
         Ok(tesseract) => {
             if let Some(path) = tesseract.path() {
                 tracing::info!("Tesseract OCR support enabled from {}", path.display());
@@ -163,19 +201,29 @@ pub(crate) async fn run(
         log,
     )?;
 
-    let mut server = pin!(web::setup(
+    let (mut stop_server, stop) = oneshot::channel();
+
+    let mut server = Box::pin(web::setup(
         listener,
         background.clone(),
-        system_events.clone()
+        system_events.clone(),
+        stop,
     )?);
+
     tracing::info!("Listening on http://{local_addr}");
+    record_address(background.dirs(), local_addr);
 
     if !service_args.no_open {
-        let address = format!("http://localhost:{local_port}");
-        open_uri::open(&address);
+        open_uri::open(&address(local_port, service_args));
     }
 
+    tokio::spawn(mpv::run(background.clone(), system_events.clone()));
+    tokio::spawn(ocr::run(background.clone(), system_events.clone()));
+    tokio::spawn(digest::run(background.clone(), system_events.clone()));
+    tokio::spawn(webhooks::run(background.clone(), system_events.clone()));
+
     let mut tasks = Tasks::new();
+    warmup::start(&background, &mut tasks).await;
 
     let mut shutdown_signal = pin!(Fuse::new(async {
         tokio::select! {
@@ -209,7 +257,64 @@ pub(crate) async fn run(
                 shutdown.notify_waiters();
             }
             Some(event) = receiver.recv() => {
+                let bind = match &event {
+                    BackgroundEvent::SaveConfig(config, _) if service_args.bind.is_none() => {
+                        Some(config.bind.clone())
+                    }
+                    _ => None,
+                };
+
                 background.handle_event(event, args, &mut tasks).await.context("Handling background event")?;
+
+                let Some(bind) = bind else {
+                    continue;
+                };
+
+                let (new_addr, listener) = match rebind(bind.as_deref(), addr) {
+                    Ok(Some(rebind)) => rebind,
+                    Ok(None) => continue,
+                    Err(error) => {
+                        tracing::error!("Failed to rebind web server: {error:#}");
+                        continue;
+                    }
+                };
+
+                let new_local_addr = listener.local_addr()?;
+                let (new_stop_server, stop) = oneshot::channel();
+
+                let new_server = match web::setup(listener, background.clone(), system_events.clone(), stop) {
+                    Ok(server) => server,
+                    Err(error) => {
+                        tracing::error!("Failed to rebind web server: {error:#}");
+                        continue;
+                    }
+                };
+
+                tracing::info!("Listening on http://{new_local_addr}");
+                record_address(background.dirs(), new_local_addr);
+
+                let port = web::PORT.unwrap_or(new_local_addr.port());
+
+                system_events.send(system::Event::Rebind(system::Rebind {
+                    port,
+                    addr: new_local_addr,
+                }));
+
+                // Let the old server finish in-flight requests in the
+                // background, it stops accepting new connections as soon as
+                // it's signalled.
+                let old_server = mem::replace(&mut server, Box::pin(new_server));
+                let _ = mem::replace(&mut stop_server, new_stop_server).send(());
+                let old_local_addr = mem::replace(&mut local_addr, new_local_addr);
+                addr = new_addr;
+
+                tokio::spawn(async move {
+                    if let Err(error) = old_server.await {
+                        tracing::error!("Old server failed: {error:#}");
+                    }
+
+                    tracing::info!("Closed listener on http://{old_local_addr}");
+                });
             }
             result = tasks.wait() => {
                 let completed = result?;
@@ -225,6 +330,93 @@ pub(crate) async fn run(
 
     // Causes any background processes to shut down.
     tasks.finish().await;
+
+    if let Err(error) = background.save_history().await {
+        tracing::error!("Failed to save history: {error:#}");
+    }
+
+    let _ = std::fs::remove_file(background.dirs().service_address_path());
     tracing::info!("Bye!");
     Ok(())
 }
+
+/// The address to open the dictionary at.
+fn address(port: u16, service_args: &ServiceArgs) -> String {
+    let path = service_args.path.as_deref().unwrap_or_default();
+    format!("http://localhost:{port}{path}")
+}
+
+/// Open a path in an already running service which can't be asked for its
+/// port, at the address it recorded when it started listening or at the
+/// configured address if it hasn't recorded one.
+///
+/// Without a path nothing is opened, since this happens when the service is
+/// started a second time.
+fn open_running(dirs: &Dirs, configured: SocketAddr, service_args: &ServiceArgs) {
+    if service_args.path.is_none() || service_args.no_open {
+        return;
+    }
+
+    let addr = running_address(dirs).unwrap_or(configured);
+    let path = service_args.path.as_deref().unwrap_or_default();
+    open_uri::open(&format!(
+        "http://{}{path}",
+        service_address(&addr.to_string())
+    ));
+}
+
+/// Record the address the service listens on, so that it can be found by
+/// [`open_running`] even if it was bound to some other address than the
+/// configured one.
+fn record_address(dirs: &Dirs, addr: SocketAddr) {
+    let path = dirs.service_address_path();
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, addr.to_string()));
+
+    if let Err(error) = result {
+        tracing::warn!(
+            "Failed to record service address in {}: {error}",
+            path.display()
+        );
+    }
+}
+
+/// Read the address recorded by a running service.
+fn running_address(dirs: &Dirs) -> Option<SocketAddr> {
+    let addr = std::fs::read_to_string(dirs.service_address_path()).ok()?;
+    addr.trim().parse().ok()
+}
+
+/// Bind a new listener if the configured address differs from the one the
+/// server is currently bound to.
+fn rebind(bind: Option<&str>, current: SocketAddr) -> Result<Option<(SocketAddr, TcpListener)>> {
+    let bind = bind.unwrap_or(web::BIND);
+
+    let addr: SocketAddr = bind
+        .parse()
+        .with_context(|| format!("Parsing bind address `{bind}`"))?;
+
+    if addr == current {
+        return Ok(None);
+    }
+
+    let listener = TcpListener::bind(addr).with_context(|| format!("Binding to {addr}"))?;
+    Ok(Some((addr, listener)))
+}
+
+#[test]
+fn test_running_address() {
+    let root = std::env::temp_dir().join(format!("jpv-test-service-{}", std::process::id()));
+    let dirs = Dirs::in_dir(&root);
+
+    assert_eq!(running_address(&dirs), None);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    record_address(&dirs, addr);
+    assert_eq!(running_address(&dirs), Some(addr));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
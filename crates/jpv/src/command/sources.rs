@@ -0,0 +1,131 @@
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use lib::config::Config;
+use lib::Dirs;
+
+use crate::background;
+use crate::Args;
+
+#[derive(Parser)]
+pub(crate) struct SourcesArgs {
+    #[command(subcommand)]
+    command: SourcesCommand,
+}
+
+#[derive(Subcommand)]
+enum SourcesCommand {
+    /// List configured dictionary sources, with the version of the downloaded
+    /// file and the latest remote version.
+    List(ListArgs),
+    /// Download the latest version of dictionary sources and rebuild their
+    /// indexes.
+    Update(UpdateArgs),
+}
+
+#[derive(Parser)]
+struct ListArgs {
+    /// Don't check the latest remote version.
+    #[arg(long)]
+    offline: bool,
+}
+
+#[derive(Parser)]
+struct UpdateArgs {
+    /// Sources to update. Defaults to every enabled source.
+    #[arg(value_name = "name")]
+    names: Vec<String>,
+}
+
+pub(crate) async fn run(
+    _: &Args,
+    sources_args: &SourcesArgs,
+    dirs: &Dirs,
+    config: Config,
+) -> Result<()> {
+    match &sources_args.command {
+        SourcesCommand::List(list_args) => list(list_args, dirs, &config).await,
+        SourcesCommand::Update(update_args) => update(update_args, dirs, &config).await,
+    }
+}
+
+async fn list(list_args: &ListArgs, dirs: &Dirs, config: &Config) -> Result<()> {
+    let to_download = background::config_to_download(config, dirs, Default::default(), None);
+
+    for to_download in to_download {
+        let enabled = if config.is_enabled(&to_download.name) {
+            "enabled"
+        } else {
+            "disabled"
+        };
+
+        println!("{} ({enabled}): {}", to_download.name, to_download.url);
+
+        match background::download_path(dirs, &to_download.url) {
+            Some(path) if path.is_file() => {
+                let version = match background::declared_version(&path) {
+                    Ok(version) => version.unwrap_or_else(|| String::from("unknown")),
+                    Err(error) => format!("unreadable: {error}"),
+                };
+
+                println!("  Local: {version} ({})", path.display());
+            }
+            _ => {
+                println!("  Local: not downloaded");
+            }
+        }
+
+        if list_args.offline {
+            continue;
+        }
+
+        match background::remote_version(&to_download.url).await {
+            Ok(remote) => {
+                let last_modified = remote.last_modified.as_deref().unwrap_or("unknown");
+
+                match remote.size {
+                    Some(size) => println!("  Remote: {last_modified} ({size} bytes)"),
+                    None => println!("  Remote: {last_modified}"),
+                }
+            }
+            Err(error) => {
+                println!("  Remote: {error}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn update(update_args: &UpdateArgs, dirs: &Dirs, config: &Config) -> Result<()> {
+    for name in &update_args.names {
+        if !config.indexes.contains_key(name) {
+            bail!("No such source `{name}`");
+        }
+    }
+
+    let filter = if update_args.names.is_empty() {
+        config
+            .indexes
+            .keys()
+            .filter(|name| config.is_enabled(name))
+            .cloned()
+            .collect()
+    } else {
+        update_args.names.clone()
+    };
+
+    let to_download =
+        background::config_to_download(config, dirs, Default::default(), Some(&filter));
+
+    // Forcing the build downloads the latest version of the source.
+    background::build_all(dirs, &to_download, |_| true).await?;
+
+    for to_download in &to_download {
+        println!("Updated {}", to_download.name);
+    }
+
+    background::remove_stale_indexes(dirs);
+
+    crate::dbus::shutdown().await?;
+    Ok(())
+}
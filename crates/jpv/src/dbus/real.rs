@@ -9,11 +9,14 @@ use tokio_dbus::{ty, BodyBuf, Connection, Flags, Message, MessageKind, ObjectPat
 
 use crate::command::service::ServiceArgs;
 use crate::open_uri;
-use crate::system::{Event, SendClipboardData, Setup, Start, SystemEvents};
+use crate::system::{Event, Notification, SendClipboardData, Setup, Start, SystemEvents};
 
 const NAME: &str = "se.tedro.JapaneseDictionary";
 const PATH: &ObjectPath = ObjectPath::new_const(b"/se/tedro/JapaneseDictionary");
 
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &ObjectPath = ObjectPath::new_const(b"/org/freedesktop/Notifications");
+
 pub(crate) async fn send_clipboard(ty: Option<&str>, data: &[u8]) -> Result<()> {
     let mut c = Connection::session_bus().await?;
 
@@ -49,6 +52,39 @@ pub(crate) async fn shutdown() -> Result<()> {
     Ok(())
 }
 
+/// Show a desktop notification through `org.freedesktop.Notifications`.
+async fn notify(notification: &Notification) -> Result<()> {
+    let mut c = Connection::session_bus().await?;
+
+    let (_, send, body) = c.buffers();
+
+    body.arguments((
+        "Japanese Dictionary",
+        0u32,
+        NAME,
+        notification.summary.as_str(),
+        notification.body.as_str(),
+    ))?;
+
+    // Actions and hints.
+    body.store_array::<ty::Str>()?.finish();
+    body.store_array::<(ty::Str, ty::Variant)>()?.finish();
+    // Use the default expiration timeout.
+    body.store(-1i32)?;
+
+    let m = send
+        .method_call(NOTIFICATIONS_PATH, "Notify")
+        .with_interface(NOTIFICATIONS_NAME)
+        .with_destination(NOTIFICATIONS_NAME)
+        .with_body(body)
+        .with_flags(Flags::NO_REPLY_EXPECTED);
+
+    send.write_message(m)?;
+
+    c.flush().await?;
+    Ok(())
+}
+
 /// Request port from D-Bus service. This will cause the service to activate if
 /// it isn't already.
 async fn get_port(c: &mut Connection) -> Result<u16> {
@@ -113,6 +149,8 @@ impl Start for DbusStart {
     ) -> Pin<Box<dyn Future<Output = Result<()>> + 'a>> {
         Box::pin(async move {
             let mut shutdown = pin!(Fuse::new(shutdown));
+            let mut events = system_events.subscribe();
+
             let mut state = State {
                 port,
                 system_events,
@@ -153,6 +191,19 @@ impl Start for DbusStart {
                             }
                         }
                     }
+                    event = events.recv() => {
+                        match event {
+                            Ok(Event::Notify(notification)) => {
+                                if let Err(error) = notify(&notification).await {
+                                    tracing::warn!("Failed to show notification: {error}");
+                                }
+                            }
+                            Ok(Event::Rebind(rebind)) => {
+                                state.port = rebind.port;
+                            }
+                            _ => {}
+                        }
+                    }
                     _ = shutdown.as_mut() => {
                         return Ok(());
                     }
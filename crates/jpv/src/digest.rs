@@ -0,0 +1,70 @@
+//! Daily desktop notifications with a digest of the words looked up the day
+//! before, to encourage reviewing them.
+//!
+//! The service doesn't know the local time zone, so days are counted in UTC.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::Result;
+use lib::history::{self, Period};
+
+use crate::background::{self, Background};
+use crate::system;
+
+/// How often it's checked whether a digest is due.
+const INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The number of words mentioned in a notification.
+const NOTIFY_LIMIT: usize = 5;
+
+/// Send a digest once a day while it's enabled in the configuration.
+pub(crate) async fn run(background: Background, system_events: system::SystemEvents) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if !background.config().await.digest {
+            continue;
+        }
+
+        if let Err(error) = notify(&background, &system_events).await {
+            tracing::warn!("Failed to send digest of looked up words: {error:#}");
+        }
+    }
+}
+
+async fn notify(background: &Background, system_events: &system::SystemEvents) -> Result<()> {
+    let digest = background.digest(Period::Yesterday, 0).await?;
+
+    if digest.is_empty() {
+        return Ok(());
+    }
+
+    let today = history::day(background::now(), 0);
+
+    if !background.mark_digest_notified(today).await? {
+        return Ok(());
+    }
+
+    let db = background.database().await;
+    let words = history::resolve(&db, &digest, NOTIFY_LIMIT)?;
+
+    let mut body = format!("You looked up {} word(s) yesterday:", digest.len());
+
+    for word in &words {
+        write!(body, "\n{} ({}×)", word.text, word.digested.count)?;
+    }
+
+    if let Some(more) = digest.len().checked_sub(words.len()).filter(|&n| n > 0) {
+        write!(body, "\n…and {more} more, see `jpv digest`")?;
+    }
+
+    system_events.send(system::Event::Notify(system::Notification {
+        summary: String::from("Words to review"),
+        body,
+    }));
+
+    Ok(())
+}
@@ -4,10 +4,12 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Instant, SystemTime};
 
+use anyhow::Result;
 use lib::api;
 use parking_lot::Mutex;
 use tracing::Subscriber;
 use tracing_subscriber::fmt::format::{PrettyVisitor, Writer};
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
 use crate::system::{self, Event};
 
@@ -20,7 +22,10 @@ thread_local! {
     static THREAD_INDEX_THREAD: Cell<Option<usize>> = const { Cell::new(None) };
 }
 
-pub fn new(system_events: system::SystemEvents) -> (Layer, Capture) {
+/// Handle to the filter of what is logged, which can be changed at runtime.
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+pub fn new(system_events: system::SystemEvents, filter: FilterHandle) -> (Layer, Capture) {
     let threads = num_cpus::get();
     let mut log = Vec::with_capacity(threads);
 
@@ -40,7 +45,16 @@ pub fn new(system_events: system::SystemEvents) -> (Layer, Capture) {
         system_events,
     };
 
-    let capturing = Capture { inner };
+    let default_filter = filter
+        .with_current(|filter| filter.to_string())
+        .unwrap_or_default();
+
+    let capturing = Capture {
+        inner,
+        filter,
+        default_filter: default_filter.into(),
+    };
+
     (layer, capturing)
 }
 
@@ -130,6 +144,9 @@ where
 #[derive(Clone)]
 pub struct Capture {
     inner: Arc<Inner>,
+    filter: FilterHandle,
+    /// The filter the process was started with.
+    default_filter: Arc<str>,
 }
 
 impl Capture {
@@ -145,6 +162,32 @@ impl Capture {
         output.sort_by_key(|entry| entry.timestamp);
         output
     }
+
+    /// Get the directives of the filter in use, like `jpv=info`.
+    pub(crate) fn filter(&self) -> Result<String> {
+        Ok(self.filter.with_current(|filter| filter.to_string())?)
+    }
+
+    /// Filter what is logged with the directives in the configuration, unless
+    /// the filter is set through the `RUST_LOG` environment variable which
+    /// takes precedence.
+    pub(crate) fn set_config_filter(&self, directives: Option<&str>) -> Result<()> {
+        if std::env::var_os("RUST_LOG").is_some() {
+            return Ok(());
+        }
+
+        self.set_filter(directives)
+    }
+
+    /// Filter what is logged with the given directives, like
+    /// `jpv=info,jpv::ocr=debug`, or with the filter the process was started
+    /// with if `None`.
+    pub(crate) fn set_filter(&self, directives: Option<&str>) -> Result<()> {
+        let directives = directives.unwrap_or(&self.default_filter);
+        let filter = EnvFilter::builder().parse(directives)?;
+        self.filter.reload(filter)?;
+        Ok(())
+    }
 }
 
 fn to_level_string(level: tracing::Level) -> &'static str {
@@ -149,10 +149,69 @@
 //! jpv service --background
 //! ```
 //!
+//! A single dictionary can be rebuilt without re-parsing the others with
+//! `jpv build --only jmnedict`, or by posting to `/api/rebuild?index=jmnedict`
+//! while the service is running.
+//!
+//! Thin clients which can't hold every dictionary at once can use
+//! `jpv build --shards <dir>`, which splits phrases and names by the kana their
+//! reading starts with into files which can be loaded on their own, together
+//! with a `manifest.json` listing them. Start with `--manifest <dir>/manifest.json`
+//! and `--shard ka` to only load some of them, in which case searches list the
+//! shards which could hold more results in `missing-shards`.
+//!
 //! ![Good morning!](https://github.com/udoprog/jpv/blob/main/gfx/splash.png?raw=true)
 //!
 //! <br>
 //!
+//! ### Running headless
+//!
+//! To run `jpv` in a container or on a headless server, use `--no-open-uri` so that
+//! it never tries to launch a browser, and bind to an address which is reachable
+//! from the outside:
+//!
+//! ```text
+//! jpv service --background --no-open-uri --bind 0.0.0.0:44714
+//! ```
+//!
+//! The directories used can be overridden with the `JPV_CONFIG_DIR`,
+//! `JPV_DATA_DIR`, and `JPV_CACHE_DIR` environment variables. The `/healthz`
+//! endpoint responds as soon as the service is up, while `/readyz` only responds
+//! successfully once indexes have been loaded.
+//!
+//! On devices with little memory the first search can stall while indexes are
+//! paged in from disk. Enabling *Read dictionaries into memory after startup*
+//! under *Server* in the settings reads them in the background instead, and
+//! `/readyz` doesn't respond successfully until this is done.
+//!
+//! If `--bind` isn't specified, the address can instead be changed under *Server*
+//! in the settings. The running service then switches over to the new address
+//! and open tabs are redirected to it.
+//!
+//! ### Following subtitles in mpv
+//!
+//! Start mpv with a JSON IPC socket and enter its path under *Media players* in
+//! the settings:
+//!
+//! ```text
+//! mpv --input-ipc-server=/tmp/mpvsocket video.mkv
+//! ```
+//!
+//! The current subtitle line is then sent to the dictionary as it changes, the
+//! same way as captured clipboard text.
+//!
+//! ### Linking to the dictionary
+//!
+//! Other applications can link to searches with `jpv://` URLs, which open the
+//! dictionary at the given query and start the service if it isn't running:
+//!
+//! ```text
+//! jpv://search?q=%E9%A3%9F%E3%81%B9%E3%82%8B
+//! ```
+//!
+//! The handler is registered by the Linux packages and the Windows installer. It
+//! can also be invoked directly with `jpv open <url>`.
+//!
 //! ## Features
 //!
 //! For rust features, we have the following:
@@ -176,6 +235,20 @@
 //! more notable features are:
 //!
 //! * `jpv cli <query>` can be used to perform commandline queries.
+//! * `jpv drill --form past --form negative` prints a worksheet of conjugation
+//!   drills for common verbs, followed by an answer key.
+//! * `jpv digest --week` lists the words you've looked up this week, with the
+//!   ones looked up the most first, so that you can review them.
+//! * `jpv gloss file.txt --out glossed.html` renders a text file as a standalone
+//!   HTML page with furigana, where meanings are shown when hovering over words.
+//! * `jpv export jsonl --out dir/` writes every phrase, kanji and name as
+//!   line-delimited JSON, together with the index each entry comes from.
+//! * `jpv ocr image.png --lang jpn_vert --search` recognizes the text in an image
+//!   and searches for the word in the middle of it. The same is available to
+//!   other tools by posting the image as the `image` field of a multipart form
+//!   to `/api/ocr`.
+//! * `jpv sources list` shows the downloaded and latest remote version of each
+//!   dictionary, and `jpv sources update` downloads and rebuilds them.
 //! * `jpv send-clipboard --type text/plain hello` can be used to inject a phrase
 //!   into the dictionary for analysis (requires the `dbus` feature).
 //!
@@ -252,21 +325,33 @@
 #![cfg_attr(all(not(feature = "cli"), windows), windows_subsystem = "windows")]
 
 mod background;
+mod checkpoint;
 mod command;
 mod dbus;
+mod digest;
 mod hash;
 mod log;
+mod maintenance;
+mod mpv;
+mod ocr;
 mod open_uri;
 mod reporter;
 mod system;
 mod tasks;
+mod warmup;
 mod web;
+mod webhooks;
 mod windows;
+mod zip;
 
 #[allow(unused)]
 static VERSION: &str = include_str!(concat!(env!("OUT_DIR"), "/version.txt"));
 #[allow(unused)]
 static USER_AGENT: &str = include_str!(concat!(env!("OUT_DIR"), "/user_agent.txt"));
+/// The git revision the binary was built from.
+static REV: &str = include_str!(concat!(env!("OUT_DIR"), "/rev.txt"));
+/// When the binary was built, in seconds since the Unix epoch.
+const BUILT: u64 = include!(concat!(env!("OUT_DIR"), "/built.txt"));
 
 use std::path::PathBuf;
 
@@ -274,7 +359,11 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use clap::Subcommand;
 use lib::config::Config;
+use lib::data;
+use lib::database::Database;
 use lib::Dirs;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
@@ -288,6 +377,22 @@ enum Command {
     SendClipboard(command::send_clipboard::SendClipboardArgs),
     /// Build the dictionary database. This must be performed before the cli or service can be used.
     Build(command::build::BuildArgs),
+    /// Report disk usage, verify indexes and remove files which are no longer needed.
+    Maintenance(command::maintenance::MaintenanceArgs),
+    /// Generate a worksheet of conjugation drills.
+    Drill(command::drill::DrillArgs),
+    /// Print a digest of the words looked up yesterday or this week, to review them.
+    Digest(command::digest::DigestArgs),
+    /// Gloss a text file with furigana and meanings, such as a standalone HTML reading handout.
+    Gloss(command::gloss::GlossArgs),
+    /// Export the whole dictionary database, such as to line-delimited JSON.
+    Export(command::export::ExportArgs),
+    /// Recognize Japanese text in an image file, optionally searching for the word in it.
+    Ocr(command::ocr::OcrArgs),
+    /// List dictionary sources with their versions, or update them.
+    Sources(command::sources::SourcesArgs),
+    /// Open a `jpv://` URL, such as `jpv://search?q=食べる`, starting the service if needed.
+    Open(command::open::OpenArgs),
 }
 
 #[derive(Parser)]
@@ -295,20 +400,66 @@ struct Args {
     /// Specify paths to indexes to use.
     #[arg(long, value_name = "index")]
     index: Vec<PathBuf>,
+    /// Load indexes from the shards listed in a manifest, as built by `jpv
+    /// build --shards`.
+    #[arg(long, value_name = "path")]
+    manifest: Option<PathBuf>,
+    /// Only load the given shards of phrases and names from the manifest, like
+    /// `ka`. Can be specified multiple times, by default every shard is loaded.
+    #[arg(long, value_name = "shard", requires = "manifest")]
+    shard: Vec<String>,
     /// Command to run, by default this runs the service.
     #[command(subcommand)]
     command: Option<Command>,
 }
 
+impl Args {
+    /// Get the indexes to open.
+    fn indexes(&self) -> Indexes {
+        Indexes {
+            index: self.index.clone(),
+            manifest: self.manifest.clone(),
+            shard: self.shard.clone(),
+        }
+    }
+}
+
+/// The indexes to open, which are kept so that the database can be re-opened.
+#[derive(Clone)]
+struct Indexes {
+    index: Vec<PathBuf>,
+    manifest: Option<PathBuf>,
+    shard: Vec<String>,
+}
+
+impl Indexes {
+    /// Open the database, only loading the selected shards if a manifest is
+    /// specified.
+    fn open(&self, dirs: &Dirs, config: &Config) -> Result<Database> {
+        let Some(manifest) = &self.manifest else {
+            let indexes = data::open_from_args(&self.index[..], dirs)?;
+            return Database::open(indexes, config);
+        };
+
+        let (manifest, indexes) = data::open_shards(manifest, &self.shard[..])?;
+        Database::open_partial(indexes, config, &manifest)
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    use tracing_subscriber::Layer;
-
     let args = Args::try_parse()?;
 
     let directive = match &args.command {
         // Logging is not desired for CLI tool by default.
-        Some(Command::Cli(..)) => None,
+        Some(
+            Command::Cli(..)
+            | Command::Drill(..)
+            | Command::Digest(..)
+            | Command::Gloss(..)
+            | Command::Export(..)
+            | Command::Ocr(..),
+        ) => None,
         _ => Some("jpv=info"),
     };
 
@@ -320,15 +471,18 @@ async fn main() -> Result<()> {
 
     let filter = filter.from_env_lossy();
 
+    // The filter can be changed at runtime through the service.
+    let (filter, filter_handle) = reload::Layer::new(filter);
+
     let system_events = system::SystemEvents::new();
 
-    let (capturing_layer, log) = log::new(system_events.clone());
+    let (capturing_layer, log) = log::new(system_events.clone(), filter_handle);
 
-    let layer = tracing_subscriber::Registry::default();
-    let layer = tracing_subscriber::fmt::layer().with_subscriber(layer);
-    let layer = capturing_layer.with_subscriber(layer);
-    let layer = filter.with_subscriber(layer);
-    layer.try_init()?;
+    tracing_subscriber::Registry::default()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(capturing_layer)
+        .try_init()?;
 
     let dirs = Dirs::open()?;
 
@@ -353,6 +507,30 @@ async fn main() -> Result<()> {
         Some(Command::Build(build_args)) => {
             self::command::build::run(&args, build_args, &dirs, config).await?;
         }
+        Some(Command::Maintenance(maintenance_args)) => {
+            self::command::maintenance::run(&args, maintenance_args, &dirs, config).await?;
+        }
+        Some(Command::Drill(drill_args)) => {
+            self::command::drill::run(&args, drill_args, &dirs, config).await?;
+        }
+        Some(Command::Digest(digest_args)) => {
+            self::command::digest::run(&args, digest_args, &dirs, config).await?;
+        }
+        Some(Command::Gloss(gloss_args)) => {
+            self::command::gloss::run(&args, gloss_args, &dirs, config).await?;
+        }
+        Some(Command::Export(export_args)) => {
+            self::command::export::run(&args, export_args, &dirs, config).await?;
+        }
+        Some(Command::Ocr(ocr_args)) => {
+            self::command::ocr::run(&args, ocr_args, &dirs, config).await?;
+        }
+        Some(Command::Sources(sources_args)) => {
+            self::command::sources::run(&args, sources_args, &dirs, config).await?;
+        }
+        Some(Command::Open(open_args)) => {
+            self::command::open::run(&args, open_args, dirs, config, system_events, log).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,232 @@
+//! Maintenance of the data and cache directories.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use lib::api::{Artifact, ArtifactKind, ArtifactStatus, MaintenanceResponse};
+use lib::config::Config;
+use lib::database::{Index, IndexOpenError};
+use lib::locale::Locale;
+use lib::Dirs;
+
+/// Scan the data and cache directories, verifying indexes and removing
+/// artifacts which are no longer needed unless `dry_run` is set.
+///
+/// Only files which are recognized as indexes and downloads are ever removed,
+/// anything else is left alone since it might hold user data. Sizes are
+/// formatted for the given `locale`.
+pub(crate) fn run(
+    dirs: &Dirs,
+    config: &Config,
+    dry_run: bool,
+    locale: Locale,
+) -> Result<MaintenanceResponse> {
+    let mut artifacts = Vec::new();
+
+    let mut downloads = HashMap::new();
+
+    for (id, index) in &config.indexes {
+        if let Some(path) = crate::background::download_path(dirs, &index.url) {
+            downloads.insert(path, id.as_str());
+        }
+    }
+
+    let stale = dirs.stale_indexes()?;
+
+    for (path, size) in files(dirs.data_dir())? {
+        let (kind, status) = data_status(config, &stale, &path);
+        artifacts.push(artifact(kind, path, size, status, dry_run, locale)?);
+    }
+
+    for (path, size) in files(dirs.cache_root())? {
+        let (kind, status) = cache_status(config, &downloads, &path);
+        artifacts.push(artifact(kind, path, size, status, dry_run, locale)?);
+    }
+
+    Ok(MaintenanceResponse { dry_run, artifacts })
+}
+
+/// Get the status of a file in the data directory.
+fn data_status(config: &Config, stale: &[PathBuf], path: &Path) -> (ArtifactKind, ArtifactStatus) {
+    match Dirs::index_name(path) {
+        Some((id, _)) => {
+            let status = if config.is_installing(id) {
+                ArtifactStatus::Installing
+            } else if !config.indexes.contains_key(id) {
+                ArtifactStatus::Orphaned
+            } else if stale.iter().any(|p| p == path) {
+                ArtifactStatus::Stale
+            } else {
+                verify(path)
+            };
+
+            (ArtifactKind::Index, status)
+        }
+        None => match tempfile_index(path) {
+            Some(id) if config.is_installing(id) => {
+                (ArtifactKind::Other, ArtifactStatus::Installing)
+            }
+            Some(..) => (ArtifactKind::Other, ArtifactStatus::Orphaned),
+            None => (ArtifactKind::Other, ArtifactStatus::Unknown),
+        },
+    }
+}
+
+/// Get the status of a file in the cache directory.
+fn cache_status(
+    config: &Config,
+    downloads: &HashMap<PathBuf, &str>,
+    path: &Path,
+) -> (ArtifactKind, ArtifactStatus) {
+    match downloads.get(path) {
+        Some(id) if config.is_installing(id) => {
+            (ArtifactKind::Download, ArtifactStatus::Installing)
+        }
+        Some(..) => (ArtifactKind::Download, ArtifactStatus::Valid),
+        None if is_download(path) => (ArtifactKind::Download, ArtifactStatus::Orphaned),
+        None => (ArtifactKind::Other, ArtifactStatus::Unknown),
+    }
+}
+
+/// Get the id of the index a temporary index file at the given path is
+/// written for.
+fn tempfile_index(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+
+    if !name.ends_with(".index.tmp") {
+        return None;
+    }
+
+    let (id, _) = name.split_once('.')?;
+    Some(id)
+}
+
+/// Test if the path is named like a download, which is the hash of the url it
+/// was downloaded from followed by the name of the file, see
+/// [`crate::background::download_path`].
+fn is_download(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+        return false;
+    };
+
+    let Some((hash, rest)) = name.split_once('-') else {
+        return false;
+    };
+
+    hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()) && !rest.is_empty()
+}
+
+/// Verify that the index at the given path can be opened.
+fn verify(path: &Path) -> ArtifactStatus {
+    let data = match lib::data::open(path) {
+        Ok(data) => data,
+        Err(error) => {
+            tracing::warn!("{}: {error}", path.display());
+            return ArtifactStatus::Invalid;
+        }
+    };
+
+    match Index::open(data) {
+        Ok(..) => ArtifactStatus::Valid,
+        Err(IndexOpenError::Outdated) => ArtifactStatus::Outdated,
+        Err(error) => {
+            tracing::warn!("{}: {error}", path.display());
+            ArtifactStatus::Invalid
+        }
+    }
+}
+
+fn artifact(
+    kind: ArtifactKind,
+    path: PathBuf,
+    size: u64,
+    status: ArtifactStatus,
+    dry_run: bool,
+    locale: Locale,
+) -> Result<Artifact> {
+    let removed = if !dry_run && status.is_removable() {
+        fs::remove_file(&path).with_context(|| path.display().to_string())?;
+        tracing::info!("Removed {}", path.display());
+        true
+    } else {
+        false
+    };
+
+    Ok(Artifact {
+        kind,
+        path: path.display().to_string(),
+        size,
+        size_text: locale.size(size),
+        status,
+        removed,
+    })
+}
+
+/// List regular files in the given directory together with their size.
+///
+/// Symbolic links and directories are never included, so that nothing outside
+/// of the directory is touched.
+fn files(dir: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut output = Vec::new();
+
+    let d = match fs::read_dir(dir) {
+        Ok(d) => d,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(output);
+        }
+        Err(e) => {
+            return Err(e).with_context(|| dir.display().to_string());
+        }
+    };
+
+    for e in d {
+        let e = e?;
+        let path = e.path();
+        let metadata = fs::symlink_metadata(&path)?;
+
+        if metadata.is_file() {
+            output.push((path, metadata.len()));
+        }
+    }
+
+    output.sort();
+    Ok(output)
+}
+
+#[test]
+fn test_keep_unknown_files() {
+    let config = Config::default();
+    let downloads = HashMap::from([(PathBuf::from("cache/0123abcd-jmdict.gz"), "jmdict")]);
+
+    let data = |path: &str| data_status(&config, &[], Path::new(path));
+    let cache = |path: &str| cache_status(&config, &downloads, Path::new(path));
+
+    assert_eq!(
+        data("data/history.json"),
+        (ArtifactKind::Other, ArtifactStatus::Unknown)
+    );
+    assert_eq!(
+        data("data/unknown.index"),
+        (ArtifactKind::Index, ArtifactStatus::Orphaned)
+    );
+    assert_eq!(
+        data("data/unknown.1234.index.tmp"),
+        (ArtifactKind::Other, ArtifactStatus::Orphaned)
+    );
+    assert_eq!(
+        cache("cache/0123abcd-jmdict.gz"),
+        (ArtifactKind::Download, ArtifactStatus::Valid)
+    );
+    assert_eq!(
+        cache("cache/89abcdef-old.xml.gz"),
+        (ArtifactKind::Download, ArtifactStatus::Orphaned)
+    );
+    assert_eq!(
+        cache("cache/notes.txt"),
+        (ArtifactKind::Other, ArtifactStatus::Unknown)
+    );
+    assert!(!ArtifactStatus::Unknown.is_removable());
+}
@@ -0,0 +1,126 @@
+//! Integration with mpv through its [JSON IPC] socket.
+//!
+//! The current subtitle line is followed and sent to clients in the same way
+//! as captured clipboard text.
+//!
+//! [JSON IPC]: https://mpv.io/manual/stable/#json-ipc
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::time::Interval;
+
+use crate::background::Background;
+use crate::system;
+
+/// How often the configuration is checked and connections are retried.
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// Command sent to observe the subtitle text.
+const OBSERVE: &[u8] = b"{\"command\":[\"observe_property_string\",1,\"sub-text\"]}\n";
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    event: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    data: Option<String>,
+}
+
+/// Follow the subtitles of the mpv instance listening on the configured socket.
+///
+/// This runs until the service shuts down, reconnecting whenever the player
+/// goes away or the configured socket changes.
+pub(crate) async fn run(background: Background, system_events: system::SystemEvents) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let Some(path) = background.config().await.mpv else {
+            continue;
+        };
+
+        let stream = match connect(&path).await {
+            Ok(stream) => stream,
+            Err(error) => {
+                tracing::trace!("Failed to connect to mpv at {path}: {error}");
+                continue;
+            }
+        };
+
+        tracing::info!("Connected to mpv at {path}");
+
+        match follow(&background, &system_events, &path, stream, &mut interval).await {
+            Ok(()) => tracing::info!("Disconnected from mpv at {path}"),
+            Err(error) => tracing::warn!("Connection to mpv at {path} failed: {error:#}"),
+        }
+    }
+}
+
+async fn follow<S>(
+    background: &Background,
+    system_events: &system::SystemEvents,
+    path: &str,
+    stream: S,
+    interval: &mut Interval,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    writer.write_all(OBSERVE).await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let mut last = String::new();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+
+                let message: Message = serde_json::from_str(&line).context("Decoding message")?;
+
+                if message.event.as_deref() != Some("property-change")
+                    || message.name.as_deref() != Some("sub-text")
+                {
+                    continue;
+                }
+
+                let Some(text) = message.data else {
+                    continue;
+                };
+
+                let text = text.trim();
+
+                if text.is_empty() || text == last {
+                    continue;
+                }
+
+                last = text.to_owned();
+                system_events.send(system::Event::SendText(last.clone()));
+            }
+            _ = interval.tick() => {
+                if background.config().await.mpv.as_deref() != Some(path) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn connect(path: &str) -> Result<tokio::net::UnixStream> {
+    Ok(tokio::net::UnixStream::connect(path).await?)
+}
+
+#[cfg(windows)]
+async fn connect(path: &str) -> Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    Ok(tokio::net::windows::named_pipe::ClientOptions::new().open(path)?)
+}
@@ -0,0 +1,492 @@
+//! Queue of image recognition jobs for captured images.
+//!
+//! Images arrive as system events, and identical consecutive images are only
+//! recognized once. At most [`MAX_RUNNING`] jobs run at a time, and only the
+//! latest image waits for its turn, so a job which is superseded by a newer
+//! image is cancelled. Clients are told about the state of each job, and
+//! recognized text is sent to them as [`system::Event::SendText`].
+//!
+//! Recognized text is cleaned up before it's sent, and if a word can be found
+//! near the middle of the text clients are asked to analyze from it instead
+//! of searching for the whole text. The same pipeline is used by `jpv ocr`
+//! and `POST /api/ocr` through [`recognize_image`].
+
+use anyhow::Result;
+use image::{DynamicImage, ImageFormat};
+use lib::database::Database;
+use lib::{api, kana, Weight};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinSet;
+
+use crate::background::Background;
+use crate::system;
+
+/// The tesseract language used for captured images.
+pub(crate) const LANGUAGE: &str = "jpn";
+
+/// The number of jobs which are recognized at the same time. Recognition
+/// uses a single shared handle, so running more jobs would only queue them
+/// on it.
+const MAX_RUNNING: usize = 1;
+
+/// A line which only has kana and is at most this fraction of the length of
+/// the longest line is considered to be furigana.
+const FURIGANA_RATIO: usize = 2;
+
+/// How many characters before the middle of the text the word which is
+/// analyzed may start.
+const MAX_LOOKBEHIND: usize = 8;
+
+/// A captured image.
+enum Frame {
+    /// An encoded image, like a PNG from the clipboard.
+    Encoded { format: ImageFormat, data: Vec<u8> },
+    /// An already decoded image.
+    Image(DynamicImage),
+}
+
+impl Frame {
+    fn hash(&self) -> u64 {
+        match self {
+            Frame::Encoded { data, .. } => crate::hash::hash(data),
+            Frame::Image(image) => {
+                crate::hash::hash((image.width(), image.height(), image.as_bytes()))
+            }
+        }
+    }
+
+    fn decode(self) -> Result<DynamicImage> {
+        match self {
+            Frame::Encoded { format, data } => {
+                tracing::trace!(len = data.len(), "Decoding image");
+                Ok(image::load_from_memory_with_format(&data[..], format)?)
+            }
+            Frame::Image(image) => Ok(image),
+        }
+    }
+}
+
+/// Images waiting to be recognized.
+#[derive(Default)]
+struct Queue {
+    /// The latest image, which is the only one waiting for its turn.
+    queued: Option<(u64, Frame)>,
+    /// The hash of the latest image.
+    last: Option<u64>,
+    next_id: u64,
+}
+
+impl Queue {
+    /// Queue a frame, cancelling the one which was waiting. Frames which are
+    /// identical to the latest one are skipped.
+    fn push(&mut self, system_events: &system::SystemEvents, frame: Frame) {
+        let hash = frame.hash();
+
+        if self.last.replace(hash) == Some(hash) {
+            tracing::trace!("Skipping identical image");
+            return;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        if let Some((id, _)) = self.queued.replace((id, frame)) {
+            report(system_events, id, api::OcrState::Cancelled);
+        }
+
+        report(system_events, id, api::OcrState::Queued);
+    }
+
+    /// Take the frame which is waiting, if any.
+    fn pop(&mut self) -> Option<(u64, Frame)> {
+        self.queued.take()
+    }
+
+    /// Test if a frame is waiting, which supersedes the running ones.
+    fn is_waiting(&self) -> bool {
+        self.queued.is_some()
+    }
+}
+
+/// Recognize text in captured images until the service shuts down.
+pub(crate) async fn run(background: Background, system_events: system::SystemEvents) {
+    let mut receiver = system_events.subscribe();
+    let mut running = JoinSet::new();
+    let mut queue = Queue::default();
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let frame = match event {
+                    Ok(system::Event::SendClipboardData(clipboard)) => {
+                        let Some(format) = image_format(&clipboard.mimetype) else {
+                            continue;
+                        };
+
+                        Frame::Encoded {
+                            format,
+                            data: clipboard.data,
+                        }
+                    }
+                    Ok(system::Event::SendDynamicImage(image)) => Frame::Image(image),
+                    Ok(..) => continue,
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("Image recognition missed {n} event(s)");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if background.tesseract().is_none() {
+                    continue;
+                }
+
+                queue.push(&system_events, frame);
+            }
+            Some(result) = running.join_next(), if !running.is_empty() => {
+                if let Ok((id, result)) = result {
+                    finish(&system_events, id, result, queue.is_waiting());
+                }
+            }
+        }
+
+        while running.len() < MAX_RUNNING {
+            let Some((id, frame)) = queue.pop() else {
+                break;
+            };
+
+            report(&system_events, id, api::OcrState::Recognizing);
+
+            let background = background.clone();
+
+            running.spawn(async move {
+                let database = background.database().await;
+
+                let result =
+                    tokio::task::spawn_blocking(move || recognize(&background, &database, frame))
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result);
+
+                (id, result)
+            });
+        }
+    }
+}
+
+/// Report the result of a job, which is discarded if it has been superseded
+/// by a newer image.
+fn finish(
+    system_events: &system::SystemEvents,
+    id: u64,
+    result: Result<Option<Recognized>>,
+    superseded: bool,
+) {
+    if superseded {
+        report(system_events, id, api::OcrState::Cancelled);
+        return;
+    }
+
+    match result {
+        Ok(recognized) => {
+            report(system_events, id, api::OcrState::Done);
+
+            if let Some(recognized) = recognized {
+                send(system_events, recognized);
+            }
+        }
+        Err(error) => {
+            tracing::warn!("Image recognition failed: {error}");
+            report(system_events, id, api::OcrState::Failed);
+        }
+    }
+}
+
+/// Send recognized text to clients, asking them to analyze it from the
+/// target word if there is one.
+fn send(system_events: &system::SystemEvents, recognized: Recognized) {
+    let Some(analyze_at) = recognized.target else {
+        system_events.send(system::Event::SendText(recognized.text));
+        return;
+    };
+
+    let json = api::SendClipboardJson {
+        primary: recognized.text,
+        secondary: None,
+        analyze_at: Some(analyze_at),
+    };
+
+    match serde_json::to_vec(&json) {
+        Ok(data) => {
+            system_events.send(system::Event::SendClipboardData(
+                system::SendClipboardData {
+                    mimetype: String::from("application/json"),
+                    data,
+                },
+            ));
+        }
+        Err(error) => {
+            tracing::warn!("Failed to encode recognized text: {error}");
+        }
+    }
+}
+
+fn report(system_events: &system::SystemEvents, id: u64, state: api::OcrState) {
+    system_events.send(system::Event::OcrJob(api::OcrJob { id, state }));
+}
+
+pub(crate) fn image_format(mimetype: &str) -> Option<ImageFormat> {
+    Some(match mimetype {
+        "image/png" => ImageFormat::Png,
+        "image/tiff" => ImageFormat::Tiff,
+        "image/webp" => ImageFormat::WebP,
+        "image/jpeg" | "image/jpg" => ImageFormat::Jpeg,
+        _ => return None,
+    })
+}
+
+/// Text recognized in an image.
+pub(crate) struct Recognized {
+    /// The cleaned up text.
+    pub(crate) text: String,
+    /// Byte offset of the word in the text which is most likely to be the
+    /// one being looked up.
+    pub(crate) target: Option<usize>,
+}
+
+impl Recognized {
+    /// The most probable word starting at the target, if there is one.
+    pub(crate) fn word(&self, database: &Database) -> Result<Option<&str>> {
+        let Some(start) = self.target else {
+            return Ok(None);
+        };
+
+        let analysis = database.analyze(&self.text, start)?;
+        Ok(analysis.into_values().next())
+    }
+}
+
+/// Recognize the text in a frame, returning `None` if there was none.
+fn recognize(
+    background: &Background,
+    database: &Database,
+    frame: Frame,
+) -> Result<Option<Recognized>> {
+    let Some(tesseract) = background.tesseract() else {
+        return Ok(None);
+    };
+
+    let image = frame.decode()?;
+    recognize_image(&mut tesseract.blocking_lock(), database, &image)
+}
+
+/// Recognize the text in an image, returning `None` if there was none.
+pub(crate) fn recognize_image(
+    tesseract: &mut tesseract::Tesseract,
+    database: &Database,
+    image: &DynamicImage,
+) -> Result<Option<Recognized>> {
+    let data = image.as_bytes();
+    let width = usize::try_from(image.width())?;
+    let height = usize::try_from(image.height())?;
+    let bytes_per_pixel = usize::from(image.color().bytes_per_pixel());
+
+    tracing::trace!(len = data.len(), width, height, bytes_per_pixel);
+
+    let text = tesseract.image_to_text(data, width, height, bytes_per_pixel)?;
+
+    let cleaned = clean(&text[..]);
+
+    tracing::trace!(text = &text[..], ?cleaned, "Recognized");
+
+    if cleaned.is_empty() {
+        return Ok(None);
+    }
+
+    let target = target_word(database, &cleaned)?;
+
+    Ok(Some(Recognized {
+        text: cleaned,
+        target,
+    }))
+}
+
+/// Clean up recognized text.
+///
+/// Lines are merged since recognition breaks lines in the middle of words,
+/// lines which look like furigana are dropped, and if the text is mostly
+/// Japanese any ASCII noise except for digits is removed.
+fn clean(text: &str) -> String {
+    let lines = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<String>())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    let longest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    let any_kanji = lines.iter().any(|line| {
+        line.chars()
+            .any(|c| kana::is_japanese(c) && kana::is_kanji(c))
+    });
+
+    let mut output = String::new();
+
+    for line in &lines {
+        if any_kanji && is_furigana(line, longest) {
+            continue;
+        }
+
+        output.push_str(line);
+    }
+
+    if kana::japanese_percentage(&output) >= 50 {
+        output.retain(|c| !c.is_ascii() || c.is_ascii_digit());
+    }
+
+    output
+}
+
+fn is_furigana(line: &str, longest: usize) -> bool {
+    let len = line.chars().count();
+
+    len * FURIGANA_RATIO <= longest
+        && line
+            .chars()
+            .all(|c| kana::is_hiragana(c) || kana::is_katakana(c))
+}
+
+/// Find the start of the most probable word which covers the middle of the
+/// text, using the same analysis as when clicking on text.
+pub(crate) fn target_word(database: &Database, text: &str) -> Result<Option<usize>> {
+    let starts = text.char_indices().map(|(n, _)| n).collect::<Vec<_>>();
+
+    let Some(&middle) = starts.get(starts.len() / 2) else {
+        return Ok(None);
+    };
+
+    let from = (starts.len() / 2).saturating_sub(MAX_LOOKBEHIND);
+    let mut best = None::<(Weight, usize)>;
+
+    for &start in &starts[from..=starts.len() / 2] {
+        let analysis = database.analyze(text, start)?;
+
+        let Some((&weight, word)) = analysis.iter().next() else {
+            continue;
+        };
+
+        if start + word.len() <= middle {
+            continue;
+        }
+
+        // Weights are ordered with the heaviest first.
+        let better = match best {
+            Some((best, _)) => weight < best,
+            None => true,
+        };
+
+        if better {
+            best = Some((weight, start));
+        }
+    }
+
+    Ok(best.map(|(_, start)| start))
+}
+
+/// Collect the states reported for image recognition jobs so far.
+#[cfg(test)]
+fn ocr_jobs(
+    receiver: &mut tokio::sync::broadcast::Receiver<system::Event>,
+) -> Vec<(u64, api::OcrState)> {
+    let mut jobs = Vec::new();
+
+    while let Ok(event) = receiver.try_recv() {
+        if let system::Event::OcrJob(job) = event {
+            jobs.push((job.id, job.state));
+        }
+    }
+
+    jobs
+}
+
+#[test]
+fn test_queue() {
+    use api::OcrState::*;
+
+    let system_events = system::SystemEvents::new();
+    let mut receiver = system_events.subscribe();
+    let mut queue = Queue::default();
+
+    let frame =
+        |color: u8| Frame::Image(image::GrayImage::from_pixel(2, 2, image::Luma([color])).into());
+
+    queue.push(&system_events, frame(0));
+    // Identical consecutive images are only queued once.
+    queue.push(&system_events, frame(0));
+    assert_eq!(ocr_jobs(&mut receiver), [(0, Queued)]);
+
+    // A newer image replaces the one which is waiting.
+    queue.push(&system_events, frame(1));
+    assert_eq!(ocr_jobs(&mut receiver), [(0, Cancelled), (1, Queued)]);
+
+    let (id, _) = queue.pop().unwrap();
+    assert_eq!(id, 1);
+    assert!(!queue.is_waiting());
+    assert!(queue.pop().is_none());
+
+    // An image which is the same as an earlier but not the latest one is
+    // recognized again.
+    queue.push(&system_events, frame(0));
+    queue.push(&system_events, frame(1));
+    assert_eq!(
+        ocr_jobs(&mut receiver),
+        [(2, Queued), (2, Cancelled), (3, Queued)]
+    );
+}
+
+#[test]
+fn test_finish() {
+    use api::OcrState::*;
+
+    let system_events = system::SystemEvents::new();
+    let mut receiver = system_events.subscribe();
+
+    let recognized = || Recognized {
+        text: String::from("猫"),
+        target: None,
+    };
+
+    // Results of jobs which were superseded by a newer image are discarded.
+    finish(&system_events, 0, Ok(Some(recognized())), true);
+    assert_eq!(ocr_jobs(&mut receiver), [(0, Cancelled)]);
+
+    finish(&system_events, 1, Ok(Some(recognized())), false);
+    assert!(matches!(
+        receiver.try_recv(),
+        Ok(system::Event::OcrJob(api::OcrJob { id: 1, state: Done }))
+    ));
+    assert!(matches!(receiver.try_recv(), Ok(system::Event::SendText(text)) if text == "猫"));
+
+    finish(&system_events, 2, Err(anyhow::anyhow!("broken")), false);
+    assert_eq!(ocr_jobs(&mut receiver), [(2, Failed)]);
+}
+
+#[test]
+fn test_clean() {
+    // Lines are merged and furigana over kanji is dropped.
+    assert_eq!(
+        clean("たいせつ\n大切な話を しましょう\nそうしましょう"),
+        "大切な話をしましょうそうしましょう"
+    );
+    // Without kanji short kana lines are kept.
+    assert_eq!(clean("ねこ\nがすきです"), "ねこがすきです");
+    // ASCII noise is removed from Japanese text, except for digits.
+    assert_eq!(clean("|今日は 3 時に.\n会う'"), "今日は3時に会う");
+    // But kept in text which is mostly ASCII.
+    assert_eq!(clean("Hello, 世界"), "Hello,世界");
+    assert_eq!(clean(" \n\n"), "");
+}
@@ -1,6 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set if opening URIs has been disabled.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Disable opening URIs for the remainder of the process.
+pub(crate) fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Open the given URI, unless opening URIs has been disabled.
+pub(crate) fn open(uri: &str) {
+    if DISABLED.load(Ordering::Relaxed) {
+        tracing::info!("Not opening {uri} since opening URIs is disabled");
+        return;
+    }
+
+    r#impl(uri);
+}
+
 // Use a better method for launching URIs on GIO-enabled platforms like GNOME.
 #[cfg(all(unix, feature = "gio"))]
-pub(crate) fn open(uri: &str) {
+fn r#impl(uri: &str) {
     let _ = gio::AppInfo::launch_default_for_uri(uri, gio::AppLaunchContext::NONE);
 }
 
@@ -10,6 +30,6 @@ pub(crate) fn open(uri: &str) {
 /// because it spawns the browser as a child process to the current one which
 /// does not detach itself.
 #[cfg(not(all(unix, feature = "gio")))]
-pub(crate) fn open(uri: &str) {
+fn r#impl(uri: &str) {
     let _ = webbrowser::open(uri);
 }
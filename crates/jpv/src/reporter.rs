@@ -1,7 +1,8 @@
 use std::fmt;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use lib::reporter::Reporter;
+use lib::reporter::{format_seconds, Level, Reporter, Tracker};
 
 use crate::background::BackgroundTasks;
 use crate::system::{Event, SystemEvents};
@@ -14,7 +15,13 @@ pub(crate) struct EventsReporter {
 }
 
 impl Reporter for EventsReporter {
-    fn instrument_start(&self, _: &'static str, text: &dyn fmt::Display, total: Option<usize>) {
+    fn instrument_start(
+        &self,
+        _: &'static str,
+        text: &dyn fmt::Display,
+        total: Option<usize>,
+        weight: u32,
+    ) {
         use std::fmt::Write;
 
         let Some(name) = &self.name else {
@@ -30,8 +37,7 @@ impl Reporter for EventsReporter {
 
             progress.text.clear();
             write!(progress.text, "{}", text).unwrap();
-            progress.value = 0;
-            progress.total = total;
+            progress.tracker.start(total, weight);
             progress.clone()
         };
 
@@ -50,7 +56,7 @@ impl Reporter for EventsReporter {
                 return;
             };
 
-            progress.value = progress.value.wrapping_add(stride);
+            progress.tracker.progress(stride);
             progress.clone()
         };
 
@@ -69,12 +75,117 @@ impl Reporter for EventsReporter {
                 return;
             };
 
-            progress.value = total;
-            progress.total = Some(total);
+            progress.tracker.end(total);
             progress.step += 1;
             progress.clone()
         };
 
         self.system_events.send(Event::TaskProgress(progress));
     }
+
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display) {
+        match level {
+            Level::Info => tracing::info!("{module_path}: {what}"),
+            Level::Warn => tracing::warn!("{module_path}: {what}"),
+        }
+    }
+}
+
+/// How often a [`ConsoleReporter`] prints progress.
+const PRINT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A reporter which prints the steps of a task and their progress to
+/// stderr, used when building from the command line.
+pub(crate) struct ConsoleReporter {
+    name: String,
+    steps: usize,
+    state: Mutex<ConsoleState>,
+}
+
+struct ConsoleState {
+    tracker: Tracker,
+    step: usize,
+    printed: Instant,
+}
+
+impl ConsoleReporter {
+    pub(crate) fn new(name: &str, steps: usize) -> Self {
+        Self {
+            name: name.to_owned(),
+            steps,
+            state: Mutex::new(ConsoleState {
+                tracker: Tracker::new(),
+                step: 0,
+                printed: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn instrument_start(
+        &self,
+        _: &'static str,
+        text: &dyn fmt::Display,
+        total: Option<usize>,
+        weight: u32,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.tracker.start(total, weight);
+        state.printed = Instant::now();
+
+        eprintln!(
+            "{}: [{}/{}] {text}",
+            self.name,
+            (state.step + 1).min(self.steps),
+            self.steps
+        );
+    }
+
+    fn instrument_progress(&self, stride: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.tracker.progress(stride);
+
+        if state.printed.elapsed() < PRINT_INTERVAL {
+            return;
+        }
+
+        state.printed = Instant::now();
+
+        let tracker = &state.tracker;
+        let mut line = format!("{}: {:.1}%", self.name, tracker.done() as f32 / 10.0);
+
+        match tracker.total() {
+            Some(total) => line.push_str(&format!(" ({}/{total}", tracker.value())),
+            None => line.push_str(&format!(" ({}", tracker.value())),
+        }
+
+        if let Some(rate) = tracker.rate() {
+            line.push_str(&format!(", {rate:.0}/s"));
+        }
+
+        line.push(')');
+
+        if let Some(remaining) = tracker.remaining() {
+            line.push_str(&format!(
+                ", about {} left",
+                format_seconds(remaining.as_secs())
+            ));
+        }
+
+        eprintln!("{line}");
+    }
+
+    fn instrument_end(&self, total: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.tracker.end(total);
+        state.step += 1;
+    }
+
+    fn report(&self, module_path: &'static str, level: Level, what: &dyn fmt::Display) {
+        match level {
+            Level::Info => tracing::info!("{module_path}: {what}"),
+            Level::Warn => tracing::warn!("{module_path}: {what}"),
+        }
+    }
 }
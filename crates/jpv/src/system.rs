@@ -1,11 +1,19 @@
+use std::collections::VecDeque;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
+use axum::http::uri::Authority;
 use lib::api;
+use lib::reporter::Tracker;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::{Receiver, Sender};
 use tokio::sync::futures::Notified;
 
+use crate::webhooks;
+
 /// Service startup.
 pub(crate) trait Start {
     fn start<'a>(
@@ -33,8 +41,7 @@ pub(crate) struct SendClipboardData {
 #[derive(Clone)]
 pub(crate) struct TaskProgress {
     pub(crate) name: Box<str>,
-    pub(crate) value: usize,
-    pub(crate) total: Option<usize>,
+    pub(crate) tracker: Tracker,
     pub(crate) step: usize,
     pub(crate) steps: usize,
     pub(crate) text: String,
@@ -45,6 +52,41 @@ pub(crate) struct TaskCompleted {
     pub(crate) name: Box<str>,
 }
 
+#[derive(Clone)]
+#[allow(unused)]
+pub(crate) struct Notification {
+    pub(crate) summary: String,
+    pub(crate) body: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct Rebind {
+    /// The port integrations should open the service on.
+    pub(crate) port: u16,
+    /// The address the web server is now bound to.
+    pub(crate) addr: SocketAddr,
+}
+
+impl Rebind {
+    /// The URL a client which reached the service through the given `Host`
+    /// should redirect to.
+    ///
+    /// A server bound to every interface can still be reached through the
+    /// host the client is using, otherwise the client has to move to the
+    /// address the server is bound to.
+    pub(crate) fn url(&self, host: Option<&str>) -> String {
+        let host = host
+            .and_then(|host| host.parse::<Authority>().ok())
+            .filter(|_| self.addr.ip().is_unspecified());
+
+        match host {
+            Some(authority) => format!("http://{}:{}", authority.host(), self.port),
+            None if self.addr.ip().is_unspecified() => format!("http://localhost:{}", self.port),
+            None => format!("http://{}", SocketAddr::new(self.addr.ip(), self.port)),
+        }
+    }
+}
+
 #[derive(Clone)]
 #[allow(unused)]
 pub(crate) enum Event {
@@ -61,22 +103,277 @@ pub(crate) enum Event {
     TaskCompleted(TaskCompleted),
     /// Indicate that clients should refresh their state.
     Refresh,
+    /// Show a desktop notification.
+    Notify(Notification),
+    /// Indicate that the web server has moved to a new address.
+    Rebind(Rebind),
+    /// Indicate that indexes have been enabled or disabled.
+    IndexesChanged(api::OwnedIndexesChanged),
+    /// Report the state of an image recognition job.
+    OcrJob(api::OcrJob),
+    /// A search was performed, which is posted to webhooks.
+    Searched(webhooks::Search),
+}
+
+impl Event {
+    /// Test if the event is replayed to clients which reconnect.
+    ///
+    /// Clipboard contents and text to analyze are not, since they would
+    /// replace whatever the user has been doing since.
+    fn is_replayed(&self) -> bool {
+        matches!(
+            self,
+            Event::LogEntry(..)
+                | Event::TaskProgress(..)
+                | Event::TaskCompleted(..)
+                | Event::Refresh
+                | Event::IndexesChanged(..)
+                | Event::OcrJob(..)
+        )
+    }
+}
+
+/// The number of events kept around for clients which reconnect.
+const HISTORY_CAPACITY: usize = 256;
+
+/// Recently sent events.
+struct History {
+    /// Identifies this instance of the service, so that a client can't resume
+    /// from a sequence number handed out by an earlier one.
+    session: u64,
+    /// The sequence number of the last event which was sent.
+    sequence: u64,
+    /// The sequence number of the last event which was dropped from the
+    /// history. Clients which have seen it can be resumed.
+    evicted: u64,
+    events: VecDeque<(u64, Event)>,
+}
+
+impl History {
+    fn push(&mut self, sequence: u64, event: Event) {
+        // Only the latest progress of a task matters.
+        if let Event::TaskProgress(progress) = &event {
+            self.events
+                .retain(|(_, e)| !matches!(e, Event::TaskProgress(p) if p.name == progress.name));
+        }
+
+        if self.events.len() == HISTORY_CAPACITY {
+            if let Some((sequence, _)) = self.events.pop_front() {
+                self.evicted = sequence;
+            }
+        }
+
+        self.events.push_back((sequence, event));
+    }
 }
 
 #[derive(Clone)]
-pub(crate) struct SystemEvents(Sender<Event>);
+pub(crate) struct SystemEvents {
+    sender: Sender<Event>,
+    history: Arc<Mutex<History>>,
+}
 
 impl SystemEvents {
     pub(crate) fn new() -> Self {
         let (sender, _) = tokio::sync::broadcast::channel(16);
-        Self(sender)
+
+        let history = History {
+            session: rand::random(),
+            sequence: 0,
+            evicted: 0,
+            events: VecDeque::new(),
+        };
+
+        Self {
+            sender,
+            history: Arc::new(Mutex::new(history)),
+        }
     }
 
     pub(crate) fn send(&self, value: Event) {
-        let _ = self.0.send(value);
+        // The lock is held while sending, so that events are numbered in the
+        // order subscribers receive them.
+        let mut history = self.history.lock().unwrap();
+        history.sequence += 1;
+
+        if value.is_replayed() {
+            let sequence = history.sequence;
+            history.push(sequence, value.clone());
+        }
+
+        let _ = self.sender.send(value);
     }
 
     pub(crate) fn subscribe(&self) -> Receiver<Event> {
-        self.0.subscribe()
+        self.sender.subscribe()
     }
+
+    /// Subscribe to events with sequence numbers, resuming from the given
+    /// session and the sequence number of the last event seen in it.
+    ///
+    /// If the session can be resumed, the replayable events which were sent
+    /// after the given sequence number are returned in
+    /// [`Subscription::missed`].
+    pub(crate) fn resume(&self, resume: Option<(u64, u64)>) -> Subscription {
+        let history = self.history.lock().unwrap();
+
+        let missed = match resume {
+            Some((session, sequence))
+                if session == history.session
+                    && sequence >= history.evicted
+                    && sequence <= history.sequence =>
+            {
+                let missed = history
+                    .events
+                    .iter()
+                    .filter(|(s, _)| *s > sequence)
+                    .cloned()
+                    .collect();
+
+                Some(missed)
+            }
+            _ => None,
+        };
+
+        Subscription {
+            session: history.session,
+            sequence: history.sequence,
+            missed,
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// A subscription to events which are numbered in the order they were sent.
+pub(crate) struct Subscription {
+    /// The session the sequence numbers belong to.
+    pub(crate) session: u64,
+    /// The sequence number of the last event received.
+    pub(crate) sequence: u64,
+    /// Events which were missed since the subscription was resumed, or `None`
+    /// if it couldn't be resumed.
+    pub(crate) missed: Option<Vec<(u64, Event)>>,
+    receiver: Receiver<Event>,
+}
+
+impl Subscription {
+    /// Receive the next event with its sequence number.
+    ///
+    /// This is cancellation safe.
+    pub(crate) async fn recv(&mut self) -> Result<(u64, Event), RecvError> {
+        match self.receiver.recv().await {
+            Ok(event) => {
+                self.sequence += 1;
+                Ok((self.sequence, event))
+            }
+            Err(RecvError::Lagged(n)) => {
+                self.sequence += n;
+                Err(RecvError::Lagged(n))
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+fn task_progress(name: &str, step: usize) -> Event {
+    Event::TaskProgress(TaskProgress {
+        name: name.into(),
+        tracker: Tracker::new(),
+        step,
+        steps: 2,
+        text: String::new(),
+    })
+}
+
+#[cfg(test)]
+fn sequences(events: &[(u64, Event)]) -> Vec<u64> {
+    events.iter().map(|(sequence, _)| *sequence).collect()
+}
+
+#[test]
+fn test_history() {
+    let events = SystemEvents::new();
+
+    events.send(task_progress("jmdict", 0));
+    events.send(Event::Refresh);
+    events.send(task_progress("jmdict", 1));
+    events.send(task_progress("kanjidic2", 0));
+    // Not replayed, but still numbered.
+    events.send(Event::SendText(String::from("猫")));
+
+    let history = events.history.lock().unwrap();
+    assert_eq!(history.sequence, 5);
+    // Only the latest progress of each task is kept.
+    let kept = history.events.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+    assert_eq!(kept, [2, 3, 4]);
+    drop(history);
+
+    for _ in 0..HISTORY_CAPACITY {
+        events.send(Event::Refresh);
+    }
+
+    let history = events.history.lock().unwrap();
+    assert_eq!(history.events.len(), HISTORY_CAPACITY);
+    assert_eq!(history.evicted, 4);
+}
+
+#[test]
+fn test_resume() {
+    let events = SystemEvents::new();
+
+    events.send(Event::Refresh);
+    events.send(Event::SendText(String::from("猫")));
+    events.send(task_progress("jmdict", 0));
+
+    let first = events.resume(None);
+    assert_eq!(first.sequence, 3);
+    assert!(first.missed.is_none());
+
+    let resumed = events.resume(Some((first.session, 1)));
+    assert_eq!(sequences(resumed.missed.as_deref().unwrap()), [3]);
+
+    let resumed = events.resume(Some((first.session, 3)));
+    assert_eq!(resumed.missed.as_deref().map(sequences), Some(vec![]));
+
+    // Another session, or a sequence number which wasn't handed out yet,
+    // can't be resumed.
+    assert!(events.resume(Some((first.session ^ 1, 1))).missed.is_none());
+    assert!(events.resume(Some((first.session, 4))).missed.is_none());
+
+    for _ in 0..HISTORY_CAPACITY {
+        events.send(Event::Refresh);
+    }
+
+    // Events which a client that only saw the first one missed have been
+    // evicted.
+    assert!(events.resume(Some((first.session, 1))).missed.is_none());
+    let last = events.resume(Some((first.session, 3 + HISTORY_CAPACITY as u64)));
+    assert_eq!(last.missed.as_deref().map(sequences), Some(vec![]));
+}
+
+#[test]
+fn test_rebind_url() {
+    let rebind = |addr: &str| Rebind {
+        port: 8080,
+        addr: addr.parse().unwrap(),
+    };
+
+    // Clients move to the interface the server is bound to.
+    let specific = rebind("192.168.1.10:8080");
+    assert_eq!(
+        specific.url(Some("localhost:44714")),
+        "http://192.168.1.10:8080"
+    );
+    assert_eq!(rebind("[::1]:8080").url(None), "http://[::1]:8080");
+
+    // Or stay on the host they're using if it's bound to every interface.
+    let any = rebind("0.0.0.0:8080");
+    assert_eq!(
+        any.url(Some("example.com:44714")),
+        "http://example.com:8080"
+    );
+    assert_eq!(any.url(Some("[::1]:44714")), "http://[::1]:8080");
+    assert_eq!(any.url(Some("example.com")), "http://example.com:8080");
+    assert_eq!(any.url(None), "http://localhost:8080");
 }
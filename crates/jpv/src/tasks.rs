@@ -9,12 +9,15 @@ use tokio::sync::{mpsc, oneshot};
 pub(crate) enum TaskName {
     /// Rebuilding the specified database.
     Build(String),
+    /// Reading indexes into memory after startup.
+    Warmup,
 }
 
 impl fmt::Display for TaskName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TaskName::Build(name) => write!(f, "Building {name}"),
+            TaskName::Warmup => write!(f, "Warming up indexes"),
         }
     }
 }
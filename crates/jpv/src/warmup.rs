@@ -0,0 +1,54 @@
+//! Reading indexes into memory in the background after startup.
+
+use std::time::Instant;
+
+use lib::token::Token;
+
+use crate::background::Background;
+use crate::tasks::{TaskName, Tasks};
+
+/// Start reading indexes into memory in the background, if enabled in the
+/// configuration.
+///
+/// The service is reported as not ready by `/readyz` until this has finished.
+pub(crate) async fn start(background: &Background, tasks: &mut Tasks) {
+    if !background.config().await.warmup {
+        return;
+    }
+
+    let Some((shutdown, completion)) = tasks.unique_task(TaskName::Warmup) else {
+        return;
+    };
+
+    background.start_task(&completion, 1);
+    let database = background.database().await;
+
+    tokio::spawn(async move {
+        // Capture the completion handler so that it is dropped with the task.
+        let _completion = completion;
+
+        let token = Token::default();
+        let start = Instant::now();
+
+        let mut task = tokio::task::spawn_blocking({
+            let token = token.clone();
+            move || database.warmup(&token)
+        });
+
+        let result = tokio::select! {
+            result = &mut task => result,
+            _ = shutdown => {
+                token.set();
+                task.await
+            }
+        };
+
+        match result {
+            Ok(keys) => tracing::info!(
+                "Warmed up {keys} lookup keys of indexes in {:?}",
+                start.elapsed()
+            ),
+            Err(error) => tracing::warn!("Failed to warm up indexes: {error}"),
+        }
+    });
+}
@@ -1,10 +1,18 @@
+use axum::async_trait;
+use axum::body::Body;
+use axum::extract::FromRequest;
 use axum::http::header::{self, HeaderValue};
-use axum::http::StatusCode;
+use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
+use lib::api;
+use musli::de::DecodeOwned;
 use musli::mode::Text;
 use musli::Encode;
 use musli_json::Encoding;
+use serde::de::DeserializeOwned;
+
+use super::RequestError;
 
 const ENCODING: Encoding = Encoding::new();
 
@@ -40,3 +48,42 @@ where
         }
     }
 }
+
+/// A JSON request body.
+///
+/// The body uses the same `kebab-case` names as responses if the request sets
+/// [`api::API_VERSION_HEADER`] to `2` or later. Otherwise it uses the
+/// `snake_case` names of version 1, so that existing clients keep working.
+pub(super) struct JsonBody<T>(pub(super) T);
+
+#[async_trait]
+impl<S, T> FromRequest<S, Body> for JsonBody<T>
+where
+    S: Send + Sync,
+    T: DecodeOwned<Text> + DeserializeOwned,
+{
+    type Rejection = RequestError;
+
+    async fn from_request(req: Request<Body>, state: &S) -> Result<Self, Self::Rejection> {
+        let version = req
+            .headers()
+            .get(api::API_VERSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(1);
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|error| RequestError::bad_request(error.body_text()))?;
+
+        let value = if version >= 2 {
+            ENCODING
+                .from_slice(&bytes)
+                .map_err(RequestError::bad_request)?
+        } else {
+            serde_json::from_slice(&bytes).map_err(RequestError::bad_request)?
+        };
+
+        Ok(Self(value))
+    }
+}
@@ -10,33 +10,58 @@ mod ws;
 
 mod json;
 
-use self::json::Json;
+#[cfg(test)]
+mod tests;
+
+use self::json::{Json, JsonBody};
 pub(crate) use self::r#impl::{BIND, PORT};
 
 use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::fmt;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use axum::body::{boxed, Body};
-use axum::extract::{Path, Query};
-use axum::http::StatusCode;
+use axum::extract::{DefaultBodyLimit, Multipart, Path, Query};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Extension, Router};
+use lib::accent::{self, Notation};
 use lib::api;
-use lib::config::Config;
-use musli::Encode;
+use lib::config::{ClientSettings, Config, EmbedLanding};
+use lib::database::{
+    self, Database, EntryResultKey, KanjiConstraints, Key, LinkKind, Search, ShiritoriConstraints,
+    Source, WordSort,
+};
+use lib::history;
+use lib::locale::Locale;
+use lib::phrasebook::{self, Phrase};
+use lib::sheet;
+use lib::{Form, PartOfSpeech};
+use tokio::sync::oneshot;
 use tower_http::cors::{AllowMethods, AllowOrigin, CorsLayer};
 
-use crate::background::{Background, Install};
+use crate::background::{self, Background, Install};
+use crate::ocr;
 use crate::system;
+use crate::webhooks;
 
+/// Set up the web server on the given listener.
+///
+/// The server stops accepting new connections once `shutdown` is signalled or
+/// dropped, and the returned future completes once all in-flight requests have
+/// finished.
 pub(crate) fn setup(
     listener: TcpListener,
     background: Background,
     system_events: system::SystemEvents,
+    shutdown: oneshot::Receiver<()>,
 ) -> Result<impl Future<Output = Result<()>>> {
     let server = match axum::Server::from_tcp(listener) {
         Ok(server) => server,
@@ -54,7 +79,11 @@ pub(crate) fn setup(
         .layer(Extension(system_events))
         .layer(cors);
 
-    let service = server.serve(app.into_make_service_with_connect_info::<SocketAddr>());
+    let service = server
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.await;
+        });
 
     Ok(async move {
         service.await?;
@@ -64,14 +93,88 @@ pub(crate) fn setup(
 
 fn common_routes(router: Router) -> Router {
     router
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
         .route("/api/version", get(version))
         .route("/api/config", get(config).post(update_config))
+        .route("/api/pins", get(pins).post(update_pins))
+        .route("/api/log-filter", get(log_filter).post(update_log_filter))
+        .route("/api/phrasebook", get(phrasebook).post(save_phrase))
+        .route("/api/phrasebook/remove", post(remove_phrase))
+        .route("/api/phrasebook/categories", post(update_category))
+        .route("/api/phrasebook/export", get(export_phrasebook))
+        .route(
+            "/api/client/settings",
+            get(client_settings).put(update_client_settings),
+        )
         .route("/api/rebuild", post(rebuild))
+        .route("/api/indexes/:name/enable", post(enable_index))
+        .route("/api/indexes/:name/disable", post(disable_index))
+        .route("/api/maintenance", post(maintenance))
+        .route("/api/raw-entry", get(raw_entry))
         .route("/api/analyze", get(analyze))
+        .route("/api/segment", get(segment))
         .route("/api/search", get(search))
+        .route("/api/search.html", get(search_html))
+        .route("/api/instant", get(instant))
+        .route("/api/popup", get(popup))
+        .route("/api/normalize", get(normalize))
+        .route("/api/entities", get(entities))
         .route("/api/entry/:sequence", get(entry))
+        .route("/api/entry/:sequence/examples", get(examples))
+        .route("/api/entry/:sequence/related", get(related))
         .route("/api/kanji/:literal", get(kanji))
+        .route("/api/kanji/:literal/card", get(kanji_card))
+        .route("/api/kanji/:literal/words", get(kanji_words))
+        .route("/api/radicals", get(radicals))
+        .route("/api/shiritori", get(shiritori))
+        .route("/api/example-sentences", get(example_sentences))
+        .route("/api/minimal-pairs", get(minimal_pairs))
+        .route("/api/drill", get(drill))
+        .route("/api/typing", get(typing))
+        .route("/api/ruby", get(ruby))
+        .route("/api/gloss", get(gloss))
+        .route("/api/digest", get(digest))
+        .route("/api/embed", get(embed_landing))
+        .route(
+            "/api/ocr",
+            post(recognize).layer(DefaultBodyLimit::max(OCR_MAX_SIZE)),
+        )
         .route("/ws", get(ws::entry))
+        .route_layer(middleware::from_fn(read_only))
+}
+
+/// Requests which are made with a method that usually modifies the service,
+/// but which don't.
+const READ_ONLY_EXEMPT: &[&str] = &["/api/ocr"];
+
+/// Requests which only read, but which show what the user of the service has
+/// looked up or saved.
+const READ_ONLY_PRIVATE: &[&str] = &[
+    "/api/digest",
+    "/api/embed",
+    "/api/phrasebook",
+    "/api/phrasebook/export",
+];
+
+/// Refuse requests which would modify a read-only service.
+async fn read_only(
+    Extension(bg): Extension<Background>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let path = request.uri().path();
+
+    let reads = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) && !READ_ONLY_PRIVATE.contains(&path);
+
+    if reads || READ_ONLY_EXEMPT.contains(&path) || !bg.is_read_only().await {
+        return next.run(request).await;
+    }
+
+    RequestError::forbidden("The service is read-only").into_response()
 }
 
 type RequestResult<T> = std::result::Result<T, RequestError>;
@@ -92,6 +195,26 @@ impl RequestError {
         }
     }
 
+    fn bad_request<M>(msg: M) -> Self
+    where
+        M: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        Self {
+            error: anyhow::Error::msg(msg),
+            status: Some(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    fn forbidden<M>(msg: M) -> Self
+    where
+        M: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        Self {
+            error: anyhow::Error::msg(msg),
+            status: Some(StatusCode::FORBIDDEN),
+        }
+    }
+
     fn internal<M>(msg: M) -> Self
     where
         M: fmt::Display + fmt::Debug + Send + Sync + 'static,
@@ -117,20 +240,223 @@ async fn entry(
     Path(sequence): Path<u32>,
     Extension(bg): Extension<Background>,
 ) -> RequestResult<Json<api::OwnedEntryResponse>> {
+    let request = api::EntryRequest { sequence };
+
+    let Some(response) = handle_entry(&bg, &request).await? else {
+        return Err(RequestError::not_found(format!(
+            "Missing entry by id `{sequence}`"
+        )));
+    };
+
+    Ok(Json(response))
+}
+
+async fn handle_entry(
+    bg: &Background,
+    request: &api::EntryRequest,
+) -> Result<Option<api::OwnedEntryResponse>> {
+    let sequence = request.sequence;
     let db = bg.database().await;
 
     let Some(entry) = db.sequence_to_entry(sequence)? else {
+        return Ok(None);
+    };
+
+    let mut priorities = Vec::<api::PriorityInfo>::new();
+
+    for priority in entry.priorities() {
+        let info = api::PriorityInfo::new(priority);
+
+        if !priorities.iter().any(|p| p.code == info.code) {
+            priorities.push(info);
+        }
+    }
+
+    let mut links = Vec::new();
+
+    for link in db.links(sequence)? {
+        let Some(sense) = entry.senses.get(link.sense) else {
+            continue;
+        };
+
+        let texts = match link.kind {
+            LinkKind::Antonym => &sense.antonym,
+            _ => &sense.xref,
+        };
+
+        let Some(text) = texts.get(link.index) else {
+            continue;
+        };
+
+        links.push(api::Link {
+            sense: link.sense,
+            kind: link.kind,
+            sequence: link.sequence,
+            target_sense: link.target_sense,
+            text: (*text).to_owned(),
+        });
+    }
+
+    Ok(Some(api::OwnedEntryResponse {
+        entry: lib::to_owned(entry),
+        priorities,
+        links,
+    }))
+}
+
+async fn examples(
+    Path(sequence): Path<u32>,
+    Query(mut request): Query<api::ExamplesRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::ExamplesResponse>> {
+    request.sequence = sequence;
+
+    let Some(response) = handle_examples(&bg, &request).await? else {
         return Err(RequestError::not_found(format!(
-            "Missing entry by id `{}`",
-            sequence
+            "Missing entry by id `{sequence}`"
         )));
     };
 
-    Ok(Json(api::OwnedEntryResponse {
-        entry: lib::to_owned(entry),
+    Ok(Json(response))
+}
+
+/// The default number of examples per page.
+const EXAMPLES_LIMIT: usize = 20;
+/// The maximum number of examples per page.
+const EXAMPLES_MAX_LIMIT: usize = 100;
+
+async fn handle_examples(
+    bg: &Background,
+    request: &api::ExamplesRequest,
+) -> Result<Option<api::ExamplesResponse>> {
+    let config = bg.config().await;
+    let db = bg.database().await;
+
+    let Some(entry) = db.sequence_to_entry(request.sequence)? else {
+        return Ok(None);
+    };
+
+    let limit = request
+        .limit
+        .unwrap_or(EXAMPLES_LIMIT)
+        .clamp(1, EXAMPLES_MAX_LIMIT);
+
+    let q = request.q.as_deref().map(str::to_lowercase);
+
+    let mut matching = Vec::new();
+
+    for (index, sense) in entry.senses.iter().enumerate() {
+        for example in &sense.examples {
+            let source = example.sources.first();
+
+            if let Some(id) = &request.source {
+                if source.map(|s| s.text) != Some(id.as_str()) {
+                    continue;
+                }
+            }
+
+            if let Some(q) = &q {
+                if !example
+                    .sentences
+                    .iter()
+                    .any(|s| s.text.to_lowercase().contains(q.as_str()))
+                {
+                    continue;
+                }
+            }
+
+            matching.push((index, source, example));
+        }
+    }
+
+    let total = matching.len();
+    let mut examples = Vec::new();
+
+    for (sense, source, example) in matching
+        .into_iter()
+        .skip(request.page.saturating_mul(limit))
+        .take(limit)
+    {
+        let mut furigana = Vec::new();
+        let mut reading = None;
+        let mut translations = Vec::new();
+
+        for sentence in &example.sentences {
+            if matches!(sentence.lang, None | Some("jpn")) && furigana.is_empty() {
+                let segments = lib::examples::annotate(&entry, sentence.text);
+
+                if request.readings {
+                    reading = example_reading(&db, &segments)?;
+                }
+
+                for segment in segments {
+                    furigana.push(api::ExampleSegment {
+                        text: segment.text,
+                        reading: segment.reading,
+                    });
+                }
+            } else {
+                translations.push(api::ExampleTranslation {
+                    text: sentence.text.to_owned(),
+                    lang: sentence.lang.map(str::to_owned),
+                });
+            }
+        }
+
+        examples.push(api::Example {
+            sense,
+            source: source.map(|s| s.text.to_owned()),
+            source_type: source.and_then(|s| s.ty).map(str::to_owned),
+            audio: source.and_then(|s| config.example_audio_url(s.text, s.ty)),
+            furigana,
+            reading,
+            translations,
+        });
+    }
+
+    Ok(Some(api::ExamplesResponse {
+        sequence: request.sequence,
+        page: request.page,
+        limit,
+        total,
+        examples,
     }))
 }
 
+/// Write an annotated example sentence entirely in kana.
+///
+/// Only the word the sentence is an example of is annotated, so the rest of the
+/// sentence is read through the same analysis as when glossing text. Words
+/// which can't be found are left as they are, so if any kanji remain there is
+/// no reading.
+fn example_reading(db: &Database, segments: &[lib::examples::Segment]) -> Result<Option<String>> {
+    let mut output = String::new();
+
+    for segment in segments {
+        match &segment.reading {
+            Some(reading) => output.push_str(reading),
+            None => {
+                for s in lib::gloss::segments(db, &segment.text)? {
+                    output.push_str(s.kana());
+                }
+            }
+        }
+    }
+
+    if !is_kana_only(&output) {
+        return Ok(None);
+    }
+
+    Ok(Some(output))
+}
+
+/// Test if text contains no kanji, so that it can be read as it's written.
+fn is_kana_only(text: &str) -> bool {
+    !text
+        .chars()
+        .any(|c| lib::kana::is_japanese(c) && lib::kana::is_kanji(c) && c != 'ー')
+}
+
 async fn kanji(
     Path(literal): Path<String>,
     Extension(bg): Extension<Background>,
@@ -158,108 +484,2018 @@ async fn handle_kanji(bg: &Background, literal: &str) -> Result<Option<api::Owne
         radicals: radicals
             .map(|e| lib::to_owned(e.radicals))
             .unwrap_or_default(),
+        variants: literal
+            .chars()
+            .flat_map(lib::variants::variants)
+            .map(String::from)
+            .collect(),
     }))
 }
 
-async fn search(
-    Query(request): Query<api::SearchRequest>,
+async fn kanji_card(
+    Path(literal): Path<String>,
+    Query(mut request): Query<api::KanjiCardRequest>,
     Extension(bg): Extension<Background>,
-) -> RequestResult<Json<api::OwnedSearchResponse>> {
-    Ok(Json(handle_search_request(&bg, request).await?))
+) -> RequestResult<Json<api::OwnedKanjiCardResponse>> {
+    request.literal = literal;
+
+    let Some(card) = handle_kanji_card(&bg, &request).await? else {
+        return Err(RequestError::not_found(format!(
+            "Missing kanji by literal `{}`",
+            request.literal
+        )));
+    };
+
+    Ok(Json(card))
 }
 
-async fn handle_search_request(
+/// The default number of common words on a kanji card.
+const KANJI_CARD_LIMIT: usize = 10;
+/// The maximum number of common words on a kanji card.
+const KANJI_CARD_MAX_LIMIT: usize = 50;
+
+async fn handle_kanji_card(
     bg: &Background,
-    request: api::SearchRequest,
-) -> Result<api::OwnedSearchResponse> {
+    request: &api::KanjiCardRequest,
+) -> Result<Option<api::OwnedKanjiCardResponse>> {
     let db = bg.database().await;
-    let search = db.search(&request.q)?;
 
-    let mut phrases = Vec::new();
-    let mut names = Vec::new();
+    let Some(kanji) = db.literal_to_kanji(&request.literal)? else {
+        return Ok(None);
+    };
 
-    for (key, phrase) in search.phrases {
-        phrases.push(api::OwnedSearchPhrase {
-            key,
-            phrase: lib::to_owned(phrase),
-        });
+    let mut onyomi = Vec::new();
+    let mut kunyomi = Vec::new();
+
+    for reading in &kanji.readings {
+        match reading.ty {
+            "ja_on" => onyomi.push(reading.text),
+            "ja_kun" => kunyomi.push(reading.text),
+            _ => {}
+        }
     }
 
-    for (key, name) in search.names {
-        names.push(api::OwnedSearchName {
-            key,
-            name: lib::to_owned(name),
-        });
+    let meanings = kanji
+        .meanings
+        .iter()
+        .filter(|m| matches!(m.lang, None | Some("en")))
+        .map(|m| m.text)
+        .collect::<Vec<_>>();
+
+    let decomposition = db
+        .literal_to_radicals(kanji.literal)?
+        .map(|e| e.radicals)
+        .unwrap_or_default();
+
+    let mut radicals = Vec::new();
+
+    for entry in db.radicals()? {
+        if decomposition.contains(&entry.radical) {
+            radicals.push(api::Radical {
+                radical: entry.radical,
+                strokes: entry.strokes,
+                image: entry.image,
+            });
+        }
     }
 
-    Ok(api::OwnedSearchResponse {
-        phrases,
-        names,
-        characters: lib::to_owned(search.characters),
+    let limit = request
+        .limit
+        .unwrap_or(KANJI_CARD_LIMIT)
+        .min(KANJI_CARD_MAX_LIMIT);
+
+    let words = db.words_with_kanji(kanji.literal, limit, WordSort::Common)?;
+
+    let card = api::KanjiCardResponse {
+        literal: kanji.literal,
+        onyomi,
+        kunyomi,
+        meanings,
+        stroke_count: kanji.misc.stroke_counts.first().copied(),
+        decomposition,
+        radicals,
+        words,
+    };
+
+    Ok(Some(lib::to_owned(card)))
+}
+
+async fn kanji_words(
+    Path(literal): Path<String>,
+    Query(mut request): Query<api::KanjiWordsRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedKanjiWordsResponse>> {
+    request.literal = literal;
+    Ok(Json(handle_kanji_words(&bg, &request).await?))
+}
+
+/// The default number of words listed for a kanji.
+const KANJI_WORDS_LIMIT: usize = 20;
+/// The maximum number of words listed for a kanji.
+const KANJI_WORDS_MAX_LIMIT: usize = 200;
+
+async fn handle_kanji_words(
+    bg: &Background,
+    request: &api::KanjiWordsRequest,
+) -> Result<api::OwnedKanjiWordsResponse> {
+    let db = bg.database().await;
+
+    let limit = request
+        .limit
+        .unwrap_or(KANJI_WORDS_LIMIT)
+        .min(KANJI_WORDS_MAX_LIMIT);
+
+    let words = db.words_with_kanji(&request.literal, limit, request.sort)?;
+    Ok(lib::to_owned(api::KanjiWordsResponse { words }))
+}
+
+async fn shiritori(
+    Query(request): Query<api::ShiritoriRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedShiritoriResponse>> {
+    Ok(Json(handle_shiritori(&bg, &request).await?))
+}
+
+/// The default number of shiritori candidates.
+const SHIRITORI_LIMIT: usize = 20;
+/// The maximum number of shiritori candidates.
+const SHIRITORI_MAX_LIMIT: usize = 100;
+
+async fn handle_shiritori(
+    bg: &Background,
+    request: &api::ShiritoriRequest,
+) -> Result<api::OwnedShiritoriResponse> {
+    let db = bg.database().await;
+
+    let constraints = ShiritoriConstraints {
+        exclude_n: !request.allow_n,
+        common: request.common,
+        played: request
+            .played
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
+            .collect(),
+        limit: request
+            .limit
+            .unwrap_or(SHIRITORI_LIMIT)
+            .min(SHIRITORI_MAX_LIMIT),
+    };
+
+    let words = db.shiritori_candidates(&request.kana, &constraints)?;
+
+    Ok(api::OwnedShiritoriResponse {
+        words: lib::to_owned(words),
     })
 }
 
-#[derive(Encode)]
-#[musli(mode = Text, name_all = "kebab-case")]
-struct VersionResponse {
-    version: &'static str,
+async fn example_sentences(
+    Query(request): Query<api::ExampleSentencesRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedExampleSentencesResponse>> {
+    Ok(Json(handle_example_sentences(&bg, &request).await?))
 }
 
-/// Get the current service version.
-async fn version() -> RequestResult<Json<VersionResponse>> {
-    Ok(Json(VersionResponse {
-        version: crate::VERSION,
+/// The default number of example sentences.
+const EXAMPLE_SENTENCES_LIMIT: usize = 20;
+/// The maximum number of example sentences.
+const EXAMPLE_SENTENCES_MAX_LIMIT: usize = 100;
+
+async fn handle_example_sentences(
+    bg: &Background,
+    request: &api::ExampleSentencesRequest,
+) -> Result<api::OwnedExampleSentencesResponse> {
+    let db = bg.database().await;
+
+    let limit = request
+        .limit
+        .unwrap_or(EXAMPLE_SENTENCES_LIMIT)
+        .min(EXAMPLE_SENTENCES_MAX_LIMIT);
+
+    let found = db.example_sentences(&request.q, limit)?;
+
+    Ok(lib::to_owned(api::ExampleSentencesResponse {
+        sentences: found.sentences,
+        truncated: found.truncated,
     }))
 }
 
-/// Read the current service configuration.
-async fn config(Extension(bg): Extension<Background>) -> RequestResult<Json<Config>> {
-    Ok(Json(bg.config().await))
+async fn minimal_pairs(
+    Query(request): Query<api::MinimalPairsRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedMinimalPairsResponse>> {
+    Ok(Json(handle_minimal_pairs(&bg, &request).await?))
 }
 
-/// Read the current service configuration.
-async fn update_config(
+/// The default number of minimal pairs.
+const MINIMAL_PAIRS_LIMIT: usize = 20;
+/// The maximum number of minimal pairs.
+const MINIMAL_PAIRS_MAX_LIMIT: usize = 100;
+
+async fn handle_minimal_pairs(
+    bg: &Background,
+    request: &api::MinimalPairsRequest,
+) -> Result<api::OwnedMinimalPairsResponse> {
+    let db = bg.database().await;
+
+    let limit = request
+        .limit
+        .unwrap_or(MINIMAL_PAIRS_LIMIT)
+        .min(MINIMAL_PAIRS_MAX_LIMIT);
+
+    let pairs = db
+        .minimal_pairs(request.reading.trim(), limit)?
+        .into_iter()
+        .map(|(confusion, reading, entry)| api::MinimalPair {
+            confusion,
+            reading,
+            entry,
+        })
+        .collect();
+
+    Ok(lib::to_owned(api::MinimalPairsResponse { pairs }))
+}
+
+async fn drill(
+    Query(request): Query<api::DrillRequest>,
     Extension(bg): Extension<Background>,
-    axum::Json(config): axum::Json<Config>,
-) -> RequestResult<Json<api::Empty>> {
-    if bg.update_config(config).await.is_none() {
-        return Err(RequestError::internal("Failed to update configuration"));
+) -> RequestResult<Json<api::DrillResponse>> {
+    let (pos, filter) =
+        drill_filter(&request).map_err(|e| RequestError::bad_request(format!("{e:#}")))?;
+    Ok(Json(handle_drill(&bg, &request, &pos, &filter).await?))
+}
+
+/// The default number of drills.
+const DRILL_LIMIT: usize = 20;
+/// The maximum number of drills.
+const DRILL_MAX_LIMIT: usize = 200;
+
+/// Parse the parts of speech and the filter of a drill request.
+fn drill_filter(request: &api::DrillRequest) -> Result<(Vec<PartOfSpeech>, lib::drill::Filter)> {
+    let pos = parse_pos(&request.pos)?;
+
+    let mut filter = lib::drill::Filter {
+        polite: request.polite,
+        ..Default::default()
+    };
+
+    for keyword in request
+        .forms
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let Some(form) = Form::parse_keyword(keyword) else {
+            bail!("Unknown form `{keyword}`");
+        };
+
+        filter.forms.insert(form);
     }
 
-    Ok(Json(api::Empty))
+    Ok((pos, filter))
 }
 
-/// Trigger a rebuild of the database.
-async fn rebuild(Extension(bg): Extension<Background>) -> RequestResult<Json<api::Empty>> {
-    bg.install(Install::default());
-    Ok(Json(api::Empty))
+async fn handle_drill(
+    bg: &Background,
+    request: &api::DrillRequest,
+    pos: &[PartOfSpeech],
+    filter: &lib::drill::Filter,
+) -> Result<api::DrillResponse> {
+    let db = bg.database().await;
+
+    let limit = request.limit.unwrap_or(DRILL_LIMIT).min(DRILL_MAX_LIMIT);
+    let drills = db.drills(pos, filter, request.seed, limit)?;
+
+    let drills = drills
+        .into_iter()
+        .map(|drill| api::Drill {
+            sequence: drill.sequence,
+            dictionary: drill.dictionary,
+            reading: drill.reading,
+            prompt: drill.prompt,
+            forms: drill.inflection.iter().map(|form| form.keyword()).collect(),
+            answers: drill.answers,
+        })
+        .collect();
+
+    Ok(api::DrillResponse { drills })
 }
 
-/// Perform text analysis.
-async fn analyze(
-    Query(request): Query<api::AnalyzeRequest>,
+async fn typing(
+    Query(request): Query<api::TypingRequest>,
     Extension(bg): Extension<Background>,
-) -> RequestResult<Json<api::OwnedAnalyzeResponse>> {
-    Ok(Json(handle_analyze_request(&bg, request).await?))
+) -> RequestResult<Json<api::TypingResponse>> {
+    let pos = parse_pos(&request.pos).map_err(|e| RequestError::bad_request(format!("{e:#}")))?;
+    Ok(Json(handle_typing(&bg, &request, &pos).await?))
 }
 
-async fn handle_analyze_request(
+/// The default number of words for typing practice.
+const TYPING_LIMIT: usize = 20;
+/// The maximum number of words for typing practice.
+const TYPING_MAX_LIMIT: usize = 200;
+
+/// Parse the comma-separated parts of speech of a request.
+fn parse_pos(pos: &str) -> Result<Vec<PartOfSpeech>> {
+    let mut output = Vec::new();
+
+    for keyword in pos.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some(pos) = PartOfSpeech::parse_keyword(keyword) else {
+            bail!("Unknown part of speech `{keyword}`");
+        };
+
+        output.push(pos);
+    }
+
+    output.sort();
+    output.dedup();
+    Ok(output)
+}
+
+async fn handle_typing(
     bg: &Background,
-    request: api::AnalyzeRequest,
-) -> Result<api::OwnedAnalyzeResponse> {
-    let mut data = Vec::new();
+    request: &api::TypingRequest,
+    pos: &[PartOfSpeech],
+) -> Result<api::TypingResponse> {
+    let (db, generation) = bg.database_generation().await;
+
+    // Candidates are collected once for each combination of parts of speech,
+    // since that means going through every phrase which has them.
+    let key = pos.iter().map(|p| p.ident()).collect::<Vec<_>>().join(",");
+
+    let candidates = match bg.cached_typing(generation, &key).await {
+        Some(candidates) => candidates,
+        None => {
+            let candidates = Arc::new(db.typing_candidates(pos)?);
+            bg.cache_typing(generation, &key, candidates.clone()).await;
+            candidates
+        }
+    };
+
+    let filter = lib::typing::Filter {
+        min_length: request.min_length,
+        max_length: request.max_length,
+        kana_only: request.kana_only,
+        difficulty: request.difficulty,
+    };
+
+    let seed = request.seed.unwrap_or_else(rand::random);
+    let limit = request.limit.unwrap_or(TYPING_LIMIT).min(TYPING_MAX_LIMIT);
+    let continuation = request.continuation.unwrap_or_default();
+
+    let (words, continuation) = db.typing_words(&candidates, &filter, seed, continuation, limit)?;
 
+    let words = words
+        .into_iter()
+        .map(|word| api::TypingWord {
+            sequence: word.sequence,
+            text: word.text,
+            reading: word.reading,
+            difficulty: word.difficulty,
+        })
+        .collect();
+
+    Ok(api::TypingResponse {
+        words,
+        seed,
+        continuation,
+    })
+}
+
+async fn radicals(
+    Query(request): Query<api::RadicalsRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedRadicalsResponse>> {
+    Ok(Json(handle_radicals(&bg, &request).await?))
+}
+
+async fn handle_radicals(
+    bg: &Background,
+    request: &api::RadicalsRequest,
+) -> Result<api::OwnedRadicalsResponse> {
     let db = bg.database().await;
 
-    for (key, string) in db.analyze(&request.q, request.start)? {
-        data.push(api::OwnedAnalyzeEntry {
-            key,
-            string: string.to_owned(),
+    let mut radicals = Vec::new();
+
+    for entry in db.radicals()? {
+        radicals.push(api::OwnedRadical {
+            radical: entry.radical.to_owned(),
+            strokes: entry.strokes,
+            image: entry.image.map(str::to_owned),
         });
     }
 
-    data.sort_by(|a, b| (Reverse(a.string.len()), &a.key).cmp(&(Reverse(b.string.len()), &b.key)));
-    Ok(api::OwnedAnalyzeResponse { data })
+    let radicals_filter = request
+        .radicals
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>();
+
+    let strokes = match (request.min_strokes, request.max_strokes) {
+        (None, None) => None,
+        (min, max) => Some(min.unwrap_or(0)..=max.unwrap_or(u8::MAX)),
+    };
+
+    let constraints = KanjiConstraints {
+        radicals: radicals_filter.iter().map(String::as_str).collect(),
+        strokes,
+        max_grade: request.max_grade,
+    };
+
+    let kanji = if constraints.is_empty() {
+        Vec::new()
+    } else {
+        lib::to_owned(db.search_kanji(&constraints)?)
+    };
+
+    Ok(api::OwnedRadicalsResponse { radicals, kanji })
+}
+
+async fn related(
+    Path(sequence): Path<u32>,
+    Query(mut request): Query<api::RelatedRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedRelatedResponse>> {
+    request.sequence = sequence;
+
+    let Some(response) = handle_related(&bg, &request).await? else {
+        return Err(RequestError::not_found(format!(
+            "Missing entry by id `{sequence}`"
+        )));
+    };
+
+    Ok(Json(response))
+}
+
+/// The default number of related entries.
+const RELATED_LIMIT: usize = 10;
+/// The maximum number of related entries.
+const RELATED_MAX_LIMIT: usize = 50;
+
+async fn handle_related(
+    bg: &Background,
+    request: &api::RelatedRequest,
+) -> Result<Option<api::OwnedRelatedResponse>> {
+    let db = bg.database().await;
+
+    if db.sequence_to_entry(request.sequence)?.is_none() {
+        return Ok(None);
+    }
+
+    let limit = request
+        .limit
+        .unwrap_or(RELATED_LIMIT)
+        .clamp(1, RELATED_MAX_LIMIT);
+
+    let entries = db
+        .related(request.sequence, limit)?
+        .into_iter()
+        .map(|(score, entry)| api::RelatedEntry { score, entry })
+        .collect::<Vec<_>>();
+
+    Ok(Some(api::OwnedRelatedResponse {
+        sequence: request.sequence,
+        entries: lib::to_owned(entries),
+    }))
+}
+
+async fn ruby(
+    Query(request): Query<api::RubyRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::RubyResponse>> {
+    if request.sequence.is_none() && request.text.is_none() {
+        return Err(RequestError::bad_request(
+            "Either `sequence` or `text` must be specified",
+        ));
+    }
+
+    let Some(response) = handle_ruby(&bg, &request).await? else {
+        return Err(RequestError::not_found(format!(
+            "Missing entry by id `{}`",
+            request.sequence.unwrap_or_default()
+        )));
+    };
+
+    Ok(Json(response))
+}
+
+/// Render furigana as ruby HTML, either for every reading of an entry, for an
+/// arbitrary text and reading pair, or for text whose readings are looked up.
+async fn handle_ruby(
+    bg: &Background,
+    request: &api::RubyRequest,
+) -> Result<Option<api::RubyResponse>> {
+    fn form(text: &str, reading: &str) -> api::RubyForm {
+        api::RubyForm {
+            text: text.to_owned(),
+            reading: reading.to_owned(),
+            html: lib::Furigana::new(text, reading, "").ruby().to_string(),
+        }
+    }
+
+    let mut forms = Vec::new();
+
+    if let Some(sequence) = request.sequence {
+        let db = bg.database().await;
+
+        let Some(entry) = db.sequence_to_entry(sequence)? else {
+            return Ok(None);
+        };
+
+        for (kanji, (_, reading), _) in lib::inflection::reading_permutations(&entry) {
+            let text = kanji.map(|(_, kanji)| kanji).unwrap_or(reading);
+            forms.push(form(text, reading));
+        }
+    } else if let (Some(text), Some(reading)) = (&request.text, &request.reading) {
+        forms.push(form(text, reading));
+    } else if let Some(text) = &request.text {
+        let db = bg.database().await;
+        let segments = lib::gloss::segments(&db, text)?;
+
+        forms.push(api::RubyForm {
+            text: text.clone(),
+            reading: segments.iter().map(|s| s.kana()).collect(),
+            html: lib::gloss::ruby(&segments).to_string(),
+        });
+    } else {
+        bail!("Either `sequence` or `text` must be specified");
+    }
+
+    Ok(Some(api::RubyResponse { forms }))
+}
+
+async fn gloss(
+    Query(request): Query<api::GlossRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::GlossResponse>> {
+    Ok(Json(handle_gloss(&bg, &request).await?))
+}
+
+/// Split text into words, optionally with the grammar patterns detected in
+/// it.
+async fn handle_gloss(bg: &Background, request: &api::GlossRequest) -> Result<api::GlossResponse> {
+    let db = bg.database().await;
+    let segments = lib::gloss::segments(&db, &request.text)?;
+
+    let mut grammar = Vec::new();
+
+    if request.grammar {
+        for hint in lib::grammar::detect(&segments) {
+            grammar.push(api::GrammarHint {
+                pattern: hint.pattern.id.to_owned(),
+                form: hint.pattern.form.map(|form| form.keyword()),
+                title: hint.pattern.title().to_owned(),
+                url: hint.pattern.url().map(str::to_owned),
+                start: hint.start,
+                end: hint.end,
+            });
+        }
+    }
+
+    let segments = segments
+        .into_iter()
+        .map(|s| api::GlossSegment {
+            text: s.text,
+            reading: s.reading,
+            sequence: s.sequence,
+            glosses: s.glosses,
+        })
+        .collect();
+
+    Ok(api::GlossResponse { segments, grammar })
+}
+
+async fn digest(
+    Query(request): Query<api::DigestRequest>,
+    Extension(bg): Extension<Background>,
+    headers: HeaderMap,
+) -> RequestResult<Json<api::OwnedDigestResponse>> {
+    Ok(Json(handle_digest(&bg, &request, locale(&headers)).await?))
+}
+
+/// The default number of words in a digest.
+const DIGEST_LIMIT: usize = 20;
+/// The maximum number of words in a digest.
+const DIGEST_MAX_LIMIT: usize = 200;
+
+async fn handle_digest(
+    bg: &Background,
+    request: &api::DigestRequest,
+    locale: Locale,
+) -> Result<api::OwnedDigestResponse> {
+    let limit = request.limit.unwrap_or(DIGEST_LIMIT).min(DIGEST_MAX_LIMIT);
+
+    let utc_offset = request.utc_offset.saturating_mul(60);
+    let digest = bg.digest(request.period, utc_offset).await?;
+    let db = bg.database().await;
+
+    let entries = history::resolve(&db, &digest, limit)?
+        .into_iter()
+        .map(|word| api::DigestEntry {
+            count: word.digested.count,
+            last: word.digested.last,
+            last_text: locale.date(word.digested.last, utc_offset),
+            link: history::search_link(word.text).to_string(),
+            entry: word.entry,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(api::OwnedDigestResponse {
+        period: request.period,
+        total: digest.len(),
+        entries: lib::to_owned(entries),
+    })
+}
+
+async fn search(
+    Query(request): Query<api::SearchRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedSearchResponse>> {
+    Ok(Json(handle_search_request(&bg, request).await?))
+}
+
+async fn instant(
+    Query(request): Query<api::InstantRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedInstantResponse>> {
+    Ok(Json(handle_instant(&bg, &request).await?))
+}
+
+/// The default number of entries in an instant lookup.
+const INSTANT_LIMIT: usize = 5;
+/// The maximum number of entries in an instant lookup.
+const INSTANT_MAX_LIMIT: usize = 50;
+
+/// Look up the first entries stored under exactly the given key, skipping
+/// everything a search does which a quick lookup doesn't need.
+async fn handle_instant(
+    bg: &Background,
+    request: &api::InstantRequest,
+) -> Result<api::OwnedInstantResponse> {
+    let limit = request
+        .limit
+        .unwrap_or(INSTANT_LIMIT)
+        .min(INSTANT_MAX_LIMIT);
+
+    let db = bg.database().await;
+
+    let mut phrases = Vec::new();
+    let mut names = Vec::new();
+
+    for entry in db.lookup_exact_limited(&request.q, limit)? {
+        match entry {
+            database::Entry::Phrase(entry) => phrases.push(entry),
+            database::Entry::Name(entry) => names.push(entry),
+            _ => {}
+        }
+    }
+
+    Ok(lib::to_owned(api::InstantResponse { phrases, names }))
+}
+
+/// Popups are shown while hovering over text, so the same words are looked up
+/// over and over again. Clients can revalidate responses they have cached,
+/// which are only reused until the database changes.
+async fn popup(
+    Query(request): Query<api::PopupRequest>,
+    Extension(bg): Extension<Background>,
+    headers: HeaderMap,
+) -> RequestResult<Response> {
+    let (generation, response) = handle_popup(&bg, &request).await?;
+
+    let etag = format!("\"{generation:016x}\"");
+
+    let cached = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+        });
+
+    let headers = [
+        (header::CACHE_CONTROL, String::from("private, no-cache")),
+        (header::ETAG, etag),
+    ];
+
+    if cached {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    Ok((headers, Json(&*response)).into_response())
+}
+
+/// The number of candidates in a popup.
+const POPUP_LIMIT: usize = 5;
+/// The longest query in characters which candidates are looked up for.
+const POPUP_MAX_QUERY: usize = 32;
+/// The number of ids matching a wildcard pattern which candidates are picked
+/// from.
+const POPUP_LOOKUP_LIMIT: usize = 100;
+
+/// Look up the best candidates for a query, with only what's needed to show
+/// them in a popup. Responses are cached until the database changes, and are
+/// returned with the generation of the database they were produced by.
+async fn handle_popup(
+    bg: &Background,
+    request: &api::PopupRequest,
+) -> Result<(u64, Arc<api::OwnedPopupResponse>)> {
+    let q = request.q.trim();
+    let lang = request.lang.as_deref().unwrap_or("eng");
+    let (db, generation) = bg.database_generation().await;
+
+    if q.is_empty() || q.chars().count() > POPUP_MAX_QUERY {
+        let response = api::OwnedPopupResponse {
+            candidates: Vec::new(),
+        };
+
+        return Ok((generation, Arc::new(response)));
+    }
+
+    // Responses differ by the language of their glosses.
+    let key = format!("{lang}:{q}");
+
+    if let Some(response) = bg.cached_popup(generation, &key).await {
+        return Ok((generation, response));
+    }
+
+    let search = db.search_limited(q, POPUP_LOOKUP_LIMIT)?;
+
+    let candidates = search
+        .phrases
+        .iter()
+        .take(POPUP_LIMIT)
+        .map(|(_, e)| {
+            let reading = e.reading_elements.first().map_or("", |r| r.text);
+            let glosses = e.senses.iter().flat_map(|s| &s.gloss);
+
+            let gloss = glosses
+                .clone()
+                .find(|g| g.is_lang(lang))
+                .or_else(|| glosses.clone().next())
+                .map(|g| g.text);
+
+            api::PopupCandidate {
+                sequence: e.sequence,
+                text: e.kanji_elements.first().map_or(reading, |k| k.text),
+                reading,
+                gloss: gloss.unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let response = Arc::new(lib::to_owned(api::PopupResponse { candidates }));
+    bg.cache_popup(generation, &key, response.clone()).await;
+    Ok((generation, response))
+}
+
+async fn normalize(
+    Query(request): Query<api::NormalizeRequest>,
+    Extension(bg): Extension<Background>,
+) -> Json<api::NormalizeResponse> {
+    Json(handle_normalize(&bg, &request).await)
+}
+
+/// Describe how the query would be interpreted by a search, by normalizing
+/// and parsing it the same way and with the keys phrases are looked up by.
+async fn handle_normalize(
+    bg: &Background,
+    request: &api::NormalizeRequest,
+) -> api::NormalizeResponse {
+    let db = bg.database().await;
+    let q = lib::search::normalize(&request.q);
+    let query = lib::search::parse(&q);
+
+    api::NormalizeResponse {
+        q: q.to_string(),
+        phrases: query
+            .phrases
+            .iter()
+            .map(|s| db.lookup_key(s).into_owned())
+            .collect(),
+        entities: query.entities.iter().map(|s| s.to_string()).collect(),
+        fields: query.fields.iter().map(|s| s.to_string()).collect(),
+        priorities: query.priorities.iter().map(|p| p.to_string()).collect(),
+        inflection: query.inflection.iter().map(|form| form.keyword()).collect(),
+        glosses: query.glosses.iter().map(|s| s.to_string()).collect(),
+        mine: query.mine,
+        phrasebook: query.phrasebook.map(str::to_owned),
+        has_example: query.has_example,
+        common: query.common,
+    }
+}
+
+/// Descriptions of entities never change while the service is running, so
+/// clients are allowed to cache them.
+async fn entities() -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=86400")],
+        Json(handle_entities()),
+    )
+}
+
+/// Describe every entity which might be attached to an entry.
+fn handle_entities() -> api::EntitiesResponse {
+    let entities = lib::entities::descriptions()
+        .map(|(kind, ident, help)| api::EntityDescription {
+            kind: kind.to_owned(),
+            ident: ident.to_owned(),
+            help: help.to_owned(),
+        })
+        .collect();
+
+    api::EntitiesResponse { entities }
+}
+
+/// Resolve the inflection sources of a phrase to the forms they match.
+fn inflection_matches<'a>(
+    key: &EntryResultKey,
+    entry: &lib::jmdict::Entry<'a>,
+) -> Vec<api::InflectionMatch<'a>> {
+    let mut output = Vec::new();
+    let mut conjugations = None;
+
+    for source in &key.sources {
+        let Source::Inflection { data } = source else {
+            continue;
+        };
+
+        let Some((kanji, reading)) = data.resolve(entry) else {
+            continue;
+        };
+
+        let conjugations = conjugations.get_or_insert_with(|| lib::inflection::conjugate(entry));
+
+        let inflected = conjugations
+            .iter()
+            .find(|(r, ..)| *r == data.reading)
+            .and_then(|(_, inflections, _)| inflections.get(data.inflection))
+            .map(|fragments| fragments.furigana());
+
+        output.push(api::InflectionMatch {
+            data: *data,
+            kanji,
+            reading,
+            inflected: inflected.as_ref().map(|f| f.kanji().to_string()),
+            inflected_reading: inflected.as_ref().map(|f| f.reading().to_string()),
+        });
+    }
+
+    output
+}
+
+/// Collect the keys of the phrases and names matched by a search.
+fn result_keys(search: &Search<'_>) -> HashSet<Key> {
+    let phrases = search.phrases.iter().map(|(key, _)| key.key);
+    let names = search.names.iter().map(|(key, _)| key.key);
+    phrases.chain(names).collect()
+}
+
+/// Get the sequence number of the entry a search looked up, which is the best
+/// match if the query is exactly one of its forms.
+fn looked_up(q: &str, search: &Search<'_>) -> Option<u32> {
+    let q = q.trim();
+    let (_, phrase) = search.phrases.first()?;
+
+    let kanji = phrase.kanji_elements.iter().map(|e| e.text);
+    let readings = phrase.reading_elements.iter().map(|e| e.text);
+
+    if !kanji.chain(readings).any(|text| text == q) {
+        return None;
+    }
+
+    u32::try_from(phrase.sequence).ok()
+}
+
+/// The maximum number of words which were looked up before to include when
+/// searching with `#mine`.
+const MINE_LOOKUPS_LIMIT: usize = 50;
+
+async fn handle_search_request(
+    bg: &Background,
+    request: api::SearchRequest,
+) -> Result<api::OwnedSearchResponse> {
+    let (db, generation) = bg.database_generation().await;
+    let continuation = request.continuation.unwrap_or_default();
+    let mut search = db.search_from(&request.q, continuation)?;
+
+    // Only complete searches are cached, since they are used to refine
+    // later searches.
+    if continuation == 0 && search.continuation.is_none() {
+        bg.cache_search(generation, &request.q, Arc::new(result_keys(&search)))
+            .await;
+    }
+
+    if let Some(sequence) = looked_up(&request.q, &search) {
+        let bg = bg.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = bg.record_lookup(sequence).await {
+                tracing::warn!("Failed to record lookup: {error:#}");
+            }
+        });
+    }
+
+    let previous = match &request.refine_of {
+        Some(refine_of) => match bg.cached_search(generation, refine_of).await {
+            Some(keys) => Some(keys),
+            None => {
+                let previous = db.search(refine_of)?;
+
+                // A search which was cut short would drop the results it
+                // never got to, so it's neither used to refine nor cached.
+                if previous.continuation.is_none() {
+                    let keys = Arc::new(result_keys(&previous));
+                    bg.cache_search(generation, refine_of, keys.clone()).await;
+                    Some(keys)
+                } else {
+                    None
+                }
+            }
+        },
+        None => None,
+    };
+
+    if let Some(previous) = previous {
+        search
+            .phrases
+            .retain(|(key, _)| previous.contains(&key.key));
+        search.names.retain(|(key, _)| previous.contains(&key.key));
+
+        let sequences = search
+            .phrases
+            .iter()
+            .map(|(_, phrase)| phrase.sequence)
+            .collect::<HashSet<_>>();
+
+        search
+            .inflections
+            .retain(|(sequence, _)| sequences.contains(sequence));
+    }
+
+    let q = lib::search::normalize(&request.q);
+    let query = lib::search::parse(&q);
+
+    // What the user has looked up or saved is private on read-only services.
+    let private = !bg.is_read_only().await;
+
+    // Searching the user's own data also covers every category of the
+    // phrasebook.
+    let category = query.phrasebook.or(query.mine.then_some(""));
+
+    let phrasebook = match category {
+        Some(category) if private => {
+            let category = (!category.is_empty()).then_some(category);
+            let terms = &query.phrases;
+            bg.phrasebook(|book| book.search(category, terms).cloned().collect())
+                .await?
+        }
+        _ => Vec::new(),
+    };
+
+    if query.mine && private {
+        let mut seen = search
+            .mine
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect::<HashSet<_>>();
+
+        let looked_up = bg.looked_up().await?;
+
+        for entry in db.search_sequences(looked_up, &query.phrases, MINE_LOOKUPS_LIMIT)? {
+            if seen.insert(entry.sequence) {
+                search.mine.push(entry);
+            }
+        }
+    }
+
+    let config = bg.config().await;
+
+    let mut linked_names = Vec::with_capacity(search.phrases.len());
+
+    for (_, phrase) in &search.phrases {
+        linked_names.push(db.linked_names(phrase)?);
+    }
+
+    // Names linked to one of the phrases are collapsed into it, so that the
+    // phrases which are also names are exactly those whose names were
+    // removed.
+    let collapsed = if config.collapse_names {
+        let linked = linked_names.iter().flatten().collect::<HashSet<_>>();
+        let mut collapsed = HashSet::new();
+
+        search.names.retain(|(_, name)| {
+            if !linked.contains(&name.sequence) {
+                return true;
+            }
+
+            collapsed.insert(name.sequence);
+            false
+        });
+
+        collapsed
+    } else {
+        HashSet::new()
+    };
+
+    if continuation == 0 {
+        bg.searched(request.client.as_deref(), &request.q, || {
+            webhooks::search(&request.q, &search)
+        })
+        .await;
+    }
+
+    let start = Instant::now();
+    let span = tracing::debug_span!("serialize").entered();
+
+    let mut phrases = Vec::new();
+    let mut names = Vec::new();
+
+    for ((key, mut phrase), linked_names) in search.phrases.into_iter().zip(linked_names) {
+        let inflections = inflection_matches(&key, &phrase);
+
+        let preferred = phrase
+            .preferred_form()
+            .map(|(kanji, reading)| api::PreferredForm {
+                kanji: kanji.map(|k| k.text),
+                reading: reading.text,
+            });
+
+        let readings = if request.readings {
+            kana_forms(&phrase)
+        } else {
+            Vec::new()
+        };
+
+        let also_name = linked_names.iter().any(|s| collapsed.contains(s));
+        let examples = if request.compact {
+            let counts = example_counts(&phrase);
+
+            for sense in &mut phrase.senses {
+                sense.examples.clear();
+            }
+
+            counts
+        } else {
+            Vec::new()
+        };
+        let accents = reading_accents(&db, &phrase, request.notation)?;
+        let index = config.index_name(db.index_name(key.key)?).to_owned();
+
+        phrases.push(api::OwnedSearchPhrase {
+            key,
+            preferred: lib::to_owned(preferred),
+            phrase: lib::to_owned(phrase),
+            inflections: lib::to_owned(inflections),
+            readings: lib::to_owned(readings),
+            accents: lib::to_owned(accents),
+            also_name,
+            names: linked_names,
+            examples,
+            index,
+        });
+    }
+
+    for (key, name) in search.names {
+        let index = config.index_name(db.index_name(key.key)?).to_owned();
+        let linked_phrases = db.linked_phrases(&name)?;
+
+        names.push(api::OwnedSearchName {
+            key,
+            name: lib::to_owned(name),
+            phrases: linked_phrases,
+            index,
+        });
+    }
+
+    let characters = lib::to_owned(search.characters);
+    let mine = lib::to_owned(search.mine);
+
+    let mut inflections = Vec::new();
+
+    for (sequence, form) in search.inflections {
+        let furigana = form.furigana();
+
+        inflections.push(api::SearchInflection {
+            sequence,
+            text: furigana.kanji().to_string(),
+            reading: furigana.reading().to_string(),
+            furigana: furigana.borrow().to_string(),
+        });
+    }
+
+    span.exit();
+
+    let timings = match request.debug {
+        Some(api::SearchDebug::Timings) => Some(api::SearchTimings {
+            parse: micros(search.timings.parse),
+            lookup: micros(search.timings.lookup),
+            decode: micros(search.timings.decode),
+            rank: micros(search.timings.rank),
+            serialize: micros(start.elapsed()),
+        }),
+        None => None,
+    };
+
+    Ok(api::OwnedSearchResponse {
+        phrases,
+        names,
+        characters,
+        inflections,
+        mine,
+        phrasebook,
+        missing_shards: search.missing_shards,
+        continuation: search.continuation,
+        fallback: search.fallback,
+        timings,
+    })
+}
+
+/// Every form of a phrase written with kanji together with the readings which
+/// apply to it, leaving out readings which aren't written entirely in kana.
+fn kana_forms<'a>(phrase: &lib::jmdict::Entry<'a>) -> Vec<api::KanaForm<'a>> {
+    lib::inflection::reading_permutations(phrase)
+        .into_iter()
+        .filter_map(|(kanji, (_, reading), _)| {
+            let (_, text) = kanji?;
+
+            if !is_kana_only(reading) {
+                return None;
+            }
+
+            Some(api::KanaForm { text, reading })
+        })
+        .collect()
+}
+
+/// The pitch accents of the forms of a phrase which any index has them for,
+/// rendered with the given notation if there is one.
+pub(crate) fn reading_accents<'a>(
+    db: &Database,
+    phrase: &lib::jmdict::Entry<'a>,
+    notation: Option<Notation>,
+) -> Result<Vec<api::ReadingAccent<'a>>> {
+    let mut output = Vec::new();
+
+    for (kanji, (_, reading), _) in lib::inflection::reading_permutations(phrase) {
+        let text = kanji.map(|(_, text)| text).unwrap_or(reading);
+
+        if let Some(accents) = db.accents(text, reading)? {
+            let rendered = match notation {
+                Some(notation) => accents
+                    .iter()
+                    .filter_map(|&accent| accent::render(reading, usize::from(accent), notation))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            output.push(api::ReadingAccent {
+                text,
+                reading,
+                accents: accents.to_vec(),
+                rendered,
+            });
+        }
+    }
+
+    Ok(output)
+}
+
+/// The number of example sentences in each sense of a phrase, or nothing if
+/// none of them have any.
+fn example_counts(phrase: &lib::jmdict::Entry<'_>) -> Vec<usize> {
+    if phrase.senses.iter().all(|sense| sense.examples.is_empty()) {
+        return Vec::new();
+    }
+
+    phrase
+        .senses
+        .iter()
+        .map(|sense| sense.examples.len())
+        .collect()
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Cargo features which are reported by [`version`].
+const FEATURES: &[(&str, bool)] = &[
+    ("bundle", cfg!(feature = "bundle")),
+    ("cli", cfg!(feature = "cli")),
+    ("dbus", cfg!(feature = "dbus")),
+    ("gnome", cfg!(feature = "gnome")),
+    ("memmap", cfg!(feature = "memmap")),
+    ("reqwest", cfg!(feature = "reqwest")),
+];
+
+/// Get the current service version and how it was built.
+async fn version(
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::VersionResponse>> {
+    let tesseract = bg.tesseract_version().map(str::to_owned);
+
+    let features = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    Ok(Json(api::VersionResponse {
+        version: crate::VERSION.to_owned(),
+        rev: crate::REV.to_owned(),
+        built: crate::BUILT,
+        features,
+        database_version: lib::DATABASE_VERSION,
+        api_version: api::API_VERSION,
+        tesseract,
+    }))
+}
+
+/// Health check, which succeeds as long as the service is responding.
+async fn healthz() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// Readiness check, which only succeeds once indexes have been loaded and
+/// warmed up.
+async fn readyz(Extension(bg): Extension<Background>) -> impl IntoResponse {
+    if bg.database().await.is_empty() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "no indexes loaded");
+    }
+
+    if bg.is_warming_up() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "warming up indexes");
+    }
+
+    (StatusCode::OK, "ok")
+}
+
+/// Read the current service configuration.
+async fn config(Extension(bg): Extension<Background>) -> RequestResult<Json<Config>> {
+    Ok(Json(bg.public_config().await))
+}
+
+/// Read the current service configuration.
+async fn update_config(
+    Extension(bg): Extension<Background>,
+    JsonBody(config): JsonBody<Config>,
+) -> RequestResult<Json<api::Empty>> {
+    config
+        .validate()
+        .map_err(|e| RequestError::bad_request(format!("{e:#}")))?;
+
+    if bg.update_config(config).await.is_none() {
+        return Err(RequestError::internal("Failed to update configuration"));
+    }
+
+    Ok(Json(api::Empty))
+}
+
+/// The cookie identifying a client.
+const CLIENT_COOKIE: &str = "jpv-client";
+
+/// Get the identifier of the client from its cookie.
+fn client_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == CLIENT_COOKIE)
+        .map(|(_, id)| id)
+        .filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+/// Get the locale human-facing strings in responses are formatted for from
+/// the `Accept-Language` header.
+fn locale(headers: &HeaderMap) -> Locale {
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(Locale::from_accept_language)
+        .unwrap_or_default()
+}
+
+/// Get the identifier of the client, assigning it a new one through a cookie
+/// if it doesn't have one.
+fn client_id_or_assign(headers: &HeaderMap) -> (String, HeaderMap) {
+    let mut response = HeaderMap::new();
+
+    if let Some(id) = client_id(headers) {
+        return (id.to_owned(), response);
+    }
+
+    let id = format!("{:032x}", rand::random::<u128>());
+
+    let cookie = format!("{CLIENT_COOKIE}={id}; Path=/; Max-Age=315360000; SameSite=Strict");
+
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.insert(header::SET_COOKIE, cookie);
+    }
+
+    (id, response)
+}
+
+/// Read the user interface settings of the current client.
+async fn client_settings(
+    Extension(bg): Extension<Background>,
+    headers: HeaderMap,
+) -> RequestResult<(HeaderMap, Json<ClientSettings>)> {
+    let (id, response) = client_id_or_assign(&headers);
+    let settings = bg.client_settings(&id).await?;
+    Ok((response, Json(settings)))
+}
+
+/// Update the user interface settings of the current client.
+async fn update_client_settings(
+    Extension(bg): Extension<Background>,
+    headers: HeaderMap,
+    JsonBody(settings): JsonBody<ClientSettings>,
+) -> RequestResult<(HeaderMap, Json<ClientSettings>)> {
+    let (id, response) = client_id_or_assign(&headers);
+    bg.update_client_settings(&id, settings.clone()).await?;
+    Ok((response, Json(settings)))
+}
+
+/// Enable an index without having to update the whole configuration.
+async fn enable_index(
+    Path(name): Path<String>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedIndexesChanged>> {
+    set_index_enabled(&bg, &name, true).await
+}
+
+/// Disable an index without having to update the whole configuration.
+async fn disable_index(
+    Path(name): Path<String>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedIndexesChanged>> {
+    set_index_enabled(&bg, &name, false).await
+}
+
+async fn set_index_enabled(
+    bg: &Background,
+    name: &str,
+    enabled: bool,
+) -> RequestResult<Json<api::OwnedIndexesChanged>> {
+    let Some(response) = handle_set_index_enabled(bg, name, enabled).await? else {
+        return Err(RequestError::not_found(format!("Missing index `{name}`")));
+    };
+
+    Ok(Json(response))
+}
+
+async fn handle_set_index_enabled(
+    bg: &Background,
+    name: &str,
+    enabled: bool,
+) -> Result<Option<api::OwnedIndexesChanged>> {
+    bg.set_index_enabled(name, enabled).await
+}
+
+/// List pinned entries.
+async fn pins(Extension(bg): Extension<Background>) -> RequestResult<Json<api::PinsResponse>> {
+    let config = bg.config().await;
+    Ok(Json(api::PinsResponse { pins: config.pins }))
+}
+
+/// Pin entries to the top of the results for a query pattern.
+async fn update_pins(
+    Extension(bg): Extension<Background>,
+    JsonBody(request): JsonBody<api::UpdatePinsRequest>,
+) -> RequestResult<Json<api::PinsResponse>> {
+    let Some(response) = handle_update_pins(&bg, request).await else {
+        return Err(RequestError::internal("Failed to update configuration"));
+    };
+
+    Ok(Json(response))
+}
+
+async fn log_filter(
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::LogFilterResponse>> {
+    let filter = bg.log_filter()?;
+    Ok(Json(api::LogFilterResponse { filter }))
+}
+
+/// Change what the service logs, like the `RUST_LOG` environment variable
+/// but without restarting it.
+async fn update_log_filter(
+    Extension(bg): Extension<Background>,
+    JsonBody(request): JsonBody<api::UpdateLogFilterRequest>,
+) -> RequestResult<Json<api::LogFilterResponse>> {
+    let response = match handle_update_log_filter(&bg, request).await {
+        Ok(response) => response,
+        Err(error) => return Err(RequestError::bad_request(format!("{error:#}"))),
+    };
+
+    Ok(Json(response))
+}
+
+async fn handle_update_log_filter(
+    bg: &Background,
+    request: api::UpdateLogFilterRequest,
+) -> Result<api::LogFilterResponse> {
+    let filter = bg.set_log_filter(request.filter, request.persist).await?;
+    Ok(api::LogFilterResponse { filter })
+}
+
+async fn handle_update_pins(
+    bg: &Background,
+    request: api::UpdatePinsRequest,
+) -> Option<api::PinsResponse> {
+    let mut config = bg.config().await;
+
+    if request.sequences.is_empty() {
+        config.pins.remove(&request.pattern);
+    } else {
+        config.pins.insert(request.pattern, request.sequences);
+    }
+
+    let config = bg.update_config(config).await?;
+    Some(api::PinsResponse { pins: config.pins })
+}
+
+async fn phrasebook(
+    Query(request): Query<api::PhrasebookRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::PhrasebookResponse>> {
+    Ok(Json(handle_phrasebook(&bg, &request).await?))
+}
+
+async fn handle_phrasebook(
+    bg: &Background,
+    request: &api::PhrasebookRequest,
+) -> Result<api::PhrasebookResponse> {
+    let terms = request.q.as_deref().into_iter().collect::<Vec<_>>();
+
+    bg.phrasebook(|book| api::PhrasebookResponse {
+        categories: book.categories().to_vec(),
+        phrases: book
+            .search(request.category.as_deref(), &terms)
+            .cloned()
+            .collect(),
+    })
+    .await
+}
+
+/// Add a phrase to the phrasebook, or update an existing one.
+async fn save_phrase(
+    Extension(bg): Extension<Background>,
+    JsonBody(request): JsonBody<api::SavePhraseRequest>,
+) -> RequestResult<Json<api::SavePhraseResponse>> {
+    let response = handle_save_phrase(&bg, request)
+        .await
+        .map_err(phrasebook_error)?;
+
+    Ok(Json(response))
+}
+
+/// Report phrases and categories which can't be saved as bad requests.
+fn phrasebook_error(error: anyhow::Error) -> RequestError {
+    let status = error
+        .is::<phrasebook::Error>()
+        .then_some(StatusCode::BAD_REQUEST);
+
+    RequestError { error, status }
+}
+
+async fn handle_save_phrase(
+    bg: &Background,
+    request: api::SavePhraseRequest,
+) -> Result<api::SavePhraseResponse> {
+    let phrase = Phrase {
+        id: request.id.unwrap_or_default(),
+        text: request.text,
+        reading: request.reading,
+        translation: request.translation,
+        notes: request.notes,
+        category: request.category,
+        added: 0,
+    };
+
+    let phrase = bg
+        .update_phrasebook(|book| match request.id {
+            Some(id) => match book.update(phrase)? {
+                Some(phrase) => Ok(phrase.clone()),
+                None => bail!("No phrase with id {id}"),
+            },
+            None => Ok(book.add(phrase, background::now())?.clone()),
+        })
+        .await?;
+
+    Ok(api::SavePhraseResponse { phrase })
+}
+
+async fn remove_phrase(
+    Extension(bg): Extension<Background>,
+    JsonBody(request): JsonBody<api::RemovePhraseRequest>,
+) -> RequestResult<Json<api::Empty>> {
+    Ok(Json(handle_remove_phrase(&bg, &request).await?))
+}
+
+async fn handle_remove_phrase(
+    bg: &Background,
+    request: &api::RemovePhraseRequest,
+) -> Result<api::Empty> {
+    bg.update_phrasebook(|book| match book.remove(request.id) {
+        Some(..) => Ok(api::Empty),
+        None => bail!("No phrase with id {}", request.id),
+    })
+    .await
+}
+
+async fn update_category(
+    Extension(bg): Extension<Background>,
+    JsonBody(request): JsonBody<api::UpdateCategoryRequest>,
+) -> RequestResult<Json<api::CategoriesResponse>> {
+    let response = handle_update_category(&bg, &request)
+        .await
+        .map_err(phrasebook_error)?;
+
+    Ok(Json(response))
+}
+
+async fn handle_update_category(
+    bg: &Background,
+    request: &api::UpdateCategoryRequest,
+) -> Result<api::CategoriesResponse> {
+    bg.update_phrasebook(|book| {
+        let name = request.name.as_str();
+
+        let found = if request.remove {
+            book.remove_category(name)
+        } else if let Some(to) = &request.rename {
+            book.rename_category(name, to)?
+        } else {
+            book.add_category(name)?;
+            true
+        };
+
+        if !found {
+            bail!("No category named `{name}`");
+        }
+
+        Ok(api::CategoriesResponse {
+            categories: book.categories().to_vec(),
+        })
+    })
+    .await
+}
+
+/// Export the phrasebook as a file which can be downloaded.
+async fn export_phrasebook(
+    Query(request): Query<api::ExportPhrasebookRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<(HeaderMap, String)> {
+    let category = request.category.as_deref();
+
+    let data = bg
+        .phrasebook(|book| phrasebook::export(request.format, book.search(category, &[])))
+        .await?;
+
+    let mut headers = HeaderMap::new();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(request.format.content_type()),
+    );
+
+    let disposition = format!(
+        "attachment; filename=\"phrasebook.{}\"",
+        request.format.extension()
+    );
+
+    if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+
+    Ok((headers, data))
+}
+
+/// The default number of phrases on a printable vocabulary sheet.
+const SEARCH_HTML_LIMIT: usize = 50;
+/// The maximum number of phrases on a printable vocabulary sheet.
+const SEARCH_HTML_MAX_LIMIT: usize = 500;
+
+/// Render the results of a search as a printable vocabulary sheet.
+async fn search_html(
+    Query(request): Query<api::SearchHtmlRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<(HeaderMap, String)> {
+    let limit = request
+        .limit
+        .unwrap_or(SEARCH_HTML_LIMIT)
+        .min(SEARCH_HTML_MAX_LIMIT);
+
+    let db = bg.database().await;
+    let search = db.search(&request.q)?;
+
+    let phrases = search.phrases.iter().take(limit).map(|(_, phrase)| phrase);
+    let sheet = sheet::Sheet::new(&db, &request.q, phrases)?;
+
+    let mut headers = HeaderMap::new();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+
+    let html = sheet::html(&sheet).to_string();
+    Ok((headers, html))
+}
+
+/// The number of phrasebook phrases shown when the embedded view lands on the
+/// phrasebook.
+const EMBED_PHRASEBOOK_LIMIT: usize = 20;
+
+async fn embed_landing(
+    Query(request): Query<api::EmbedLandingRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::EmbedLandingResponse>> {
+    Ok(Json(handle_embed_landing(&bg, &request).await?))
+}
+
+/// Decide what the embedded view shows when it's opened without a query.
+async fn handle_embed_landing(
+    bg: &Background,
+    request: &api::EmbedLandingRequest,
+) -> Result<api::EmbedLandingResponse> {
+    let landing = bg.config().await.embed_landing;
+
+    let mut query = None;
+    let mut phrasebook = Vec::new();
+
+    match landing {
+        EmbedLanding::Empty => {}
+        EmbedLanding::LastQuery => {
+            if let Some(client) = &request.client {
+                query = bg.last_query(client);
+            }
+        }
+        EmbedLanding::Phrasebook => {
+            phrasebook = bg
+                .phrasebook(|book| {
+                    book.phrases()
+                        .iter()
+                        .rev()
+                        .take(EMBED_PHRASEBOOK_LIMIT)
+                        .cloned()
+                        .collect()
+                })
+                .await?;
+        }
+        EmbedLanding::WordOfDay => {
+            let day = history::day(background::now(), request.utc_offset.saturating_mul(60));
+            let db = bg.database().await;
+
+            query = db.word_of_day(day)?.and_then(|entry| {
+                let (kanji, reading) = entry.preferred_form()?;
+                Some(kanji.map_or(reading.text, |k| k.text).to_owned())
+            });
+        }
+    }
+
+    Ok(api::EmbedLandingResponse {
+        landing,
+        query,
+        phrasebook,
+    })
+}
+
+/// The largest image which can be uploaded for recognition.
+const OCR_MAX_SIZE: usize = 32 * 1024 * 1024;
+
+/// Recognize text in an uploaded image.
+///
+/// The image is sent as the `image` field of a multipart form. The `lang`
+/// field picks another installed tesseract language than the one used for
+/// captured images, like `jpn_vert` for vertical text, and setting `search` to `true`
+/// also searches for the word which is most likely being looked up.
+async fn recognize(
+    Extension(bg): Extension<Background>,
+    mut multipart: Multipart,
+) -> RequestResult<Json<api::OcrResponse>> {
+    let mut image = None;
+    let mut lang = None;
+    let mut search = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(RequestError::bad_request)?
+    {
+        match field.name() {
+            Some("image") => {
+                let format = field.content_type().and_then(ocr::image_format);
+                let data = field.bytes().await.map_err(RequestError::bad_request)?;
+                image = Some((format, data));
+            }
+            Some("lang") => {
+                lang = Some(field.text().await.map_err(RequestError::bad_request)?);
+            }
+            Some("search") => {
+                search = field.text().await.map_err(RequestError::bad_request)? == "true";
+            }
+            _ => {}
+        }
+    }
+
+    let Some((format, data)) = image else {
+        return Err(RequestError::bad_request("Missing `image` field"));
+    };
+
+    let image = match format {
+        Some(format) => image::load_from_memory_with_format(&data, format),
+        None => image::load_from_memory(&data),
+    };
+
+    let image = image.map_err(RequestError::bad_request)?;
+
+    let Some(tesseract) = bg.tesseract() else {
+        return Err(RequestError::bad_request(
+            "Image recognition is not available",
+        ));
+    };
+
+    let lang = lang.filter(|lang| lang != ocr::LANGUAGE);
+
+    if let Some(lang) = &lang {
+        let languages = tesseract.lock().await.languages();
+
+        if !tesseract::is_language_name(lang) || !languages.contains(lang) {
+            return Err(RequestError::bad_request(format!(
+                "Language `{lang}` is not installed"
+            )));
+        }
+    }
+
+    let db = bg.database().await;
+
+    let task = tokio::task::spawn_blocking({
+        let bg = bg.clone();
+
+        move || -> Result<_> {
+            let recognized = match lang {
+                Some(lang) => {
+                    let tesseract = bg.tesseract_for(&lang)?;
+                    let mut tesseract = tesseract.blocking_lock();
+                    ocr::recognize_image(&mut tesseract, &db, &image)?
+                }
+                None => {
+                    let Some(tesseract) = bg.tesseract() else {
+                        bail!("Image recognition is not available");
+                    };
+
+                    ocr::recognize_image(&mut tesseract.blocking_lock(), &db, &image)?
+                }
+            };
+
+            let Some(recognized) = recognized else {
+                return Ok(None);
+            };
+
+            let word = recognized.word(&db)?.map(str::to_owned);
+            Ok(Some((recognized, word)))
+        }
+    });
+
+    let Some((recognized, word)) = task.await.map_err(anyhow::Error::from)?? else {
+        return Ok(Json(api::OcrResponse {
+            text: String::new(),
+            target: None,
+            word: None,
+            search: None,
+        }));
+    };
+
+    let search = match &word {
+        Some(word) if search => {
+            let request = api::SearchRequest {
+                q: word.clone(),
+                refine_of: None,
+                debug: None,
+                continuation: None,
+                readings: false,
+                compact: false,
+                notation: None,
+                client: None,
+            };
+
+            Some(handle_search_request(&bg, request).await?)
+        }
+        _ => None,
+    };
+
+    Ok(Json(api::OcrResponse {
+        text: recognized.text,
+        target: recognized.target,
+        word,
+        search,
+    }))
+}
+
+/// Trigger a rebuild of the database, or of a single index.
+async fn rebuild(
+    Query(request): Query<api::RebuildRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::Empty>> {
+    Ok(Json(handle_rebuild(&bg, request).await?))
+}
+
+async fn handle_rebuild(
+    bg: &Background,
+    request: api::RebuildRequest,
+) -> RequestResult<api::Empty> {
+    let Some(index) = request.index else {
+        bg.install(Install::default());
+        return Ok(api::Empty);
+    };
+
+    if !bg.config().await.indexes.contains_key(&index) {
+        return Err(RequestError::not_found(format!("Missing index `{index}`")));
+    }
+
+    // Only the named index is re-parsed, the others are left as they are.
+    bg.install(Install {
+        filter: Some(vec![index]),
+        force: true,
+    });
+
+    Ok(api::Empty)
+}
+
+/// Verify indexes and remove files which are no longer needed.
+async fn maintenance(
+    Query(request): Query<api::MaintenanceRequest>,
+    Extension(bg): Extension<Background>,
+    headers: HeaderMap,
+) -> RequestResult<Json<api::MaintenanceResponse>> {
+    Ok(Json(
+        handle_maintenance(&bg, request, locale(&headers)).await?,
+    ))
+}
+
+async fn handle_maintenance(
+    bg: &Background,
+    request: api::MaintenanceRequest,
+    locale: Locale,
+) -> Result<api::MaintenanceResponse> {
+    let config = bg.config().await;
+    let bg = bg.clone();
+
+    tokio::task::spawn_blocking(move || {
+        crate::maintenance::run(bg.dirs(), &config, request.dry_run, locale)
+    })
+    .await?
+}
+
+/// Dump how an entry is stored, for debugging how entries are encoded.
+async fn raw_entry(
+    Query(request): Query<api::RawEntryRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedRawEntryResponse>> {
+    Ok(Json(handle_raw_entry(&bg, request).await?))
+}
+
+async fn handle_raw_entry(
+    bg: &Background,
+    request: api::RawEntryRequest,
+) -> RequestResult<api::OwnedRawEntryResponse> {
+    // The layout of indexes is only of interest to whoever runs the service,
+    // so it's not shared by a read-only one.
+    if bg.is_read_only().await {
+        return Err(RequestError::forbidden("The service is read-only"));
+    }
+
+    let db = bg.database().await;
+
+    let entries = match request {
+        api::RawEntryRequest {
+            sequence: Some(sequence),
+            ..
+        } => db.raw_entries_by_sequence(sequence)?,
+        api::RawEntryRequest {
+            index: Some(index),
+            offset: Some(offset),
+            ..
+        } => {
+            let entry = match db.raw_entry_at(&index, offset) {
+                Ok(entry) => entry,
+                Err(error) => return Err(RequestError::not_found(format!("{error:#}"))),
+            };
+
+            entry.into_iter().collect()
+        }
+        _ => {
+            return Err(RequestError::bad_request(
+                "Either `sequence` or both `index` and `offset` have to be set",
+            ));
+        }
+    };
+
+    if entries.is_empty() {
+        return Err(RequestError::not_found("No entry is stored there"));
+    }
+
+    let entries = entries
+        .into_iter()
+        .map(|raw| {
+            let bytes = raw.hex();
+
+            let (phrase, kanji, name) = match raw.entry {
+                database::Entry::Phrase(e) => (Some(e), None, None),
+                database::Entry::Kanji(e) => (None, Some(e), None),
+                database::Entry::Name(e) => (None, None, Some(e)),
+                _ => (None, None, None),
+            };
+
+            api::RawEntry {
+                index: raw.index,
+                section: raw.section,
+                offset: raw.offset,
+                bytes,
+                round_trips: raw.round_trips,
+                phrase,
+                kanji,
+                name,
+            }
+        })
+        .collect();
+
+    Ok(lib::to_owned(api::RawEntryResponse { entries }))
+}
+
+/// Perform text analysis.
+async fn analyze(
+    Query(request): Query<api::AnalyzeRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedAnalyzeResponse>> {
+    Ok(Json(handle_analyze_request(&bg, request).await?))
+}
+
+async fn handle_analyze_request(
+    bg: &Background,
+    request: api::AnalyzeRequest,
+) -> Result<api::OwnedAnalyzeResponse> {
+    let mut data = Vec::new();
+
+    let encoding = request.offset_encoding;
+
+    let Some(start) = encoding.to_byte_offset(&request.q, request.start) else {
+        bail!("Offset {} is not a character boundary", request.start);
+    };
+
+    let db = bg.database().await;
+
+    let analysis = db.analyze(&request.q, start)?;
+    let confidence = database::analysis_confidence(&analysis);
+
+    for ((key, string), confidence) in analysis.into_iter().zip(confidence) {
+        data.push(api::OwnedAnalyzeEntry {
+            key,
+            string: string.to_owned(),
+            start: request.start,
+            end: encoding.to_offset(&request.q, start + string.len()),
+            confidence,
+        });
+    }
+
+    data.sort_by(|a, b| (Reverse(a.string.len()), &a.key).cmp(&(Reverse(b.string.len()), &b.key)));
+    Ok(api::OwnedAnalyzeResponse { data })
+}
+
+/// Split text into words.
+async fn segment(
+    Query(request): Query<api::SegmentRequest>,
+    Extension(bg): Extension<Background>,
+) -> RequestResult<Json<api::OwnedSegmentResponse>> {
+    Ok(Json(handle_segment_request(&bg, &request).await?))
+}
+
+async fn handle_segment_request(
+    bg: &Background,
+    request: &api::SegmentRequest,
+) -> Result<api::OwnedSegmentResponse> {
+    let encoding = request.offset_encoding;
+    let db = bg.database().await;
+
+    let segments = db
+        .segment(&request.q)?
+        .into_iter()
+        .map(|segment| api::SegmentEntry {
+            string: segment.text,
+            start: encoding.to_offset(&request.q, segment.start),
+            end: encoding.to_offset(&request.q, segment.start + segment.text.len()),
+            sequence: segment.sequence,
+            inflection: segment.inflection,
+        })
+        .collect();
+
+    Ok(lib::to_owned(api::SegmentResponse { segments }))
 }
 
 impl IntoResponse for RequestError {
@@ -0,0 +1,680 @@
+//! Tests which serve requests from databases built from small inputs.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::Query;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::Extension;
+use lib::api;
+use lib::config::{ClientSettings, Config};
+use lib::data;
+use lib::database::{self, Database, Input, Location, WordSort};
+use lib::reporter::EmptyReporter;
+use lib::token::Token;
+use lib::Dirs;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing_subscriber::{reload, EnvFilter};
+
+use crate::background::{Background, BackgroundEvent, CLIENT_SETTINGS_CLIENTS};
+use crate::system::SystemEvents;
+
+use super::json::JsonBody;
+
+/// A background serving a database, which is removed from disk when it's
+/// dropped.
+struct Fixture {
+    bg: Background,
+    root: PathBuf,
+    _receiver: UnboundedReceiver<BackgroundEvent>,
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Build a background serving a database with a JMdict index built from the
+/// given entries.
+fn fixture(entries: &str) -> Result<Fixture> {
+    fixture_with(entries, Config::default(), |_| Ok(()))
+}
+
+/// Build a background like [`fixture`] with the given configuration, after
+/// preparing its directories.
+fn fixture_with(
+    entries: &str,
+    config: Config,
+    prepare: impl FnOnce(&Dirs) -> Result<()>,
+) -> Result<Fixture> {
+    let input = format!("<JMdict>{entries}</JMdict>");
+    fixture_from(&[("jmdict", Input::Jmdict(&input, None))], config, prepare)
+}
+
+/// Build a background like [`fixture_with`] with an index for each input,
+/// named after the index it is built as.
+fn fixture_from(
+    inputs: &[(&str, Input<'_>)],
+    config: Config,
+    prepare: impl FnOnce(&Dirs) -> Result<()>,
+) -> Result<Fixture> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let id = NEXT.fetch_add(1, Ordering::SeqCst);
+    let root = std::env::temp_dir().join(format!("jpv-test-web-{}-{id}", process::id()));
+    let dirs = Dirs::in_dir(&root);
+    fs::create_dir_all(dirs.config_dir())?;
+    fs::create_dir_all(dirs.data_dir())?;
+    prepare(&dirs)?;
+
+    let mut indexes = Vec::new();
+
+    for &(name, input) in inputs {
+        let buf = database::build(&EmptyReporter, &Token::default(), name, input)?;
+        let path = dirs.data_dir().join(format!("{name}.index"));
+        fs::write(&path, buf.as_slice())?;
+        let data = data::open(&path)?;
+        indexes.push((data, Location::Path(path.into())));
+    }
+
+    let db = Database::open(indexes, &config)?;
+
+    let (channel, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let system_events = SystemEvents::new();
+    let (_, filter) = reload::Layer::new(EnvFilter::new("info"));
+    let (_, log) = crate::log::new(system_events.clone(), filter);
+
+    let bg = Background::new(dirs, channel, config, db, system_events, None, log)?;
+
+    Ok(Fixture {
+        bg,
+        root,
+        _receiver: receiver,
+    })
+}
+
+#[tokio::test]
+async fn test_popup() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss xml:lang="ger">Fluss</gloss><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>皮</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss xml:lang="ger">Haut</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    let request = |q: &str, lang: Option<&str>| api::PopupRequest {
+        q: q.to_owned(),
+        lang: lang.map(str::to_owned),
+    };
+
+    let glosses = |response: &api::OwnedPopupResponse| {
+        let mut glosses = response
+            .candidates
+            .iter()
+            .map(|c| (c.sequence, c.gloss.clone()))
+            .collect::<Vec<_>>();
+        glosses.sort();
+        glosses
+    };
+
+    // Glosses are picked in English unless asked otherwise, and phrases
+    // without one in the language fall back to their first gloss.
+    let (generation, english) = super::handle_popup(bg, &request(" かわ ", None)).await?;
+    assert_eq!(
+        glosses(&english),
+        [(1, String::from("river")), (2, String::from("Haut"))]
+    );
+
+    let (_, german) = super::handle_popup(bg, &request("かわ", Some("ger"))).await?;
+    assert_eq!(
+        glosses(&german),
+        [(1, String::from("Fluss")), (2, String::from("Haut"))]
+    );
+
+    // Responses are cached by language.
+    let (_, cached) = super::handle_popup(bg, &request("かわ", None)).await?;
+    assert!(Arc::ptr_eq(&english, &cached));
+
+    let (_, empty) = super::handle_popup(bg, &request(&"か".repeat(33), None)).await?;
+    assert!(empty.candidates.is_empty());
+
+    // Clients revalidate responses by the generation of the database.
+    let mut headers = HeaderMap::new();
+    let etag = format!("\"{generation:016x}\"");
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&etag)?);
+
+    let response = super::popup(Query(request("かわ", None)), Extension(bg.clone()), headers)
+        .await
+        .map_err(|error| error.error)?;
+
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(
+        response.headers().get(header::ETAG),
+        Some(&HeaderValue::from_str(&etag)?)
+    );
+
+    let response = super::popup(
+        Query(request("かわ", None)),
+        Extension(bg.clone()),
+        HeaderMap::new(),
+    )
+    .await
+    .map_err(|error| error.error)?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(header::CACHE_CONTROL),
+        Some(&HeaderValue::from_static("private, no-cache"))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_typing() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb><re_pri>spec2</re_pri></r_ele><sense><pos>&n;</pos><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><r_ele><reb>わに</reb><re_pri>nf02</re_pri></r_ele><sense><pos>&n;</pos><gloss>crocodile</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><r_ele><reb>たべる</reb><re_pri>ichi1</re_pri></r_ele><sense><pos>&v1;</pos><gloss>to eat</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    assert!(super::parse_pos("n,bogus").is_err());
+
+    let sequences = |response: api::TypingResponse| {
+        let mut sequences = response
+            .words
+            .iter()
+            .map(|w| w.sequence)
+            .collect::<Vec<_>>();
+        sequences.sort();
+        sequences
+    };
+
+    // Words which only have a word frequency category aren't common.
+    let request = api::TypingRequest::default();
+    let pos = super::parse_pos("")?;
+    let response = super::handle_typing(bg, &request, &pos).await?;
+    assert_eq!(sequences(response), [1, 3]);
+
+    let pos = super::parse_pos(" n ")?;
+    let response = super::handle_typing(bg, &request, &pos).await?;
+    assert_eq!(sequences(response), [1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_normalize() -> Result<()> {
+    let fixture = fixture("")?;
+
+    let request = api::NormalizeRequest {
+        q: String::from("らーめん　＃ｃｏｍｍｏｎ #phrasebook:travel #has-example #mine ﾊﾟﾝ"),
+    };
+
+    let response = super::handle_normalize(&fixture.bg, &request).await;
+    assert_eq!(
+        response.q,
+        "らーめん #common #phrasebook:travel #has-example #mine パン"
+    );
+    assert_eq!(response.phrases, ["らあめん", "パン"]);
+    assert_eq!(response.common, Some(true));
+    assert_eq!(response.phrasebook.as_deref(), Some("travel"));
+    assert!(response.has_example);
+    assert!(response.mine);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_mine() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>皮</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>skin</gloss></sense></entry>
+<entry><ent_seq>3</ent_seq><k_ele><keb>鰐</keb></k_ele><r_ele><reb>わに</reb></r_ele><sense><gloss>crocodile</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    bg.record_lookup(3).await?;
+    bg.record_lookup(1).await?;
+
+    bg.update_phrasebook(|book| {
+        let phrase = lib::phrasebook::Phrase {
+            text: String::from("川で泳ぐ"),
+            ..Default::default()
+        };
+
+        book.add(phrase, 0)?;
+        Ok(())
+    })
+    .await?;
+
+    let search = |q: &str| {
+        let request = api::SearchRequest {
+            q: q.to_owned(),
+            ..Default::default()
+        };
+
+        super::handle_search_request(bg, request)
+    };
+
+    // Words which were looked up are included, most recent first.
+    let response = search("#mine").await?;
+    let mine = response.mine.iter().map(|e| e.sequence).collect::<Vec<_>>();
+    assert_eq!(mine, [1, 3]);
+    assert_eq!(response.phrasebook.len(), 1);
+
+    let response = search("#mine わに").await?;
+    let mine = response.mine.iter().map(|e| e.sequence).collect::<Vec<_>>();
+    assert_eq!(mine, [3]);
+    assert!(response.phrasebook.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_drill_filter() -> Result<()> {
+    let request = |pos: &str, forms: &str| api::DrillRequest {
+        pos: pos.to_owned(),
+        forms: forms.to_owned(),
+        ..Default::default()
+    };
+
+    let (pos, filter) = super::drill_filter(&request(" v5k , v1", "past, pot"))?;
+    assert_eq!(pos.len(), 2);
+    assert!(filter.forms.contains(lib::Form::Past));
+    assert!(filter.forms.contains(lib::Form::Potential));
+
+    let (pos, filter) = super::drill_filter(&request("", ""))?;
+    assert!(pos.is_empty());
+    assert!(filter.forms.is_empty());
+
+    assert!(super::drill_filter(&request("v5k,bogus", "")).is_err());
+    assert!(super::drill_filter(&request("", "past,bogus")).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_startup_config() -> Result<()> {
+    const ENTRY: &str = r#"<entry><ent_seq>1</ent_seq><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>"#;
+
+    // Configuration which is up to date isn't written.
+    let config = Config {
+        database_version: Some(lib::DATABASE_VERSION),
+        ..Config::default()
+    };
+
+    let fixture = fixture_with(ENTRY, config, |_| Ok(()))?;
+    assert!(!fixture.bg.dirs().config_path().exists());
+
+    // Configuration which needs to be migrated is written.
+    let config = Config {
+        version: 0,
+        ..Config::default()
+    };
+
+    let fixture = fixture_with(ENTRY, config.clone(), |_| Ok(()))?;
+    let saved = Config::load(fixture.bg.dirs())?;
+    assert_eq!(saved.version, lib::config::CONFIG_VERSION);
+    assert_eq!(saved.database_version, Some(lib::DATABASE_VERSION));
+
+    // But failing to write it doesn't prevent the service from starting.
+    let fixture = fixture_with(ENTRY, config, |dirs| {
+        fs::create_dir_all(dirs.config_path())?;
+        Ok(())
+    })?;
+
+    let config = fixture.bg.config().await;
+    assert_eq!(config.version, lib::config::CONFIG_VERSION);
+    assert_eq!(config.database_version, Some(lib::DATABASE_VERSION));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_phrasebook() -> Result<()> {
+    let fixture = fixture("")?;
+    let bg = &fixture.bg;
+
+    let save = |text: &str, category: &str| {
+        let request = api::SavePhraseRequest {
+            text: text.to_owned(),
+            category: Some(category.to_owned()),
+            ..Default::default()
+        };
+
+        super::save_phrase(Extension(bg.clone()), JsonBody(request))
+    };
+
+    let category = |name: &str, rename: Option<&str>| {
+        let request = api::UpdateCategoryRequest {
+            name: name.to_owned(),
+            rename: rename.map(str::to_owned),
+            ..Default::default()
+        };
+
+        super::update_category(Extension(bg.clone()), JsonBody(request))
+    };
+
+    // Phrases and categories which can't be saved are bad requests.
+    let Err(error) = save(" ", "travel").await else {
+        panic!("empty phrase was saved");
+    };
+
+    assert_eq!(error.status, Some(StatusCode::BAD_REQUEST));
+
+    let Err(error) = category(" travel", None).await else {
+        panic!("category with surrounding whitespace was added");
+    };
+
+    assert_eq!(error.status, Some(StatusCode::BAD_REQUEST));
+
+    save("駅はどこですか", "day trips")
+        .await
+        .map_err(|error| error.error)?;
+
+    // Renaming a category to itself keeps it.
+    let response = category("day trips", Some("day trips"))
+        .await
+        .map_err(|error| error.error)?;
+    assert_eq!(response.0.categories, ["day trips"]);
+
+    // Categories with spaces are searched by quoting them.
+    let request = api::SearchRequest {
+        q: String::from("#phrasebook:\"day trips\""),
+        ..Default::default()
+    };
+
+    let response = super::handle_search_request(bg, request).await?;
+    assert_eq!(response.phrasebook.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_settings() -> Result<()> {
+    let fixture = fixture("")?;
+    let bg = &fixture.bg;
+
+    let settings = ClientSettings {
+        font_size: Some(24),
+        ..ClientSettings::default()
+    };
+
+    for n in 0..=CLIENT_SETTINGS_CLIENTS {
+        bg.update_client_settings(&format!("client{n}"), settings.clone())
+            .await?;
+    }
+
+    // Only a limited number of clients are stored.
+    let clients = ClientSettings::load_all(bg.dirs())?;
+    assert_eq!(clients.len(), CLIENT_SETTINGS_CLIENTS);
+    assert!(clients
+        .values()
+        .all(|c| c.font_size == Some(24) && c.updated > 0));
+
+    // Settings which are the same as the defaults aren't stored.
+    bg.update_client_settings("client1", ClientSettings::default())
+        .await?;
+    assert_eq!(
+        bg.client_settings("client1").await?,
+        ClientSettings::default()
+    );
+
+    let clients = ClientSettings::load_all(bg.dirs())?;
+    assert!(!clients.contains_key("client1"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_history_saved_in_batches() -> Result<()> {
+    let fixture = fixture("")?;
+    let bg = &fixture.bg;
+
+    let saved = || -> Result<Vec<u32>> {
+        let history = lib::history::History::load(bg.dirs())?;
+        Ok(history.lookups().iter().map(|l| l.sequence).collect())
+    };
+
+    bg.record_lookup(1).await?;
+    bg.record_lookup(2).await?;
+
+    // Lookups are held in memory until the history is saved.
+    assert!(saved()?.is_empty());
+    assert_eq!(bg.looked_up().await?, [2, 1]);
+
+    bg.save_history().await?;
+    assert_eq!(saved()?, [1, 2]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_version() -> Result<()> {
+    let fixture = fixture("")?;
+
+    let response = super::version(Extension(fixture.bg.clone()))
+        .await
+        .map_err(|error| error.error)?;
+
+    assert_eq!(response.0.database_version, lib::DATABASE_VERSION);
+    assert_eq!(response.0.tesseract, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compact_search() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><sense><gloss>cat</gloss><example><ex_srce exsrc_type="tat">1</ex_srce><ex_text>猫</ex_text><ex_sent xml:lang="jpn">猫が好きです。</ex_sent><ex_sent xml:lang="eng">I like cats.</ex_sent></example></sense><sense><gloss>geisha</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    let search = |compact: bool| {
+        let request = api::SearchRequest {
+            q: String::from("猫"),
+            compact,
+            ..Default::default()
+        };
+
+        super::handle_search_request(bg, request)
+    };
+
+    // Examples are only counted when they're left out.
+    let response = search(false).await?;
+    let phrase = &response.phrases[0];
+    assert_eq!(phrase.phrase.senses[0].examples.len(), 1);
+    assert!(phrase.examples.is_empty());
+
+    let response = search(true).await?;
+    let phrase = &response.phrases[0];
+    assert!(phrase.phrase.senses.iter().all(|s| s.examples.is_empty()));
+    assert_eq!(phrase.examples, [1, 0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_name() -> Result<()> {
+    let entries = r#"<entry><ent_seq>1</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><sense><gloss>cat</gloss></sense></entry>"#;
+
+    let search = |bg: Background| async move {
+        let request = api::SearchRequest {
+            q: String::from("猫"),
+            ..Default::default()
+        };
+
+        super::handle_search_request(&bg, request).await
+    };
+
+    // Results are shown with the identifier of an index without a name.
+    let fixture = fixture(entries)?;
+    let response = search(fixture.bg.clone()).await?;
+    assert_eq!(response.phrases[0].index, "jmdict");
+
+    let mut config = Config::default();
+    config.indexes.get_mut("jmdict").expect("jmdict index").name = Some(String::from("Words"));
+
+    let fixture = fixture_with(entries, config, |_| Ok(()))?;
+    let response = search(fixture.bg.clone()).await?;
+    assert_eq!(response.phrases[0].index, "Words");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_readings() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><r_ele><reb>ね猫</reb></r_ele><sense><gloss>cat</gloss><example><ex_srce exsrc_type="tat">1</ex_srce><ex_text>猫</ex_text><ex_sent xml:lang="jpn">猫が好きです。</ex_sent></example><example><ex_srce exsrc_type="tat">2</ex_srce><ex_text>猫</ex_text><ex_sent xml:lang="jpn">猫と犬。</ex_sent></example></sense></entry><entry><ent_seq>2</ent_seq><k_ele><keb>好き</keb></k_ele><r_ele><reb>すき</reb></r_ele><sense><gloss>liked</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    // Readings which aren't entirely in kana are left out.
+    let request = api::SearchRequest {
+        q: String::from("猫"),
+        readings: true,
+        ..Default::default()
+    };
+
+    let response = super::handle_search_request(bg, request).await?;
+    let readings = response.phrases[0]
+        .readings
+        .iter()
+        .map(|form| (form.text.as_str(), form.reading.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(readings, [("猫", "ねこ")]);
+
+    // Sentences with words which can't be read have no reading.
+    let request = api::ExamplesRequest {
+        sequence: 1,
+        readings: true,
+        ..Default::default()
+    };
+
+    let response = super::handle_examples(bg, &request)
+        .await?
+        .expect("examples");
+    let readings = response
+        .examples
+        .iter()
+        .map(|example| example.reading.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(readings, [Some("ねこがすきです。"), None]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_target_word() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>大好き</keb></k_ele><r_ele><reb>だいすき</reb></r_ele><sense><gloss>loveable</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele><sense><gloss>cat</gloss></sense></entry>"#,
+    )?;
+
+    let db = fixture.bg.database().await;
+
+    // The word covering the middle of the text is picked, even if it starts
+    // before it.
+    let text = "今日は大好きな猫です";
+    assert_eq!(crate::ocr::target_word(&db, text)?, text.find("大好き"));
+    assert_eq!(crate::ocr::target_word(&db, "猫")?, Some(0));
+
+    // Nothing is picked if no word covers the middle.
+    assert_eq!(crate::ocr::target_word(&db, "猫です今日は")?, None);
+    assert_eq!(crate::ocr::target_word(&db, "")?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_kanji_words() -> Result<()> {
+    let fixture = fixture(
+        r#"<entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>小川</keb><ke_pri>ichi1</ke_pri></k_ele><r_ele><reb>おがわ</reb><re_pri>ichi1</re_pri></r_ele><sense><gloss>brook</gloss></sense></entry>"#,
+    )?;
+
+    let bg = &fixture.bg;
+
+    let words = |response: &api::OwnedKanjiWordsResponse| {
+        response
+            .words
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect::<Vec<_>>()
+    };
+
+    // Common words are listed first by default.
+    let request = api::KanjiWordsRequest {
+        literal: "川".to_owned(),
+        ..Default::default()
+    };
+
+    let response = super::handle_kanji_words(bg, &request).await?;
+    assert_eq!(words(&response), [2, 1]);
+
+    let request = api::KanjiWordsRequest {
+        literal: "川".to_owned(),
+        limit: Some(1),
+        sort: WordSort::Short,
+    };
+
+    let response = super::handle_kanji_words(bg, &request).await?;
+    assert_eq!(words(&response), [1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_collapse_names() -> Result<()> {
+    let phrases = r#"<JMdict><entry><ent_seq>1</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>river</gloss></sense></entry>
+<entry><ent_seq>2</ent_seq><k_ele><keb>皮</keb></k_ele><r_ele><reb>かわ</reb></r_ele><sense><gloss>skin</gloss></sense></entry></JMdict>"#;
+
+    let names = r#"<JMnedict>
+<entry><ent_seq>10</ent_seq><k_ele><keb>川</keb></k_ele><r_ele><reb>かわ</reb></r_ele><trans><trans_det>Kawa</trans_det></trans></entry>
+<entry><ent_seq>11</ent_seq><k_ele><keb>河</keb></k_ele><r_ele><reb>かわ</reb></r_ele><trans><trans_det>Kawa</trans_det></trans></entry>
+</JMnedict>"#;
+
+    let inputs = [
+        ("jmdict", Input::Jmdict(phrases, None)),
+        ("jmnedict", Input::Jmnedict(names, None)),
+    ];
+
+    async fn search(fixture: &Fixture) -> Result<(Vec<(u64, bool)>, Vec<u64>)> {
+        let request = api::SearchRequest {
+            q: String::from("かわ"),
+            ..Default::default()
+        };
+
+        let response = super::handle_search_request(&fixture.bg, request).await?;
+
+        let mut phrases = response
+            .phrases
+            .iter()
+            .map(|p| (p.phrase.sequence, p.also_name))
+            .collect::<Vec<_>>();
+        phrases.sort();
+
+        let mut names = response
+            .names
+            .iter()
+            .map(|n| n.name.sequence)
+            .collect::<Vec<_>>();
+        names.sort();
+
+        Ok((phrases, names))
+    }
+
+    // Names are listed separately by default.
+    let fixture = fixture_from(&inputs, Config::default(), |_| Ok(()))?;
+    let (phrases, names) = search(&fixture).await?;
+    assert_eq!(phrases, [(1, false), (2, false)]);
+    assert_eq!(names, [10, 11]);
+
+    // Only names linked to a phrase are collapsed into it.
+    let config = Config {
+        collapse_names: true,
+        ..Config::default()
+    };
+
+    let fixture = fixture_from(&inputs, config, |_| Ok(()))?;
+    let (phrases, names) = search(&fixture).await?;
+    assert_eq!(phrases, [(1, true), (2, false)]);
+    assert_eq!(names, [11]);
+    Ok(())
+}
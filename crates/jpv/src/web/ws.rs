@@ -1,37 +1,64 @@
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::io::Write;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Instant;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
-use axum::extract::ConnectInfo;
+use axum::extract::{ConnectInfo, Query};
+use axum::http::{header, HeaderMap};
 use axum::response::IntoResponse;
 use axum::Extension;
+use flate2::write::DeflateEncoder;
 use lib::api::{self, Request};
+use lib::locale::Locale;
+use lib::reporter::Tracker;
 use musli::mode::Binary;
 use musli::Encode;
 use musli_utils::reader::SliceReader;
 use rand::prelude::*;
 use rand::rngs::SmallRng;
-use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::Duration;
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 use tracing::{Instrument, Level};
 
 use crate::background::{Background, Install};
 use crate::system;
 
+/// Messages at least this large are compressed for clients which ask for it.
+const COMPRESSION_THRESHOLD: usize = 16384;
+
 pub(super) async fn entry(
     ws: WebSocketUpgrade,
+    Query(params): Query<api::WebSocketParams>,
     Extension(bg): Extension<Background>,
     Extension(system_events): Extension<system::SystemEvents>,
     ConnectInfo(remote): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
+    let locale = super::locale(&headers);
+
+    let host = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
     ws.on_upgrade(move |socket| async move {
-        let span = tracing::span!(Level::INFO, "websocket", ?remote);
+        let span =
+            tracing::span!(Level::INFO, "websocket", ?remote, compression = ?params.compression);
 
         let mut server = Server {
             system_events,
             bg: bg.clone(),
+            compression: params.compression,
+            resume: params.session.zip(params.sequence),
+            locale,
+            host,
             output: Vec::new(),
             body: Vec::new(),
             socket,
@@ -46,6 +73,13 @@ pub(super) async fn entry(
 struct Server {
     system_events: system::SystemEvents,
     bg: Background,
+    compression: api::Compression,
+    /// The session and sequence number the client asked to resume from.
+    resume: Option<(u64, u64)>,
+    /// The locale human-facing strings are formatted for.
+    locale: Locale,
+    /// The `Host` the client connected through.
+    host: Option<String>,
     output: Vec<u8>,
     body: Vec<u8>,
     socket: WebSocket,
@@ -68,11 +102,35 @@ impl Server {
         let mut ping_interval = tokio::time::interval(PING_TIMEOUT);
         ping_interval.reset();
 
-        let mut receiver = self.system_events.subscribe();
+        let mut subscription = self.system_events.resume(self.resume);
+
+        self.send(api::ClientEvent::Broadcast(api::Broadcast {
+            kind: api::BroadcastKind::Hello(api::Hello {
+                session: subscription.session,
+                sequence: subscription.sequence,
+                resumed: subscription.missed.is_some(),
+                version: api::PROTOCOL_VERSION,
+            }),
+            sequence: None,
+        }))
+        .await?;
 
-        self.log_backfill().await?;
+        match subscription.missed.take() {
+            Some(missed) => {
+                tracing::trace!(missed = missed.len(), "Resuming session");
 
-        let close_here = loop {
+                for (sequence, event) in missed {
+                    if let Err(error) = self.system_event(sequence, event).await {
+                        tracing::error!(?error, "Failed to replay system event");
+                    }
+                }
+            }
+            None => {
+                self.log_backfill().await?;
+            }
+        }
+
+        let close_here = 'outer: loop {
             tokio::select! {
                 _ = close_interval.tick() => {
                     break Some((CLOSE_NORMAL, "connection timed out"));
@@ -85,12 +143,16 @@ impl Server {
                     self.socket.send(Message::Ping(data)).await?;
                     ping_interval.reset();
                 }
-                event = receiver.recv() => {
-                    let Ok(event) = event else {
-                        break Some((CLOSE_NORMAL, "system shutting down"));
+                event = subscription.recv() => {
+                    let (sequence, event) = match event {
+                        Ok(event) => event,
+                        // The client resumes from the events it missed when
+                        // it reconnects.
+                        Err(RecvError::Lagged(..)) => break Some((CLOSE_NORMAL, "lagged behind")),
+                        Err(RecvError::Closed) => break Some((CLOSE_NORMAL, "system shutting down")),
                     };
 
-                    if let Err(error) = self.system_event(event).await {
+                    if let Err(error) = self.system_event(sequence, event).await {
                         tracing::error!(?error, "Failed to process system event");
                     };
                 }
@@ -99,55 +161,85 @@ impl Server {
                         break None;
                     };
 
-                    match message? {
-                        Message::Text(_) => break Some((CLOSE_PROTOCOL_ERROR, "unsupported message")),
-                        Message::Binary(bytes) => {
-                            let mut reader = SliceReader::new(&bytes);
-                            let (request, result) = self.handle_envelope(&mut reader).await?;
-
-                            if reader.remaining() > 0 {
-                                break Some((CLOSE_PROTOCOL_ERROR, "extra data"));
-                            }
-
-                            let error = match result {
-                                Ok(()) => None,
-                                Err(error) => {
-                                    tracing::warn!(?error, "Failed to handle request");
-                                    self.body.clear();
-                                    Some(error.to_string())
+                    let mut queue = VecDeque::from([message?]);
+
+                    loop {
+                        // Drain messages which are already available, so that
+                        // searches superseded by a later one can be skipped.
+                        while let Poll::Ready(Some(message)) = self.try_next().await {
+                            queue.push_back(message?);
+                        }
+
+                        let Some(message) = queue.pop_front() else {
+                            break;
+                        };
+
+                        match message {
+                            Message::Text(_) => break 'outer Some((CLOSE_PROTOCOL_ERROR, "unsupported message")),
+                            Message::Binary(bytes) => {
+                                let start = Instant::now();
+
+                                let superseded = is_search(&bytes) && queue.iter().any(|m| matches!(m, Message::Binary(bytes) if is_search(bytes)));
+
+                                let mut reader = SliceReader::new(&bytes);
+                                let (request, result) = self.handle_envelope(&mut reader, superseded).await?;
+
+                                let protocol_error = match &result {
+                                    Ok(()) => None,
+                                    Err(error) => error.downcast_ref::<api::ProtocolError>().copied(),
+                                };
+
+                                // The body of an unsupported request is left
+                                // as-is, since it can't be decoded.
+                                if !superseded && protocol_error.is_none() && reader.remaining() > 0 {
+                                    break 'outer Some((CLOSE_PROTOCOL_ERROR, "extra data"));
+                                }
+
+                                let error = match result {
+                                    Ok(()) => None,
+                                    Err(error) => {
+                                        if !superseded {
+                                            tracing::warn!(?error, kind = request.kind, "Failed to handle request");
+                                        }
+
+                                        self.body.clear();
+                                        Some(error.to_string())
+                                    }
+                                };
+
+                                self.write(api::ClientEvent::ClientResponse(api::ClientResponseEnvelope {
+                                    index: request.index,
+                                    serial: request.serial,
+                                    error: error.as_deref(),
+                                    protocol_error,
+                                    queue: u32::try_from(queue.len()).unwrap_or(u32::MAX),
+                                    latency: u32::try_from(start.elapsed().as_millis()).unwrap_or(u32::MAX),
+                                }))?;
+
+                                self.output.extend_from_slice(&self.body);
+                                self.body.clear();
+                                self.flush().await?;
+                            },
+                            Message::Ping(payload) => {
+                                self.socket.send(Message::Pong(payload)).await?;
+                            },
+                            Message::Pong(data) => {
+                                tracing::trace!(data = ?&data[..], "Pong");
+
+                                let Some(expected) = last_ping else {
+                                    continue;
+                                };
+
+                                if expected.to_ne_bytes()[..] != data[..] {
+                                    continue;
                                 }
-                            };
-
-                            self.write(api::ClientEvent::ClientResponse(api::ClientResponseEnvelope {
-                                index: request.index,
-                                serial: request.serial,
-                                error: error.as_deref(),
-                            }))?;
-
-                            self.output.extend_from_slice(&self.body);
-                            self.body.clear();
-                            self.flush().await?;
-                        },
-                        Message::Ping(payload) => {
-                            self.socket.send(Message::Pong(payload)).await?;
-                            continue;
-                        },
-                        Message::Pong(data) => {
-                            tracing::trace!(data = ?&data[..], "Pong");
-
-                            let Some(expected) = last_ping else {
-                                continue;
-                            };
-
-                            if expected.to_ne_bytes()[..] != data[..] {
-                                continue;
-                            }
-
-                            close_interval.reset();
-                            ping_interval.reset();
-                            last_ping = None;
-                        },
-                        Message::Close(_) => break None,
+
+                                close_interval.reset();
+                                ping_interval.reset();
+                                last_ping = None;
+                            },
+                            Message::Close(_) => break 'outer None,
+                        }
                     }
                 }
             }
@@ -186,6 +278,16 @@ impl Server {
         Ok(())
     }
 
+    /// Refuse a request which modifies the service, or which shows what its
+    /// user has looked up or saved, if it's read-only.
+    async fn ensure_writable(&mut self) -> Result<()> {
+        if self.bg.is_read_only().await {
+            bail!("The service is read-only");
+        }
+
+        Ok(())
+    }
+
     fn write_body<T>(&mut self, value: T) -> Result<()>
     where
         T: Encode<Binary>,
@@ -196,19 +298,24 @@ impl Server {
 
     async fn flush(&mut self) -> Result<()> {
         const MAX_CAPACITY: usize = 1048576;
-        self.socket
-            .send(Message::Binary(self.output.clone()))
-            .await?;
+        let data = compress(self.compression, &self.output)?;
+        self.socket.send(Message::Binary(data)).await?;
         self.output.clear();
         self.output.shrink_to(MAX_CAPACITY);
         Ok(())
     }
 
     async fn log_backfill(&mut self) -> Result<()> {
+        // The log mentions local paths.
+        if self.bg.is_read_only().await {
+            return Ok(());
+        }
+
         let log = self.bg.log();
 
         self.send(api::OwnedClientEvent::Broadcast(api::OwnedBroadcast {
             kind: api::OwnedBroadcastKind::LogBackFill(api::OwnedLogBackFill { log }),
+            sequence: None,
         }))
         .await?;
 
@@ -233,8 +340,9 @@ impl Server {
                 };
 
                 let result = api::GetConfigResult {
-                    config: self.bg.config().await,
+                    config: self.bg.public_config().await,
                     installed: database.installed()?,
+                    languages: database.languages()?,
                     missing_ocr,
                 };
 
@@ -245,17 +353,108 @@ impl Server {
                 let response = super::handle_search_request(&self.bg, request).await?;
                 self.write_body(&response)?;
             }
+            api::InstantRequest::KIND => {
+                let request: api::InstantRequest = musli_storage::decode(reader)?;
+                let response = super::handle_instant(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::PopupRequest::KIND => {
+                let request: api::PopupRequest = musli_storage::decode(reader)?;
+                let (_, response) = super::handle_popup(&self.bg, &request).await?;
+                self.write_body(&*response)?;
+            }
+            api::NormalizeRequest::KIND => {
+                let request: api::NormalizeRequest = musli_storage::decode(reader)?;
+                let response = super::handle_normalize(&self.bg, &request).await;
+                self.write_body(&response)?;
+            }
+            api::SetIndexEnabledRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::SetIndexEnabledRequest = musli_storage::decode(reader)?;
+
+                let Some(response) =
+                    super::handle_set_index_enabled(&self.bg, &request.name, request.enabled)
+                        .await?
+                else {
+                    bail!("No such index");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::EntitiesRequest::KIND => {
+                let response = super::handle_entities();
+                self.write_body(&response)?;
+            }
             api::AnalyzeRequest::KIND => {
                 let request = musli_storage::decode(reader)?;
                 let response = super::handle_analyze_request(&self.bg, request).await?;
                 self.write_body(&response)?;
             }
+            api::SegmentRequest::KIND => {
+                let request: api::SegmentRequest = musli_storage::decode(reader)?;
+                let response = super::handle_segment_request(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
             api::InstallAllRequest::KIND => {
+                self.ensure_writable().await?;
                 self.bg.install(Install::default());
             }
+            api::RebuildRequest::KIND => {
+                self.ensure_writable().await?;
+                let request = musli_storage::decode(reader)?;
+                let response = super::handle_rebuild(&self.bg, request)
+                    .await
+                    .map_err(|e| e.error)?;
+                self.write_body(response)?;
+            }
+            api::UpdatePinsRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::UpdatePinsRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_update_pins(&self.bg, request).await else {
+                    bail!("Failed to update configuration");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::UpdateLogFilterRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::UpdateLogFilterRequest = musli_storage::decode(reader)?;
+                let response = super::handle_update_log_filter(&self.bg, request).await?;
+                self.write_body(&response)?;
+            }
+            api::PhrasebookRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::PhrasebookRequest = musli_storage::decode(reader)?;
+                let response = super::handle_phrasebook(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::SavePhraseRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::SavePhraseRequest = musli_storage::decode(reader)?;
+                let response = super::handle_save_phrase(&self.bg, request).await?;
+                self.write_body(&response)?;
+            }
+            api::RemovePhraseRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::RemovePhraseRequest = musli_storage::decode(reader)?;
+                let response = super::handle_remove_phrase(&self.bg, &request).await?;
+                self.write_body(response)?;
+            }
+            api::UpdateCategoryRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::UpdateCategoryRequest = musli_storage::decode(reader)?;
+                let response = super::handle_update_category(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
             api::UpdateConfigRequest::KIND => {
+                self.ensure_writable().await?;
                 let request: api::UpdateConfigRequest = musli_storage::decode(reader)?;
 
+                if let Some(config) = &request.config {
+                    config.validate()?;
+                }
+
                 if !request.update_indexes.is_empty() {
                     let install = Install {
                         filter: Some(request.update_indexes),
@@ -286,34 +485,190 @@ impl Server {
 
                 self.write_body(&response)?;
             }
-            kind => bail!("Unsupported request kind {kind}"),
+            api::RadicalsRequest::KIND => {
+                let request: api::RadicalsRequest = musli_storage::decode(reader)?;
+                let response = super::handle_radicals(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::ShiritoriRequest::KIND => {
+                let request: api::ShiritoriRequest = musli_storage::decode(reader)?;
+                let response = super::handle_shiritori(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::ExampleSentencesRequest::KIND => {
+                let request: api::ExampleSentencesRequest = musli_storage::decode(reader)?;
+                let response = super::handle_example_sentences(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::MinimalPairsRequest::KIND => {
+                let request: api::MinimalPairsRequest = musli_storage::decode(reader)?;
+                let response = super::handle_minimal_pairs(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::DrillRequest::KIND => {
+                let request: api::DrillRequest = musli_storage::decode(reader)?;
+                let (pos, filter) = super::drill_filter(&request)?;
+                let response = super::handle_drill(&self.bg, &request, &pos, &filter).await?;
+                self.write_body(&response)?;
+            }
+            api::TypingRequest::KIND => {
+                let request: api::TypingRequest = musli_storage::decode(reader)?;
+                let pos = super::parse_pos(&request.pos)?;
+                let response = super::handle_typing(&self.bg, &request, &pos).await?;
+                self.write_body(&response)?;
+            }
+            api::ExamplesRequest::KIND => {
+                let request: api::ExamplesRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_examples(&self.bg, &request).await? else {
+                    bail!("No such entry");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::KanjiCardRequest::KIND => {
+                let request: api::KanjiCardRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_kanji_card(&self.bg, &request).await? else {
+                    bail!("No such kanji");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::KanjiWordsRequest::KIND => {
+                let request: api::KanjiWordsRequest = musli_storage::decode(reader)?;
+                let response = super::handle_kanji_words(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::EntryRequest::KIND => {
+                let request: api::EntryRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_entry(&self.bg, &request).await? else {
+                    bail!("No such entry");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::RelatedRequest::KIND => {
+                let request: api::RelatedRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_related(&self.bg, &request).await? else {
+                    bail!("No such entry");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::RubyRequest::KIND => {
+                let request: api::RubyRequest = musli_storage::decode(reader)?;
+
+                let Some(response) = super::handle_ruby(&self.bg, &request).await? else {
+                    bail!("No such entry");
+                };
+
+                self.write_body(&response)?;
+            }
+            api::GlossRequest::KIND => {
+                let request: api::GlossRequest = musli_storage::decode(reader)?;
+                let response = super::handle_gloss(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::DigestRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::DigestRequest = musli_storage::decode(reader)?;
+                let response = super::handle_digest(&self.bg, &request, self.locale).await?;
+                self.write_body(&response)?;
+            }
+            api::EmbedLandingRequest::KIND => {
+                self.ensure_writable().await?;
+                let request: api::EmbedLandingRequest = musli_storage::decode(reader)?;
+                let response = super::handle_embed_landing(&self.bg, &request).await?;
+                self.write_body(&response)?;
+            }
+            api::MaintenanceRequest::KIND => {
+                self.ensure_writable().await?;
+                let request = musli_storage::decode(reader)?;
+                let response = super::handle_maintenance(&self.bg, request, self.locale).await?;
+                self.write_body(&response)?;
+            }
+            api::RawEntryRequest::KIND => {
+                let request = musli_storage::decode(reader)?;
+                let response = super::handle_raw_entry(&self.bg, request)
+                    .await
+                    .map_err(|e| e.error)?;
+                self.write_body(&response)?;
+            }
+            _ => bail!(api::ProtocolError::UnsupportedKind),
         }
 
         Ok(())
     }
 
+    /// Receive a message if one is immediately available.
+    async fn try_next(&mut self) -> Poll<Option<Result<Message, axum::Error>>> {
+        poll_fn(|cx| Poll::Ready(Pin::new(&mut self.socket).poll_next(cx))).await
+    }
+
     async fn handle_envelope<'de>(
         &mut self,
         reader: &mut SliceReader<'de>,
+        superseded: bool,
     ) -> Result<(api::ClientRequestEnvelope<'de>, Result<()>)> {
         let request: api::ClientRequestEnvelope = musli_storage::decode(&mut *reader)?;
+
+        if superseded {
+            return Ok((request, Err(anyhow!("Superseded by a later search"))));
+        }
+
+        if request.version > api::PROTOCOL_VERSION {
+            return Ok((request, Err(api::ProtocolError::UnsupportedVersion.into())));
+        }
+
         let result = self.handle_request(reader, &request).await;
         Ok((request, result))
     }
 
-    async fn system_event(&mut self, event: system::Event) -> Result<()> {
+    /// Send captured text to the client, leaving out what's past the
+    /// clipboard limit so that it can be analyzed a chunk at a time.
+    async fn send_text<T>(&mut self, sequence: u64, data: &T) -> Result<()>
+    where
+        T: ?Sized + AsRef<[u8]>,
+    {
+        let data = filter_data(data);
+        let limit = self.bg.clipboard_limit().await;
+
+        let (data, rest) = match std::str::from_utf8(&data) {
+            Ok(text) => {
+                let (chunk, rest) = lib::clipboard::split(text, limit);
+                (chunk.as_bytes(), (!rest.is_empty()).then_some(rest))
+            }
+            Err(..) => (&data[..], None),
+        };
+
+        if let Some(rest) = rest {
+            tracing::debug!(
+                len = data.len(),
+                rest = rest.len(),
+                "Truncated captured text"
+            );
+        }
+
+        self.send(api::ClientEvent::Broadcast(api::Broadcast {
+            kind: api::BroadcastKind::SendClipboardData(api::SendClipboard {
+                ty: Some("text/plain"),
+                data,
+                rest,
+                skipped: None,
+            }),
+            sequence: Some(sequence),
+        }))
+        .await
+    }
+
+    async fn system_event(&mut self, sequence: u64, event: system::Event) -> Result<()> {
         match event {
             system::Event::SendClipboardData(clipboard) => match clipboard.mimetype.as_str() {
                 "UTF8_STRING" | "text/plain;charset=utf-8" => {
-                    let data = filter_data(&clipboard.data);
-
-                    self.send(api::ClientEvent::Broadcast(api::Broadcast {
-                        kind: api::BroadcastKind::SendClipboardData(api::SendClipboard {
-                            ty: Some("text/plain"),
-                            data: data.as_ref(),
-                        }),
-                    }))
-                    .await?;
+                    self.send_text(sequence, &clipboard.data).await?;
                 }
                 "STRING" | "text/plain" => {
                     let Some(data) = decode_escaped(&clipboard.data[..]) else {
@@ -321,76 +676,79 @@ impl Server {
                         return Ok(());
                     };
 
-                    let data = filter_data(&data);
-
-                    self.send(api::ClientEvent::Broadcast(api::Broadcast {
-                        kind: api::BroadcastKind::SendClipboardData(api::SendClipboard {
-                            ty: Some("text/plain"),
-                            data: data.as_ref(),
-                        }),
-                    }))
-                    .await?;
+                    self.send_text(sequence, &data).await?;
                 }
                 ty @ "application/json" => {
+                    let limit = self.bg.clipboard_limit().await;
+
+                    let chunk = serde_json::from_slice::<api::SendClipboardJson>(&clipboard.data)
+                        .ok()
+                        .and_then(|json| json_chunk(json, limit));
+
+                    let (data, rest, skipped) = match &chunk {
+                        Some(chunk) => {
+                            tracing::debug!(
+                                len = chunk.data.len(),
+                                skipped = chunk.skipped,
+                                rest = chunk.rest.as_ref().map(|rest| rest.len()),
+                                "Truncated captured JSON"
+                            );
+
+                            let skipped = (chunk.skipped > 0).then_some(chunk.skipped);
+                            (&chunk.data[..], chunk.rest.as_deref(), skipped)
+                        }
+                        None => (&clipboard.data[..], None, None),
+                    };
+
                     self.send(api::ClientEvent::Broadcast(api::Broadcast {
                         kind: api::BroadcastKind::SendClipboardData(api::SendClipboard {
                             ty: Some(ty),
-                            data: &clipboard.data,
+                            data,
+                            rest,
+                            skipped,
                         }),
+                        sequence: Some(sequence),
                     }))
                     .await?;
                 }
-                ty => {
-                    let Some(tesseract) = self.bg.tesseract() else {
-                        return Ok(());
-                    };
-
-                    let Some(event) = handle_mimetype_image(tesseract, ty, &clipboard).await?
-                    else {
-                        return Ok(());
-                    };
-
-                    self.send(event).await?;
-                }
+                // Images are recognized by the OCR queue.
+                _ => {}
             },
-            system::Event::SendDynamicImage(image) => {
-                let Some(tesseract) = self.bg.tesseract() else {
-                    return Ok(());
-                };
-
-                let Some(event) = handle_image(tesseract, image).await? else {
-                    return Ok(());
-                };
-
-                self.send(event).await?;
-            }
+            system::Event::SendDynamicImage(..) => {}
             system::Event::SendText(text) => {
-                let data = filter_data(&text);
-
-                self.send(api::ClientEvent::Broadcast(api::Broadcast {
-                    kind: api::BroadcastKind::SendClipboardData(api::SendClipboard {
-                        ty: Some("text/plain"),
-                        data: data.as_ref(),
-                    }),
-                }))
-                .await?;
+                self.send_text(sequence, &text).await?;
             }
             system::Event::LogEntry(event) => {
+                if self.bg.is_read_only().await {
+                    return Ok(());
+                }
+
                 self.send(api::OwnedClientEvent::Broadcast(api::OwnedBroadcast {
                     kind: api::OwnedBroadcastKind::LogEntry(event),
+                    sequence: Some(sequence),
                 }))
                 .await?;
             }
             system::Event::TaskProgress(task) => {
+                let summary = task_summary(self.locale, &task.tracker);
+
                 self.send(api::ClientEvent::Broadcast(api::Broadcast {
                     kind: api::BroadcastKind::TaskProgress(api::TaskProgress {
                         name: &task.name,
-                        value: task.value,
-                        total: task.total,
+                        value: task.tracker.value(),
+                        total: task.tracker.total(),
                         step: task.step,
                         steps: task.steps,
                         text: &task.text,
+                        done: task.tracker.done(),
+                        rate: task.tracker.rate(),
+                        remaining: task
+                            .tracker
+                            .remaining()
+                            .map(|remaining| remaining.as_secs()),
+                        summary: &summary,
                     }),
+                    sequence: Some(sequence),
                 }))
                 .await?;
             }
@@ -399,21 +757,90 @@ impl Server {
                     kind: api::BroadcastKind::TaskCompleted(api::TaskCompleted {
                         name: &task.name,
                     }),
+                    sequence: Some(sequence),
                 }))
                 .await?;
             }
             system::Event::Refresh => {
                 self.send(api::ClientEvent::Broadcast(api::Broadcast {
                     kind: api::BroadcastKind::Refresh,
+                    sequence: Some(sequence),
+                }))
+                .await?;
+            }
+            system::Event::Rebind(rebind) => {
+                self.send(api::ClientEvent::Broadcast(api::Broadcast {
+                    kind: api::BroadcastKind::Rebind(api::Rebind {
+                        url: &rebind.url(self.host.as_deref()),
+                    }),
+                    sequence: Some(sequence),
+                }))
+                .await?;
+            }
+            system::Event::OcrJob(job) => {
+                self.send(api::OwnedClientEvent::Broadcast(api::OwnedBroadcast {
+                    kind: api::OwnedBroadcastKind::OcrJob(job),
+                    sequence: Some(sequence),
+                }))
+                .await?;
+            }
+            system::Event::IndexesChanged(changed) => {
+                self.send(api::OwnedClientEvent::Broadcast(api::OwnedBroadcast {
+                    kind: api::OwnedBroadcastKind::IndexesChanged(changed),
+                    sequence: Some(sequence),
                 }))
                 .await?;
             }
+            // Desktop notifications are delivered by the system integration.
+            system::Event::Notify(..) => {}
+            // Searches are only posted to webhooks.
+            system::Event::Searched(..) => {}
         }
 
         Ok(())
     }
 }
 
+/// The chunk of a JSON clipboard payload which is analyzed.
+#[derive(Debug)]
+struct JsonChunk {
+    /// The payload with only the chunk as its primary text.
+    data: Vec<u8>,
+    /// The number of characters before the chunk.
+    skipped: usize,
+    /// The text after the chunk, if any.
+    rest: Option<String>,
+}
+
+/// Limit the primary text of a JSON clipboard payload to the chunk which is
+/// analyzed.
+///
+/// Returns `None` if the text fits within the limit.
+fn json_chunk(mut json: api::SendClipboardJson, limit: usize) -> Option<JsonChunk> {
+    let offset = json.analyze_at.unwrap_or_default();
+    let (start, chunk, rest) = lib::clipboard::chunk_at(&json.primary, limit, offset);
+
+    if start == 0 && rest.is_empty() {
+        return None;
+    }
+
+    let skipped = json.primary[..start].chars().count();
+    let rest = (!rest.is_empty()).then(|| rest.to_owned());
+
+    json.analyze_at = json
+        .analyze_at
+        .and_then(|at| at.checked_sub(start))
+        .filter(|&at| at < chunk.len());
+
+    json.primary = chunk.to_owned();
+    let data = serde_json::to_vec(&json).ok()?;
+    Some(JsonChunk {
+        data,
+        skipped,
+        rest,
+    })
+}
+
 fn decode_escaped(data: &[u8]) -> Option<String> {
     fn h(b: u8) -> Option<u32> {
         let b = match b {
@@ -453,93 +880,36 @@ fn decode_escaped(data: &[u8]) -> Option<String> {
     Some(s)
 }
 
-async fn handle_mimetype_image(
-    tesseract: &Mutex<tesseract::Tesseract>,
-    ty: &str,
-    c: &system::SendClipboardData,
-) -> Result<Option<api::OwnedClientEvent>> {
-    use image::ImageFormat;
-
-    let format = match ty {
-        "image/png" => ImageFormat::Png,
-        "image/tiff" => ImageFormat::Tiff,
-        "image/webp" => ImageFormat::WebP,
-        "image/jpeg" | "image/jpg" => ImageFormat::Jpeg,
-        _ => return Ok(None),
-    };
-
-    tracing::trace!(len = c.data.len(), "Decoding image");
-
-    let image = match image::load_from_memory_with_format(&c.data[..], format) {
-        Ok(image) => image,
-        Err(error) => {
-            tracing::warn!(?error, "Failed to load clipboard image");
-            return Ok(None);
+/// Encode an outgoing message with the compression the client asked for.
+fn compress(compression: api::Compression, data: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        api::Compression::None => Ok(data.to_vec()),
+        api::Compression::Deflate if data.len() < COMPRESSION_THRESHOLD => {
+            let mut output = Vec::with_capacity(data.len() + 1);
+            output.push(api::Compression::PLAIN);
+            output.extend_from_slice(data);
+            Ok(output)
         }
-    };
-
-    handle_image(tesseract, image).await
-}
-
-async fn handle_image(
-    tesseract: &Mutex<tesseract::Tesseract>,
-    image: image::DynamicImage,
-) -> Result<Option<api::OwnedClientEvent>> {
-    let data = image.as_bytes();
-    let width = usize::try_from(image.width())?;
-    let height = usize::try_from(image.height())?;
-    let bytes_per_pixel = usize::from(image.color().bytes_per_pixel());
-
-    tracing::trace!(len = data.len(), width, height, bytes_per_pixel);
-
-    let text = match tesseract
-        .lock()
-        .await
-        .image_to_text(data, width, height, bytes_per_pixel)
-    {
-        Ok(text) => text,
-        Err(error) => {
-            tracing::warn!(?error, "Image recognition failed");
-            return Ok(None);
-        }
-    };
-
-    let trimmed = trim_whitespace(&text[..]);
-
-    tracing::trace!(text = &text[..], ?trimmed, "Recognized");
-
-    Ok(Some(api::OwnedClientEvent::Broadcast(
-        api::OwnedBroadcast {
-            kind: api::OwnedBroadcastKind::SendClipboardData(api::OwnedSendClipboard {
-                ty: Some("text/plain".to_owned()),
-                data: filter_data(trimmed.as_ref()).into(),
-            }),
-        },
-    )))
-}
-
-fn trim_whitespace(input: &str) -> Cow<'_, str> {
-    let mut output = String::new();
-    let mut c = input.char_indices();
-
-    'ws: {
-        for (n, c) in c.by_ref() {
-            if c.is_whitespace() {
-                output.push_str(&input[..n]);
-                break 'ws;
-            }
-        }
-
-        return Cow::Borrowed(input);
-    };
-
-    for (_, c) in c {
-        if !c.is_whitespace() {
-            output.push(c);
+        api::Compression::Deflate => {
+            let output = vec![api::Compression::DEFLATE];
+            let mut encoder = DeflateEncoder::new(output, flate2::Compression::fast());
+            encoder.write_all(data)?;
+            let output = encoder.finish()?;
+            tracing::trace!(
+                len = data.len(),
+                compressed = output.len(),
+                "Compressed message"
+            );
+            Ok(output)
         }
     }
+}
 
-    Cow::Owned(output)
+/// Test if the given message is a search request.
+fn is_search(bytes: &[u8]) -> bool {
+    let mut reader = SliceReader::new(bytes);
+    let request: Result<api::ClientRequestEnvelope<'_>, _> = musli_storage::decode(&mut reader);
+    matches!(request, Ok(request) if request.kind == api::SearchRequest::KIND)
 }
 
 fn filter_data<T>(data: &T) -> Cow<'_, [u8]>
@@ -594,3 +964,49 @@ where
 
     Cow::Owned(output)
 }
+
+/// Format the progress of the current step of a task, like
+/// `12,345 / 200,000 (1,234/s)`.
+fn task_summary(locale: Locale, tracker: &Tracker) -> String {
+    let mut summary = locale.number(tracker.value() as u64);
+
+    if let Some(total) = tracker.total() {
+        summary.push_str(" / ");
+        summary.push_str(&locale.number(total as u64));
+    }
+
+    if let Some(rate) = tracker.rate() {
+        summary.push_str(&format!(" ({}/s)", locale.decimal(rate.into(), 0)));
+    }
+
+    summary
+}
+
+#[test]
+fn test_json_chunk() {
+    let json = |primary: &str, analyze_at| api::SendClipboardJson {
+        primary: primary.to_owned(),
+        secondary: None,
+        analyze_at,
+    };
+
+    let primary = |chunk: &JsonChunk| {
+        serde_json::from_slice::<api::SendClipboardJson>(&chunk.data)
+            .unwrap()
+            .primary
+    };
+
+    assert!(json_chunk(json("今日は。", None), 5).is_none());
+
+    let chunk = json_chunk(json("今日は。明日は。", None), 5).unwrap();
+    assert_eq!(primary(&chunk), "今日は。");
+    assert_eq!(chunk.skipped, 0);
+    assert_eq!(chunk.rest.as_deref(), Some("明日は。"));
+
+    // Analyzing the last chunk leaves nothing after it, but what came before
+    // it is reported.
+    let chunk = json_chunk(json("今日は。明日は。", Some(12)), 5).unwrap();
+    assert_eq!(primary(&chunk), "明日は。");
+    assert_eq!(chunk.skipped, 4);
+    assert_eq!(chunk.rest, None);
+}
@@ -0,0 +1,242 @@
+//! Webhooks which are posted a JSON summary of searches and clipboard
+//! captures, to integrate with automations like note-taking tools.
+//!
+//! Searches are performed as the user types, so a search is only posted once
+//! no other search has followed it for [`SETTLE`]. Each post is made in a task
+//! of its own, and failed posts are retried with exponential backoff.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use lib::api;
+use lib::config::WebhookEvent;
+use lib::database;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+use crate::background::{self, Background};
+use crate::system;
+
+/// How long a search has to go without being followed by another one before
+/// it's posted.
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// The number of times a failed post is retried.
+const RETRIES: u32 = 4;
+
+/// How long to wait before the first retry, which is doubled for every
+/// following retry.
+const BACKOFF: Duration = Duration::from_secs(1);
+
+/// The number of phrases included in the summary of a search.
+const SEARCH_LIMIT: usize = 5;
+
+/// Summary of a search.
+#[derive(Clone, Serialize)]
+pub(crate) struct Search {
+    query: String,
+    /// The number of phrases and names found.
+    total: usize,
+    /// The best matching phrases.
+    phrases: Vec<Phrase>,
+}
+
+/// Summary of a phrase found by a search.
+#[derive(Clone, Serialize)]
+struct Phrase {
+    sequence: u64,
+    text: String,
+    reading: String,
+    /// English glosses of the first sense.
+    glosses: Vec<String>,
+}
+
+/// The payload which is posted to webhooks.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum Payload {
+    Search {
+        #[serde(flatten)]
+        search: Search,
+        at: u64,
+    },
+    Clipboard {
+        text: String,
+        at: u64,
+    },
+}
+
+/// Summarize a search for webhooks.
+pub(crate) fn search(query: &str, search: &database::Search<'_>) -> Search {
+    let phrases = search
+        .phrases
+        .iter()
+        .take(SEARCH_LIMIT)
+        .filter_map(|(_, entry)| {
+            let (kanji, reading) = entry.preferred_form()?;
+
+            let glosses = entry
+                .senses
+                .first()
+                .into_iter()
+                .flat_map(|sense| sense.gloss.iter())
+                .filter(|gloss| gloss.lang.unwrap_or("eng") == "eng")
+                .map(|gloss| gloss.text.to_owned())
+                .collect();
+
+            Some(Phrase {
+                sequence: entry.sequence,
+                text: kanji.map_or(reading.text, |k| k.text).to_owned(),
+                reading: reading.text.to_owned(),
+                glosses,
+            })
+        })
+        .collect();
+
+    Search {
+        query: query.to_owned(),
+        total: search.phrases.len() + search.names.len(),
+        phrases,
+    }
+}
+
+/// Post events to the configured webhooks until the service shuts down.
+pub(crate) async fn run(background: Background, system_events: system::SystemEvents) {
+    let mut receiver = system_events.subscribe();
+    let mut pending = None::<(Instant, Search)>;
+
+    loop {
+        let deadline = pending.as_ref().map(|(deadline, _)| *deadline);
+
+        let settled = async move {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            event = receiver.recv() => {
+                let text = match event {
+                    Ok(system::Event::Searched(search)) => {
+                        pending = Some((Instant::now() + SETTLE, search));
+                        continue;
+                    }
+                    Ok(system::Event::SendClipboardData(clipboard)) => {
+                        let Some(text) = clipboard_text(&clipboard) else {
+                            continue;
+                        };
+
+                        text
+                    }
+                    Ok(system::Event::SendText(text)) => text,
+                    Ok(..) => continue,
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("Webhooks missed {n} event(s)");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let at = background::now();
+                post(&background, WebhookEvent::Clipboard, &Payload::Clipboard { text, at }).await;
+            }
+            _ = settled => {
+                if let Some((_, search)) = pending.take() {
+                    let at = background::now();
+                    post(&background, WebhookEvent::Search, &Payload::Search { search, at }).await;
+                }
+            }
+        }
+    }
+}
+
+/// Get the text of captured clipboard data, if it's text.
+fn clipboard_text(clipboard: &system::SendClipboardData) -> Option<String> {
+    match clipboard.mimetype.as_str() {
+        "UTF8_STRING" | "STRING" | "text/plain" | "text/plain;charset=utf-8" => {
+            Some(String::from_utf8_lossy(&clipboard.data).into_owned())
+        }
+        "application/json" => {
+            let json = serde_json::from_slice::<api::SendClipboardJson>(&clipboard.data).ok()?;
+            Some(json.primary)
+        }
+        // Images are posted once text has been recognized in them.
+        _ => None,
+    }
+}
+
+/// Post a payload to every webhook which wants the event.
+async fn post(background: &Background, event: WebhookEvent, payload: &Payload) {
+    let webhooks = background.config().await.webhooks;
+
+    if !webhooks.iter().any(|webhook| webhook.wants(event)) {
+        return;
+    }
+
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(error) => {
+            tracing::warn!("Failed to encode webhook payload: {error}");
+            return;
+        }
+    };
+
+    for webhook in webhooks {
+        if webhook.wants(event) {
+            tokio::spawn(deliver(webhook.url, body.clone()));
+        }
+    }
+}
+
+/// Deliver a payload to a webhook, retrying with exponential backoff.
+async fn deliver(url: String, body: Vec<u8>) {
+    let mut backoff = BACKOFF;
+
+    for attempt in 0..=RETRIES {
+        let error = match send(&url, &body).await {
+            Ok(()) => return,
+            Err(error) => error,
+        };
+
+        if attempt == RETRIES {
+            tracing::warn!("Failed to post to webhook {url}: {error:#}");
+            return;
+        }
+
+        tracing::debug!(
+            "Failed to post to webhook {url}, retrying in {}s: {error:#}",
+            backoff.as_secs()
+        );
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+#[cfg(not(feature = "reqwest"))]
+async fn send(_: &str, _: &[u8]) -> Result<()> {
+    anyhow::bail!("Webhooks are not supported")
+}
+
+#[cfg(feature = "reqwest")]
+async fn send(url: &str, body: &[u8]) -> Result<()> {
+    use reqwest::header::CONTENT_TYPE;
+
+    /// How long to wait for a webhook to respond.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    let client = reqwest::ClientBuilder::new().timeout(TIMEOUT).build()?;
+
+    client
+        .post(url)
+        .header("User-Agent", crate::USER_AGENT)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_vec())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
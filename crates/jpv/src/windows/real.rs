@@ -35,12 +35,15 @@ impl Start for Windows {
         system_events: &'a SystemEvents,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + 'a>> {
         Box::pin(async move {
+            let mut port = port;
             let mut shutdown = pin!(Fuse::new(shutdown));
+            let mut events = system_events.subscribe();
             let mut window = winctx::CreateWindow::new(NAME).clipboard_events(true);
 
             let icon = window.icons().insert_buffer(ICON, 22, 22);
 
             let area = window.new_area().icon(icon);
+            let area_id = area.id();
             let menu = area.popup_menu();
 
             let open = menu
@@ -55,6 +58,21 @@ impl Start for Windows {
                     _ = shutdown.as_mut() => {
                         sender.shutdown();
                     },
+                    event = events.recv() => {
+                        match event {
+                            Ok(system::Event::Notify(notification)) => {
+                                sender
+                                    .notification(area_id)
+                                    .title(notification.summary)
+                                    .message(notification.body)
+                                    .send();
+                            }
+                            Ok(system::Event::Rebind(rebind)) => {
+                                port = rebind.port;
+                            }
+                            _ => {}
+                        }
+                    },
                     event = event_loop.tick() => {
                         match event? {
                             Event::Clipboard { event, .. } => match event {
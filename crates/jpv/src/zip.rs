@@ -0,0 +1,249 @@
+//! Minimal reader for zip archives, which is what Yomichan dictionaries are
+//! distributed as.
+//!
+//! Only archives with stored or deflated entries are supported, which covers
+//! what dictionaries are packaged with in practice.
+
+use std::io::Read;
+
+use anyhow::{bail, Context, Result};
+use flate2::read::DeflateDecoder;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// An entry in a zip archive.
+pub(crate) struct Entry<'a> {
+    data: &'a [u8],
+    name: &'a str,
+    method: u16,
+    flags: u16,
+    compressed_size: usize,
+    size: usize,
+    offset: usize,
+}
+
+impl<'a> Entry<'a> {
+    /// The name of the entry.
+    pub(crate) fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Read the decompressed contents of the entry.
+    pub(crate) fn read(&self) -> Result<Vec<u8>> {
+        if self.flags & 1 != 0 {
+            bail!("{}: Encrypted entries are not supported", self.name);
+        }
+
+        let header = self.offset;
+
+        if u32_at(self.data, header)? != LOCAL_FILE_HEADER {
+            bail!("{}: Missing local file header", self.name);
+        }
+
+        let name_len = u16_at(self.data, header + 26)? as usize;
+        let extra_len = u16_at(self.data, header + 28)? as usize;
+        let start = header + 30 + name_len + extra_len;
+
+        let Some(compressed) = self.data.get(start..start + self.compressed_size) else {
+            bail!("{}: Entry out of bounds", self.name);
+        };
+
+        match self.method {
+            STORED => Ok(compressed.to_vec()),
+            DEFLATED => {
+                // The size is only a hint, since it's read from the archive.
+                let mut output = Vec::with_capacity(self.size.min(self.data.len()));
+                DeflateDecoder::new(compressed)
+                    .read_to_end(&mut output)
+                    .with_context(|| self.name.to_owned())?;
+                Ok(output)
+            }
+            method => bail!("{}: Unsupported compression method {method}", self.name),
+        }
+    }
+}
+
+/// List the file entries of a zip archive.
+pub(crate) fn entries(data: &[u8]) -> Result<Vec<Entry<'_>>> {
+    let end = find_end(data).context("Not a zip archive")?;
+
+    let count = u16_at(data, end + 10)? as usize;
+    let mut at = u32_at(data, end + 16)? as usize;
+
+    if at == u32::MAX as usize {
+        bail!("Zip64 archives are not supported");
+    }
+
+    let mut entries = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        if u32_at(data, at)? != CENTRAL_DIRECTORY_HEADER {
+            bail!("Corrupt central directory at offset {at}");
+        }
+
+        let flags = u16_at(data, at + 8)?;
+        let method = u16_at(data, at + 10)?;
+        let compressed_size = u32_at(data, at + 20)?;
+        let size = u32_at(data, at + 24)?;
+        let name_len = u16_at(data, at + 28)? as usize;
+        let extra_len = u16_at(data, at + 30)? as usize;
+        let comment_len = u16_at(data, at + 32)? as usize;
+        let offset = u32_at(data, at + 42)?;
+
+        if [compressed_size, size, offset].contains(&u32::MAX) {
+            bail!("Zip64 archives are not supported");
+        }
+
+        let name = data
+            .get(at + 46..at + 46 + name_len)
+            .context("Entry name out of bounds")?;
+        let name = std::str::from_utf8(name).context("Entry name is not UTF-8")?;
+
+        // Directories have no contents.
+        if !name.ends_with('/') {
+            entries.push(Entry {
+                data,
+                name,
+                method,
+                flags,
+                compressed_size: compressed_size as usize,
+                size: size as usize,
+                offset: offset as usize,
+            });
+        }
+
+        at += 46 + name_len + extra_len + comment_len;
+    }
+
+    Ok(entries)
+}
+
+/// Find the end of central directory record, which is followed by a comment
+/// of at most `u16::MAX` bytes.
+fn find_end(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(22)?;
+    let first = last.saturating_sub(u16::MAX as usize);
+
+    (first..=last)
+        .rev()
+        .find(|&at| u32_at(data, at).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16> {
+    let Some(&[a, b]) = data.get(at..at + 2) else {
+        bail!("Unexpected end of archive at offset {at}");
+    };
+
+    Ok(u16::from_le_bytes([a, b]))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32> {
+    let Some(&[a, b, c, d]) = data.get(at..at + 4) else {
+        bail!("Unexpected end of archive at offset {at}");
+    };
+
+    Ok(u32::from_le_bytes([a, b, c, d]))
+}
+
+/// Build an archive of the given entries, deflating them if `deflate` is set.
+#[cfg(test)]
+fn archive(entries: &[(&str, &[u8])], deflate: bool, size: Option<u32>) -> Vec<u8> {
+    use std::io::Write;
+
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+
+    for &(name, contents) in entries {
+        let (method, data) = if deflate {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(contents).unwrap();
+            (DEFLATED, encoder.finish().unwrap())
+        } else {
+            (STORED, contents.to_vec())
+        };
+
+        let offset = out.len() as u32;
+        let size = size.unwrap_or(contents.len() as u32);
+
+        out.extend(LOCAL_FILE_HEADER.to_le_bytes());
+        out.extend([0; 22]);
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        out.extend(name.as_bytes());
+        out.extend(&data);
+
+        directory.extend(CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+        directory.extend([0; 4]);
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(method.to_le_bytes());
+        directory.extend([0; 8]);
+        directory.extend((data.len() as u32).to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend((name.len() as u16).to_le_bytes());
+        directory.extend([0; 12]);
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+
+    let start = out.len() as u32;
+    let len = directory.len() as u32;
+    out.extend(directory);
+
+    out.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    out.extend([0; 6]);
+    out.extend((entries.len() as u16).to_le_bytes());
+    out.extend(len.to_le_bytes());
+    out.extend(start.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+    out
+}
+
+#[test]
+fn test_entries() {
+    let files: [(&str, &[u8]); 3] = [
+        ("index.json", b"{\"title\":\"Test\"}"),
+        ("banks/", b""),
+        ("banks/term_bank_1.json", b"[]"),
+    ];
+
+    for deflate in [false, true] {
+        let data = archive(&files, deflate, None);
+        let entries = entries(&data).unwrap();
+
+        let names = entries.iter().map(Entry::name).collect::<Vec<_>>();
+        assert_eq!(names, ["index.json", "banks/term_bank_1.json"]);
+        assert_eq!(entries[0].read().unwrap(), files[0].1);
+        assert_eq!(entries[1].read().unwrap(), files[2].1);
+    }
+}
+
+#[test]
+fn test_untrusted_size() {
+    // A size which is far larger than the archive doesn't allocate it.
+    let data = archive(&[("index.json", b"{}")], true, Some(u32::MAX - 1));
+    let entries = entries(&data).unwrap();
+    assert_eq!(entries[0].read().unwrap(), b"{}");
+}
+
+#[test]
+fn test_corrupt() {
+    let data = archive(&[("index.json", b"{}")], false, None);
+
+    assert!(entries(b"not a zip archive").is_err());
+    assert!(entries(&data[..data.len() - 1]).is_err());
+
+    // An entry which claims to be larger than the archive.
+    let mut data = data;
+    let end = find_end(&data).unwrap();
+    let at = u32_at(&data, end + 16).unwrap() as usize + 20;
+    data[at..at + 4].copy_from_slice(&1024u32.to_le_bytes());
+    assert!(entries(&data).unwrap()[0].read().is_err());
+}
@@ -1,7 +1,7 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 use lib::api;
-use lib::config::ConfigIndex;
+use lib::config::{ConfigIndex, EmbedLanding};
 use yew::prelude::*;
 
 use crate::c;
@@ -12,6 +12,23 @@ pub(crate) enum Msg {
     GetConfig(api::GetConfigResult),
     Toggle(String),
     ToggleOcr,
+    ToggleNotifications,
+    ToggleDigest,
+    ToggleKanjiVariants,
+    ToggleCollapseNames,
+    ToggleCommonOnly,
+    ToggleWarmup,
+    ChangeEmbedLanding(EmbedLanding),
+    ChangeBind(String),
+    ChangeMpv(String),
+    ChangeLogFilter(String),
+    ChangeExampleAudio(String),
+    ChangeClipboardThreshold(String),
+    ChangeClipboardLimit(String),
+    PinPattern(String),
+    PinSequences(String),
+    PinAdd,
+    PinRemove(String),
     IndexAdd,
     IndexAddSave(String, ConfigIndex),
     IndexAddCancel,
@@ -49,10 +66,13 @@ pub(crate) struct Config {
     pending: bool,
     state: Option<State>,
     installed: HashSet<String>,
+    languages: BTreeMap<String, Vec<String>>,
     missing_ocr: Option<api::MissingOcr>,
     edit_index: HashSet<String>,
     update_indexes: HashSet<String>,
     index_add: bool,
+    pin_pattern: String,
+    pin_sequences: String,
     request: ws::Request,
 }
 
@@ -73,10 +93,13 @@ impl Component for Config {
             pending: true,
             state: None,
             installed: HashSet::new(),
+            languages: BTreeMap::new(),
             missing_ocr: None,
             edit_index: HashSet::new(),
             update_indexes: HashSet::new(),
             index_add: false,
+            pin_pattern: String::new(),
+            pin_sequences: String::new(),
             request,
         }
     }
@@ -90,6 +113,7 @@ impl Component for Config {
                 });
 
                 self.installed = result.installed;
+                self.languages = result.languages;
                 self.missing_ocr = result.missing_ocr;
                 self.pending = false;
             }
@@ -103,6 +127,104 @@ impl Component for Config {
                     state.local.ocr = !state.local.ocr;
                 }
             }
+            Msg::ToggleNotifications => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.notifications = !state.local.notifications;
+                }
+            }
+            Msg::ToggleDigest => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.digest = !state.local.digest;
+                }
+            }
+            Msg::ToggleKanjiVariants => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.kanji_variants = !state.local.kanji_variants;
+                }
+            }
+            Msg::ToggleCollapseNames => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.collapse_names = !state.local.collapse_names;
+                }
+            }
+            Msg::ToggleCommonOnly => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.common_only = !state.local.common_only;
+                }
+            }
+            Msg::ToggleWarmup => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.warmup = !state.local.warmup;
+                }
+            }
+            Msg::ChangeEmbedLanding(embed_landing) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.embed_landing = embed_landing;
+                }
+            }
+            Msg::ChangeBind(bind) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.bind = (!bind.is_empty()).then_some(bind);
+                }
+            }
+            Msg::ChangeMpv(mpv) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.mpv = (!mpv.is_empty()).then_some(mpv);
+                }
+            }
+            Msg::ChangeLogFilter(log_filter) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.log_filter = (!log_filter.is_empty()).then_some(log_filter);
+                }
+            }
+            Msg::ChangeExampleAudio(example_audio) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.example_audio =
+                        (!example_audio.is_empty()).then_some(example_audio);
+                }
+            }
+            Msg::ChangeClipboardThreshold(threshold) => {
+                if let Some(state) = self.state.as_mut() {
+                    if let Ok(threshold) = threshold.parse::<u32>() {
+                        state.local.clipboard_threshold = threshold.min(100);
+                    }
+                }
+            }
+            Msg::ChangeClipboardLimit(limit) => {
+                if let Some(state) = self.state.as_mut() {
+                    if let Ok(limit) = limit.parse::<u32>() {
+                        state.local.clipboard_limit = limit;
+                    }
+                }
+            }
+            Msg::PinPattern(pattern) => {
+                self.pin_pattern = pattern;
+            }
+            Msg::PinSequences(sequences) => {
+                self.pin_sequences = sequences;
+            }
+            Msg::PinAdd => {
+                let sequences = self
+                    .pin_sequences
+                    .split([',', ' '])
+                    .filter_map(|s| s.trim().parse::<u32>().ok())
+                    .collect::<Vec<_>>();
+
+                if let Some(state) = self.state.as_mut() {
+                    if !self.pin_pattern.is_empty() && !sequences.is_empty() {
+                        state
+                            .local
+                            .pins
+                            .insert(std::mem::take(&mut self.pin_pattern), sequences);
+                        self.pin_sequences.clear();
+                    }
+                }
+            }
+            Msg::PinRemove(pattern) => {
+                if let Some(state) = self.state.as_mut() {
+                    state.local.pins.remove(&pattern);
+                }
+            }
             Msg::IndexAdd => {
                 self.index_add = true;
             }
@@ -206,6 +328,20 @@ impl Component for Config {
     fn view(&self, ctx: &Context<Self>) -> Html {
         let mut indexes = Vec::new();
         let mut ocr = None;
+        let mut notifications = None;
+        let mut digest = None;
+        let mut bind = None;
+        let mut kanji_variants = None;
+        let mut collapse_names = None;
+        let mut common_only = None;
+        let mut warmup = None;
+        let mut embed_landing = None;
+        let mut mpv = None;
+        let mut log_filter = None;
+        let mut example_audio = None;
+        let mut clipboard_threshold = None;
+        let mut clipboard_limit = None;
+        let mut pins = None;
 
         if let Some(state) = &self.state {
             for (id, index) in &state.local.indexes {
@@ -279,6 +415,12 @@ impl Component for Config {
                         }
                     });
 
+                    let languages = self.languages.get(id).map(|languages| {
+                        html! {
+                            <span class="bullet" title="Languages of meanings in this dictionary">{languages.join(", ")}</span>
+                        }
+                    });
+
                     let updated = is_updated.then(|| {
                         html! {
                             <span title="Has been updated and will be applied on Save">{"＊"}</span>
@@ -288,10 +430,11 @@ impl Component for Config {
                     indexes.push(html! {
                         <div {class}>
                             <input id={id.to_owned()} type="checkbox" {checked} disabled={self.pending || installing} {onchange} />
-                            <label for={id.to_owned()}>{id.to_owned()}</label>
+                            <label for={id.to_owned()} title={id.to_owned()}>{state.local.index_name(id).to_owned()}</label>
                             <label for={id.to_owned()}>{index.description.clone()}</label>
                             {for updated}
                             {not_installed}
+                            {languages}
                             <button class="btn btn-primary row-end index-edit" {onclick} title={"Change this dictionary"}>{"Edit"}</button>
                             {help}
                         </div>
@@ -341,6 +484,251 @@ impl Component for Config {
                     </>
                 }
             });
+
+            notifications = Some({
+                let checked = state.local.notifications;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleNotifications);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="notifications" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="notifications">{"Notify when tasks like rebuilds finish"}</label>
+                    </div>
+                }
+            });
+
+            digest = Some({
+                let checked = state.local.digest;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleDigest);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="digest" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="digest">{"Remind me daily of the words I looked up"}</label>
+                    </div>
+                }
+            });
+
+            kanji_variants = Some({
+                let checked = state.local.kanji_variants;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleKanjiVariants);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="kanji-variants" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="kanji-variants">{"Match old and variant forms of kanji, like 國 for 国"}</label>
+                    </div>
+                }
+            });
+
+            collapse_names = Some({
+                let checked = state.local.collapse_names;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleCollapseNames);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="collapse-names" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="collapse-names">{"Collapse names which are written and read the same as a phrase"}</label>
+                    </div>
+                }
+            });
+
+            common_only = Some({
+                let checked = state.local.common_only;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleCommonOnly);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="common-only" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="common-only">{"Only show common words, unless searching with #all"}</label>
+                    </div>
+                }
+            });
+
+            warmup = Some({
+                let checked = state.local.warmup;
+
+                let onchange = ctx.link().callback(move |_| Msg::ToggleWarmup);
+
+                html! {
+                    <div class="block row row-spaced">
+                        <input id="warmup" type="checkbox" {checked} disabled={self.pending} {onchange} />
+                        <label for="warmup">{"Read dictionary lookups into memory after startup"}</label>
+                    </div>
+                }
+            });
+
+            embed_landing = Some({
+                let current = state.local.embed_landing;
+
+                let onchange = ctx.link().batch_callback(|e: Event| {
+                    let select = e.target_dyn_into::<web_sys::HtmlSelectElement>()?;
+                    let value = select.value();
+                    let landing = EmbedLanding::all().into_iter().find(|l| l.id() == value)?;
+                    Some(Msg::ChangeEmbedLanding(landing))
+                });
+
+                let options = EmbedLanding::all().into_iter().map(|landing| {
+                    html! {
+                        <option value={landing.id()} selected={current == landing}>{landing.description()}</option>
+                    }
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="embed-landing">{"Shown when opened without a query"}</label>
+                        <select id="embed-landing" disabled={self.pending} {onchange}>{for options}</select>
+                    </div>
+                }
+            });
+
+            bind = Some({
+                let value = state.local.bind.clone().unwrap_or_default();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeBind(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="bind">{"Bind address"}</label>
+                        <input id="bind" type="text" placeholder="127.0.0.1:44714" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            mpv = Some({
+                let value = state.local.mpv.clone().unwrap_or_default();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeMpv(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="mpv">{"mpv IPC socket"}</label>
+                        <input id="mpv" type="text" placeholder="/tmp/mpvsocket" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            log_filter = Some({
+                let value = state.local.log_filter.clone().unwrap_or_default();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeLogFilter(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="log-filter" title="Uses the same syntax as RUST_LOG, and is applied without restarting">{"Log filter"}</label>
+                        <input id="log-filter" type="text" placeholder="jpv=info" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            example_audio = Some({
+                let value = state.local.example_audio.clone().unwrap_or_default();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeExampleAudio(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="example-audio">{"Audio URL"}</label>
+                        <input id="example-audio" type="text" placeholder="https://example.com/audio/{id}.mp3" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            clipboard_threshold = Some({
+                let value = state.local.clipboard_threshold.to_string();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeClipboardThreshold(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="clipboard-threshold">{"Percentage of kana or kanji required to search captured text"}</label>
+                        <input id="clipboard-threshold" type="number" min="0" max="100" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            clipboard_limit = Some({
+                let value = state.local.clipboard_limit.to_string();
+
+                let oninput = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::ChangeClipboardLimit(input.value()))
+                });
+
+                html! {
+                    <div class="block row row-spaced">
+                        <label for="clipboard-limit">{"Characters of captured text analyzed at once (0 for no limit)"}</label>
+                        <input id="clipboard-limit" type="number" min="0" {value} disabled={self.pending} {oninput} />
+                    </div>
+                }
+            });
+
+            pins = Some({
+                let existing = state.local.pins.iter().map(|(pattern, sequences)| {
+                    let onclick = ctx.link().callback({
+                        let pattern = pattern.clone();
+                        move |_| Msg::PinRemove(pattern.clone())
+                    });
+
+                    let sequences = sequences
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    html! {
+                        <div class="block row row-spaced">
+                            <span class="text">{pattern}</span>
+                            <span class="text">{sequences}</span>
+                            <button class="row-end btn btn-danger" disabled={self.pending} {onclick}>{"Remove"}</button>
+                        </div>
+                    }
+                });
+
+                let onpattern = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::PinPattern(input.value()))
+                });
+
+                let onsequences = ctx.link().batch_callback(|e: InputEvent| {
+                    let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                    Some(Msg::PinSequences(input.value()))
+                });
+
+                let onclick = ctx.link().callback(|_| Msg::PinAdd);
+
+                html! {
+                    <>
+                        {for existing}
+
+                        <div class="block row row-spaced">
+                            <input type="text" placeholder="ある" value={self.pin_pattern.clone()} disabled={self.pending} oninput={onpattern} />
+                            <input type="text" placeholder="1296400" value={self.pin_sequences.clone()} disabled={self.pending} oninput={onsequences} />
+                            <button class="row-end btn btn-primary" disabled={self.pending} {onclick}>{"Pin"}</button>
+                        </div>
+                    </>
+                }
+            });
         }
 
         let add = if self.index_add {
@@ -403,10 +791,21 @@ impl Component for Config {
             }
         });
 
+        let read_only = matches!(&self.state, Some(s) if s.remote.read_only);
+
         let disabled = self.pending
+            || read_only
             || matches!(&self.state, Some(s) if s.local == s.remote)
                 && self.update_indexes.is_empty();
 
+        let read_only = read_only.then(|| {
+            html! {
+                <div class="block block-lg row row-spaced">
+                    {"This is a read-only instance, so changes can't be saved."}
+                </div>
+            }
+        });
+
         let pending = self.pending.then(|| {
             html! {
                 <div class="block block-lg row row-spaced">
@@ -423,17 +822,76 @@ impl Component for Config {
                 </div>
 
                 {pending}
+                {read_only}
 
                 <h5>{"Dictionaries"}</h5>
                 <div class="block block-lg">{dictionaries}</div>
 
+                <h5>{"Search"}</h5>
+
+                <div class="block block-lg">
+                    {for kanji_variants}
+                    {for collapse_names}
+                    {for common_only}
+                </div>
+
+                <h5>{"Embedded view"}</h5>
+
+                <div class="block block-lg">
+                    {for embed_landing}
+                </div>
+
                 <h5>{"OCR"}</h5>
 
                 <div class="block block-lg">
                     {for ocr}
                 </div>
 
+                <h5>{"Notifications"}</h5>
+
+                <div class="block block-lg">
+                    {for notifications}
+                    {for digest}
+                </div>
+
+                <h5>{"Server"}</h5>
+
+                <div class="block block-lg">
+                    {for bind}
+                    {for warmup}
+                </div>
+
+                <h5>{"Clipboard"}</h5>
+
+                <div class="block block-lg">
+                    {for clipboard_threshold}
+                    {for clipboard_limit}
+                </div>
+
+                <h5>{"Media players"}</h5>
+
+                <div class="block block-lg">
+                    {for mpv}
+                </div>
+
+                <h5>{"Example sentences"}</h5>
+
+                <div class="block block-lg">
+                    {for example_audio}
+                </div>
+
+                <h5>{"Pinned entries"}</h5>
+
+                <div class="block block-lg">
+                    {for pins}
+                </div>
+
                 <h5>{"Log"}</h5>
+
+                <div class="block block-lg">
+                    {for log_filter}
+                </div>
+
                 {log}
             </>
         }
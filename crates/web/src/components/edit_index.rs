@@ -5,9 +5,11 @@ use yew::prelude::*;
 pub(crate) enum Msg {
     ChangeId(String),
     ChangeFormat(IndexFormat),
+    ChangeName(String),
     ChangeDescription(String),
     ChangeUrl(String),
     ChangeHelp(String),
+    ChangeFrequencies(String),
     Save,
 }
 
@@ -46,9 +48,11 @@ pub(crate) struct Props {
 pub(crate) struct EditIndex {
     id: String,
     format: IndexFormat,
+    name: String,
     description: String,
     url: String,
     help: String,
+    frequencies: String,
     errors: Errors,
 }
 
@@ -62,11 +66,15 @@ impl Component for EditIndex {
         Self {
             id: String::new(),
             format: index.map(|i| i.format).unwrap_or_default(),
+            name: index.and_then(|i| i.name.clone()).unwrap_or_default(),
             description: index
                 .and_then(|i| i.description.clone())
                 .unwrap_or_default(),
             url: index.map(|i| i.url.clone()).unwrap_or_default(),
             help: index.and_then(|i| i.help.clone()).unwrap_or_default(),
+            frequencies: index
+                .and_then(|i| i.frequencies.clone())
+                .unwrap_or_default(),
             errors: Errors::default(),
         }
     }
@@ -80,6 +88,9 @@ impl Component for EditIndex {
             Msg::ChangeFormat(format) => {
                 self.format = format;
             }
+            Msg::ChangeName(name) => {
+                self.name = name;
+            }
             Msg::ChangeDescription(description) => {
                 self.description = description;
             }
@@ -91,6 +102,9 @@ impl Component for EditIndex {
                 self.help = help;
                 self.validate(ctx);
             }
+            Msg::ChangeFrequencies(frequencies) => {
+                self.frequencies = frequencies;
+            }
             Msg::Save => {
                 self.validate(ctx);
 
@@ -99,6 +113,11 @@ impl Component for EditIndex {
                         enabled: true,
                         installing: false,
                         format: self.format,
+                        name: if self.name.is_empty() {
+                            None
+                        } else {
+                            Some(self.name.clone())
+                        },
                         description: Some(self.description.clone()),
                         url: self.url.clone(),
                         help: if self.help.is_empty() {
@@ -106,6 +125,13 @@ impl Component for EditIndex {
                         } else {
                             Some(self.help.clone())
                         },
+                        frequencies: if self.frequencies.is_empty()
+                            || self.format != IndexFormat::Jmnedict
+                        {
+                            None
+                        } else {
+                            Some(self.frequencies.clone())
+                        },
                     };
 
                     if let Some(onsave) = &ctx.props().onsavenew {
@@ -129,6 +155,14 @@ impl Component for EditIndex {
             }
         });
 
+        let onchangename = ctx.link().batch_callback({
+            move |e: Event| {
+                let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                let name = input.value();
+                Some(Msg::ChangeName(name))
+            }
+        });
+
         let onchangedescription = ctx.link().batch_callback({
             move |e: Event| {
                 let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
@@ -153,6 +187,14 @@ impl Component for EditIndex {
             }
         });
 
+        let onchangefrequencies = ctx.link().batch_callback({
+            move |e: Event| {
+                let input = e.target_dyn_into::<web_sys::HtmlInputElement>()?;
+                let frequencies = input.value();
+                Some(Msg::ChangeFrequencies(frequencies))
+            }
+        });
+
         let oncancel = ctx.props().oncancel.reform(|_| ());
 
         let onsave = ctx.link().callback(move |_| Msg::Save);
@@ -238,6 +280,22 @@ impl Component for EditIndex {
             .as_ref()
             .map(|error| html!(<p class="form-error">{error.clone()}</p>));
 
+        let frequencies = match self.format {
+            IndexFormat::Jmdict => Some(("Word Frequencies", "Path to a word frequency list like the BCCWJ, used to rank common words higher. Changing it requires the dictionary to be rebuilt.")),
+            IndexFormat::Jmnedict => Some(("Name Frequencies", "Path to a file with a name and how common it is on each line, used to rank names. Changing it requires the dictionary to be rebuilt.")),
+            _ => None,
+        };
+
+        let frequencies = frequencies.map(|(title, help)| {
+            html! {
+                <div class="block form">
+                    <h6>{title}</h6>
+                    <p class="form-help">{help}</p>
+                    <input type="text" disabled={ctx.props().pending} value={self.frequencies.clone()} onchange={onchangefrequencies} />
+                </div>
+            }
+        });
+
         let options = IndexFormat::all().into_iter().map(|format| {
             html! {
                 <option value={format.id()} selected={self.format == format}>{format.description()}</option>
@@ -258,6 +316,11 @@ impl Component for EditIndex {
                     <input type="text" disabled={ctx.props().pending} value={self.url.clone()} onchange={onchangeurl} />
                     <>{url_error}</>
                 </div>
+                <div class="block form">
+                    <h6>{"Name"}</h6>
+                    <p class="form-help">{"The name the dictionary is shown as in the settings and next to search results, instead of its id."}</p>
+                    <input type="text" disabled={ctx.props().pending} value={self.name.clone()} onchange={onchangename} />
+                </div>
                 <div class="block form">
                     <h6>{"Description"}</h6>
                     <input type="text" disabled={ctx.props().pending} value={self.description.clone()} onchange={onchangedescription} />
@@ -267,6 +330,7 @@ impl Component for EditIndex {
                     <input type="text" disabled={ctx.props().pending} value={self.help.clone()} onchange={onchangehelp} />
                     <>{help_error}</>
                 </div>
+                {frequencies}
                 <div class="block row row-spaced">
                     <button class="btn" disabled={ctx.props().pending} onclick={oncancel}>{"Cancel"}</button>
 
@@ -1,5 +1,7 @@
 use std::collections::BTreeSet;
 
+use lib::accent::{self, Notation};
+use lib::api::OwnedReadingAccent;
 use lib::database::Source;
 use lib::entities::KanjiInfo;
 use lib::jmdict::{
@@ -74,6 +76,20 @@ pub struct Props {
     pub embed: bool,
     pub sources: BTreeSet<Source>,
     pub entry: jmdict::OwnedEntry,
+    /// Set if a name with the same form and reading was collapsed into the
+    /// entry.
+    #[prop_or_default]
+    pub also_name: bool,
+    /// Jump to the names which are written and read the same as the entry, if
+    /// there are any.
+    #[prop_or_default]
+    pub onname: Option<Callback<()>>,
+    /// Pitch accents of the forms of the entry.
+    #[prop_or_default]
+    pub accents: Vec<OwnedReadingAccent>,
+    /// The name of the index the entry comes from.
+    #[prop_or_default]
+    pub index: Option<String>,
     pub onchange: Callback<(String, Option<String>), ()>,
     pub ontag: Callback<&'static str>,
     pub onpriority: Callback<Priority>,
@@ -82,7 +98,12 @@ pub struct Props {
 impl PartialEq for Props {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.sources == other.sources && self.entry.sequence == other.entry.sequence
+        self.sources == other.sources
+            && self.entry.sequence == other.entry.sequence
+            && self.also_name == other.also_name
+            && self.onname.is_some() == other.onname.is_some()
+            && self.accents == other.accents
+            && self.index == other.index
     }
 }
 
@@ -198,6 +219,18 @@ impl Component for Entry {
             },
         );
 
+        let accents = iter(
+            seq(
+                render_accents(&ctx.props().accents),
+                |accent, not_last| html!(<>{accent}{for not_last.then(comma)}</>),
+            ),
+            |iter| {
+                html! {
+                    html!(<div class="block row entry-accents"><span>{"Accent"}</span>{colon()}{spacing()}{for iter}</div>)
+                }
+            },
+        );
+
         let other_kana = iter(
             seq(
                 self.readings.iter().filter(|c| c.is_search_only()),
@@ -227,16 +260,37 @@ impl Component for Entry {
             |iter| html!(<ul class="block block-lg list-numerical">{for iter}</ul>),
         );
 
-        let sequence = (!ctx.props().embed).then(|| html! {
-            <div class="block block row entry-sequence"><a href={format!("/api/entry/{}", entry.sequence)} target="_api">{format!("#{}", entry.sequence)}</a></div>
+        let sequence = (!ctx.props().embed).then(|| {
+            let index = ctx.props().index.as_ref().map(|index| html! {
+                <span class="bullet sm" title="The dictionary this entry comes from">{index.clone()}</span>
+            });
+
+            html! {
+                <div class="block block row entry-sequence"><a href={format!("/api/entry/{}", entry.sequence)} target="_api">{format!("#{}", entry.sequence)}</a>{for index}</div>
+            }
+        });
+
+        let also_name = ctx.props().also_name.then(|| html! {
+            <div class="block row bullets"><span class="bullet sm" title="A name written and read the same way was collapsed into this entry">{"also a name"}</span></div>
+        });
+
+        let onname = ctx.props().onname.as_ref().map(|onname| {
+            let onclick = onname.reform(|_: MouseEvent| ());
+
+            html! {
+                <div class="block row bullets"><a class="bullet sm" title="A name is written and read the same way as this entry" {onclick}>{"see name entry"}</a></div>
+            }
         });
 
         html! {
             <div class="block block-lg entry">
                 {sequence}
+                {for also_name}
+                {for onname}
                 {for extras}
                 {for reading}
                 {for common}
+                {for accents}
                 {for senses}
                 {for other_kana}
                 {for other_kanji}
@@ -536,6 +590,32 @@ fn render_reading(ctx: &Context<Entry>, reading: &OwnedReadingElement, not_last:
     }
 }
 
+/// Render the distinct pitch accents of the readings of an entry, with the
+/// pattern of high and low morae as a title.
+fn render_accents(accents: &[OwnedReadingAccent]) -> Vec<Html> {
+    let mut seen = BTreeSet::new();
+    let mut output = Vec::new();
+
+    for a in accents {
+        for &n in &a.accents {
+            if !seen.insert((a.reading.as_str(), n)) {
+                continue;
+            }
+
+            let n = usize::from(n);
+
+            let Some(downstep) = accent::render(&a.reading, n, Notation::Downstep) else {
+                continue;
+            };
+
+            let pattern = accent::render(&a.reading, n, Notation::Pattern).unwrap_or_default();
+            output.push(html!(<span class="text" title={pattern}>{downstep}</span>));
+        }
+    }
+
+    output
+}
+
 fn render_combined(
     ctx: &Context<Entry>,
     c @ Combined { kanji, .. }: &Combined,
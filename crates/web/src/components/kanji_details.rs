@@ -11,6 +11,7 @@ use super::{comma, seq, spacing};
 
 pub(crate) enum Msg {
     GetKanji(Box<api::OwnedKanjiResponse>),
+    GetWords(api::OwnedKanjiWordsResponse),
     Error(Error),
 }
 
@@ -30,7 +31,9 @@ pub(crate) struct Props {
 pub(crate) struct KanjiDetails {
     pending: bool,
     request: ws::Request,
+    words_request: ws::Request,
     kanji: Option<api::OwnedKanjiResponse>,
+    words: Vec<lib::jmdict::OwnedEntry>,
 }
 
 impl Component for KanjiDetails {
@@ -48,10 +51,14 @@ impl Component for KanjiDetails {
             }),
         );
 
+        let words_request = request_words(ctx);
+
         Self {
             pending: false,
             request,
+            words_request,
             kanji: None,
+            words: Vec::new(),
         }
     }
 
@@ -61,6 +68,9 @@ impl Component for KanjiDetails {
                 self.pending = false;
                 self.kanji = Some(*kanji);
             }
+            Msg::GetWords(response) => {
+                self.words = response.words;
+            }
             Msg::Error(error) => {
                 log::error!("{}", error);
                 self.pending = false;
@@ -87,6 +97,7 @@ impl Component for KanjiDetails {
             }),
         );
 
+        self.words_request = request_words(ctx);
         true
     }
 
@@ -125,6 +136,24 @@ impl Component for KanjiDetails {
                 }
             });
 
+            let variants = (!kanji.variants.is_empty()).then(|| {
+                let variants = seq(&kanji.variants, |literal, not_last| {
+                    let onclick = ctx.props().onclick.reform({
+                        let literal = literal.clone();
+                        move |_| literal.clone()
+                    });
+                    html! {<><span class="text highlight"><a onclick={onclick.clone()}>{literal.clone()}</a></span>{not_last.then(comma)}</>}
+                });
+
+                html! {
+                    <div class="block block-lg row">
+                        <span class="highlight clickable">{"Variants:"}{spacing()}</span>
+
+                        {for variants}
+                    </div>
+                }
+            });
+
             let strokes = (!kanji.kanji.misc.stroke_counts.is_empty()).then(|| {
                 let strokes = seq(&kanji.kanji.misc.stroke_counts, |strokes, not_last| {
                     html! {<><span class="text highlight">{strokes}</span>{not_last.then(comma)}</>}
@@ -139,12 +168,39 @@ impl Component for KanjiDetails {
                 }
             });
 
+            let words = (!self.words.is_empty()).then(|| {
+                let words = seq(&self.words, |entry, not_last| {
+                    let text = entry
+                        .kanji_elements
+                        .iter()
+                        .find(|k| k.text.contains(&*ctx.props().kanji))
+                        .map(|k| k.text.as_str());
+
+                    let reading = entry
+                        .reading_elements
+                        .first()
+                        .map(|r| html!(<span class="text">{format!(" [{}]", r.text)}</span>));
+
+                    html! {<><span class="text highlight">{for text}</span>{for reading}{not_last.then(comma)}</>}
+                });
+
+                html! {
+                    <div class="block block-lg row">
+                        <span class="highlight">{"Words:"}{spacing()}</span>
+
+                        {for words}
+                    </div>
+                }
+            });
+
             html! {
                 <>
                     <div class="block block-lg character">
                         <c::Character embed={false} character={kanji.kanji.clone()} />
                         {for strokes}
                         {for radicals}
+                        {for variants}
+                        {for words}
                     </div>
                 </>
             }
@@ -158,3 +214,18 @@ impl Component for KanjiDetails {
         }
     }
 }
+
+/// Request the most common words written with the kanji.
+fn request_words(ctx: &Context<KanjiDetails>) -> ws::Request {
+    ctx.props().ws.request(
+        api::KanjiWordsRequest {
+            literal: ctx.props().kanji.to_string(),
+            limit: None,
+            sort: Default::default(),
+        },
+        ctx.link().callback(|result| match result {
+            Ok(response) => Msg::GetWords(response),
+            Err(error) => Msg::Error(error),
+        }),
+    )
+}
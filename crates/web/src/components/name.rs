@@ -11,8 +11,15 @@ pub enum Msg {
 pub struct Props {
     pub embed: bool,
     pub entry: jmnedict::OwnedEntry,
+    /// The name of the index the name comes from.
+    #[prop_or_default]
+    pub index: Option<String>,
     pub onclick: Callback<String>,
     pub ontag: Callback<&'static str>,
+    /// Jump to the dictionary entries which are written and read the same as
+    /// the name, if there are any.
+    #[prop_or_default]
+    pub onphrase: Option<Callback<()>>,
 }
 
 pub struct Name;
@@ -74,11 +81,48 @@ impl Component for Name {
 
         let bullets = bullets!(ctx, entry.name_types, "sm");
 
+        let index = ctx.props().index.as_ref().filter(|_| !ctx.props().embed).map(|index| html! {
+            <span class="bullet sm" title="The dictionary this name comes from">{index.clone()}</span>
+        });
+
+        let onphrase = ctx.props().onphrase.as_ref().map(|onphrase| {
+            let onclick = onphrase.reform(|_: MouseEvent| ());
+
+            html! {
+                <a class="bullet sm" title="A dictionary entry is written and read the same way as this name" {onclick}>{"see dictionary entry"}</a>
+            }
+        });
+
+        let translations = (!entry.translations.is_empty()).then(|| {
+            let it = entry.translations.iter().map(|translation| {
+                let lang = translation
+                    .lang
+                    .as_deref()
+                    .filter(|lang| *lang != "eng")
+                    .map(|lang| html!(<span class="bullet sm">{lang}</span>));
+
+                html!(<span class="text">{&translation.text}{for lang}</span>)
+            });
+
+            let it = seq(
+                it,
+                |it, not_last| html!(<>{it}{for not_last.then(comma)}</>),
+            );
+
+            html!(<div class="block row entry-glossary">{for it}</div>)
+        });
+
         html! {
-            <span class="row">
-                {entries}
-                {for bullets}
-            </span>
+            <>
+                <span class="row">
+                    {entries}
+                    {for bullets}
+                    {for index}
+                    {for onphrase}
+                </span>
+
+                {for translations}
+            </>
         }
     }
 }
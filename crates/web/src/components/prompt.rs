@@ -5,9 +5,15 @@ use std::mem::replace;
 use std::rc::Rc;
 use std::str::from_utf8;
 
+use gloo::timers::callback::Timeout;
 use gloo::utils::format::JsValueSerdeExt;
 use lib::api;
+use lib::config::ClientSettings;
+use lib::database::Fallback;
+use lib::kana;
 use lib::kanjidic2;
+use lib::phrasebook::Phrase;
+use lib::reporter::format_seconds;
 use lib::romaji;
 use lib::Priority;
 use musli::{Decode, Encode};
@@ -25,7 +31,7 @@ use crate::error::Error;
 use crate::query::{Mode, Query, Tab};
 use crate::ws;
 
-use super::{comma, seq, spacing};
+use super::{comma, ruby, seq, spacing};
 
 const DEFAULT_LIMIT: usize = 100;
 
@@ -41,15 +47,28 @@ pub(crate) enum Msg {
     OpenConfig,
     Mode(Mode),
     CaptureClipboard(bool),
+    CaptureAny(bool),
     Tab(Tab),
+    /// Switch to the given tab, fetching the phrases with the given sequence
+    /// numbers if none of them are among the results. Names can't be fetched
+    /// by their sequence numbers, so the given text is searched for instead.
+    Jump(Tab, Vec<u64>, String),
+    /// A phrase jumped to which wasn't among the results.
+    LinkedResponse(api::OwnedEntryResponse),
     Change(String),
+    Search,
     ForceChange(String, Option<String>),
     AddTag(&'static str),
     AddPriority(Priority),
     Analyze(usize),
     AnalyzeCycle,
+    /// Analyze the next chunk of captured text which was too long.
+    ClipboardNext,
     HistoryChanged(Location),
     GetConfig(api::GetConfigResult),
+    /// User interface settings stored for this client.
+    ClientSettings(ClientSettings),
+    EmbedLanding(api::EmbedLandingResponse),
     SearchResponse(api::OwnedSearchResponse),
     AnalyzeResponse(api::OwnedAnalyzeResponse),
     MoreEntries,
@@ -84,18 +103,44 @@ impl From<Error> for Msg {
 pub(crate) struct Prompt {
     query: Query,
     phrases: Vec<api::OwnedSearchPhrase>,
+    /// Phrases jumped to from a name which weren't among the results.
+    linked: Vec<api::OwnedEntryResponse>,
+    pending_linked: Vec<ws::Request>,
     names: Vec<api::OwnedSearchName>,
+    inflections: Vec<api::SearchInflection>,
+    /// How the phrase searched for was modified to find something.
+    fallback: Option<Fallback>,
+    /// The number of results shown at a time.
+    page_limit: usize,
     limit_entries: usize,
     characters: Vec<kanjidic2::OwnedCharacter>,
     limit_characters: usize,
     pending_search: ws::Request,
+    search_timeout: Option<Timeout>,
     log: Vec<api::OwnedLogEntry>,
     tasks: BTreeMap<String, api::OwnedTaskProgress>,
+    /// Image recognition jobs which haven't finished yet.
+    recognizing: BTreeSet<u64>,
     analysis: Rc<[String]>,
     ocr: bool,
+    clipboard_threshold: u32,
+    clipboard_limit: u32,
+    /// Captured text which was left out because it was too long.
+    clipboard_rest: Option<String>,
+    /// The number of characters before captured text which were left out.
+    clipboard_skipped: usize,
     missing: BTreeSet<String>,
     missing_ocr: Option<api::MissingOcr>,
+    /// Whether the service refuses to be modified.
+    read_only: bool,
+    /// Whether the service speaks another version of the websocket protocol
+    /// than this page, which happens after it has been updated.
+    outdated: bool,
     get_config: Option<ws::Request>,
+    embed_landing: Option<ws::Request>,
+    /// Phrasebook phrases shown in the embedded view before anything is
+    /// looked up.
+    landing_phrases: Vec<Phrase>,
     is_open: bool,
     _callback: Closure<dyn FnMut(MessageEvent)>,
     _location_handle: Option<LocationHandle>,
@@ -139,18 +184,33 @@ impl Component for Prompt {
         let mut this = Self {
             query,
             phrases: Vec::default(),
+            linked: Vec::new(),
+            pending_linked: Vec::new(),
             names: Vec::default(),
+            inflections: Vec::default(),
+            fallback: None,
+            page_limit: DEFAULT_LIMIT,
             limit_entries: DEFAULT_LIMIT,
             characters: Vec::default(),
             limit_characters: DEFAULT_LIMIT,
             pending_search: ws::Request::empty(),
+            search_timeout: None,
             log: Vec::new(),
             tasks: BTreeMap::new(),
+            recognizing: BTreeSet::new(),
             analysis: Rc::from([]),
             ocr: false,
+            clipboard_threshold: 0,
+            clipboard_limit: 0,
+            clipboard_rest: None,
+            clipboard_skipped: 0,
             missing: BTreeSet::new(),
             missing_ocr: None,
+            read_only: false,
+            outdated: false,
             get_config: None,
+            embed_landing: None,
+            landing_phrases: Vec::new(),
             is_open: false,
             _callback: callback,
             _location_handle: location_handle,
@@ -159,7 +219,13 @@ impl Component for Prompt {
         };
 
         this.get_config(ctx);
+        this.client_settings(ctx);
         this.reload(ctx);
+
+        if this.query.embed && this.query.text.is_empty() {
+            this.embed_landing(ctx);
+        }
+
         this
     }
 
@@ -192,6 +258,16 @@ impl Component for Prompt {
                     any |= true;
                 }
 
+                if state.config.clipboard_threshold != self.clipboard_threshold {
+                    self.clipboard_threshold = state.config.clipboard_threshold;
+                    any |= true;
+                }
+
+                if state.config.clipboard_limit != self.clipboard_limit {
+                    self.clipboard_limit = state.config.clipboard_limit;
+                    any |= true;
+                }
+
                 if missing != self.missing {
                     self.missing = missing;
                     any |= true;
@@ -202,16 +278,60 @@ impl Component for Prompt {
                     any |= true;
                 }
 
+                if state.config.read_only != self.read_only {
+                    self.read_only = state.config.read_only;
+                    any |= true;
+                }
+
                 any
             }
+            Msg::ClientSettings(settings) => {
+                if let Err(error) = apply_style(&settings) {
+                    log::error!("Failed to apply client settings: {error}");
+                }
+
+                self.page_limit = settings
+                    .result_limit
+                    .filter(|&limit| limit > 0)
+                    .unwrap_or(DEFAULT_LIMIT);
+                self.limit_entries = self.page_limit;
+                self.limit_characters = self.page_limit;
+
+                if settings.embed && !self.query.embed {
+                    self.query.embed = true;
+
+                    if self.query.text.is_empty() {
+                        self.embed_landing(ctx);
+                    }
+                }
+
+                true
+            }
+            Msg::EmbedLanding(response) => {
+                self.embed_landing = None;
+                self.landing_phrases = response.phrasebook;
+
+                // Something might have been looked up while waiting.
+                if let Some(text) = response.query.filter(|_| self.query.text.is_empty()) {
+                    self.query.set(text, None);
+                    self.save_query(ctx, History::Replace);
+                    self.search(ctx);
+                }
+
+                true
+            }
             Msg::SearchResponse(response) => {
                 self.phrases = response.phrases;
+                self.linked.clear();
+                self.pending_linked.clear();
                 self.names = response.names;
                 self.phrases.sort_by(|a, b| a.key.weight.cmp(&b.key.weight));
                 self.names.sort_by(|a, b| a.key.weight.cmp(&b.key.weight));
                 self.characters = response.characters;
-                self.limit_entries = DEFAULT_LIMIT;
-                self.limit_characters = DEFAULT_LIMIT;
+                self.inflections = response.inflections;
+                self.fallback = response.fallback;
+                self.limit_entries = self.page_limit;
+                self.limit_characters = self.page_limit;
                 true
             }
             Msg::AnalyzeResponse(response) => {
@@ -244,11 +364,68 @@ impl Component for Prompt {
                 self.save_query(ctx, History::Replace);
                 true
             }
+            Msg::CaptureAny(capture_any) => {
+                self.query.capture_any = capture_any;
+                self.save_query(ctx, History::Replace);
+                true
+            }
             Msg::Tab(tab) => {
                 self.query.tab = tab;
                 self.save_query(ctx, History::Push);
                 true
             }
+            Msg::Jump(tab, sequences, text) => {
+                let shown = match tab {
+                    Tab::Names => self
+                        .names
+                        .iter()
+                        .any(|n| sequences.contains(&n.name.sequence)),
+                    _ => self
+                        .phrases
+                        .iter()
+                        .map(|p| &p.phrase)
+                        .chain(self.linked.iter().map(|e| &e.entry))
+                        .any(|p| sequences.contains(&p.sequence)),
+                };
+
+                if !shown {
+                    if let Tab::Names = tab {
+                        self.query.set(text, None);
+                        self.analysis = Rc::from([]);
+                        self.search(ctx);
+                    } else {
+                        self.linked.clear();
+
+                        self.pending_linked = sequences
+                            .iter()
+                            .filter_map(|&sequence| u32::try_from(sequence).ok())
+                            .map(|sequence| {
+                                ctx.props().ws.request(
+                                    api::EntryRequest { sequence },
+                                    ctx.link().callback(|result| match result {
+                                        Ok(response) => Msg::LinkedResponse(response),
+                                        Err(error) => Msg::Error(error),
+                                    }),
+                                )
+                            })
+                            .collect();
+                    }
+                }
+
+                self.query.tab = tab;
+                self.save_query(ctx, History::Push);
+                true
+            }
+            Msg::LinkedResponse(response) => {
+                let sequence = response.entry.sequence;
+
+                if self.linked.iter().any(|e| e.entry.sequence == sequence) {
+                    return false;
+                }
+
+                self.linked.push(response);
+                true
+            }
             Msg::Change(input) => {
                 log::trace!("{:?}", input);
 
@@ -260,13 +437,19 @@ impl Component for Prompt {
 
                 if self.query.text != input {
                     self.query.set(input, None);
+                    self.clipboard_rest = None;
+                    self.clipboard_skipped = 0;
                     self.analysis = Rc::from([]);
                     self.save_query(ctx, History::Replace);
-                    self.search(ctx);
+                    self.search_debounced(ctx);
                 }
 
                 true
             }
+            Msg::Search => {
+                self.search(ctx);
+                false
+            }
             Msg::ForceChange(input, translation) => {
                 let input = match self.query.mode {
                     Mode::Unfiltered => input,
@@ -313,6 +496,19 @@ impl Component for Prompt {
                     false
                 }
             }
+            Msg::ClipboardNext => {
+                let Some(rest) = self.clipboard_rest.take() else {
+                    return false;
+                };
+
+                let (chunk, rest) = lib::clipboard::split(&rest, self.clipboard_limit as usize);
+                self.query.set(chunk.to_owned(), None);
+                self.clipboard_rest = (!rest.is_empty()).then(|| rest.to_owned());
+                self.analysis = Rc::from([]);
+                self.save_query(ctx, History::Push);
+                self.search(ctx);
+                true
+            }
             Msg::HistoryChanged(location) => {
                 // Prevents internal history changes from firing.
                 if location.state::<IsInternal>().filter(|s| s.set()).is_some() {
@@ -336,11 +532,11 @@ impl Component for Prompt {
                 true
             }
             Msg::MoreEntries => {
-                self.limit_entries += DEFAULT_LIMIT;
+                self.limit_entries += self.page_limit;
                 true
             }
             Msg::MoreCharacters => {
-                self.limit_characters += DEFAULT_LIMIT;
+                self.limit_characters += self.page_limit;
                 true
             }
             Msg::ContentMessage(message) => {
@@ -372,11 +568,24 @@ impl Component for Prompt {
             }
             Msg::Broadcast(event) => {
                 match event {
+                    api::OwnedBroadcastKind::Hello(hello) => {
+                        self.outdated = hello.version != api::PROTOCOL_VERSION;
+
+                        // Without a resumed session, state built up from
+                        // earlier events is backfilled again.
+                        if !hello.resumed {
+                            self.log.clear();
+                            self.tasks.clear();
+                            self.recognizing.clear();
+                        }
+                    }
                     api::OwnedBroadcastKind::SendClipboardData(clipboard) => {
                         if let Err(error) = self.update_from_clipboard(
                             ctx,
                             clipboard.ty.as_deref(),
                             &clipboard.data,
+                            clipboard.rest,
+                            clipboard.skipped.unwrap_or_default(),
                         ) {
                             ctx.link().send_message(error);
                         }
@@ -397,6 +606,23 @@ impl Component for Prompt {
                         self.get_config(ctx);
                         self.reload(ctx);
                     }
+                    api::OwnedBroadcastKind::Rebind(rebind) => {
+                        if let Err(error) = redirect(&rebind.url) {
+                            ctx.link().send_message(error);
+                        }
+                    }
+                    api::OwnedBroadcastKind::IndexesChanged(changed) => {
+                        self.missing
+                            .retain(|id| !changed.enabled.iter().any(|e| e == id));
+                    }
+                    api::OwnedBroadcastKind::OcrJob(job) => match job.state {
+                        api::OcrState::Queued | api::OcrState::Recognizing => {
+                            self.recognizing.insert(job.id);
+                        }
+                        _ => {
+                            self.recognizing.remove(&job.id);
+                        }
+                    },
                 }
 
                 true
@@ -424,7 +650,27 @@ impl Component for Prompt {
             Some(Msg::Change(value))
         });
 
-        let analyze = if self.query.text.is_empty() {
+        let analyze = if self.query.text.is_empty() && !self.landing_phrases.is_empty() {
+            let phrases = self.landing_phrases.iter().map(|p| {
+                let onclick = ctx.link().callback({
+                    let text = p.text.clone();
+                    move |_| Msg::ForceChange(text.clone(), None)
+                });
+
+                let translation = (!p.translation.is_empty()).then(|| {
+                    html!(<>{spacing()}<span>{p.translation.clone()}</span></>)
+                });
+
+                html! {
+                    <div class="block row">
+                        <span class="text kanji highlight clickable" {onclick}>{p.text.clone()}</span>
+                        {for translation}
+                    </div>
+                }
+            });
+
+            html!(<div id="analyze" class="block block-lg">{for phrases}</div>)
+        } else if self.query.text.is_empty() {
             let text = if self.query.embed {
                 "Nothing to analyze"
             } else {
@@ -438,6 +684,38 @@ impl Component for Prompt {
             html!(<c::AnalyzeToggle query={self.query.text.clone()} analyzed={self.analysis.clone()} index={self.query.index} analyze_at={self.query.analyze_at} {on_analyze} {on_analyze_cycle} />)
         };
 
+        let fallback = self.fallback.as_ref().map(|f| {
+            let how = if f.prefix { "starting with" } else { "for" };
+
+            html! {
+                <div class="block row" id="fallback">
+                    {format!("Nothing found for {}, showing results {how} {}", f.original, f.matched)}
+                </div>
+            }
+        });
+
+        let skipped = (self.clipboard_skipped > 0).then(|| {
+            let skipped = self.clipboard_skipped;
+
+            html! {
+                <div class="block row" id="skipped">
+                    <span>{format!("Captured text is analyzed from where it was captured, {skipped} characters before it were left out")}</span>
+                </div>
+            }
+        });
+
+        let truncated = self.clipboard_rest.as_ref().map(|rest| {
+            let onclick = ctx.link().callback(|_| Msg::ClipboardNext);
+            let left = rest.chars().count();
+
+            html! {
+                <div class="block row row-spaced" id="truncated">
+                    <span>{format!("Captured text was too long, {left} characters are left to analyze")}</span>
+                    <button class="row-end btn btn-lg" {onclick}>{"Next part"}</button>
+                </div>
+            }
+        });
+
         let translation = self.query.translation.as_ref().map(|text| {
             html! {
                 <div class="block row" id="translation">
@@ -448,7 +726,20 @@ impl Component for Prompt {
             }
         });
 
-        let phrases = (!self.phrases.is_empty()).then(|| {
+        let phrases = (!self.phrases.is_empty() || !self.linked.is_empty()).then(|| {
+            let linked = self.linked.iter().map(|e| {
+                let entry = e.entry.clone();
+
+                let onchange = ctx.link().callback(|(input, translation)| {
+                    Msg::ForceChange(input, translation)
+                });
+
+                let ontag = ctx.link().callback(Msg::AddTag);
+                let onpriority = ctx.link().callback(Msg::AddPriority);
+
+                html!(<c::Entry embed={self.query.embed} sources={BTreeSet::new()} {entry} {onchange} {ontag} {onpriority} />)
+            });
+
             let phrases = self.phrases.iter().take(self.limit_entries).map(|e| {
                 let entry = e.phrase.clone();
 
@@ -458,10 +749,22 @@ impl Component for Prompt {
 
                 let ontag = ctx.link().callback(Msg::AddTag);
                 let onpriority = ctx.link().callback(Msg::AddPriority);
-                html!(<c::Entry embed={self.query.embed} sources={e.key.sources.clone()} {entry} {onchange} {ontag} {onpriority} />)
+
+                let onname = (!e.also_name && !e.names.is_empty()).then(|| {
+                    let names = e.names.clone();
+
+                    let text = match e.phrase.kanji_elements.first() {
+                        Some(kanji) => kanji.text.clone(),
+                        None => e.phrase.reading_elements.first().map(|r| r.text.clone()).unwrap_or_default(),
+                    };
+
+                    ctx.link().callback(move |_| Msg::Jump(Tab::Names, names.clone(), text.clone()))
+                });
+
+                html!(<c::Entry embed={self.query.embed} sources={e.key.sources.clone()} {entry} also_name={e.also_name} accents={e.accents.clone()} index={Some(e.index.clone())} {onchange} {ontag} {onpriority} {onname} />)
             });
 
-            let phrases = seq(phrases, |entry, not_last| {
+            let phrases = seq(linked.chain(phrases), |entry, not_last| {
                 if not_last {
                     html!(<>{entry}<div class="entry-separator" /></>)
                 } else {
@@ -487,9 +790,19 @@ impl Component for Prompt {
                 html!(<h4>{"Phrases"}</h4>)
             });
 
+            let inflections = (!self.inflections.is_empty()).then(|| {
+                let inflections = self.inflections.iter().map(|i| {
+                    let furigana = lib::Furigana::new(&i.text, &i.reading, "");
+                    html!(<div class="block row"><span class="text kanji highlight">{ruby(furigana)}</span></div>)
+                });
+
+                html!(<div class="block block-lg">{for inflections}</div>)
+            });
+
             html! {
                 <div class="block block-lg">
                     {header}
+                    {for inflections}
                     {for phrases}
                     {for more}
                 </div>
@@ -503,10 +816,20 @@ impl Component for Prompt {
 
             let ontag = ctx.link().callback(Msg::AddTag);
 
-            let names = self
-                .names
-                .iter()
-                .map(|e| html!(<c::Name embed={self.query.embed} entry={e.name.clone()} onclick={onclick.clone()} ontag={ontag.clone()} />));
+            let names = self.names.iter().map(|e| {
+                let onphrase = (!e.phrases.is_empty()).then(|| {
+                    let phrases = e.phrases.clone();
+
+                    let text = match e.name.kanji.first() {
+                        Some(kanji) => kanji.clone(),
+                        None => e.name.reading.first().map(|r| r.text.clone()).unwrap_or_default(),
+                    };
+
+                    ctx.link().callback(move |_| Msg::Jump(Tab::Phrases, phrases.clone(), text.clone()))
+                });
+
+                html!(<c::Name embed={self.query.embed} entry={e.name.clone()} index={Some(e.index.clone())} onclick={onclick.clone()} ontag={ontag.clone()} {onphrase} />)
+            });
 
             let header = (!self.query.embed).then(|| html!(<h4>{"Names"}</h4>));
 
@@ -624,7 +947,10 @@ impl Component for Prompt {
             html! {
                 <>
                     <div class="block block-lg">{analyze}</div>
+                    {for skipped}
+                    {for truncated}
                     {for translation}
+                    {for fallback}
                     <div class="tabs">
                         {for tabs}
                         {for active_tab}
@@ -659,6 +985,20 @@ impl Component for Prompt {
                         move |_| Msg::CaptureClipboard(!capture_clipboard)
                     });
 
+                    let capture_any = self.query.capture_clipboard.then(|| {
+                        let oncaptureany = ctx.link().callback({
+                            let capture_any = self.query.capture_any;
+                            move |_| Msg::CaptureAny(!capture_any)
+                        });
+
+                        html! {
+                            <button title="Capture text in any language" onclick={oncaptureany}>
+                                <span>{"🌐"}</span>
+                                <input type="checkbox" checked={self.query.capture_any} />
+                            </button>
+                        }
+                    });
+
                     let onclick = ctx.link().callback(|_| Msg::OpenConfig);
 
                     let (title, description) = match self.query.mode {
@@ -678,6 +1018,8 @@ impl Component for Prompt {
                                 <span>{"📋"}</span>
                                 <input type="checkbox" checked={self.query.capture_clipboard} />
                             </button>
+
+                            {for capture_any}
                         </div>
 
                         <div class="block block-lg row row-spaced">
@@ -698,7 +1040,10 @@ impl Component for Prompt {
 
                             <>
                                 <div class="block block-xl">{analyze}</div>
+                                {for skipped}
+                                {for truncated}
                                 {for translation}
+                                {for fallback}
 
                                 <div class="columns">
                                     <div class="column">{phrases}{names}</div>
@@ -714,6 +1059,19 @@ impl Component for Prompt {
         let tasks = (!self.tasks.is_empty()).then(|| {
             let tasks = self.tasks.values().map(|task| {
                 let (progress, done, value) = match task.total {
+                    // Tasks with weighted steps show how much of the whole
+                    // task is done.
+                    _ if task.done > 0 => {
+                        let progress = html! {
+                            <progress max="1000" value={task.done.to_string()} />
+                        };
+
+                        let value = task.total.is_none().then(|| {
+                            html!(<div class="task-field task-value">{task_value(task)}</div>)
+                        });
+
+                        (progress, task.done >= 1000, value)
+                    }
                     Some(total) => {
                         let progress = html! {
                             <progress max={total.to_string()} value={task.value.to_string()} />
@@ -723,11 +1081,16 @@ impl Component for Prompt {
                     }
                     None => {
                         let progress = html!(<progress />);
-                        let value = html!(<div class="task-field task-value">{task.value.to_string()}</div>);
+                        let value = html!(<div class="task-field task-value">{task_value(task)}</div>);
                         (progress, false, Some(value))
                     }
                 };
 
+                let remaining = task.remaining.filter(|_| !done).map(|remaining| {
+                    let remaining = format!("about {} left", format_seconds(remaining));
+                    html!(<div class="task-field task-remaining">{remaining}</div>)
+                });
+
                 let class = classes! {
                     "block",
                     "row",
@@ -749,6 +1112,7 @@ impl Component for Prompt {
                         {text}
                         <div class="task-field task-progress">{progress}</div>
                         {value}
+                        {remaining}
                     </div>
                 }
             });
@@ -760,6 +1124,42 @@ impl Component for Prompt {
             }
         });
 
+        let recognizing = (!self.recognizing.is_empty()).then(|| {
+            html! {
+                <div class="block block-lg" id="recognizing">
+                    <div class="block row row-spaced task">
+                        <div class="task-field task-name">{"Recognizing image ..."}</div>
+                        <div class="task-field task-progress"><progress /></div>
+                    </div>
+                </div>
+            }
+        });
+
+        let read_only = (self.read_only && !self.query.embed).then(|| {
+            html! {
+                <div class="block block-lg" id="read-only">
+                    <div class="block row">{"This is a read-only instance, so its settings and dictionaries can't be changed."}</div>
+                </div>
+            }
+        });
+
+        let outdated = self.outdated.then(|| {
+            let onclick = Callback::from(|_: MouseEvent| {
+                if let Some(window) = window() {
+                    let _ = window.location().reload();
+                }
+            });
+
+            html! {
+                <div class="block block-lg block-danger" id="outdated">
+                    <div class="block block-sm row row-spaced">
+                        <span>{"The service has been updated since this page was loaded."}</span>
+                        <button class="row-end btn btn-lg" {onclick}>{"Reload"}</button>
+                    </div>
+                </div>
+            }
+        });
+
         let missing = (self.query.tab != Tab::Settings && !self.missing.is_empty()).then(|| {
             let missing = seq(self.missing.iter(), |id, not_last| {
                 html! {
@@ -770,14 +1170,17 @@ impl Component for Prompt {
                 }
             });
 
-            let onclick = ctx.link().callback(|_| Msg::Tab(Tab::Settings));
+            let fix = (!self.read_only).then(|| {
+                let onclick = ctx.link().callback(|_| Msg::Tab(Tab::Settings));
+                html!(<button class="row-end btn btn-lg" {onclick}>{"⚙ Fix in Settings"}</button>)
+            });
 
             html! {
                 <div class="block block-lg block-danger">
                     <div class="block block-sm row row-spaced">
                         <span class="title">{"Dictionaries missing:"}</span>
                         <span>{for missing}</span>
-                        <button class="row-end btn btn-lg" {onclick}>{"⚙ Fix in Settings"}</button>
+                        {for fix}
                     </div>
                 </div>
             }
@@ -874,9 +1277,12 @@ impl Component for Prompt {
                 {window_top}
 
                 <div id="content" {class}>
+                    {for outdated}
+                    {for read_only}
                     {missing}
                     {missing_ocr}
                     {tasks}
+                    {recognizing}
                     {page}
                     <div class="block block-xl" id="copyright">{copyright()}</div>
                 </div>
@@ -897,6 +1303,16 @@ impl Prompt {
     }
 }
 
+/// The progress of a task as formatted by the service, falling back to the raw
+/// value for services which don't format it.
+fn task_value(task: &api::OwnedTaskProgress) -> String {
+    if task.summary.is_empty() {
+        task.value.to_string()
+    } else {
+        task.summary.clone()
+    }
+}
+
 fn process_query<'a, F>(input: &'a str, segment: F) -> String
 where
     F: Copy + FnOnce(&romaji::Segment<'a>) -> &'a str,
@@ -984,6 +1400,32 @@ impl Prompt {
         ));
     }
 
+    fn client_settings(&mut self, ctx: &Context<Self>) {
+        ctx.link().send_future(async {
+            match fetch_client_settings().await {
+                Ok(settings) => Msg::ClientSettings(settings),
+                Err(error) => Msg::Error(error),
+            }
+        });
+    }
+
+    fn embed_landing(&mut self, ctx: &Context<Self>) {
+        // Minutes to add to local time to get UTC, the inverse of what the
+        // service expects.
+        let utc_offset = -(web_sys::js_sys::Date::new_0().get_timezone_offset() as i64);
+
+        self.embed_landing = Some(ctx.props().ws.request(
+            api::EmbedLandingRequest {
+                utc_offset,
+                client: self.query.client.clone(),
+            },
+            ctx.link().callback(|result| match result {
+                Ok(response) => Msg::EmbedLanding(response),
+                Err(error) => Msg::Error(error),
+            }),
+        ));
+    }
+
     fn reload(&mut self, ctx: &Context<Self>) {
         log::trace!("Reload");
 
@@ -994,7 +1436,26 @@ impl Prompt {
         self.search(ctx);
     }
 
+    /// Search after the debounce suggested by the service, which is
+    /// immediate unless it's struggling to keep up.
+    fn search_debounced(&mut self, ctx: &Context<Self>) {
+        let debounce = ctx.props().ws.debounce();
+
+        if debounce == 0 {
+            self.search(ctx);
+            return;
+        }
+
+        let link = ctx.link().clone();
+
+        self.search_timeout = Some(Timeout::new(debounce, move || {
+            link.send_message(Msg::Search);
+        }));
+    }
+
     fn search(&mut self, ctx: &Context<Self>) {
+        self.search_timeout = None;
+
         let text = if let Some(input) = self.analysis.get(self.query.index) {
             input.clone()
         } else {
@@ -1006,7 +1467,16 @@ impl Prompt {
         let text = text.to_lowercase();
 
         self.pending_search = ctx.props().ws.request(
-            api::SearchRequest { q: text },
+            api::SearchRequest {
+                q: text,
+                refine_of: None,
+                debug: None,
+                continuation: None,
+                readings: false,
+                compact: false,
+                notation: None,
+                client: self.query.client.clone(),
+            },
             ctx.link().callback(|result| match result {
                 Ok(response) => Msg::SearchResponse(response),
                 Err(error) => Msg::Error(error),
@@ -1027,6 +1497,7 @@ impl Prompt {
             api::AnalyzeRequest {
                 q: input,
                 start: analyze,
+                offset_encoding: api::OffsetEncoding::Utf8,
             },
             ctx.link().callback(|result| match result {
                 Ok(response) => Msg::AnalyzeResponse(response),
@@ -1065,45 +1536,78 @@ impl Prompt {
         &mut self,
         ctx: &Context<Self>,
         json: &lib::api::SendClipboardJson,
+        rest: Option<String>,
+        skipped: usize,
     ) -> Result<(), Error> {
-        if self.query.capture_clipboard && self.query.text != json.primary.as_str() {
+        if self.should_capture(&json.primary) {
             self.query.set(
                 json.primary.clone(),
                 json.secondary.as_ref().filter(|s| !s.is_empty()).cloned(),
             );
+            self.query.analyze_at = json.analyze_at;
+            self.clipboard_rest = rest;
+            self.clipboard_skipped = skipped;
             self.analysis = Rc::from([]);
             self.save_query(ctx, History::Push);
-            self.search(ctx);
+
+            if !self.analyze(ctx) {
+                self.search(ctx);
+            }
         }
 
         Ok(())
     }
 
+    /// Test if captured text should replace the current query, which requires
+    /// it to look sufficiently Japanese unless any language is captured.
+    fn should_capture(&self, text: &str) -> bool {
+        if !self.query.capture_clipboard || self.query.text == text {
+            return false;
+        }
+
+        if self.query.capture_any {
+            return true;
+        }
+
+        let percentage = kana::japanese_percentage(text);
+
+        if percentage < self.clipboard_threshold {
+            log::trace!("Ignoring captured text which is {percentage}% Japanese");
+            return false;
+        }
+
+        true
+    }
+
     /// Update from clipboard.
     fn update_from_clipboard(
         &mut self,
         ctx: &Context<Self>,
         ty: Option<&str>,
         data: &[u8],
+        rest: Option<String>,
+        skipped: usize,
     ) -> Result<(), Error> {
         if matches!(ty, Some("application/json")) {
             let json = serde_json::from_slice::<lib::api::SendClipboardJson>(data)?;
-            self.update_from_clipboard_json(ctx, &json)?;
+            self.update_from_clipboard_json(ctx, &json, rest, skipped)?;
             return Ok(());
         }
 
         // Heuristics.
         if data.starts_with(b"{") {
             if let Ok(json) = serde_json::from_slice::<lib::api::SendClipboardJson>(data) {
-                self.update_from_clipboard_json(ctx, &json)?;
+                self.update_from_clipboard_json(ctx, &json, rest, skipped)?;
                 return Ok(());
             }
         }
 
         let data = from_utf8(data)?;
 
-        if self.query.capture_clipboard && self.query.text != data {
+        if self.should_capture(data) {
             self.query.set(data.to_owned(), None);
+            self.clipboard_rest = rest;
+            self.clipboard_skipped = skipped;
             self.analysis = Rc::from([]);
             self.save_query(ctx, History::Push);
             self.search(ctx);
@@ -1113,6 +1617,26 @@ impl Prompt {
     }
 }
 
+/// Redirect to the same page on the given origin, unless it's already the
+/// current one.
+fn redirect(url: &str) -> Result<(), Error> {
+    let location = window().ok_or("no window")?.location();
+
+    if location.origin()? == url {
+        return Ok(());
+    }
+
+    let href = format!(
+        "{url}{}{}{}",
+        location.pathname()?,
+        location.search()?,
+        location.hash()?
+    );
+
+    location.set_href(&href)?;
+    Ok(())
+}
+
 /// Internal state for the history API, so it can be read by the listener and
 /// avoid double-querying.
 struct IsInternal(Cell<bool>);
@@ -1178,3 +1702,35 @@ where
     parent.post_message(&message, "*")?;
     Ok(())
 }
+
+/// Fetch the user interface settings stored for this client.
+async fn fetch_client_settings() -> Result<ClientSettings, Error> {
+    let response = gloo::net::http::Request::get("/api/client/settings")
+        .send()
+        .await?;
+
+    if !response.ok() {
+        return Err(Error::from("Failed to fetch client settings"));
+    }
+
+    Ok(response.json().await?)
+}
+
+/// Apply the theme and font size of the client settings to the document.
+fn apply_style(settings: &ClientSettings) -> Result<(), Error> {
+    let root = gloo::utils::document_element();
+
+    match &settings.theme {
+        Some(theme) => root.set_attribute("data-theme", theme)?,
+        None => root.remove_attribute("data-theme")?,
+    }
+
+    let body = gloo::utils::body();
+
+    match settings.font_size {
+        Some(size) => body.set_attribute("style", &format!("font-size: {size}px"))?,
+        None => body.remove_attribute("style")?,
+    }
+
+    Ok(())
+}
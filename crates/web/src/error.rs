@@ -76,3 +76,12 @@ impl From<Utf8Error> for Error {
         }
     }
 }
+
+impl From<gloo::net::Error> for Error {
+    #[inline]
+    fn from(error: gloo::net::Error) -> Self {
+        Self {
+            error: anyhow::Error::from(error),
+        }
+    }
+}
@@ -31,7 +31,12 @@ pub(crate) struct Query {
     pub(crate) index: usize,
     pub(crate) mode: Mode,
     pub(crate) capture_clipboard: bool,
+    /// Capture clipboard text regardless of how Japanese it looks.
+    pub(crate) capture_any: bool,
     pub(crate) embed: bool,
+    /// The identifier of the client, which the service remembers the last
+    /// query of.
+    pub(crate) client: Option<String>,
     pub(crate) tab: Tab,
 }
 
@@ -78,7 +83,9 @@ impl Query {
         let mut translation = None;
         let mut mode = Mode::default();
         let mut capture_clipboard = false;
+        let mut capture_any = false;
         let mut embed = false;
+        let mut client = None;
         let mut tab = Tab::default();
         let mut index = 0;
 
@@ -100,9 +107,15 @@ impl Query {
                 "cb" => {
                     capture_clipboard = value == "yes";
                 }
+                "any" => {
+                    capture_any = value == "yes";
+                }
                 "embed" => {
                     embed = value == "yes";
                 }
+                "client" => {
+                    client = Some(value);
+                }
                 "tab" => {
                     tab = if let Some((first, second)) = value.split_once('/') {
                         match (first, second) {
@@ -143,7 +156,9 @@ impl Query {
             translation,
             mode,
             capture_clipboard,
+            capture_any,
             embed,
+            client,
             tab,
             analyze_at,
             index,
@@ -177,10 +192,18 @@ impl Query {
             out.push(("cb", Cow::Borrowed("yes")));
         }
 
+        if self.capture_any {
+            out.push(("any", Cow::Borrowed("yes")));
+        }
+
         if !no_embed && self.embed {
             out.push(("embed", Cow::Borrowed("yes")));
         }
 
+        if let Some(client) = &self.client {
+            out.push(("client", Cow::Borrowed(client.as_str())));
+        }
+
         if let Some(analyze_at) = self.analyze_at {
             out.push(("at", Cow::Owned(analyze_at.to_string())));
         }
@@ -1,9 +1,11 @@
 use std::cell::{Cell, RefCell};
+use std::io::Read;
 use std::marker::PhantomData;
 use std::mem::take;
 use std::rc::Rc;
 
 use anyhow::anyhow;
+use flate2::read::DeflateDecoder;
 use gloo::timers::callback::Timeout;
 use lib::api;
 use musli_utils::reader::SliceReader;
@@ -18,6 +20,8 @@ use crate::error::{Error, Result};
 
 const INITIAL_TIMEOUT: u32 = 250;
 const MAX_TIMEOUT: u32 = 16000;
+/// The longest a request will be debounced for in milliseconds.
+const MAX_DEBOUNCE: u32 = 1000;
 
 pub enum Msg {
     Reconnect,
@@ -33,10 +37,19 @@ struct Opened {
     at: Option<f64>,
 }
 
+/// The session and the last event seen in it, which is resumed when
+/// reconnecting.
+#[derive(Debug, Clone, Copy)]
+struct Resume {
+    session: u64,
+    sequence: u64,
+}
+
 pub struct Service<C> {
     shared: Rc<Shared>,
     socket: Option<WebSocket>,
     opened: Option<Opened>,
+    resume: Option<Resume>,
     state: State,
     buffer: Vec<(api::OwnedClientRequestEnvelope, Vec<u8>)>,
     output: Vec<u8>,
@@ -62,6 +75,7 @@ where
             requests: RefCell::new(Slab::new()),
             broadcasts: RefCell::new(Slab::new()),
             state_changes: RefCell::new(Slab::new()),
+            load: Cell::new(Load::default()),
         });
 
         let on_open = {
@@ -108,6 +122,7 @@ where
             shared: shared.clone(),
             socket: None,
             opened: None,
+            resume: None,
             state: State::Closed,
             buffer: Vec::new(),
             output: Vec::new(),
@@ -228,13 +243,22 @@ where
                     return;
                 };
 
-                let buffer = Uint8Array::new(&array_buffer).to_vec();
+                let buffer = match decompress(Uint8Array::new(&array_buffer).to_vec()) {
+                    Ok(buffer) => buffer,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        return;
+                    }
+                };
+
                 let mut reader = SliceReader::new(&buffer);
 
                 let event: api::ClientEvent<'_> = match musli_storage::decode(&mut reader) {
                     Ok(event) => event,
                     Err(error) => {
-                        log::error!("{}", error);
+                        // Likely an event which was added after this page was
+                        // loaded, see the version in the hello message.
+                        log::error!("Unsupported message from service: {}", error);
                         return;
                     }
                 };
@@ -243,6 +267,29 @@ where
 
                 match event {
                     api::ClientEvent::Broadcast(event) => {
+                        if let api::BroadcastKind::Hello(hello) = &event.kind {
+                            if hello.version != api::PROTOCOL_VERSION {
+                                log::warn!(
+                                    "Service speaks protocol version {}, but this page speaks {}",
+                                    hello.version,
+                                    api::PROTOCOL_VERSION
+                                );
+                            }
+
+                            // After resuming, the sequence is updated by the
+                            // replayed events as they arrive.
+                            if !hello.resumed {
+                                self.resume = Some(Resume {
+                                    session: hello.session,
+                                    sequence: hello.sequence,
+                                });
+                            }
+                        } else if let (Some(resume), Some(sequence)) =
+                            (&mut self.resume, event.sequence)
+                        {
+                            resume.sequence = sequence;
+                        }
+
                         let broadcasts = self.shared.broadcasts.borrow();
 
                         let mut it = broadcasts.iter();
@@ -259,11 +306,18 @@ where
                     }
                     api::ClientEvent::ClientResponse(response) => {
                         log::trace!(
-                            "Got response: index={}, serial={}",
+                            "Got response: index={}, serial={}, queue={}, latency={}",
                             response.index,
-                            response.serial
+                            response.serial,
+                            response.queue,
+                            response.latency
                         );
 
+                        let load = self.shared.load.get();
+                        self.shared
+                            .load
+                            .set(load.update(response.queue, response.latency));
+
                         let requests = self.shared.requests.borrow();
 
                         let Some(pending) = requests.get(response.index) else {
@@ -271,7 +325,11 @@ where
                         };
 
                         if pending.serial == response.serial {
-                            if let Some(error) = response.error {
+                            if let Some(error) = response.protocol_error {
+                                pending.callback.emit(Err(Error::from(anyhow!(
+                                    "{error}, the service is older than this page"
+                                ))));
+                            } else if let Some(error) = response.error {
                                 pending
                                     .callback
                                     .emit(Err(Error::from(anyhow!("{}", error))));
@@ -321,7 +379,17 @@ where
     pub(crate) fn connect(&mut self, ctx: &Context<C>) -> Result<()> {
         let window = window().ok_or("no window")?;
         let port = window.location().port()?;
-        let url = format!("ws://127.0.0.1:{port}/ws");
+        let mut url = format!(
+            "ws://127.0.0.1:{port}/ws?compression={}",
+            api::Compression::Deflate.as_str()
+        );
+
+        if let Some(resume) = self.resume {
+            url.push_str(&format!(
+                "&session={}&sequence={}",
+                resume.session, resume.sequence
+            ));
+        }
 
         let ws = match WebSocket::new(&url) {
             Ok(ws) => ws,
@@ -350,6 +418,27 @@ where
     }
 }
 
+/// Decode a message from the service, which starts with a byte saying whether
+/// it's compressed since we ask for compression when connecting.
+fn decompress(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    match data.first() {
+        Some(&api::Compression::PLAIN) => {
+            data.remove(0);
+            Ok(data)
+        }
+        Some(&api::Compression::DEFLATE) => {
+            let mut output = Vec::new();
+
+            DeflateDecoder::new(&data[1..])
+                .read_to_end(&mut output)
+                .map_err(anyhow::Error::from)?;
+
+            Ok(output)
+        }
+        _ => Err(anyhow!("Unsupported message encoding").into()),
+    }
+}
+
 fn now() -> Option<f64> {
     Some(window()?.performance()?.now())
 }
@@ -419,12 +508,41 @@ pub(crate) enum State {
     Closed,
 }
 
+/// Load reported by the service.
+#[derive(Default, Debug, Clone, Copy)]
+struct Load {
+    /// The number of requests queued up behind the last one.
+    queue: u32,
+    /// Smoothed latency of requests in milliseconds.
+    latency: f64,
+}
+
+impl Load {
+    fn update(self, queue: u32, latency: u32) -> Self {
+        Self {
+            queue,
+            latency: self.latency * 0.75 + f64::from(latency) * 0.25,
+        }
+    }
+
+    /// How long to debounce for in milliseconds, see [`Handle::debounce`].
+    fn debounce(self) -> u32 {
+        if self.queue == 0 {
+            return 0;
+        }
+
+        let debounce = self.latency * f64::from(self.queue.saturating_add(1));
+        (debounce as u32).min(MAX_DEBOUNCE)
+    }
+}
+
 struct Shared {
     serial: Cell<u32>,
     onmessage: Callback<(api::OwnedClientRequestEnvelope, Vec<u8>)>,
     requests: RefCell<Slab<Pending>>,
     broadcasts: RefCell<Slab<Callback<api::OwnedBroadcastKind>>>,
     state_changes: RefCell<Slab<Callback<State>>>,
+    load: Cell<Load>,
 }
 
 #[derive(Clone)]
@@ -478,6 +596,7 @@ impl Handle {
                 index,
                 serial,
                 kind: T::KIND.to_string(),
+                version: api::PROTOCOL_VERSION,
             },
             body,
         ));
@@ -487,6 +606,16 @@ impl Handle {
         }
     }
 
+    /// How long to wait in milliseconds before sending a request which is
+    /// likely to be superseded, like a search while typing.
+    ///
+    /// This adapts to the load reported by the service, so that a service
+    /// which keeps up, with no requests queued up behind the last one, isn't
+    /// delayed at all.
+    pub(crate) fn debounce(&self) -> u32 {
+        self.shared.load.get().debounce()
+    }
+
     pub(crate) fn listen<C>(&self, ctx: &Context<C>) -> Listener
     where
         C: Component,